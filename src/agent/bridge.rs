@@ -1,7 +1,9 @@
 //! Agent Bridge - Trait y proveedores para comunicación con agentes IA
 
+use std::collections::VecDeque;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Mutex;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
@@ -54,6 +56,19 @@ impl std::fmt::Display for AgentError {
 
 impl std::error::Error for AgentError {}
 
+impl AgentError {
+    /// Si el error es transitorio (red, timeout, rate limit) y vale la pena reintentar
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::ConnectionError(_) | Self::Timeout { .. } | Self::RateLimited { .. }
+        )
+    }
+}
+
+/// Callback que recibe cada fragmento de texto generado por el agente
+pub type ChunkCallback<'a> = &'a mut (dyn FnMut(&str) + Send);
+
 /// Trait para proveedores de agentes IA
 ///
 /// Implementa este trait para conectar AURA con diferentes agentes IA.
@@ -82,6 +97,23 @@ pub trait AgentProvider: Send + Sync {
         request: AgentRequest,
     ) -> Pin<Box<dyn Future<Output = Result<AgentResponse, AgentError>> + Send + 'a>>;
 
+    /// Como `send_request`, pero reporta el texto a medida que se genera
+    ///
+    /// Por defecto espera la respuesta completa y llama a `on_chunk` una
+    /// sola vez con la explicacion. Los proveedores con streaming real
+    /// (Ollama) lo sobrescriben.
+    fn send_request_streaming<'a>(
+        &'a self,
+        request: AgentRequest,
+        on_chunk: ChunkCallback<'a>,
+    ) -> Pin<Box<dyn Future<Output = Result<AgentResponse, AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let response = self.send_request(request).await?;
+            on_chunk(&response.explanation);
+            Ok(response)
+        })
+    }
+
     /// Nombre del proveedor
     fn name(&self) -> &str {
         "unknown"
@@ -95,7 +127,9 @@ pub trait AgentProvider: Send + Sync {
 
 /// Proveedor mock para pruebas
 ///
-/// Genera respuestas predecibles basadas en el tipo de evento.
+/// Genera respuestas predecibles basadas en el tipo de evento. Con
+/// `with_scripted` / `with_error_then` se puede fijar la secuencia exacta
+/// de resultados, uno por solicitud.
 pub struct MockProvider {
     /// Nombre del proveedor
     name: String,
@@ -105,8 +139,14 @@ pub struct MockProvider {
     request_count: AtomicU64,
     /// Si debe fallar
     should_fail: bool,
+    /// Cantidad de solicitudes iniciales que fallan con error de conexion
+    transient_failures: u64,
     /// Respuesta fija (si se configura)
     fixed_response: Option<AgentResponse>,
+    /// Resultados guionados; se consume uno por solicitud
+    script: Mutex<VecDeque<Result<AgentResponse, AgentError>>>,
+    /// Solicitudes recibidas, en orden
+    received: Mutex<Vec<AgentRequest>>,
 }
 
 impl MockProvider {
@@ -117,7 +157,10 @@ impl MockProvider {
             latency_ms: 10,
             request_count: AtomicU64::new(0),
             should_fail: false,
+            transient_failures: 0,
             fixed_response: None,
+            script: Mutex::new(VecDeque::new()),
+            received: Mutex::new(Vec::new()),
         }
     }
 
@@ -133,12 +176,37 @@ impl MockProvider {
         self
     }
 
+    /// Simula `n` errores de conexion antes de responder normalmente
+    pub fn with_failures(mut self, n: u64) -> Self {
+        self.transient_failures = n;
+        self
+    }
+
     /// Configura una respuesta fija
     pub fn with_response(mut self, response: AgentResponse) -> Self {
         self.fixed_response = Some(response);
         self
     }
 
+    /// Agrega respuestas al guion; cada solicitud consume la siguiente
+    ///
+    /// Cuando el guion se agota vuelve al comportamiento normal.
+    pub fn with_scripted(self, responses: Vec<AgentResponse>) -> Self {
+        self.script.lock().unwrap().extend(responses.into_iter().map(Ok));
+        self
+    }
+
+    /// Agrega un error al guion, antes de las respuestas que se agreguen despues
+    pub fn with_error_then(self, error: AgentError) -> Self {
+        self.script.lock().unwrap().push_back(Err(error));
+        self
+    }
+
+    /// Solicitudes recibidas hasta ahora, en orden
+    pub fn received_requests(&self) -> Vec<AgentRequest> {
+        self.received.lock().unwrap().clone()
+    }
+
     /// Obtiene el número de solicitudes procesadas
     pub fn request_count(&self) -> u64 {
         self.request_count.load(Ordering::SeqCst)
@@ -166,9 +234,7 @@ impl MockProvider {
                     "35.0"
                 } else if var_name.contains("max") || var_name.contains("limit") || var_name.contains("size") {
                     "100"
-                } else if var_name.contains("min") {
-                    "0"
-                } else if var_name.contains("count") || var_name.contains("total") {
+                } else if var_name.contains("min") || var_name.contains("count") || var_name.contains("total") {
                     "0"
                 } else if var_name.contains("timeout") || var_name.contains("delay") {
                     "5000"
@@ -254,7 +320,7 @@ impl MockProvider {
             EventType::Missing => {
                 // Simular generación de código faltante
                 AgentResponse::generate(
-                    format!("// Generated stub\nfn placeholder() {{\n  todo!()\n}}"),
+                    "// Generated stub\nfn placeholder() {\n  todo!()\n}".to_string(),
                     "Código generado como placeholder",
                     0.7,
                 )
@@ -315,7 +381,8 @@ impl AgentProvider for MockProvider {
     ) -> Pin<Box<dyn Future<Output = Result<AgentResponse, AgentError>> + Send + 'a>> {
         Box::pin(async move {
             // Incrementar contador
-            self.request_count.fetch_add(1, Ordering::SeqCst);
+            let count = self.request_count.fetch_add(1, Ordering::SeqCst) + 1;
+            self.received.lock().unwrap().push(request.clone());
 
             // Simular latencia
             if self.latency_ms > 0 {
@@ -329,6 +396,17 @@ impl AgentProvider for MockProvider {
                 ));
             }
 
+            if count <= self.transient_failures {
+                return Err(AgentError::ConnectionError(format!(
+                    "Fallo simulado {} de {}",
+                    count, self.transient_failures
+                )));
+            }
+
+            if let Some(scripted) = self.script.lock().unwrap().pop_front() {
+                return scripted;
+            }
+
             // Retornar respuesta fija o generada
             Ok(self.fixed_response.clone().unwrap_or_else(|| {
                 self.generate_mock_response(&request)
@@ -341,6 +419,126 @@ impl AgentProvider for MockProvider {
     }
 }
 
+/// Reintentos por defecto de `RetryingProvider`
+const DEFAULT_RETRIES: u32 = 3;
+
+/// Espera antes del primer reintento; se duplica en cada intento
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Tope de espera entre reintentos
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Decorador que reintenta las solicitudes fallidas con backoff exponencial
+///
+/// Solo reintenta errores transitorios (`AgentError::is_retryable`); un
+/// error de autenticacion o una respuesta invalida se retornan enseguida.
+///
+/// ## Ejemplo
+///
+/// ```ignore
+/// let provider = RetryingProvider::new(OllamaProvider::default())
+///     .with_retries(5);
+/// let mut engine = HealingEngine::new(provider);
+/// ```
+pub struct RetryingProvider<P: AgentProvider> {
+    inner: P,
+    /// Reintentos despues del primer intento
+    retries: u32,
+    /// Espera antes del primer reintento
+    backoff: Duration,
+}
+
+impl<P: AgentProvider> RetryingProvider<P> {
+    /// Envuelve un proveedor con los reintentos por defecto
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            retries: DEFAULT_RETRIES,
+            backoff: DEFAULT_BACKOFF,
+        }
+    }
+
+    /// Configura la cantidad de reintentos (0 = sin reintentos)
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Configura la espera antes del primer reintento
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Proveedor envuelto
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Espera antes del reintento numero `attempt` (desde 0)
+    ///
+    /// Si el proveedor indico `retry_after_ms`, se respeta si es mayor.
+    fn delay_for(&self, attempt: u32, error: &AgentError) -> Duration {
+        let exponential = self.backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_BACKOFF);
+
+        match error {
+            AgentError::RateLimited { retry_after_ms: Some(ms) } => {
+                exponential.max(Duration::from_millis(*ms))
+            }
+            _ => exponential,
+        }
+    }
+}
+
+impl<P: AgentProvider> AgentProvider for RetryingProvider<P> {
+    fn send_request<'a>(
+        &'a self,
+        request: AgentRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<AgentResponse, AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut attempt = 0;
+            loop {
+                match self.inner.send_request(request.clone()).await {
+                    Err(e) if e.is_retryable() && attempt < self.retries => {
+                        tokio::time::sleep(self.delay_for(attempt, &e)).await;
+                        attempt += 1;
+                    }
+                    result => return result,
+                }
+            }
+        })
+    }
+
+    fn send_request_streaming<'a>(
+        &'a self,
+        request: AgentRequest,
+        on_chunk: ChunkCallback<'a>,
+    ) -> Pin<Box<dyn Future<Output = Result<AgentResponse, AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut attempt = 0;
+            loop {
+                match self.inner.send_request_streaming(request.clone(), &mut *on_chunk).await {
+                    Err(e) if e.is_retryable() && attempt < self.retries => {
+                        tokio::time::sleep(self.delay_for(attempt, &e)).await;
+                        attempt += 1;
+                    }
+                    result => return result,
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_available<'a>(&'a self) -> Pin<Box<dyn Future<Output = bool> + Send + 'a>> {
+        self.inner.is_available()
+    }
+}
+
 /// Placeholder para el proveedor de Claude API
 ///
 /// Esta implementación se completará cuando se integre con la API real de Claude.
@@ -468,6 +666,105 @@ mod tests {
         assert!(response.confidence > 0.0);
     }
 
+    #[tokio::test]
+    async fn test_default_streaming_yields_once() {
+        let provider = MockProvider::new().with_latency(0);
+        let request = AgentRequest::error("x + 1", "test.aura", 1, 1)
+            .with_message("Variable 'x' no definida");
+
+        let mut chunks = Vec::new();
+        let response = provider
+            .send_request_streaming(request, &mut |chunk: &str| chunks.push(chunk.to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(chunks, vec![response.explanation]);
+    }
+
+    #[tokio::test]
+    async fn test_retrying_provider_succeeds_after_failures() {
+        let provider = RetryingProvider::new(MockProvider::new().with_latency(0).with_failures(2))
+            .with_retries(3)
+            .with_backoff(Duration::from_millis(1));
+
+        let request = AgentRequest::error("x + 1", "test.aura", 1, 1);
+        let response = provider.send_request(request).await.unwrap();
+
+        assert_eq!(response.action, Action::Patch);
+        assert_eq!(provider.inner().request_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retrying_provider_gives_up_after_limit() {
+        let provider = RetryingProvider::new(MockProvider::new().with_latency(0).with_failures(5))
+            .with_retries(2)
+            .with_backoff(Duration::from_millis(1));
+
+        let result = provider.send_request(AgentRequest::new(EventType::Error)).await;
+
+        assert!(matches!(result, Err(AgentError::ConnectionError(_))));
+        // Un intento inicial mas dos reintentos
+        assert_eq!(provider.inner().request_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retrying_provider_does_not_retry_permanent_errors() {
+        let provider = RetryingProvider::new(MockProvider::new().with_latency(0).failing())
+            .with_backoff(Duration::from_millis(1));
+
+        let result = provider.send_request(AgentRequest::new(EventType::Error)).await;
+
+        assert!(result.is_err());
+        assert_eq!(provider.inner().request_count(), 1);
+    }
+
+    #[test]
+    fn test_retry_backoff_is_exponential_and_capped() {
+        let provider = RetryingProvider::new(MockProvider::new())
+            .with_backoff(Duration::from_millis(100));
+        let error = AgentError::Timeout { timeout_ms: 1000 };
+
+        assert_eq!(provider.delay_for(0, &error), Duration::from_millis(100));
+        assert_eq!(provider.delay_for(1, &error), Duration::from_millis(200));
+        assert_eq!(provider.delay_for(3, &error), Duration::from_millis(800));
+        assert_eq!(provider.delay_for(20, &error), MAX_BACKOFF);
+
+        let limited = AgentError::RateLimited { retry_after_ms: Some(5000) };
+        assert_eq!(provider.delay_for(0, &limited), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_error_retryability() {
+        assert!(AgentError::ConnectionError("x".into()).is_retryable());
+        assert!(AgentError::Timeout { timeout_ms: 1 }.is_retryable());
+        assert!(AgentError::RateLimited { retry_after_ms: None }.is_retryable());
+        assert!(!AgentError::AuthenticationError("x".into()).is_retryable());
+        assert!(!AgentError::InvalidResponse("x".into()).is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_mock_provider_scripted_sequence() {
+        let provider = MockProvider::new()
+            .with_latency(0)
+            .with_error_then(AgentError::Timeout { timeout_ms: 10 })
+            .with_scripted(vec![
+                AgentResponse::generate("a = 1", "primero", 0.9),
+                AgentResponse::generate("a = 2", "segundo", 0.9),
+            ]);
+
+        let request = AgentRequest::error("a", "test.aura", 1, 1);
+        assert!(provider.send_request(request.clone()).await.is_err());
+        let first = provider.send_request(request.clone()).await.unwrap();
+        let second = provider.send_request(request.clone()).await.unwrap();
+        assert_eq!(first.generated_code.as_deref(), Some("a = 1"));
+        assert_eq!(second.generated_code.as_deref(), Some("a = 2"));
+
+        // Guion agotado: vuelve a la respuesta generada
+        let fallback = provider.send_request(request).await.unwrap();
+        assert_eq!(fallback.action, Action::Patch);
+        assert_eq!(provider.received_requests().len(), 4);
+    }
+
     #[tokio::test]
     async fn test_mock_provider_failing() {
         let provider = MockProvider::new().failing();
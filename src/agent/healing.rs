@@ -32,11 +32,11 @@
 use std::fmt;
 use std::path::PathBuf;
 
-use super::{AgentRequest, AgentResponse, AgentProvider, AgentError, Action, Context};
+use super::{AgentRequest, AgentResponse, AgentProvider, AgentError, Action, ChunkCallback, Context};
 use super::snapshot::{SnapshotReason, SnapshotId};
 use super::undo::{UndoManager, HealingAction, VerificationResult};
-use super::response::Patch;
-use crate::vm::RuntimeError;
+use super::response::{Patch, Suggestion};
+use crate::vm::{RuntimeError, Value, VM};
 
 /// Umbral minimo de confianza para aplicar fixes automaticamente
 const DEFAULT_CONFIDENCE_THRESHOLD: f32 = 0.8;
@@ -44,6 +44,80 @@ const DEFAULT_CONFIDENCE_THRESHOLD: f32 = 0.8;
 /// Numero maximo de intentos de reparacion por defecto
 const DEFAULT_MAX_ATTEMPTS: usize = 3;
 
+/// Expresiones que puede evaluar cada invariant, incluidas las definiciones
+/// del patch que menciona; un patch que no termina viola el invariant
+const INVARIANT_STEP_BUDGET: u64 = 100_000;
+
+/// Razon de escalar cuando el agente propone un fix ya intentado
+const REPEATED_FIX_REASON: &str = "agent repeating itself";
+
+/// Normaliza un patch para compararlo: colapsa espacios y saltos de linea
+fn normalize_patch(patch: &str) -> String {
+    patch.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Retorna el primer invariant que no se cumple con el codigo parcheado
+///
+/// Cada invariant es una expresion AURA. Se evalua con `source` ligado al
+/// texto del patch y con las definiciones sin parametros del patch (ej:
+/// `api_url = "..."`) que el invariant menciona. Un invariant que no parsea,
+/// falla al evaluarse o no da `true` cuenta como violado.
+///
+/// El patch viene del agente y no es confiable: se evalua en una VM sin
+/// efectos (http, db, env y email fallan), con `print` capturado y un
+/// limite de pasos.
+pub fn find_violated_invariant(patched_source: &str, invariants: &[String]) -> Option<String> {
+    if invariants.is_empty() {
+        return None;
+    }
+
+    let program = crate::lexer::tokenize(patched_source)
+        .ok()
+        .and_then(|tokens| crate::parser::parse(tokens).ok());
+
+    invariants
+        .iter()
+        .find(|invariant| !invariant_holds(invariant, patched_source, program.as_ref()))
+        .cloned()
+}
+
+/// Evalua un invariant en una VM aislada con el programa parcheado cargado
+fn invariant_holds(invariant: &str, patched_source: &str, program: Option<&crate::parser::Program>) -> bool {
+    let Ok(tokens) = crate::lexer::tokenize(invariant) else {
+        return false;
+    };
+    let names: Vec<String> = tokens.iter()
+        .filter_map(|t| match &t.value {
+            crate::lexer::Token::Ident(name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect();
+    let Ok(expr) = crate::parser::parse_expression_complete(tokens) else {
+        return false;
+    };
+
+    let mut vm = VM::new()
+        .with_sandbox()
+        .with_captured_stdout()
+        .with_step_budget(INVARIANT_STEP_BUDGET);
+    if let Some(program) = program {
+        vm.load(program);
+        for def in &program.definitions {
+            if let crate::parser::Definition::FuncDef(func) = def
+                && func.params.is_empty()
+                && func.name != "main"
+                && names.contains(&func.name)
+                && let Ok(value) = vm.call_by_name(&func.name, Vec::new())
+            {
+                vm.define_var(func.name.clone(), value);
+            }
+        }
+    }
+    vm.define_var("source".to_string(), Value::String(patched_source.to_string()));
+
+    matches!(vm.eval(&expr), Ok(Value::Bool(true)))
+}
+
 /// Motor de auto-reparacion de errores
 ///
 /// Coordina la comunicacion entre la VM y el agente IA para
@@ -58,6 +132,8 @@ pub struct HealingEngine<P: AgentProvider> {
     confidence_threshold: f32,
     /// Historial de intentos fallidos (para evitar repetir soluciones)
     previous_attempts: Vec<String>,
+    /// Confianza de la ultima respuesta del agente
+    last_confidence: Option<f32>,
 }
 
 impl<P: AgentProvider> HealingEngine<P> {
@@ -69,6 +145,7 @@ impl<P: AgentProvider> HealingEngine<P> {
             max_attempts: DEFAULT_MAX_ATTEMPTS,
             confidence_threshold: DEFAULT_CONFIDENCE_THRESHOLD,
             previous_attempts: Vec::new(),
+            last_confidence: None,
         }
     }
 
@@ -118,12 +195,59 @@ impl<P: AgentProvider> HealingEngine<P> {
             .await
             .map_err(HealingError::ProviderError)?;
 
-        // Procesar la respuesta
+        self.record_result(response, context)
+    }
+
+    /// Como `heal_error`, pero pasa a `on_chunk` el texto que genera el agente
+    ///
+    /// Sirve para mostrar progreso mientras un modelo lento responde.
+    pub async fn heal_error_streaming(
+        &mut self,
+        error: &RuntimeError,
+        context: &HealingContext,
+        on_chunk: ChunkCallback<'_>,
+    ) -> Result<HealingResult, HealingError> {
+        if self.previous_attempts.len() >= self.max_attempts {
+            return Err(HealingError::MaxAttemptsReached);
+        }
+
+        let request = self.build_request(error, context);
+
+        let response = self.provider
+            .send_request_streaming(request, on_chunk)
+            .await
+            .map_err(HealingError::ProviderError)?;
+
+        self.record_result(response, context)
+    }
+
+    /// Procesa la respuesta y registra el intento si fue un fix
+    ///
+    /// Un fix igual a uno ya intentado (ignorando espacios) no se vuelve a
+    /// aplicar: se escala a un humano.
+    fn record_result(
+        &mut self,
+        response: AgentResponse,
+        context: &HealingContext,
+    ) -> Result<HealingResult, HealingError> {
+        self.last_confidence = Some(response.confidence);
         let result = self.process_response(response, context)?;
 
-        // Registrar intento si fue un fix
         if let HealingResult::Fixed { ref patch, .. } = result {
+            let normalized = normalize_patch(patch);
+            if self.previous_attempts.iter().any(|p| normalize_patch(p) == normalized) {
+                return Ok(HealingResult::NeedsHuman {
+                    reason: REPEATED_FIX_REASON.to_string(),
+                });
+            }
             self.previous_attempts.push(patch.clone());
+
+            // Queda registrado como intento para que el agente no lo repita
+            if let Some(invariant) = find_violated_invariant(patch, &context.invariants) {
+                return Ok(HealingResult::CannotFix {
+                    reason: format!("El fix viola el invariant: {}", invariant),
+                });
+            }
         }
 
         Ok(result)
@@ -256,12 +380,11 @@ impl<P: AgentProvider> HealingEngine<P> {
                 } else {
                     // Retornar como sugerencia si no auto-aplicamos
                     Ok(HealingResult::Suggested {
-                        suggestions: vec![
-                            format!("Patch sugerido (confianza: {:.0}%): {}",
-                                response.confidence * 100.0,
-                                patch.new_code
-                            )
-                        ],
+                        suggestions: vec![Suggestion {
+                            code: patch.new_code,
+                            rationale: response.explanation,
+                            confidence: response.confidence,
+                        }],
                     })
                 }
             }
@@ -281,21 +404,19 @@ impl<P: AgentProvider> HealingEngine<P> {
                     })
                 } else {
                     Ok(HealingResult::Suggested {
-                        suggestions: vec![
-                            format!("Codigo generado (confianza: {:.0}%): {}",
-                                response.confidence * 100.0,
-                                code
-                            )
-                        ],
+                        suggestions: vec![Suggestion {
+                            code,
+                            rationale: response.explanation,
+                            confidence: response.confidence,
+                        }],
                     })
                 }
             }
 
             Action::Suggest => {
-                let suggestions: Vec<String> = response.suggestions
-                    .into_iter()
-                    .map(|s| format!("{} (confianza: {:.0}%)", s.code, s.confidence * 100.0))
-                    .collect();
+                let mut suggestions = response.suggestions;
+                // La mas confiable primero; empates conservan el orden del agente
+                suggestions.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
 
                 if suggestions.is_empty() {
                     Ok(HealingResult::CannotFix {
@@ -338,6 +459,11 @@ impl<P: AgentProvider> HealingEngine<P> {
         self.confidence_threshold
     }
 
+    /// Confianza reportada en la ultima respuesta del agente
+    pub fn last_confidence(&self) -> Option<f32> {
+        self.last_confidence
+    }
+
     /// Obtiene el numero de intentos previos
     pub fn attempts_count(&self) -> usize {
         self.previous_attempts.len()
@@ -416,16 +542,14 @@ impl<P: AgentProvider> HealingEngine<P> {
             let verification = verifier(patch);
 
             match verification {
-                VerificationResult::Failure { ref error, .. } => {
-                    // Preparar para revertir
-                    if undo_manager.can_undo() {
-                        // El caller debe aplicar el undo manualmente
-                        return Ok(SafeHealingResult::VerificationFailed {
-                            snapshot_id: snapshot_id.clone(),
-                            patch: patch.clone(),
-                            error: error.clone(),
-                        });
-                    }
+                // Preparar para revertir
+                VerificationResult::Failure { ref error, .. } if undo_manager.can_undo() => {
+                    // El caller debe aplicar el undo manualmente
+                    return Ok(SafeHealingResult::VerificationFailed {
+                        snapshot_id: snapshot_id.clone(),
+                        patch: patch.clone(),
+                        error: error.clone(),
+                    });
                 }
                 _ => {
                     // Verificacion exitosa, actualizar el historial
@@ -563,8 +687,8 @@ pub enum HealingResult {
     },
     /// Se tienen sugerencias pero no se aplicaron automaticamente
     Suggested {
-        /// Lista de sugerencias de codigo
-        suggestions: Vec<String>,
+        /// Sugerencias de codigo, de mayor a menor confianza
+        suggestions: Vec<Suggestion>,
     },
     /// Se necesita intervencion humana
     NeedsHuman {
@@ -603,12 +727,17 @@ impl HealingResult {
     }
 
     /// Obtiene las sugerencias si estan disponibles
-    pub fn get_suggestions(&self) -> Option<&[String]> {
+    pub fn get_suggestions(&self) -> Option<&[Suggestion]> {
         match self {
             HealingResult::Suggested { suggestions } => Some(suggestions),
             _ => None,
         }
     }
+
+    /// La sugerencia con mayor confianza
+    pub fn best_suggestion(&self) -> Option<&Suggestion> {
+        self.get_suggestions().and_then(|s| s.first())
+    }
 }
 
 /// Resultado de healing seguro con snapshots
@@ -627,8 +756,8 @@ pub enum SafeHealingResult {
     Suggested {
         /// ID del snapshot (puede no tener cambios)
         snapshot_id: SnapshotId,
-        /// Sugerencias de codigo
-        suggestions: Vec<String>,
+        /// Sugerencias de codigo, de mayor a menor confianza
+        suggestions: Vec<Suggestion>,
     },
     /// Se necesita intervencion humana
     NeedsHuman {
@@ -793,6 +922,248 @@ mod tests {
         assert!(result.get_patch().is_some());
     }
 
+    #[tokio::test]
+    async fn test_heal_error_streaming_reports_chunks() {
+        let provider = MockProvider::new().with_latency(0);
+        let mut engine = HealingEngine::new(provider)
+            .with_auto_apply(true)
+            .with_confidence_threshold(0.5);
+
+        let error = RuntimeError::new("Variable no definida: x");
+        let context = HealingContext::new("x + 1", "test.aura", 1, 1);
+
+        let mut chunks = 0;
+        let result = engine
+            .heal_error_streaming(&error, &context, &mut |_: &str| chunks += 1)
+            .await
+            .unwrap();
+
+        assert!(result.is_fixed());
+        assert_eq!(chunks, 1);
+        assert_eq!(engine.attempts_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_heal_error_with_retrying_provider() {
+        let provider = crate::agent::RetryingProvider::new(
+            MockProvider::new().with_latency(0).with_failures(2),
+        )
+        .with_backoff(std::time::Duration::from_millis(1));
+        let mut engine = HealingEngine::new(provider)
+            .with_auto_apply(true)
+            .with_confidence_threshold(0.5);
+
+        let error = RuntimeError::new("Variable no definida: x");
+        let context = HealingContext::new("x + 1", "test.aura", 1, 1);
+
+        // Los fallos transitorios no llegan al engine
+        assert!(engine.heal_error(&error, &context).await.unwrap().is_fixed());
+    }
+
+    fn fix(code: &str, confidence: f32) -> AgentResponse {
+        AgentResponse::patch(Patch::new("x + 1", code), "fix guionado", confidence)
+    }
+
+    #[tokio::test]
+    async fn test_last_confidence_tracks_latest_response() {
+        let provider = MockProvider::new()
+            .with_latency(0)
+            .with_scripted(vec![fix("x = 0; x + 1", 0.9), fix("x = 1; x + 1", 0.3)]);
+        let mut engine = HealingEngine::new(provider)
+            .with_auto_apply(true)
+            .with_confidence_threshold(0.5);
+        assert_eq!(engine.last_confidence(), None);
+
+        let error = RuntimeError::new("Variable no definida: x");
+        let context = HealingContext::new("x + 1", "test.aura", 1, 1);
+
+        engine.heal_error(&error, &context).await.unwrap();
+        assert_eq!(engine.last_confidence(), Some(0.9));
+        engine.heal_error(&error, &context).await.unwrap();
+        assert_eq!(engine.last_confidence(), Some(0.3));
+    }
+
+    #[tokio::test]
+    async fn test_scripted_fixes_across_attempts() {
+        let provider = MockProvider::new()
+            .with_latency(0)
+            .with_scripted(vec![fix("x = 0; x + 1", 0.9), fix("x = 1; x + 1", 0.9)]);
+        let mut engine = HealingEngine::new(provider)
+            .with_auto_apply(true)
+            .with_confidence_threshold(0.5);
+
+        let error = RuntimeError::new("Variable no definida: x");
+        let context = HealingContext::new("x + 1", "test.aura", 1, 1);
+
+        let first = engine.heal_error(&error, &context).await.unwrap();
+        let second = engine.heal_error(&error, &context).await.unwrap();
+        assert_eq!(first.get_patch(), Some("x = 0; x + 1"));
+        assert_eq!(second.get_patch(), Some("x = 1; x + 1"));
+
+        // El segundo pedido informa el fix anterior para que no se repita
+        let requests = engine.provider.received_requests();
+        assert!(requests[0].previous_attempts.is_empty());
+        assert_eq!(requests[1].previous_attempts, vec!["x = 0; x + 1".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_heal_with_retry_skips_low_confidence_and_errors() {
+        let provider = MockProvider::new()
+            .with_latency(0)
+            .with_scripted(vec![fix("x = nil; x + 1", 0.3)])
+            .with_error_then(AgentError::ConnectionError("caido".to_string()))
+            .with_scripted(vec![fix("x = 0; x + 1", 0.9)]);
+        let mut engine = HealingEngine::new(provider)
+            .with_auto_apply(true)
+            .with_confidence_threshold(0.8);
+
+        let error = RuntimeError::new("Variable no definida: x");
+        let context = HealingContext::new("x + 1", "test.aura", 1, 1);
+
+        let result = engine.heal_with_retry(&error, &context).await.unwrap();
+        assert_eq!(result.get_patch(), Some("x = 0; x + 1"));
+        assert_eq!(engine.provider.request_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_patch_needs_human() {
+        let provider = MockProvider::new()
+            .with_latency(0)
+            .with_scripted(vec![fix("x = 0; x + 1", 0.9), fix("x = 0;\n  x + 1 ", 0.9)]);
+        let mut engine = HealingEngine::new(provider)
+            .with_auto_apply(true)
+            .with_confidence_threshold(0.5);
+
+        let error = RuntimeError::new("Variable no definida: x");
+        let context = HealingContext::new("x + 1", "test.aura", 1, 1);
+
+        assert!(engine.heal_error(&error, &context).await.unwrap().is_fixed());
+
+        let repeated = engine.heal_error(&error, &context).await.unwrap();
+        assert!(matches!(&repeated, HealingResult::NeedsHuman { reason } if reason == "agent repeating itself"));
+        // El fix repetido no se registra como un intento nuevo
+        assert_eq!(engine.attempts_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_heal_with_retry_stops_on_repeated_patch() {
+        let provider = MockProvider::new()
+            .with_latency(0)
+            .with_scripted(vec![fix("x = 0; x + 1", 0.9), fix("x = 0; x + 1", 0.9)]);
+        let mut engine = HealingEngine::new(provider)
+            .with_auto_apply(true)
+            .with_confidence_threshold(0.5);
+
+        let error = RuntimeError::new("Variable no definida: x");
+        let context = HealingContext::new("x + 1", "test.aura", 1, 1);
+
+        // Primer intento: fix; el engine lo considera ya aplicado
+        assert!(engine.heal_with_retry(&error, &context).await.unwrap().is_fixed());
+        // Si el fix no sirvio y el agente repite, se escala en vez de reintentar
+        assert!(engine.heal_with_retry(&error, &context).await.unwrap().needs_human());
+        assert_eq!(engine.provider.request_count(), 2);
+    }
+
+    #[test]
+    fn test_normalize_patch() {
+        assert_eq!(normalize_patch("  a =\t1\n\nb = 2 "), "a = 1 b = 2");
+    }
+
+    #[tokio::test]
+    async fn test_fix_violating_invariant_is_rejected() {
+        // El fix por defecto del mock reemplaza `api_url` por una URL literal
+        let provider = MockProvider::new().with_latency(0);
+        let mut engine = HealingEngine::new(provider)
+            .with_auto_apply(true)
+            .with_confidence_threshold(0.5);
+
+        let error = RuntimeError::new("Variable no definida: api_url");
+        let context = HealingContext::new("main = api_url", "test.aura", 1, 1)
+            .with_invariants(vec!["!contains(source, \"jsonplaceholder\")".to_string()]);
+
+        let result = engine.heal_error(&error, &context).await.unwrap();
+        match result {
+            HealingResult::CannotFix { reason } => {
+                assert!(reason.contains("!contains(source, \"jsonplaceholder\")"), "{}", reason);
+            }
+            other => panic!("Expected CannotFix, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fix_respecting_invariants_is_applied() {
+        let provider = MockProvider::new()
+            .with_latency(0)
+            .with_scripted(vec![fix("api_url = \"https://staging.example.com\"\nmain = api_url", 0.9)]);
+        let mut engine = HealingEngine::new(provider)
+            .with_auto_apply(true)
+            .with_confidence_threshold(0.5);
+
+        let error = RuntimeError::new("Variable no definida: api_url");
+        let context = HealingContext::new("main = api_url", "test.aura", 1, 1)
+            .with_invariants(vec![
+                "api_url != \"https://production.com\"".to_string(),
+                "!contains(source, \"mock_data\")".to_string(),
+            ]);
+
+        assert!(engine.heal_error(&error, &context).await.unwrap().is_fixed());
+    }
+
+    #[test]
+    fn test_find_violated_invariant() {
+        let patched = "api_url = \"https://production.com\"\nmain = api_url";
+        let invariants = vec![
+            "!contains(source, \"mock_data\")".to_string(),
+            "api_url != \"https://production.com\"".to_string(),
+        ];
+        assert_eq!(find_violated_invariant(patched, &invariants), Some(invariants[1].clone()));
+
+        assert_eq!(find_violated_invariant("main = mock_data()", &invariants[..1]), Some(invariants[0].clone()));
+        assert_eq!(find_violated_invariant("main = 1", &invariants[..1]), None);
+        // Un invariant que no se puede evaluar no deja pasar el fix
+        assert!(find_violated_invariant("main = 1", &["undefined_var > 0".to_string()]).is_some());
+    }
+
+    #[test]
+    fn test_invariant_check_does_not_run_patch_effects() {
+        // El patch intenta escribir en una base al evaluarse
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("patch.db");
+        let patched = format!(
+            "+db\nconn = db.connect(\"sqlite:{}\")\nmain = conn",
+            db_path.display()
+        );
+
+        find_violated_invariant(&patched, &["conn != nil".to_string()]);
+        assert!(!db_path.exists());
+    }
+
+    #[test]
+    fn test_invariant_check_stops_runaway_patch() {
+        let patched = "fib(n) = if n < 2 n else fib(n - 1) + fib(n - 2)\nspin = fib(60)\nmain = spin";
+        let invariants = vec!["spin == 0".to_string()];
+
+        let started = std::time::Instant::now();
+        assert_eq!(find_violated_invariant(patched, &invariants), Some(invariants[0].clone()));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_heal_with_retry_exhausts_script() {
+        let provider = MockProvider::new()
+            .with_latency(0)
+            .with_error_then(AgentError::Timeout { timeout_ms: 1 })
+            .with_error_then(AgentError::Timeout { timeout_ms: 1 })
+            .with_error_then(AgentError::Timeout { timeout_ms: 1 });
+        let mut engine = HealingEngine::new(provider).with_max_attempts(3);
+
+        let error = RuntimeError::new("Variable no definida: x");
+        let context = HealingContext::new("x + 1", "test.aura", 1, 1);
+
+        let result = engine.heal_with_retry(&error, &context).await;
+        assert!(matches!(result, Err(HealingError::ProviderError(AgentError::Timeout { .. }))));
+    }
+
     #[tokio::test]
     async fn test_heal_error_low_confidence() {
         // Crear respuesta con baja confianza
@@ -864,11 +1235,14 @@ mod tests {
 
     #[tokio::test]
     async fn test_heal_error_max_attempts() {
-        let provider = MockProvider::new().with_latency(0);
+        // Fixes distintos: uno repetido se escalaria en vez de contar
+        let provider = MockProvider::new()
+            .with_latency(0)
+            .with_scripted(vec![fix("a = 1", 0.85), fix("a = 2", 0.85)]);
         let mut engine = HealingEngine::new(provider)
             .with_max_attempts(2)
             .with_auto_apply(true)
-            .with_confidence_threshold(0.5); // MockProvider returns 0.85 confidence
+            .with_confidence_threshold(0.5);
 
         let error = RuntimeError::new("Error");
         let context = HealingContext::new("codigo", "test.aura", 1, 1);
@@ -933,6 +1307,48 @@ mod tests {
         assert!(context.surrounding_code.is_some());
     }
 
+    #[tokio::test]
+    async fn test_suggestions_sorted_by_confidence() {
+        let suggestion = |code: &str, confidence: f32| Suggestion {
+            code: code.to_string(),
+            rationale: format!("opcion {}", code),
+            confidence,
+        };
+        let provider = MockProvider::new()
+            .with_latency(0)
+            .with_response(AgentResponse::suggest(
+                vec![suggestion("a", 0.4), suggestion("b", 0.9), suggestion("c", 0.6), suggestion("d", 0.6)],
+                "varias opciones",
+            ));
+        let mut engine = HealingEngine::new(provider);
+
+        let error = RuntimeError::new("Error");
+        let context = HealingContext::new("x", "test.aura", 1, 1);
+        let result = engine.heal_error(&error, &context).await.unwrap();
+
+        let codes: Vec<&str> = result.get_suggestions().unwrap().iter().map(|s| s.code.as_str()).collect();
+        assert_eq!(codes, vec!["b", "c", "d", "a"]);
+        assert_eq!(result.best_suggestion().unwrap().confidence, 0.9);
+    }
+
+    #[tokio::test]
+    async fn test_low_confidence_patch_keeps_score() {
+        let provider = MockProvider::new()
+            .with_latency(0)
+            .with_scripted(vec![fix("x = 0; x + 1", 0.3)]);
+        let mut engine = HealingEngine::new(provider)
+            .with_auto_apply(true)
+            .with_confidence_threshold(0.8);
+
+        let error = RuntimeError::new("Variable no definida: x");
+        let context = HealingContext::new("x + 1", "test.aura", 1, 1);
+        let result = engine.heal_error(&error, &context).await.unwrap();
+
+        let best = result.best_suggestion().unwrap();
+        assert_eq!(best.code, "x = 0; x + 1");
+        assert_eq!(best.confidence, 0.3);
+    }
+
     #[test]
     fn test_healing_result_helpers() {
         let fixed = HealingResult::Fixed {
@@ -944,7 +1360,11 @@ mod tests {
         assert_eq!(fixed.get_patch(), Some("new code"));
 
         let suggested = HealingResult::Suggested {
-            suggestions: vec!["try this".to_string()],
+            suggestions: vec![Suggestion {
+                code: "try this".to_string(),
+                rationale: "why".to_string(),
+                confidence: 0.5,
+            }],
         };
         assert!(!suggested.is_fixed());
         assert!(suggested.has_suggestions());
@@ -0,0 +1,307 @@
+//! Healing Log - Historial de intentos de reparacion para auditoria
+//!
+//! Cada intento de `aura heal` (exitoso o no) se agrega como una linea JSON
+//! a `.aura/healing_log.jsonl`. El archivo solo crece: se abre en modo
+//! append y cada entrada se escribe con una sola llamada, asi dos procesos
+//! que sanan a la vez no intercalan lineas.
+//!
+//! ## Ejemplo de uso
+//!
+//! ```ignore
+//! use aura::agent::healing_log::{self, HealingLogEntry, HEALING_LOG_FILE};
+//!
+//! let entry = HealingLogEntry::from_result("main.aura", "Variable no definida: x", "mock", &result)
+//!     .with_confidence(0.9);
+//! healing_log::append(HEALING_LOG_FILE, &entry)?;
+//!
+//! let fixed: Vec<_> = healing_log::read(HEALING_LOG_FILE)?
+//!     .into_iter()
+//!     .filter(|e| e.outcome == HealingOutcome::Fixed)
+//!     .collect();
+//! ```
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::healing::{HealingError, HealingResult};
+
+/// Archivo del historial, relativo al directorio del proyecto
+pub const HEALING_LOG_FILE: &str = ".aura/healing_log.jsonl";
+
+/// Resultado de un intento, tal como queda en el log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealingOutcome {
+    Fixed,
+    Suggested,
+    NeedsHuman,
+    CannotFix,
+    /// El proveedor o el engine fallaron
+    Error,
+}
+
+impl HealingOutcome {
+    /// Nombre usado en el JSON y en `aura heal-log --outcome`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fixed => "fixed",
+            Self::Suggested => "suggested",
+            Self::NeedsHuman => "needs_human",
+            Self::CannotFix => "cannot_fix",
+            Self::Error => "error",
+        }
+    }
+
+    /// Parsea el nombre de un outcome
+    pub fn parse(name: &str) -> Option<Self> {
+        [Self::Fixed, Self::Suggested, Self::NeedsHuman, Self::CannotFix, Self::Error]
+            .into_iter()
+            .find(|o| o.as_str() == name)
+    }
+}
+
+/// Una linea del historial
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealingLogEntry {
+    pub timestamp: DateTime<Utc>,
+    /// Archivo que se intento sanar
+    pub file: String,
+    /// Mensaje del error de runtime
+    pub error: String,
+    /// Proveedor consultado
+    pub provider: String,
+    pub outcome: HealingOutcome,
+    /// Fix aplicado o mejor sugerencia
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    /// Razon de un NeedsHuman/CannotFix o mensaje de error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Si el fix vino de la memoria de patrones en vez del agente
+    #[serde(default)]
+    pub from_memory: bool,
+}
+
+impl HealingLogEntry {
+    /// Crea una entrada con la hora actual
+    pub fn new(
+        file: impl Into<String>,
+        error: impl Into<String>,
+        provider: impl Into<String>,
+        outcome: HealingOutcome,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            file: file.into(),
+            error: error.into(),
+            provider: provider.into(),
+            outcome,
+            patch: None,
+            confidence: None,
+            reason: None,
+            from_memory: false,
+        }
+    }
+
+    /// Crea la entrada que corresponde a un resultado del engine
+    pub fn from_result(
+        file: impl Into<String>,
+        error: impl Into<String>,
+        provider: impl Into<String>,
+        result: &Result<HealingResult, HealingError>,
+    ) -> Self {
+        let (outcome, patch, confidence, reason) = match result {
+            Ok(HealingResult::Fixed { patch, .. }) => {
+                (HealingOutcome::Fixed, Some(patch.clone()), None, None)
+            }
+            Ok(suggested @ HealingResult::Suggested { .. }) => {
+                let best = suggested.best_suggestion();
+                (
+                    HealingOutcome::Suggested,
+                    best.map(|s| s.code.clone()),
+                    best.map(|s| s.confidence),
+                    None,
+                )
+            }
+            Ok(HealingResult::NeedsHuman { reason }) => {
+                (HealingOutcome::NeedsHuman, None, None, Some(reason.clone()))
+            }
+            Ok(HealingResult::CannotFix { reason }) => {
+                (HealingOutcome::CannotFix, None, None, Some(reason.clone()))
+            }
+            Err(e) => (HealingOutcome::Error, None, None, Some(e.to_string())),
+        };
+
+        Self {
+            patch,
+            confidence,
+            reason,
+            ..Self::new(file, error, provider, outcome)
+        }
+    }
+
+    pub fn with_patch(mut self, patch: impl Into<String>) -> Self {
+        self.patch = Some(patch.into());
+        self
+    }
+
+    /// Confianza del agente; no reemplaza la de una sugerencia ya cargada
+    pub fn with_confidence(mut self, confidence: f32) -> Self {
+        self.confidence.get_or_insert(confidence);
+        self
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Marca la entrada como fix recuperado de la memoria
+    pub fn from_memory(mut self) -> Self {
+        self.from_memory = true;
+        self
+    }
+
+    /// Serializa la entrada como una linea JSON (sin salto final)
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Agrega una entrada al final del log, creando el directorio si hace falta
+pub fn append<P: AsRef<Path>>(path: P, entry: &HealingLogEntry) -> io::Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut line = entry.to_json_line();
+    line.push('\n');
+
+    // Una sola escritura en modo append: las lineas de procesos distintos no se mezclan
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())
+}
+
+/// Lee todas las entradas del log, en orden de escritura
+///
+/// Un log inexistente se trata como vacio; las lineas que no se pueden
+/// parsear (por ejemplo, una escritura cortada) se ignoran.
+pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Vec<HealingLogEntry>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{AgentError, Suggestion};
+
+    #[test]
+    fn test_entry_json_roundtrip() {
+        let entry = HealingLogEntry::new("main.aura", "Variable no definida: x", "mock", HealingOutcome::Fixed)
+            .with_patch("x = 1\nmain = x")
+            .with_confidence(0.95);
+
+        let line = entry.to_json_line();
+        assert!(!line.contains('\n'));
+
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["outcome"], "fixed");
+        assert_eq!(json["provider"], "mock");
+        assert_eq!(json["patch"], "x = 1\nmain = x");
+        assert_eq!(json["from_memory"], false);
+        assert!(json["timestamp"].is_string());
+        assert!(json.get("reason").is_none());
+
+        let parsed: HealingLogEntry = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed, entry);
+    }
+
+    #[test]
+    fn test_entry_from_results() {
+        let suggested = Ok(HealingResult::Suggested {
+            suggestions: vec![Suggestion {
+                code: "x = 0".to_string(),
+                rationale: "inicializar".to_string(),
+                confidence: 0.6,
+            }],
+        });
+        let entry = HealingLogEntry::from_result("a.aura", "err", "mock", &suggested).with_confidence(0.1);
+        assert_eq!(entry.outcome, HealingOutcome::Suggested);
+        assert_eq!(entry.patch.as_deref(), Some("x = 0"));
+        assert_eq!(entry.confidence, Some(0.6));
+
+        let failed = Err(HealingError::ProviderError(AgentError::Timeout { timeout_ms: 5 }));
+        let entry = HealingLogEntry::from_result("a.aura", "err", "mock", &failed);
+        assert_eq!(entry.outcome, HealingOutcome::Error);
+        assert!(entry.reason.unwrap().contains("Timeout"));
+
+        let cannot = Ok(HealingResult::CannotFix { reason: "invariant".to_string() });
+        let entry = HealingLogEntry::from_result("a.aura", "err", "mock", &cannot);
+        assert_eq!(entry.outcome, HealingOutcome::CannotFix);
+        assert_eq!(entry.reason.as_deref(), Some("invariant"));
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".aura").join("healing_log.jsonl");
+
+        assert!(read(&path).unwrap().is_empty());
+
+        append(&path, &HealingLogEntry::new("a.aura", "e1", "mock", HealingOutcome::Fixed)).unwrap();
+        append(&path, &HealingLogEntry::new("b.aura", "e2", "mock", HealingOutcome::CannotFix)).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 2);
+
+        let entries = read(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].error, "e1");
+        assert_eq!(entries[1].outcome, HealingOutcome::CannotFix);
+    }
+
+    #[test]
+    fn test_read_skips_malformed_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("healing_log.jsonl");
+
+        append(&path, &HealingLogEntry::new("a.aura", "e1", "mock", HealingOutcome::Fixed)).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"timestamp\": \"cortado\n\n").unwrap();
+        append(&path, &HealingLogEntry::new("a.aura", "e2", "mock", HealingOutcome::Error)).unwrap();
+
+        let errors: Vec<String> = read(&path).unwrap().into_iter().map(|e| e.error).collect();
+        assert_eq!(errors, vec!["e1", "e2"]);
+    }
+
+    #[test]
+    fn test_outcome_names() {
+        for outcome in [HealingOutcome::Fixed, HealingOutcome::NeedsHuman, HealingOutcome::Error] {
+            assert_eq!(HealingOutcome::parse(outcome.as_str()), Some(outcome));
+            assert_eq!(
+                serde_json::to_value(outcome).unwrap(),
+                serde_json::Value::String(outcome.as_str().to_string())
+            );
+        }
+        assert_eq!(HealingOutcome::parse("bogus"), None);
+    }
+}
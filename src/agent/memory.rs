@@ -44,6 +44,13 @@ pub const MEMORY_VERSION: &str = "2.0";
 /// Nombre del archivo de memoria por defecto
 pub const MEMORY_FILE: &str = ".aura-memory.json";
 
+/// Similitud minima (0.0 - 1.0) entre templates para reusar un patron
+pub const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.85;
+
+fn default_similarity_threshold() -> f32 {
+    DEFAULT_SIMILARITY_THRESHOLD
+}
+
 /// Reasoning episode for cognitive memory
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReasoningEpisode {
@@ -97,6 +104,10 @@ pub struct HealingMemory {
     /// Reasoning episodes from cognitive execution (v2.0)
     #[serde(default)]
     pub reasoning_episodes: Vec<ReasoningEpisode>,
+
+    /// Similitud minima para la busqueda aproximada (no se persiste)
+    #[serde(skip, default = "default_similarity_threshold")]
+    pub similarity_threshold: f32,
 }
 
 /// Patron de error conocido
@@ -105,6 +116,10 @@ pub struct Pattern {
     /// Mensaje de error (o patron de mensaje)
     pub error: String,
 
+    /// Error normalizado con `error_template` (`Variable no definida: X`)
+    #[serde(default)]
+    pub template: String,
+
     /// Contexto donde ocurrio el error
     pub context: String,
 
@@ -118,6 +133,19 @@ pub struct Pattern {
     pub last_used: DateTime<Utc>,
 }
 
+/// Resultado de `HealingMemory::import`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    /// Patrones nuevos
+    pub added: usize,
+    /// Patrones que ya existian y se combinaron o reemplazaron
+    pub updated: usize,
+    /// Defaults agregados o sobrescritos
+    pub defaults_set: usize,
+    /// Defaults con otro valor en la memoria local
+    pub conflicts: Vec<String>,
+}
+
 /// Errores del sistema de memoria
 #[derive(Debug, Clone)]
 pub enum MemoryError {
@@ -169,9 +197,16 @@ impl HealingMemory {
             patterns: Vec::new(),
             project_defaults: HashMap::new(),
             reasoning_episodes: Vec::new(),
+            similarity_threshold: DEFAULT_SIMILARITY_THRESHOLD,
         }
     }
 
+    /// Configura la similitud minima de `find_pattern` (0.0 - 1.0)
+    pub fn with_similarity_threshold(mut self, threshold: f32) -> Self {
+        self.similarity_threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
     /// Carga la memoria desde un archivo
     ///
     /// Si el archivo no existe, retorna una memoria vacia.
@@ -192,6 +227,11 @@ impl HealingMemory {
             // reasoning_episodes will be empty due to #[serde(default)]
         }
 
+        // Patrones guardados antes de que existieran los templates
+        for pattern in memory.patterns.iter_mut().filter(|p| p.template.is_empty()) {
+            pattern.template = error_template(&pattern.error);
+        }
+
         Ok(memory)
     }
 
@@ -204,8 +244,11 @@ impl HealingMemory {
 
     /// Busca un patron que coincida con el error dado
     ///
-    /// Usa coincidencia parcial: si el mensaje de error contiene el patron
-    /// o el patron contiene el mensaje, se considera coincidencia.
+    /// Prueba, en orden:
+    /// 1. Coincidencia exacta del mensaje
+    /// 2. Mismo template (`Variable no definida: foo` y `... bar`)
+    /// 3. Coincidencia parcial: un mensaje contiene al otro
+    /// 4. El template mas parecido, si supera `similarity_threshold`
     pub fn find_pattern(&self, error: &str) -> Option<&Pattern> {
         // Normalizar el error para comparacion
         let error_lower = error.to_lowercase();
@@ -215,10 +258,39 @@ impl HealingMemory {
             return Some(pattern);
         }
 
+        let template = error_template(error).to_lowercase();
+        if let Some(pattern) = self.patterns.iter().find(|p| p.template.to_lowercase() == template) {
+            return Some(pattern);
+        }
+
         // Buscar coincidencia parcial
-        self.patterns.iter().find(|p| {
+        if let Some(pattern) = self.patterns.iter().find(|p| {
             let pattern_lower = p.error.to_lowercase();
             error_lower.contains(&pattern_lower) || pattern_lower.contains(&error_lower)
+        }) {
+            return Some(pattern);
+        }
+
+        self.patterns.iter()
+            .map(|p| (p, similarity(&template, &p.template.to_lowercase())))
+            .filter(|(_, score)| *score >= self.similarity_threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(p, _)| p)
+    }
+
+    /// Busca un fix que se pueda aplicar tal cual al archivo `source`
+    ///
+    /// `fix` es el archivo completo ya corregido, asi que solo sirve para el
+    /// mismo archivo en el que se registro: ademas del error (exacto o por
+    /// template) el contexto guardado tiene que ser exactamente `source`.
+    /// Los demas patrones son solo pistas para el agente.
+    pub fn find_fix_for_source(&self, error: &str, source: &str) -> Option<&Pattern> {
+        let error_lower = error.to_lowercase();
+        let template = error_template(error).to_lowercase();
+
+        self.patterns.iter().find(|p| {
+            p.context == source
+                && (p.error.to_lowercase() == error_lower || p.template.to_lowercase() == template)
         })
     }
 
@@ -258,9 +330,11 @@ impl HealingMemory {
             // Actualizar patron existente
             self.patterns[index].count += 1;
             self.patterns[index].last_used = now;
-            // Actualizar el fix si es diferente (el mas reciente gana)
+            // Actualizar el fix si es diferente (el mas reciente gana); el
+            // contexto es el archivo al que corresponde ese fix
             if self.patterns[index].fix != fix {
                 self.patterns[index].fix = fix.to_string();
+                self.patterns[index].context = context.to_string();
             }
             // Actualizar contexto si estaba vacio
             if self.patterns[index].context.is_empty() && !context.is_empty() {
@@ -270,6 +344,7 @@ impl HealingMemory {
             // Crear nuevo patron
             self.patterns.push(Pattern {
                 error: error.to_string(),
+                template: error_template(error),
                 context: context.to_string(),
                 fix: fix.to_string(),
                 count: 1,
@@ -278,6 +353,79 @@ impl HealingMemory {
         }
     }
 
+    /// Copia portable de la memoria: patrones y defaults, sin episodios
+    ///
+    /// Los episodios de razonamiento describen ejecuciones locales, no
+    /// conocimiento que sirva en otro proyecto.
+    pub fn export(&self) -> HealingMemory {
+        HealingMemory {
+            patterns: self.patterns.clone(),
+            project_defaults: self.project_defaults.clone(),
+            ..HealingMemory::new()
+        }
+    }
+
+    /// Incorpora los patrones y defaults de otra memoria
+    ///
+    /// Un patron con el mismo error se combina si `merge` (los contadores se
+    /// suman y queda el fix mas reciente) o se reemplaza si no. Un default
+    /// que ya existe con otro valor es un conflicto: se conserva el local,
+    /// salvo con `force`.
+    pub fn import(&mut self, other: HealingMemory, merge: bool, force: bool) -> ImportReport {
+        let mut report = ImportReport::default();
+
+        for incoming in other.patterns {
+            let existing = self.patterns.iter_mut()
+                .find(|p| p.error.to_lowercase() == incoming.error.to_lowercase());
+
+            match existing {
+                Some(pattern) if merge => {
+                    pattern.count += incoming.count;
+                    if incoming.last_used > pattern.last_used {
+                        pattern.last_used = incoming.last_used;
+                        pattern.fix = incoming.fix;
+                    }
+                    if pattern.context.is_empty() {
+                        pattern.context = incoming.context;
+                    }
+                    report.updated += 1;
+                }
+                Some(pattern) => {
+                    *pattern = incoming;
+                    report.updated += 1;
+                }
+                None => {
+                    self.patterns.push(incoming);
+                    report.added += 1;
+                }
+            }
+        }
+
+        // Archivos exportados antes de los templates
+        for pattern in self.patterns.iter_mut().filter(|p| p.template.is_empty()) {
+            pattern.template = error_template(&pattern.error);
+        }
+
+        let mut defaults: Vec<_> = other.project_defaults.into_iter().collect();
+        defaults.sort();
+        for (key, value) in defaults {
+            match self.project_defaults.get(&key) {
+                Some(current) if *current == value => continue,
+                Some(_) => {
+                    report.conflicts.push(key.clone());
+                    if !force {
+                        continue;
+                    }
+                }
+                None => {}
+            }
+            self.project_defaults.insert(key, value);
+            report.defaults_set += 1;
+        }
+
+        report
+    }
+
     /// Obtiene un valor por defecto del proyecto
     pub fn get_default(&self, key: &str) -> Option<&String> {
         self.project_defaults.get(key)
@@ -332,9 +480,8 @@ impl HealingMemory {
                 if observations.is_empty() {
                     return true;
                 }
-                let overlap = ep.observations.iter()
-                    .any(|obs| observations.iter().any(|o| obs.contains(o.as_str()) || o.contains(obs.as_str())));
-                overlap
+                ep.observations.iter()
+                    .any(|obs| observations.iter().any(|o| obs.contains(o.as_str()) || o.contains(obs.as_str())))
             })
             .collect()
     }
@@ -352,14 +499,14 @@ impl HealingMemory {
     /// Obtiene los patrones ordenados por uso (mas usados primero)
     pub fn patterns_by_usage(&self) -> Vec<&Pattern> {
         let mut patterns: Vec<_> = self.patterns.iter().collect();
-        patterns.sort_by(|a, b| b.count.cmp(&a.count));
+        patterns.sort_by_key(|p| std::cmp::Reverse(p.count));
         patterns
     }
 
     /// Obtiene los patrones ordenados por fecha (mas recientes primero)
     pub fn patterns_by_date(&self) -> Vec<&Pattern> {
         let mut patterns: Vec<_> = self.patterns.iter().collect();
-        patterns.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+        patterns.sort_by_key(|p| std::cmp::Reverse(p.last_used));
         patterns
     }
 
@@ -382,8 +529,10 @@ impl HealingMemory {
 impl Pattern {
     /// Crea un nuevo patron
     pub fn new(error: impl Into<String>, context: impl Into<String>, fix: impl Into<String>) -> Self {
+        let error = error.into();
         Self {
-            error: error.into(),
+            template: error_template(&error),
+            error,
             context: context.into(),
             fix: fix.into(),
             count: 1,
@@ -404,6 +553,99 @@ impl Pattern {
     }
 }
 
+/// Reduce un mensaje de error a su forma general
+///
+/// Lo que sigue al primer `:` suele nombrar cosas del programa: los
+/// identificadores y strings pasan a `X`. Los numeros pasan a `N` en todo
+/// el mensaje.
+///
+/// ```
+/// use aura::agent::memory::error_template;
+///
+/// assert_eq!(error_template("Variable no definida: api_url"), "Variable no definida: X");
+/// assert_eq!(error_template("Indice 7 fuera de rango"), "Indice N fuera de rango");
+/// ```
+pub fn error_template(error: &str) -> String {
+    let (head, tail) = match error.find(':') {
+        Some(i) => error.split_at(i + 1),
+        None => (error, ""),
+    };
+
+    let mut template = replace_words(head, false);
+    template.push_str(&replace_words(tail, true));
+    template
+}
+
+/// Reemplaza numeros por `N` y, si `identifiers`, palabras y strings por `X`
+fn replace_words(text: &str, identifiers: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '"' || c == '\'' {
+            if identifiers {
+                // Un string completo cuenta como un solo valor
+                for next in chars.by_ref() {
+                    if next == c {
+                        break;
+                    }
+                }
+                out.push('X');
+            } else {
+                out.push(c);
+            }
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut word = String::from(c);
+            while let Some(&next) = chars.peek() {
+                if next.is_alphanumeric() || next == '_' || next == '.' {
+                    word.push(next);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let numeric = word.chars().all(|ch| ch.is_ascii_digit() || ch == '.');
+            if numeric {
+                out.push('N');
+            } else if identifiers {
+                out.push('X');
+            } else {
+                out.push_str(&word);
+            }
+        } else {
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+/// Similitud entre dos strings: 1.0 si son iguales, 0.0 si no comparten nada
+///
+/// Es la distancia de Levenshtein normalizada por el largo mayor.
+fn similarity(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    1.0 - previous[b.len()] as f32 / longest as f32
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -472,6 +714,154 @@ mod tests {
         assert!(pattern.is_some());
     }
 
+    #[test]
+    fn test_error_template() {
+        assert_eq!(error_template("Variable no definida: foo"), "Variable no definida: X");
+        assert_eq!(error_template("Funcion no definida: get_users"), "Funcion no definida: X");
+        assert_eq!(error_template("Campo \"email\" no existe: \"user data\""), "Campo \"email\" no existe: X");
+        assert_eq!(error_template("Indice 10 fuera de rango (largo 3)"), "Indice N fuera de rango (largo N)");
+        assert_eq!(error_template("Division por cero"), "Division por cero");
+    }
+
+    #[test]
+    fn test_find_pattern_by_template() {
+        let mut memory = HealingMemory::new();
+        memory.record_fix("Variable no definida: foo", "", "foo = 1");
+        assert_eq!(memory.patterns[0].template, "Variable no definida: X");
+
+        let pattern = memory.find_pattern("Variable no definida: bar").unwrap();
+        assert_eq!(pattern.error, "Variable no definida: foo");
+
+        // Otro tipo de error no usa el mismo patron
+        assert!(memory.find_pattern("Funcion no definida: bar").is_none());
+    }
+
+    #[test]
+    fn test_find_pattern_near_miss() {
+        let mut memory = HealingMemory::new();
+        memory.record_fix("Variable no definida: foo", "", "foo = 1");
+
+        // Template casi igual: supera el umbral por defecto
+        let pattern = memory.find_pattern("Variables no definidas: bar");
+        assert!(pattern.is_some());
+
+        // Con un umbral mas estricto ya no coincide
+        let strict = memory.clone().with_similarity_threshold(0.99);
+        assert!(strict.find_pattern("Variables no definidas: bar").is_none());
+
+        // Errores distintos quedan lejos del umbral
+        assert!(memory.find_pattern("Tipo incorrecto en suma").is_none());
+    }
+
+    #[test]
+    fn test_load_fills_missing_templates() {
+        let json = r#"{
+            "version": "2.0",
+            "patterns": [{
+                "error": "Variable no definida: x",
+                "context": "",
+                "fix": "x = 1",
+                "count": 1,
+                "last_used": "2024-01-01T00:00:00Z"
+            }],
+            "project_defaults": {}
+        }"#;
+
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), json).unwrap();
+
+        let memory = HealingMemory::load(file.path()).unwrap();
+        assert_eq!(memory.patterns[0].template, "Variable no definida: X");
+        assert_eq!(memory.similarity_threshold, DEFAULT_SIMILARITY_THRESHOLD);
+        assert!(memory.find_pattern("Variable no definida: y").is_some());
+    }
+
+    #[test]
+    fn test_export_skips_episodes() {
+        let mut memory = HealingMemory::new();
+        memory.record_fix("Error A", "", "fix a");
+        memory.set_default("timeout", "30");
+        memory.record_episode(ReasoningEpisode {
+            trigger_type: "reason".to_string(),
+            observations: Vec::new(),
+            decision: "continue".to_string(),
+            decision_detail: String::new(),
+            outcome: None,
+            timestamp: Utc::now(),
+            context: EpisodeContext { file: "a.aura".to_string(), function: None, goals: Vec::new() },
+        });
+
+        let exported = memory.export();
+        assert_eq!(exported.pattern_count(), 1);
+        assert_eq!(exported.get_default("timeout").unwrap(), "30");
+        assert_eq!(exported.episode_count(), 0);
+    }
+
+    #[test]
+    fn test_import_merge_sums_counts() {
+        let mut local = HealingMemory::new();
+        local.record_fix("Variable no definida: x", "", "x = 1");
+        local.record_fix("Variable no definida: x", "", "x = 1");
+
+        let mut shared = HealingMemory::new();
+        shared.record_fix("Variable no definida: x", "", "x = 2");
+        shared.patterns[0].count = 3;
+        shared.patterns[0].last_used = Utc::now() + chrono::Duration::minutes(1);
+        shared.record_fix("Division por cero", "", "guard");
+
+        let report = local.import(shared, true, false);
+        assert_eq!(report.added, 1);
+        assert_eq!(report.updated, 1);
+        assert_eq!(local.pattern_count(), 2);
+
+        let merged = local.find_pattern("Variable no definida: x").unwrap();
+        assert_eq!(merged.count, 5);
+        // El fix importado es mas reciente
+        assert_eq!(merged.fix, "x = 2");
+    }
+
+    #[test]
+    fn test_import_without_merge_overwrites() {
+        let mut local = HealingMemory::new();
+        local.record_fix("Error A", "", "fix local");
+        local.patterns[0].count = 10;
+
+        let mut shared = HealingMemory::new();
+        shared.record_fix("error a", "", "fix compartido");
+
+        let report = local.import(shared, false, false);
+        assert_eq!(report.updated, 1);
+        assert_eq!(local.pattern_count(), 1);
+        assert_eq!(local.patterns[0].count, 1);
+        assert_eq!(local.patterns[0].fix, "fix compartido");
+    }
+
+    #[test]
+    fn test_import_default_conflicts() {
+        let mut local = HealingMemory::new();
+        local.set_default("api_url", "https://local.dev");
+        local.set_default("timeout", "30");
+
+        let mut shared = HealingMemory::new();
+        shared.set_default("api_url", "https://shared.dev");
+        shared.set_default("timeout", "30");
+        shared.set_default("retries", "3");
+
+        let report = local.clone().import(shared.clone(), true, false);
+        assert_eq!(report.conflicts, vec!["api_url"]);
+        assert_eq!(report.defaults_set, 1);
+
+        let mut kept = local.clone();
+        kept.import(shared.clone(), true, false);
+        assert_eq!(kept.get_default("api_url").unwrap(), "https://local.dev");
+        assert_eq!(kept.get_default("retries").unwrap(), "3");
+
+        let report = local.import(shared, true, true);
+        assert_eq!(report.conflicts, vec!["api_url"]);
+        assert_eq!(report.defaults_set, 2);
+        assert_eq!(local.get_default("api_url").unwrap(), "https://shared.dev");
+    }
+
     #[test]
     fn test_record_fix_updates_existing() {
         let mut memory = HealingMemory::new();
@@ -483,6 +873,31 @@ mod tests {
         assert_eq!(memory.patterns[0].fix, "fix 2"); // El mas reciente gana
     }
 
+    #[test]
+    fn test_find_fix_for_source_requires_same_file() {
+        let mut memory = HealingMemory::new();
+        memory.record_fix("Variable no definida: api_url", "main = api_url\n", "main = \"https://api\"\n");
+
+        let pattern = memory.find_fix_for_source("Variable no definida: api_url", "main = api_url\n").unwrap();
+        assert_eq!(pattern.fix, "main = \"https://api\"\n");
+
+        // Mismo template en otro archivo: no se reusa el archivo corregido
+        let other = "total(x) = x * 2\nmain = total(foo)\n";
+        assert!(memory.find_fix_for_source("Variable no definida: foo", other).is_none());
+        assert!(memory.find_pattern("Variable no definida: foo").is_some());
+    }
+
+    #[test]
+    fn test_record_fix_keeps_context_with_fix() {
+        let mut memory = HealingMemory::new();
+        memory.record_fix("Error A", "archivo 1", "fix 1");
+        memory.record_fix("Error A", "archivo 2", "fix 2");
+
+        assert_eq!(memory.patterns[0].context, "archivo 2");
+        assert!(memory.find_fix_for_source("Error A", "archivo 1").is_none());
+        assert_eq!(memory.find_fix_for_source("Error A", "archivo 2").unwrap().fix, "fix 2");
+    }
+
     #[test]
     fn test_project_defaults() {
         let mut memory = HealingMemory::new();
@@ -59,6 +59,7 @@ mod bridge;
 mod healing;
 mod snapshot;
 mod undo;
+pub mod healing_log;
 pub mod memory;
 pub mod prompts;
 #[cfg(feature = "claude-api")]
@@ -70,11 +71,12 @@ mod openai;
 
 pub use request::{AgentRequest, EventType, Context, SourceLocation, Constraints};
 pub use response::{AgentResponse, Action, Patch, Suggestion};
-pub use bridge::{AgentProvider, AgentError, MockProvider};
-pub use healing::{HealingEngine, HealingContext, HealingResult, HealingError, SafeHealingResult};
-pub use snapshot::{Snapshot, SnapshotId, SnapshotManager, SnapshotReason, SnapshotError, FileSnapshot, SnapshotSummary, RestoreResult};
+pub use bridge::{AgentProvider, AgentError, ChunkCallback, MockProvider, ProviderSelector, RetryingProvider};
+pub use healing::{find_violated_invariant, HealingEngine, HealingContext, HealingResult, HealingError, SafeHealingResult};
+pub use snapshot::{content_hash, hash_matches, unified_diff, FileDrift, Snapshot, SnapshotId, SnapshotManager, SnapshotReason, SnapshotError, FileSnapshot, SnapshotSummary, RestoreResult};
 pub use undo::{UndoManager, UndoError, HealingAction, VerificationResult, UndoResult, RedoResult};
-pub use memory::{HealingMemory, Pattern as MemoryPattern, MemoryError, MEMORY_FILE, ReasoningEpisode, EpisodeOutcome, EpisodeContext};
+pub use healing_log::{HealingLogEntry, HealingOutcome, HEALING_LOG_FILE};
+pub use memory::{HealingMemory, Pattern as MemoryPattern, MemoryError, ImportReport, MEMORY_FILE, ReasoningEpisode, EpisodeOutcome, EpisodeContext};
 
 #[cfg(feature = "claude-api")]
 pub use claude::ClaudeProvider;
@@ -16,6 +16,11 @@
 //!     .with_message("Variable 'x' no definida");
 //!
 //! let response = provider.send_request(request).await?;
+//!
+//! // Con streaming, cada fragmento llega a medida que el modelo lo genera
+//! let response = provider
+//!     .send_request_streaming(request, &mut |chunk: &str| print!("{}", chunk))
+//!     .await?;
 //! ```
 //!
 //! ## Requisitos
@@ -29,7 +34,7 @@ use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
-use super::bridge::{AgentError, AgentProvider};
+use super::bridge::{AgentError, AgentProvider, ChunkCallback};
 use super::request::AgentRequest;
 use super::response::{AgentResponse, Patch, Suggestion};
 
@@ -54,8 +59,7 @@ pub struct OllamaProvider {
     client: reqwest::Client,
     /// Timeout para requests
     timeout: Duration,
-    /// Habilitar streaming (no usado actualmente, reservado para futuro)
-    #[allow(dead_code)]
+    /// Si `send_request` usa el stream de /api/generate
     streaming: bool,
 }
 
@@ -89,7 +93,9 @@ impl OllamaProvider {
         self
     }
 
-    /// Habilita o deshabilita streaming (reservado para futuro uso)
+    /// Habilita o deshabilita streaming en `send_request`
+    ///
+    /// `send_request_streaming` siempre usa el stream.
     pub fn with_streaming(mut self, streaming: bool) -> Self {
         self.streaming = streaming;
         self
@@ -244,13 +250,65 @@ impl OllamaProvider {
 
     /// Llama a la API de Ollama usando el endpoint /api/generate
     async fn call_api(&self, prompt: &str) -> Result<String, AgentError> {
+        let response = self.post_generate(prompt, false).await?;
+
+        let api_response: OllamaGenerateResponse = response.json().await.map_err(|e| {
+            AgentError::SerializationError(format!("Error parseando respuesta de Ollama: {}", e))
+        })?;
+
+        Ok(api_response.response)
+    }
+
+    /// Llama a /api/generate con `stream: true`
+    ///
+    /// Ollama responde una linea JSON por fragmento; cada fragmento se pasa
+    /// a `on_chunk` y se retorna el texto completo.
+    async fn call_api_streaming(
+        &self,
+        prompt: &str,
+        on_chunk: ChunkCallback<'_>,
+    ) -> Result<String, AgentError> {
+        let mut response = self.post_generate(prompt, true).await?;
+        let mut decoder = StreamDecoder::default();
+        let mut text = String::new();
+
+        loop {
+            let bytes = response.chunk().await.map_err(|e| self.map_request_error(e))?;
+            let finished = bytes.is_none();
+            let chunks = match bytes {
+                Some(bytes) => decoder.push(&bytes)?,
+                None => decoder.finish()?,
+            };
+
+            for chunk in chunks {
+                if let Some(error) = chunk.error {
+                    return Err(AgentError::InvalidResponse(format!("Error de Ollama: {}", error)));
+                }
+                if !chunk.response.is_empty() {
+                    on_chunk(&chunk.response);
+                    text.push_str(&chunk.response);
+                }
+                if chunk.done {
+                    return Ok(text);
+                }
+            }
+
+            if finished {
+                // El stream termino sin `done: true`; usar lo recibido
+                return Ok(text);
+            }
+        }
+    }
+
+    /// Envia el request a /api/generate y valida el status
+    async fn post_generate(&self, prompt: &str, stream: bool) -> Result<reqwest::Response, AgentError> {
         let url = format!("{}/api/generate", self.base_url);
 
         let request_body = OllamaGenerateRequest {
             model: self.model.clone(),
             prompt: prompt.to_string(),
             system: prompts::healing_system_prompt(),
-            stream: false,
+            stream,
             options: Some(OllamaOptions {
                 temperature: Some(0.2), // Baja temperatura para respuestas más determinísticas
                 num_predict: Some(4096),
@@ -265,20 +323,7 @@ impl OllamaProvider {
             .json(&request_body)
             .send()
             .await
-            .map_err(|e| {
-                if e.is_timeout() {
-                    AgentError::Timeout {
-                        timeout_ms: self.timeout.as_millis() as u64,
-                    }
-                } else if e.is_connect() {
-                    AgentError::ConnectionError(format!(
-                        "No se pudo conectar a Ollama en {}: {}. ¿Está Ollama corriendo?",
-                        self.base_url, e
-                    ))
-                } else {
-                    AgentError::ConnectionError(format!("Error de red: {}", e))
-                }
-            })?;
+            .map_err(|e| self.map_request_error(e))?;
 
         let status = response.status();
 
@@ -291,11 +336,23 @@ impl OllamaProvider {
             )));
         }
 
-        let api_response: OllamaGenerateResponse = response.json().await.map_err(|e| {
-            AgentError::SerializationError(format!("Error parseando respuesta de Ollama: {}", e))
-        })?;
+        Ok(response)
+    }
 
-        Ok(api_response.response)
+    /// Traduce un error de reqwest a AgentError
+    fn map_request_error(&self, e: reqwest::Error) -> AgentError {
+        if e.is_timeout() {
+            AgentError::Timeout {
+                timeout_ms: self.timeout.as_millis() as u64,
+            }
+        } else if e.is_connect() {
+            AgentError::ConnectionError(format!(
+                "No se pudo conectar a Ollama en {}: {}. ¿Está Ollama corriendo?",
+                self.base_url, e
+            ))
+        } else {
+            AgentError::ConnectionError(format!("Error de red: {}", e))
+        }
     }
 
     /// Verifica si Ollama está corriendo y el modelo está disponible
@@ -341,7 +398,23 @@ impl AgentProvider for OllamaProvider {
     ) -> Pin<Box<dyn Future<Output = Result<AgentResponse, AgentError>> + Send + 'a>> {
         Box::pin(async move {
             let prompt = self.format_prompt(&request);
-            let response_text = self.call_api(&prompt).await?;
+            let response_text = if self.streaming {
+                self.call_api_streaming(&prompt, &mut |_: &str| {}).await?
+            } else {
+                self.call_api(&prompt).await?
+            };
+            self.parse_response(&response_text)
+        })
+    }
+
+    fn send_request_streaming<'a>(
+        &'a self,
+        request: AgentRequest,
+        on_chunk: ChunkCallback<'a>,
+    ) -> Pin<Box<dyn Future<Output = Result<AgentResponse, AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let prompt = self.format_prompt(&request);
+            let response_text = self.call_api_streaming(&prompt, on_chunk).await?;
             self.parse_response(&response_text)
         })
     }
@@ -389,6 +462,59 @@ struct OllamaGenerateResponse {
     done: bool,
 }
 
+/// Una linea del stream de /api/generate
+#[derive(Deserialize)]
+struct OllamaStreamChunk {
+    #[serde(default)]
+    response: String,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Separa el body del stream en lineas JSON completas
+///
+/// Un fragmento de red puede cortar una linea (o un caracter UTF-8) a la
+/// mitad; lo que queda sin `\n` se guarda hasta el proximo fragmento.
+#[derive(Default)]
+struct StreamDecoder {
+    buffer: Vec<u8>,
+}
+
+impl StreamDecoder {
+    /// Agrega bytes y retorna las lineas completas
+    fn push(&mut self, bytes: &[u8]) -> Result<Vec<OllamaStreamChunk>, AgentError> {
+        self.buffer.extend_from_slice(bytes);
+
+        let Some(last_newline) = self.buffer.iter().rposition(|&b| b == b'\n') else {
+            return Ok(Vec::new());
+        };
+
+        let rest = self.buffer.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.buffer, rest);
+        Self::parse_lines(&complete)
+    }
+
+    /// Procesa lo que quedo en el buffer al terminar el stream
+    fn finish(&mut self) -> Result<Vec<OllamaStreamChunk>, AgentError> {
+        let rest = std::mem::take(&mut self.buffer);
+        Self::parse_lines(&rest)
+    }
+
+    fn parse_lines(bytes: &[u8]) -> Result<Vec<OllamaStreamChunk>, AgentError> {
+        bytes
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+            .map(|line| {
+                serde_json::from_slice(line).map_err(|e| {
+                    AgentError::SerializationError(format!("Error parseando stream de Ollama: {}", e))
+                })
+            })
+            .collect()
+    }
+}
+
 /// Response del endpoint /api/tags (para listar modelos)
 #[derive(Deserialize)]
 struct OllamaTagsResponse {
@@ -708,6 +834,100 @@ This should fix the issue."#;
         assert_eq!(result.unwrap().action, Action::Patch);
     }
 
+    #[test]
+    fn test_stream_decoder_splits_lines_across_chunks() {
+        let mut decoder = StreamDecoder::default();
+
+        let chunks = decoder.push(br#"{"response":"ho","done":false}
+{"respon"#).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].response, "ho");
+
+        let chunks = decoder.push(b"se\":\"la\",\"done\":false}\n").unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].response, "la");
+
+        // Ultima linea sin salto al cerrar el stream
+        decoder.push(br#"{"response":"","done":true}"#).unwrap();
+        let chunks = decoder.finish().unwrap();
+        assert!(chunks[0].done);
+    }
+
+    #[test]
+    fn test_stream_decoder_invalid_line() {
+        let mut decoder = StreamDecoder::default();
+        assert!(decoder.push(b"no es json\n").is_err());
+    }
+
+    /// Sirve `lines` como body, un fragmento de red por linea
+    async fn mock_stream_server(lines: Vec<String>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 64 * 1024];
+            let _ = socket.read(&mut request).await.unwrap();
+
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-type: application/x-ndjson\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            for line in lines {
+                socket.write_all(line.as_bytes()).await.unwrap();
+                socket.flush().await.unwrap();
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_send_request_streaming_with_mocked_body() {
+        let answer = r#"{"action": "generate", "generated_code": "x = 1", "explanation": "Define x", "confidence": 0.9}"#;
+        let mut lines: Vec<String> = answer
+            .as_bytes()
+            .chunks(16)
+            .map(|part| {
+                let part = String::from_utf8(part.to_vec()).unwrap();
+                format!("{}\n", serde_json::json!({"model": "llama3.2", "response": part, "done": false}))
+            })
+            .collect();
+        lines.push(format!("{}\n", serde_json::json!({"model": "llama3.2", "response": "", "done": true})));
+        let expected_chunks = lines.len() - 1;
+
+        let base_url = mock_stream_server(lines).await;
+        let provider = OllamaProvider::new().with_base_url(base_url);
+        let request = AgentRequest::error("x + 1", "test.aura", 1, 1);
+
+        let mut received = Vec::new();
+        let response = provider
+            .send_request_streaming(request, &mut |chunk: &str| received.push(chunk.to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(received.len(), expected_chunks);
+        assert_eq!(received.concat(), answer);
+        assert_eq!(response.action, Action::Generate);
+        assert_eq!(response.generated_code.as_deref(), Some("x = 1"));
+    }
+
+    #[tokio::test]
+    async fn test_streaming_error_line() {
+        let base_url = mock_stream_server(vec![
+            "{\"error\":\"model 'nope' not found\"}\n".to_string(),
+        ]).await;
+        let provider = OllamaProvider::new().with_base_url(base_url);
+
+        let result = provider
+            .send_request_streaming(AgentRequest::error("x", "t.aura", 1, 1), &mut |_: &str| {})
+            .await;
+        assert!(matches!(result, Err(AgentError::InvalidResponse(msg)) if msg.contains("not found")));
+    }
+
     #[test]
     fn test_provider_name() {
         let provider = OllamaProvider::new();
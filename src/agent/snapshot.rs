@@ -6,6 +6,8 @@ use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 
+use crate::caps::sha256_hex;
+
 /// Identificador único de snapshot
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SnapshotId(pub String);
@@ -70,22 +72,186 @@ pub struct FileSnapshot {
 
 impl FileSnapshot {
     pub fn new(path: PathBuf, content: String) -> Self {
-        let hash = Self::compute_hash(&content);
+        let hash = content_hash(&content);
         Self { path, content, hash }
     }
 
-    fn compute_hash(content: &str) -> String {
-        // Simple hash para comparación rápida
-        let mut hash: u64 = 0;
-        for byte in content.bytes() {
-            hash = hash.wrapping_mul(31).wrapping_add(byte as u64);
+    pub fn content_changed(&self, new_content: &str) -> bool {
+        !hash_matches(&self.hash, new_content)
+    }
+}
+
+/// Prefijo de los hashes SHA-256; los snapshots viejos no lo tienen
+const SHA256_PREFIX: &str = "sha256:";
+
+/// Hash con el que se guarda el contenido de un archivo
+pub fn content_hash(content: &str) -> String {
+    format!("{}{}", SHA256_PREFIX, sha256_hex(content))
+}
+
+/// Compara un hash guardado con un contenido
+///
+/// Acepta tambien el hash de 64 bits de los snapshots creados antes de
+/// usar SHA-256.
+pub fn hash_matches(hash: &str, content: &str) -> bool {
+    match hash.strip_prefix(SHA256_PREFIX) {
+        Some(digest) => digest == sha256_hex(content),
+        None => hash == legacy_hash(content),
+    }
+}
+
+fn legacy_hash(content: &str) -> String {
+    let mut hash: u64 = 0;
+    for byte in content.bytes() {
+        hash = hash.wrapping_mul(31).wrapping_add(byte as u64);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Estado de un archivo en disco respecto de su snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileDrift {
+    /// El archivo es igual al snapshot: restaurarlo no cambia nada
+    Unchanged,
+    /// El archivo cambio desde el snapshot
+    Drifted,
+    /// El archivo ya no existe
+    Missing,
+    /// El contenido guardado no coincide con su hash
+    Corrupt,
+}
+
+impl FileDrift {
+    /// Compara el archivo actual (`None` si no existe) con lo guardado
+    pub fn check(hash: &str, saved_content: &str, current: Option<&str>) -> Self {
+        if !hash_matches(hash, saved_content) {
+            return Self::Corrupt;
+        }
+        match current {
+            None => Self::Missing,
+            Some(content) if hash_matches(hash, content) => Self::Unchanged,
+            Some(_) => Self::Drifted,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unchanged => "unchanged",
+            Self::Drifted => "drifted",
+            Self::Missing => "missing",
+            Self::Corrupt => "corrupt",
         }
-        format!("{:016x}", hash)
     }
+}
 
-    pub fn content_changed(&self, new_content: &str) -> bool {
-        Self::compute_hash(new_content) != self.hash
+/// Lineas de contexto alrededor de cada cambio en `unified_diff`
+const DIFF_CONTEXT: usize = 3;
+
+/// Diff unificado (`---`/`+++`/`@@`) de `old` a `new`, vacio si son iguales
+///
+/// Se usa para mostrar que cambiaria al restaurar un snapshot.
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_ops(&old_lines, &new_lines);
+    if ops.iter().all(|op| matches!(op, DiffOp::Equal(..))) {
+        return String::new();
+    }
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", path, path);
+    let mut i = 0;
+    while i < ops.len() {
+        // Saltar hasta el proximo cambio
+        let Some(first_change) = ops[i..].iter().position(|op| !matches!(op, DiffOp::Equal(..))) else {
+            break;
+        };
+        let start = (i + first_change).saturating_sub(DIFF_CONTEXT).max(i);
+
+        // El hunk termina cuando hay mas de 2 * contexto lineas iguales seguidas
+        let mut end = i + first_change;
+        let mut equal_run = 0;
+        for (k, op) in ops.iter().enumerate().skip(end) {
+            if matches!(op, DiffOp::Equal(..)) {
+                equal_run += 1;
+                if equal_run > DIFF_CONTEXT * 2 {
+                    break;
+                }
+            } else {
+                equal_run = 0;
+                end = k;
+            }
+        }
+        let end = (end + DIFF_CONTEXT + 1).min(ops.len());
+
+        let hunk = &ops[start..end];
+        let (old_start, new_start) = hunk_start(&ops[..start]);
+        let old_count = hunk.iter().filter(|op| !matches!(op, DiffOp::Insert(_))).count();
+        let new_count = hunk.iter().filter(|op| !matches!(op, DiffOp::Delete(_))).count();
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start + usize::from(old_count > 0), old_count,
+            new_start + usize::from(new_count > 0), new_count
+        ));
+        for op in hunk {
+            match op {
+                DiffOp::Equal(line) => out.push_str(&format!(" {}\n", line)),
+                DiffOp::Delete(line) => out.push_str(&format!("-{}\n", line)),
+                DiffOp::Insert(line) => out.push_str(&format!("+{}\n", line)),
+            }
+        }
+
+        i = end;
+    }
+
+    out
+}
+
+enum DiffOp<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Lineas de `old` y `new` consumidas antes de un hunk
+fn hunk_start(before: &[DiffOp]) -> (usize, usize) {
+    let old = before.iter().filter(|op| !matches!(op, DiffOp::Insert(_))).count();
+    let new = before.iter().filter(|op| !matches!(op, DiffOp::Delete(_))).count();
+    (old, new)
+}
+
+/// Diff linea a linea por subsecuencia comun mas larga
+fn diff_ops<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffOp<'a>> {
+    // lcs[i][j] = largo de la LCS de old[i..] y new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
     }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            ops.push(DiffOp::Equal(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            ops.push(DiffOp::Delete(old[i]));
+            i += 1;
+        } else {
+            ops.push(DiffOp::Insert(new[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[i..].iter().map(|line| DiffOp::Delete(line)));
+    ops.extend(new[j..].iter().map(|line| DiffOp::Insert(line)));
+    ops
 }
 
 /// Un snapshot completo del estado
@@ -318,6 +484,62 @@ mod tests {
         assert!(snap.content_changed("modified"));
     }
 
+    #[test]
+    fn test_content_hash_is_sha256() {
+        let hash = content_hash("main = 1");
+        assert!(hash.starts_with("sha256:"));
+        assert_eq!(hash.len(), "sha256:".len() + 64);
+        assert!(hash_matches(&hash, "main = 1"));
+        assert!(!hash_matches(&hash, "main = 2"));
+    }
+
+    #[test]
+    fn test_legacy_hash_still_matches() {
+        let legacy = legacy_hash("main = 1");
+        assert_eq!(legacy.len(), 16);
+        assert!(hash_matches(&legacy, "main = 1"));
+        assert!(!hash_matches(&legacy, "main = 2"));
+    }
+
+    #[test]
+    fn test_file_drift() {
+        let hash = content_hash("main = 1");
+        assert_eq!(FileDrift::check(&hash, "main = 1", Some("main = 1")), FileDrift::Unchanged);
+        assert_eq!(FileDrift::check(&hash, "main = 1", Some("main = 2")), FileDrift::Drifted);
+        assert_eq!(FileDrift::check(&hash, "main = 1", None), FileDrift::Missing);
+        // El snapshot fue editado a mano
+        assert_eq!(FileDrift::check(&hash, "main = 3", Some("main = 3")), FileDrift::Corrupt);
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(unified_diff("a.aura", "x = 1\n", "x = 1\n"), "");
+
+        let diff = unified_diff("a.aura", "x = 100\nmain = x\n", "x = 42\nmain = x\n");
+        assert_eq!(diff, "--- a/a.aura\n+++ b/a.aura\n@@ -1,2 +1,2 @@\n-x = 100\n+x = 42\n main = x\n");
+    }
+
+    #[test]
+    fn test_unified_diff_splits_distant_hunks() {
+        let old: Vec<String> = (1..=20).map(|n| format!("l{}", n)).collect();
+        let mut new = old.clone();
+        new[1] = "cambio".to_string();
+        new[18] = "otro".to_string();
+
+        let diff = unified_diff("a.aura", &old.join("\n"), &new.join("\n"));
+        assert_eq!(diff.matches("@@ -").count(), 2);
+        assert!(diff.contains("@@ -1,5 +1,5 @@"));
+        assert!(diff.contains("@@ -16,5 +16,5 @@"));
+        assert!(diff.contains("-l2\n+cambio\n"));
+        assert!(!diff.contains(" l10\n"));
+    }
+
+    #[test]
+    fn test_unified_diff_new_file() {
+        let diff = unified_diff("a.aura", "", "x = 1\n");
+        assert!(diff.contains("@@ -0,0 +1,1 @@\n+x = 1\n"));
+    }
+
     #[test]
     fn test_snapshot_manager_create() {
         let mut manager = SnapshotManager::new(10);
@@ -0,0 +1,233 @@
+//! Capability +auth - Passwords y JWT
+//!
+//! - `auth.hash(password)` / `auth.verify(password, hash)`: Argon2id con salt
+//!   aleatorio, en formato PHC (`$argon2id$v=19$m=...,t=...,p=...$salt$hash`).
+//!   El hash guarda sus parametros, asi `verify` no necesita configuracion;
+//!   como el hash puede venir de afuera, `verify` rechaza costos mayores a
+//!   `MAX_M_COST`/`MAX_T_COST`/`MAX_P_COST` en vez de calcularlos.
+//! - `auth.jwt_sign(claims, secret)` / `auth.jwt_verify(token, secret)`: JWT
+//!   HS256. `jwt_verify` retorna el record de claims, o nil si la firma no
+//!   coincide, el token esta mal formado, `exp` no es un entero o ya paso.
+
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use argon2::password_hash::SaltString;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::caps::json::{json_parse, json_stringify};
+use crate::vm::{RuntimeError, Value};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SALT_LEN: usize = 16;
+
+/// Costo maximo aceptado al verificar: memoria en KiB (64 MiB)
+const MAX_M_COST: u32 = 64 * 1024;
+/// Costo maximo aceptado al verificar: pasadas sobre la memoria
+const MAX_T_COST: u32 = 10;
+/// Costo maximo aceptado al verificar: lanes en paralelo
+const MAX_P_COST: u32 = 4;
+
+/// Header fijo de los tokens firmados: solo se emite y acepta HS256
+const JWT_HEADER: &str = r#"{"alg":"HS256","typ":"JWT"}"#;
+
+/// Argon2id con los parametros por defecto de la crate (19 MiB, t=2, p=1)
+fn hasher() -> Argon2<'static> {
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default())
+}
+
+/// Hashea un password con Argon2id y un salt aleatorio
+pub fn hash_password(password: &str) -> Result<String, RuntimeError> {
+    let mut salt = [0u8; SALT_LEN];
+    getrandom::getrandom(&mut salt)
+        .map_err(|e| RuntimeError::new(format!("auth.hash: no se pudo generar el salt: {}", e)))?;
+    let salt = SaltString::encode_b64(&salt)
+        .map_err(|e| RuntimeError::new(format!("auth.hash: salt invalido: {}", e)))?;
+
+    hasher()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| RuntimeError::new(format!("auth.hash: {}", e)))
+}
+
+/// Compara un password con un hash de `hash_password`. Un hash mal formado,
+/// de otro algoritmo o con costos por encima de los maximos cuenta como
+/// password incorrecto.
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        return false;
+    };
+    if Algorithm::try_from(parsed.algorithm).is_err() {
+        return false;
+    }
+    let Ok(params) = Params::try_from(&parsed) else {
+        return false;
+    };
+    if params.m_cost() > MAX_M_COST || params.t_cost() > MAX_T_COST || params.p_cost() > MAX_P_COST {
+        return false;
+    }
+
+    hasher().verify_password(password.as_bytes(), &parsed).is_ok()
+}
+
+/// Firma un record de claims como JWT HS256
+pub fn jwt_sign(claims: &Value, secret: &str) -> Result<String, RuntimeError> {
+    if !matches!(claims, Value::Record(_)) {
+        return Err(RuntimeError::new("auth.jwt_sign requiere claims como record"));
+    }
+    let payload = json_stringify(claims)?;
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(JWT_HEADER),
+        URL_SAFE_NO_PAD.encode(payload)
+    );
+    let signature = URL_SAFE_NO_PAD.encode(sign(&signing_input, secret));
+    Ok(format!("{}.{}", signing_input, signature))
+}
+
+/// Verifica un JWT HS256 y retorna sus claims, o nil si no es valido
+pub fn jwt_verify(token: &str, secret: &str) -> Value {
+    decode_jwt(token, secret).unwrap_or(Value::Nil)
+}
+
+fn decode_jwt(token: &str, secret: &str) -> Option<Value> {
+    let (signing_input, signature) = token.rsplit_once('.')?;
+    let (header, payload) = signing_input.split_once('.')?;
+
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).ok()?;
+    mac.update(signing_input.as_bytes());
+    mac.verify_slice(&signature).ok()?;
+
+    // Con la firma valida, el header igual tiene que decir HS256
+    let header: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header).ok()?).ok()?;
+    if header.get("alg").and_then(|alg| alg.as_str()) != Some("HS256") {
+        return None;
+    }
+
+    let payload = String::from_utf8(URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    let claims = json_parse(&payload).ok()?;
+    let Value::Record(fields) = &claims else {
+        return None;
+    };
+    match fields.get("exp") {
+        None => {}
+        Some(Value::Int(exp)) if *exp > chrono::Utc::now().timestamp() => {}
+        // Vencido, o un `exp` que no es un timestamp entero
+        Some(_) => return None,
+    }
+    Some(claims)
+}
+
+fn sign(input: &str, secret: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC acepta claves de cualquier largo");
+    mac.update(input.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn claims(pairs: &[(&str, Value)]) -> Value {
+        Value::Record(pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect::<HashMap<_, _>>())
+    }
+
+    #[test]
+    fn test_hash_and_verify_password() {
+        let hash = hash_password("secreto").unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=19456,t=2,p=1$"), "{}", hash);
+        assert!(verify_password("secreto", &hash));
+        assert!(!verify_password("Secreto", &hash));
+        assert!(!verify_password("secreto", "no-es-un-hash"));
+    }
+
+    #[test]
+    fn test_hash_password_uses_random_salt() {
+        let a = hash_password("x").unwrap();
+        let b = hash_password("x").unwrap();
+        assert_ne!(a, b);
+        assert!(verify_password("x", &a));
+    }
+
+    #[test]
+    fn test_verify_rejects_costs_above_limits() {
+        let hash = hash_password("secreto").unwrap();
+        let (_, rest) = hash.split_once("m=19456,t=2,p=1").unwrap();
+
+        // Un hash con costos enormes no se calcula: se rechaza enseguida
+        for params in ["m=4194304,t=2,p=1", "m=19456,t=4000000000,p=1", "m=19456,t=2,p=64"] {
+            let hostile = format!("$argon2id$v=19${}{}", params, rest);
+            let started = std::time::Instant::now();
+            assert!(!verify_password("secreto", &hostile), "{}", hostile);
+            assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        }
+
+        // Dentro de los limites se sigue verificando con los parametros del hash
+        let within = format!("$argon2id$v=19$m=8192,t=1,p=1{}", rest);
+        assert!(!verify_password("secreto", &within));
+    }
+
+    #[test]
+    fn test_verify_rejects_other_schemes() {
+        assert!(!verify_password("secreto", "pbkdf2-sha256$1000$c2FsdA$aGFzaA"));
+        assert!(!verify_password("secreto", "$scrypt$ln=16,r=8,p=1$c2FsdA$aGFzaA"));
+    }
+
+    #[test]
+    fn test_jwt_round_trip() {
+        let original = claims(&[("sub", Value::String("alice".to_string())), ("admin", Value::Bool(true))]);
+        let token = jwt_sign(&original, "clave").unwrap();
+
+        assert_eq!(token.split('.').count(), 3);
+        assert_eq!(jwt_verify(&token, "clave"), original);
+        assert_eq!(jwt_verify(&token, "otra-clave"), Value::Nil);
+    }
+
+    #[test]
+    fn test_jwt_tampered_token_is_rejected() {
+        let token = jwt_sign(&claims(&[("role", Value::String("user".to_string()))]), "clave").unwrap();
+        let parts: Vec<&str> = token.split('.').collect();
+        let forged_payload = URL_SAFE_NO_PAD.encode(r#"{"role":"admin"}"#);
+        let forged = format!("{}.{}.{}", parts[0], forged_payload, parts[2]);
+
+        assert_eq!(jwt_verify(&forged, "clave"), Value::Nil);
+        assert_eq!(jwt_verify("no.es.jwt", "clave"), Value::Nil);
+        assert_eq!(jwt_verify("", "clave"), Value::Nil);
+    }
+
+    #[test]
+    fn test_jwt_expired_token_is_rejected() {
+        let past = chrono::Utc::now().timestamp() - 60;
+        let future = chrono::Utc::now().timestamp() + 3600;
+
+        let expired = jwt_sign(&claims(&[("exp", Value::Int(past))]), "clave").unwrap();
+        let valid = jwt_sign(&claims(&[("exp", Value::Int(future))]), "clave").unwrap();
+
+        assert_eq!(jwt_verify(&expired, "clave"), Value::Nil);
+        assert!(matches!(jwt_verify(&valid, "clave"), Value::Record(_)));
+    }
+
+    #[test]
+    fn test_jwt_non_integer_exp_is_rejected() {
+        let future = chrono::Utc::now().timestamp() + 3600;
+
+        for exp in [
+            Value::String(future.to_string()),
+            Value::Float(future as f64 + 0.5),
+            Value::Bool(true),
+            Value::Nil,
+        ] {
+            let token = jwt_sign(&claims(&[("exp", exp.clone())]), "clave").unwrap();
+            assert_eq!(jwt_verify(&token, "clave"), Value::Nil, "exp = {:?}", exp);
+        }
+    }
+
+    #[test]
+    fn test_jwt_sign_requires_record() {
+        assert!(jwt_sign(&Value::Int(1), "clave").is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! Capability +crypto - Hashing y bytes aleatorios
+//!
+//! - `crypto.sha256(data)`: SHA-256 en hexadecimal, de un string o de bytes.
+//!   Tambien lo usan los snapshots para verificar los archivos guardados.
+//! - `crypto.random_bytes(n)`: `n` bytes del generador del sistema operativo,
+//!   o de un generador determinista si se fijo una semilla (`aura --seed`).
+
+use std::sync::Mutex;
+
+use sha2::{Digest, Sha256};
+
+use crate::vm::RuntimeError;
+
+/// Maximo de bytes por llamada a `random_bytes`
+const MAX_RANDOM_BYTES: usize = 1024 * 1024;
+
+/// SHA-256 de los datos, en hexadecimal (64 caracteres en minuscula)
+///
+/// ```
+/// use aura::caps::sha256_hex;
+///
+/// assert_eq!(
+///     sha256_hex("abc"),
+///     "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+/// );
+/// ```
+pub fn sha256_hex(data: impl AsRef<[u8]>) -> String {
+    Sha256::digest(data.as_ref())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Generador fijado con `set_random_seed`; `None` usa el sistema operativo
+static SEEDED: Mutex<Option<SeededRng>> = Mutex::new(None);
+
+/// Generador determinista (SplitMix64): la misma semilla da siempre los
+/// mismos bytes, en cualquier plataforma. No es criptografico; sirve para
+/// tests y demos reproducibles.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn fill_bytes(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            let word = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+    }
+}
+
+/// Desde ahora `random_bytes` sale de un generador con esta semilla, para
+/// todo el proceso (flag `--seed`)
+pub fn set_random_seed(seed: u64) {
+    *SEEDED.lock().unwrap_or_else(|e| e.into_inner()) = Some(SeededRng::new(seed));
+}
+
+/// `n` bytes aleatorios del sistema operativo, o del generador de
+/// `set_random_seed` si se fijo una semilla
+pub fn random_bytes(n: usize) -> Result<Vec<u8>, RuntimeError> {
+    if n > MAX_RANDOM_BYTES {
+        return Err(RuntimeError::new(format!(
+            "crypto.random_bytes: maximo {} bytes, se pidieron {}",
+            MAX_RANDOM_BYTES, n
+        )));
+    }
+    let mut bytes = vec![0u8; n];
+    if let Some(rng) = SEEDED.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        rng.fill_bytes(&mut bytes);
+        return Ok(bytes);
+    }
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| RuntimeError::new(format!("crypto.random_bytes: {}", e)))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_bytes() {
+        let a = random_bytes(32).unwrap();
+        assert_eq!(a.len(), 32);
+        assert_ne!(a, random_bytes(32).unwrap());
+        assert!(random_bytes(0).unwrap().is_empty());
+        assert!(random_bytes(MAX_RANDOM_BYTES + 1).is_err());
+    }
+
+    #[test]
+    fn test_seeded_rng_is_reproducible() {
+        let draw = |seed| {
+            let mut bytes = [0u8; 20];
+            SeededRng::new(seed).fill_bytes(&mut bytes);
+            bytes
+        };
+        assert_eq!(draw(42), draw(42));
+        assert_ne!(draw(42), draw(43));
+
+        // Valor de referencia de SplitMix64: no debe cambiar entre versiones
+        assert_eq!(SeededRng::new(0).next_u64(), 0xE220_A839_7B1D_CDAF);
+    }
+
+    #[test]
+    fn test_sha256_known_vectors() {
+        assert_eq!(
+            sha256_hex(""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256_hex("The quick brown fox jumps over the lazy dog"),
+            "d7a8fbb307d7809469ca9abcb0082e4f8d5651e46d3cdb762d02d0bf37c9e592"
+        );
+    }
+
+    #[test]
+    fn test_sha256_is_sensitive_to_small_changes() {
+        assert_ne!(sha256_hex("main = 1"), sha256_hex("main = 2"));
+        assert_eq!(sha256_hex("main = 1").len(), 64);
+    }
+}
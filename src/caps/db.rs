@@ -20,6 +20,7 @@
 //!
 //!     db.execute!(conn, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)")
 //!     db.execute!(conn, "INSERT INTO users (name) VALUES (?)", ["Alice"])
+//!     db.execute!(conn, "INSERT INTO users (name) VALUES (:name)", {name: "Bob"})
 //!     let rows = db.query!(conn, "SELECT * FROM users")
 //!     db.close!(conn)
 //!     rows
@@ -53,8 +54,8 @@ const DB_TYPE_POSTGRES: &str = "db:postgres";
 fn detect_db_type(url: &str) -> (&'static str, &str) {
     if url == ":memory:" {
         (DB_TYPE_SQLITE, url)
-    } else if url.starts_with("sqlite:") {
-        (DB_TYPE_SQLITE, &url[7..]) // Remove "sqlite:" prefix
+    } else if let Some(path) = url.strip_prefix("sqlite:") {
+        (DB_TYPE_SQLITE, path)
     } else if url.starts_with("postgres://") || url.starts_with("postgresql://") {
         (DB_TYPE_POSTGRES, url)
     } else {
@@ -174,7 +175,8 @@ fn get_or_create_runtime() -> Result<tokio::runtime::Handle, RuntimeError> {
 /// # Arguments
 /// * `conn` - Database connection handle (from `db_connect`)
 /// * `sql` - SQL query string
-/// * `params` - Query parameters (positional, using `?` for SQLite, `$1` for PostgreSQL)
+/// * `params` - Query parameters (positional, using `?` for SQLite, `$1` for PostgreSQL;
+///   see `db_query_named` for `:name` placeholders)
 ///
 /// # Returns
 /// A `Value::List` containing `Value::Record` for each row.
@@ -367,6 +369,99 @@ fn execute_postgres(handle: u64, sql: &str, params: &[Value]) -> Result<Value, R
     })
 }
 
+/// Executes a SELECT query with named parameters.
+///
+/// `:name` placeholders in `sql` take their value from the field of the same
+/// name in `params`. A name may appear more than once; fields not referenced
+/// in the query are ignored.
+///
+/// # Errors
+/// Returns `RuntimeError` if a placeholder has no matching field, or for any
+/// error `db_query` would return.
+pub fn db_query_named(conn: &Value, sql: &str, params: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    let (sql, positional) = bind_named_params(sql, params, placeholder_style(conn))?;
+    db_query(conn, &sql, &positional)
+}
+
+/// Executes a statement with named parameters (see `db_query_named`).
+pub fn db_execute_named(conn: &Value, sql: &str, params: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    let (sql, positional) = bind_named_params(sql, params, placeholder_style(conn))?;
+    db_execute(conn, &sql, &positional)
+}
+
+/// Prefix of numbered placeholders: `?1` for SQLite, `$1` for PostgreSQL.
+fn placeholder_style(conn: &Value) -> char {
+    match conn {
+        Value::Native { type_id, .. } if type_id == DB_TYPE_POSTGRES => '$',
+        _ => '?',
+    }
+}
+
+/// Rewrites `:name` placeholders into numbered positional ones and returns the
+/// values in binding order.
+///
+/// Text inside single-quoted literals, double-quoted identifiers and
+/// PostgreSQL `::type` casts is left untouched.
+fn bind_named_params(
+    sql: &str,
+    params: &HashMap<String, Value>,
+    prefix: char,
+) -> Result<(String, Vec<Value>), RuntimeError> {
+    let mut out = String::with_capacity(sql.len());
+    let mut names: Vec<&str> = Vec::new();
+    let mut values = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut chars = sql.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+            out.push(c);
+            continue;
+        }
+        match c {
+            '\'' | '"' => {
+                quote = Some(c);
+                out.push(c);
+            }
+            ':' if chars.peek().is_some_and(|&(_, n)| n == ':') => {
+                chars.next();
+                out.push_str("::");
+            }
+            ':' if chars.peek().is_some_and(|&(_, n)| n.is_ascii_alphabetic() || n == '_') => {
+                let start = i + 1;
+                let mut end = start;
+                while let Some(&(j, n)) = chars.peek() {
+                    if !(n.is_ascii_alphanumeric() || n == '_') {
+                        break;
+                    }
+                    end = j + n.len_utf8();
+                    chars.next();
+                }
+                let name = &sql[start..end];
+                let index = match names.iter().position(|n| *n == name) {
+                    Some(pos) => pos + 1,
+                    None => {
+                        let value = params.get(name).ok_or_else(|| {
+                            RuntimeError::new(format!("Named parameter ':{}' not found in params record", name))
+                        })?;
+                        names.push(name);
+                        values.push(value.clone());
+                        names.len()
+                    }
+                };
+                out.push(prefix);
+                out.push_str(&index.to_string());
+            }
+            _ => out.push(c),
+        }
+    }
+
+    Ok((out, values))
+}
+
 /// Closes a database connection.
 ///
 /// # Arguments
@@ -462,7 +557,7 @@ fn get_postgres_connection(handle: u64) -> Result<Arc<tokio::sync::Mutex<PgClien
 
 /// Converts AURA values to SQLite parameters.
 fn convert_params_sqlite(params: &[Value]) -> Result<Vec<SqliteValue>, RuntimeError> {
-    params.iter().map(|v| aura_to_sqlite_value(v)).collect()
+    params.iter().map(aura_to_sqlite_value).collect()
 }
 
 /// Converts an AURA value to a SQLite value.
@@ -472,6 +567,7 @@ fn aura_to_sqlite_value(value: &Value) -> Result<SqliteValue, RuntimeError> {
         Value::Int(n) => Ok(SqliteValue::Integer(*n)),
         Value::Float(f) => Ok(SqliteValue::Real(*f)),
         Value::String(s) => Ok(SqliteValue::Text(s.clone())),
+        Value::Bytes(b) => Ok(SqliteValue::Blob(b.clone())),
         Value::Bool(b) => Ok(SqliteValue::Integer(if *b { 1 } else { 0 })),
         Value::List(items) => {
             // Convert list to JSON string for storage
@@ -505,10 +601,7 @@ fn sqlite_value_to_aura(value: rusqlite::types::ValueRef) -> Value {
         rusqlite::types::ValueRef::Text(s) => {
             Value::String(String::from_utf8_lossy(s).to_string())
         }
-        rusqlite::types::ValueRef::Blob(b) => {
-            // Convert blob to base64 string
-            Value::String(format!("blob:{}", base64_encode(b)))
-        }
+        rusqlite::types::ValueRef::Blob(b) => Value::Bytes(b.to_vec()),
     }
 }
 
@@ -524,6 +617,7 @@ enum PgParam {
     Float(f64),
     String(String),
     Bool(bool),
+    Bytes(Vec<u8>),
 }
 
 impl tokio_postgres::types::ToSql for PgParam {
@@ -534,6 +628,7 @@ impl tokio_postgres::types::ToSql for PgParam {
             PgParam::Float(f) => f.to_sql(ty, out),
             PgParam::String(s) => s.to_sql(ty, out),
             PgParam::Bool(b) => b.to_sql(ty, out),
+            PgParam::Bytes(b) => b.to_sql(ty, out),
         }
     }
 
@@ -542,6 +637,7 @@ impl tokio_postgres::types::ToSql for PgParam {
             || <f64 as tokio_postgres::types::ToSql>::accepts(ty)
             || <String as tokio_postgres::types::ToSql>::accepts(ty)
             || <bool as tokio_postgres::types::ToSql>::accepts(ty)
+            || <Vec<u8> as tokio_postgres::types::ToSql>::accepts(ty)
             || *ty == tokio_postgres::types::Type::TEXT
             || *ty == tokio_postgres::types::Type::VARCHAR
     }
@@ -561,6 +657,7 @@ fn aura_to_pg_param(value: &Value) -> Result<Box<dyn tokio_postgres::types::ToSq
         Value::Int(n) => Ok(Box::new(PgParam::Int(*n))),
         Value::Float(f) => Ok(Box::new(PgParam::Float(*f))),
         Value::String(s) => Ok(Box::new(PgParam::String(s.clone()))),
+        Value::Bytes(b) => Ok(Box::new(PgParam::Bytes(b.clone()))),
         Value::Bool(b) => Ok(Box::new(PgParam::Bool(*b))),
         Value::List(items) => {
             // Convert list to JSON string for storage
@@ -627,13 +724,13 @@ fn pg_value_to_aura(row: &tokio_postgres::Row, idx: usize) -> Result<Value, Runt
         }
         "json" | "jsonb" => {
             if let Ok(v) = row.try_get::<_, Option<serde_json::Value>>(idx) {
-                return Ok(v.map(|json| json_to_aura(&json)).unwrap_or(Value::Nil));
+                return Ok(v.map(|json| Value::from_json(&json)).unwrap_or(Value::Nil));
             }
             Ok(Value::Nil)
         }
         "bytea" => {
             if let Ok(v) = row.try_get::<_, Option<Vec<u8>>>(idx) {
-                return Ok(v.map(|b| Value::String(format!("blob:{}", base64_encode(&b)))).unwrap_or(Value::Nil));
+                return Ok(v.map(Value::Bytes).unwrap_or(Value::Nil));
             }
             Ok(Value::Nil)
         }
@@ -648,72 +745,12 @@ fn pg_value_to_aura(row: &tokio_postgres::Row, idx: usize) -> Result<Value, Runt
     }
 }
 
-/// Converts a serde_json::Value to an AURA Value.
-fn json_to_aura(json: &serde_json::Value) -> Value {
-    match json {
-        serde_json::Value::Null => Value::Nil,
-        serde_json::Value::Bool(b) => Value::Bool(*b),
-        serde_json::Value::Number(n) => {
-            if let Some(i) = n.as_i64() {
-                Value::Int(i)
-            } else if let Some(f) = n.as_f64() {
-                Value::Float(f)
-            } else {
-                Value::Nil
-            }
-        }
-        serde_json::Value::String(s) => Value::String(s.clone()),
-        serde_json::Value::Array(arr) => {
-            Value::List(arr.iter().map(json_to_aura).collect())
-        }
-        serde_json::Value::Object(obj) => {
-            let mut record = HashMap::new();
-            for (k, v) in obj {
-                record.insert(k.clone(), json_to_aura(v));
-            }
-            Value::Record(record)
-        }
-    }
-}
-
-// ============================================================================
-// Utilities
-// ============================================================================
-
-/// Simple base64 encoding for blobs.
-fn base64_encode(data: &[u8]) -> String {
-    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
-    let mut result = String::new();
-
-    for chunk in data.chunks(3) {
-        let b0 = chunk[0] as usize;
-        let b1 = chunk.get(1).copied().unwrap_or(0) as usize;
-        let b2 = chunk.get(2).copied().unwrap_or(0) as usize;
-
-        result.push(ALPHABET[b0 >> 2] as char);
-        result.push(ALPHABET[((b0 & 0x03) << 4) | (b1 >> 4)] as char);
-
-        if chunk.len() > 1 {
-            result.push(ALPHABET[((b1 & 0x0f) << 2) | (b2 >> 6)] as char);
-        } else {
-            result.push('=');
-        }
-
-        if chunk.len() > 2 {
-            result.push(ALPHABET[b2 & 0x3f] as char);
-        } else {
-            result.push('=');
-        }
-    }
-
-    result
-}
-
 // ============================================================================
 // Tests
 // ============================================================================
 
 #[cfg(test)]
+#[allow(clippy::collapsible_if, clippy::approx_constant)]
 mod tests {
     use super::*;
 
@@ -930,6 +967,72 @@ mod tests {
         db_close(&conn).unwrap();
     }
 
+    fn named(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+    }
+
+    #[test]
+    fn test_bind_named_params_rewrites_placeholders() {
+        let params = named(&[
+            ("name", Value::String("Alice".to_string())),
+            ("age", Value::Int(30)),
+            ("unused", Value::Nil),
+        ]);
+        let (sql, values) = bind_named_params(
+            "SELECT * FROM users WHERE name = :name OR alias = :name AND age > :age AND note != ':skip'",
+            &params,
+            '?',
+        ).unwrap();
+
+        assert_eq!(sql, "SELECT * FROM users WHERE name = ?1 OR alias = ?1 AND age > ?2 AND note != ':skip'");
+        assert_eq!(values, vec![Value::String("Alice".to_string()), Value::Int(30)]);
+    }
+
+    #[test]
+    fn test_bind_named_params_postgres_style_keeps_casts() {
+        let params = named(&[("id", Value::Int(7))]);
+        let (sql, values) = bind_named_params("SELECT :id::text AS id", &params, '$').unwrap();
+
+        assert_eq!(sql, "SELECT $1::text AS id");
+        assert_eq!(values, vec![Value::Int(7)]);
+    }
+
+    #[test]
+    fn test_bind_named_params_missing_field() {
+        let err = bind_named_params("SELECT :missing", &HashMap::new(), '?').unwrap_err();
+        assert!(err.message.contains(":missing"));
+    }
+
+    #[test]
+    fn test_sqlite_named_params() {
+        let conn = db_connect(":memory:").unwrap();
+
+        db_execute(&conn, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, age INTEGER)", &[]).unwrap();
+        db_execute_named(
+            &conn,
+            "INSERT INTO users (name, age) VALUES (:name, :age)",
+            &named(&[("age", Value::Int(30)), ("name", Value::String("Alice".to_string()))]),
+        ).unwrap();
+        db_execute(&conn, "INSERT INTO users (name, age) VALUES (?, ?)", &[
+            Value::String("Bob".to_string()),
+            Value::Int(25),
+        ]).unwrap();
+
+        let result = db_query_named(
+            &conn,
+            "SELECT name, age FROM users WHERE age >= :min AND name != :skip",
+            &named(&[("min", Value::Int(20)), ("skip", Value::String("Bob".to_string()))]),
+        ).unwrap();
+
+        let Value::List(rows) = result else { panic!("Expected List") };
+        assert_eq!(rows.len(), 1);
+        let Value::Record(row) = &rows[0] else { panic!("Expected Record") };
+        assert_eq!(row.get("name"), Some(&Value::String("Alice".to_string())));
+        assert_eq!(row.get("age"), Some(&Value::Int(30)));
+
+        db_close(&conn).unwrap();
+    }
+
     #[test]
     fn test_sqlite_null_values() {
         let conn = db_connect(":memory:").unwrap();
@@ -1047,10 +1150,25 @@ mod tests {
     }
 
     #[test]
-    fn test_base64_encode() {
-        assert_eq!(base64_encode(b"Hello"), "SGVsbG8=");
-        assert_eq!(base64_encode(b"Hi"), "SGk=");
-        assert_eq!(base64_encode(b""), "");
+    fn test_sqlite_blob_values() {
+        let conn = db_connect(":memory:").unwrap();
+        // Incluye bytes que no son UTF-8 valido
+        let data = vec![0u8, 159, 146, 150, 255];
+
+        db_execute(&conn, "CREATE TABLE blobs (data BLOB)", &[]).unwrap();
+        db_execute(&conn, "INSERT INTO blobs (data) VALUES (?)", &[Value::Bytes(data.clone())]).unwrap();
+
+        let result = db_query(&conn, "SELECT data, typeof(data) AS kind FROM blobs", &[]).unwrap();
+        let Value::List(rows) = result else {
+            panic!("Expected rows");
+        };
+        let Value::Record(row) = &rows[0] else {
+            panic!("Expected record");
+        };
+        assert_eq!(row.get("data"), Some(&Value::Bytes(data)));
+        assert_eq!(row.get("kind"), Some(&Value::String("blob".to_string())));
+
+        db_close(&conn).unwrap();
     }
 
     // ========================================================================
@@ -1162,22 +1280,22 @@ mod tests {
     #[test]
     fn test_json_to_aura() {
         let json_null = serde_json::Value::Null;
-        assert_eq!(json_to_aura(&json_null), Value::Nil);
+        assert_eq!(Value::from_json(&json_null), Value::Nil);
 
         let json_bool = serde_json::Value::Bool(true);
-        assert_eq!(json_to_aura(&json_bool), Value::Bool(true));
+        assert_eq!(Value::from_json(&json_bool), Value::Bool(true));
 
         let json_int = serde_json::json!(42);
-        assert_eq!(json_to_aura(&json_int), Value::Int(42));
+        assert_eq!(Value::from_json(&json_int), Value::Int(42));
 
         let json_float = serde_json::json!(3.14);
-        assert_eq!(json_to_aura(&json_float), Value::Float(3.14));
+        assert_eq!(Value::from_json(&json_float), Value::Float(3.14));
 
         let json_string = serde_json::json!("hello");
-        assert_eq!(json_to_aura(&json_string), Value::String("hello".to_string()));
+        assert_eq!(Value::from_json(&json_string), Value::String("hello".to_string()));
 
         let json_array = serde_json::json!([1, 2, 3]);
-        if let Value::List(items) = json_to_aura(&json_array) {
+        if let Value::List(items) = Value::from_json(&json_array) {
             assert_eq!(items.len(), 3);
             assert_eq!(items[0], Value::Int(1));
         } else {
@@ -1185,7 +1303,7 @@ mod tests {
         }
 
         let json_object = serde_json::json!({"name": "test"});
-        if let Value::Record(fields) = json_to_aura(&json_object) {
+        if let Value::Record(fields) = Value::from_json(&json_object) {
             assert_eq!(fields.get("name"), Some(&Value::String("test".to_string())));
         } else {
             panic!("Expected Record");
@@ -0,0 +1,397 @@
+//! Capability +email - Envio de mails
+//!
+//! `email.send(to, subject, body)` arma un mail de texto plano y lo entrega
+//! con el transporte elegido por el entorno:
+//!
+//! - `AURA_EMAIL_TRANSPORT=mock`: no hay red; el mensaje queda guardado en
+//!   memoria y se puede leer con `sent_messages()` (tests, demos).
+//! - por defecto, SMTP con `SMTP_HOST`, `SMTP_PORT`, `SMTP_FROM`, `SMTP_TLS`
+//!   y, si estan, `SMTP_USER` / `SMTP_PASSWORD`.
+//!
+//! `SMTP_TLS` elige el cifrado: `starttls` (por defecto, puerto 587), `tls`
+//! (SMTPS, puerto 465) o `none` (puerto 25). Con `starttls` el servidor
+//! tiene que ofrecer STARTTLS o no se envia nada; `none` es solo para un
+//! relay local y no acepta credenciales, que nunca viajan sin cifrar.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use lettre::Transport;
+use lettre::message::Mailbox;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+
+use crate::caps::env::{env_get, env_get_or};
+use crate::vm::{RuntimeError, Value};
+
+/// Variable de entorno que elige el transporte
+pub const TRANSPORT_VAR: &str = "AURA_EMAIL_TRANSPORT";
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+static NEXT_MESSAGE: AtomicU64 = AtomicU64::new(1);
+
+lazy_static::lazy_static! {
+    /// Mensajes entregados por el transporte mock
+    static ref MOCK_OUTBOX: Mutex<Vec<EmailMessage>> = Mutex::new(Vec::new());
+}
+
+/// Un mail listo para entregar
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailMessage {
+    pub message_id: String,
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Forma de entregar un mensaje
+pub trait EmailTransport {
+    /// Nombre para el record que retorna `email.send`
+    fn name(&self) -> &'static str;
+
+    fn send(&self, message: &EmailMessage) -> Result<(), RuntimeError>;
+}
+
+/// Guarda los mensajes en memoria en vez de enviarlos
+pub struct MockTransport;
+
+impl EmailTransport for MockTransport {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn send(&self, message: &EmailMessage) -> Result<(), RuntimeError> {
+        MOCK_OUTBOX.lock()
+            .map_err(|e| RuntimeError::new(format!("email: outbox no disponible: {}", e)))?
+            .push(message.clone());
+        Ok(())
+    }
+}
+
+/// Mensajes entregados hasta ahora por `MockTransport`
+pub fn sent_messages() -> Vec<EmailMessage> {
+    MOCK_OUTBOX.lock().map(|outbox| outbox.clone()).unwrap_or_default()
+}
+
+/// Cifrado de la conexion SMTP (`SMTP_TLS`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// `starttls`: conexion en texto plano que tiene que pasar a TLS
+    StartTls,
+    /// `tls`: TLS desde el primer byte (SMTPS)
+    Tls,
+    /// `none`: sin cifrar, solo para un relay local y sin credenciales
+    None,
+}
+
+impl SmtpSecurity {
+    fn parse(value: &str) -> Result<Self, RuntimeError> {
+        match value.to_ascii_lowercase().as_str() {
+            "starttls" => Ok(Self::StartTls),
+            "tls" => Ok(Self::Tls),
+            "none" => Ok(Self::None),
+            other => Err(RuntimeError::new(format!(
+                "SMTP_TLS invalido: {} (usar starttls, tls o none)", other
+            ))),
+        }
+    }
+
+    fn default_port(self) -> u16 {
+        match self {
+            Self::StartTls => 587,
+            Self::Tls => 465,
+            Self::None => 25,
+        }
+    }
+}
+
+/// Cliente SMTP (lettre) con TLS o STARTTLS obligatorio si hay credenciales
+pub struct SmtpTransport {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub credentials: Option<(String, String)>,
+}
+
+impl SmtpTransport {
+    /// Configuracion desde `SMTP_HOST`, `SMTP_TLS`, `SMTP_PORT`, `SMTP_USER`
+    /// y `SMTP_PASSWORD`
+    pub fn from_env() -> Result<Self, RuntimeError> {
+        let host = match env_get("SMTP_HOST") {
+            Value::String(host) => host,
+            _ => return Err(RuntimeError::new("email.send requiere SMTP_HOST (o AURA_EMAIL_TRANSPORT=mock)")),
+        };
+        let security = match env_get("SMTP_TLS") {
+            Value::String(value) => SmtpSecurity::parse(&value)?,
+            _ => SmtpSecurity::StartTls,
+        };
+        let default_port = Value::String(security.default_port().to_string());
+        let port = match env_get_or("SMTP_PORT", &default_port) {
+            Value::String(port) => port.parse::<u16>()
+                .map_err(|_| RuntimeError::new(format!("SMTP_PORT invalido: {}", port)))?,
+            _ => security.default_port(),
+        };
+        let credentials = match (env_get("SMTP_USER"), env_get("SMTP_PASSWORD")) {
+            (Value::String(user), Value::String(password)) => Some((user, password)),
+            _ => None,
+        };
+        Ok(Self { host, port, security, credentials })
+    }
+
+    fn tls(&self) -> Result<Tls, RuntimeError> {
+        let parameters = || TlsParameters::new(self.host.clone())
+            .map_err(|e| RuntimeError::new(format!("SMTP error: {}", e)));
+        Ok(match self.security {
+            SmtpSecurity::StartTls => Tls::Required(parameters()?),
+            SmtpSecurity::Tls => Tls::Wrapper(parameters()?),
+            SmtpSecurity::None => Tls::None,
+        })
+    }
+}
+
+impl EmailTransport for SmtpTransport {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    fn send(&self, message: &EmailMessage) -> Result<(), RuntimeError> {
+        if self.security == SmtpSecurity::None && self.credentials.is_some() {
+            return Err(RuntimeError::new(
+                "email.send: SMTP_USER/SMTP_PASSWORD requieren SMTP_TLS=starttls o tls; \
+                 las credenciales no se envian sin cifrar",
+            ));
+        }
+
+        let mut builder = lettre::SmtpTransport::builder_dangerous(self.host.as_str())
+            .port(self.port)
+            .tls(self.tls()?)
+            .timeout(Some(SMTP_TIMEOUT));
+        if let Some((user, password)) = &self.credentials {
+            builder = builder.credentials(Credentials::new(user.clone(), password.clone()));
+        }
+
+        builder.build()
+            .send(&build_message(message)?)
+            .map_err(|e| RuntimeError::new(format!("SMTP error: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Envia un mail con el transporte configurado y retorna
+/// `{message_id, to, transport}`
+pub fn email_send(to: &str, subject: &str, body: &str) -> Result<Value, RuntimeError> {
+    let mock = matches!(env_get(TRANSPORT_VAR), Value::String(t) if t == "mock");
+    if mock {
+        send_with(&MockTransport, to, subject, body)
+    } else {
+        send_with(&SmtpTransport::from_env()?, to, subject, body)
+    }
+}
+
+/// Arma el mensaje y lo entrega con `transport`
+pub fn send_with(transport: &dyn EmailTransport, to: &str, subject: &str, body: &str) -> Result<Value, RuntimeError> {
+    // Un salto de linea en un header permitiria inyectar headers o destinatarios
+    if to.contains(['\r', '\n']) || subject.contains(['\r', '\n']) {
+        return Err(RuntimeError::new("email.send: 'to' y 'subject' no pueden tener saltos de linea"));
+    }
+    if !to.contains('@') {
+        return Err(RuntimeError::new(format!("email.send: direccion invalida '{}'", to)));
+    }
+
+    let from = match env_get("SMTP_FROM") {
+        Value::String(from) => from,
+        _ => "aura@localhost".to_string(),
+    };
+    let domain = from.rsplit_once('@').map(|(_, d)| d.to_string()).unwrap_or_else(|| "localhost".to_string());
+    let message = EmailMessage {
+        message_id: format!(
+            "<{}.{}@{}>",
+            chrono::Utc::now().timestamp_millis(),
+            NEXT_MESSAGE.fetch_add(1, Ordering::SeqCst),
+            domain
+        ),
+        from,
+        to: to.to_string(),
+        subject: subject.to_string(),
+        body: body.to_string(),
+    };
+
+    transport.send(&message)?;
+
+    let mut record = std::collections::HashMap::new();
+    record.insert("message_id".to_string(), Value::String(message.message_id));
+    record.insert("to".to_string(), Value::String(message.to));
+    record.insert("transport".to_string(), Value::String(transport.name().to_string()));
+    Ok(Value::Record(record))
+}
+
+/// Mensaje de texto plano para lettre, que codifica el subject y hace el
+/// dot-stuffing del cuerpo
+fn build_message(message: &EmailMessage) -> Result<lettre::Message, RuntimeError> {
+    let mailbox = |address: &str| address.parse::<Mailbox>()
+        .map_err(|e| RuntimeError::new(format!("email.send: direccion invalida '{}': {}", address, e)));
+
+    lettre::Message::builder()
+        .from(mailbox(&message.from)?)
+        .to(mailbox(&message.to)?)
+        .subject(message.subject.clone())
+        .message_id(Some(message.message_id.clone()))
+        .header(ContentType::TEXT_PLAIN)
+        .body(message.body.clone())
+        .map_err(|e| RuntimeError::new(format!("email.send: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_mock_transport_captures_message() {
+        let result = send_with(&MockTransport, "bob@example.com", "Hola mock", "linea 1\nlinea 2").unwrap();
+
+        let Value::Record(fields) = result else { panic!("Expected Record") };
+        assert_eq!(fields.get("transport"), Some(&Value::String("mock".to_string())));
+        let Some(Value::String(id)) = fields.get("message_id") else { panic!("missing message_id") };
+
+        let sent = sent_messages();
+        let message = sent.iter().find(|m| &m.message_id == id).expect("message captured");
+        assert_eq!(message.to, "bob@example.com");
+        assert_eq!(message.subject, "Hola mock");
+        assert_eq!(message.body, "linea 1\nlinea 2");
+    }
+
+    #[test]
+    fn test_header_injection_is_rejected() {
+        let err = send_with(&MockTransport, "bob@example.com\r\nBcc: eve@example.com", "x", "y").unwrap_err();
+        assert!(err.message.contains("saltos de linea"));
+        assert!(send_with(&MockTransport, "sin-arroba", "x", "y").is_err());
+    }
+
+    #[test]
+    fn test_message_encodes_subject_and_keeps_id() {
+        let message = EmailMessage {
+            message_id: "<1@x.com>".to_string(),
+            from: "a@x.com".to_string(),
+            to: "b@x.com".to_string(),
+            subject: "Año".to_string(),
+            body: "hola".to_string(),
+        };
+        let formatted = String::from_utf8(build_message(&message).unwrap().formatted()).unwrap();
+        assert!(formatted.contains("Message-ID: <1@x.com>"), "{}", formatted);
+        assert!(!formatted.contains("Subject: Año"), "{}", formatted);
+        assert!(formatted.contains("Subject: =?utf-8?"), "{}", formatted);
+    }
+
+    #[test]
+    fn test_smtp_security_names_and_ports() {
+        assert_eq!(SmtpSecurity::parse("STARTTLS").unwrap().default_port(), 587);
+        assert_eq!(SmtpSecurity::parse("tls").unwrap().default_port(), 465);
+        assert_eq!(SmtpSecurity::parse("none").unwrap().default_port(), 25);
+        assert!(SmtpSecurity::parse("ssl3").is_err());
+    }
+
+    /// Servidor SMTP minimo: responde cada comando y devuelve lo recibido
+    fn fake_smtp_server(ehlo_reply: &'static [u8]) -> (u16, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut transcript = Vec::new();
+            writer.write_all(b"220 test ESMTP\r\n").unwrap();
+            let mut in_data = false;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    break;
+                }
+                let line = line.trim_end().to_string();
+                transcript.push(line.clone());
+                let reply: &[u8] = if in_data {
+                    if line != "." {
+                        continue;
+                    }
+                    in_data = false;
+                    b"250 queued\r\n"
+                } else if line.starts_with("EHLO") {
+                    ehlo_reply
+                } else if line.starts_with("AUTH") {
+                    b"235 ok\r\n"
+                } else if line == "DATA" {
+                    in_data = true;
+                    b"354 go\r\n"
+                } else if line == "QUIT" {
+                    let _ = writer.write_all(b"221 bye\r\n");
+                    break;
+                } else {
+                    b"250 ok\r\n"
+                };
+                if writer.write_all(reply).is_err() {
+                    break;
+                }
+            }
+            transcript
+        });
+
+        (port, server)
+    }
+
+    #[test]
+    fn test_smtp_transport_conversation() {
+        let (port, server) = fake_smtp_server(b"250-test\r\n250 8BITMIME\r\n");
+
+        // Relay local sin cifrar y sin credenciales
+        let transport = SmtpTransport {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::None,
+            credentials: None,
+        };
+        send_with(&transport, "bob@example.com", "Prueba", "hola\n.punto").unwrap();
+
+        let transcript = server.join().unwrap();
+        assert!(transcript.contains(&"RCPT TO:<bob@example.com>".to_string()), "{:?}", transcript);
+        assert!(transcript.contains(&"Subject: Prueba".to_string()));
+        assert!(transcript.contains(&"..punto".to_string()));
+        assert!(!transcript.iter().any(|line| line.starts_with("AUTH")));
+        assert_eq!(transcript.last(), Some(&"QUIT".to_string()));
+    }
+
+    #[test]
+    fn test_plaintext_smtp_refuses_credentials() {
+        let transport = SmtpTransport {
+            host: "127.0.0.1".to_string(),
+            port: 1,
+            security: SmtpSecurity::None,
+            credentials: Some(("user".to_string(), "pass".to_string())),
+        };
+        let err = send_with(&transport, "bob@example.com", "x", "y").unwrap_err();
+        assert!(err.message.contains("sin cifrar"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_starttls_is_required_before_auth() {
+        // El servidor ofrece AUTH pero no STARTTLS
+        let (port, server) = fake_smtp_server(b"250-test\r\n250 AUTH PLAIN\r\n");
+
+        let transport = SmtpTransport {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::StartTls,
+            credentials: Some(("user".to_string(), "pass".to_string())),
+        };
+        assert!(send_with(&transport, "bob@example.com", "x", "y").is_err());
+
+        let transcript = server.join().unwrap();
+        assert!(!transcript.iter().any(|line| line.starts_with("AUTH")), "{:?}", transcript);
+        assert!(!transcript.iter().any(|line| line.starts_with("MAIL FROM")), "{:?}", transcript);
+    }
+}
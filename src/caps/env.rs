@@ -1,7 +1,8 @@
 //! Capability ENV para AURA
 //!
 //! Proporciona funciones para acceder a variables de entorno.
-//! Requiere +env en el programa.
+//! Requiere +env en el programa. Con `+env(DB_URL, API_KEY)` solo se pueden
+//! leer esas claves; el resto se ve como no definido (ver [`EnvAllowlist`]).
 //!
 //! # Example
 //!
@@ -19,11 +20,44 @@
 //! }
 //! ```
 
+use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::path::Path;
 use crate::vm::{Value, RuntimeError};
 
+/// Claves de entorno que el programa puede leer
+///
+/// `+env` sin lista deja leer cualquier clave; `+env(A, B)` solo `A` y `B`,
+/// asi un programa no puede leer secretos que no declaro.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnvAllowlist(Option<HashSet<String>>);
+
+impl EnvAllowlist {
+    /// Sin restricciones (`+env` a secas)
+    pub fn any() -> Self {
+        Self(None)
+    }
+
+    /// Lista de `+env(...)`; vacia equivale a `+env`
+    pub fn from_keys(keys: &[String]) -> Self {
+        if keys.is_empty() {
+            Self(None)
+        } else {
+            Self::only(keys)
+        }
+    }
+
+    /// Solo estas claves, aunque la lista este vacia
+    pub fn only(keys: &[String]) -> Self {
+        Self(Some(keys.iter().cloned().collect()))
+    }
+
+    pub fn allows(&self, key: &str) -> bool {
+        self.0.as_ref().is_none_or(|keys| keys.contains(key))
+    }
+}
+
 /// Loads environment variables from a .env file if it exists.
 /// This is called automatically when the program starts.
 ///
@@ -149,6 +183,7 @@ pub fn env_exists(name: &str) -> Value {
 }
 
 #[cfg(test)]
+#[allow(clippy::writeln_empty_string)]
 mod tests {
     use super::*;
     use std::env;
@@ -231,6 +266,24 @@ mod tests {
         assert_eq!(env_exists("AURA_EXISTS_TEST"), Value::Bool(false));
     }
 
+    #[test]
+    fn test_allowlist_any() {
+        let allowlist = EnvAllowlist::any();
+        assert!(allowlist.allows("HOME"));
+        assert_eq!(EnvAllowlist::from_keys(&[]), allowlist);
+    }
+
+    #[test]
+    fn test_allowlist_declared_keys() {
+        let allowlist = EnvAllowlist::from_keys(&["DB_URL".to_string(), "API_KEY".to_string()]);
+        assert!(allowlist.allows("DB_URL"));
+        assert!(allowlist.allows("API_KEY"));
+        assert!(!allowlist.allows("AWS_SECRET_ACCESS_KEY"));
+        assert!(!allowlist.allows("db_url"));
+
+        assert!(!EnvAllowlist::only(&[]).allows("HOME"));
+    }
+
     #[test]
     fn test_strip_quotes_double() {
         assert_eq!(strip_quotes("\"hello\""), "hello");
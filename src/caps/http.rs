@@ -2,89 +2,143 @@
 //!
 //! Proporciona funciones para hacer requests HTTP reales.
 //! Requiere +http en el programa.
+//!
+//! Cada llamada retorna `{status, headers, body}`, con `body` como string;
+//! `resp.json()` lo parsea y `resp.json(Tipo)` ademas valida los campos.
 
 use std::collections::HashMap;
+use std::time::Duration;
+use reqwest::Method;
 use crate::vm::{Value, RuntimeError};
 
-/// Realiza un GET HTTP
-pub fn http_get(url: &str, headers: Option<&HashMap<String, String>>) -> Result<Value, RuntimeError> {
-    let client = reqwest::blocking::Client::new();
-    let mut request = client.get(url);
-
-    // Agregar headers si se proporcionaron
-    if let Some(hdrs) = headers {
-        for (key, value) in hdrs {
-            request = request.header(key.as_str(), value.as_str());
-        }
-    }
+/// Timeout por defecto de cada intento
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 
-    match request.send() {
-        Ok(response) => response_to_value(response),
-        Err(e) => Err(RuntimeError::new(format!("HTTP GET error: {}", e))),
+/// Variables de entorno con los defaults globales
+pub const TIMEOUT_ENV: &str = "AURA_HTTP_TIMEOUT_MS";
+pub const RETRIES_ENV: &str = "AURA_HTTP_RETRIES";
+
+/// Timeout y reintentos de un request
+///
+/// Un reintento se hace solo si el request no llego a tener respuesta
+/// (timeout o error de conexion); un status 500 se retorna tal cual.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpOptions {
+    pub timeout: Duration,
+    pub retries: u32,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self { timeout: DEFAULT_TIMEOUT, retries: 0 }
     }
 }
 
-/// Realiza un POST HTTP
-pub fn http_post(url: &str, body: Option<&str>, headers: Option<&HashMap<String, String>>) -> Result<Value, RuntimeError> {
-    let client = reqwest::blocking::Client::new();
-    let mut request = client.post(url);
-
-    // Agregar headers si se proporcionaron
-    if let Some(hdrs) = headers {
-        for (key, value) in hdrs {
-            request = request.header(key.as_str(), value.as_str());
+impl HttpOptions {
+    /// Defaults leidos de `AURA_HTTP_TIMEOUT_MS` y `AURA_HTTP_RETRIES`
+    pub fn from_env() -> Result<Self, RuntimeError> {
+        let mut options = Self::default();
+        if let Ok(ms) = std::env::var(TIMEOUT_ENV) {
+            let ms = ms.parse::<u64>()
+                .map_err(|_| RuntimeError::new(format!("{} invalido: {}", TIMEOUT_ENV, ms)))?;
+            options.timeout = Duration::from_millis(ms);
         }
+        if let Ok(retries) = std::env::var(RETRIES_ENV) {
+            options.retries = retries.parse::<u32>()
+                .map_err(|_| RuntimeError::new(format!("{} invalido: {}", RETRIES_ENV, retries)))?;
+        }
+        Ok(options)
     }
 
-    // Agregar body si se proporcionó
-    if let Some(b) = body {
-        request = request.body(b.to_string());
+    /// Aplica un record `{timeout_ms, retries}` sobre estas opciones
+    pub fn with_record(mut self, record: &HashMap<String, Value>) -> Result<Self, RuntimeError> {
+        for (key, value) in record {
+            match (key.as_str(), value) {
+                ("timeout_ms", Value::Int(ms)) if *ms > 0 => self.timeout = Duration::from_millis(*ms as u64),
+                ("retries", Value::Int(n)) if *n >= 0 => self.retries = *n as u32,
+                ("timeout_ms" | "retries", other) => {
+                    return Err(RuntimeError::new(format!("Opcion HTTP '{}' invalida: {}", key, other)));
+                }
+                _ => return Err(RuntimeError::new(format!("Opcion HTTP desconocida: '{}'", key))),
+            }
+        }
+        Ok(self)
     }
+}
 
-    match request.send() {
-        Ok(response) => response_to_value(response),
-        Err(e) => Err(RuntimeError::new(format!("HTTP POST error: {}", e))),
-    }
+/// Realiza un GET HTTP
+pub fn http_get(url: &str, headers: Option<&HashMap<String, String>>, options: &HttpOptions) -> Result<Value, RuntimeError> {
+    send(Method::GET, url, None, headers, options)
+}
+
+/// Realiza un POST HTTP
+pub fn http_post(url: &str, body: Option<&str>, headers: Option<&HashMap<String, String>>, options: &HttpOptions) -> Result<Value, RuntimeError> {
+    send(Method::POST, url, body, headers, options)
 }
 
 /// Realiza un PUT HTTP
-pub fn http_put(url: &str, body: Option<&str>, headers: Option<&HashMap<String, String>>) -> Result<Value, RuntimeError> {
-    let client = reqwest::blocking::Client::new();
-    let mut request = client.put(url);
-
-    // Agregar headers si se proporcionaron
-    if let Some(hdrs) = headers {
-        for (key, value) in hdrs {
-            request = request.header(key.as_str(), value.as_str());
+pub fn http_put(url: &str, body: Option<&str>, headers: Option<&HashMap<String, String>>, options: &HttpOptions) -> Result<Value, RuntimeError> {
+    send(Method::PUT, url, body, headers, options)
+}
+
+/// Realiza un DELETE HTTP
+pub fn http_delete(url: &str, headers: Option<&HashMap<String, String>>, options: &HttpOptions) -> Result<Value, RuntimeError> {
+    send(Method::DELETE, url, None, headers, options)
+}
+
+/// Envia el request, reintentando mientras no haya respuesta
+fn send(
+    method: Method,
+    url: &str,
+    body: Option<&str>,
+    headers: Option<&HashMap<String, String>>,
+    options: &HttpOptions,
+) -> Result<Value, RuntimeError> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(options.timeout)
+        .build()
+        .map_err(|e| RuntimeError::new(format!("HTTP {} error: {}", method, e)))?;
+
+    let mut attempt = 0;
+    loop {
+        let mut request = client.request(method.clone(), url);
+
+        // Agregar headers si se proporcionaron
+        if let Some(hdrs) = headers {
+            for (key, value) in hdrs {
+                request = request.header(key.as_str(), value.as_str());
+            }
         }
-    }
 
-    // Agregar body si se proporcionó
-    if let Some(b) = body {
-        request = request.body(b.to_string());
-    }
+        // Agregar body si se proporcionó
+        if let Some(b) = body {
+            request = request.body(b.to_string());
+        }
 
-    match request.send() {
-        Ok(response) => response_to_value(response),
-        Err(e) => Err(RuntimeError::new(format!("HTTP PUT error: {}", e))),
-    }
-}
+        let error = match request.send() {
+            Ok(response) => return response_to_value(response),
+            Err(e) => e,
+        };
 
-/// Realiza un DELETE HTTP
-pub fn http_delete(url: &str, headers: Option<&HashMap<String, String>>) -> Result<Value, RuntimeError> {
-    let client = reqwest::blocking::Client::new();
-    let mut request = client.delete(url);
-
-    // Agregar headers si se proporcionaron
-    if let Some(hdrs) = headers {
-        for (key, value) in hdrs {
-            request = request.header(key.as_str(), value.as_str());
+        let retryable = error.is_timeout() || error.is_connect();
+        if retryable && attempt < options.retries {
+            attempt += 1;
+            continue;
         }
-    }
 
-    match request.send() {
-        Ok(response) => response_to_value(response),
-        Err(e) => Err(RuntimeError::new(format!("HTTP DELETE error: {}", e))),
+        let attempts = attempt + 1;
+        return Err(if error.is_timeout() {
+            RuntimeError::new(format!(
+                "HTTP {} timeout: {} no respondio en {}ms ({} intento{})",
+                method,
+                url,
+                options.timeout.as_millis(),
+                attempts,
+                if attempts == 1 { "" } else { "s" }
+            ))
+        } else {
+            RuntimeError::new(format!("HTTP {} error: {}", method, error))
+        });
     }
 }
 
@@ -119,12 +173,107 @@ fn response_to_value(response: reqwest::blocking::Response) -> Result<Value, Run
 }
 
 #[cfg(test)]
+#[allow(clippy::collapsible_if)]
 mod tests {
     use super::*;
 
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Servidor que acepta conexiones y nunca responde; cuenta las conexiones
+    fn silent_server() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        std::thread::spawn(move || {
+            let mut open = Vec::new();
+            for stream in listener.incoming() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                open.push(stream);
+            }
+        });
+        (url, connections)
+    }
+
+    /// Responde un solo request con `raw` (status line, headers y body)
+    fn respond_once(raw: &'static str) -> String {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            stream.write_all(raw.as_bytes()).unwrap();
+        });
+        url
+    }
+
+    #[test]
+    fn test_response_is_status_headers_body_record() {
+        let url = respond_once("HTTP/1.1 201 Created\r\nX-Request-Id: abc\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhecho");
+
+        let Value::Record(record) = http_get(&url, None, &HttpOptions::default()).unwrap() else {
+            panic!("Expected Record");
+        };
+        assert_eq!(record.get("status"), Some(&Value::Int(201)));
+        assert_eq!(record.get("body"), Some(&Value::String("hecho".to_string())));
+        let Some(Value::Record(headers)) = record.get("headers") else { panic!("Expected headers record") };
+        assert_eq!(headers.get("x-request-id"), Some(&Value::String("abc".to_string())));
+    }
+
+    #[test]
+    fn test_error_status_is_a_response_not_an_error() {
+        let url = respond_once("HTTP/1.1 503 Service Unavailable\r\nContent-Length: 4\r\nConnection: close\r\n\r\ndown");
+
+        let Value::Record(record) = http_delete(&url, None, &HttpOptions::default()).unwrap() else {
+            panic!("Expected Record");
+        };
+        assert_eq!(record.get("status"), Some(&Value::Int(503)));
+        assert_eq!(record.get("body"), Some(&Value::String("down".to_string())));
+    }
+
+    #[test]
+    fn test_timeout_error_is_distinct() {
+        let (url, _) = silent_server();
+        let options = HttpOptions { timeout: Duration::from_millis(200), retries: 0 };
+
+        let err = http_get(&url, None, &options).unwrap_err();
+        assert!(err.message.starts_with("HTTP GET timeout:"), "{}", err.message);
+        assert!(err.message.contains("200ms"));
+    }
+
+    #[test]
+    fn test_timeout_is_retried() {
+        let (url, connections) = silent_server();
+        let options = HttpOptions { timeout: Duration::from_millis(100), retries: 2 };
+
+        let err = http_post(&url, Some("x"), None, &options).unwrap_err();
+        assert!(err.message.contains("3 intentos"), "{}", err.message);
+        assert_eq!(connections.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_options_from_record() {
+        let mut record = HashMap::new();
+        record.insert("timeout_ms".to_string(), Value::Int(1500));
+        record.insert("retries".to_string(), Value::Int(2));
+        let options = HttpOptions::default().with_record(&record).unwrap();
+        assert_eq!(options, HttpOptions { timeout: Duration::from_millis(1500), retries: 2 });
+
+        record.insert("timeout".to_string(), Value::Int(1));
+        assert!(HttpOptions::default().with_record(&record).unwrap_err().message.contains("desconocida"));
+    }
+
     #[test]
     fn test_http_get_httpbin() {
-        let result = http_get("https://httpbin.org/get", None);
+        let result = http_get("https://httpbin.org/get", None, &HttpOptions::default());
         assert!(result.is_ok());
 
         if let Ok(Value::Record(record)) = result {
@@ -140,7 +289,7 @@ mod tests {
 
     #[test]
     fn test_http_post_httpbin() {
-        let result = http_post("https://httpbin.org/post", Some("{\"test\": true}"), None);
+        let result = http_post("https://httpbin.org/post", Some("{\"test\": true}"), None, &HttpOptions::default());
         assert!(result.is_ok());
 
         if let Ok(Value::Record(record)) = result {
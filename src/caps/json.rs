@@ -3,91 +3,45 @@
 //! Proporciona funciones para parsear y serializar JSON.
 //! Requiere +json en el programa.
 
-use std::collections::HashMap;
 use serde_json::{self, Value as JsonValue};
 use crate::vm::{Value, RuntimeError};
 
 /// Parsea un string JSON a un Value de AURA
 pub fn json_parse(text: &str) -> Result<Value, RuntimeError> {
     match serde_json::from_str::<JsonValue>(text) {
-        Ok(json) => json_to_value(json),
+        Ok(json) => Ok(Value::from_json(&json)),
         Err(e) => Err(RuntimeError::new(format!("JSON parse error: {}", e))),
     }
 }
 
 /// Serializa un Value de AURA a string JSON
 pub fn json_stringify(value: &Value) -> Result<String, RuntimeError> {
-    let json = value_to_json(value)?;
+    let json = serializable_json(value)?;
     serde_json::to_string(&json)
         .map_err(|e| RuntimeError::new(format!("JSON stringify error: {}", e)))
 }
 
 /// Serializa un Value con formato legible (pretty print)
 pub fn json_stringify_pretty(value: &Value) -> Result<String, RuntimeError> {
-    let json = value_to_json(value)?;
+    let json = serializable_json(value)?;
     serde_json::to_string_pretty(&json)
         .map_err(|e| RuntimeError::new(format!("JSON stringify error: {}", e)))
 }
 
-/// Convierte un serde_json::Value a un Value de AURA
-fn json_to_value(json: JsonValue) -> Result<Value, RuntimeError> {
-    match json {
-        JsonValue::Null => Ok(Value::Nil),
-        JsonValue::Bool(b) => Ok(Value::Bool(b)),
-        JsonValue::Number(n) => {
-            // Intentar primero como entero, luego como flotante
-            if let Some(i) = n.as_i64() {
-                Ok(Value::Int(i))
-            } else if let Some(f) = n.as_f64() {
-                Ok(Value::Float(f))
-            } else {
-                Err(RuntimeError::new("JSON number out of range"))
-            }
-        }
-        JsonValue::String(s) => Ok(Value::String(s)),
-        JsonValue::Array(arr) => {
-            let items: Result<Vec<Value>, RuntimeError> = arr
-                .into_iter()
-                .map(json_to_value)
-                .collect();
-            Ok(Value::List(items?))
-        }
-        JsonValue::Object(obj) => {
-            let mut map = HashMap::new();
-            for (key, val) in obj {
-                map.insert(key, json_to_value(val)?);
-            }
-            Ok(Value::Record(map))
-        }
-    }
+/// Valida que el valor tenga equivalente exacto en JSON antes de pasarlo a
+/// `Value::to_json`, que convierte funciones, handles y NaN en placeholders
+fn serializable_json(value: &Value) -> Result<JsonValue, RuntimeError> {
+    check_serializable(value)?;
+    Ok(value.to_json())
 }
 
-/// Convierte un Value de AURA a serde_json::Value
-fn value_to_json(value: &Value) -> Result<JsonValue, RuntimeError> {
+fn check_serializable(value: &Value) -> Result<(), RuntimeError> {
     match value {
-        Value::Nil => Ok(JsonValue::Null),
-        Value::Bool(b) => Ok(JsonValue::Bool(*b)),
-        Value::Int(n) => Ok(JsonValue::Number((*n).into())),
-        Value::Float(f) => {
-            serde_json::Number::from_f64(*f)
-                .map(JsonValue::Number)
-                .ok_or_else(|| RuntimeError::new("Float value cannot be represented in JSON (NaN or Infinity)"))
-        }
-        Value::String(s) => Ok(JsonValue::String(s.clone())),
-        Value::List(items) => {
-            let arr: Result<Vec<JsonValue>, RuntimeError> = items
-                .iter()
-                .map(value_to_json)
-                .collect();
-            Ok(JsonValue::Array(arr?))
-        }
-        Value::Record(fields) => {
-            let mut obj = serde_json::Map::new();
-            for (key, val) in fields {
-                obj.insert(key.clone(), value_to_json(val)?);
-            }
-            Ok(JsonValue::Object(obj))
+        Value::Float(f) if !f.is_finite() => {
+            Err(RuntimeError::new("Float value cannot be represented in JSON (NaN or Infinity)"))
         }
+        Value::List(items) => items.iter().try_for_each(check_serializable),
+        Value::Record(fields) => fields.values().try_for_each(check_serializable),
         Value::Function(name) => {
             // Las funciones no se pueden serializar a JSON
             Err(RuntimeError::new(format!(
@@ -102,12 +56,14 @@ fn value_to_json(value: &Value) -> Result<JsonValue, RuntimeError> {
                 type_id
             )))
         }
+        _ => Ok(()),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
 
     #[test]
     fn test_json_parse_object() {
@@ -202,4 +158,11 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().message.contains("native handle"));
     }
+
+    #[test]
+    fn test_json_stringify_rejects_nested_nan() {
+        let value = Value::List(vec![Value::Int(1), Value::Float(f64::NAN)]);
+        let result = json_stringify(&value);
+        assert!(result.unwrap_err().message.contains("NaN"));
+    }
 }
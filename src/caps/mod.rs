@@ -3,12 +3,18 @@
 //! Cada capability proporciona acceso a recursos externos
 //! que requieren permisos explícitos (+http, +db, +fs, +json, +env, etc.)
 
+pub mod auth;
+pub mod crypto;
 pub mod db;
+pub mod email;
 pub mod env;
 pub mod http;
 pub mod json;
 
-pub use db::{db_connect, db_query, db_execute, db_close};
-pub use env::{load_dotenv, load_dotenv_from_path, env_get, env_get_or, env_set, env_remove, env_exists};
-pub use http::{http_get, http_post, http_put, http_delete};
+pub use auth::{hash_password, verify_password, jwt_sign, jwt_verify};
+pub use crypto::{sha256_hex, random_bytes};
+pub use db::{db_connect, db_query, db_execute, db_query_named, db_execute_named, db_close};
+pub use email::{email_send, sent_messages, EmailMessage, EmailTransport};
+pub use env::{EnvAllowlist, load_dotenv, load_dotenv_from_path, env_get, env_get_or, env_set, env_remove, env_exists};
+pub use http::{http_get, http_post, http_put, http_delete, HttpOptions};
 pub use json::{json_parse, json_stringify, json_stringify_pretty};
@@ -5,6 +5,57 @@
 
 use serde::{Deserialize, Serialize};
 
+/// Process exit codes used by every CLI command, one per error category
+///
+/// `--json` output and `--quiet` don't change them, so scripts can branch on
+/// the code alone.
+pub mod exit_code {
+    use crate::loader::{LoadError, LoadErrorKind};
+
+    pub const SUCCESS: i32 = 0;
+    /// Failure without a category of its own: bad arguments, nothing to undo, ...
+    pub const FAILURE: i32 = 1;
+    /// The source doesn't tokenize or parse, or its imports don't resolve
+    pub const PARSE: i32 = 2;
+    /// The type checker rejected the program
+    pub const TYPE: i32 = 3;
+    /// The program failed while running (including failed `expect`s)
+    pub const RUNTIME: i32 = 4;
+    /// A file couldn't be read or written
+    pub const IO: i32 = 5;
+
+    /// Code for an error loading a program and its imports
+    pub fn for_load_error(err: &LoadError) -> i32 {
+        match err.kind {
+            LoadErrorKind::Io => IO,
+            LoadErrorKind::Syntax | LoadErrorKind::Import => PARSE,
+        }
+    }
+}
+
+/// Version of the JSON format of the result types below. Bump it whenever a
+/// field is removed, renamed or changes meaning; new optional fields don't
+/// need a bump.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Leading `schema_version` and `kind` of every command result, so an agent
+/// can tell which format it is reading before looking at the rest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schema {
+    pub schema_version: u32,
+    /// Which result this is: `check`, `run`, `snapshot_restore`, ...
+    pub kind: String,
+}
+
+impl Schema {
+    pub fn new(kind: impl Into<String>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            kind: kind.into(),
+        }
+    }
+}
+
 /// Location information for errors and warnings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonLocation {
@@ -30,6 +81,14 @@ impl JsonLocation {
             end_col: Some(end_col),
         }
     }
+
+    /// Line and column (1-based) of a byte offset in `source`
+    pub fn from_offset(source: &str, offset: usize) -> Self {
+        let before = &source[..offset.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Self::new(line, before[line_start..].chars().count() + 1)
+    }
 }
 
 /// Error information with code, message, location, and optional suggestion
@@ -41,6 +100,12 @@ pub struct JsonError {
     pub location: Option<JsonLocation>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub suggestion: Option<String>,
+    /// Primera definicion cuando el error es un nombre duplicado
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_location: Option<JsonLocation>,
+    /// Lineas del fuente alrededor del error (la del error con `highlight`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context: Vec<crate::error::ContextLine>,
 }
 
 impl JsonError {
@@ -50,6 +115,8 @@ impl JsonError {
             message: message.into(),
             location: None,
             suggestion: None,
+            previous_location: None,
+            context: Vec::new(),
         }
     }
 
@@ -79,11 +146,28 @@ impl JsonError {
     pub fn from_type_error(err: &crate::types::TypeError, source: &str) -> Self {
         let mut error = Self::new("E201", &err.message);
         if let Some(ref span) = err.span {
-            error = error.with_location(span_to_location(span, source));
+            let location = span_to_location(span, source);
+            error.context = crate::error::ContextLine::around(source, location.line, 1);
+            error = error.with_location(location);
         }
         if let Some(ref suggestion) = err.suggestion {
             error = error.with_suggestion(suggestion);
         }
+        if let Some(ref previous) = err.previous_span {
+            error.previous_location = Some(span_to_location(previous, source));
+        }
+        error
+    }
+
+    /// Create from a type checker warning
+    pub fn from_type_warning(warning: &crate::types::TypeWarning, source: &str) -> Self {
+        let mut error = Self::new(warning.code, &warning.message);
+        if let Some(ref span) = warning.span {
+            error = error.with_location(span_to_location(span, source));
+        }
+        if let Some(ref suggestion) = warning.suggestion {
+            error = error.with_suggestion(suggestion);
+        }
         error
     }
 
@@ -92,40 +176,64 @@ impl JsonError {
         Self::new("E401", &err.message)
     }
 
+    /// Create for a failed `expect` that stopped the run
+    pub fn from_failed_expectations(err: &crate::vm::RuntimeError) -> Self {
+        Self::new("E402", &err.message)
+    }
+
+    /// Create for goal checks that didn't pass (`run --enforce-goals`)
+    pub fn from_failed_goals(goals: &[crate::vm::GoalOutcome]) -> Self {
+        let failed: Vec<&str> = goals.iter()
+            .filter(|g| !g.passed)
+            .map(|g| g.description.as_str())
+            .collect();
+        Self::new("E403", format!("Goals no cumplidos: {}", failed.join("; ")))
+    }
+
     /// Create for file read errors
     pub fn file_error(message: impl Into<String>) -> Self {
         Self::new("E501", message)
     }
+
+    /// Create from an error loading a program: E501 if a file couldn't be read
+    pub fn from_load_error(err: &crate::loader::LoadError) -> Self {
+        match err.kind {
+            crate::loader::LoadErrorKind::Io => Self::file_error(&err.message),
+            _ => Self::new("E001", &err.message),
+        }
+    }
 }
 
 /// Convert a span to a JSON location
 fn span_to_location(span: &crate::lexer::Span, source: &str) -> JsonLocation {
-    let before = &source[..span.start.min(source.len())];
-    let line = before.lines().count().max(1);
-    let last_newline = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
-    let col = span.start.saturating_sub(last_newline) + 1;
-    let end_col = if span.end > span.start {
-        Some(col + (span.end - span.start))
-    } else {
-        None
-    };
-
-    if let Some(end) = end_col {
-        JsonLocation::with_end(line, col, end)
-    } else {
-        JsonLocation::new(line, col)
+    let location = crate::error::Location::from_span(span, source, "");
+    match location.end_col {
+        Some(end) => JsonLocation::with_end(location.line, location.col, end),
+        None => JsonLocation::new(location.line, location.col),
     }
 }
 
 /// Result of `aura check` command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckResult {
+    #[serde(flatten)]
+    pub schema: Schema,
     pub success: bool,
     pub file: String,
     pub errors: Vec<JsonError>,
     pub warnings: Vec<JsonError>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stats: Option<CheckStats>,
+    /// Fixes written by `check --fix`, with the snapshot taken before writing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixes: Option<CheckFixes>,
+}
+
+/// Fixes applied to the source by `check --fix`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckFixes {
+    pub applied: usize,
+    pub snapshot_id: String,
 }
 
 /// Statistics about the checked program
@@ -138,6 +246,7 @@ pub struct CheckStats {
 impl CheckResult {
     pub fn success(file: impl Into<String>, capabilities: usize, definitions: usize) -> Self {
         Self {
+            schema: Schema::new("check"),
             success: true,
             file: file.into(),
             errors: Vec::new(),
@@ -146,16 +255,109 @@ impl CheckResult {
                 capabilities,
                 definitions,
             }),
+            fixes: None,
         }
     }
 
     pub fn failure(file: impl Into<String>, errors: Vec<JsonError>) -> Self {
         Self {
+            schema: Schema::new("check"),
             success: false,
             file: file.into(),
             errors,
             warnings: Vec::new(),
             stats: None,
+            fixes: None,
+        }
+    }
+
+    pub fn with_warnings(mut self, warnings: Vec<JsonError>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    pub fn with_fixes(mut self, applied: usize, snapshot_id: impl Into<String>) -> Self {
+        self.fixes = Some(CheckFixes {
+            applied,
+            snapshot_id: snapshot_id.into(),
+        });
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura check <dir>`: one `CheckResult` per `.aura` file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckDirResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    /// True when every file checked without errors
+    pub success: bool,
+    pub directory: String,
+    pub files: Vec<CheckResult>,
+    /// Errors reading the directory itself
+    pub errors: Vec<JsonError>,
+}
+
+impl CheckDirResult {
+    pub fn new(directory: impl Into<String>, files: Vec<CheckResult>) -> Self {
+        Self {
+            schema: Schema::new("check_dir"),
+            success: files.iter().all(|f| f.success),
+            directory: directory.into(),
+            files,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn failure(directory: impl Into<String>, errors: Vec<JsonError>) -> Self {
+        Self {
+            schema: Schema::new("check_dir"),
+            success: false,
+            directory: directory.into(),
+            files: Vec::new(),
+            errors,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura parse --json`: the AST with imports resolved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program: Option<crate::parser::Program>,
+    pub errors: Vec<JsonError>,
+}
+
+impl ParseResult {
+    pub fn success(file: impl Into<String>, program: crate::parser::Program) -> Self {
+        Self {
+            schema: Schema::new("parse"),
+            success: true,
+            file: file.into(),
+            program: Some(program),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn failure(file: impl Into<String>, errors: Vec<JsonError>) -> Self {
+        Self {
+            schema: Schema::new("parse"),
+            success: false,
+            file: file.into(),
+            program: None,
+            errors,
         }
     }
 
@@ -167,6 +369,8 @@ impl CheckResult {
 /// Result of `aura run` command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunResult {
+    #[serde(flatten)]
+    pub schema: Schema,
     pub success: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub result: Option<serde_json::Value>,
@@ -175,69 +379,297 @@ pub struct RunResult {
     pub result_type: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration_ms: Option<u64>,
+    /// Valores registrados por `observe` durante la ejecucion
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub observations: Vec<JsonObservation>,
+    /// Lineas impresas con `print`, capturadas para no mezclarlas con el JSON
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stdout: Vec<String>,
+    /// Pasos de deliberacion (`aura run --cognitive --trace`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Vec<crate::vm::TraceEntry>>,
+    /// Goals con `check`, evaluados al terminar `main`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub goals: Vec<JsonGoal>,
+    /// Llamadas y tiempo por funcion (`aura run --profile`), de mayor a menor tiempo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Vec<crate::vm::FunctionProfile>>,
+    /// Avisos no fatales, por ejemplo `env.get` de una clave no declarada en `+env(...)`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<JsonError>,
 }
 
+/// Resultado del check de un goal en `aura run --json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonGoal {
+    pub description: String,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<&crate::vm::GoalOutcome> for JsonGoal {
+    fn from(outcome: &crate::vm::GoalOutcome) -> Self {
+        Self {
+            description: outcome.description.clone(),
+            passed: outcome.passed,
+            value: outcome.value.as_ref().map(|v| value_to_json(v).0),
+            error: outcome.error.clone(),
+        }
+    }
+}
+
+/// Resultado de un `#test` en `aura test --json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonTest {
+    pub name: String,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<&crate::vm::TestOutcome> for JsonTest {
+    fn from(outcome: &crate::vm::TestOutcome) -> Self {
+        Self {
+            name: outcome.name.clone(),
+            passed: outcome.passed,
+            value: outcome.value.as_ref().map(|v| value_to_json(v).0),
+            expected: outcome.expected.as_ref().map(|v| value_to_json(v).0),
+            error: outcome.error.clone(),
+        }
+    }
+}
+
+/// Result of `aura test`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    /// True if every test passed
+    pub success: bool,
+    pub file: String,
+    pub passed: usize,
+    pub failed: usize,
+    pub tests: Vec<JsonTest>,
+    pub errors: Vec<JsonError>,
+}
+
+impl TestResult {
+    pub fn new(file: impl Into<String>, outcomes: &[crate::vm::TestOutcome]) -> Self {
+        let passed = outcomes.iter().filter(|o| o.passed).count();
+        Self {
+            schema: Schema::new("test"),
+            success: passed == outcomes.len(),
+            file: file.into(),
+            passed,
+            failed: outcomes.len() - passed,
+            tests: outcomes.iter().map(JsonTest::from).collect(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn failure(file: impl Into<String>, errors: Vec<JsonError>) -> Self {
+        Self {
+            schema: Schema::new("test"),
+            success: false,
+            file: file.into(),
+            passed: 0,
+            failed: 0,
+            tests: Vec::new(),
+            errors,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Un `observe` registrado, como aparece en `aura run --json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonObservation {
+    pub target: String,
+    pub value: serde_json::Value,
+    #[serde(rename = "type")]
+    pub value_type: String,
+}
+
+impl JsonObservation {
+    /// Solo los eventos `Observed` tienen forma de observacion
+    pub fn from_event(event: &crate::vm::ObservationEvent) -> Option<Self> {
+        match event {
+            crate::vm::ObservationEvent::Observed { target, value } => {
+                let (value, value_type) = value_to_json(value);
+                Some(Self { target: target.clone(), value, value_type })
+            }
+            _ => None,
+        }
+    }
+}
+
 impl RunResult {
     pub fn success(result: serde_json::Value, result_type: impl Into<String>, duration_ms: u64) -> Self {
         Self {
+            schema: Schema::new("run"),
             success: true,
             result: Some(result),
             result_type: Some(result_type.into()),
             duration_ms: Some(duration_ms),
+            observations: Vec::new(),
+            stdout: Vec::new(),
+            trace: None,
+            goals: Vec::new(),
+            profile: None,
+            warnings: Vec::new(),
             error: None,
         }
     }
 
     pub fn failure(error: JsonError) -> Self {
         Self {
+            schema: Schema::new("run"),
             success: false,
             result: None,
             result_type: None,
             duration_ms: None,
+            observations: Vec::new(),
+            stdout: Vec::new(),
+            trace: None,
+            goals: Vec::new(),
+            profile: None,
+            warnings: Vec::new(),
             error: Some(error),
         }
     }
 
+    pub fn with_observations(mut self, observations: Vec<JsonObservation>) -> Self {
+        self.observations = observations;
+        self
+    }
+
+    pub fn with_stdout(mut self, stdout: Vec<String>) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    pub fn with_trace(mut self, trace: Vec<crate::vm::TraceEntry>) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    pub fn with_goals(mut self, goals: &[crate::vm::GoalOutcome]) -> Self {
+        self.goals = goals.iter().map(JsonGoal::from).collect();
+        self
+    }
+
+    pub fn with_profile(mut self, profile: Option<Vec<crate::vm::FunctionProfile>>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn with_warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
     pub fn to_json(&self) -> String {
         serde_json::to_string_pretty(self).unwrap_or_default()
     }
 }
 
-/// Convert a VM Value to JSON value and type string
-pub fn value_to_json(value: &crate::vm::Value) -> (serde_json::Value, String) {
-    use crate::vm::Value;
-    match value {
-        Value::Nil => (serde_json::Value::Null, "Nil".to_string()),
-        Value::Int(n) => (serde_json::json!(n), "Int".to_string()),
-        Value::Float(n) => (serde_json::json!(n), "Float".to_string()),
-        Value::String(s) => (serde_json::json!(s), "String".to_string()),
-        Value::Bool(b) => (serde_json::json!(b), "Bool".to_string()),
-        Value::List(items) => {
-            let json_items: Vec<serde_json::Value> = items
-                .iter()
-                .map(|v| value_to_json(v).0)
-                .collect();
-            (serde_json::json!(json_items), "List".to_string())
-        }
-        Value::Record(fields) => {
-            let json_fields: serde_json::Map<String, serde_json::Value> = fields
-                .iter()
-                .map(|(k, v)| (k.clone(), value_to_json(v).0))
-                .collect();
-            (serde_json::Value::Object(json_fields), "Record".to_string())
+/// Result of `aura bench` command (durations in microseconds)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub file: String,
+    pub iterations: usize,
+    pub min_us: u64,
+    pub max_us: u64,
+    pub mean_us: u64,
+    pub median_us: u64,
+    pub p95_us: u64,
+    /// Duracion de cada corrida, en el orden en que se ejecutaron
+    pub runs_us: Vec<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonError>,
+}
+
+impl BenchResult {
+    pub fn success(file: impl Into<String>, runs_us: Vec<u64>) -> Self {
+        let mut sorted = runs_us.clone();
+        sorted.sort_unstable();
+        let total: u64 = sorted.iter().sum();
+        let mean_us = if sorted.is_empty() { 0 } else { total / sorted.len() as u64 };
+        Self {
+            schema: Schema::new("bench"),
+            success: true,
+            file: file.into(),
+            iterations: runs_us.len(),
+            min_us: sorted.first().copied().unwrap_or(0),
+            max_us: sorted.last().copied().unwrap_or(0),
+            mean_us,
+            median_us: percentile(&sorted, 50),
+            p95_us: percentile(&sorted, 95),
+            runs_us,
+            error: None,
         }
-        Value::Function(name) => (serde_json::json!(format!("<fn {}>", name)), "Function".to_string()),
-        Value::Native { type_id, handle } => {
-            (serde_json::json!(format!("<{} #{}>", type_id, handle)), type_id.clone())
+    }
+
+    pub fn failure(file: impl Into<String>, runs_us: Vec<u64>, error: JsonError) -> Self {
+        Self {
+            success: false,
+            error: Some(error),
+            ..Self::success(file, runs_us)
         }
     }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Percentil por rango mas cercano sobre muestras ya ordenadas
+fn percentile(sorted: &[u64], pct: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Convert a VM Value to JSON value and type string
+pub fn value_to_json(value: &crate::vm::Value) -> (serde_json::Value, String) {
+    use crate::vm::Value;
+    let type_name = match value {
+        Value::Nil => "Nil",
+        Value::Int(_) => "Int",
+        Value::Float(_) => "Float",
+        Value::String(_) => "String",
+        Value::Bool(_) => "Bool",
+        Value::Bytes(_) => "Bytes",
+        Value::List(_) => "List",
+        Value::Record(_) => "Record",
+        Value::Function(_) => "Function",
+        Value::Native { .. } => "Native",
+    };
+    (value.to_json(), type_name.to_string())
 }
 
 /// Result of `aura undo --list` command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UndoListResult {
+    #[serde(flatten)]
+    pub schema: Schema,
     pub success: bool,
     pub actions: Vec<UndoActionInfo>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -257,6 +689,7 @@ pub struct UndoActionInfo {
 impl UndoListResult {
     pub fn success(actions: Vec<UndoActionInfo>) -> Self {
         Self {
+            schema: Schema::new("undo_list"),
             success: true,
             actions,
             error: None,
@@ -265,6 +698,7 @@ impl UndoListResult {
 
     pub fn failure(error: impl Into<String>) -> Self {
         Self {
+            schema: Schema::new("undo_list"),
             success: false,
             actions: Vec::new(),
             error: Some(error.into()),
@@ -279,6 +713,8 @@ impl UndoListResult {
 /// Result of `aura undo` command (reverting)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UndoResult {
+    #[serde(flatten)]
+    pub schema: Schema,
     pub success: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub restored_snapshot: Option<String>,
@@ -291,6 +727,7 @@ pub struct UndoResult {
 impl UndoResult {
     pub fn success(snapshot_id: impl Into<String>, files: Vec<String>) -> Self {
         Self {
+            schema: Schema::new("undo"),
             success: true,
             restored_snapshot: Some(snapshot_id.into()),
             files_restored: files,
@@ -300,6 +737,7 @@ impl UndoResult {
 
     pub fn failure(error: impl Into<String>) -> Self {
         Self {
+            schema: Schema::new("undo"),
             success: false,
             restored_snapshot: None,
             files_restored: Vec::new(),
@@ -312,9 +750,55 @@ impl UndoResult {
     }
 }
 
+/// Result of `--dry-run` on `aura undo` and `aura snapshots restore`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestorePreviewResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub dry_run: bool,
+    pub snapshot: String,
+    /// Files a real restore would write
+    pub files_to_overwrite: Vec<String>,
+    pub files: Vec<RestorePreviewFile>,
+}
+
+/// What restoring one file would do
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestorePreviewFile {
+    pub file: String,
+    pub status: crate::agent::FileDrift,
+    /// Unified diff from the file on disk to the snapshot
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub diff: String,
+}
+
+impl RestorePreviewResult {
+    pub fn new(snapshot: impl Into<String>, files: Vec<RestorePreviewFile>) -> Self {
+        use crate::agent::FileDrift;
+        Self {
+            schema: Schema::new("restore_preview"),
+            success: !files.iter().any(|f| f.status == FileDrift::Corrupt),
+            dry_run: true,
+            snapshot: snapshot.into(),
+            files_to_overwrite: files.iter()
+                .filter(|f| matches!(f.status, FileDrift::Drifted | FileDrift::Missing))
+                .map(|f| f.file.clone())
+                .collect(),
+            files,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
 /// Result of `aura snapshots` command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SnapshotsListResult {
+    #[serde(flatten)]
+    pub schema: Schema,
     pub success: bool,
     pub snapshots: Vec<SnapshotInfo>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -333,6 +817,7 @@ pub struct SnapshotInfo {
 impl SnapshotsListResult {
     pub fn success(snapshots: Vec<SnapshotInfo>) -> Self {
         Self {
+            schema: Schema::new("snapshots"),
             success: true,
             snapshots,
             error: None,
@@ -341,6 +826,7 @@ impl SnapshotsListResult {
 
     pub fn failure(error: impl Into<String>) -> Self {
         Self {
+            schema: Schema::new("snapshots"),
             success: false,
             snapshots: Vec::new(),
             error: Some(error.into()),
@@ -355,6 +841,8 @@ impl SnapshotsListResult {
 /// Result of `aura snapshots create` command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SnapshotCreateResult {
+    #[serde(flatten)]
+    pub schema: Schema,
     pub success: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
@@ -369,6 +857,7 @@ pub struct SnapshotCreateResult {
 impl SnapshotCreateResult {
     pub fn success(id: impl Into<String>, timestamp: u64, files: Vec<String>) -> Self {
         Self {
+            schema: Schema::new("snapshot_create"),
             success: true,
             id: Some(id.into()),
             timestamp: Some(timestamp),
@@ -379,6 +868,7 @@ impl SnapshotCreateResult {
 
     pub fn failure(error: impl Into<String>) -> Self {
         Self {
+            schema: Schema::new("snapshot_create"),
             success: false,
             id: None,
             timestamp: None,
@@ -395,6 +885,8 @@ impl SnapshotCreateResult {
 /// Result of `aura snapshots restore` command
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SnapshotRestoreResult {
+    #[serde(flatten)]
+    pub schema: Schema,
     pub success: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub restored_snapshot: Option<String>,
@@ -402,6 +894,9 @@ pub struct SnapshotRestoreResult {
     pub files_restored: Vec<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub files_failed: Vec<SnapshotRestoreFailure>,
+    /// Files that already matched the snapshot, left untouched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files_unchanged: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
@@ -420,20 +915,29 @@ impl SnapshotRestoreResult {
         files_failed: Vec<SnapshotRestoreFailure>,
     ) -> Self {
         Self {
+            schema: Schema::new("snapshot_restore"),
             success: files_failed.is_empty(),
             restored_snapshot: Some(snapshot_id.into()),
             files_restored,
             files_failed,
+            files_unchanged: Vec::new(),
             error: None,
         }
     }
 
+    pub fn with_unchanged(mut self, files: Vec<String>) -> Self {
+        self.files_unchanged = files;
+        self
+    }
+
     pub fn failure(error: impl Into<String>) -> Self {
         Self {
+            schema: Schema::new("snapshot_restore"),
             success: false,
             restored_snapshot: None,
             files_restored: Vec::new(),
             files_failed: Vec::new(),
+            files_unchanged: Vec::new(),
             error: Some(error.into()),
         }
     }
@@ -443,31 +947,50 @@ impl SnapshotRestoreResult {
     }
 }
 
-/// Result of `aura snapshots prune` command
+/// Result of `aura snapshots verify` command
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SnapshotPruneResult {
+pub struct SnapshotVerifyResult {
+    #[serde(flatten)]
+    pub schema: Schema,
     pub success: bool,
-    pub removed_count: usize,
-    pub remaining_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// True if any file differs from the snapshot or is missing
+    pub drift: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<SnapshotFileStatus>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
-impl SnapshotPruneResult {
-    pub fn success(removed: usize, remaining: usize) -> Self {
+/// Drift status of one file in a snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotFileStatus {
+    pub file: String,
+    pub status: crate::agent::FileDrift,
+}
+
+impl SnapshotVerifyResult {
+    /// Fails only if a snapshot file is corrupt; drift is reported, not an error
+    pub fn new(id: impl Into<String>, files: Vec<SnapshotFileStatus>) -> Self {
+        use crate::agent::FileDrift;
         Self {
-            success: true,
-            removed_count: removed,
-            remaining_count: remaining,
+            schema: Schema::new("snapshot_verify"),
+            success: !files.iter().any(|f| f.status == FileDrift::Corrupt),
+            id: Some(id.into()),
+            drift: files.iter().any(|f| matches!(f.status, FileDrift::Drifted | FileDrift::Missing)),
+            files,
             error: None,
         }
     }
 
     pub fn failure(error: impl Into<String>) -> Self {
         Self {
+            schema: Schema::new("snapshot_verify"),
             success: false,
-            removed_count: 0,
-            remaining_count: 0,
+            id: None,
+            drift: false,
+            files: Vec::new(),
             error: Some(error.into()),
         }
     }
@@ -477,13 +1000,803 @@ impl SnapshotPruneResult {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_check_result_success() {
-        let result = CheckResult::success("test.aura", 2, 3);
+/// Result of `aura snapshots prune` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotPruneResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub removed_count: usize,
+    pub remaining_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl SnapshotPruneResult {
+    pub fn success(removed: usize, remaining: usize) -> Self {
+        Self {
+            schema: Schema::new("snapshot_prune"),
+            success: true,
+            removed_count: removed,
+            remaining_count: remaining,
+            error: None,
+        }
+    }
+
+    pub fn failure(error: impl Into<String>) -> Self {
+        Self {
+            schema: Schema::new("snapshot_prune"),
+            success: false,
+            removed_count: 0,
+            remaining_count: 0,
+            error: Some(error.into()),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura fmt` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FmtResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub file: String,
+    /// Whether the formatted output differs from the original source
+    pub changed: bool,
+    /// Whether the file was overwritten (`--write`)
+    pub written: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<String>,
+    pub errors: Vec<JsonError>,
+    pub warnings: Vec<String>,
+}
+
+impl FmtResult {
+    pub fn success(file: impl Into<String>, formatted: Option<String>, changed: bool, written: bool) -> Self {
+        Self {
+            schema: Schema::new("fmt"),
+            success: true,
+            file: file.into(),
+            changed,
+            written,
+            formatted,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn failure(file: impl Into<String>, errors: Vec<JsonError>) -> Self {
+        Self {
+            schema: Schema::new("fmt"),
+            success: false,
+            file: file.into(),
+            changed: false,
+            written: false,
+            formatted: None,
+            errors,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn with_warning(mut self, warning: impl Into<String>) -> Self {
+        self.warnings.push(warning.into());
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura ast-diff` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub old_file: String,
+    pub new_file: String,
+    pub functions: DiffSection,
+    pub types: DiffSection,
+    pub total_changes: usize,
+    pub errors: Vec<JsonError>,
+}
+
+/// Added/removed/modified definitions of one kind
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiffSection {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<ModifiedDefinition>,
+}
+
+/// A definition present in both files that changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModifiedDefinition {
+    pub name: String,
+    pub reasons: Vec<String>,
+}
+
+impl DiffResult {
+    pub fn success(
+        old_file: impl Into<String>,
+        new_file: impl Into<String>,
+        functions: DiffSection,
+        types: DiffSection,
+    ) -> Self {
+        let total_changes = functions.len() + types.len();
+        Self {
+            schema: Schema::new("ast_diff"),
+            success: true,
+            old_file: old_file.into(),
+            new_file: new_file.into(),
+            functions,
+            types,
+            total_changes,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn failure(old_file: impl Into<String>, new_file: impl Into<String>, errors: Vec<JsonError>) -> Self {
+        Self {
+            schema: Schema::new("ast_diff"),
+            success: false,
+            old_file: old_file.into(),
+            new_file: new_file.into(),
+            functions: DiffSection::default(),
+            types: DiffSection::default(),
+            total_changes: 0,
+            errors,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura explain` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    /// Code as requested by the user
+    pub code: String,
+    /// Code of the entry that matched (the category, e.g. `E3xx`, when the
+    /// exact code has no entry of its own)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub causes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ExplainResult {
+    pub fn success(code: impl Into<String>, explanation: &crate::error::ErrorExplanation) -> Self {
+        Self {
+            schema: Schema::new("explain"),
+            success: true,
+            code: code.into(),
+            matched: Some(explanation.code.to_string()),
+            title: Some(explanation.title.to_string()),
+            description: Some(explanation.description.to_string()),
+            causes: explanation.causes.iter().map(|c| c.to_string()).collect(),
+            fix: Some(explanation.fix.to_string()),
+            error: None,
+        }
+    }
+
+    pub fn not_found(code: impl Into<String>) -> Self {
+        let code = code.into();
+        Self {
+            schema: Schema::new("explain"),
+            success: false,
+            error: Some(format!("Codigo de error desconocido: {}", code)),
+            code,
+            matched: None,
+            title: None,
+            description: None,
+            causes: Vec::new(),
+            fix: None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura complete` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub file: String,
+    pub offset: usize,
+    /// Partial identifier before `offset` that the candidates start with
+    pub prefix: String,
+    pub candidates: Vec<crate::types::complete::Completion>,
+    pub errors: Vec<JsonError>,
+}
+
+impl CompleteResult {
+    pub fn success(
+        file: impl Into<String>,
+        offset: usize,
+        prefix: impl Into<String>,
+        candidates: Vec<crate::types::complete::Completion>,
+    ) -> Self {
+        Self {
+            schema: Schema::new("complete"),
+            success: true,
+            file: file.into(),
+            offset,
+            prefix: prefix.into(),
+            candidates,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn failure(file: impl Into<String>, offset: usize, errors: Vec<JsonError>) -> Self {
+        Self {
+            schema: Schema::new("complete"),
+            success: false,
+            file: file.into(),
+            offset,
+            prefix: String::new(),
+            candidates: Vec::new(),
+            errors,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura symbols` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolsResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub file: String,
+    pub symbols: Vec<SymbolInfo>,
+    pub errors: Vec<JsonError>,
+}
+
+/// A top-level definition in an outline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolInfo {
+    pub name: String,
+    /// `function`, `type`, `enum`, `goal`, `invariant`, `observe`, ...
+    pub kind: String,
+    pub start: JsonLocation,
+    pub end: JsonLocation,
+}
+
+impl SymbolInfo {
+    pub fn from_definition(def: &crate::parser::Definition, source: &str) -> Self {
+        use crate::parser::Definition;
+
+        let name = match def {
+            Definition::TypeDef(t) => t.name.clone(),
+            Definition::EnumDef(e) => e.name.clone(),
+            Definition::FuncDef(f) => f.name.clone(),
+            Definition::ApiDef(a) => a.base_path.clone(),
+            Definition::TestDef(t) => t.name.clone(),
+            Definition::Goal(g) => g.description.clone(),
+            Definition::Invariant(i) => crate::fmt::format_expr(&i.expr),
+            Definition::Observe(o) => o.target.clone(),
+            Definition::Route(r) => format!("{} {}", r.method, r.path),
+            Definition::Import(i) if i.names.is_empty() => i.path.clone(),
+            Definition::Import(i) => i.names.join(", "),
+        };
+        let span = def.span();
+        Self {
+            name,
+            kind: def.kind().to_string(),
+            start: JsonLocation::from_offset(source, span.start),
+            end: JsonLocation::from_offset(source, span.end),
+        }
+    }
+}
+
+impl SymbolsResult {
+    pub fn success(file: impl Into<String>, program: &crate::parser::Program, source: &str) -> Self {
+        Self {
+            schema: Schema::new("symbols"),
+            success: true,
+            file: file.into(),
+            symbols: program.definitions.iter()
+                .map(|def| SymbolInfo::from_definition(def, source))
+                .collect(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn failure(file: impl Into<String>, errors: Vec<JsonError>) -> Self {
+        Self {
+            schema: Schema::new("symbols"),
+            success: false,
+            file: file.into(),
+            symbols: Vec::new(),
+            errors,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura docs` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocsResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub file: String,
+    pub docs: Vec<crate::docs::DocEntry>,
+    pub errors: Vec<JsonError>,
+}
+
+impl DocsResult {
+    pub fn success(file: impl Into<String>, docs: Vec<crate::docs::DocEntry>) -> Self {
+        Self {
+            schema: Schema::new("docs"),
+            success: true,
+            file: file.into(),
+            docs,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn failure(file: impl Into<String>, errors: Vec<JsonError>) -> Self {
+        Self {
+            schema: Schema::new("docs"),
+            success: false,
+            file: file.into(),
+            docs: Vec::new(),
+            errors,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Failure of a command whose success has its own result type (`serve`,
+/// `heal_log`, `memory_*`): just the kind and the error message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailureResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub error: String,
+}
+
+impl FailureResult {
+    pub fn new(kind: &str, error: impl Into<String>) -> Self {
+        Self {
+            schema: Schema::new(kind),
+            success: false,
+            error: error.into(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura heal --json`
+///
+/// `stage` names the step that failed (`read`, `tokenize`, `parse`, `apply`,
+/// `verify` or `heal`); the flags are only present once the run got that far.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub needed_healing: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_memory: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saved_to_memory: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub needs_human: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cannot_fix: Option<bool>,
+    /// Result of running the program (after the fix, if one was applied)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+    /// Suggestions from the agent, best first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestions: Option<Vec<crate::agent::Suggestion>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Byte span of a syntax error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<crate::lexer::Span>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<JsonLocation>,
+}
+
+impl HealResult {
+    fn new(success: bool) -> Self {
+        Self {
+            schema: Schema::new("heal"),
+            success,
+            stage: None,
+            needed_healing: None,
+            fixed: None,
+            from_memory: None,
+            saved_to_memory: None,
+            needs_human: None,
+            cannot_fix: None,
+            result: None,
+            patch: None,
+            explanation: None,
+            suggestions: None,
+            reason: None,
+            error: None,
+            span: None,
+            location: None,
+        }
+    }
+
+    /// The program ran without errors
+    pub fn no_healing_needed(result: impl Into<String>) -> Self {
+        Self {
+            needed_healing: Some(false),
+            result: Some(result.into()),
+            ..Self::new(true)
+        }
+    }
+
+    /// A fix was applied to the file and the program now runs
+    pub fn fixed(patch: impl Into<String>, result: impl Into<String>) -> Self {
+        Self {
+            needed_healing: Some(true),
+            fixed: Some(true),
+            patch: Some(patch.into()),
+            result: Some(result.into()),
+            ..Self::new(true)
+        }
+    }
+
+    /// A fix was found but not applied (no `--apply`)
+    pub fn proposed(patch: impl Into<String>, explanation: impl Into<String>) -> Self {
+        Self {
+            needed_healing: Some(true),
+            fixed: Some(false),
+            patch: Some(patch.into()),
+            explanation: Some(explanation.into()),
+            ..Self::new(true)
+        }
+    }
+
+    pub fn suggested(suggestions: Vec<crate::agent::Suggestion>) -> Self {
+        Self {
+            needed_healing: Some(true),
+            fixed: Some(false),
+            suggestions: Some(suggestions),
+            ..Self::new(true)
+        }
+    }
+
+    pub fn needs_human(reason: impl Into<String>) -> Self {
+        Self {
+            stage: Some("heal".to_string()),
+            needs_human: Some(true),
+            reason: Some(reason.into()),
+            ..Self::new(false)
+        }
+    }
+
+    pub fn cannot_fix(reason: impl Into<String>) -> Self {
+        Self {
+            stage: Some("heal".to_string()),
+            cannot_fix: Some(true),
+            reason: Some(reason.into()),
+            ..Self::new(false)
+        }
+    }
+
+    pub fn failure(stage: &str, error: impl Into<String>) -> Self {
+        Self {
+            stage: Some(stage.to_string()),
+            error: Some(error.into()),
+            ..Self::new(false)
+        }
+    }
+
+    /// The fix came from the healing memory instead of the agent
+    pub fn from_memory(mut self) -> Self {
+        self.from_memory = Some(true);
+        self
+    }
+
+    pub fn saved_to_memory(mut self) -> Self {
+        self.saved_to_memory = Some(true);
+        self
+    }
+
+    /// Where a syntax error is: byte span and its line/column in `source`
+    pub fn with_span(mut self, span: &crate::lexer::Span, source: &str) -> Self {
+        self.location = Some(JsonLocation::from_offset(source, span.start));
+        self.span = Some(span.clone());
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura heal-log --json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealLogResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    /// Attempts matching the filters, before applying `-n`
+    pub total: usize,
+    pub entries: Vec<crate::agent::HealingLogEntry>,
+}
+
+impl HealLogResult {
+    pub fn success(total: usize, entries: Vec<crate::agent::HealingLogEntry>) -> Self {
+        Self {
+            schema: Schema::new("heal_log"),
+            success: true,
+            total,
+            entries,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura memory list --json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryListResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub pattern_count: usize,
+    pub patterns: Vec<crate::agent::MemoryPattern>,
+    pub defaults: std::collections::BTreeMap<String, String>,
+}
+
+impl MemoryListResult {
+    pub fn success(patterns: Vec<crate::agent::MemoryPattern>, defaults: &std::collections::HashMap<String, String>) -> Self {
+        Self {
+            schema: Schema::new("memory_list"),
+            success: true,
+            pattern_count: patterns.len(),
+            patterns,
+            defaults: defaults.clone().into_iter().collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura memory clear --json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryClearResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub patterns_cleared: usize,
+    pub defaults_cleared: usize,
+}
+
+impl MemoryClearResult {
+    pub fn success(patterns_cleared: usize, defaults_cleared: usize) -> Self {
+        Self {
+            schema: Schema::new("memory_clear"),
+            success: true,
+            patterns_cleared,
+            defaults_cleared,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura memory export --json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryExportResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub path: String,
+    pub patterns: usize,
+    pub defaults: usize,
+}
+
+impl MemoryExportResult {
+    pub fn success(path: impl Into<String>, patterns: usize, defaults: usize) -> Self {
+        Self {
+            schema: Schema::new("memory_export"),
+            success: true,
+            path: path.into(),
+            patterns,
+            defaults,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura memory import --json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryImportResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    pub added: usize,
+    pub updated: usize,
+    pub defaults_set: usize,
+    /// Defaults that had another value locally
+    pub conflicts: Vec<String>,
+    /// Whether conflicting defaults were overwritten (`--force`)
+    pub forced: bool,
+}
+
+impl MemoryImportResult {
+    pub fn success(report: crate::agent::ImportReport, forced: bool) -> Self {
+        Self {
+            schema: Schema::new("memory_import"),
+            success: true,
+            added: report.added,
+            updated: report.updated,
+            defaults_set: report.defaults_set,
+            conflicts: report.conflicts,
+            forced,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura memory defaults --json`: the change made (`set` or
+/// `remove`), or the current defaults when listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryDefaultsResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<std::collections::BTreeMap<String, String>>,
+}
+
+impl MemoryDefaultsResult {
+    fn new() -> Self {
+        Self {
+            schema: Schema::new("memory_defaults"),
+            success: true,
+            action: None,
+            key: None,
+            value: None,
+            defaults: None,
+        }
+    }
+
+    pub fn set(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            action: Some("set".to_string()),
+            key: Some(key.into()),
+            value: Some(value.into()),
+            ..Self::new()
+        }
+    }
+
+    pub fn removed(key: impl Into<String>) -> Self {
+        Self {
+            action: Some("remove".to_string()),
+            key: Some(key.into()),
+            ..Self::new()
+        }
+    }
+
+    pub fn list(defaults: &std::collections::HashMap<String, String>) -> Self {
+        Self {
+            defaults: Some(defaults.clone().into_iter().collect()),
+            ..Self::new()
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Result of `aura info --json`: the fields of `crate::runtime_info`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InfoResult {
+    #[serde(flatten)]
+    pub schema: Schema,
+    #[serde(flatten)]
+    pub info: serde_json::Value,
+}
+
+impl InfoResult {
+    /// Runtime info of this build
+    pub fn current() -> Self {
+        Self {
+            schema: Schema::new("info"),
+            info: crate::runtime_info(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+impl DiffSection {
+    /// Number of changed definitions in this section
+    pub fn len(&self) -> usize {
+        self.added.len() + self.removed.len() + self.modified.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_result_success() {
+        let result = CheckResult::success("test.aura", 2, 3);
         let json = result.to_json();
         assert!(json.contains("\"success\": true"));
         assert!(json.contains("\"file\": \"test.aura\""));
@@ -504,6 +1817,25 @@ mod tests {
         assert!(json.contains("\"line\": 5"));
     }
 
+    #[test]
+    fn test_type_error_context_lines() {
+        let source = "x = 1\ny = z\nw = 2";
+        let err = crate::types::TypeError::new("'z' no esta definido")
+            .with_span(crate::lexer::Span::new(10, 11));
+        let error = JsonError::from_type_error(&err, source);
+
+        assert_eq!(error.location.as_ref().unwrap().line, 2);
+        assert_eq!(error.context.len(), 3);
+        assert_eq!(error.context[1].code, "y = z");
+        assert!(error.context[1].highlight);
+        assert!(!error.context[0].highlight && !error.context[2].highlight);
+
+        // Sin span no hay contexto, y el campo no aparece en el JSON
+        let error = JsonError::from_type_error(&crate::types::TypeError::new("x"), source);
+        assert!(error.context.is_empty());
+        assert!(!serde_json::to_string(&error).unwrap().contains("context"));
+    }
+
     #[test]
     fn test_run_result_success() {
         let result = RunResult::success(serde_json::json!("Hello World"), "String", 12);
@@ -524,6 +1856,45 @@ mod tests {
         assert!(json.contains("\"code\": \"E401\""));
     }
 
+    #[test]
+    fn test_bench_result_stats() {
+        let result = BenchResult::success("bench.aura", vec![50, 10, 30, 20, 40]);
+        assert_eq!(result.iterations, 5);
+        assert_eq!(result.min_us, 10);
+        assert_eq!(result.max_us, 50);
+        assert_eq!(result.mean_us, 30);
+        assert_eq!(result.median_us, 30);
+        assert_eq!(result.p95_us, 50);
+        assert_eq!(result.runs_us, vec![50, 10, 30, 20, 40]);
+    }
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let samples: Vec<u64> = (1..=20).collect();
+        assert_eq!(percentile(&samples, 50), 10);
+        assert_eq!(percentile(&samples, 95), 19);
+        assert_eq!(percentile(&[7], 95), 7);
+        assert_eq!(percentile(&[], 50), 0);
+    }
+
+    #[test]
+    fn test_run_result_observations() {
+        use crate::vm::{ObservationEvent, Value};
+
+        let events = [
+            ObservationEvent::CheckpointCreated { name: "c".to_string() },
+            ObservationEvent::Observed { target: "response.status".to_string(), value: Value::Int(503) },
+        ];
+        let observations: Vec<_> = events.iter().filter_map(JsonObservation::from_event).collect();
+        let json: serde_json::Value = serde_json::from_str(
+            &RunResult::success(serde_json::json!(1), "Int", 0).with_observations(observations).to_json()
+        ).unwrap();
+        assert_eq!(json["observations"], serde_json::json!([{"target": "response.status", "value": 503, "type": "Int"}]));
+
+        let plain = RunResult::success(serde_json::json!(1), "Int", 0).to_json();
+        assert!(!plain.contains("observations"));
+    }
+
     #[test]
     fn test_value_to_json() {
         use crate::vm::Value;
@@ -540,4 +1911,86 @@ mod tests {
         assert_eq!(json, serde_json::json!(true));
         assert_eq!(ty, "Bool");
     }
+
+    #[test]
+    fn test_native_value_to_json() {
+        use crate::vm::Value;
+
+        let conn = Value::Native { type_id: "db:sqlite".to_string(), handle: 3 };
+        let (json, ty) = value_to_json(&conn);
+        assert_eq!(json, serde_json::json!({"native": "db:sqlite", "handle": 3}));
+        assert_eq!(ty, "Native");
+
+        let (json, _) = value_to_json(&Value::List(vec![conn]));
+        assert_eq!(json[0]["handle"], 3);
+    }
+
+    #[test]
+    fn test_every_result_has_schema_version_and_kind() {
+        let explanation = crate::error::explain_code("E201").unwrap();
+        let program = crate::parse(crate::tokenize("main = 1").unwrap()).unwrap();
+
+        let results = [
+            ("check", serde_json::to_value(CheckResult::success("a.aura", 0, 1))),
+            ("check_dir", serde_json::to_value(CheckDirResult::new("src", Vec::new()))),
+            ("parse", serde_json::to_value(ParseResult::success("a.aura", program.clone()))),
+            ("run", serde_json::to_value(RunResult::failure(JsonError::new("E401", "boom")))),
+            ("bench", serde_json::to_value(BenchResult::failure("a.aura", vec![1], JsonError::new("E401", "boom")))),
+            ("undo_list", serde_json::to_value(UndoListResult::success(Vec::new()))),
+            ("undo", serde_json::to_value(UndoResult::failure("nothing to undo"))),
+            ("restore_preview", serde_json::to_value(RestorePreviewResult::new("s1", Vec::new()))),
+            ("snapshots", serde_json::to_value(SnapshotsListResult::success(Vec::new()))),
+            ("snapshot_create", serde_json::to_value(SnapshotCreateResult::success("s1", 0, Vec::new()))),
+            ("snapshot_restore", serde_json::to_value(SnapshotRestoreResult::failure("missing"))),
+            ("snapshot_verify", serde_json::to_value(SnapshotVerifyResult::new("s1", Vec::new()))),
+            ("snapshot_prune", serde_json::to_value(SnapshotPruneResult::success(1, 2))),
+            ("fmt", serde_json::to_value(FmtResult::success("a.aura", None, false, false))),
+            ("ast_diff", serde_json::to_value(DiffResult::failure("a.aura", "b.aura", Vec::new()))),
+            ("explain", serde_json::to_value(ExplainResult::success("E201", explanation))),
+            ("complete", serde_json::to_value(CompleteResult::success("a.aura", 0, "", Vec::new()))),
+            ("symbols", serde_json::to_value(SymbolsResult::success("a.aura", &program, "main = 1"))),
+            ("test", serde_json::to_value(TestResult::new("a.aura", &[]))),
+            ("docs", serde_json::to_value(DocsResult::success("a.aura", Vec::new()))),
+            ("serve", serde_json::to_value(FailureResult::new("serve", "No routes found"))),
+            ("heal", serde_json::to_value(HealResult::no_healing_needed("2"))),
+            ("heal", serde_json::to_value(HealResult::cannot_fix("no"))),
+            ("heal_log", serde_json::to_value(HealLogResult::success(0, Vec::new()))),
+            ("memory_list", serde_json::to_value(MemoryListResult::success(Vec::new(), &Default::default()))),
+            ("memory_clear", serde_json::to_value(MemoryClearResult::success(1, 0))),
+            ("memory_export", serde_json::to_value(MemoryExportResult::success("m.json", 1, 0))),
+            ("memory_import", serde_json::to_value(MemoryImportResult::success(Default::default(), false))),
+            ("memory_defaults", serde_json::to_value(MemoryDefaultsResult::removed("timeout"))),
+            ("info", serde_json::to_value(InfoResult::current())),
+        ];
+
+        for (kind, json) in results {
+            let json = json.unwrap();
+            assert_eq!(json["schema_version"], SCHEMA_VERSION, "{}", kind);
+            assert_eq!(json["kind"], kind);
+        }
+
+        // Primeros en el JSON, antes que `success`
+        let json = CheckResult::success("a.aura", 0, 1).to_json();
+        assert!(json.find("schema_version").unwrap() < json.find("success").unwrap());
+
+        // Y se leen de vuelta
+        let parsed: RunResult = serde_json::from_str(&RunResult::failure(JsonError::new("E401", "boom")).to_json()).unwrap();
+        assert_eq!(parsed.schema, Schema::new("run"));
+    }
+
+    #[test]
+    fn test_symbols_result_mixed_program() {
+        let source = "@User { name s }\n\ngoal \"keep users\"\ninvariant 1 > 0\nobserve users\n\ndouble(x) = x * 2\nmain = double(2)\n";
+        let program = crate::parse(crate::tokenize(source).unwrap()).unwrap();
+        let result = SymbolsResult::success("app.aura", &program, source);
+
+        let kinds: Vec<&str> = result.symbols.iter().map(|s| s.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["type", "goal", "invariant", "observe", "function", "function"]);
+        let names: Vec<&str> = result.symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["User", "keep users", "1 > 0", "users", "double", "main"]);
+
+        let double = &result.symbols[4];
+        assert_eq!((double.start.line, double.start.col), (7, 1));
+        assert_eq!((double.end.line, double.end.col), (7, 18));
+    }
 }
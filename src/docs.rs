@@ -0,0 +1,167 @@
+// Documentacion a partir de comentarios `#doc`
+// El lexer conserva los `#doc` como tokens y el parser los salta; aqui se
+// asocian por posicion a la definicion (o campo de tipo) que los sigue.
+
+use serde::{Deserialize, Serialize};
+
+use crate::lexer::{Span, Spanned, Token};
+use crate::parser::{Definition, Program};
+
+/// Documentacion de una funcion, tipo o enum
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocEntry {
+    pub name: String,
+    /// `function`, `type` o `enum`
+    pub kind: String,
+    /// Texto de los `#doc` anteriores, una linea por comentario
+    pub doc: Option<String>,
+    /// `double(x)`, `save!(user)`, `@User`, `@Color = Red | Green`
+    pub signature: String,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub fields: Vec<FieldDoc>,
+    pub span: Span,
+}
+
+/// Campo documentado de un tipo
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldDoc {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub doc: Option<String>,
+}
+
+/// Un comentario `#doc texto` (y no `#docs` o `#documento`)
+pub fn is_doc_comment(comment: &str) -> bool {
+    comment.strip_prefix("#doc").is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+/// Documentacion de las funciones, tipos y enums del programa, en orden
+///
+/// Un `#doc` pertenece a lo primero que empieza despues de el; varios
+/// `#doc` seguidos se juntan en un texto de varias lineas. Los tokens deben
+/// ser los mismos de los que salio `program`, para que los spans coincidan.
+pub fn extract_docs(tokens: &[Spanned<Token>], program: &Program) -> Vec<DocEntry> {
+    let comments: Vec<(usize, &str)> = tokens.iter()
+        .filter_map(|t| match &t.value {
+            Token::Comment(c) if is_doc_comment(c) => Some((t.span.start, c["#doc".len()..].trim())),
+            _ => None,
+        })
+        .collect();
+
+    // Cada definicion y campo toma los comentarios entre el elemento anterior y su inicio
+    let mut previous_end = 0;
+    let take_doc = |previous_end: &mut usize, start: usize, end: usize| -> Option<String> {
+        let lines: Vec<&str> = comments.iter()
+            .filter(|(pos, _)| *pos >= *previous_end && *pos < start)
+            .map(|(_, text)| *text)
+            .collect();
+        *previous_end = end;
+        if lines.is_empty() { None } else { Some(lines.join("\n")) }
+    };
+
+    let mut entries = Vec::new();
+    for def in &program.definitions {
+        let span = def.span();
+        let entry = match def {
+            Definition::FuncDef(f) => {
+                let doc = take_doc(&mut previous_end, span.start, span.end);
+                DocEntry {
+                    name: f.name.clone(),
+                    kind: def.kind().to_string(),
+                    doc,
+                    signature: crate::fmt::format_signature(f),
+                    fields: Vec::new(),
+                    span: span.clone(),
+                }
+            }
+            Definition::TypeDef(t) => {
+                // El doc del tipo termina donde empieza el tipo; los de los campos van adentro
+                let doc = take_doc(&mut previous_end, span.start, span.start);
+                let fields = t.fields.iter()
+                    .map(|field| FieldDoc {
+                        name: field.name.clone(),
+                        ty: field.ty.to_string(),
+                        doc: take_doc(&mut previous_end, field.span.start, field.span.end),
+                    })
+                    .collect();
+                previous_end = span.end;
+                DocEntry {
+                    name: t.name.clone(),
+                    kind: def.kind().to_string(),
+                    doc,
+                    signature: format!("@{}", t.name),
+                    fields,
+                    span: span.clone(),
+                }
+            }
+            Definition::EnumDef(e) => {
+                let doc = take_doc(&mut previous_end, span.start, span.end);
+                DocEntry {
+                    name: e.name.clone(),
+                    kind: def.kind().to_string(),
+                    doc,
+                    signature: crate::fmt::format_definition(def),
+                    fields: Vec::new(),
+                    span: span.clone(),
+                }
+            }
+            // Un `#doc` antes de un goal, test o import no se asocia con lo siguiente
+            _ => {
+                take_doc(&mut previous_end, span.start, span.end);
+                continue;
+            }
+        };
+        entries.push(entry);
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+    use crate::parser::parse;
+
+    fn docs(source: &str) -> Vec<DocEntry> {
+        let tokens = tokenize(source).unwrap();
+        let program = parse(tokens.clone()).unwrap();
+        extract_docs(&tokens, &program)
+    }
+
+    #[test]
+    fn test_function_docs() {
+        let entries = docs("#doc Duplica un numero\n#doc Sirve para pruebas\ndouble(x) = x * 2\n\n# comentario normal\nsave!(user) = user\nmain = double(2)\n");
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].name, "double");
+        assert_eq!(entries[0].kind, "function");
+        assert_eq!(entries[0].doc.as_deref(), Some("Duplica un numero\nSirve para pruebas"));
+        assert_eq!(entries[0].signature, "double(x)");
+        assert_eq!(entries[1].doc, None);
+        assert_eq!(entries[1].signature, "save!(user)");
+        assert_eq!(entries[2].signature, "main");
+    }
+
+    #[test]
+    fn test_type_field_docs() {
+        let source = "#doc Un usuario\n@User {\n    #doc Nombre completo\n    name :s\n    age :i\n}\n#doc Un punto\n@Point {\n    x :f\n}\nmain = 1\n";
+        let entries = docs(source);
+        assert_eq!(entries[0].doc.as_deref(), Some("Un usuario"));
+        assert_eq!(entries[0].signature, "@User");
+        assert_eq!(entries[0].fields[0].doc.as_deref(), Some("Nombre completo"));
+        assert_eq!(entries[0].fields[0].ty, ":s");
+        assert_eq!(entries[0].fields[1].doc, None);
+        // El doc del campo no pasa al tipo siguiente
+        assert_eq!(entries[1].doc.as_deref(), Some("Un punto"));
+        assert_eq!(entries[1].fields[0].doc, None);
+        assert_eq!(entries[2].doc, None);
+    }
+
+    #[test]
+    fn test_doc_prefix() {
+        assert!(is_doc_comment("#doc texto"));
+        assert!(is_doc_comment("#doc"));
+        assert!(!is_doc_comment("#docs texto"));
+    }
+}
@@ -27,6 +27,8 @@ pub use pretty::{
     format_reference_error,
     format_capability_error,
     format_effect_error,
+    color_enabled,
+    disable_color,
     ErrorType,
 };
 
@@ -73,6 +75,197 @@ impl ErrorCode {
     }
 }
 
+/// Descripcion de un codigo de error para `aura explain`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ErrorExplanation {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub causes: &'static [&'static str],
+    pub fix: &'static str,
+}
+
+/// Codigos concretos que emite el CLI
+static EXPLANATIONS: &[ErrorExplanation] = &[
+    ErrorExplanation {
+        code: "E001",
+        title: "Error lexico o de carga",
+        description: "El archivo no se pudo tokenizar, o el programa no se pudo cargar en la VM.",
+        causes: &[
+            "Un caracter que no pertenece al lenguaje (por ejemplo `$` o `;` sueltos)",
+            "Un string sin cerrar",
+            "Una definicion que la VM rechaza al cargar el programa",
+        ],
+        fix: "Revisa la linea indicada en `location`; cierra strings y quita caracteres invalidos.",
+    },
+    ErrorExplanation {
+        code: "E002",
+        title: "Proveedor desconocido",
+        description: "El proveedor de agente pedido con `--provider` no existe.",
+        causes: &["Un nombre de proveedor mal escrito", "Un proveedor que no esta compilado en este binario"],
+        fix: "Usa uno de los proveedores soportados: mock, claude u ollama.",
+    },
+    ErrorExplanation {
+        code: "E003",
+        title: "Comentario que el formateador no puede conservar",
+        description: "`aura fmt --write` se nego a reescribir el archivo porque perderia un comentario.",
+        causes: &[
+            "Un comentario al final de una linea con codigo",
+            "Un comentario dentro del cuerpo de una definicion",
+        ],
+        fix: "Mueve el comentario a su propia linea, antes de la definicion o del campo al que se refiere.",
+    },
+    ErrorExplanation {
+        code: "E101",
+        title: "Error de parsing",
+        description: "Los tokens son validos pero no forman una expresion o definicion de AURA.",
+        causes: &[
+            "Parentesis o corchetes sin cerrar",
+            "Un `if` sin `else`",
+            "Un record con claves que no son identificadores",
+        ],
+        fix: "Compara la expresion con la sintaxis de SYNTAX.md; el error apunta al primer token inesperado.",
+    },
+    ErrorExplanation {
+        code: "E201",
+        title: "Error de tipos o referencia",
+        description: "El type checker encontro un nombre no definido o un valor de tipo incorrecto.",
+        causes: &[
+            "Una variable o funcion usada antes de definirla",
+            "Un nombre duplicado en el mismo scope",
+            "Argumentos de un tipo distinto al declarado",
+        ],
+        fix: "Sigue la `suggestion` del error si la hay; si el nombre existe, revisa que este en scope.",
+    },
+    ErrorExplanation {
+        code: "E301",
+        title: "Funcion con efectos llamada sin `!`",
+        description: "Advertencia: se llama sin `!` a una funcion que tiene efectos, declarados o heredados de lo que llama.",
+        causes: &[
+            "Una funcion declarada con `!` (`save!(x) = ...`) llamada como `save(x)`",
+            "Una funcion que usa `http`, `db` o `email`, directamente o a traves de otra funcion",
+        ],
+        fix: "Llama a la funcion con `!` (`fetch!(url)`); el mensaje indica de donde viene el efecto.",
+    },
+    ErrorExplanation {
+        code: "E401",
+        title: "Error de runtime",
+        description: "El programa fallo mientras se ejecutaba en la VM.",
+        causes: &[
+            "Division por cero",
+            "Acceso a un campo o indice que no existe",
+            "Una llamada a una capacidad que fallo (red, base de datos, archivos)",
+        ],
+        fix: "Lee el mensaje del error; `aura run --json` lo entrega estructurado para inspeccionarlo.",
+    },
+    ErrorExplanation {
+        code: "E402",
+        title: "Expectativa fallida",
+        description: "Un `expect` evaluo a falso y la ejecucion se detuvo ahi.",
+        causes: &["Un `expect` cuya condicion no se cumple con los datos actuales"],
+        fix: "Corrige el dato o la condicion; el mensaje incluye el texto del `expect` y su condicion.",
+    },
+    ErrorExplanation {
+        code: "E403",
+        title: "Goal no cumplido",
+        description: "Con `aura run --enforce-goals`, el `check` de al menos un goal no dio `true` al terminar `main`.",
+        causes: &[
+            "El resultado del programa no cumple la intencion declarada",
+            "Un check que depende de un valor que `main` no deja definido",
+        ],
+        fix: "Revisa `goals` en la salida: cada goal trae el valor (o el error) de su check.",
+    },
+    ErrorExplanation {
+        code: "E501",
+        title: "Archivo o capacidad no disponible",
+        description: "No se pudo leer el archivo pedido, o el programa usa una capacidad que no declaro.",
+        causes: &[
+            "La ruta del archivo no existe o no tiene permisos de lectura",
+            "Se llama a `http.*`, `db.*`, etc. sin declarar `+http`, `+db`, ...",
+        ],
+        fix: "Verifica la ruta, o agrega la capacidad (`+http`) al inicio del archivo.",
+    },
+];
+
+/// Una entrada por categoria, para codigos sin descripcion propia
+static CATEGORIES: &[ErrorExplanation] = &[
+    ErrorExplanation {
+        code: "E0xx",
+        title: "Errores de sintaxis",
+        description: "El codigo fuente no se pudo leer como AURA.",
+        causes: &["Caracteres invalidos", "Strings o bloques sin cerrar"],
+        fix: "Revisa la ubicacion del error y la sintaxis en SYNTAX.md.",
+    },
+    ErrorExplanation {
+        code: "E1xx",
+        title: "Errores de tipos",
+        description: "Un valor no coincide con el tipo esperado.",
+        causes: &["Anotaciones de tipo que no coinciden con el valor", "Operaciones entre tipos incompatibles"],
+        fix: "Ajusta el valor o la anotacion de tipo.",
+    },
+    ErrorExplanation {
+        code: "E2xx",
+        title: "Errores de referencias",
+        description: "Un nombre usado en el programa no esta definido.",
+        causes: &["Variables o funciones mal escritas", "Uso fuera del scope donde se definieron"],
+        fix: "Define el nombre o corrige la referencia.",
+    },
+    ErrorExplanation {
+        code: "E3xx",
+        title: "Errores de efectos",
+        description: "Una funcion realiza efectos que no declara.",
+        causes: &["Una funcion pura que llama a una capacidad con efectos"],
+        fix: "Declara el efecto en la funcion o mueve la llamada fuera de ella.",
+    },
+    ErrorExplanation {
+        code: "E4xx",
+        title: "Errores de runtime",
+        description: "El programa fallo durante la ejecucion.",
+        causes: &["Operaciones invalidas sobre valores", "Fallos de capacidades externas"],
+        fix: "Lee el mensaje del error y el valor involucrado.",
+    },
+    ErrorExplanation {
+        code: "E5xx",
+        title: "Errores de capacidades",
+        description: "El programa necesita una capacidad o recurso que no tiene disponible.",
+        causes: &["Capacidades usadas sin declarar", "Archivos o recursos inaccesibles"],
+        fix: "Declara la capacidad con `+nombre` o verifica el recurso.",
+    },
+    ErrorExplanation {
+        code: "E9xx",
+        title: "Errores de agente",
+        description: "El agente IA no pudo resolver o aplicar una correccion.",
+        causes: &["Proveedor no disponible", "Una respuesta del agente que no se pudo aplicar"],
+        fix: "Revisa la configuracion del proveedor o corrige el error manualmente.",
+    },
+];
+
+/// Busca la descripcion de un codigo (`E501`, `e501`, `E5xx`). Un codigo sin
+/// entrada propia cae en la de su categoria; `None` si no es un codigo AURA.
+pub fn explain_code(code: &str) -> Option<&'static ErrorExplanation> {
+    let code = code.trim().to_ascii_uppercase();
+    if let Some(explanation) = EXPLANATIONS.iter().find(|e| e.code == code) {
+        return Some(explanation);
+    }
+
+    let digits = code.strip_prefix('E')?;
+    let mut chars = digits.chars();
+    let category = chars.next().filter(|c| c.is_ascii_digit())?;
+    let rest: String = chars.collect();
+    let valid = (rest.len() == 2 && rest.chars().all(|c| c.is_ascii_digit())) || rest == "XX";
+    if !valid {
+        return None;
+    }
+    CATEGORIES.iter().find(|e| e.code.as_bytes()[1] as char == category)
+}
+
+impl ErrorCode {
+    /// Descripcion de este codigo para `aura explain`
+    pub fn explain(&self) -> Option<&'static ErrorExplanation> {
+        explain_code(&self.0)
+    }
+}
+
 /// Severidad del error
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -99,6 +292,26 @@ pub struct ContextLine {
     pub highlight: bool,
 }
 
+impl ContextLine {
+    /// Extrae la linea `line` (1-based) del fuente con `radius` lineas de
+    /// contexto a cada lado; solo la linea del error queda resaltada
+    pub fn around(source: &str, line: usize, radius: usize) -> Vec<Self> {
+        let first = line.saturating_sub(radius).max(1);
+        source
+            .lines()
+            .enumerate()
+            .map(|(i, code)| (i + 1, code))
+            .skip(first - 1)
+            .take_while(|(n, _)| *n <= line + radius)
+            .map(|(n, code)| Self {
+                line: n,
+                code: code.to_string(),
+                highlight: n == line,
+            })
+            .collect()
+    }
+}
+
 /// Ubicacion del error
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Location {
@@ -136,9 +349,14 @@ impl Location {
     }
 
     /// Crea una ubicacion a partir de un span y el codigo fuente
+    ///
+    /// Linea y columna empiezan en 1; la columna se cuenta en bytes desde
+    /// el inicio de la linea.
     pub fn from_span(span: &Span, source: &str, file: &str) -> Self {
         let before = &source[..span.start.min(source.len())];
-        let line = before.lines().count().max(1);
+        // Contar saltos y no `lines()`: un span al inicio de una linea deja
+        // `before` terminado en '\n' y `lines()` no cuenta esa linea vacia
+        let line = before.matches('\n').count() + 1;
         let last_newline = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
         let col = span.start.saturating_sub(last_newline) + 1;
         let end_col = if span.end > span.start {
@@ -411,3 +629,56 @@ impl Errors {
         self.errors.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_known_code() {
+        let explanation = explain_code("E501").unwrap();
+        assert_eq!(explanation.code, "E501");
+        assert!(!explanation.causes.is_empty());
+        assert_eq!(explain_code("e402").unwrap().code, "E402");
+    }
+
+    #[test]
+    fn test_every_category_resolves() {
+        let constructors: [fn(u8) -> ErrorCode; 7] = [
+            ErrorCode::syntax,
+            ErrorCode::type_error,
+            ErrorCode::reference,
+            ErrorCode::effect,
+            ErrorCode::runtime,
+            ErrorCode::capability,
+            ErrorCode::agent,
+        ];
+        for make in constructors {
+            let code = make(99);
+            let explanation = code.explain().unwrap_or_else(|| panic!("{} sin descripcion", code.0));
+            assert_eq!(explanation.code.as_bytes()[1], code.0.as_bytes()[1]);
+        }
+        assert_eq!(explain_code("E3xx").unwrap().code, "E3xx");
+    }
+
+    #[test]
+    fn test_context_lines_around() {
+        let source = "a = 1\nb = 2\nc = 3\nd = 4\n";
+        let context = ContextLine::around(source, 2, 1);
+        let lines: Vec<(usize, &str, bool)> = context.iter().map(|c| (c.line, c.code.as_str(), c.highlight)).collect();
+        assert_eq!(lines, vec![(1, "a = 1", false), (2, "b = 2", true), (3, "c = 3", false)]);
+
+        // En los bordes del archivo no hay lineas de mas
+        assert_eq!(ContextLine::around(source, 1, 1).len(), 2);
+        assert_eq!(ContextLine::around(source, 4, 1).len(), 2);
+    }
+
+    #[test]
+    fn test_explain_unknown_code() {
+        assert!(explain_code("E700").is_none());
+        assert!(explain_code("E5").is_none());
+        assert!(explain_code("E5012").is_none());
+        assert!(explain_code("X501").is_none());
+        assert!(explain_code("").is_none());
+    }
+}
@@ -5,11 +5,43 @@
 //! y sugerencias de correccion.
 
 use ariadne::{Color, Config, Label, Report, ReportKind, Source};
+use std::ffi::OsStr;
 use std::fmt::Write as FmtWrite;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::error::{AuraError, Severity};
 use crate::lexer::Span;
 
+/// `--no-color`: desactiva los colores aunque la terminal los soporte
+static COLOR_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Desactiva los colores para el resto del proceso (flag `--no-color`)
+pub fn disable_color() {
+    COLOR_DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Si la salida debe llevar colores ANSI
+///
+/// No, si se llamo a `disable_color`, si `NO_COLOR` esta definida (y no
+/// vacia, ver <https://no-color.org>) o si stdout no es una terminal.
+pub fn color_enabled() -> bool {
+    !COLOR_DISABLED.load(Ordering::Relaxed)
+        && color_allowed(std::env::var_os("NO_COLOR").as_deref(), std::io::stdout().is_terminal())
+}
+
+/// Decision de `color_enabled` a partir del valor de `NO_COLOR` y de si
+/// stdout es una terminal
+pub fn color_allowed(no_color: Option<&OsStr>, stdout_is_tty: bool) -> bool {
+    let no_color = no_color.is_some_and(|value| !value.is_empty());
+    stdout_is_tty && !no_color
+}
+
+/// Configuracion de ariadne segun `color_enabled`
+fn report_config() -> Config {
+    Config::default().with_color(color_enabled())
+}
+
 /// Tipo de error para categorizar el formateo
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorType {
@@ -89,6 +121,8 @@ fn severity_to_report_kind(severity: &Severity) -> ReportKind<'static> {
 
 /// Formatea un error de AURA de forma bonita con colores y contexto.
 ///
+/// Los colores dependen de `color_enabled`.
+///
 /// # Argumentos
 ///
 /// * `error` - El error de AURA a formatear
@@ -137,10 +171,10 @@ pub fn format_error_pretty(error: &AuraError, source: &str, filename: &str) -> S
     )
     .with_code(&error.code.0)
     .with_message(&error.message)
-    .with_config(Config::default().with_color(true));
+    .with_config(report_config());
 
     // Etiqueta principal en la ubicacion del error
-    let label_message = format!("{}", error_type.display_name());
+    let label_message = error_type.display_name().to_string();
     report = report.with_label(
         Label::new((filename, span.start..span.end))
             .with_message(label_message)
@@ -158,10 +192,9 @@ pub fn format_error_pretty(error: &AuraError, source: &str, filename: &str) -> S
     }
 
     // Agregar detalles adicionales si existen
-    if let Some(ref details) = error.details {
-        if let Some(note) = details.get("note").and_then(|v| v.as_str()) {
-            report = report.with_note(note);
-        }
+    if let Some(ref details) = error.details
+        && let Some(note) = details.get("note").and_then(|v| v.as_str()) {
+        report = report.with_note(note);
     }
 
     // Escribir el reporte a un buffer
@@ -258,7 +291,7 @@ pub fn format_parse_error(
     let mut report = Report::build(ReportKind::Error, filename, span.start)
         .with_code("E001")
         .with_message(message)
-        .with_config(Config::default().with_color(true))
+        .with_config(report_config())
         .with_label(
             Label::new((filename, span.start..span.end))
                 .with_message("aqui")
@@ -293,7 +326,7 @@ pub fn format_type_error(
     Report::build(ReportKind::Error, filename, span.start)
         .with_code("E101")
         .with_message(message)
-        .with_config(Config::default().with_color(true))
+        .with_config(report_config())
         .with_label(
             Label::new((filename, span.start..span.end))
                 .with_message(label_msg)
@@ -322,7 +355,7 @@ pub fn format_reference_error(
     let mut report = Report::build(ReportKind::Error, filename, span.start)
         .with_code("E201")
         .with_message(&message)
-        .with_config(Config::default().with_color(true))
+        .with_config(report_config())
         .with_label(
             Label::new((filename, span.start..span.end))
                 .with_message("referencia no encontrada")
@@ -330,11 +363,10 @@ pub fn format_reference_error(
         );
 
     // Sugerir nombres similares si los hay
-    if let Some(similars) = similar {
-        if !similars.is_empty() {
-            let suggestions = similars.join(", ");
-            report = report.with_help(format!("Quizas quisiste decir: {}", suggestions));
-        }
+    if let Some(similars) = similar
+        && !similars.is_empty() {
+        let suggestions = similars.join(", ");
+        report = report.with_help(format!("Quizas quisiste decir: {}", suggestions));
     }
 
     report
@@ -342,7 +374,7 @@ pub fn format_reference_error(
         .write((filename, Source::from(source)), &mut output)
         .expect("Error al escribir el reporte");
 
-    String::from_utf8(output).unwrap_or_else(|_| message)
+    String::from_utf8(output).unwrap_or(message)
 }
 
 /// Formatea un error de capacidad faltante
@@ -362,7 +394,7 @@ pub fn format_capability_error(
     Report::build(ReportKind::Error, filename, span.start)
         .with_code("E501")
         .with_message(&message)
-        .with_config(Config::default().with_color(true))
+        .with_config(report_config())
         .with_label(
             Label::new((filename, span.start..span.end))
                 .with_message(format!("requiere +{}", capability))
@@ -376,7 +408,7 @@ pub fn format_capability_error(
         .write((filename, Source::from(source)), &mut output)
         .expect("Error al escribir el reporte");
 
-    String::from_utf8(output).unwrap_or_else(|_| message)
+    String::from_utf8(output).unwrap_or(message)
 }
 
 /// Formatea un error de efecto no manejado
@@ -396,7 +428,7 @@ pub fn format_effect_error(
     Report::build(ReportKind::Error, filename, span.start)
         .with_code("E301")
         .with_message(&message)
-        .with_config(Config::default().with_color(true))
+        .with_config(report_config())
         .with_label(
             Label::new((filename, span.start..span.end))
                 .with_message("funcion con efectos")
@@ -410,7 +442,7 @@ pub fn format_effect_error(
         .write((filename, Source::from(source)), &mut output)
         .expect("Error al escribir el reporte");
 
-    String::from_utf8(output).unwrap_or_else(|_| message)
+    String::from_utf8(output).unwrap_or(message)
 }
 
 #[cfg(test)]
@@ -559,6 +591,44 @@ send_email!(report)"#
         assert!(output.contains("Total: 1 error(es), 1 advertencia(s)"));
     }
 
+    #[test]
+    fn test_color_allowed() {
+        assert!(color_allowed(None, true));
+        assert!(!color_allowed(None, false));
+        assert!(!color_allowed(Some(OsStr::new("1")), true));
+        // NO_COLOR vacia no cuenta
+        assert!(color_allowed(Some(OsStr::new("")), true));
+    }
+
+    #[test]
+    fn test_no_ansi_escapes_when_color_disabled() {
+        disable_color();
+
+        let source = "x = foo\ny = bar";
+        let errors = vec![
+            AuraError::new(
+                ErrorCode::reference(1),
+                Severity::Error,
+                Location::with_range("test.aura", 1, 5, 8),
+                "'foo' no esta definido",
+            )
+            .with_suggestion("Definir foo", None),
+            AuraError::new(
+                ErrorCode::reference(1),
+                Severity::Warning,
+                Location::with_range("test.aura", 2, 5, 8),
+                "'bar' no esta definido",
+            ),
+        ];
+
+        let single = format_error_pretty(&errors[0], source, "test.aura");
+        let multiple = format_errors_pretty(&errors, source, "test.aura");
+
+        assert!(single.contains("E201"));
+        assert!(!single.contains('\x1b'));
+        assert!(!multiple.contains('\x1b'));
+    }
+
     #[test]
     fn test_error_type_from_code() {
         assert_eq!(ErrorType::from_code("E001"), ErrorType::Parse);
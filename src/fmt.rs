@@ -0,0 +1,998 @@
+//! Formateador canonico de codigo AURA
+//!
+//! Convierte un `Program` ya parseado de vuelta a texto con un unico estilo:
+//!
+//! - Capacidades en una sola linea (`+http +json`)
+//! - Una linea en blanco entre definiciones
+//! - Campos de tipos alineados en columnas (nombre, tipo, anotaciones)
+//! - Un espacio alrededor de operadores binarios y despues de cada coma
+//!
+//! El resultado vuelve a parsear al mismo AST (parse -> format -> parse).
+//! Los parentesis se agregan solo donde la precedencia los exige.
+//!
+//! Los comentarios no forman parte del AST. `format_with_comments` los
+//! vuelve a ubicar cuando ocupan su propia linea entre definiciones o antes
+//! de un campo de tipo (donde van los `#doc`); uno dentro de una expresion o
+//! al final de una linea de codigo no tiene lugar y se reporta como
+//! `UnplacedComment`.
+
+use std::collections::HashMap;
+
+use logos::Logos;
+
+use crate::lexer::{Span, Token};
+use crate::parser::{
+    Annotation, ApiDef, BinaryOp, Definition, EnumDef, Expr, FuncDef, GoalDef, HealMode,
+    HttpMethod, MatchArm, ObserveDef, Pattern, Program, SelfHealConfig, StringPart, TestDef,
+    TypeDef, UnaryOp,
+};
+
+/// Indentacion de campos y rutas
+const INDENT: &str = "  ";
+
+// Niveles de precedencia, de menor a mayor. Una subexpresion se envuelve
+// en parentesis cuando su nivel es menor al que exige su posicion.
+
+/// `if`, bloques, `expect`, lambdas: consumen todo lo que les sigue
+const PREC_TAIL: u8 = 0;
+const PREC_PIPE: u8 = 1;
+const PREC_COALESCE: u8 = 2;
+const PREC_OR: u8 = 3;
+const PREC_AND: u8 = 4;
+const PREC_BIT_OR: u8 = 5;
+const PREC_BIT_XOR: u8 = 6;
+const PREC_BIT_AND: u8 = 7;
+const PREC_COMPARISON: u8 = 8;
+const PREC_SHIFT: u8 = 9;
+const PREC_ADDITIVE: u8 = 10;
+const PREC_MULTIPLICATIVE: u8 = 11;
+const PREC_UNARY: u8 = 12;
+const PREC_CAST: u8 = 13;
+const PREC_POSTFIX: u8 = 14;
+
+/// Formatea un programa completo
+///
+/// # Ejemplo
+///
+/// ```
+/// use aura::{parse, tokenize};
+///
+/// let program = parse(tokenize("+http\nadd(a b)=a+b").unwrap()).unwrap();
+/// assert_eq!(aura::fmt::format(&program), "+http\n\nadd(a, b) = a + b\n");
+/// ```
+pub fn format(program: &Program) -> String {
+    format_program(program, &PlacedComments::default())
+}
+
+/// Comentario que `format_with_comments` no puede conservar
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnplacedComment {
+    pub text: String,
+    pub span: Span,
+}
+
+/// Formatea un programa conservando los comentarios de `source`
+///
+/// `source` debe ser el texto del que salio `program`, para que los spans
+/// coincidan. Un comentario en su propia linea va antes de la definicion (o
+/// del campo de tipo) que le sigue; uno despues de la ultima definicion
+/// queda al final.
+///
+/// # Ejemplo
+///
+/// ```
+/// use aura::{parse, tokenize};
+///
+/// let source = "#doc Suma\nadd(a b)=a+b";
+/// let program = parse(tokenize(source).unwrap()).unwrap();
+/// let formatted = aura::fmt::format_with_comments(&program, source).unwrap();
+/// assert_eq!(formatted, "#doc Suma\nadd(a, b) = a + b\n");
+/// ```
+pub fn format_with_comments(program: &Program, source: &str) -> Result<String, UnplacedComment> {
+    let comments = place_comments(program, source)?;
+    Ok(format_program(program, &comments))
+}
+
+/// Comentarios de `source` ubicados respecto del programa
+#[derive(Debug, Default)]
+struct PlacedComments<'a> {
+    /// Antes de las capacidades
+    header: Vec<&'a str>,
+    /// Antes de cada definicion, por indice
+    definitions: HashMap<usize, Vec<&'a str>>,
+    /// Antes de cada campo, por indice de la definicion y del campo
+    fields: HashMap<usize, HashMap<usize, Vec<&'a str>>>,
+    /// Despues de la ultima definicion
+    trailing: Vec<&'a str>,
+}
+
+fn place_comments<'a>(program: &Program, source: &'a str) -> Result<PlacedComments<'a>, UnplacedComment> {
+    let mut placed = PlacedComments::default();
+    let caps_end = program.capabilities.last().map(|c| c.span.end);
+
+    for (token, range) in Token::lexer(source).spanned() {
+        let Ok(Token::Comment(comment)) = token else { continue };
+        if crate::parser::is_test_comment(&comment) {
+            continue;
+        }
+        let text = source[range.clone()].trim_end();
+        let pos = range.start;
+        let unplaced = || UnplacedComment { text: text.to_string(), span: Span::new(range.start, range.end) };
+
+        // Solo se reubican comentarios que ocupan su propia linea
+        let line_start = source[..pos].rfind('\n').map_or(0, |i| i + 1);
+        if !source[line_start..pos].trim().is_empty() {
+            return Err(unplaced());
+        }
+
+        if caps_end.is_some_and(|end| pos < end) {
+            placed.header.push(text);
+            continue;
+        }
+
+        let inside = program.definitions.iter()
+            .position(|def| def.span().start <= pos && pos < def.span().end);
+        if let Some(index) = inside {
+            // Dentro de una definicion solo hay lugar antes de un campo de tipo
+            let Definition::TypeDef(ty) = &program.definitions[index] else { return Err(unplaced()) };
+            let field = ty.fields.iter().position(|f| pos < f.span.end).ok_or_else(unplaced)?;
+            if ty.fields[field].span.start <= pos {
+                return Err(unplaced());
+            }
+            placed.fields.entry(index).or_default().entry(field).or_default().push(text);
+            continue;
+        }
+
+        match program.definitions.iter().position(|def| pos < def.span().start) {
+            Some(index) => placed.definitions.entry(index).or_default().push(text),
+            None => placed.trailing.push(text),
+        }
+    }
+
+    Ok(placed)
+}
+
+/// Lineas de comentario seguidas del texto que documentan
+fn with_comments(comments: Option<&Vec<&str>>, indent: &str, text: String) -> String {
+    match comments {
+        Some(lines) => {
+            let mut out: String = lines.iter().map(|line| format!("{}{}\n", indent, line)).collect();
+            out.push_str(&text);
+            out
+        }
+        None => text,
+    }
+}
+
+fn format_program(program: &Program, comments: &PlacedComments) -> String {
+    let mut sections = Vec::new();
+
+    if !program.capabilities.is_empty() {
+        let caps: Vec<String> = program.capabilities.iter()
+            .map(|c| if c.args.is_empty() {
+                format!("+{}", c.name)
+            } else {
+                let args: Vec<String> = c.args.iter()
+                    .map(|a| match &a.value {
+                        Some(value) => format!("{}: {}", a.name, format_expr(value)),
+                        None => a.name.clone(),
+                    })
+                    .collect();
+                format!("+{}({})", c.name, args.join(", "))
+            })
+            .collect();
+        let header = (!comments.header.is_empty()).then_some(&comments.header);
+        sections.push(with_comments(header, "", caps.join(" ")));
+    }
+
+    let no_fields = HashMap::new();
+    for (index, def) in program.definitions.iter().enumerate() {
+        let text = match def {
+            Definition::TypeDef(t) => format_type_def(t, comments.fields.get(&index).unwrap_or(&no_fields)),
+            _ => format_definition(def),
+        };
+        sections.push(with_comments(comments.definitions.get(&index), "", text));
+    }
+
+    if !comments.trailing.is_empty() {
+        sections.push(comments.trailing.join("\n"));
+    }
+
+    let mut out = sections.join("\n\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Formatea una sola expresion
+pub fn format_expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Int(n) => n.to_string(),
+        Expr::Float(f) => format_float(*f),
+        Expr::String(s) => quote(s),
+        Expr::RawString(s) => format!("r\"{}\"", s),
+        Expr::Bool(b) => b.to_string(),
+        Expr::Nil => "nil".to_string(),
+        Expr::Ident(name) => name.clone(),
+        Expr::Placeholder => "_".to_string(),
+        Expr::List(items) => format!("[{}]", join_exprs(items)),
+        Expr::Record(fields) => {
+            let fields: Vec<String> = fields.iter()
+                .map(|(name, value)| match value {
+                    Expr::Spread(_) => format_expr(value),
+                    _ => format!("{}: {}", name, expr_at(value, PREC_TAIL)),
+                })
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+        Expr::FieldAccess(base, field) => format!("{}.{}", expr_at(base, PREC_POSTFIX), field),
+        Expr::SafeAccess(base, field) => format!("{}?.{}", expr_at(base, PREC_POSTFIX), field),
+        Expr::Call { func, args, has_effect } => format!(
+            "{}{}({})",
+            expr_at(func, PREC_POSTFIX),
+            if *has_effect { "!" } else { "" },
+            join_exprs(args)
+        ),
+        Expr::BinaryOp { left, op, right } => {
+            // Asociatividad por la izquierda: el lado derecho exige un nivel mas
+            let prec = binary_precedence(op);
+            format!(
+                "{} {} {}",
+                expr_at(left, prec),
+                binary_symbol(op),
+                expr_at(right, prec + 1)
+            )
+        }
+        Expr::UnaryOp { op, expr } => {
+            let symbol = match op {
+                UnaryOp::Neg => "-",
+                UnaryOp::Not => "!",
+            };
+            format!("{}{}", symbol, expr_at(expr, PREC_UNARY))
+        }
+        Expr::Pipe(stages) => {
+            let stages: Vec<String> = stages.iter()
+                .map(|s| expr_at(s, PREC_BIT_OR))
+                .collect();
+            stages.join(" |> ")
+        }
+        Expr::Match { expr, arms } => {
+            let arms: Vec<String> = arms.iter().map(format_match_arm).collect();
+            format!("{} ? {}", expr_at(expr, PREC_POSTFIX), arms.join(" | "))
+        }
+        Expr::Lambda { params, body } => {
+            let params = if params.len() == 1 {
+                params[0].clone()
+            } else {
+                format!("({})", params.join(", "))
+            };
+            format!("{} -> {}", params, expr_at(body, PREC_TAIL))
+        }
+        Expr::Block(items) => format_block(items),
+        Expr::Let { name, value } => format!("{} = {}", name, expr_at(value, PREC_TAIL)),
+        Expr::If { condition, then_branch, else_branch } => {
+            format_if(condition, then_branch, else_branch.as_deref())
+        }
+        Expr::For { var, iter, body } => format!(
+            "for {} in {} {}",
+            var,
+            expr_at(iter, PREC_PIPE),
+            expr_at(body, PREC_TAIL)
+        ),
+        Expr::InterpolatedString(parts) => {
+            let mut out = String::from("\"");
+            for part in parts {
+                match part {
+                    StringPart::Literal(s) => out.push_str(&escape(s)),
+                    StringPart::Expr(e) => {
+                        out.push('{');
+                        out.push_str(&expr_at(e, PREC_TAIL));
+                        out.push('}');
+                    }
+                }
+            }
+            out.push('"');
+            out
+        }
+        Expr::Spread(inner) => format!("...{}", expr_at(inner, PREC_TAIL)),
+        Expr::Cast { expr, ty } => format!(
+            "{} as {}",
+            expr_at(expr, PREC_CAST),
+            ty.cast_name().unwrap_or("string")
+        ),
+        // Asociatividad por la derecha: `a ?? b ?? c` es `a ?? (b ?? c)`
+        Expr::NullCoalesce(left, right) => format!(
+            "{} ?? {}",
+            expr_at(left, PREC_COALESCE + 1),
+            expr_at(right, PREC_COALESCE)
+        ),
+        Expr::Expect { condition, message } => {
+            let mut out = format!("expect {}", expr_at(condition, PREC_COMPARISON));
+            if let Some(msg) = message {
+                out.push(' ');
+                out.push_str(&quote(msg));
+            }
+            out
+        }
+        Expr::Observe { target, condition } => {
+            format_observe(target, condition.as_deref())
+        }
+        Expr::Reason { observations, question } => {
+            if observations.is_empty() {
+                format!("reason {}", quote(question))
+            } else {
+                format!(
+                    "reason {{ observed: [{}], question: {} }}",
+                    join_exprs(observations),
+                    quote(question)
+                )
+            }
+        }
+    }
+}
+
+/// Retorna true si el codigo fuente contiene comentarios (que `format`
+/// descarta); los `#test` son definiciones y se conservan
+pub fn has_comments(source: &str) -> bool {
+    Token::lexer(source).any(|t| matches!(t, Ok(Token::Comment(c)) if !crate::parser::is_test_comment(&c)))
+}
+
+pub(crate) fn format_definition(def: &Definition) -> String {
+    match def {
+        Definition::TypeDef(t) => format_type_def(t, &HashMap::new()),
+        Definition::EnumDef(e) => format_enum_def(e),
+        Definition::FuncDef(f) => format_func_def(f),
+        Definition::ApiDef(api) => format_api_def(api),
+        Definition::TestDef(t) => format_test_def(t),
+        Definition::Goal(goal) => format_goal(goal),
+        Definition::Invariant(inv) => format!("invariant {}", expr_at(&inv.expr, PREC_TAIL)),
+        Definition::Observe(obs) => format_observe_def(obs),
+        Definition::Route(route) => format!(
+            "route {} {} {}",
+            quote(&route.method),
+            quote(&route.path),
+            route.handler
+        ),
+        Definition::Import(import) if import.names.is_empty() => format!("import {}", quote(&import.path)),
+        Definition::Import(import) => format!("import {} from {}", import.names.join(", "), quote(&import.path)),
+    }
+}
+
+/// `@Name {` con los campos alineados en columnas, cada uno precedido por
+/// sus comentarios
+fn format_type_def(def: &TypeDef, field_comments: &HashMap<usize, Vec<&str>>) -> String {
+    let mut out = format!("@{} {{", def.name);
+
+    if !def.fields.is_empty() {
+        let types: Vec<String> = def.fields.iter().map(|f| f.ty.to_string()).collect();
+        let name_width = def.fields.iter().map(|f| f.name.len()).max().unwrap_or(0);
+        let type_width = types.iter().map(|t| t.len()).max().unwrap_or(0);
+
+        out.push('\n');
+        for (index, (field, ty)) in def.fields.iter().zip(&types).enumerate() {
+            for comment in field_comments.get(&index).into_iter().flatten() {
+                out.push_str(INDENT);
+                out.push_str(comment);
+                out.push('\n');
+            }
+            let mut line = format!("{}{:<nw$} {:<tw$}", INDENT, field.name, ty, nw = name_width, tw = type_width);
+            if !field.annotations.is_empty() {
+                line.push(' ');
+                line.push_str(&format_annotations(&field.annotations));
+            }
+            if let Some(default) = &field.default {
+                line.push_str(" = ");
+                line.push_str(&expr_at(default, PREC_TAIL));
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+    }
+
+    out.push('}');
+    if !def.annotations.is_empty() {
+        out.push(' ');
+        out.push_str(&format_annotations(&def.annotations));
+    }
+    out
+}
+
+fn format_annotations(annotations: &[Annotation]) -> String {
+    let anns: Vec<String> = annotations.iter()
+        .map(|a| {
+            if a.args.is_empty() {
+                format!("@{}", a.name)
+            } else {
+                format!("@{}({})", a.name, join_exprs(&a.args))
+            }
+        })
+        .collect();
+    anns.join(" ")
+}
+
+fn format_enum_def(def: &EnumDef) -> String {
+    let variants: Vec<String> = def.variants.iter()
+        .map(|v| match &v.fields {
+            Some(fields) => {
+                let fields: Vec<String> = fields.iter().map(|t| t.to_string()).collect();
+                format!("{}({})", v.name, fields.join(", "))
+            }
+            None => v.name.clone(),
+        })
+        .collect();
+    format!("@{} = {}", def.name, variants.join(" | "))
+}
+
+fn format_func_def(def: &FuncDef) -> String {
+    let mut out = String::new();
+
+    if let Some(config) = &def.self_heal {
+        out.push_str(&format_self_heal(config));
+        out.push('\n');
+    }
+
+    out.push_str(&format_signature(def));
+    out.push_str(" = ");
+    out.push_str(&expr_at(&def.body, PREC_TAIL));
+    out
+}
+
+/// Nombre, `!` y parametros de una funcion: lo que va antes del `=`
+pub fn format_signature(def: &FuncDef) -> String {
+    let mut out = def.name.clone();
+    if def.has_effect {
+        out.push('!');
+    }
+    if !def.params.is_empty() {
+        let params: Vec<String> = def.params.iter()
+            .map(|p| if p.rest { format!("...{}", p.name) } else { p.name.clone() })
+            .collect();
+        out.push_str(&format!("({})", params.join(", ")));
+    }
+    out
+}
+
+/// `@self_heal` con solo los parametros que difieren del default
+fn format_self_heal(config: &SelfHealConfig) -> String {
+    let default = SelfHealConfig::default();
+    let mut params = Vec::new();
+
+    if config.max_attempts != default.max_attempts {
+        params.push(format!("max_attempts: {}", config.max_attempts));
+    }
+    if config.mode != default.mode {
+        let mode = match config.mode {
+            HealMode::Technical => "technical",
+            HealMode::Semantic => "semantic",
+            HealMode::Auto => "auto",
+        };
+        params.push(format!("mode: {}", quote(mode)));
+    }
+
+    if params.is_empty() {
+        "@self_heal".to_string()
+    } else {
+        format!("@self_heal({})", params.join(", "))
+    }
+}
+
+fn format_api_def(def: &ApiDef) -> String {
+    let mut out = format!("+api({}):", quote(&def.base_path));
+    for route in &def.routes {
+        let method = match route.method {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Delete => "DEL",
+        };
+        out.push_str(&format!("\n{}{} {} -> {}", INDENT, method, route.path, expr_at(&route.handler, PREC_TAIL)));
+        if !route.annotations.is_empty() {
+            out.push(' ');
+            out.push_str(&format_annotations(&route.annotations));
+        }
+    }
+    out
+}
+
+fn format_test_def(def: &TestDef) -> String {
+    format!("#test {}: {}", def.name, expr_at(&def.expr, PREC_TAIL))
+}
+
+fn format_goal(goal: &GoalDef) -> String {
+    match &goal.check {
+        Some(check) => format!("goal {} check {}", quote(&goal.description), expr_at(check, PREC_TAIL)),
+        None => format!("goal {}", quote(&goal.description)),
+    }
+}
+
+fn format_observe_def(obs: &ObserveDef) -> String {
+    format_observe(&obs.target, obs.condition.as_ref())
+}
+
+fn format_observe(target: &str, condition: Option<&Expr>) -> String {
+    match condition {
+        Some(cond) => format!("observe {} where {}", target, expr_at(cond, PREC_TAIL)),
+        None => format!("observe {}", target),
+    }
+}
+
+fn format_match_arm(arm: &MatchArm) -> String {
+    let guard = match &arm.guard {
+        Some(guard) => format!(" where {}", expr_at(guard, PREC_COMPARISON)),
+        None => String::new(),
+    };
+    format!("{}{} -> {}", format_pattern(&arm.pattern), guard, expr_at(&arm.body, PREC_COMPARISON))
+}
+
+fn format_pattern(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Wildcard => "_".to_string(),
+        Pattern::Ident(name) => name.clone(),
+        Pattern::Literal(expr) => expr_at(expr, PREC_COMPARISON),
+        Pattern::Constructor { name, fields } => {
+            let fields: Vec<String> = fields.iter().map(format_pattern).collect();
+            format!("{}({})", name, fields.join(", "))
+        }
+    }
+}
+
+/// `if cond then else other`, o la forma `? cond -> a | _ -> b` cuando alguna
+/// rama `then` de la cadena se confundiria con la continuacion de su condicion
+fn format_if(condition: &Expr, then_branch: &Expr, else_branch: Option<&Expr>) -> String {
+    if chain_is_ambiguous(condition, then_branch, else_branch) {
+        return format_if_as_match(condition, then_branch, else_branch);
+    }
+
+    let mut out = format!(
+        "if {} {}",
+        expr_at(condition, PREC_PIPE),
+        expr_at(then_branch, PREC_PIPE)
+    );
+    if let Some(other) = else_branch {
+        out.push_str(" else ");
+        out.push_str(&expr_at(other, PREC_TAIL));
+    }
+    out
+}
+
+fn chain_is_ambiguous(condition: &Expr, then_branch: &Expr, else_branch: Option<&Expr>) -> bool {
+    let then_str = expr_at(then_branch, PREC_PIPE);
+
+    // Tras la condicion, un `-` se lee como resta y un `(` (o `!(`) despues
+    // de algo invocable se lee como llamada
+    if then_str.starts_with('-')
+        || (ends_with_callable(condition) && (then_str.starts_with('(') || then_str.starts_with("!(")))
+    {
+        return true;
+    }
+
+    match else_branch {
+        Some(Expr::If { condition, then_branch, else_branch }) => {
+            chain_is_ambiguous(condition, then_branch, else_branch.as_deref())
+        }
+        _ => false,
+    }
+}
+
+/// Forma de match: el parser la convierte en la misma cadena de `If`
+fn format_if_as_match(condition: &Expr, then_branch: &Expr, else_branch: Option<&Expr>) -> String {
+    let mut arms = vec![format_match_if_arm(condition, then_branch)];
+    let mut rest = else_branch;
+
+    while let Some(expr) = rest {
+        match expr {
+            Expr::If { condition, then_branch, else_branch } => {
+                arms.push(format_match_if_arm(condition, then_branch));
+                rest = else_branch.as_deref();
+            }
+            other => {
+                arms.push(format!("_ -> {}", expr_at(other, PREC_COMPARISON)));
+                rest = None;
+            }
+        }
+    }
+
+    format!("? {}", arms.join(" | "))
+}
+
+fn format_match_if_arm(condition: &Expr, body: &Expr) -> String {
+    // Un `_` suelto seria el brazo por defecto
+    let cond = match condition {
+        Expr::Placeholder => "(_)".to_string(),
+        other => expr_at(other, PREC_COMPARISON),
+    };
+    format!("{} -> {}", cond, expr_at(body, PREC_COMPARISON))
+}
+
+/// `: a; b; c` — el bloque termina en el salto de linea
+fn format_block(items: &[Expr]) -> String {
+    let last = items.len().saturating_sub(1);
+    let items: Vec<String> = items.iter().enumerate()
+        .map(|(i, item)| {
+            // Un bloque anidado al final de un item se tragaria los siguientes
+            if i == last || !ends_with_block(item) {
+                return format_expr(item);
+            }
+            match item {
+                // Un let no puede ir entre parentesis, su valor si
+                Expr::Let { name, value } => format!("{} = ({})", name, format_expr(value)),
+                other => format!("({})", format_expr(other)),
+            }
+        })
+        .collect();
+    format!(": {}", items.join("; "))
+}
+
+/// Envuelve la expresion en parentesis si su precedencia es menor a `min`
+fn expr_at(expr: &Expr, min: u8) -> String {
+    let s = format_expr(expr);
+    if precedence(expr) < min {
+        format!("({})", s)
+    } else {
+        s
+    }
+}
+
+fn join_exprs(exprs: &[Expr]) -> String {
+    let items: Vec<String> = exprs.iter().map(|e| expr_at(e, PREC_TAIL)).collect();
+    items.join(", ")
+}
+
+fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::If { .. }
+        | Expr::Block(_)
+        | Expr::Let { .. }
+        | Expr::Expect { .. }
+        | Expr::Lambda { .. }
+        | Expr::Match { .. }
+        | Expr::For { .. } => PREC_TAIL,
+        Expr::Observe { condition: Some(_), .. } => PREC_TAIL,
+        Expr::Pipe(_) => PREC_PIPE,
+        Expr::NullCoalesce(_, _) => PREC_COALESCE,
+        Expr::BinaryOp { op, .. } => binary_precedence(op),
+        Expr::UnaryOp { .. } | Expr::Spread(_) => PREC_UNARY,
+        Expr::Cast { .. } => PREC_CAST,
+        _ => PREC_POSTFIX,
+    }
+}
+
+fn binary_precedence(op: &BinaryOp) -> u8 {
+    match op {
+        BinaryOp::Or => PREC_OR,
+        BinaryOp::And => PREC_AND,
+        BinaryOp::Eq
+        | BinaryOp::NotEq
+        | BinaryOp::Lt
+        | BinaryOp::Gt
+        | BinaryOp::LtEq
+        | BinaryOp::GtEq
+        | BinaryOp::In => PREC_COMPARISON,
+        BinaryOp::BitOr => PREC_BIT_OR,
+        BinaryOp::BitXor => PREC_BIT_XOR,
+        BinaryOp::BitAnd => PREC_BIT_AND,
+        BinaryOp::Shl | BinaryOp::Shr => PREC_SHIFT,
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Concat => PREC_ADDITIVE,
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => PREC_MULTIPLICATIVE,
+    }
+}
+
+pub(crate) fn binary_symbol(op: &BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Mod => "%",
+        BinaryOp::Eq => "==",
+        BinaryOp::NotEq => "!=",
+        BinaryOp::Lt => "<",
+        BinaryOp::Gt => ">",
+        BinaryOp::LtEq => "<=",
+        BinaryOp::GtEq => ">=",
+        BinaryOp::And => "&&",
+        BinaryOp::Or => "||",
+        BinaryOp::Concat => "++",
+        BinaryOp::In => "in",
+        BinaryOp::BitAnd => "&",
+        BinaryOp::BitOr => "|",
+        BinaryOp::BitXor => "^",
+        BinaryOp::Shl => "<<",
+        BinaryOp::Shr => ">>",
+    }
+}
+
+/// Retorna true si la expresion impresa termina en algo que el parser
+/// trataria como invocable (un `(` siguiente seria una llamada)
+fn ends_with_callable(expr: &Expr) -> bool {
+    match expr {
+        Expr::Ident(_)
+        | Expr::FieldAccess(_, _)
+        | Expr::SafeAccess(_, _)
+        | Expr::Call { .. }
+        | Expr::Lambda { .. } => true,
+        Expr::BinaryOp { right, .. } | Expr::NullCoalesce(_, right) => ends_with_callable(right),
+        Expr::UnaryOp { expr, .. } | Expr::Spread(expr) => ends_with_callable(expr),
+        Expr::Pipe(stages) => stages.last().is_some_and(ends_with_callable),
+        _ => false,
+    }
+}
+
+/// Retorna true si la expresion impresa termina en un bloque sin parentesis
+fn ends_with_block(expr: &Expr) -> bool {
+    match expr {
+        Expr::Block(_) => true,
+        Expr::Let { value, .. } => ends_with_block(value),
+        Expr::If { else_branch: Some(other), .. } => ends_with_block(other),
+        Expr::Observe { condition: Some(cond), .. } => ends_with_block(cond),
+        Expr::Lambda { body, .. } | Expr::For { body, .. } => ends_with_block(body),
+        _ => false,
+    }
+}
+
+/// Los floats siempre llevan punto decimal (`2.0`), si no se leerian como int
+fn format_float(f: f64) -> String {
+    let s = f.to_string();
+    if s.contains('.') || !f.is_finite() {
+        s
+    } else {
+        format!("{}.0", s)
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", escape(s))
+}
+
+/// Inverso de los escapes que procesa el lexer
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+    use crate::parser::parse;
+
+    fn parse_source(source: &str) -> Program {
+        parse(tokenize(source).unwrap()).unwrap()
+    }
+
+    /// Compara ASTs ignorando las posiciones (`span`)
+    fn without_spans(program: &Program) -> serde_json::Value {
+        fn strip(value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    map.remove("span");
+                    map.values_mut().for_each(strip);
+                }
+                serde_json::Value::Array(items) => items.iter_mut().for_each(strip),
+                _ => {}
+            }
+        }
+        let mut value = serde_json::to_value(program).unwrap();
+        strip(&mut value);
+        value
+    }
+
+    /// Formatea, verifica el round-trip y retorna el texto formateado
+    fn roundtrip(source: &str) -> String {
+        let program = parse_source(source);
+        let formatted = format(&program);
+        let reparsed = parse(tokenize(&formatted).unwrap())
+            .unwrap_or_else(|e| panic!("no parsea:\n{}\n{:?}", formatted, e));
+        assert_eq!(without_spans(&program), without_spans(&reparsed), "AST distinto:\n{}", formatted);
+        assert_eq!(format(&reparsed), formatted, "no es idempotente");
+        formatted
+    }
+
+    #[test]
+    fn test_format_operators_spacing() {
+        assert_eq!(roundtrip("f(a b)=a+b*2"), "f(a, b) = a + b * 2\n");
+        assert_eq!(roundtrip("f = \"a\"++\"b\""), "f = \"a\" ++ \"b\"\n");
+        assert_eq!(roundtrip("f(x) = x in [1,2]"), "f(x) = x in [1, 2]\n");
+        assert_eq!(roundtrip("f(a b) = [...a,1]++[...b]"), "f(a, b) = [...a, 1] ++ [...b]\n");
+        assert_eq!(roundtrip("f(a) = {...a,x:1}"), "f(a) = {...a, x: 1}\n");
+    }
+
+    #[test]
+    fn test_format_match_on_value() {
+        assert_eq!(
+            roundtrip("f(r) = r ? Ok(v) -> v | Err(_) -> 0"),
+            "f(r) = r ? Ok(v) -> v | Err(_) -> 0\n"
+        );
+        assert_eq!(
+            roundtrip("f(r) = r ? Ok(n) where n>0 -> n | _ -> 0"),
+            "f(r) = r ? Ok(n) where n > 0 -> n | _ -> 0\n"
+        );
+    }
+
+    #[test]
+    fn test_format_string_literals() {
+        assert_eq!(roundtrip(r#"f = r"a\{b}""#), "f = r\"a\\{b}\"\n");
+        assert_eq!(roundtrip("f = \"\"\"\nuno\ndos\"\"\""), "f = \"uno\\ndos\"\n");
+    }
+
+    #[test]
+    fn test_format_capability_args() {
+        assert_eq!(roundtrip("+env(A,B) +http(timeout:5000)\nf = 1"), "+env(A, B) +http(timeout: 5000)\n\nf = 1\n");
+    }
+
+    #[test]
+    fn test_format_selective_import() {
+        assert_eq!(roundtrip("import a b from \"lib.aura\""), "import a, b from \"lib.aura\"\n");
+        assert_eq!(roundtrip("import \"models/*.aura\""), "import \"models/*.aura\"\n");
+    }
+
+    #[test]
+    fn test_format_null_coalesce() {
+        assert_eq!(roundtrip("f(a b c) = a??b??c"), "f(a, b, c) = a ?? b ?? c\n");
+        assert_eq!(roundtrip("f(a b c) = (a ?? b) ?? c"), "f(a, b, c) = (a ?? b) ?? c\n");
+        assert_eq!(roundtrip("f(a b) = a | 1 ?? b"), "f(a, b) = a | 1 ?? b\n");
+        assert_eq!(roundtrip("f(a) = : x ??= a; x"), "f(a) = : x = x ?? a; x\n");
+    }
+
+    #[test]
+    fn test_format_cast() {
+        assert_eq!(roundtrip("f(x) = x as int+1"), "f(x) = x as int + 1\n");
+        assert_eq!(roundtrip("f(x) = (-x) as float"), "f(x) = (-x) as float\n");
+        assert_eq!(roundtrip("f(x) = (x as string).len"), "f(x) = (x as string).len\n");
+        assert_eq!(roundtrip("f(x) = x as int as string"), "f(x) = x as int as string\n");
+    }
+
+    #[test]
+    fn test_format_rest_param() {
+        assert_eq!(roundtrip("sum(first ...rest) = rest"), "sum(first, ...rest) = rest\n");
+    }
+
+    #[test]
+    fn test_format_keeps_required_parens() {
+        assert_eq!(roundtrip("f(a b) = (a + b) * 2"), "f(a, b) = (a + b) * 2\n");
+        assert_eq!(roundtrip("f(a b c) = a - (b - c)"), "f(a, b, c) = a - (b - c)\n");
+        assert_eq!(roundtrip("f(a b c) = (a - b) - c"), "f(a, b, c) = a - b - c\n");
+        assert_eq!(roundtrip("f(x) = -(x + 1)"), "f(x) = -(x + 1)\n");
+        assert_eq!(roundtrip("f(x) = (x + 1).y"), "f(x) = (x + 1).y\n");
+    }
+
+    #[test]
+    fn test_format_bitwise_operators() {
+        assert_eq!(roundtrip("f(a b) = a & b | a ^ b"), "f(a, b) = a & b | a ^ b\n");
+        assert_eq!(roundtrip("f(a) = (a | 1) & 2"), "f(a) = (a | 1) & 2\n");
+        assert_eq!(roundtrip("f(a) = (a & 4) == 4"), "f(a) = (a & 4) == 4\n");
+        assert_eq!(roundtrip("f(a) = a << 1 + 1"), "f(a) = a << 1 + 1\n");
+        assert_eq!(roundtrip("f(a) = (a << 1) + 1"), "f(a) = (a << 1) + 1\n");
+    }
+
+    #[test]
+    fn test_format_if_inside_operator() {
+        assert_eq!(
+            roundtrip("f(x) = (if x > 0 1 else 2) + 1"),
+            "f(x) = (if x > 0 1 else 2) + 1\n"
+        );
+    }
+
+    #[test]
+    fn test_format_match_becomes_if_chain() {
+        assert_eq!(
+            roundtrip("f(x) = ? x > 0 -> \"pos\" | x < 0 -> \"neg\" | _ -> \"cero\""),
+            "f(x) = if x > 0 \"pos\" else if x < 0 \"neg\" else \"cero\"\n"
+        );
+    }
+
+    #[test]
+    fn test_format_ambiguous_then_branch_uses_match() {
+        // `if x -1` se leeria como `x - 1`
+        assert_eq!(roundtrip("f(x) = ? x -> -1 | _ -> 1"), "f(x) = ? x -> -1 | _ -> 1\n");
+        // `if ok (a)` se leeria como la llamada `ok(a)`
+        assert_eq!(roundtrip("f(ok a) = ? ok -> (if a 1) | _ -> 2"), "f(ok, a) = ? ok -> (if a 1) | _ -> 2\n");
+    }
+
+    #[test]
+    fn test_format_block_items() {
+        assert_eq!(
+            roundtrip("f(x) = : y = x*2 ; z = y+1; z"),
+            "f(x) = : y = x * 2; z = y + 1; z\n"
+        );
+    }
+
+    #[test]
+    fn test_format_strings_and_floats() {
+        assert_eq!(roundtrip(r#"f = "dice \"hola\"\n""#), "f = \"dice \\\"hola\\\"\\n\"\n");
+        assert_eq!(roundtrip("f = 2.0 + 0.5"), "f = 2.0 + 0.5\n");
+        assert_eq!(roundtrip("f(name) = \"Hola {name}\""), "f(name) = \"Hola {name}\"\n");
+    }
+
+    #[test]
+    fn test_format_type_def_aligned() {
+        let formatted = roundtrip("@User {\nid:i @pk\nemail:s @unique @email\nage:i? @min(0)\nauthor User\n}");
+        assert_eq!(
+            formatted,
+            "@User {\n  id     :i   @pk\n  email  :s   @unique @email\n  age    :i?  @min(0)\n  author User\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_format_self_heal() {
+        assert_eq!(roundtrip("@self_heal\nmain = 1"), "@self_heal\nmain = 1\n");
+        assert_eq!(
+            roundtrip("@self_heal(mode: \"semantic\", max_attempts: 5)\nmain = 1"),
+            "@self_heal(max_attempts: 5, mode: \"semantic\")\nmain = 1\n"
+        );
+        assert_eq!(roundtrip("@self_heal(max_attempts: 3)\nmain = 1"), "@self_heal\nmain = 1\n");
+    }
+
+    #[test]
+    fn test_format_cognitive_definitions() {
+        let formatted = roundtrip(
+            "goal \"rapido\" check latency<100\ninvariant limit>0\nobserve response.status where response.status!=200\nf(x)=: observe x; reason {observed: [x], question: \"ok?\"}"
+        );
+        assert_eq!(
+            formatted,
+            "goal \"rapido\" check latency < 100\n\ninvariant limit > 0\n\nobserve response.status where response.status != 200\n\nf(x) = : observe x; reason { observed: [x], question: \"ok?\" }\n"
+        );
+    }
+
+    #[test]
+    fn test_format_empty_program() {
+        assert_eq!(format(&parse_source("")), "");
+    }
+
+    /// Formatea conservando comentarios y verifica AST, docs e idempotencia
+    fn roundtrip_with_comments(source: &str) -> String {
+        let program = parse_source(source);
+        let formatted = format_with_comments(&program, source).unwrap();
+        let tokens = tokenize(&formatted).unwrap();
+        let reparsed = parse(tokens.clone()).unwrap();
+        assert_eq!(without_spans(&program), without_spans(&reparsed), "AST distinto:\n{}", formatted);
+        assert_eq!(format_with_comments(&reparsed, &formatted).unwrap(), formatted, "no es idempotente");
+
+        let docs = |tokens: &[crate::lexer::Spanned<Token>], program: &Program| -> Vec<Option<String>> {
+            crate::docs::extract_docs(tokens, program).into_iter().map(|d| d.doc).collect()
+        };
+        assert_eq!(docs(&tokenize(source).unwrap(), &program), docs(&tokens, &reparsed));
+        formatted
+    }
+
+    #[test]
+    fn test_format_keeps_comments() {
+        let source = "# archivo de prueba\n+http\n\n#doc Un usuario\n@User {\n  #doc Correo\n  email:s\n  name :s\n}\n# comentario suelto\n#doc Duplica\ndouble(x)=x*2\n\n#test doble: double(2) == 4\nmain = double(2)\n# al final\n";
+        assert_eq!(
+            roundtrip_with_comments(source),
+            "# archivo de prueba\n+http\n\n#doc Un usuario\n@User {\n  #doc Correo\n  email :s\n  name  :s\n}\n\n# comentario suelto\n#doc Duplica\ndouble(x) = x * 2\n\n#test doble: double(2) == 4\n\nmain = double(2)\n\n# al final\n"
+        );
+    }
+
+    #[test]
+    fn test_format_rejects_comments_it_cannot_place() {
+        for source in [
+            "main = 1 # al final\n",
+            "f(x) =\n  # dentro del cuerpo\n  x * 2\nmain = f(1)\n",
+            "@User {\n  email :s # tras el campo\n}\n",
+        ] {
+            let program = parse_source(source);
+            let err = format_with_comments(&program, source).unwrap_err();
+            assert!(err.text.starts_with('#'), "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn test_has_comments() {
+        assert!(has_comments("# comentario\nmain = 1"));
+        assert!(has_comments("main = 1 # al final"));
+        assert!(!has_comments("main = \"# no es comentario\""));
+    }
+}
@@ -3,7 +3,7 @@ pub mod tokens;
 use logos::Logos;
 use serde::{Deserialize, Serialize};
 
-pub use tokens::{Span, Spanned, Token};
+pub use tokens::{Span, Spanned, Token, TokenError};
 
 /// Error de lexer
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -12,6 +12,14 @@ pub struct LexError {
     pub span: Span,
 }
 
+impl LexError {
+    /// Linea y columna (desde 1) donde empieza el token invalido
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        let location = crate::error::Location::from_span(&self.span, source, "");
+        (location.line, location.col)
+    }
+}
+
 /// Resultado del lexer: tokens con posiciones o errores
 pub type LexResult = Result<Vec<Spanned<Token>>, Vec<LexError>>;
 
@@ -31,14 +39,19 @@ pub fn tokenize(source: &str) -> LexResult {
         match result {
             Ok(token) => {
                 // Ignorar comentarios que no son #test o #doc
-                if let Token::Comment(ref c) = token {
-                    if !c.starts_with("#test") && !c.starts_with("#doc") {
-                        continue;
-                    }
+                if let Token::Comment(ref c) = token
+                    && !c.starts_with("#test") && !c.starts_with("#doc") {
+                    continue;
                 }
                 tokens.push(Spanned::new(token, span));
             }
-            Err(_) => {
+            Err(TokenError::Number(reason)) => {
+                errors.push(LexError {
+                    message: format!("Literal numerico invalido '{}': {}", lexer.slice(), reason),
+                    span,
+                });
+            }
+            Err(TokenError::Invalid) => {
                 errors.push(LexError {
                     message: format!("Token inválido: '{}'", lexer.slice()),
                     span,
@@ -63,6 +76,7 @@ pub fn tokenize_json(source: &str) -> String {
 }
 
 #[cfg(test)]
+#[allow(clippy::len_zero)]
 mod tests {
     use super::*;
 
@@ -116,6 +130,13 @@ mod tests {
         assert_eq!(tokens[2].value, Token::AnnEmail);
     }
 
+    #[test]
+    fn test_bitwise_operators() {
+        let tokens = tokenize("a & b | c ^ d << 1 >> 2 > 0").unwrap();
+        let ops: Vec<&Token> = tokens.iter().map(|t| &t.value).filter(|t| t.is_operator()).collect();
+        assert_eq!(ops, vec![&Token::Ampersand, &Token::Pipe, &Token::Caret, &Token::Shl, &Token::Shr, &Token::Gt]);
+    }
+
     #[test]
     fn test_pattern_matching() {
         let tokens = tokenize("x | Ok(v) -> v | Err(e) -> nil").unwrap();
@@ -145,4 +166,71 @@ fetch(id) = http.get("users/{id}").json(User)
         let result = tokenize(source);
         assert!(result.is_ok(), "Failed to tokenize: {:?}", result);
     }
+
+    fn single_value(source: &str) -> Token {
+        let tokens = tokenize(source).unwrap();
+        assert_eq!(tokens.len(), 1, "{:?}", tokens);
+        tokens[0].value.clone()
+    }
+
+    #[test]
+    fn test_error_line_col() {
+        let source = "x = 1\ny = 2 $\n\n$z = 3\n";
+        let errors = tokenize(source).unwrap_err();
+        let positions: Vec<_> = errors.iter().map(|e| e.line_col(source)).collect();
+        // El segundo `$` esta al inicio de su linea
+        assert_eq!(positions, vec![(2, 7), (4, 1)]);
+    }
+
+    #[test]
+    fn test_raw_string() {
+        assert_eq!(
+            single_value(r#"r"C:\path\{not_interpolated}""#),
+            Token::RawString(r"C:\path\{not_interpolated}".to_string())
+        );
+        // `r` suelto sigue siendo un identificador
+        let tokens = tokenize(r#"r "x""#).unwrap();
+        assert!(matches!(tokens[0].value, Token::Ident(ref s) if s == "r"));
+    }
+
+    #[test]
+    fn test_triple_quoted_string() {
+        let source = "\"\"\"\nlinea 1\n  \"citada\" {x}\\t\n\"\"\"";
+        assert_eq!(single_value(source), Token::String("linea 1\n  \"citada\" {x}\t\n".to_string()));
+        assert_eq!(single_value("\"\"\"una\"\"\""), Token::String("una".to_string()));
+
+        let tokens = tokenize("x = \"\"\"a\nb\"\"\"\ny = 1").unwrap();
+        assert_eq!(tokens[2].value, Token::String("a\nb".to_string()));
+        assert_eq!(tokens[3].value, Token::Newline);
+    }
+
+    #[test]
+    fn test_integer_bases() {
+        assert_eq!(single_value("0xFF"), Token::Int(255));
+        assert_eq!(single_value("0Xff"), Token::Int(255));
+        assert_eq!(single_value("0b1010"), Token::Int(10));
+        assert_eq!(single_value("0o17"), Token::Int(15));
+        assert_eq!(single_value("0xFF_FF"), Token::Int(0xFFFF));
+        assert_eq!(single_value("0b1111_0000"), Token::Int(0xF0));
+    }
+
+    #[test]
+    fn test_digit_separators() {
+        assert_eq!(single_value("1_000_000"), Token::Int(1_000_000));
+        assert_eq!(single_value("1_000.5"), Token::Float(1000.5));
+        assert_eq!(single_value("0.000_1"), Token::Float(0.0001));
+    }
+
+    #[test]
+    fn test_malformed_numbers_are_rejected() {
+        for source in ["0xZZ", "0b102", "0o8", "0x", "1_", "1__000", "1_.5", "99999999999999999999", "0x1_FFFF_FFFF_FFFF_FFFF"] {
+            let errors = tokenize(source).expect_err(source);
+            assert_eq!(errors.len(), 1, "{}", source);
+            assert!(errors[0].message.starts_with("Literal numerico invalido"), "{}: {}", source, errors[0].message);
+            assert_eq!(errors[0].span, Span::new(0, source.len()));
+        }
+
+        let errors = tokenize("x = 0b12").unwrap_err();
+        assert!(errors[0].message.contains("'2' no es un digito en base 2"), "{}", errors[0].message);
+    }
 }
@@ -1,10 +1,21 @@
 use logos::Logos;
 use serde::{Deserialize, Serialize};
 
+/// Motivo por el que el lexer rechazo un token
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum TokenError {
+    /// Caracter que no inicia ningun token
+    #[default]
+    Invalid,
+    /// Literal numerico mal formado (`0xZZ`, `1__0`, `1_`, fuera de rango)
+    Number(String),
+}
+
 /// Tokens de AURA
 /// Diseñados para ser mínimos y no ambiguos
 #[derive(Logos, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[logos(skip r"[ \t]+")]  // Ignorar espacios y tabs (no newlines)
+#[logos(error = TokenError)]
 pub enum Token {
     // ═══════════════════════════════════════════════════════════
     // CAPACIDADES (+http, +json, etc.)
@@ -117,6 +128,15 @@ pub enum Token {
     #[token("&")]
     Ampersand,
 
+    #[token("^")]
+    Caret,
+
+    #[token("<<")]
+    Shl,
+
+    #[token(">>")]
+    Shr,
+
     #[token("++")]
     PlusPlus,
 
@@ -135,6 +155,9 @@ pub enum Token {
     #[token("??")]
     NullCoalesce,
 
+    #[token("??=")]
+    NullCoalesceAssign,
+
     #[token("?.")]
     SafeNav,
 
@@ -165,6 +188,9 @@ pub enum Token {
     #[token("in")]
     In,
 
+    #[token("as")]
+    As,
+
     #[token("while")]
     While,
 
@@ -320,36 +346,35 @@ pub enum Token {
     // ═══════════════════════════════════════════════════════════
     #[regex(r#""([^"\\]|\\.)*""#, |lex| {
         let s = lex.slice();
-        let inner = &s[1..s.len()-1];
-        // Process escape sequences
-        let mut result = String::new();
-        let mut chars = inner.chars().peekable();
-        while let Some(c) = chars.next() {
-            if c == '\\' {
-                match chars.next() {
-                    Some('n') => result.push('\n'),
-                    Some('t') => result.push('\t'),
-                    Some('r') => result.push('\r'),
-                    Some('\\') => result.push('\\'),
-                    Some('"') => result.push('"'),
-                    Some(other) => {
-                        result.push('\\');
-                        result.push(other);
-                    }
-                    None => result.push('\\'),
-                }
-            } else {
-                result.push(c);
-            }
-        }
-        result
+        unescape(&s[1..s.len()-1])
+    })]
+    // Multi-linea: `"""..."""`, con escapes e interpolacion como un string normal.
+    // Puede contener `"` y `""` sueltos; un salto de linea justo despues de las
+    // comillas de apertura se descarta.
+    #[regex(r#""""([^"]|"[^"]|""[^"])*"{0,2}""""#, |lex| {
+        let s = lex.slice();
+        let inner = &s[3..s.len()-3];
+        unescape(inner.strip_prefix('\n').unwrap_or(inner))
     })]
     String(String),
 
-    #[regex(r"[0-9]+\.[0-9]+", |lex| lex.slice().parse::<f64>().ok())]
+    /// `r"..."`: sin escapes ni interpolacion, puede ocupar varias lineas
+    #[regex(r#"r"[^"]*""#, |lex| {
+        let s = lex.slice();
+        s[2..s.len()-1].to_string()
+    })]
+    RawString(String),
+
+    #[regex(r"[0-9][0-9_]*\.[0-9][0-9_]*", |lex| parse_float(lex.slice()))]
     Float(f64),
 
-    #[regex(r"[0-9]+", |lex| lex.slice().parse::<i64>().ok(), priority = 2)]
+    // Decimal (`1_000`), hex (`0xFF`), binario (`0b1010`) u octal (`0o17`).
+    // Los prefijos aceptan cualquier caracter alfanumerico para poder
+    // reportar `0xZZ` como literal invalido en vez de `0` seguido de `xZZ`.
+    #[regex(r"[0-9][0-9_]*", |lex| parse_int(lex.slice(), 10), priority = 2)]
+    #[regex(r"0[xX][0-9a-zA-Z_]*", |lex| parse_int(&lex.slice()[2..], 16))]
+    #[regex(r"0[bB][0-9a-zA-Z_]*", |lex| parse_int(&lex.slice()[2..], 2))]
+    #[regex(r"0[oO][0-9a-zA-Z_]*", |lex| parse_int(&lex.slice()[2..], 8))]
     Int(i64),
 
     // ═══════════════════════════════════════════════════════════
@@ -370,6 +395,10 @@ pub enum Token {
     #[token("..")]
     Spread,
 
+    /// Parametro rest en una definicion: `sum(...nums) = ...`
+    #[token("...")]
+    Ellipsis,
+
     // ═══════════════════════════════════════════════════════════
     // COMENTARIOS
     // ═══════════════════════════════════════════════════════════
@@ -383,6 +412,61 @@ pub enum Token {
     Path(String),
 }
 
+/// Procesa las secuencias de escape de un string literal
+fn unescape(inner: &str) -> String {
+    let mut result = String::new();
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some('r') => result.push('\r'),
+                Some('\\') => result.push('\\'),
+                Some('"') => result.push('"'),
+                Some(other) => {
+                    result.push('\\');
+                    result.push(other);
+                }
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Quita los `_` separadores de un literal, validando que separen digitos
+fn strip_separators(digits: &str) -> Result<String, TokenError> {
+    if digits.is_empty() {
+        return Err(TokenError::Number("faltan digitos".to_string()));
+    }
+    if digits.ends_with('_') || digits.contains("__") {
+        return Err(TokenError::Number("'_' debe ir entre digitos".to_string()));
+    }
+    Ok(digits.replace('_', ""))
+}
+
+/// Parsea los digitos de un entero (sin prefijo) en la base dada
+fn parse_int(digits: &str, radix: u32) -> Result<i64, TokenError> {
+    let clean = strip_separators(digits)?;
+    if let Some(bad) = clean.chars().find(|c| !c.is_digit(radix)) {
+        return Err(TokenError::Number(format!("'{}' no es un digito en base {}", bad, radix)));
+    }
+    i64::from_str_radix(&clean, radix)
+        .map_err(|_| TokenError::Number("fuera del rango de un entero de 64 bits".to_string()))
+}
+
+fn parse_float(literal: &str) -> Result<f64, TokenError> {
+    // Un `_` pegado al punto (`1_.5`, `1._5`) tampoco separa digitos
+    for part in literal.split('.') {
+        strip_separators(part)?;
+    }
+    literal.replace('_', "").parse::<f64>()
+        .map_err(|e| TokenError::Number(e.to_string()))
+}
+
 impl Token {
     /// Retorna true si el token es un operador
     pub fn is_operator(&self) -> bool {
@@ -401,6 +485,9 @@ impl Token {
                 | Token::GtEq
                 | Token::Ampersand
                 | Token::Pipe
+                | Token::Caret
+                | Token::Shl
+                | Token::Shr
                 | Token::PlusPlus
         )
     }
@@ -417,6 +504,7 @@ impl Token {
                 | Token::Else
                 | Token::For
                 | Token::In
+                | Token::As
                 | Token::While
                 | Token::Return
                 | Token::Break
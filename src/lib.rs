@@ -14,11 +14,14 @@
 pub mod agent;
 pub mod caps;
 pub mod cli_output;
+pub mod docs;
 pub mod error;
+pub mod fmt;
 pub mod lexer;
 pub mod loader;
 pub mod parser;
 pub mod reload;
+pub mod repl;
 pub mod server;
 pub mod types;
 pub mod vm;
@@ -34,10 +37,11 @@ pub use error::{
     format_errors_pretty,
 };
 pub use lexer::{tokenize, Token};
-pub use loader::{load_file, is_builtin_capability, LoadError};
+pub use loader::{load_file, find_aura_files, is_builtin_capability, LoadError, LoadErrorKind};
 pub use parser::{parse, parse_expression, parse_function_def, looks_like_function_def, Program, Expr, Type, Definition, FuncDef, SelfHealConfig, HealMode, GoalDef, ObserveDef};
-pub use vm::{Value, ExpectationFailure, CognitiveRuntime, CognitiveDecision, ObservationEvent, DeliberationTrigger, NullCognitiveRuntime, VMCheckpoint, CheckpointManager};
+pub use vm::{Value, ExpectationFailure, GoalOutcome, TestOutcome, FunctionProfile, CognitiveRuntime, CognitiveDecision, ObservationEvent, DeliberationTrigger, NullCognitiveRuntime, TraceEntry, VMCheckpoint, CheckpointManager};
 pub use vm::runner::{run_cognitive, run_program_cognitive, CognitiveRunResult};
+pub use vm::recording::{RecordingCognitiveRuntime, RecordedEvent, RecordedKind};
 pub use vm::agent_cognitive::{AgentCognitiveRuntime, ReasoningEpisode, EpisodeOutcome, EpisodeContext, CognitiveSafetyConfig, validate_fix};
 
 /// Versión de AURA
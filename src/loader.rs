@@ -3,12 +3,18 @@
 //! Handles file imports with +archivo syntax.
 //! When +nombre is encountered and it's not a builtin capability,
 //! the loader searches for nombre.aura in the same directory as the main file.
+//!
+//! `import parse, render from "lib.aura"` trae solo las definiciones
+//! nombradas. Las funciones auxiliares que usen tambien hay que importarlas.
+//!
+//! `import "models/*.aura"` trae enteros todos los archivos que coinciden
+//! (un directorio trae todos sus `.aura`), en orden de ruta.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use crate::lexer::{tokenize, Span};
-use crate::parser::{parse, Capability, Program, ParseError};
+use crate::parser::{parse, Capability, Definition, ImportDef, Program, ParseError};
 
 /// Builtin capabilities that should not be treated as file imports
 const BUILTIN_CAPABILITIES: &[&str] = &[
@@ -22,6 +28,18 @@ pub struct LoadError {
     pub message: String,
     pub file: Option<String>,
     pub span: Option<Span>,
+    pub kind: LoadErrorKind,
+}
+
+/// Que fallo al cargar un modulo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadErrorKind {
+    /// No se pudo leer un archivo (o no existe)
+    Io,
+    /// El codigo no tokeniza o no parsea
+    Syntax,
+    /// Los archivos se leyeron pero los imports no cierran
+    Import,
 }
 
 impl LoadError {
@@ -30,6 +48,15 @@ impl LoadError {
             message: message.into(),
             file: None,
             span: None,
+            kind: LoadErrorKind::Import,
+        }
+    }
+
+    /// Error leyendo `path` del disco
+    pub fn io(path: &Path, err: std::io::Error) -> Self {
+        Self {
+            kind: LoadErrorKind::Io,
+            ..Self::new(format!("Error leyendo '{}': {}", path.display(), err))
         }
     }
 
@@ -38,28 +65,60 @@ impl LoadError {
         self
     }
 
-    pub fn file_not_found(name: &str, searched_path: &Path) -> Self {
+    pub fn file_not_found(file: &str, searched_path: &Path) -> Self {
         Self {
             message: format!(
-                "No se encontro '{}.aura' (buscado en: {})",
-                name,
+                "No se encontro '{}' (buscado en: {})",
+                file,
                 searched_path.display()
             ),
             file: None,
             span: None,
+            kind: LoadErrorKind::Io,
+        }
+    }
+
+    /// Un import selectivo pide un nombre que el modulo no define
+    pub fn missing_import(name: &str, import: &ImportDef) -> Self {
+        Self {
+            message: format!("'{}' no esta definido en '{}'", name, import.path),
+            file: None,
+            span: Some(import.span.clone()),
+            kind: LoadErrorKind::Import,
+        }
+    }
+
+    /// Un glob o directorio importado no tiene ningun `.aura`
+    pub fn no_matches(pattern: &str) -> Self {
+        Self {
+            message: format!("Ningun archivo coincide con '{}'", pattern),
+            file: None,
+            span: None,
+            kind: LoadErrorKind::Io,
         }
     }
 
-    pub fn circular_import(name: &str, chain: &[String]) -> Self {
+    /// Dos archivos de un mismo import definen `name`
+    pub fn name_collision(name: &str, first: &str, second: &str, import: &ImportDef) -> Self {
         Self {
             message: format!(
-                "Import circular detectado: {} (cadena: {} -> {})",
-                name,
-                chain.join(" -> "),
-                name
+                "'{}' esta definido en '{}' y en '{}' (importados con '{}')",
+                name, first, second, import.path
             ),
             file: None,
+            span: Some(import.span.clone()),
+            kind: LoadErrorKind::Import,
+        }
+    }
+
+    /// `chain` son los archivos en carga, desde el principal hasta el que
+    /// importa `file`, que ya estaba en la cadena
+    pub fn circular_import(file: &str, chain: &[String]) -> Self {
+        Self {
+            message: format!("Import circular detectado: {} -> {}", chain.join(" -> "), file),
+            file: chain.last().cloned(),
             span: None,
+            kind: LoadErrorKind::Import,
         }
     }
 
@@ -71,6 +130,7 @@ impl LoadError {
             message: format!("Error de parsing en '{}': {}", file, messages.join("; ")),
             file: Some(file.to_string()),
             span: errors.first().map(|e| e.span.clone()),
+            kind: LoadErrorKind::Syntax,
         }
     }
 
@@ -79,6 +139,7 @@ impl LoadError {
             message: format!("Error de tokenizacion en '{}': {}", file, message),
             file: Some(file.to_string()),
             span: None,
+            kind: LoadErrorKind::Syntax,
         }
     }
 }
@@ -102,10 +163,19 @@ pub fn is_builtin_capability(name: &str) -> bool {
 pub struct Loader {
     /// Base directory for resolving imports
     base_dir: PathBuf,
-    /// Set of already imported files (to detect circular imports)
+    /// Modulos ya parseados, por ruta canonica: un import compartido
+    /// (diamante) se tokeniza y parsea una sola vez y sus definiciones
+    /// entran una sola vez al programa
     imported: HashSet<PathBuf>,
-    /// Import chain for error messages
+    /// Archivos en carga, del principal al actual; un import que ya esta
+    /// aca es circular
+    loading: Vec<PathBuf>,
+    /// Nombres de `loading`, para los mensajes de error
     import_chain: Vec<String>,
+    /// Programas ya cargados de cada modulo importado, por ruta canonica
+    modules: HashMap<PathBuf, Program>,
+    /// Modulos mezclados enteros con `+nombre`
+    merged: HashSet<PathBuf>,
 }
 
 impl Loader {
@@ -114,7 +184,10 @@ impl Loader {
         Self {
             base_dir: base_dir.as_ref().to_path_buf(),
             imported: HashSet::new(),
+            loading: Vec::new(),
             import_chain: Vec::new(),
+            modules: HashMap::new(),
+            merged: HashSet::new(),
         }
     }
 
@@ -127,13 +200,32 @@ impl Loader {
         Self::new(base_dir)
     }
 
+    /// Cantidad de archivos tokenizados y parseados en esta carga
+    pub fn parsed_modules(&self) -> usize {
+        self.imported.len()
+    }
+
     /// Load a program from source code, resolving all imports
     pub fn load_source(&mut self, source: &str, file_name: &str) -> Result<Program, LoadError> {
-        // Mark this file as imported
         let file_path = self.base_dir.join(file_name);
+        self.load_module(source, file_name, &file_path)
+    }
+
+    fn load_module(&mut self, source: &str, file_name: &str, path: &Path) -> Result<Program, LoadError> {
+        // Mark this file as imported
+        let file_path = module_key(path);
         self.imported.insert(file_path.clone());
+        self.loading.push(file_path);
         self.import_chain.push(file_name.to_string());
 
+        let result = self.load_program(source, file_name);
+
+        self.loading.pop();
+        self.import_chain.pop();
+        result
+    }
+
+    fn load_program(&mut self, source: &str, file_name: &str) -> Result<Program, LoadError> {
         // Tokenize
         let tokens = tokenize(source)
             .map_err(|errors| {
@@ -151,7 +243,6 @@ impl Loader {
         // Process imports
         self.resolve_imports(&mut program)?;
 
-        self.import_chain.pop();
         Ok(program)
     }
 
@@ -163,9 +254,34 @@ impl Loader {
             .unwrap_or("unknown");
 
         let source = std::fs::read_to_string(path)
-            .map_err(|e| LoadError::new(format!("Error leyendo '{}': {}", path.display(), e)))?;
+            .map_err(|e| LoadError::io(path, e))?;
+
+        self.load_module(&source, file_name, path)
+    }
+
+    /// Programa de un modulo importado, con sus propios imports resueltos
+    ///
+    /// Cada archivo se carga una vez; los imports siguientes usan la copia.
+    fn module(&mut self, file: &str, path: &Path) -> Result<Program, LoadError> {
+        let key = module_key(path);
+
+        // Check for circular imports
+        if self.loading.contains(&key) {
+            return Err(LoadError::circular_import(file, &self.import_chain));
+        }
+
+        if let Some(program) = self.modules.get(&key) {
+            return Ok(program.clone());
+        }
+
+        // Check if file exists
+        if !path.exists() {
+            return Err(LoadError::file_not_found(file, path));
+        }
 
-        self.load_source(&source, file_name)
+        let program = self.load_file(path)?;
+        self.modules.insert(key, program.clone());
+        Ok(program)
     }
 
     /// Resolve imports in a program
@@ -187,36 +303,226 @@ impl Loader {
 
         // Process each file import
         for import in file_imports {
-            let import_name = &import.name;
-            let import_file = format!("{}.aura", import_name);
+            let import_file = format!("{}.aura", import.name);
             let import_path = self.base_dir.join(&import_file);
 
-            // Check for circular imports
-            if self.imported.contains(&import_path) {
-                return Err(LoadError::circular_import(import_name, &self.import_chain));
-            }
+            // Load the imported file
+            let imported_program = self.module(&import_file, &import_path)?;
 
-            // Check if file exists
-            if !import_path.exists() {
-                return Err(LoadError::file_not_found(import_name, &import_path));
+            // Ya mezclado entero por otro import: sus definiciones ya estan en el programa
+            if !self.merged.insert(module_key(&import_path)) {
+                continue;
             }
 
-            // Load the imported file
-            let imported_program = self.load_file(&import_path)?;
+            merge_capabilities(program, imported_program.capabilities);
 
-            // Merge capabilities (avoiding duplicates)
-            for cap in imported_program.capabilities {
-                if !program.capabilities.iter().any(|c| c.name == cap.name) {
-                    program.capabilities.push(cap);
+            // Merge definitions
+            program.definitions.extend(imported_program.definitions);
+        }
+
+        // Imports selectivos: solo las definiciones nombradas
+        let (imports, definitions): (Vec<Definition>, Vec<Definition>) = program.definitions
+            .drain(..)
+            .partition(|def| matches!(def, Definition::Import(_)));
+        program.definitions = definitions;
+
+        for def in imports {
+            let Definition::Import(import) = def else { continue };
+            let modules = self.import_modules(&import)?;
+
+            // `import "path"`: cada modulo entero, como `+nombre`
+            if import.names.is_empty() {
+                for (path, module) in modules {
+                    if self.merged.insert(module_key(&path)) {
+                        merge_capabilities(program, module.capabilities);
+                        program.definitions.extend(module.definitions);
+                    }
                 }
+                continue;
             }
 
-            // Merge definitions
-            program.definitions.extend(imported_program.definitions);
+            let mut selected = Vec::new();
+            for name in &import.names {
+                let found = modules.iter()
+                    .flat_map(|(_, module)| &module.definitions)
+                    .find(|d| d.name() == Some(name.as_str()));
+                match found {
+                    Some(def) => selected.push(def.clone()),
+                    None => {
+                        let mut error = LoadError::missing_import(name, &import);
+                        error.file = self.import_chain.last().cloned();
+                        return Err(error);
+                    }
+                }
+            }
+
+            for (_, module) in modules {
+                merge_capabilities(program, module.capabilities);
+            }
+            for def in selected {
+                // Puede haber llegado por otro import del mismo modulo
+                if !program.definitions.contains(&def) {
+                    program.definitions.push(def);
+                }
+            }
         }
 
         Ok(())
     }
+
+    /// Modulos que nombra un `import`, en orden de ruta
+    ///
+    /// Si el path es un glob o un directorio y dos de sus archivos definen
+    /// el mismo nombre, es un error: el resultado dependeria del orden.
+    fn import_modules(&mut self, import: &ImportDef) -> Result<Vec<(PathBuf, Program)>, LoadError> {
+        let with_context = |mut error: LoadError, chain: &[String]| {
+            error.file = chain.last().cloned();
+            error
+        };
+
+        let paths = self.import_paths(&import.path)
+            .map_err(|e| with_context(e, &self.import_chain))?;
+
+        let mut modules = Vec::new();
+        let mut owners: HashMap<String, String> = HashMap::new();
+        for path in paths {
+            let file = path.strip_prefix(&self.base_dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .into_owned();
+            let module = self.module(&file, &path)?;
+
+            for name in module.definitions.iter().filter_map(Definition::name) {
+                if let Some(first) = owners.insert(name.to_string(), file.clone()) {
+                    let error = LoadError::name_collision(name, &first, &file, import);
+                    return Err(with_context(error, &self.import_chain));
+                }
+            }
+            modules.push((path, module));
+        }
+        Ok(modules)
+    }
+
+    /// Archivos que nombra el path de un `import`: el archivo mismo, los
+    /// `.aura` de un directorio (recursivamente) o los que coinciden con un
+    /// glob de `*` y `?` (`models/*.aura`)
+    fn import_paths(&self, pattern: &str) -> Result<Vec<PathBuf>, LoadError> {
+        let path = self.base_dir.join(pattern);
+
+        let files = if is_glob(pattern) {
+            expand_glob(&self.base_dir, pattern)
+        } else if path.is_dir() {
+            find_aura_files(&path).map_err(|e| LoadError::io(&path, e))?
+        } else {
+            // Un archivo suelto; si no existe lo reporta `module`
+            return Ok(vec![path]);
+        };
+
+        if files.is_empty() {
+            return Err(LoadError::no_matches(pattern));
+        }
+        Ok(files)
+    }
+}
+
+/// Si el path de un import tiene comodines
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Archivos bajo `base` que coinciden con `pattern`, ordenados por ruta.
+/// Cada componente del patron se compara con un nivel de directorio
+/// (`*` no cruza `/`); los directorios que no se pueden leer se ignoran.
+fn expand_glob(base: &Path, pattern: &str) -> Vec<PathBuf> {
+    let segments: Vec<&str> = pattern.split('/')
+        .filter(|s| !s.is_empty() && *s != ".")
+        .collect();
+
+    let mut current = vec![base.to_path_buf()];
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i + 1 == segments.len();
+        let mut next = Vec::new();
+
+        for dir in &current {
+            if !is_glob(segment) {
+                next.push(dir.join(segment));
+                continue;
+            }
+            let Ok(entries) = std::fs::read_dir(dir) else { continue };
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                if name.to_str().is_some_and(|name| glob_match(segment, name)) {
+                    next.push(entry.path());
+                }
+            }
+        }
+
+        // Los intermedios tienen que ser directorios; el ultimo, archivos
+        next.retain(|p| if last { p.is_file() } else { p.is_dir() });
+        current = next;
+    }
+
+    current.sort();
+    current
+}
+
+/// `*` coincide con cualquier secuencia (incluso vacia) y `?` con un caracter
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Ultimo `*` visto y la posicion de `name` desde la que se reintenta
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    p = sp + 1;
+                    n = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Merge capabilities (avoiding duplicates)
+///
+/// Si ambos declaran argumentos (`+env(A)` y `+env(B)`) se juntan; ante el
+/// mismo nombre gana el del programa que importa. Si uno de los dos no tiene
+/// argumentos, vale la declaracion del programa que importa.
+fn merge_capabilities(program: &mut Program, capabilities: Vec<Capability>) {
+    for cap in capabilities {
+        match program.capabilities.iter_mut().find(|c| c.name == cap.name) {
+            Some(existing) if !existing.args.is_empty() && !cap.args.is_empty() => {
+                for arg in cap.args {
+                    if !existing.args.iter().any(|a| a.name == arg.name) {
+                        existing.args.push(arg);
+                    }
+                }
+            }
+            Some(_) => {}
+            None => program.capabilities.push(cap),
+        }
+    }
+}
+
+/// Clave de un modulo en el loader: la ruta canonica, asi `./b.aura` y
+/// `sub/../b.aura` son el mismo archivo. Si no existe (source en memoria)
+/// se usa la ruta tal cual.
+fn module_key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
 }
 
 /// Convenience function to load a file with all its imports resolved
@@ -226,7 +532,7 @@ pub fn load_file(file_path: impl AsRef<Path>) -> Result<Program, LoadError> {
 
     // Read the main file
     let source = std::fs::read_to_string(path)
-        .map_err(|e| LoadError::new(format!("Error leyendo '{}': {}", path.display(), e)))?;
+        .map_err(|e| LoadError::io(path, e))?;
 
     let file_name = path.file_name()
         .and_then(|n| n.to_str())
@@ -241,6 +547,32 @@ pub fn load_source(source: &str, base_dir: impl AsRef<Path>, file_name: &str) ->
     loader.load_source(source, file_name)
 }
 
+/// Busca recursivamente los archivos `.aura` bajo `dir`, ordenados por ruta.
+/// Omite los directorios ocultos (como `.aura/`, donde vive el estado local).
+pub fn find_aura_files(dir: impl AsRef<Path>) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.as_ref().to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                let hidden = path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with('.'));
+                if !hidden {
+                    pending.push(path);
+                }
+            } else if path.extension().is_some_and(|e| e == "aura") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -264,4 +596,185 @@ mod tests {
         assert_eq!(program.capabilities[0].name, "http");
         assert_eq!(program.definitions.len(), 1);
     }
+
+    #[test]
+    fn test_circular_import_names_the_cycle() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.aura"), "+b\nmain = helper\n").unwrap();
+        std::fs::write(dir.path().join("b.aura"), "+a\nhelper = 1\n").unwrap();
+
+        let error = load_file(dir.path().join("a.aura")).unwrap_err();
+        assert_eq!(error.message, "Import circular detectado: a.aura -> b.aura -> a.aura");
+        assert_eq!(error.file.as_deref(), Some("b.aura"));
+    }
+
+    fn function_names(program: &Program) -> Vec<&str> {
+        program.definitions.iter().filter_map(Definition::name).collect()
+    }
+
+    #[test]
+    fn test_selective_import_brings_only_named_definitions() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lib.aura"), "+json\ndouble(x) = x * 2\ntriple(x) = x * 3\nsecret = 42\n").unwrap();
+        std::fs::write(
+            dir.path().join("main.aura"),
+            "import double, triple from \"lib.aura\"\nimport double from \"lib.aura\"\nmain = double(triple(1))\n",
+        ).unwrap();
+
+        let program = load_file(dir.path().join("main.aura")).unwrap();
+        assert_eq!(function_names(&program), vec!["main", "double", "triple"]);
+        assert!(!program.definitions.iter().any(|d| matches!(d, Definition::Import(_))));
+        // Las capacidades del modulo siguen haciendo falta
+        assert!(program.capabilities.iter().any(|c| c.name == "json"));
+    }
+
+    #[test]
+    fn test_selective_import_of_missing_name() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lib.aura"), "double(x) = x * 2\n").unwrap();
+        std::fs::write(dir.path().join("main.aura"), "import double, halve from \"lib.aura\"\nmain = 1\n").unwrap();
+
+        let error = load_file(dir.path().join("main.aura")).unwrap_err();
+        assert_eq!(error.message, "'halve' no esta definido en 'lib.aura'");
+        assert_eq!(error.file.as_deref(), Some("main.aura"));
+        assert_eq!(error.span.map(|s| s.start), Some(0));
+    }
+
+    #[test]
+    fn test_selective_import_of_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.aura"), "import x from \"nope.aura\"\nmain = 1\n").unwrap();
+
+        let error = load_file(dir.path().join("main.aura")).unwrap_err();
+        assert!(error.message.starts_with("No se encontro 'nope.aura'"), "{}", error.message);
+    }
+
+    #[test]
+    fn test_module_key_ignores_path_spelling() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("b.aura"), "x = 1\n").unwrap();
+
+        let direct = module_key(&dir.path().join("b.aura"));
+        let roundabout = module_key(&dir.path().join("sub").join("..").join(".").join("b.aura"));
+        assert_eq!(direct, roundabout);
+    }
+
+    #[test]
+    fn test_self_import_is_circular() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.aura"), "+a\nmain = 1\n").unwrap();
+
+        let error = load_file(dir.path().join("a.aura")).unwrap_err();
+        assert!(error.message.ends_with("a.aura -> a.aura"), "{}", error.message);
+    }
+
+    #[test]
+    fn test_diamond_import_parses_shared_module_once() {
+        // a importa b y c; los dos importan utils
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.aura"), "+b +c\nmain = from_b + from_c\n").unwrap();
+        std::fs::write(dir.path().join("b.aura"), "+utils\nfrom_b = one\n").unwrap();
+        std::fs::write(dir.path().join("c.aura"), "+utils\nfrom_c = one\n").unwrap();
+        std::fs::write(dir.path().join("utils.aura"), "one = 1\n").unwrap();
+
+        let mut loader = Loader::from_file(dir.path().join("a.aura"));
+        let program = loader.load_file(dir.path().join("a.aura")).unwrap();
+        // a, b, c y utils: utils se parsea una sola vez
+        assert_eq!(loader.parsed_modules(), 4);
+
+        let names: Vec<&str> = program.definitions.iter()
+            .filter_map(|d| match d {
+                crate::parser::Definition::FuncDef(f) => Some(f.name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names.iter().filter(|n| **n == "one").count(), 1);
+        assert!(names.contains(&"from_b") && names.contains(&"from_c"));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.aura", "user.aura"));
+        assert!(glob_match("u?er.aura", "user.aura"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("*.aura", "user.txt"));
+        assert!(!glob_match("u?er.aura", "uer.aura"));
+    }
+
+    fn write_models(dir: &Path) {
+        std::fs::create_dir_all(dir.join("models")).unwrap();
+        std::fs::write(dir.join("models/user.aura"), "@User {\n  name:s\n}\nuser_label(u) = u.name\n").unwrap();
+        std::fs::write(dir.join("models/post.aura"), "+json\n@Post {\n  title:s\n}\n").unwrap();
+        std::fs::write(dir.join("models/notes.txt"), "no es aura").unwrap();
+    }
+
+    #[test]
+    fn test_glob_import_merges_matching_files_in_path_order() {
+        let dir = tempfile::tempdir().unwrap();
+        write_models(dir.path());
+        std::fs::write(dir.path().join("main.aura"), "import \"models/*.aura\"\nmain = 1\n").unwrap();
+
+        let program = load_file(dir.path().join("main.aura")).unwrap();
+        // post.aura antes que user.aura, sin importar el orden del directorio
+        assert_eq!(function_names(&program), vec!["main", "Post", "User", "user_label"]);
+        assert!(program.capabilities.iter().any(|c| c.name == "json"));
+        assert!(!program.definitions.iter().any(|d| matches!(d, Definition::Import(_))));
+    }
+
+    #[test]
+    fn test_directory_import_and_selective_glob_import() {
+        let dir = tempfile::tempdir().unwrap();
+        write_models(dir.path());
+
+        std::fs::write(dir.path().join("main.aura"), "import \"models\"\nmain = 1\n").unwrap();
+        let program = load_file(dir.path().join("main.aura")).unwrap();
+        assert_eq!(function_names(&program), vec!["main", "Post", "User", "user_label"]);
+
+        std::fs::write(dir.path().join("main.aura"), "import User from \"models/*.aura\"\nmain = 1\n").unwrap();
+        let program = load_file(dir.path().join("main.aura")).unwrap();
+        assert_eq!(function_names(&program), vec!["main", "User"]);
+    }
+
+    #[test]
+    fn test_glob_import_reports_name_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+        write_models(dir.path());
+        std::fs::write(dir.path().join("models/admin.aura"), "@User {\n  role:s\n}\n").unwrap();
+        std::fs::write(dir.path().join("main.aura"), "main = 1\nimport \"models/*.aura\"\n").unwrap();
+
+        let error = load_file(dir.path().join("main.aura")).unwrap_err();
+        assert_eq!(
+            error.message,
+            "'User' esta definido en 'models/admin.aura' y en 'models/user.aura' (importados con 'models/*.aura')"
+        );
+        assert_eq!(error.kind, LoadErrorKind::Import);
+        assert_eq!(error.file.as_deref(), Some("main.aura"));
+        assert_eq!(error.span.map(|s| s.start), Some(9));
+    }
+
+    #[test]
+    fn test_glob_import_without_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.aura"), "import \"models/*.aura\"\nmain = 1\n").unwrap();
+
+        let error = load_file(dir.path().join("main.aura")).unwrap_err();
+        assert_eq!(error.message, "Ningun archivo coincide con 'models/*.aura'");
+        assert_eq!(error.kind, LoadErrorKind::Io);
+    }
+
+    #[test]
+    fn test_find_aura_files_recurses_and_skips_hidden() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sub")).unwrap();
+        std::fs::create_dir_all(dir.path().join(".aura")).unwrap();
+        std::fs::write(dir.path().join("main.aura"), "main = 1").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+        std::fs::write(dir.path().join("sub/lib.aura"), "x = 1").unwrap();
+        std::fs::write(dir.path().join(".aura/old.aura"), "x = 1").unwrap();
+
+        let files = find_aura_files(dir.path()).unwrap();
+        assert_eq!(files, vec![dir.path().join("main.aura"), dir.path().join("sub/lib.aura")]);
+    }
 }
@@ -1,5 +1,8 @@
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use aura::cli_output::exit_code;
 
 #[derive(Parser)]
 #[command(name = "aura")]
@@ -9,10 +12,30 @@ use std::path::PathBuf;
     All commands support --json flag for structured output.")]
 #[command(version)]
 struct Cli {
+    /// Suppress informational messages; errors, results and exit codes are unchanged
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Disable colored output (also disabled by NO_COLOR or when stdout is not a terminal)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Seed for crypto.random_bytes, so runs (and heal demos) are reproducible
+    #[arg(long, global = true, value_name = "N")]
+    seed: Option<u64>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// `--quiet`, fijado una vez en `main`
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Si hay que omitir los mensajes informativos (banners, progreso, confirmaciones)
+fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Execute an AURA file
@@ -28,11 +51,59 @@ enum Commands {
         #[arg(long, default_value = "mock")]
         provider: String,
 
+        /// Record each deliberation step (trigger, observations, decision)
+        #[arg(long, requires = "cognitive")]
+        trace: bool,
+
+        /// Abort after evaluating this many expressions (guards against runaway loops)
+        #[arg(long, conflicts_with = "cognitive")]
+        max_steps: Option<u64>,
+
+        /// Exit non-zero if a goal's `check` is not true after `main`
+        #[arg(long, conflicts_with = "cognitive")]
+        enforce_goals: bool,
+
+        /// Report call counts and total time per function
+        #[arg(long, conflicts_with = "cognitive")]
+        profile: bool,
+
+        /// Define a global variable before running `main` (repeatable): `--set count=3`
+        #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_set_arg, conflicts_with = "cognitive")]
+        set: Vec<(String, aura::Value)>,
+
+        /// Re-run whenever the file changes
+        #[arg(long)]
+        watch: bool,
+
         /// Output result as structured JSON (agent-friendly)
         #[arg(long, help = "Output structured JSON with result, type, and duration")]
         json: bool,
     },
 
+    /// Time repeated runs of an AURA file
+    Bench {
+        /// File to benchmark
+        file: PathBuf,
+
+        /// Number of times to run `main`
+        #[arg(short = 'n', long, default_value = "10")]
+        iterations: usize,
+
+        /// Output the full duration distribution as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Run the `#test name: expr` tests of an AURA file
+    Test {
+        /// File with tests
+        file: PathBuf,
+
+        /// Output pass/fail results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Self-healing demo: run file, detect errors, fix automatically
     Heal {
         /// File to heal and execute
@@ -76,11 +147,47 @@ enum Commands {
         /// File to check
         file: PathBuf,
 
+        /// Apply the suggested replacements to the file (snapshots it first)
+        #[arg(long)]
+        fix: bool,
+
+        /// Re-check whenever the file changes
+        #[arg(long, conflicts_with = "fix")]
+        watch: bool,
+
         /// Output result as structured JSON (agent-friendly)
         #[arg(long, help = "Output structured JSON with errors and warnings")]
         json: bool,
     },
 
+    /// Format a file in canonical AURA style
+    Fmt {
+        /// File to format
+        file: PathBuf,
+
+        /// Overwrite the file instead of printing the result
+        #[arg(long)]
+        write: bool,
+
+        /// Output result as structured JSON (agent-friendly)
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Compare two files structurally (functions and types)
+    #[command(name = "ast-diff", alias = "diff")]
+    Diff {
+        /// Previous version of the file
+        old: PathBuf,
+
+        /// New version of the file
+        new: PathBuf,
+
+        /// Output result as structured JSON (agent-friendly)
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Interactive REPL
     Repl,
 
@@ -91,6 +198,49 @@ enum Commands {
         json: bool,
     },
 
+    /// List identifiers in scope at a byte offset (editor/agent completions)
+    Complete {
+        /// File to complete in
+        file: PathBuf,
+
+        /// Byte offset of the cursor
+        offset: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the `#doc` documentation of functions and types
+    Docs {
+        /// File to document
+        file: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List every top-level definition with its span (outline view)
+    Symbols {
+        /// File to list
+        file: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Describe an error code (e.g. E501): causes and how to fix it
+    Explain {
+        /// Error code, or a category such as E3xx
+        code: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Start HTTP server
     Serve {
         /// AURA file with route definitions
@@ -100,6 +250,18 @@ enum Commands {
         #[arg(short, long, default_value = "8080")]
         port: u16,
 
+        /// Log every request (method, path, status, duration)
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Write one JSON event per request to stdout (method, path, params, status, duration, error)
+        #[arg(long)]
+        events: bool,
+
+        /// Append the JSON events to this file instead of stdout
+        #[arg(long, value_name = "FILE")]
+        events_file: Option<PathBuf>,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
@@ -115,6 +277,10 @@ enum Commands {
         #[arg(long)]
         to: Option<String>,
 
+        /// Show which files would change, without writing them
+        #[arg(long)]
+        dry_run: bool,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
@@ -139,6 +305,26 @@ enum Commands {
         #[arg(long, global = true)]
         json: bool,
     },
+
+    /// Show the history of healing attempts (.aura/healing_log.jsonl)
+    #[command(name = "heal-log")]
+    HealLog {
+        /// Show only the last N attempts
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+
+        /// Filter by outcome (fixed, suggested, needs_human, cannot_fix, error)
+        #[arg(long)]
+        outcome: Option<String>,
+
+        /// Filter by healed file
+        #[arg(long)]
+        file: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -162,6 +348,20 @@ enum SnapshotsAction {
         /// Snapshot ID to restore
         id: String,
 
+        /// Show which files would change, without writing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check whether files on disk drifted from a snapshot
+    Verify {
+        /// Snapshot ID to verify
+        id: String,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
@@ -203,6 +403,34 @@ enum MemoryAction {
         json: bool,
     },
 
+    /// Export patterns and defaults to a portable JSON file
+    Export {
+        /// Destination file
+        path: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Import patterns and defaults from an exported file
+    Import {
+        /// File created with `aura memory export`
+        path: PathBuf,
+
+        /// Sum usage counts of patterns present in both memories instead of overwriting
+        #[arg(long)]
+        merge: bool,
+
+        /// Overwrite project defaults that have a different local value
+        #[arg(long)]
+        force: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Show or set project defaults
     Defaults {
         /// Set a default value (format: key=value)
@@ -223,16 +451,40 @@ fn main() {
     // Load environment variables from .env file if present
     aura::caps::load_dotenv();
 
-    let cli = Cli::parse();
+    // clap sale con 2 ante argumentos invalidos; 2 es PARSE, asi que se usa FAILURE
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) if e.use_stderr() => {
+            let _ = e.print();
+            std::process::exit(exit_code::FAILURE);
+        }
+        Err(e) => e.exit(),
+    };
+    QUIET.store(cli.quiet, Ordering::Relaxed);
+    if cli.no_color {
+        aura::error::disable_color();
+    }
+    if let Some(seed) = cli.seed {
+        aura::caps::crypto::set_random_seed(seed);
+    }
 
     match cli.command {
-        Commands::Run { file, cognitive, provider, json } => {
+        Commands::Run { file, watch: true, .. } | Commands::Check { file, watch: true, .. } => {
+            watch_file(&file);
+        }
+        Commands::Run { file, cognitive, provider, trace, max_steps, enforce_goals, profile, set, watch: _, json } => {
             if cognitive {
-                run_file_cognitive(&file, &provider, json);
+                run_file_cognitive(&file, &provider, trace, json);
             } else {
-                run_file(&file, json);
+                run_file(&file, max_steps, enforce_goals, profile, set, json);
             }
         }
+        Commands::Bench { file, iterations, json } => {
+            bench_file(&file, iterations, json);
+        }
+        Commands::Test { file, json } => {
+            test_file(&file, json);
+        }
         Commands::Heal { file, provider, apply, json } => {
             heal_file(&file, &provider, apply, json);
         }
@@ -242,8 +494,14 @@ fn main() {
         Commands::Parse { file, json } => {
             parse_file(&file, json);
         }
-        Commands::Check { file, json } => {
-            check_file(&file, json);
+        Commands::Check { file, fix, watch: _, json } => {
+            check_file(&file, fix, json);
+        }
+        Commands::Fmt { file, write, json } => {
+            fmt_file(&file, write, json);
+        }
+        Commands::Diff { old, new, json } => {
+            diff_files(&old, &new, json);
         }
         Commands::Repl => {
             run_repl();
@@ -251,11 +509,23 @@ fn main() {
         Commands::Info { json } => {
             show_info(json);
         }
-        Commands::Serve { file, port, json } => {
-            serve_file(&file, port, json);
+        Commands::Complete { file, offset, json } => {
+            complete_at(&file, offset, json);
+        }
+        Commands::Docs { file, json } => {
+            show_docs(&file, json);
+        }
+        Commands::Symbols { file, json } => {
+            list_symbols(&file, json);
+        }
+        Commands::Explain { code, json } => {
+            explain_code(&code, json);
         }
-        Commands::Undo { list, to, json } => {
-            handle_undo(list, to, json);
+        Commands::Serve { file, port, verbose, events, events_file, json } => {
+            serve_file(&file, port, verbose, events, events_file.as_deref(), json);
+        }
+        Commands::Undo { list, to, dry_run, json } => {
+            handle_undo(list, to, dry_run, json);
         }
         Commands::Snapshots { action, json } => {
             handle_snapshots(action, json);
@@ -263,11 +533,61 @@ fn main() {
         Commands::Memory { action, json } => {
             handle_memory(action, json);
         }
+        Commands::HealLog { limit, outcome, file, json } => {
+            show_heal_log(limit, outcome, file, json);
+        }
     }
 }
 
-fn run_file(path: &PathBuf, json_output: bool) {
-    use aura::cli_output::{JsonError, RunResult, value_to_json};
+/// `--watch`: repite el mismo comando (sin `--watch`) en un proceso hijo cada
+/// vez que el archivo cambia. Asi los `exit` de `run_file`/`check_file` solo
+/// terminan esa corrida y el observador sigue vivo ante errores.
+fn watch_file(path: &std::path::Path) {
+    use std::process::Command;
+
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            eprintln!("Error: no se pudo ubicar el ejecutable: {}", e);
+            std::process::exit(exit_code::IO);
+        }
+    };
+    let args: Vec<_> = std::env::args_os().skip(1).filter(|a| a != "--watch").collect();
+
+    let run_once = |clear: bool| {
+        // Limpiar pantalla y la marca de tiempo van a stderr para no mezclarse con --json
+        if clear {
+            eprint!("\x1b[2J\x1b[H");
+        }
+        let status = Command::new(&exe).args(&args).status();
+        let time = chrono::Local::now().format("%H:%M:%S");
+        match status {
+            Ok(status) if status.success() => eprintln!("[{}] ok", time),
+            Ok(status) => match status.code() {
+                Some(code) => eprintln!("[{}] exit {}", time, code),
+                None => eprintln!("[{}] interrumpido", time),
+            },
+            Err(e) => eprintln!("[{}] Error: {}", time, e),
+        }
+        if !quiet() {
+            eprintln!("Observando {} (Ctrl+C para salir)", path.display());
+        }
+        true
+    };
+
+    run_once(false);
+    aura::reload::watch::watch_file(path, || run_once(true));
+}
+
+fn run_file(
+    path: &PathBuf,
+    max_steps: Option<u64>,
+    enforce_goals: bool,
+    profile: bool,
+    globals: Vec<(String, aura::Value)>,
+    json_output: bool,
+) {
+    use aura::cli_output::{JsonError, JsonObservation, RunResult, value_to_json};
     use aura::loader;
     use std::time::Instant;
 
@@ -276,44 +596,255 @@ fn run_file(path: &PathBuf, json_output: bool) {
         Ok(p) => p,
         Err(e) => {
             if json_output {
-                let result = RunResult::failure(JsonError::new("E001", &e.message));
+                let result = RunResult::failure(JsonError::from_load_error(&e));
                 println!("{}", result.to_json());
             } else {
                 eprintln!("Error: {}", e);
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::for_load_error(&e));
         }
     };
 
-    // Execute with timing
+    // Execute with timing; with --json `print` goes into the result instead of stdout
     let mut vm = aura::vm::VM::new();
+    if json_output {
+        vm = vm.with_captured_stdout();
+    }
+    if profile {
+        vm = vm.with_profiling();
+    }
     vm.load(&program);
+    for (name, value) in globals {
+        vm.define_var(name, value);
+    }
 
     let start = Instant::now();
-    match vm.run() {
+    let outcome = match max_steps {
+        Some(max_steps) => vm.run_with_budget(max_steps),
+        None => vm.run(),
+    };
+
+    // El perfil sale tambien si la ejecucion fallo: puede explicar el fallo
+    let profile = profile.then(|| vm.take_profile());
+    if let (Some(profile), false) = (&profile, json_output) {
+        print_profile(profile);
+    }
+    let warnings = vm.take_warnings();
+    if !json_output {
+        for warning in &warnings {
+            eprintln!("Aviso: {}", warning);
+        }
+    }
+
+    match outcome {
         Ok(result) => {
             let duration_ms = start.elapsed().as_millis() as u64;
+            let goals = vm.check_goals();
+            let goals_failed = enforce_goals && goals.iter().any(|g| !g.passed);
             if json_output {
-                let (json_value, type_name) = value_to_json(&result);
-                let run_result = RunResult::success(json_value, type_name, duration_ms);
+                let observations = vm.take_observations().iter().filter_map(JsonObservation::from_event).collect();
+                let run_result = if goals_failed {
+                    RunResult::failure(JsonError::from_failed_goals(&goals))
+                } else {
+                    let (json_value, type_name) = value_to_json(&result);
+                    RunResult::success(json_value, type_name, duration_ms)
+                };
+                let run_result = run_result
+                    .with_observations(observations)
+                    .with_stdout(vm.take_stdout())
+                    .with_goals(&goals)
+                    .with_profile(profile)
+                    .with_warnings(warnings);
                 println!("{}", run_result.to_json());
             } else {
                 println!("{}", result);
+                for goal in &goals {
+                    if goal.passed {
+                        if !quiet() {
+                            eprintln!("Goal cumplido: {}", goal.description);
+                        }
+                    } else {
+                        let detail = match (&goal.value, &goal.error) {
+                            (_, Some(error)) => format!("error: {}", error),
+                            (Some(value), None) => format!("valor: {}", value),
+                            (None, None) => String::new(),
+                        };
+                        eprintln!("Goal no cumplido: {} ({})", goal.description, detail);
+                    }
+                }
+            }
+            if goals_failed {
+                std::process::exit(exit_code::RUNTIME);
             }
         }
         Err(e) => {
+            // Un `expect` que falla corta la ejecucion con su propio codigo
+            let error = if vm.has_failed_expectations() {
+                JsonError::from_failed_expectations(&e)
+            } else {
+                JsonError::from_runtime_error(&e)
+            };
             if json_output {
-                let result = RunResult::failure(JsonError::from_runtime_error(&e));
+                let result = RunResult::failure(error)
+                    .with_stdout(vm.take_stdout())
+                    .with_profile(profile)
+                    .with_warnings(warnings);
                 println!("{}", result.to_json());
             } else {
                 eprintln!("Runtime error: {}", e.message);
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::RUNTIME);
+        }
+    }
+}
+
+/// `--set clave=valor`: la clave debe ser un identificador; el valor se tipa con `Value::parse_scalar`
+fn parse_set_arg(arg: &str) -> Result<(String, aura::Value), String> {
+    let (key, value) = arg.split_once('=')
+        .ok_or_else(|| format!("se esperaba clave=valor, no '{}'", arg))?;
+    let valid = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("'{}' no es un nombre de variable valido", key));
+    }
+    Ok((key.to_string(), aura::Value::parse_scalar(value)))
+}
+
+/// Tabla de `--profile` en stderr, para no mezclarla con el resultado
+fn print_profile(profile: &[aura::FunctionProfile]) {
+    eprintln!("{:>10} {:>12}  funcion", "llamadas", "total (ms)");
+    for entry in profile {
+        eprintln!("{:>10} {:>12.3}  {}", entry.calls, entry.total_us as f64 / 1000.0, entry.name);
+    }
+}
+
+/// Corre `main` N veces sobre el mismo programa parseado. El VM se resetea
+/// antes de cada corrida para que el estado de una no afecte a la siguiente.
+fn bench_file(path: &PathBuf, iterations: usize, json_output: bool) {
+    use aura::cli_output::{BenchResult, JsonError};
+    use aura::loader;
+    use std::time::Instant;
+
+    let file_name = path.display().to_string();
+
+    if iterations == 0 {
+        let message = "--iterations debe ser al menos 1";
+        if json_output {
+            let result = BenchResult::failure(&file_name, Vec::new(), JsonError::new("E001", message));
+            println!("{}", result.to_json());
+        } else {
+            eprintln!("Error: {}", message);
+        }
+        std::process::exit(exit_code::FAILURE);
+    }
+
+    let program = match loader::load_file(path) {
+        Ok(p) => p,
+        Err(e) => {
+            if json_output {
+                let result = BenchResult::failure(&file_name, Vec::new(), JsonError::from_load_error(&e));
+                println!("{}", result.to_json());
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            std::process::exit(exit_code::for_load_error(&e));
+        }
+    };
+
+    let mut vm = aura::vm::VM::new();
+    let mut runs_us = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        vm.reset();
+        vm.load(&program);
+
+        let start = Instant::now();
+        let outcome = vm.run();
+        let elapsed = start.elapsed().as_micros() as u64;
+
+        if let Err(e) = outcome {
+            if json_output {
+                let result = BenchResult::failure(&file_name, runs_us, JsonError::from_runtime_error(&e));
+                println!("{}", result.to_json());
+            } else {
+                eprintln!("Runtime error (run {}): {}", runs_us.len() + 1, e.message);
+            }
+            std::process::exit(exit_code::RUNTIME);
+        }
+        runs_us.push(elapsed);
+    }
+
+    let result = BenchResult::success(&file_name, runs_us);
+    if json_output {
+        println!("{}", result.to_json());
+    } else {
+        let ms = |us: u64| us as f64 / 1000.0;
+        println!("{}: {} runs", result.file, result.iterations);
+        println!("  min     {:>10.3}ms", ms(result.min_us));
+        println!("  mean    {:>10.3}ms", ms(result.mean_us));
+        println!("  median  {:>10.3}ms", ms(result.median_us));
+        println!("  p95     {:>10.3}ms", ms(result.p95_us));
+        println!("  max     {:>10.3}ms", ms(result.max_us));
+    }
+}
+
+fn test_file(path: &PathBuf, json_output: bool) {
+    use aura::cli_output::{JsonError, TestResult};
+    use aura::loader;
+
+    let file_name = path.display().to_string();
+
+    let program = match loader::load_file(path) {
+        Ok(p) => p,
+        Err(e) => {
+            if json_output {
+                let result = TestResult::failure(&file_name, vec![JsonError::from_load_error(&e)]);
+                println!("{}", result.to_json());
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            std::process::exit(exit_code::for_load_error(&e));
+        }
+    };
+
+    let tests: Vec<aura::parser::TestDef> = program.definitions.iter().filter_map(|d| {
+        if let aura::Definition::TestDef(t) = d { Some(t.clone()) } else { None }
+    }).collect();
+
+    let mut vm = aura::vm::VM::new();
+    vm.load(&program);
+    let outcomes = vm.run_tests(&tests);
+    let result = TestResult::new(&file_name, &outcomes);
+
+    if json_output {
+        println!("{}", result.to_json());
+    } else {
+        for outcome in &outcomes {
+            if outcome.passed {
+                if !quiet() {
+                    println!("PASS {}", outcome.name);
+                }
+                continue;
+            }
+            match (&outcome.error, &outcome.value, &outcome.expected) {
+                (Some(error), _, _) => println!("FAIL {}: {}", outcome.name, error),
+                (None, Some(value), Some(expected)) => {
+                    println!("FAIL {}: esperado {}, obtenido {}", outcome.name, expected, value)
+                }
+                (None, Some(value), None) => println!("FAIL {}: resultado {}", outcome.name, value),
+                _ => println!("FAIL {}", outcome.name),
+            }
+        }
+        if !quiet() {
+            println!("{} tests: {} passed, {} failed", outcomes.len(), result.passed, result.failed);
         }
     }
+
+    if !result.success {
+        std::process::exit(exit_code::FAILURE);
+    }
 }
 
-fn run_file_cognitive(path: &PathBuf, provider: &str, json_output: bool) {
+fn run_file_cognitive(path: &PathBuf, provider: &str, trace: bool, json_output: bool) {
     use aura::cli_output::{JsonError, RunResult, value_to_json};
     use aura::loader;
     use std::time::Instant;
@@ -323,12 +854,12 @@ fn run_file_cognitive(path: &PathBuf, provider: &str, json_output: bool) {
         Ok(s) => s,
         Err(e) => {
             if json_output {
-                let result = RunResult::failure(JsonError::new("E001", &format!("Error reading file: {}", e)));
+                let result = RunResult::failure(JsonError::file_error(format!("Error reading file: {}", e)));
                 println!("{}", result.to_json());
             } else {
                 eprintln!("Error reading file: {}", e);
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::IO);
         }
     };
 
@@ -337,12 +868,12 @@ fn run_file_cognitive(path: &PathBuf, provider: &str, json_output: bool) {
         Ok(p) => p,
         Err(e) => {
             if json_output {
-                let result = RunResult::failure(JsonError::new("E001", &e.message));
+                let result = RunResult::failure(JsonError::from_load_error(&e));
                 println!("{}", result.to_json());
             } else {
                 eprintln!("Error: {}", e);
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::for_load_error(&e));
         }
     };
 
@@ -352,7 +883,7 @@ fn run_file_cognitive(path: &PathBuf, provider: &str, json_output: bool) {
     }).collect();
 
     let invariants: Vec<String> = program.definitions.iter().filter_map(|d| {
-        if let aura::Definition::Invariant(expr) = d { Some(format!("{:?}", expr)) } else { None }
+        if let aura::Definition::Invariant(inv) = d { Some(format!("{:?}", inv.expr)) } else { None }
     }).collect();
 
     // Create the cognitive runtime based on provider
@@ -362,28 +893,31 @@ fn run_file_cognitive(path: &PathBuf, provider: &str, json_output: bool) {
     let cognitive: Box<dyn aura::CognitiveRuntime> = match provider {
         "mock" => {
             let mock = aura::agent::MockProvider::new().with_latency(0);
-            Box::new(aura::AgentCognitiveRuntime::new(
+            let runtime = aura::AgentCognitiveRuntime::new(
                 mock, handle, goals, invariants, source.clone(),
-            ))
+            );
+            Box::new(if trace { runtime.with_trace() } else { runtime })
         }
         #[cfg(feature = "claude-api")]
         "claude" => {
             let claude = aura::agent::ClaudeProvider::from_env()
                 .expect("ANTHROPIC_API_KEY must be set for claude provider");
-            Box::new(aura::AgentCognitiveRuntime::new(
+            let runtime = aura::AgentCognitiveRuntime::new(
                 claude, handle, goals, invariants, source.clone(),
-            ))
+            );
+            Box::new(if trace { runtime.with_trace() } else { runtime })
         }
         #[cfg(feature = "ollama")]
         "ollama" => {
             let ollama = aura::agent::OllamaProvider::default();
-            Box::new(aura::AgentCognitiveRuntime::new(
+            let runtime = aura::AgentCognitiveRuntime::new(
                 ollama, handle, goals, invariants, source.clone(),
-            ))
+            );
+            Box::new(if trace { runtime.with_trace() } else { runtime })
         }
         other => {
             if json_output {
-                let result = RunResult::failure(JsonError::new("E002", &format!("Unknown provider: {}", other)));
+                let result = RunResult::failure(JsonError::new("E002", format!("Unknown provider: {}", other)));
                 println!("{}", result.to_json());
             } else {
                 eprintln!("Unknown provider: {}. Available: mock", other);
@@ -392,11 +926,11 @@ fn run_file_cognitive(path: &PathBuf, provider: &str, json_output: bool) {
                 #[cfg(feature = "ollama")]
                 eprintln!("  ollama");
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::FAILURE);
         }
     };
 
-    if !json_output {
+    if !json_output && !quiet() {
         eprintln!("Cognitive mode: provider={}", provider);
     }
 
@@ -406,13 +940,25 @@ fn run_file_cognitive(path: &PathBuf, provider: &str, json_output: bool) {
             let duration_ms = start.elapsed().as_millis() as u64;
             if json_output {
                 let (json_value, type_name) = value_to_json(&result.value);
-                let run_result = RunResult::success(json_value, type_name, duration_ms);
+                let mut run_result = RunResult::success(json_value, type_name, duration_ms);
+                if trace {
+                    run_result = run_result.with_trace(result.trace);
+                }
                 println!("{}", run_result.to_json());
             } else {
                 println!("{}", result.value);
-                if !result.applied_fixes.is_empty() {
+                if !result.applied_fixes.is_empty() && !quiet() {
                     eprintln!("  [{} fix(es) applied, {} retries]", result.applied_fixes.len(), result.retries);
                 }
+                for entry in &result.trace {
+                    eprintln!("  [trace {}] {} -> {}", entry.step, entry.trigger, entry.decision);
+                    for obs in &entry.observations {
+                        eprintln!("      observed: {}", obs);
+                    }
+                    if !entry.decision_detail.is_empty() {
+                        eprintln!("      detail: {}", entry.decision_detail);
+                    }
+                }
             }
         }
         Err(e) => {
@@ -422,7 +968,7 @@ fn run_file_cognitive(path: &PathBuf, provider: &str, json_output: bool) {
             } else {
                 eprintln!("Runtime error: {}", e.message);
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::RUNTIME);
         }
     }
 }
@@ -432,7 +978,7 @@ fn lex_file(path: &PathBuf, json: bool) {
         Ok(s) => s,
         Err(e) => {
             eprintln!("Error leyendo archivo: {}", e);
-            std::process::exit(1);
+            std::process::exit(exit_code::IO);
         }
     };
 
@@ -447,31 +993,41 @@ fn lex_file(path: &PathBuf, json: bool) {
             }
             Err(errors) => {
                 for e in errors {
-                    eprintln!("Error [{}-{}]: {}", e.span.start, e.span.end, e.message);
+                    let (line, col) = e.line_col(&source);
+                    eprintln!("error at line {} col {}: {}", line, col, e.message);
                 }
-                std::process::exit(1);
+                std::process::exit(exit_code::PARSE);
             }
         }
     }
 }
 
 fn parse_file(path: &PathBuf, json: bool) {
+    use aura::cli_output::{JsonError, ParseResult};
     use aura::loader;
 
+    let filename = path.display().to_string();
+
     // Load file with imports resolved
     let program = match loader::load_file(path) {
         Ok(p) => p,
         Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+            if json {
+                println!("{}", ParseResult::failure(&filename, vec![JsonError::from_load_error(&e)]).to_json());
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            std::process::exit(exit_code::for_load_error(&e));
         }
     };
 
     if json {
-        println!("{}", serde_json::to_string_pretty(&program).unwrap_or_default());
+        println!("{}", ParseResult::success(&filename, program).to_json());
     } else {
-        println!("Programa parseado exitosamente:");
-        println!();
+        if !quiet() {
+            println!("Programa parseado exitosamente:");
+            println!();
+        }
         println!("Capacidades: {:?}", program.capabilities.iter().map(|c| &c.name).collect::<Vec<_>>());
         println!();
         for def in &program.definitions {
@@ -498,78 +1054,514 @@ fn parse_file(path: &PathBuf, json: bool) {
     }
 }
 
-fn check_file(path: &PathBuf, json_output: bool) {
-    use aura::cli_output::{CheckResult, JsonError};
-    use aura::loader;
+fn complete_at(path: &PathBuf, offset: usize, json_output: bool) {
+    use aura::cli_output::{CompleteResult, JsonError};
+    use aura::types::complete::{complete, prefix_at};
 
     let filename = path.display().to_string();
 
-    // Read source for error reporting
+    let fail = |error: JsonError, code: i32| -> ! {
+        if json_output {
+            println!("{}", CompleteResult::failure(&filename, offset, vec![error]).to_json());
+        } else {
+            eprintln!("Error: {}", error.message);
+        }
+        std::process::exit(code);
+    };
+
     let source = match std::fs::read_to_string(path) {
         Ok(s) => s,
+        Err(e) => fail(JsonError::file_error(format!("Error reading file: {}", e)), exit_code::IO),
+    };
+    if offset > source.len() || !source.is_char_boundary(offset) {
+        fail(
+            JsonError::new("E002", format!("Offset {} fuera del archivo ({} bytes)", offset, source.len())),
+            exit_code::FAILURE,
+        );
+    }
+    let program = match aura::loader::load_file(path) {
+        Ok(p) => p,
+        Err(e) => fail(JsonError::from_load_error(&e), exit_code::for_load_error(&e)),
+    };
+
+    let candidates = complete(&program, &source, offset);
+    if json_output {
+        let result = CompleteResult::success(&filename, offset, prefix_at(&source, offset), candidates);
+        println!("{}", result.to_json());
+    } else {
+        for candidate in candidates {
+            let kind = serde_json::to_value(candidate.kind).ok()
+                .and_then(|v| v.as_str().map(String::from))
+                .unwrap_or_default();
+            println!("{:<24} {}", candidate.name, kind);
+        }
+    }
+}
+
+/// Se parsea solo el archivo (sin resolver imports) para que todos los spans
+/// sean de este fuente; los `import` aparecen como simbolos propios.
+fn list_symbols(path: &PathBuf, json_output: bool) {
+    use aura::cli_output::{JsonError, SymbolsResult};
+
+    let filename = path.display().to_string();
+
+    let fail = |errors: Vec<JsonError>, code: i32| -> ! {
+        if json_output {
+            println!("{}", SymbolsResult::failure(&filename, errors).to_json());
+        } else {
+            for e in errors {
+                eprintln!("Error: {}", e.message);
+            }
+        }
+        std::process::exit(code);
+    };
+
+    let source = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => fail(vec![JsonError::file_error(format!("Error reading file: {}", e))], exit_code::IO),
+    };
+    let tokens = match aura::tokenize(&source) {
+        Ok(t) => t,
+        Err(errors) => fail(errors.iter().map(|e| JsonError::from_lex_error(e, &source)).collect(), exit_code::PARSE),
+    };
+    let program = match aura::parse(tokens) {
+        Ok(p) => p,
+        Err(errors) => fail(errors.iter().map(|e| JsonError::from_parse_error(e, &source)).collect(), exit_code::PARSE),
+    };
+
+    let result = SymbolsResult::success(&filename, &program, &source);
+    if json_output {
+        println!("{}", result.to_json());
+    } else {
+        for symbol in &result.symbols {
+            println!(
+                "{:>4}:{:<3} {:<10} {}",
+                symbol.start.line, symbol.start.col, symbol.kind, symbol.name
+            );
+        }
+    }
+}
+
+/// Como `symbols`, solo el archivo: los `#doc` se asocian por posicion en este fuente
+fn show_docs(path: &PathBuf, json_output: bool) {
+    use aura::cli_output::{DocsResult, JsonError};
+
+    let filename = path.display().to_string();
+
+    let fail = |errors: Vec<JsonError>, code: i32| -> ! {
+        if json_output {
+            println!("{}", DocsResult::failure(&filename, errors).to_json());
+        } else {
+            for e in errors {
+                eprintln!("Error: {}", e.message);
+            }
+        }
+        std::process::exit(code);
+    };
+
+    let source = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => fail(vec![JsonError::file_error(format!("Error reading file: {}", e))], exit_code::IO),
+    };
+    let tokens = match aura::tokenize(&source) {
+        Ok(t) => t,
+        Err(errors) => fail(errors.iter().map(|e| JsonError::from_lex_error(e, &source)).collect(), exit_code::PARSE),
+    };
+    let program = match aura::parse(tokens.clone()) {
+        Ok(p) => p,
+        Err(errors) => fail(errors.iter().map(|e| JsonError::from_parse_error(e, &source)).collect(), exit_code::PARSE),
+    };
+
+    let docs = aura::docs::extract_docs(&tokens, &program);
+    if json_output {
+        println!("{}", DocsResult::success(&filename, docs).to_json());
+        return;
+    }
+
+    for (i, entry) in docs.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}", entry.signature);
+        if let Some(doc) = &entry.doc {
+            for line in doc.lines() {
+                println!("    {}", line);
+            }
+        }
+        for field in &entry.fields {
+            match &field.doc {
+                Some(doc) => println!("    {} {}  # {}", field.name, field.ty, doc.replace('\n', " ")),
+                None => println!("    {} {}", field.name, field.ty),
+            }
+        }
+    }
+}
+
+fn fmt_file(path: &PathBuf, write: bool, json_output: bool) {
+    use aura::cli_output::{FmtResult, JsonError, JsonLocation};
+
+    let filename = path.display().to_string();
+
+    let fail = |errors: Vec<JsonError>, code: i32| -> ! {
+        if json_output {
+            println!("{}", FmtResult::failure(&filename, errors).to_json());
+        } else {
+            for e in errors {
+                eprintln!("Error: {}", e.message);
+            }
+        }
+        std::process::exit(code);
+    };
+
+    let source = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => fail(vec![JsonError::file_error(format!("Error reading file: {}", e))], exit_code::IO),
+    };
+
+    // Se formatea el archivo tal cual, sin resolver imports
+    let tokens = match aura::tokenize(&source) {
+        Ok(t) => t,
+        Err(errors) => fail(errors.iter().map(|e| JsonError::from_lex_error(e, &source)).collect(), exit_code::PARSE),
+    };
+    let program = match aura::parse(tokens) {
+        Ok(p) => p,
+        Err(errors) => fail(errors.iter().map(|e| JsonError::from_parse_error(e, &source)).collect(), exit_code::PARSE),
+    };
+
+    // Con --write no se reescribe un archivo si se perderia algun comentario
+    let (formatted, warning) = match aura::fmt::format_with_comments(&program, &source) {
+        Ok(formatted) => (formatted, None),
+        Err(comment) => {
+            let message = format!("No se puede conservar el comentario '{}' al formatear", comment.text);
+            if write {
+                fail(
+                    vec![JsonError::new("E003", message)
+                        .with_location(JsonLocation::from_offset(&source, comment.span.start))],
+                    exit_code::FAILURE,
+                );
+            }
+            (aura::fmt::format(&program), Some(message))
+        }
+    };
+    let changed = formatted != source;
+
+    if write && changed
+        && let Err(e) = std::fs::write(path, &formatted) {
+        fail(vec![JsonError::file_error(format!("Error writing file: {}", e))], exit_code::IO);
+    }
+
+    if json_output {
+        let output = if write { None } else { Some(formatted) };
+        let mut result = FmtResult::success(&filename, output, changed, write && changed);
+        if let Some(w) = warning {
+            result = result.with_warning(w);
+        }
+        println!("{}", result.to_json());
+    } else {
+        if let Some(w) = warning {
+            eprintln!("Aviso: {}", w);
+        }
+        if !write {
+            print!("{}", formatted);
+        } else if !quiet() {
+            if changed {
+                println!("Formateado: {}", filename);
+            } else {
+                println!("Sin cambios: {}", filename);
+            }
+        }
+    }
+}
+
+fn diff_files(old_path: &PathBuf, new_path: &PathBuf, json_output: bool) {
+    use aura::cli_output::{DiffResult, DiffSection, JsonError, ModifiedDefinition};
+    use aura::reload::{compute_program_diff, function_changes, type_changes};
+
+    let old_name = old_path.display().to_string();
+    let new_name = new_path.display().to_string();
+
+    // Se comparan los archivos tal cual, sin resolver imports
+    let load = |path: &PathBuf| -> Result<aura::Program, (Vec<JsonError>, i32)> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            (vec![JsonError::file_error(format!("Error reading '{}': {}", path.display(), e))], exit_code::IO)
+        })?;
+        let tokens = aura::tokenize(&source).map_err(|errors| {
+            (errors.iter().map(|e| JsonError::from_lex_error(e, &source)).collect::<Vec<_>>(), exit_code::PARSE)
+        })?;
+        aura::parse(tokens).map_err(|errors| {
+            (errors.iter().map(|e| JsonError::from_parse_error(e, &source)).collect(), exit_code::PARSE)
+        })
+    };
+
+    let (old_program, new_program) = match (load(old_path), load(new_path)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (old, new) => {
+            let failures: Vec<(Vec<JsonError>, i32)> = [old.err(), new.err()].into_iter().flatten().collect();
+            // Un archivo que no se pudo leer pesa mas que uno que no parsea
+            let code = failures.iter().map(|(_, code)| *code).max().unwrap_or(exit_code::FAILURE);
+            let errors: Vec<JsonError> = failures.into_iter().flat_map(|(errors, _)| errors).collect();
+            if json_output {
+                println!("{}", DiffResult::failure(&old_name, &new_name, errors).to_json());
+            } else {
+                for e in errors {
+                    eprintln!("Error: {}", e.message);
+                }
+            }
+            std::process::exit(code);
+        }
+    };
+
+    let diff = compute_program_diff(&old_program, &new_program);
+
+    let old_functions: std::collections::HashMap<&str, &aura::FuncDef> = old_program.definitions.iter()
+        .filter_map(|d| match d {
+            aura::Definition::FuncDef(f) => Some((f.name.as_str(), f)),
+            _ => None,
+        })
+        .collect();
+    let old_types: std::collections::HashMap<&str, &aura::parser::TypeDef> = old_program.definitions.iter()
+        .filter_map(|d| match d {
+            aura::Definition::TypeDef(t) => Some((t.name.as_str(), t)),
+            _ => None,
+        })
+        .collect();
+
+    let functions = DiffSection {
+        added: diff.added_functions.iter().map(|f| f.name.clone()).collect(),
+        removed: diff.removed_functions.clone(),
+        modified: diff.modified_functions.iter()
+            .map(|f| ModifiedDefinition {
+                name: f.name.clone(),
+                reasons: old_functions.get(f.name.as_str())
+                    .map(|old| function_changes(old, f).into_iter().map(String::from).collect())
+                    .unwrap_or_default(),
+            })
+            .collect(),
+    };
+    let types = DiffSection {
+        added: diff.added_types.iter().map(|t| t.name.clone()).collect(),
+        removed: diff.removed_types.clone(),
+        modified: diff.modified_types.iter()
+            .map(|t| ModifiedDefinition {
+                name: t.name.clone(),
+                reasons: old_types.get(t.name.as_str())
+                    .map(|old| type_changes(old, t).into_iter().map(String::from).collect())
+                    .unwrap_or_default(),
+            })
+            .collect(),
+    };
+
+    let result = DiffResult::success(&old_name, &new_name, functions, types);
+
+    if json_output {
+        println!("{}", result.to_json());
+        return;
+    }
+
+    if result.total_changes == 0 {
+        println!("Sin cambios estructurales");
+        return;
+    }
+
+    for (title, section) in [("Funciones", &result.functions), ("Tipos", &result.types)] {
+        if section.is_empty() {
+            continue;
+        }
+        println!("{}:", title);
+        for name in &section.added {
+            println!("  + {}", name);
+        }
+        for name in &section.removed {
+            println!("  - {}", name);
+        }
+        for m in &section.modified {
+            println!("  ~ {} ({})", m.name, m.reasons.join(", "));
+        }
+    }
+    println!();
+    println!("{} cambios", result.total_changes);
+}
+
+fn check_file(path: &PathBuf, fix: bool, json_output: bool) {
+    if path.is_dir() {
+        check_dir(path, fix, json_output);
+        return;
+    }
+
+    let (result, code) = check_one(path, fix, json_output);
+    if json_output {
+        println!("{}", result.to_json());
+    }
+    if code != exit_code::SUCCESS {
+        std::process::exit(code);
+    }
+}
+
+/// `check` sobre un directorio: verifica cada `.aura` (recursivo) sin
+/// detenerse en el primero que falle. Con `--json` emite un array con el
+/// resultado de cada archivo; el exit code es el del peor error.
+fn check_dir(dir: &PathBuf, fix: bool, json_output: bool) {
+    use aura::cli_output::{CheckDirResult, JsonError};
+
+    let files = match aura::loader::find_aura_files(dir) {
+        Ok(files) => files,
         Err(e) => {
             if json_output {
-                let result = CheckResult::failure(&filename, vec![
-                    JsonError::file_error(format!("Error reading file: {}", e))
+                let result = CheckDirResult::failure(dir.display().to_string(), vec![
+                    JsonError::file_error(format!("Error reading directory: {}", e))
                 ]);
                 println!("{}", result.to_json());
             } else {
+                eprintln!("Error reading directory: {}", e);
+            }
+            std::process::exit(exit_code::IO);
+        }
+    };
+
+    let mut results = Vec::new();
+    let mut worst = exit_code::SUCCESS;
+    for file in &files {
+        if !json_output {
+            println!("{}:", file.display());
+        }
+        let (result, code) = check_one(file, fix, json_output);
+        results.push(result);
+        worst = worst.max(code);
+    }
+
+    if json_output {
+        println!("{}", CheckDirResult::new(dir.display().to_string(), results).to_json());
+    } else if !quiet() {
+        let failed = results.iter().filter(|r| !r.success).count();
+        println!("{} file(s) checked, {} with errors", results.len(), failed);
+    }
+    if worst != exit_code::SUCCESS {
+        std::process::exit(worst);
+    }
+}
+
+/// Verifica un archivo. En modo humano imprime el resultado; con `--json`
+/// solo lo retorna, para que el llamador lo emita solo o dentro de `check_dir`.
+fn check_one(path: &PathBuf, fix: bool, json_output: bool) -> (aura::cli_output::CheckResult, i32) {
+    use aura::cli_output::{CheckResult, JsonError};
+    use aura::loader;
+
+    let filename = path.display().to_string();
+
+    // Read source for error reporting
+    let mut source = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => {
+            if !json_output {
                 eprintln!("Error reading file: {}", e);
             }
-            std::process::exit(1);
+            let result = CheckResult::failure(&filename, vec![
+                JsonError::file_error(format!("Error reading file: {}", e))
+            ]);
+            return (result, exit_code::IO);
         }
     };
 
     // Load file with imports resolved
-    let program = match loader::load_file(path) {
+    let load_failed = |e: loader::LoadError| {
+        if !json_output {
+            eprintln!("Error: {}", e);
+        }
+        let result = CheckResult::failure(&filename, vec![JsonError::from_load_error(&e)]);
+        (result, exit_code::for_load_error(&e))
+    };
+    let mut program = match loader::load_file(path) {
         Ok(p) => p,
-        Err(e) => {
-            if json_output {
-                let result = CheckResult::failure(&filename, vec![
-                    JsonError::new("E001", &e.message)
-                ]);
-                println!("{}", result.to_json());
-            } else {
-                eprintln!("Error: {}", e);
+        Err(e) => return load_failed(e),
+    };
+
+    // --fix: aplicar los reemplazos sugeridos y volver a verificar el resultado
+    let mut fixes = None;
+    if fix
+        && let Err(errors) = aura::types::check(&program) {
+        let (fixed, applied) = aura::types::apply_replacements(&source, &errors);
+        if applied > 0 {
+            let written = storage::snapshot_file(path, "check --fix")
+                .and_then(|id| std::fs::write(path, &fixed).map(|_| id));
+            match written {
+                Ok(snapshot_id) => {
+                    source = fixed;
+                    program = match loader::load_file(path) {
+                        Ok(p) => p,
+                        Err(e) => return load_failed(e),
+                    };
+                    fixes = Some((applied, snapshot_id));
+                }
+                Err(e) => {
+                    if !json_output {
+                        eprintln!("Error applying fixes: {}", e);
+                    }
+                    let result = CheckResult::failure(&filename, vec![
+                        JsonError::file_error(format!("Error applying fixes: {}", e))
+                    ]);
+                    return (result, exit_code::IO);
+                }
             }
-            std::process::exit(1);
         }
+    }
+    if let Some((applied, snapshot_id)) = &fixes
+        && !json_output && !quiet() {
+        println!("Applied {} fix(es) (snapshot {})", applied, snapshot_id);
+    }
+    let with_fixes = |result: CheckResult| match &fixes {
+        Some((applied, snapshot_id)) => result.with_fixes(*applied, snapshot_id),
+        None => result,
     };
 
     // Type check
-    match aura::types::check(&program) {
+    let (checked, warnings) = aura::types::check_with_warnings(&program);
+    if !json_output && !quiet() && !warnings.is_empty() {
+        eprintln!("Warnings:");
+        for w in &warnings {
+            eprintln!("  [{}] {}", w.code, w.message);
+            if let Some(suggestion) = &w.suggestion {
+                eprintln!("    Suggestion: {}", suggestion);
+            }
+        }
+    }
+    let json_warnings: Vec<JsonError> = warnings
+        .iter()
+        .map(|w| JsonError::from_type_warning(w, &source))
+        .collect();
+    match checked {
         Ok(()) => {
-            if json_output {
-                let result = CheckResult::success(
-                    &filename,
-                    program.capabilities.len(),
-                    program.definitions.len(),
-                );
-                println!("{}", result.to_json());
-            } else {
+            if !json_output && !quiet() {
                 println!("Valid program");
                 println!("  {} capabilities", program.capabilities.len());
                 println!("  {} definitions", program.definitions.len());
             }
+            let result = CheckResult::success(
+                &filename,
+                program.capabilities.len(),
+                program.definitions.len(),
+            ).with_warnings(json_warnings);
+            (with_fixes(result), exit_code::SUCCESS)
         }
         Err(errors) => {
-            if json_output {
-                let json_errors: Vec<JsonError> = errors
-                    .iter()
-                    .map(|e| JsonError::from_type_error(e, &source))
-                    .collect();
-                let result = CheckResult::failure(&filename, json_errors);
-                println!("{}", result.to_json());
-            } else {
+            if !json_output {
                 eprintln!("Type errors:");
-                for e in errors {
+                for e in &errors {
                     eprintln!("  {}", e.message);
+                    if let (Some(span), Some(previous)) = (&e.span, &e.previous_span) {
+                        let line = |span| aura::error::Location::from_span(span, &source, &filename).line;
+                        eprintln!("    Defined at line {} and again at line {}", line(previous), line(span));
+                    }
                     if let Some(suggestion) = &e.suggestion {
                         eprintln!("    Suggestion: {}", suggestion);
                     }
                 }
             }
-            std::process::exit(1);
+            let json_errors: Vec<JsonError> = errors
+                .iter()
+                .map(|e| JsonError::from_type_error(e, &source))
+                .collect();
+            let result = CheckResult::failure(&filename, json_errors).with_warnings(json_warnings);
+            (with_fixes(result), exit_code::TYPE)
         }
     }
 }
@@ -578,20 +1570,71 @@ fn run_repl() {
     println!("AURA REPL v{}", aura::VERSION);
     println!("Escribe 'exit' para salir, ':reset' para reiniciar, '?help' para ayuda\n");
 
-    let stdin = std::io::stdin();
-    let mut line = String::new();
+    use rustyline::error::ReadlineError;
+    use std::io::IsTerminal;
+
+    let history_path = PathBuf::from(aura::repl::HISTORY_PATH);
+    let mut editor = match aura::repl::editor_with_history(&history_path) {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("Error iniciando el REPL: {}", e);
+            std::process::exit(exit_code::FAILURE);
+        }
+    };
 
     // Crear VM persistente que mantiene el estado entre lineas
     let mut vm = aura::vm::VM::new();
 
-    loop {
-        print!("> ");
-        use std::io::Write;
-        std::io::stdout().flush().unwrap();
+    // Ultimo archivo cargado con :load (para :reload)
+    let mut last_load: Option<PathBuf> = None;
 
-        line.clear();
-        if stdin.read_line(&mut line).is_err() {
-            break;
+    // Input multi-linea acumulado (tipos, funciones con cuerpo en varias lineas)
+    let mut buffer = String::new();
+
+    // Formato de errores, se cambia con :json on|off
+    let mut errors = aura::repl::ErrorFormat::default();
+
+    // Por un pipe (agentes, tests) se lee linea a linea como siempre; el
+    // editor solo maneja la terminal, pero el historial se guarda igual
+    let interactive = std::io::stdin().is_terminal();
+
+    loop {
+        let prompt = if buffer.is_empty() { "> " } else { "... " };
+        let read = if interactive {
+            editor.readline(prompt)
+        } else {
+            read_piped_line(prompt)
+        };
+        let line = match read {
+            Ok(line) => line,
+            // Ctrl-C descarta la linea (y el input multi-linea) sin salir
+            Err(ReadlineError::Interrupted) => {
+                buffer.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("Error leyendo la entrada: {}", e);
+                break;
+            }
+        };
+        if !line.trim().is_empty() {
+            let _ = editor.add_history_entry(line.trim_end());
+        }
+
+        // Continuacion de un input multi-linea
+        if !buffer.is_empty() {
+            // Una linea vacia fuerza la evaluacion de lo acumulado
+            if !line.trim().is_empty() {
+                buffer.push('\n');
+                buffer.push_str(line.trim_end());
+                if aura::repl::needs_more_input(&buffer) {
+                    continue;
+                }
+            }
+            let input = std::mem::take(&mut buffer);
+            eval_repl_input(&mut vm, &input, errors);
+            continue;
         }
 
         let input = line.trim();
@@ -612,6 +1655,44 @@ fn run_repl() {
             continue;
         }
 
+        // Cargar definiciones desde un archivo
+        if input == ":load" || input.starts_with(":load ") {
+            let path = input[":load".len()..].trim();
+            if path.is_empty() {
+                eprintln!("Uso: :load <archivo.aura>");
+                continue;
+            }
+            let path = PathBuf::from(path);
+            // Solo se recuerda un archivo que se pudo cargar
+            if repl_load_file(&mut vm, &path, false, errors) {
+                last_load = Some(path);
+            }
+            continue;
+        }
+
+        if input == ":reload" {
+            match last_load {
+                Some(ref path) => {
+                    repl_load_file(&mut vm, path, true, errors);
+                }
+                None => eprintln!("No hay archivo cargado. Usa :load <archivo.aura>"),
+            }
+            continue;
+        }
+
+        // Formato de errores (humano o JSON)
+        if let Some(result) = errors.apply_command(input) {
+            match result {
+                Ok(format) => {
+                    errors = format;
+                    let state = if errors == aura::repl::ErrorFormat::Json { "ON" } else { "OFF" };
+                    println!("JSON errors: {}", state);
+                }
+                Err(msg) => eprintln!("{}", msg),
+            }
+            continue;
+        }
+
         // Cognitive mode toggle
         if input == ":cognitive on" {
             println!("Cognitive mode ON (requires --cognitive flag at startup for full support)");
@@ -628,65 +1709,169 @@ fn run_repl() {
             continue;
         }
 
-        // Comandos de introspeccion
-        if input.starts_with('?') {
-            handle_introspection(input, &vm);
-            continue;
-        }
+        // Comandos de introspeccion
+        if input.starts_with('?') {
+            handle_introspection(input, &vm);
+            continue;
+        }
+
+        // Construcciones abiertas siguen en las lineas siguientes
+        if aura::repl::needs_more_input(input) {
+            buffer.push_str(input);
+            continue;
+        }
+
+        eval_repl_input(&mut vm, input, errors);
+    }
+
+    if let Err(e) = aura::repl::save_history(&mut editor, &history_path) {
+        eprintln!("No se pudo guardar el historial: {}", e);
+    }
+}
+
+/// Lee una linea de stdin cuando no es una terminal, mostrando el prompt
+fn read_piped_line(prompt: &str) -> rustyline::Result<String> {
+    use std::io::Write;
+
+    print!("{}", prompt);
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    match std::io::stdin().read_line(&mut line)? {
+        0 => Err(rustyline::error::ReadlineError::Eof),
+        _ => Ok(line),
+    }
+}
+
+/// Evalua un input completo del REPL: definicion de tipo, de funcion o expresion
+fn eval_repl_input(vm: &mut aura::vm::VM, input: &str, errors: aura::repl::ErrorFormat) {
+    use aura::cli_output::JsonError;
 
-        // Tokenizar input
-        let tokens = match aura::tokenize(input) {
-            Ok(t) => t,
-            Err(errors) => {
-                for e in errors {
-                    eprintln!("Error de sintaxis: {}", e.message);
-                }
-                continue;
+    // Tokenizar input
+    let tokens = match aura::tokenize(input) {
+        Ok(t) => t,
+        Err(lex_errors) => {
+            for e in lex_errors {
+                eprintln!("{}", errors.render("Error de sintaxis", &JsonError::from_lex_error(&e, input)));
             }
-        };
+            return;
+        }
+    };
 
-        // Determinar si es una definicion de funcion o una expresion
-        if aura::looks_like_function_def(&tokens) {
-            // Parsear como definicion de funcion
-            match aura::parse_function_def(tokens) {
-                Ok(func_def) => {
-                    let name = func_def.name.clone();
-                    vm.define_function(func_def);
-                    println!("<fn {}>", name);
+    // Capacidad (+env) o definicion de tipo (@User { ... }): se parsea como programa
+    let first = tokens.iter().find(|t| t.value != aura::Token::Newline);
+    if matches!(first.map(|t| &t.value), Some(aura::Token::At | aura::Token::Plus)) {
+        match aura::parse(tokens) {
+            Ok(program) => {
+                vm.load(&program);
+                for cap in &program.capabilities {
+                    println!("<cap +{}>", cap.name);
                 }
-                Err(e) => {
-                    eprintln!("Error de parsing: {}", e.message);
+                for def in &program.definitions {
+                    if let aura::Definition::TypeDef(ty) = def {
+                        println!("<type {}>", ty.name);
+                    }
                 }
             }
-        } else {
-            // Parsear y evaluar como expresion
-            match aura::parse_expression(tokens) {
-                Ok(expr) => {
-                    match vm.eval(&expr) {
-                        Ok(value) => {
-                            // No mostrar nil para evitar ruido
-                            if value != aura::Value::Nil {
-                                println!("{}", value);
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Error de ejecucion: {}", e.message);
+            Err(parse_errors) => {
+                for e in parse_errors {
+                    eprintln!("{}", errors.render("Error de parsing", &JsonError::from_parse_error(&e, input)));
+                }
+            }
+        }
+        return;
+    }
+
+    // Determinar si es una definicion de funcion o una expresion
+    if aura::looks_like_function_def(&tokens) {
+        // Parsear como definicion de funcion
+        match aura::parse_function_def(tokens) {
+            Ok(func_def) => {
+                let name = func_def.name.clone();
+                vm.define_function(func_def);
+                println!("<fn {}>", name);
+            }
+            Err(e) => {
+                eprintln!("{}", errors.render("Error de parsing", &JsonError::from_parse_error(&e, input)));
+            }
+        }
+    } else {
+        // Parsear y evaluar como expresion
+        match aura::parse_expression(tokens) {
+            Ok(expr) => {
+                match vm.eval_with_timeout(&expr, aura::repl::EVAL_TIMEOUT) {
+                    Ok(value) => {
+                        // No mostrar nil para evitar ruido
+                        if value != aura::Value::Nil {
+                            println!("{}", value);
                         }
                     }
-                }
-                Err(e) => {
-                    eprintln!("Error de parsing: {}", e.message);
+                    Err(e) => {
+                        eprintln!("{}", errors.render("Error de ejecucion", &JsonError::from_runtime_error(&e)));
+                    }
                 }
             }
+            Err(e) => {
+                eprintln!("{}", errors.render("Error de parsing", &JsonError::from_parse_error(&e, input)));
+            }
+        }
+    }
+}
+
+/// Carga las definiciones de un archivo en la VM del REPL
+///
+/// En un `:reload` solo se redefinen funciones y tipos, para no duplicar
+/// goals e invariants ya registrados.
+/// Carga (o recarga) las definiciones de un archivo en la VM del REPL; retorna si se pudo
+fn repl_load_file(vm: &mut aura::vm::VM, path: &PathBuf, reload: bool, errors: aura::repl::ErrorFormat) -> bool {
+    let mut program = match aura::load_file(path) {
+        Ok(p) => p,
+        Err(e) => {
+            let context = format!("Error cargando '{}'", path.display());
+            let error = aura::cli_output::JsonError::new("E001", e.to_string());
+            eprintln!("{}", errors.render(&context, &error));
+            return false;
         }
+    };
+
+    if reload {
+        program.definitions.retain(|def| {
+            matches!(def, aura::Definition::FuncDef(_) | aura::Definition::TypeDef(_))
+        });
     }
+
+    let funcs = program.definitions.iter()
+        .filter(|d| matches!(d, aura::Definition::FuncDef(_)))
+        .count();
+    let types = program.definitions.iter()
+        .filter(|d| matches!(d, aura::Definition::TypeDef(_)))
+        .count();
+
+    vm.load(&program);
+
+    let action = if reload { "Recargado" } else { "Cargado" };
+    println!("{} '{}': {} funciones, {} tipos", action, path.display(), funcs, types);
+    true
 }
 
 fn handle_introspection(cmd: &str, vm: &aura::vm::VM) {
     match cmd {
-        "?types" => println!("Tipos definidos: (ninguno aun)"),
+        "?types" => {
+            let types = vm.list_types();
+            if types.is_empty() {
+                println!("Tipos definidos: (ninguno)");
+            } else {
+                println!("Tipos definidos: {}", types.join(", "));
+            }
+        }
         "?funcs" => {
-            let funcs = vm.list_functions();
+            // Cada funcion con su aridad: `area/2`, o `sum/1+` si es variadica
+            let funcs: Vec<String> = vm.list_functions().into_iter()
+                .map(|name| match vm.get_function(&name) {
+                    Some(f) if f.rest_param().is_some() => format!("{}/{}+", name, f.min_arity()),
+                    Some(f) => format!("{}/{}", name, f.min_arity()),
+                    None => name,
+                })
+                .collect();
             if funcs.is_empty() {
                 println!("Funciones definidas: (ninguna)");
             } else {
@@ -705,15 +1890,45 @@ fn handle_introspection(cmd: &str, vm: &aura::vm::VM) {
         "?help" => {
             println!("Comandos de introspeccion:");
             println!("  ?types  - Lista tipos definidos");
-            println!("  ?funcs  - Lista funciones definidas");
+            println!("  ?type T - Muestra los campos del tipo T");
+            println!("  ?funcs  - Lista funciones definidas (con su aridad)");
+            println!("  ?signature f - Muestra los parametros de f y si tiene efecto");
             println!("  ?vars   - Lista variables definidas");
             println!("  ?caps   - Lista capacidades disponibles");
             println!("  ?help   - Muestra esta ayuda");
             println!();
             println!("Comandos especiales:");
             println!("  :reset      - Reinicia el estado de la sesion");
+            println!("  :load <f>   - Carga las definiciones de un archivo .aura");
+            println!("  :reload     - Vuelve a cargar el ultimo archivo de :load");
+            println!("  :json on|off - Errores como objetos JSON (una linea cada uno)");
             println!("  :cognitive  - Muestra estado del modo cognitivo");
             println!("  exit        - Sale del REPL");
+            println!();
+            println!("Una expresion que tarda mas de {}s se cancela.", aura::repl::EVAL_TIMEOUT.as_secs());
+        }
+        _ if cmd.starts_with("?type ") => {
+            let name = cmd["?type ".len()..].trim();
+            match vm.get_type(name) {
+                Some(ty) => {
+                    println!("@{} {{", ty.name);
+                    for field in &ty.fields {
+                        let annotations: Vec<String> = field.annotations.iter()
+                            .map(|a| format!(" @{}", a.name))
+                            .collect();
+                        println!("  {} {}{}", field.name, field.ty, annotations.concat());
+                    }
+                    println!("}}");
+                }
+                None => println!("Tipo no definido: {}", name),
+            }
+        }
+        _ if cmd.starts_with("?signature ") => {
+            let name = cmd["?signature ".len()..].trim();
+            match vm.get_function_signature(name) {
+                Some(signature) => println!("{}", signature),
+                None => println!("Funcion no definida: {}", name),
+            }
         }
         _ => println!("Comando desconocido. Usa ?help"),
     }
@@ -721,7 +1936,7 @@ fn handle_introspection(cmd: &str, vm: &aura::vm::VM) {
 
 fn show_info(json: bool) {
     if json {
-        println!("{}", serde_json::to_string_pretty(&aura::runtime_info()).unwrap());
+        println!("{}", aura::cli_output::InfoResult::current().to_json());
     } else {
         println!("AURA v{}", aura::VERSION);
         println!();
@@ -747,6 +1962,37 @@ fn show_info(json: bool) {
     }
 }
 
+fn explain_code(code: &str, json: bool) {
+    use aura::cli_output::ExplainResult;
+
+    let Some(explanation) = aura::error::explain_code(code) else {
+        let result = ExplainResult::not_found(code);
+        if json {
+            println!("{}", result.to_json());
+        } else {
+            eprintln!("Error: {}", result.error.unwrap_or_default());
+            eprintln!("Categorias: E0xx sintaxis, E1xx tipos, E2xx referencias, E3xx efectos, E4xx runtime, E5xx capacidades, E9xx agente");
+        }
+        std::process::exit(exit_code::FAILURE);
+    };
+
+    if json {
+        println!("{}", ExplainResult::success(code, explanation).to_json());
+        return;
+    }
+
+    println!("{}: {}", explanation.code, explanation.title);
+    println!();
+    println!("{}", explanation.description);
+    println!();
+    println!("Causas tipicas:");
+    for cause in explanation.causes {
+        println!("  - {}", cause);
+    }
+    println!();
+    println!("Como corregirlo: {}", explanation.fix);
+}
+
 /// Storage module for persisting snapshots and undo state
 mod storage {
     use std::path::PathBuf;
@@ -854,6 +2100,26 @@ mod storage {
         fs::write(&path, content)
     }
 
+    /// Snapshot a single file before rewriting it; returns the snapshot ID
+    pub fn snapshot_file(path: &std::path::Path, reason: &str) -> std::io::Result<String> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let content = fs::read_to_string(path)?;
+        let snapshot = PersistedSnapshot {
+            id: format!("snap_{}", now.as_nanos()),
+            timestamp: now.as_secs(),
+            reason: reason.to_string(),
+            files: vec![PersistedFileSnapshot {
+                path: path.display().to_string(),
+                hash: aura::agent::content_hash(&content),
+                content,
+            }],
+        };
+        save_snapshot(&snapshot)?;
+        Ok(snapshot.id)
+    }
+
     /// Delete a snapshot from disk
     pub fn delete_snapshot(id: &str) -> std::io::Result<()> {
         let path = snapshot_path(id)?;
@@ -871,12 +2137,11 @@ mod storage {
         for entry in fs::read_dir(&dir)? {
             let entry = entry?;
             let path = entry.path();
-            if path.extension().map(|e| e == "json").unwrap_or(false) {
-                if let Some(stem) = path.file_stem() {
-                    if let Some(s) = stem.to_str() {
-                        ids.push(s.to_string());
-                    }
-                }
+            if path.extension().map(|e| e == "json").unwrap_or(false)
+                && let Some(stem) = path.file_stem()
+                && let Some(s) = stem.to_str()
+            {
+                ids.push(s.to_string());
             }
         }
 
@@ -895,12 +2160,52 @@ mod storage {
         }
 
         // Sort by timestamp (newest first)
-        snapshots.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        snapshots.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
         Ok(snapshots)
     }
 }
 
-fn handle_undo(list: bool, to: Option<String>, json_output: bool) {
+/// Compara cada archivo del snapshot con el disco, sin escribir nada
+fn preview_restore(snapshot: &storage::PersistedSnapshot) -> aura::cli_output::RestorePreviewResult {
+    use aura::cli_output::{RestorePreviewFile, RestorePreviewResult};
+
+    let files = snapshot.files.iter().map(|file_snap| {
+        let current = std::fs::read_to_string(&file_snap.path).ok();
+        let status = aura::agent::FileDrift::check(&file_snap.hash, &file_snap.content, current.as_deref());
+        let diff = match status {
+            aura::agent::FileDrift::Drifted | aura::agent::FileDrift::Missing => aura::agent::unified_diff(
+                &file_snap.path,
+                current.as_deref().unwrap_or(""),
+                &file_snap.content,
+            ),
+            _ => String::new(),
+        };
+        RestorePreviewFile { file: file_snap.path.clone(), status, diff }
+    }).collect();
+
+    RestorePreviewResult::new(&snapshot.id, files)
+}
+
+fn print_restore_preview(preview: &aura::cli_output::RestorePreviewResult, json_output: bool) {
+    if json_output {
+        println!("{}", preview.to_json());
+    } else {
+        println!("Dry run: restoring {} would overwrite {} file(s)", preview.snapshot, preview.files_to_overwrite.len());
+        for file in &preview.files {
+            println!("  {:<9} {}", file.status.as_str(), file.file);
+        }
+        for file in preview.files.iter().filter(|f| !f.diff.is_empty()) {
+            println!();
+            print!("{}", file.diff);
+        }
+    }
+
+    if !preview.success {
+        std::process::exit(exit_code::FAILURE);
+    }
+}
+
+fn handle_undo(list: bool, to: Option<String>, dry_run: bool, json_output: bool) {
     use aura::cli_output::{UndoListResult, UndoActionInfo, UndoResult};
 
     if list {
@@ -944,7 +2249,7 @@ fn handle_undo(list: bool, to: Option<String>, json_output: bool) {
                 } else {
                     eprintln!("Error loading undo state: {}", e);
                 }
-                std::process::exit(1);
+                std::process::exit(exit_code::IO);
             }
         }
         return;
@@ -960,7 +2265,7 @@ fn handle_undo(list: bool, to: Option<String>, json_output: bool) {
                 } else {
                     eprintln!("Nothing to undo");
                 }
-                std::process::exit(1);
+                std::process::exit(exit_code::FAILURE);
             }
 
             // Determine which action to undo
@@ -982,6 +2287,10 @@ fn handle_undo(list: bool, to: Option<String>, json_output: bool) {
 
                     // Load the snapshot
                     match storage::load_snapshot(&snapshot_id) {
+                        Ok(snapshot) if dry_run => {
+                            // Ni los archivos ni el estado de undo cambian
+                            print_restore_preview(&preview_restore(&snapshot), json_output);
+                        }
                         Ok(snapshot) => {
                             let mut restored_files = Vec::new();
                             let mut errors = Vec::new();
@@ -1009,7 +2318,7 @@ fn handle_undo(list: bool, to: Option<String>, json_output: bool) {
                             if json_output {
                                 let result = UndoResult::success(snapshot_id, restored_files);
                                 println!("{}", result.to_json());
-                            } else {
+                            } else if !quiet() || !errors.is_empty() {
                                 if errors.is_empty() {
                                     println!("Successfully reverted to snapshot: {}", snapshot.id);
                                     for file in &restored_files {
@@ -1033,7 +2342,7 @@ fn handle_undo(list: bool, to: Option<String>, json_output: bool) {
                             } else {
                                 eprintln!("Error loading snapshot: {}", e);
                             }
-                            std::process::exit(1);
+                            std::process::exit(exit_code::FAILURE);
                         }
                     }
                 }
@@ -1044,7 +2353,7 @@ fn handle_undo(list: bool, to: Option<String>, json_output: bool) {
                     } else {
                         eprintln!("Snapshot not found: {}", to.unwrap_or_default());
                     }
-                    std::process::exit(1);
+                    std::process::exit(exit_code::FAILURE);
                 }
             }
         }
@@ -1055,7 +2364,7 @@ fn handle_undo(list: bool, to: Option<String>, json_output: bool) {
             } else {
                 eprintln!("Error loading undo state: {}", e);
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::IO);
         }
     }
 }
@@ -1064,6 +2373,7 @@ fn handle_snapshots(action: Option<SnapshotsAction>, parent_json: bool) {
     use aura::cli_output::{
         SnapshotsListResult, SnapshotInfo, SnapshotCreateResult,
         SnapshotRestoreResult, SnapshotRestoreFailure, SnapshotPruneResult,
+        SnapshotVerifyResult, SnapshotFileStatus,
     };
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -1108,7 +2418,7 @@ fn handle_snapshots(action: Option<SnapshotsAction>, parent_json: bool) {
                     } else {
                         eprintln!("Error listing snapshots: {}", e);
                     }
-                    std::process::exit(1);
+                    std::process::exit(exit_code::IO);
                 }
             }
         }
@@ -1149,7 +2459,7 @@ fn handle_snapshots(action: Option<SnapshotsAction>, parent_json: bool) {
                 } else {
                     eprintln!("No files to snapshot");
                 }
-                std::process::exit(1);
+                std::process::exit(exit_code::FAILURE);
             }
 
             // Read file contents
@@ -1161,16 +2471,10 @@ fn handle_snapshots(action: Option<SnapshotsAction>, parent_json: bool) {
                         let path_str = path.display().to_string();
                         file_names.push(path_str.clone());
 
-                        // Compute simple hash
-                        let mut hash: u64 = 0;
-                        for byte in content.bytes() {
-                            hash = hash.wrapping_mul(31).wrapping_add(byte as u64);
-                        }
-
                         file_snapshots.push(storage::PersistedFileSnapshot {
                             path: path_str,
+                            hash: aura::agent::content_hash(&content),
                             content,
-                            hash: format!("{:016x}", hash),
                         });
                     }
                     Err(e) => {
@@ -1182,7 +2486,7 @@ fn handle_snapshots(action: Option<SnapshotsAction>, parent_json: bool) {
                         } else {
                             eprintln!("Failed to read {}: {}", path.display(), e);
                         }
-                        std::process::exit(1);
+                        std::process::exit(exit_code::IO);
                     }
                 }
             }
@@ -1212,21 +2516,40 @@ fn handle_snapshots(action: Option<SnapshotsAction>, parent_json: bool) {
                     } else {
                         eprintln!("Failed to save snapshot: {}", e);
                     }
-                    std::process::exit(1);
+                    std::process::exit(exit_code::IO);
                 }
             }
         }
 
-        Some(SnapshotsAction::Restore { id, json }) => {
+        Some(SnapshotsAction::Restore { id, dry_run, json }) => {
             let json_output = json || parent_json;
 
             match storage::load_snapshot(&id) {
+                Ok(snapshot) if dry_run => {
+                    print_restore_preview(&preview_restore(&snapshot), json_output);
+                }
                 Ok(snapshot) => {
                     let mut restored = Vec::new();
                     let mut failed = Vec::new();
+                    let mut unchanged = Vec::new();
 
                     for file_snap in &snapshot.files {
                         let path = PathBuf::from(&file_snap.path);
+                        let current = std::fs::read_to_string(&path).ok();
+                        match aura::agent::FileDrift::check(&file_snap.hash, &file_snap.content, current.as_deref()) {
+                            aura::agent::FileDrift::Unchanged => {
+                                unchanged.push(file_snap.path.clone());
+                                continue;
+                            }
+                            aura::agent::FileDrift::Corrupt => {
+                                failed.push(SnapshotRestoreFailure {
+                                    file: file_snap.path.clone(),
+                                    reason: "snapshot content does not match its hash".to_string(),
+                                });
+                                continue;
+                            }
+                            aura::agent::FileDrift::Drifted | aura::agent::FileDrift::Missing => {}
+                        }
                         match std::fs::write(&path, &file_snap.content) {
                             Ok(_) => restored.push(file_snap.path.clone()),
                             Err(e) => failed.push(SnapshotRestoreFailure {
@@ -1237,13 +2560,19 @@ fn handle_snapshots(action: Option<SnapshotsAction>, parent_json: bool) {
                     }
 
                     if json_output {
-                        let result = SnapshotRestoreResult::success(&id, restored.clone(), failed.clone());
+                        let result = SnapshotRestoreResult::success(&id, restored.clone(), failed.clone())
+                            .with_unchanged(unchanged.clone());
                         println!("{}", result.to_json());
                     } else {
+                        for file in &unchanged {
+                            eprintln!("  Warning: {} already matches the snapshot (nothing to restore)", file);
+                        }
                         if failed.is_empty() {
-                            println!("Restored snapshot: {}", id);
-                            for file in &restored {
-                                println!("  Restored: {}", file);
+                            if !quiet() {
+                                println!("Restored snapshot: {}", id);
+                                for file in &restored {
+                                    println!("  Restored: {}", file);
+                                }
                             }
                         } else {
                             println!("Partially restored snapshot: {}", id);
@@ -1257,7 +2586,7 @@ fn handle_snapshots(action: Option<SnapshotsAction>, parent_json: bool) {
                     }
 
                     if !failed.is_empty() {
-                        std::process::exit(1);
+                        std::process::exit(exit_code::IO);
                     }
                 }
                 Err(e) => {
@@ -1267,9 +2596,47 @@ fn handle_snapshots(action: Option<SnapshotsAction>, parent_json: bool) {
                     } else {
                         eprintln!("Error loading snapshot: {}", e);
                     }
-                    std::process::exit(1);
+                    std::process::exit(exit_code::FAILURE);
+                }
+            }
+        }
+
+        Some(SnapshotsAction::Verify { id, json }) => {
+            let json_output = json || parent_json;
+
+            let snapshot = match storage::load_snapshot(&id) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    if json_output {
+                        println!("{}", SnapshotVerifyResult::failure(format!("Snapshot not found: {}", e)).to_json());
+                    } else {
+                        eprintln!("Error loading snapshot: {}", e);
+                    }
+                    std::process::exit(exit_code::FAILURE);
+                }
+            };
+
+            let files: Vec<SnapshotFileStatus> = snapshot.files.iter().map(|file_snap| {
+                let current = std::fs::read_to_string(&file_snap.path).ok();
+                SnapshotFileStatus {
+                    file: file_snap.path.clone(),
+                    status: aura::agent::FileDrift::check(&file_snap.hash, &file_snap.content, current.as_deref()),
+                }
+            }).collect();
+            let result = SnapshotVerifyResult::new(&id, files);
+
+            if json_output {
+                println!("{}", result.to_json());
+            } else {
+                println!("Snapshot {}: {}", id, if result.drift { "drift detected" } else { "no drift" });
+                for file in &result.files {
+                    println!("  {:<9} {}", file.status.as_str(), file.file);
                 }
             }
+
+            if !result.success {
+                std::process::exit(exit_code::FAILURE);
+            }
         }
 
         Some(SnapshotsAction::Prune { keep, json }) => {
@@ -1296,7 +2663,7 @@ fn handle_snapshots(action: Option<SnapshotsAction>, parent_json: bool) {
                     if json_output {
                         let result = SnapshotPruneResult::success(removed, remaining);
                         println!("{}", result.to_json());
-                    } else {
+                    } else if !quiet() {
                         println!("Pruned {} snapshots, {} remaining", removed, remaining);
                     }
                 }
@@ -1307,7 +2674,7 @@ fn handle_snapshots(action: Option<SnapshotsAction>, parent_json: bool) {
                     } else {
                         eprintln!("Error listing snapshots: {}", e);
                     }
-                    std::process::exit(1);
+                    std::process::exit(exit_code::IO);
                 }
             }
         }
@@ -1325,8 +2692,9 @@ fn truncate_str(s: &str, max_len: usize) -> String {
 }
 
 /// Serve an AURA file as HTTP server
-fn serve_file(path: &PathBuf, port: u16, json_output: bool) {
-    use aura::server::start_server;
+fn serve_file(path: &PathBuf, port: u16, verbose: bool, events: bool, events_file: Option<&std::path::Path>, json_output: bool) {
+    use aura::cli_output::FailureResult;
+    use aura::server::{start_server, EventSink, RequestLog, RequestLogger};
     use aura::loader;
 
     // Load file with imports resolved
@@ -1334,21 +2702,21 @@ fn serve_file(path: &PathBuf, port: u16, json_output: bool) {
         Ok(p) => p,
         Err(e) => {
             if json_output {
-                println!(r#"{{"success":false,"error":"{}"}}"#, e.message.replace('"', "\\\""));
+                println!("{}", FailureResult::new("serve", e.message.clone()).to_json());
             } else {
                 eprintln!("Error: {}", e);
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::for_load_error(&e));
         }
     };
 
-    // Extract routes from function definitions
-    // Convention: get_users -> GET /users, post_user -> POST /user, etc.
+    // Extract routes: explicit `route` declarations, then the naming
+    // convention (get_users -> GET /users, post_user -> POST /user, etc.)
     let routes = extract_routes(&program);
 
-    if routes.is_empty() {
+    if routes.is_empty() && aura::server::ws_paths(&program).is_empty() {
         if json_output {
-            println!(r#"{{"success":false,"error":"No routes found. Define functions like get_users, post_user, etc."}}"#);
+            println!("{}", FailureResult::new("serve", "No routes found. Define functions like get_users, post_user, etc.").to_json());
         } else {
             eprintln!("No routes found.");
             eprintln!("Define functions following REST convention:");
@@ -1357,38 +2725,80 @@ fn serve_file(path: &PathBuf, port: u16, json_output: bool) {
             eprintln!("  post_user     -> POST /user");
             eprintln!("  put_user(id)  -> PUT /user/:id");
             eprintln!("  del_user(id)  -> DELETE /user/:id");
+            eprintln!("Or declare routes explicitly:");
+            eprintln!("  route \"GET\" \"/users/:id\" find_user");
+            eprintln!("WebSocket handlers:");
+            eprintln!("  ws_chat(msg)  -> /ws/chat");
         }
-        std::process::exit(1);
+        std::process::exit(exit_code::FAILURE);
     }
 
-    if !json_output {
+    if !json_output && !quiet() {
         println!("Starting AURA server on port {}...", port);
         println!("Routes:");
     }
 
+    // Request log: one JSON line per request in --json mode
+    let logger: Option<RequestLogger> = if !verbose {
+        None
+    } else if json_output {
+        Some(std::sync::Arc::new(|log: &RequestLog| println!("{}", log.to_json())))
+    } else {
+        Some(std::sync::Arc::new(|log: &RequestLog| println!("{}", log.to_human())))
+    };
+
+    // Event stream: --events-file implies --events
+    let events: Option<EventSink> = match events_file {
+        Some(file) => match aura::server::file_sink(file) {
+            Ok(sink) => Some(sink),
+            Err(e) => {
+                if json_output {
+                    println!("{}", FailureResult::new("serve", format!("Cannot open events file: {}", e)).to_json());
+                } else {
+                    eprintln!("Error: no se pudo abrir '{}': {}", file.display(), e);
+                }
+                std::process::exit(exit_code::IO);
+            }
+        },
+        None if events => Some(aura::server::stdout_sink()),
+        None => None,
+    };
+
     // Run async server
     let rt = tokio::runtime::Runtime::new().expect("Failed to create runtime");
     rt.block_on(async {
-        if let Err(e) = start_server(port, routes, program).await {
+        if let Err(e) = start_server(port, routes, program, logger, events).await {
             if json_output {
-                println!(r#"{{"success":false,"error":"Server error: {}"}}"#, e);
+                println!("{}", FailureResult::new("serve", format!("Server error: {}", e)).to_json());
             } else {
                 eprintln!("Server error: {}", e);
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::FAILURE);
         }
     });
 }
 
-/// Extract routes from function definitions based on naming convention
+/// Extract routes from the program
+///
+/// Explicit `route "GET" "/path" handler` declarations come first. Functions
+/// not used as the handler of an explicit route fall back to the naming
+/// convention (get_users -> GET /users).
 fn extract_routes(program: &aura::Program) -> Vec<aura::server::Route> {
     let mut routes = Vec::new();
+    let mut explicit_handlers = std::collections::HashSet::new();
 
     for def in &program.definitions {
-        if let aura::Definition::FuncDef(func) = def {
-            if let Some(route) = parse_route_from_name(&func.name, &func.params) {
-                routes.push(route);
-            }
+        if let aura::Definition::Route(route) = def {
+            routes.push(aura::server::Route::new(&route.method, &route.path, &route.handler));
+            explicit_handlers.insert(route.handler.as_str());
+        }
+    }
+
+    for def in &program.definitions {
+        if let aura::Definition::FuncDef(func) = def
+            && !explicit_handlers.contains(func.name.as_str())
+            && let Some(route) = parse_route_from_name(&func.name, &func.params) {
+            routes.push(route);
         }
     }
 
@@ -1410,8 +2820,7 @@ fn parse_route_from_name(name: &str, params: &[aura::parser::Param]) -> Option<a
     ];
 
     for (prefix, method) in prefixes {
-        if name.starts_with(prefix) {
-            let resource = &name[prefix.len()..];
+        if let Some(resource) = name.strip_prefix(prefix) {
             let path = build_path(resource, params);
             return Some(aura::server::Route::new(method, &path, name));
         }
@@ -1427,8 +2836,8 @@ fn build_path(resource: &str, params: &[aura::parser::Param]) -> String {
     let mut path = format!("/{}", resource.replace('_', "/"));
 
     for param in params {
-        // Skip 'req' parameter (request object)
-        if param.name != "req" {
+        // Skip 'req' and 'request' parameters (request body / object)
+        if param.name != "req" && param.name != "request" {
             path.push_str(&format!("/:{}", param.name));
         }
     }
@@ -1441,20 +2850,35 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
     use std::io::Write;
     use std::thread;
     use std::time::Duration;
-    use aura::agent::{HealingMemory, MEMORY_FILE};
+    use aura::agent::{healing_log, HealingLogEntry, HealingMemory, HEALING_LOG_FILE, MEMORY_FILE};
+    use aura::cli_output::HealResult;
+
+    /// Codigo ANSI que solo se escribe si `color_enabled()`
+    #[derive(Clone, Copy)]
+    struct Ansi(&'static str);
+
+    impl std::fmt::Display for Ansi {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            if aura::error::color_enabled() {
+                f.write_str(self.0)
+            } else {
+                Ok(())
+            }
+        }
+    }
 
     // ANSI colors
-    const RED: &str = "\x1b[31m";
-    const GREEN: &str = "\x1b[32m";
-    const YELLOW: &str = "\x1b[33m";
-    const BLUE: &str = "\x1b[34m";
-    const CYAN: &str = "\x1b[36m";
-    const MAGENTA: &str = "\x1b[35m";
-    const BOLD: &str = "\x1b[1m";
-    const DIM: &str = "\x1b[2m";
-    const RESET: &str = "\x1b[0m";
-
-    fn print_step(icon: &str, color: &str, msg: &str) {
+    const RED: Ansi = Ansi("\x1b[31m");
+    const GREEN: Ansi = Ansi("\x1b[32m");
+    const YELLOW: Ansi = Ansi("\x1b[33m");
+    const BLUE: Ansi = Ansi("\x1b[34m");
+    const CYAN: Ansi = Ansi("\x1b[36m");
+    const MAGENTA: Ansi = Ansi("\x1b[35m");
+    const BOLD: Ansi = Ansi("\x1b[1m");
+    const DIM: Ansi = Ansi("\x1b[2m");
+    const RESET: Ansi = Ansi("\x1b[0m");
+
+    fn print_step(icon: &str, color: Ansi, msg: &str) {
         println!("{}{} {}{}", color, icon, msg, RESET);
     }
 
@@ -1462,7 +2886,7 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
         for (i, line) in code.lines().enumerate() {
             let line_num = i + 1;
             if highlight_error && line.contains("ERROR_HERE") {
-                println!("  {}{}  {}  {}{}", RED, line_num, line, RESET, "");
+                println!("  {}{}  {}  {}", RED, line_num, line, RESET);
             } else {
                 println!("  {}{}{}  {}", DIM, line_num, RESET, line);
             }
@@ -1481,23 +2905,54 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
         std::io::stdout().flush().unwrap();
     }
 
+    /// Runs the healing engine; the spinner advances with each chunk the agent streams
+    async fn consult_agent<P: aura::agent::AgentProvider>(
+        agent: P,
+        error: &aura::vm::RuntimeError,
+        context: &aura::agent::HealingContext,
+        show_progress: bool,
+    ) -> (Result<aura::agent::HealingResult, aura::agent::HealingError>, Option<f32>) {
+        let frames = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+        let mut chunks = 0usize;
+        let mut on_chunk = |_: &str| {
+            chunks += 1;
+            if show_progress {
+                print!("\r{}{}  Waiting for AI response ({} chunks){}", CYAN, frames[chunks % frames.len()], chunks, RESET);
+                std::io::stdout().flush().ok();
+            }
+        };
+
+        let mut engine = aura::agent::HealingEngine::new(agent)
+            .with_auto_apply(true)
+            .with_confidence_threshold(0.5);
+        let result = engine.heal_error_streaming(error, context, &mut on_chunk).await;
+
+        if show_progress {
+            println!();
+        }
+        (result, engine.last_confidence())
+    }
+
     // Load healing memory
     let mut memory = HealingMemory::load(MEMORY_FILE).unwrap_or_default();
 
+    // Banner, pasos y spinners; --quiet deja solo el fix y el resultado
+    let narrate = !json_output && !quiet();
+
     // Read file
     let source = match std::fs::read_to_string(path) {
         Ok(s) => s,
         Err(e) => {
             if json_output {
-                println!(r#"{{"success":false,"stage":"read","error":"{}"}}"#, e);
+                println!("{}", HealResult::failure("read", e.to_string()).to_json());
             } else {
                 print_step("✗", RED, &format!("Error reading file: {}", e));
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::IO);
         }
     };
 
-    if !json_output {
+    if narrate {
         println!();
         println!("{}{}═══════════════════════════════════════════════════════════════{}", BOLD, BLUE, RESET);
         println!("{}{}   AURA Self-Healing Demo{}", BOLD, BLUE, RESET);
@@ -1513,7 +2968,7 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
     }
 
     // Step 1: Show original code
-    if !json_output {
+    if narrate {
         print_step("1️⃣", YELLOW, "Original code:");
         println!();
         print_code(&source, false);
@@ -1522,22 +2977,31 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
     }
 
     // Step 2: Try to execute
-    if !json_output {
+    if narrate {
         print_step("2️⃣", YELLOW, "Attempting to execute...");
         spinner("Parsing and executing", 800);
     }
 
+    // Error de sintaxis para --json: el span en bytes y su linea/columna
+    let syntax_error_json = |stage: &str, message: &str, span: Option<&aura::lexer::Span>| {
+        let result = HealResult::failure(stage, message);
+        match span {
+            Some(span) => result.with_span(span, &source).to_json(),
+            None => result.to_json(),
+        }
+    };
+
     // Tokenize
     let tokens = match aura::tokenize(&source) {
         Ok(t) => t,
         Err(errors) => {
             let error_msg = errors.first().map(|e| e.message.clone()).unwrap_or_default();
             if json_output {
-                println!(r#"{{"success":false,"stage":"tokenize","error":"{}"}}"#, error_msg);
+                println!("{}", syntax_error_json("tokenize", &error_msg, errors.first().map(|e| &e.span)));
             } else {
                 print_step("❌", RED, &format!("Tokenization error: {}", error_msg));
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::PARSE);
         }
     };
 
@@ -1547,11 +3011,11 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
         Err(errors) => {
             let error_msg = errors.first().map(|e| e.message.clone()).unwrap_or_default();
             if json_output {
-                println!(r#"{{"success":false,"stage":"parse","error":"{}"}}"#, error_msg);
+                println!("{}", syntax_error_json("parse", &error_msg, errors.first().map(|e| &e.span)));
             } else {
                 print_step("❌", RED, &format!("Parse error: {}", error_msg));
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::PARSE);
         }
     };
 
@@ -1563,7 +3027,7 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
         Ok(result) => {
             // No error - program runs fine!
             if json_output {
-                println!(r#"{{"success":true,"needed_healing":false,"result":"{}"}}"#, result);
+                println!("{}", HealResult::no_healing_needed(result.to_string()).to_json());
             } else {
                 println!();
                 print_step("✅", GREEN, "Program executed successfully - no healing needed!");
@@ -1571,22 +3035,28 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
                 println!("  {}Result:{} {}", BOLD, RESET, result);
                 println!();
             }
-            return;
         }
         Err(runtime_error) => {
             // Found an error - time to heal!
-            if !json_output {
+            if narrate {
                 println!();
                 print_step("❌", RED, &format!("Runtime error detected: {}", runtime_error.message));
                 println!();
                 thread::sleep(Duration::from_millis(500));
             }
 
-            // Step 3: Check memory for known pattern
-            let known_pattern = memory.find_pattern(&runtime_error.message);
+            // Invariants every fix must respect, as AURA expressions
+            let invariants: Vec<String> = program.definitions.iter().filter_map(|d| {
+                if let aura::Definition::Invariant(inv) = d { Some(aura::fmt::format_expr(&inv.expr)) } else { None }
+            }).collect();
+
+            // Step 3: Check memory for a fix recorded for this same file (skipped
+            // if it breaks an invariant); fixes for other files only go to the agent
+            let known_pattern = memory.find_fix_for_source(&runtime_error.message, &source)
+                .filter(|p| aura::agent::find_violated_invariant(&p.fix, &invariants).is_none());
 
             if let Some(pattern) = known_pattern {
-                if !json_output {
+                if narrate {
                     print_step("3️⃣", MAGENTA, "Found known pattern in memory!");
                     println!("  {}Pattern: {}{}", DIM, pattern.error, RESET);
                     println!("  {}Used {} times before{}", DIM, pattern.count, RESET);
@@ -1601,23 +3071,29 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
                 memory.record_fix(&runtime_error.message, &source, &patch);
                 let _ = memory.save(MEMORY_FILE);
 
+                // El log es solo auditoria: un fallo al escribirlo no detiene el heal
+                let entry = HealingLogEntry::new(path.display().to_string(), &runtime_error.message, provider, aura::agent::HealingOutcome::Fixed)
+                    .with_patch(&patch)
+                    .from_memory();
+                let _ = healing_log::append(HEALING_LOG_FILE, &entry);
+
                 // Apply the known fix
                 if apply {
-                    if !json_output {
+                    if narrate {
                         print_step("4️⃣", YELLOW, "Applying known fix to file...");
                     }
 
                     // Write the fix
                     if let Err(e) = std::fs::write(path, &patch) {
                         if json_output {
-                            println!(r#"{{"success":false,"stage":"apply","error":"{}"}}"#, e);
+                            println!("{}", HealResult::failure("apply", e.to_string()).to_json());
                         } else {
                             print_step("❌", RED, &format!("Failed to write fix: {}", e));
                         }
-                        std::process::exit(1);
+                        std::process::exit(exit_code::IO);
                     }
 
-                    if !json_output {
+                    if narrate {
                         print_step("✅", GREEN, "Known fix applied to file!");
                         println!();
                     }
@@ -1631,10 +3107,7 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
                     match vm2.run() {
                         Ok(result) => {
                             if json_output {
-                                println!(r#"{{"success":true,"needed_healing":true,"fixed":true,"from_memory":true,"result":"{}","patch":"{}"}}"#,
-                                    result,
-                                    patch.replace('\n', "\\n").replace('"', "\\\"")
-                                );
+                                println!("{}", HealResult::fixed(patch.as_str(), result.to_string()).from_memory().to_json());
                             } else {
                                 println!();
                                 print_step("🎉", GREEN, "SUCCESS! Known fix works correctly!");
@@ -1649,19 +3122,16 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
                         }
                         Err(e) => {
                             if json_output {
-                                println!(r#"{{"success":false,"stage":"verify","error":"{}"}}"#, e.message);
+                                println!("{}", HealResult::failure("verify", e.message.clone()).to_json());
                             } else {
                                 print_step("❌", RED, &format!("Known fix didn't work: {}", e.message));
                             }
-                            std::process::exit(1);
+                            std::process::exit(exit_code::RUNTIME);
                         }
                     }
                 } else {
                     if json_output {
-                        println!(r#"{{"success":true,"needed_healing":true,"fixed":false,"from_memory":true,"patch":"{}","explanation":"{}"}}"#,
-                            patch.replace('\n', "\\n").replace('"', "\\\""),
-                            explanation.replace('"', "\\\"")
-                        );
+                        println!("{}", HealResult::proposed(patch.as_str(), explanation.as_str()).from_memory().to_json());
                     } else {
                         print_step("5️⃣", YELLOW, "Proposed fix (from memory):");
                         println!();
@@ -1677,7 +3147,7 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
             }
 
             // No known pattern - use agent
-            if !json_output {
+            if narrate {
                 print_step("3️⃣", YELLOW, "Initiating self-healing...");
                 spinner("Analyzing error context", 600);
                 println!();
@@ -1690,27 +3160,35 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
                 1, 1,
             )
             .with_known_patterns(memory.patterns.clone())
-            .with_project_defaults(memory.project_defaults.clone());
+            .with_project_defaults(memory.project_defaults.clone())
+            .with_invariants(invariants);
 
             // Step 4: Call the agent
-            if !json_output {
+            if narrate {
                 print_step("4️⃣", YELLOW, &format!("Consulting {} agent...", provider));
-                spinner("Waiting for AI response", 1200);
             }
 
-            // Use mock provider for demo (real providers require API keys)
-            let mock_provider = aura::agent::MockProvider::new()
-                .with_latency(0);
-
             let rt = tokio::runtime::Runtime::new().expect("Failed to create runtime");
-            let healing_result = rt.block_on(async {
-                let mut engine = aura::agent::HealingEngine::new(mock_provider)
-                    .with_auto_apply(true)
-                    .with_confidence_threshold(0.5);
-
-                engine.heal_error(&runtime_error, &context).await
+            let (healing_result, confidence) = rt.block_on(async {
+                match provider {
+                    #[cfg(feature = "ollama")]
+                    "ollama" => {
+                        let ollama = aura::agent::RetryingProvider::new(aura::agent::OllamaProvider::default());
+                        consult_agent(ollama, &runtime_error, &context, narrate).await
+                    }
+                    // Mock provider for the demo (other providers require API keys)
+                    _ => {
+                        consult_agent(aura::agent::MockProvider::new().with_latency(0), &runtime_error, &context, narrate).await
+                    }
+                }
             });
 
+            let mut entry = HealingLogEntry::from_result(path.display().to_string(), &runtime_error.message, provider, &healing_result);
+            if let Some(confidence) = confidence {
+                entry = entry.with_confidence(confidence);
+            }
+            let _ = healing_log::append(HEALING_LOG_FILE, &entry);
+
             // Track if we need to save memory after a successful fix
             let error_message = runtime_error.message.clone();
 
@@ -1743,27 +3221,27 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
 
                     // Step 6: Apply and verify
                     if apply {
-                        if !json_output {
+                        if narrate {
                             print_step("6️⃣", YELLOW, "Applying fix to file...");
                         }
 
                         // Write the fix
                         if let Err(e) = std::fs::write(path, &patch) {
                             if json_output {
-                                println!(r#"{{"success":false,"stage":"apply","error":"{}"}}"#, e);
+                                println!("{}", HealResult::failure("apply", e.to_string()).to_json());
                             } else {
                                 print_step("❌", RED, &format!("Failed to write fix: {}", e));
                             }
-                            std::process::exit(1);
+                            std::process::exit(exit_code::IO);
                         }
 
-                        if !json_output {
+                        if narrate {
                             print_step("✅", GREEN, "Fix applied to file!");
                             println!();
                         }
 
                         // Try to run again
-                        if !json_output {
+                        if narrate {
                             print_step("7️⃣", YELLOW, "Re-executing fixed code...");
                             spinner("Verifying fix", 600);
                         }
@@ -1782,15 +3260,12 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
                                     if !json_output {
                                         print_step("⚠️", YELLOW, &format!("Warning: Could not save to memory: {}", e));
                                     }
-                                } else if !json_output {
+                                } else if narrate {
                                     print_step("🧠", MAGENTA, "Fix saved to memory for future use");
                                 }
 
                                 if json_output {
-                                    println!(r#"{{"success":true,"needed_healing":true,"fixed":true,"saved_to_memory":true,"result":"{}","patch":"{}"}}"#,
-                                        result,
-                                        patch.replace('\n', "\\n").replace('"', "\\\"")
-                                    );
+                                    println!("{}", HealResult::fixed(patch.as_str(), result.to_string()).saved_to_memory().to_json());
                                 } else {
                                     println!();
                                     print_step("🎉", GREEN, "SUCCESS! Fixed code executes correctly!");
@@ -1805,20 +3280,17 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
                             }
                             Err(e) => {
                                 if json_output {
-                                    println!(r#"{{"success":false,"stage":"verify","error":"{}"}}"#, e.message);
+                                    println!("{}", HealResult::failure("verify", e.message.clone()).to_json());
                                 } else {
                                     print_step("❌", RED, &format!("Fix didn't work: {}", e.message));
                                 }
-                                std::process::exit(1);
+                                std::process::exit(exit_code::RUNTIME);
                             }
                         }
                     } else {
                         // Don't apply, just show the fix
                         if json_output {
-                            println!(r#"{{"success":true,"needed_healing":true,"fixed":false,"patch":"{}","explanation":"{}"}}"#,
-                                patch.replace('\n', "\\n").replace('"', "\\\""),
-                                explanation.replace('"', "\\\"")
-                            );
+                            println!("{}", HealResult::proposed(patch.as_str(), explanation.as_str()).to_json());
                         } else {
                             println!("  {}Use --apply to write the fix to the file{}", DIM, RESET);
                             println!();
@@ -1830,36 +3302,40 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
                     }
                 }
                 Ok(aura::agent::HealingResult::Suggested { suggestions }) => {
+                    // Suggestions arrive sorted by confidence, best first
                     if json_output {
-                        println!(r#"{{"success":true,"needed_healing":true,"fixed":false,"suggestions":{:?}}}"#, suggestions);
+                        println!("{}", HealResult::suggested(suggestions.clone()).to_json());
                     } else {
-                        print_step("💡", YELLOW, "Agent has suggestions:");
-                        for s in &suggestions {
-                            println!("  - {}", s);
+                        print_step("💡", YELLOW, "Agent has suggestions (best first):");
+                        for (i, s) in suggestions.iter().enumerate() {
+                            println!("  {}{}. [{:.0}%]{} {}", BOLD, i + 1, s.confidence * 100.0, RESET, s.code);
+                            if !s.rationale.is_empty() {
+                                println!("     {}{}{}", DIM, s.rationale, RESET);
+                            }
                         }
                     }
                 }
                 Ok(aura::agent::HealingResult::NeedsHuman { reason }) => {
                     if json_output {
-                        println!(r#"{{"success":false,"stage":"heal","needs_human":true,"reason":"{}"}}"#, reason);
+                        println!("{}", HealResult::needs_human(reason).to_json());
                     } else {
                         print_step("👤", YELLOW, &format!("Needs human intervention: {}", reason));
                     }
                 }
                 Ok(aura::agent::HealingResult::CannotFix { reason }) => {
                     if json_output {
-                        println!(r#"{{"success":false,"stage":"heal","cannot_fix":true,"reason":"{}"}}"#, reason);
+                        println!("{}", HealResult::cannot_fix(reason).to_json());
                     } else {
                         print_step("❌", RED, &format!("Cannot fix: {}", reason));
                     }
                 }
                 Err(e) => {
                     if json_output {
-                        println!(r#"{{"success":false,"stage":"heal","error":"{}"}}"#, e);
+                        println!("{}", HealResult::failure("heal", e.to_string()).to_json());
                     } else {
                         print_step("❌", RED, &format!("Healing failed: {}", e));
                     }
-                    std::process::exit(1);
+                    std::process::exit(exit_code::FAILURE);
                 }
             }
         }
@@ -1867,6 +3343,82 @@ fn heal_file(path: &PathBuf, provider: &str, apply: bool, json_output: bool) {
 }
 
 /// Handle memory commands
+fn show_heal_log(limit: usize, outcome: Option<String>, file: Option<String>, json_output: bool) {
+    use aura::agent::{healing_log, HealingOutcome, HEALING_LOG_FILE};
+    use aura::cli_output::{FailureResult, HealLogResult};
+
+    let outcome = match outcome.as_deref().map(|name| (name, HealingOutcome::parse(name))) {
+        None => None,
+        Some((_, Some(outcome))) => Some(outcome),
+        Some((name, None)) => {
+            let message = format!("Unknown outcome '{}'. Use: fixed, suggested, needs_human, cannot_fix, error", name);
+            if json_output {
+                println!("{}", FailureResult::new("heal_log", message).to_json());
+            } else {
+                eprintln!("Error: {}", message);
+            }
+            std::process::exit(exit_code::FAILURE);
+        }
+    };
+
+    let entries = match healing_log::read(HEALING_LOG_FILE) {
+        Ok(entries) => entries,
+        Err(e) => {
+            if json_output {
+                println!("{}", FailureResult::new("heal_log", e.to_string()).to_json());
+            } else {
+                eprintln!("Error reading healing log: {}", e);
+            }
+            std::process::exit(exit_code::IO);
+        }
+    };
+
+    let matching: Vec<_> = entries.into_iter()
+        .filter(|e| outcome.is_none_or(|o| e.outcome == o))
+        .filter(|e| file.as_ref().is_none_or(|f| &e.file == f))
+        .collect();
+    // Tail: las ultimas N entradas, en orden cronologico
+    let shown = &matching[matching.len().saturating_sub(limit)..];
+
+    if json_output {
+        println!("{}", HealLogResult::success(matching.len(), shown.to_vec()).to_json());
+        return;
+    }
+
+    if shown.is_empty() {
+        println!("No healing attempts logged.");
+        if !quiet() {
+            println!();
+            println!("Every 'aura heal' run appends to {}.", HEALING_LOG_FILE);
+        }
+        return;
+    }
+
+    println!("Healing log ({} of {} attempts):", shown.len(), matching.len());
+    println!();
+    for entry in shown {
+        let confidence = entry.confidence
+            .map(|c| format!(" ({:.0}%)", c * 100.0))
+            .unwrap_or_default();
+        let source = if entry.from_memory { "memory".to_string() } else { entry.provider.clone() };
+        println!("  {} {} [{}] {}{}",
+            entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            entry.file,
+            source,
+            entry.outcome.as_str(),
+            confidence
+        );
+        println!("     Error: {}", truncate_str(&entry.error, 60));
+        if let Some(patch) = &entry.patch {
+            println!("     Patch: {}", truncate_str(&patch.replace('\n', " | "), 60));
+        }
+        if let Some(reason) = &entry.reason {
+            println!("     Reason: {}", truncate_str(reason, 60));
+        }
+        println!();
+    }
+}
+
 fn handle_memory(action: Option<MemoryAction>, parent_json: bool) {
     match action {
         None => {
@@ -1882,21 +3434,28 @@ fn handle_memory(action: Option<MemoryAction>, parent_json: bool) {
         Some(MemoryAction::Defaults { set, remove, json }) => {
             handle_memory_defaults(set, remove, json || parent_json);
         }
+        Some(MemoryAction::Export { path, json }) => {
+            handle_memory_export(&path, json || parent_json);
+        }
+        Some(MemoryAction::Import { path, merge, force, json }) => {
+            handle_memory_import(&path, merge, force, json || parent_json);
+        }
     }
 }
 
 fn handle_memory_list(by_usage: bool, json_output: bool) {
     use aura::agent::{HealingMemory, MEMORY_FILE};
+    use aura::cli_output::{FailureResult, MemoryListResult};
 
     let memory = match HealingMemory::load(MEMORY_FILE) {
         Ok(m) => m,
         Err(e) => {
             if json_output {
-                println!(r#"{{"success":false,"error":"{}"}}"#, e);
+                println!("{}", FailureResult::new("memory_list", e.to_string()).to_json());
             } else {
                 eprintln!("Error loading memory: {}", e);
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::IO);
         }
     };
 
@@ -1907,32 +3466,15 @@ fn handle_memory_list(by_usage: bool, json_output: bool) {
     };
 
     if json_output {
-        let patterns_json: Vec<serde_json::Value> = patterns.iter().map(|p| {
-            serde_json::json!({
-                "error": p.error,
-                "context": p.context,
-                "fix": p.fix,
-                "count": p.count,
-                "last_used": p.last_used.to_rfc3339()
-            })
-        }).collect();
-
-        let defaults: serde_json::Map<String, serde_json::Value> = memory.project_defaults
-            .iter()
-            .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
-            .collect();
-
-        println!("{}", serde_json::json!({
-            "success": true,
-            "pattern_count": patterns.len(),
-            "patterns": patterns_json,
-            "defaults": defaults
-        }));
+        let patterns = patterns.into_iter().cloned().collect();
+        println!("{}", MemoryListResult::success(patterns, &memory.project_defaults).to_json());
     } else {
         if patterns.is_empty() {
             println!("No patterns in memory.");
-            println!();
-            println!("Patterns are saved when you run 'aura heal --apply' and the fix works.");
+            if !quiet() {
+                println!();
+                println!("Patterns are saved when you run 'aura heal --apply' and the fix works.");
+            }
         } else {
             println!("Healing Memory ({} patterns):", patterns.len());
             println!();
@@ -1959,16 +3501,17 @@ fn handle_memory_list(by_usage: bool, json_output: bool) {
 
 fn handle_memory_clear(all: bool, json_output: bool) {
     use aura::agent::{HealingMemory, MEMORY_FILE};
+    use aura::cli_output::{FailureResult, MemoryClearResult};
 
     let mut memory = match HealingMemory::load(MEMORY_FILE) {
         Ok(m) => m,
         Err(e) => {
             if json_output {
-                println!(r#"{{"success":false,"error":"{}"}}"#, e);
+                println!("{}", FailureResult::new("memory_clear", e.to_string()).to_json());
             } else {
                 eprintln!("Error loading memory: {}", e);
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::IO);
         }
     };
 
@@ -1982,17 +3525,16 @@ fn handle_memory_clear(all: bool, json_output: bool) {
 
     if let Err(e) = memory.save(MEMORY_FILE) {
         if json_output {
-            println!(r#"{{"success":false,"error":"Failed to save: {}"}}"#, e);
+            println!("{}", FailureResult::new("memory_clear", format!("Failed to save: {}", e)).to_json());
         } else {
             eprintln!("Error saving memory: {}", e);
         }
-        std::process::exit(1);
+        std::process::exit(exit_code::IO);
     }
 
     if json_output {
-        println!(r#"{{"success":true,"patterns_cleared":{},"defaults_cleared":{}}}"#,
-            patterns_cleared, defaults_cleared);
-    } else {
+        println!("{}", MemoryClearResult::success(patterns_cleared, defaults_cleared).to_json());
+    } else if !quiet() {
         println!("Cleared {} patterns from memory.", patterns_cleared);
         if all {
             println!("Cleared {} project defaults.", defaults_cleared);
@@ -2000,18 +3542,96 @@ fn handle_memory_clear(all: bool, json_output: bool) {
     }
 }
 
+fn handle_memory_export(path: &PathBuf, json_output: bool) {
+    use aura::agent::{HealingMemory, MEMORY_FILE};
+    use aura::cli_output::{FailureResult, MemoryExportResult};
+
+    let fail = |message: String| -> ! {
+        if json_output {
+            println!("{}", FailureResult::new("memory_export", message).to_json());
+        } else {
+            eprintln!("Error: {}", message);
+        }
+        std::process::exit(exit_code::FAILURE);
+    };
+
+    let memory = HealingMemory::load(MEMORY_FILE)
+        .unwrap_or_else(|e| fail(format!("Error loading memory: {}", e)));
+    let exported = memory.export();
+    if let Err(e) = exported.save(path) {
+        fail(format!("Failed to write {}: {}", path.display(), e));
+    }
+
+    if json_output {
+        let result = MemoryExportResult::success(
+            path.display().to_string(),
+            exported.pattern_count(),
+            exported.project_defaults.len(),
+        );
+        println!("{}", result.to_json());
+    } else if !quiet() {
+        println!("Exported {} patterns and {} defaults to {}",
+            exported.pattern_count(), exported.project_defaults.len(), path.display());
+    }
+}
+
+fn handle_memory_import(path: &PathBuf, merge: bool, force: bool, json_output: bool) {
+    use aura::agent::{HealingMemory, MEMORY_FILE};
+    use aura::cli_output::{FailureResult, MemoryImportResult};
+
+    let fail = |message: String| -> ! {
+        if json_output {
+            println!("{}", FailureResult::new("memory_import", message).to_json());
+        } else {
+            eprintln!("Error: {}", message);
+        }
+        std::process::exit(exit_code::FAILURE);
+    };
+
+    // `load` trata un archivo inexistente como memoria vacia; aca es un error
+    if !path.exists() {
+        fail(format!("File not found: {}", path.display()));
+    }
+    let incoming = HealingMemory::load(path)
+        .unwrap_or_else(|e| fail(format!("Invalid memory file {}: {}", path.display(), e)));
+    let mut memory = HealingMemory::load(MEMORY_FILE)
+        .unwrap_or_else(|e| fail(format!("Error loading memory: {}", e)));
+
+    let report = memory.import(incoming, merge, force);
+    if let Err(e) = memory.save(MEMORY_FILE) {
+        fail(format!("Failed to save: {}", e));
+    }
+
+    if json_output {
+        println!("{}", MemoryImportResult::success(report, force).to_json());
+        return;
+    }
+
+    let verb = if merge { "merged" } else { "overwritten" };
+    println!("Imported {} new patterns, {} {}; {} defaults set",
+        report.added, report.updated, verb, report.defaults_set);
+    for key in &report.conflicts {
+        if force {
+            println!("  Overwrote default '{}'", key);
+        } else {
+            println!("  Skipped conflicting default '{}' (use --force to overwrite)", key);
+        }
+    }
+}
+
 fn handle_memory_defaults(set: Option<String>, remove: Option<String>, json_output: bool) {
     use aura::agent::{HealingMemory, MEMORY_FILE};
+    use aura::cli_output::{FailureResult, MemoryDefaultsResult};
 
     let mut memory = match HealingMemory::load(MEMORY_FILE) {
         Ok(m) => m,
         Err(e) => {
             if json_output {
-                println!(r#"{{"success":false,"error":"{}"}}"#, e);
+                println!("{}", FailureResult::new("memory_defaults", e.to_string()).to_json());
             } else {
                 eprintln!("Error loading memory: {}", e);
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::IO);
         }
     };
 
@@ -2021,26 +3641,26 @@ fn handle_memory_defaults(set: Option<String>, remove: Option<String>, json_outp
             memory.set_default(key.trim(), value.trim());
             if let Err(e) = memory.save(MEMORY_FILE) {
                 if json_output {
-                    println!(r#"{{"success":false,"error":"Failed to save: {}"}}"#, e);
+                    println!("{}", FailureResult::new("memory_defaults", format!("Failed to save: {}", e)).to_json());
                 } else {
                     eprintln!("Error saving memory: {}", e);
                 }
-                std::process::exit(1);
+                std::process::exit(exit_code::IO);
             }
 
             if json_output {
-                println!(r#"{{"success":true,"action":"set","key":"{}","value":"{}"}}"#, key.trim(), value.trim());
-            } else {
+                println!("{}", MemoryDefaultsResult::set(key.trim(), value.trim()).to_json());
+            } else if !quiet() {
                 println!("Set default: {} = {}", key.trim(), value.trim());
             }
             return;
         } else {
             if json_output {
-                println!(r#"{{"success":false,"error":"Invalid format. Use: key=value"}}"#);
+                println!("{}", FailureResult::new("memory_defaults", "Invalid format. Use: key=value").to_json());
             } else {
                 eprintln!("Invalid format. Use: --set key=value");
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::FAILURE);
         }
     }
 
@@ -2049,45 +3669,39 @@ fn handle_memory_defaults(set: Option<String>, remove: Option<String>, json_outp
         if memory.project_defaults.remove(&key).is_some() {
             if let Err(e) = memory.save(MEMORY_FILE) {
                 if json_output {
-                    println!(r#"{{"success":false,"error":"Failed to save: {}"}}"#, e);
+                    println!("{}", FailureResult::new("memory_defaults", format!("Failed to save: {}", e)).to_json());
                 } else {
                     eprintln!("Error saving memory: {}", e);
                 }
-                std::process::exit(1);
+                std::process::exit(exit_code::IO);
             }
 
             if json_output {
-                println!(r#"{{"success":true,"action":"remove","key":"{}"}}"#, key);
-            } else {
+                println!("{}", MemoryDefaultsResult::removed(key.as_str()).to_json());
+            } else if !quiet() {
                 println!("Removed default: {}", key);
             }
         } else {
             if json_output {
-                println!(r#"{{"success":false,"error":"Key not found: {}"}}"#, key);
+                println!("{}", FailureResult::new("memory_defaults", format!("Key not found: {}", key)).to_json());
             } else {
                 eprintln!("Key not found: {}", key);
             }
-            std::process::exit(1);
+            std::process::exit(exit_code::FAILURE);
         }
         return;
     }
 
     // List defaults (default action)
     if json_output {
-        let defaults: serde_json::Map<String, serde_json::Value> = memory.project_defaults
-            .iter()
-            .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
-            .collect();
-
-        println!("{}", serde_json::json!({
-            "success": true,
-            "defaults": defaults
-        }));
+        println!("{}", MemoryDefaultsResult::list(&memory.project_defaults).to_json());
     } else {
         if memory.project_defaults.is_empty() {
             println!("No project defaults set.");
-            println!();
-            println!("Set defaults with: aura memory defaults --set key=value");
+            if !quiet() {
+                println!();
+                println!("Set defaults with: aura memory defaults --set key=value");
+            }
         } else {
             println!("Project Defaults:");
             for (key, value) in &memory.project_defaults {
@@ -12,9 +12,37 @@ pub struct Program {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Capability {
     pub name: String,
+    /// Argumentos entre parentesis: `+env(DB_URL, API_KEY)`, `+http(timeout: 5000)`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<CapabilityArg>,
     pub span: Span,
 }
 
+impl Capability {
+    /// Argumentos sin valor, en orden (`+env(DB_URL, API_KEY)`)
+    pub fn names(&self) -> Vec<String> {
+        self.args.iter()
+            .filter(|a| a.value.is_none())
+            .map(|a| a.name.clone())
+            .collect()
+    }
+
+    /// Valor de un argumento `nombre: valor`
+    pub fn arg(&self, name: &str) -> Option<&Expr> {
+        self.args.iter()
+            .find(|a| a.name == name)
+            .and_then(|a| a.value.as_ref())
+    }
+}
+
+/// Argumento de una capacidad: un nombre solo o `nombre: literal`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityArg {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Expr>,
+}
+
 /// Definición de nivel superior
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Definition {
@@ -27,9 +55,69 @@ pub enum Definition {
     Goal(GoalDef),
     /// Invariant - constraint that healing cannot violate
     /// Invariants are checked before applying any fix
-    Invariant(Expr),
+    Invariant(InvariantDef),
     /// Observe declaration at top level
     Observe(ObserveDef),
+    /// Explicit HTTP route for `aura serve` (route "GET" "/users/:id" get_user)
+    Route(RouteDef),
+    /// Selective import (import parse, render from "lib.aura"); the loader
+    /// replaces it with the named definitions
+    Import(ImportDef),
+}
+
+impl Definition {
+    /// Nombre con el que otra definicion puede referirse a esta
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Definition::TypeDef(t) => Some(&t.name),
+            Definition::EnumDef(e) => Some(&e.name),
+            Definition::FuncDef(f) => Some(&f.name),
+            _ => None,
+        }
+    }
+
+    /// Nombre corto del tipo de definicion, para listados (`aura symbols`)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Definition::TypeDef(_) => "type",
+            Definition::EnumDef(_) => "enum",
+            Definition::FuncDef(_) => "function",
+            Definition::ApiDef(_) => "api",
+            Definition::TestDef(_) => "test",
+            Definition::Goal(_) => "goal",
+            Definition::Invariant(_) => "invariant",
+            Definition::Observe(_) => "observe",
+            Definition::Route(_) => "route",
+            Definition::Import(_) => "import",
+        }
+    }
+
+    /// Rango del fuente que ocupa la definicion
+    pub fn span(&self) -> &Span {
+        match self {
+            Definition::TypeDef(t) => &t.span,
+            Definition::EnumDef(e) => &e.span,
+            Definition::FuncDef(f) => &f.span,
+            Definition::ApiDef(a) => &a.span,
+            Definition::TestDef(t) => &t.span,
+            Definition::Goal(g) => &g.span,
+            Definition::Invariant(i) => &i.span,
+            Definition::Observe(o) => &o.span,
+            Definition::Route(r) => &r.span,
+            Definition::Import(i) => &i.span,
+        }
+    }
+}
+
+/// Import selectivo (`import parse, render from "lib.aura"`) o de modulos
+/// enteros (`import "models/*.aura"`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportDef {
+    /// Funciones o tipos a traer, en el orden escrito; vacio trae todo
+    pub names: Vec<String>,
+    /// Archivo, directorio o glob, relativo al que importa
+    pub path: String,
+    pub span: Span,
 }
 
 /// Goal definition with optional active check expression
@@ -43,6 +131,13 @@ pub struct GoalDef {
     pub span: Span,
 }
 
+/// Invariant declaration: invariant <expr>
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvariantDef {
+    pub expr: Expr,
+    pub span: Span,
+}
+
 /// Observe definition at top level
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ObserveDef {
@@ -53,6 +148,18 @@ pub struct ObserveDef {
     pub span: Span,
 }
 
+/// Ruta HTTP explicita: route "GET" "/users/:id" get_user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteDef {
+    /// Metodo HTTP en mayusculas (GET, POST, PUT, PATCH, DELETE)
+    pub method: String,
+    /// Path con parametros opcionales (`/users/:id`)
+    pub path: String,
+    /// Nombre de la funcion que atiende la ruta
+    pub handler: String,
+    pub span: Span,
+}
+
 /// Definición de tipo (@User { ... })
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TypeDef {
@@ -88,6 +195,46 @@ pub enum Type {
     Optional(Box<Type>),
 }
 
+impl std::fmt::Display for Type {
+    /// Muestra el tipo con la sintaxis de AURA (`:i`, `[:s]`, `User?`)
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Int => write!(f, ":i"),
+            Type::Float => write!(f, ":f"),
+            Type::String => write!(f, ":s"),
+            Type::Bool => write!(f, ":b"),
+            Type::Timestamp => write!(f, ":ts"),
+            Type::Uuid => write!(f, ":uuid"),
+            Type::Named(name) => write!(f, "{}", name),
+            Type::List(inner) => write!(f, "[{}]", inner),
+            Type::Map(k, v) => write!(f, "{{{}: {}}}", k, v),
+            Type::Optional(inner) => write!(f, "{}?", inner),
+        }
+    }
+}
+
+impl Type {
+    /// Tipo destino de `expr as nombre`: solo `int`, `float` y `string`
+    pub fn from_cast_name(name: &str) -> Option<Type> {
+        match name {
+            "int" => Some(Type::Int),
+            "float" => Some(Type::Float),
+            "string" => Some(Type::String),
+            _ => None,
+        }
+    }
+
+    /// Nombre con el que se escribe el tipo en un `as`
+    pub fn cast_name(&self) -> Option<&'static str> {
+        match self {
+            Type::Int => Some("int"),
+            Type::Float => Some("float"),
+            Type::String => Some("string"),
+            _ => None,
+        }
+    }
+}
+
 /// Anotación (@pk, @min(5), etc.)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Annotation {
@@ -141,6 +288,7 @@ pub enum HealMode {
 }
 
 impl HealMode {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "technical" => HealMode::Technical,
@@ -163,11 +311,26 @@ pub struct FuncDef {
     pub self_heal: Option<SelfHealConfig>,
 }
 
+impl FuncDef {
+    /// Parametro rest, si la funcion es variadica (siempre el ultimo)
+    pub fn rest_param(&self) -> Option<&Param> {
+        self.params.last().filter(|p| p.rest)
+    }
+
+    /// Cantidad minima de argumentos; sin rest es tambien la maxima
+    pub fn min_arity(&self) -> usize {
+        self.params.iter().filter(|p| !p.rest).count()
+    }
+}
+
 /// Parámetro de función
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Param {
     pub name: String,
     pub ty: Option<Type>,
+    /// `...name`: recibe los argumentos restantes como lista
+    #[serde(default)]
+    pub rest: bool,
 }
 
 /// Definición de API (+api("/v1"): ...)
@@ -212,6 +375,8 @@ pub enum Expr {
     Int(i64),
     Float(f64),
     String(String),
+    /// `r"..."`: se usa tal cual, sin interpolar
+    RawString(String),
     Bool(bool),
     Nil,
 
@@ -224,7 +389,8 @@ pub enum Expr {
     // Lista [a, b, c]
     List(Vec<Expr>),
 
-    // Record {a: 1, b: 2}
+    // Record {a: 1, b: 2}; `{...base, a: 1}` guarda `...base` como un
+    // `Spread` con nombre vacio, en su posicion
     Record(Vec<(String, Expr)>),
 
     // Acceso a campo (expr.field)
@@ -294,12 +460,18 @@ pub enum Expr {
     // Interpolated string
     InterpolatedString(Vec<StringPart>),
 
-    // Spread (...expr)
+    // Spread (...expr), dentro de una lista o un record literal
     Spread(Box<Expr>),
 
     // Null coalesce (a ?? b)
     NullCoalesce(Box<Expr>, Box<Expr>),
 
+    // Conversion explicita (x as int); falla en runtime si no se puede
+    Cast {
+        expr: Box<Expr>,
+        ty: Type,
+    },
+
     // Expect - intent verification (expect condition "optional message")
     // If condition is false, registers as expectation failure (not a crash)
     Expect {
@@ -344,6 +516,14 @@ pub enum BinaryOp {
     And,
     Or,
     Concat,
+    /// `x in xs`: pertenencia en lista, clave en record o substring
+    In,
+    /// Operadores de bits, solo entre enteros: `&`, `|`, `^`, `<<`, `>>`
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
 }
 
 /// Operador unario
@@ -357,6 +537,9 @@ pub enum UnaryOp {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MatchArm {
     pub pattern: Pattern,
+    /// Guarda opcional (`Ok(n) where n > 0 -> ...`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guard: Option<Expr>,
     pub body: Expr,
 }
 
@@ -368,3 +551,14 @@ pub enum Pattern {
     Literal(Expr),
     Constructor { name: String, fields: Vec<Pattern> },
 }
+
+impl Pattern {
+    /// Variables que liga el patron, en orden de aparicion
+    pub fn bindings(&self) -> Vec<&str> {
+        match self {
+            Pattern::Ident(name) => vec![name.as_str()],
+            Pattern::Constructor { fields, .. } => fields.iter().flat_map(Pattern::bindings).collect(),
+            Pattern::Wildcard | Pattern::Literal(_) => Vec::new(),
+        }
+    }
+}
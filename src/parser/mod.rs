@@ -72,7 +72,7 @@ impl Parser {
 }
 
 /// Parse capabilities (+http +json)
-fn parse_capabilities(parser: &mut Parser) -> Vec<Capability> {
+fn parse_capabilities(parser: &mut Parser, errors: &mut Vec<ParseError>) -> Vec<Capability> {
     let mut caps = Vec::new();
 
     while let Some(Token::Plus) = parser.peek() {
@@ -80,10 +80,25 @@ fn parse_capabilities(parser: &mut Parser) -> Vec<Capability> {
         parser.advance(); // consume +
 
         if let Some(Token::Ident(name)) = parser.peek().cloned() {
-            let end = parser.current().unwrap().span.end;
+            let mut end = parser.current().unwrap().span.end;
             parser.advance();
+            let args = if parser.peek() == Some(&Token::LParen) {
+                match parse_capability_args(parser) {
+                    Ok((args, args_end)) => {
+                        end = args_end;
+                        args
+                    }
+                    Err(e) => {
+                        errors.push(e);
+                        Vec::new()
+                    }
+                }
+            } else {
+                Vec::new()
+            };
             caps.push(Capability {
                 name,
+                args,
                 span: Span::new(start, end),
             });
         }
@@ -94,6 +109,67 @@ fn parse_capabilities(parser: &mut Parser) -> Vec<Capability> {
     caps
 }
 
+/// `(DB_URL, timeout: 5000)` despues del nombre de una capacidad; retorna
+/// los argumentos y donde termina el `)`
+fn parse_capability_args(parser: &mut Parser) -> Result<(Vec<CapabilityArg>, usize), ParseError> {
+    parser.consume(Token::LParen)?;
+    let mut args = Vec::new();
+
+    loop {
+        let name = match parser.peek().cloned() {
+            Some(Token::Ident(name)) | Some(Token::String(name)) => {
+                parser.advance();
+                name
+            }
+            _ => return Err(ParseError {
+                message: "Expected a name inside '+cap(...)'".to_string(),
+                span: parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0)),
+            }),
+        };
+        let value = if parser.peek() == Some(&Token::Colon) {
+            parser.advance();
+            Some(parse_capability_value(parser)?)
+        } else {
+            None
+        };
+        args.push(CapabilityArg { name, value });
+        match parser.peek() {
+            Some(Token::Comma) => { parser.advance(); }
+            Some(Token::RParen) => break,
+            _ => return Err(ParseError {
+                message: "Expected ',' or ')' in '+cap(...)'".to_string(),
+                span: parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0)),
+            }),
+        }
+    }
+
+    let end = parser.current().map(|t| t.span.end).unwrap_or(0);
+    parser.advance(); // consume )
+    Ok((args, end))
+}
+
+/// Valor de `nombre: valor` en una capacidad: solo literales, se leen antes de ejecutar
+fn parse_capability_value(parser: &mut Parser) -> Result<Expr, ParseError> {
+    let negative = parser.peek() == Some(&Token::Minus);
+    if negative {
+        parser.advance();
+    }
+    let value = match (parser.peek().cloned(), negative) {
+        (Some(Token::Int(n)), _) => Expr::Int(if negative { -n } else { n }),
+        (Some(Token::Float(f)), _) => Expr::Float(if negative { -f } else { f }),
+        (Some(Token::String(s)), false) => Expr::String(s),
+        (Some(Token::True), false) => Expr::Bool(true),
+        (Some(Token::False), false) => Expr::Bool(false),
+        (Some(Token::Nil), false) => Expr::Nil,
+        _ => return Err(ParseError {
+            message: "Expected a literal value in '+cap(name: value)'".to_string(),
+            span: parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0)),
+        }),
+    };
+    parser.advance();
+    Ok(value)
+}
+
 /// Parse a type
 fn parse_type(parser: &mut Parser) -> Result<Type, ParseError> {
     let ty = match parser.peek() {
@@ -270,6 +346,12 @@ fn parse_type_def(parser: &mut Parser) -> Result<TypeDef, ParseError> {
     let mut fields = Vec::new();
 
     while parser.peek() != Some(&Token::RBrace) && !parser.is_at_end() {
+        // `#doc` de un campo: lo recoge `docs::extract_docs` por posicion
+        if let Some(Token::Comment(_)) = parser.peek() {
+            parser.advance();
+            parser.skip_newlines();
+            continue;
+        }
         fields.push(parse_field(parser)?);
         parser.skip_newlines();
     }
@@ -296,11 +378,11 @@ fn parse_expr(parser: &mut Parser) -> Result<Expr, ParseError> {
 }
 
 fn parse_pipe(parser: &mut Parser) -> Result<Expr, ParseError> {
-    let mut left = parse_comparison(parser)?;
+    let mut left = parse_null_coalesce(parser)?;
 
     while let Some(Token::PipeOp) = parser.peek() {
         parser.advance();
-        let right = parse_comparison(parser)?;
+        let right = parse_null_coalesce(parser)?;
 
         match left {
             Expr::Pipe(ref mut exprs) => exprs.push(right),
@@ -311,8 +393,67 @@ fn parse_pipe(parser: &mut Parser) -> Result<Expr, ParseError> {
     Ok(left)
 }
 
+/// `a ?? b`: asocia por la derecha, asi `a ?? b ?? c` solo evalua `c`
+/// si `a` y `b` son nil
+fn parse_null_coalesce(parser: &mut Parser) -> Result<Expr, ParseError> {
+    let left = parse_bit_or(parser)?;
+
+    if let Some(Token::NullCoalesce) = parser.peek() {
+        parser.advance();
+        let right = parse_null_coalesce(parser)?;
+        return Ok(Expr::NullCoalesce(Box::new(left), Box::new(right)));
+    }
+
+    Ok(left)
+}
+
+/// Operadores de bits con la precedencia de C: `|` < `^` < `&` < comparacion.
+/// Los cuerpos de los brazos de un match se parsean con `parse_comparison`,
+/// asi el `|` que separa brazos no se confunde con un or de bits.
+fn parse_bit_or(parser: &mut Parser) -> Result<Expr, ParseError> {
+    parse_binary_level(parser, parse_bit_xor, |token| match token {
+        Token::Pipe => Some(BinaryOp::BitOr),
+        _ => None,
+    })
+}
+
+fn parse_bit_xor(parser: &mut Parser) -> Result<Expr, ParseError> {
+    parse_binary_level(parser, parse_bit_and, |token| match token {
+        Token::Caret => Some(BinaryOp::BitXor),
+        _ => None,
+    })
+}
+
+fn parse_bit_and(parser: &mut Parser) -> Result<Expr, ParseError> {
+    parse_binary_level(parser, parse_comparison, |token| match token {
+        Token::Ampersand => Some(BinaryOp::BitAnd),
+        _ => None,
+    })
+}
+
+/// Un nivel de operadores binarios asociativos a izquierda
+fn parse_binary_level(
+    parser: &mut Parser,
+    operand: fn(&mut Parser) -> Result<Expr, ParseError>,
+    operator: fn(&Token) -> Option<BinaryOp>,
+) -> Result<Expr, ParseError> {
+    let mut left = operand(parser)?;
+
+    while let Some(op) = parser.peek().and_then(operator) {
+        parser.advance();
+        let right = operand(parser)?;
+        left = Expr::BinaryOp {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        };
+    }
+
+    Ok(left)
+}
+
 fn parse_comparison(parser: &mut Parser) -> Result<Expr, ParseError> {
-    let mut left = parse_additive(parser)?;
+    let mut left = parse_shift(parser)?;
 
     while let Some(op) = match parser.peek() {
         Some(Token::EqEq) => Some(BinaryOp::Eq),
@@ -321,10 +462,11 @@ fn parse_comparison(parser: &mut Parser) -> Result<Expr, ParseError> {
         Some(Token::Gt) => Some(BinaryOp::Gt),
         Some(Token::LtEq) => Some(BinaryOp::LtEq),
         Some(Token::GtEq) => Some(BinaryOp::GtEq),
+        Some(Token::In) => Some(BinaryOp::In),
         _ => None,
     } {
         parser.advance();
-        let right = parse_additive(parser)?;
+        let right = parse_shift(parser)?;
         left = Expr::BinaryOp {
             left: Box::new(left),
             op,
@@ -335,6 +477,29 @@ fn parse_comparison(parser: &mut Parser) -> Result<Expr, ParseError> {
     Ok(left)
 }
 
+fn parse_shift(parser: &mut Parser) -> Result<Expr, ParseError> {
+    parse_binary_level(parser, parse_range, |token| match token {
+        Token::Shl => Some(BinaryOp::Shl),
+        Token::Shr => Some(BinaryOp::Shr),
+        _ => None,
+    })
+}
+
+/// `a..b` es azucar para `range(a, b)` (fin exclusivo)
+fn parse_range(parser: &mut Parser) -> Result<Expr, ParseError> {
+    let start = parse_additive(parser)?;
+    if parser.peek() != Some(&Token::Spread) {
+        return Ok(start);
+    }
+    parser.advance();
+    let end = parse_additive(parser)?;
+    Ok(Expr::Call {
+        func: Box::new(Expr::Ident("range".to_string())),
+        args: vec![start, end],
+        has_effect: false,
+    })
+}
+
 fn parse_additive(parser: &mut Parser) -> Result<Expr, ParseError> {
     let mut left = parse_multiplicative(parser)?;
 
@@ -395,8 +560,33 @@ fn parse_unary(parser: &mut Parser) -> Result<Expr, ParseError> {
                 expr: Box::new(expr),
             })
         }
-        _ => parse_call(parser),
+        _ => parse_cast(parser),
+    }
+}
+
+/// `expr as int`: postfijo, liga menos que una llamada y mas que un operador
+/// unario (`-x as int` es `-(x as int)`)
+fn parse_cast(parser: &mut Parser) -> Result<Expr, ParseError> {
+    let mut expr = parse_call(parser)?;
+
+    while let Some(Token::As) = parser.peek() {
+        parser.advance();
+        let span = parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0));
+        let ty = match parser.peek().cloned() {
+            Some(Token::Ident(name)) => Type::from_cast_name(&name),
+            _ => None,
+        };
+        let Some(ty) = ty else {
+            return Err(ParseError {
+                message: "Expected int, float or string after 'as'".to_string(),
+                span,
+            });
+        };
+        parser.advance();
+        expr = Expr::Cast { expr: Box::new(expr), ty };
     }
+
+    Ok(expr)
 }
 
 /// Determina si una expresión puede ser llamada como función
@@ -497,6 +687,12 @@ fn parse_call(parser: &mut Parser) -> Result<Expr, ParseError> {
                     });
                 }
             }
+            Some(Token::Question) => {
+                // Match con valor: expr ? Ok(v) -> v | Err(e) -> ...
+                parser.advance();
+                let arms = parse_pattern_arms(parser)?;
+                return Ok(Expr::Match { expr: Box::new(expr), arms });
+            }
             _ => break,
         }
     }
@@ -518,6 +714,10 @@ fn parse_primary(parser: &mut Parser) -> Result<Expr, ParseError> {
             parser.advance();
             Ok(Expr::String(s))
         }
+        Some(Token::RawString(s)) => {
+            parser.advance();
+            Ok(Expr::RawString(s))
+        }
         Some(Token::True) => {
             parser.advance();
             Ok(Expr::Bool(true))
@@ -549,7 +749,12 @@ fn parse_primary(parser: &mut Parser) -> Result<Expr, ParseError> {
             let mut items = Vec::new();
 
             while parser.peek() != Some(&Token::RBracket) && !parser.is_at_end() {
-                items.push(parse_expr(parser)?);
+                if parser.peek() == Some(&Token::Ellipsis) {
+                    parser.advance();
+                    items.push(Expr::Spread(Box::new(parse_expr(parser)?)));
+                } else {
+                    items.push(parse_expr(parser)?);
+                }
 
                 if let Some(Token::Comma) = parser.peek() {
                     parser.advance();
@@ -564,7 +769,14 @@ fn parse_primary(parser: &mut Parser) -> Result<Expr, ParseError> {
             let mut fields = Vec::new();
 
             while parser.peek() != Some(&Token::RBrace) && !parser.is_at_end() {
-                if let Some(Token::Ident(name)) = parser.peek().cloned() {
+                if parser.peek() == Some(&Token::Ellipsis) {
+                    parser.advance();
+                    fields.push((String::new(), Expr::Spread(Box::new(parse_expr(parser)?))));
+
+                    if let Some(Token::Comma) = parser.peek() {
+                        parser.advance();
+                    }
+                } else if let Some(Token::Ident(name)) = parser.peek().cloned() {
                     parser.advance();
                     parser.consume(Token::Colon)?;
                     let value = parse_expr(parser)?;
@@ -705,17 +917,28 @@ fn parse_block(parser: &mut Parser) -> Result<Expr, ParseError> {
 /// Parse a single item in a block (either a let binding or expression)
 fn parse_block_item(parser: &mut Parser) -> Result<Expr, ParseError> {
     // Check if this looks like a let binding: Ident = expr (but NOT Ident() = or Ident(x) =)
-    if let Some(Token::Ident(name)) = parser.peek().cloned() {
-        if parser.peek_ahead(1) == Some(&Token::Eq) {
-            // Make sure it's not a function definition (no parens before =)
-            parser.advance(); // consume ident
-            parser.advance(); // consume =
-            let value = parse_expr(parser)?;
-            return Ok(Expr::Let {
-                name,
-                value: Box::new(value),
-            });
-        }
+    if let Some(Token::Ident(name)) = parser.peek().cloned()
+        && parser.peek_ahead(1) == Some(&Token::Eq) {
+        // Make sure it's not a function definition (no parens before =)
+        parser.advance(); // consume ident
+        parser.advance(); // consume =
+        let value = parse_expr(parser)?;
+        return Ok(Expr::Let {
+            name,
+            value: Box::new(value),
+        });
+    }
+
+    // `x ??= default` es `x = x ?? default`
+    if let Some(Token::Ident(name)) = parser.peek().cloned()
+        && parser.peek_ahead(1) == Some(&Token::NullCoalesceAssign) {
+        parser.advance(); // consume ident
+        parser.advance(); // consume ??=
+        let default = parse_expr(parser)?;
+        return Ok(Expr::Let {
+            name: name.clone(),
+            value: Box::new(Expr::NullCoalesce(Box::new(Expr::Ident(name)), Box::new(default))),
+        });
     }
 
     // Otherwise parse as a regular expression
@@ -752,7 +975,7 @@ fn parse_match_expr(parser: &mut Parser) -> Result<Expr, ParseError> {
         // Parse body expression
         let body = parse_comparison(parser)?;
 
-        arms.push(MatchArm { pattern, body });
+        arms.push(MatchArm { pattern, guard: None, body });
 
         // Check for more arms (|)
         if matches!(parser.peek(), Some(Token::Pipe)) {
@@ -803,6 +1026,76 @@ fn parse_match_expr(parser: &mut Parser) -> Result<Expr, ParseError> {
     })
 }
 
+/// Parse the arms of a match on a value: `Pat [where cond] -> expr | ...`
+fn parse_pattern_arms(parser: &mut Parser) -> Result<Vec<MatchArm>, ParseError> {
+    let mut arms = Vec::new();
+
+    loop {
+        parser.skip_newlines();
+        let pattern = parse_pattern(parser)?;
+
+        let guard = if let Some(Token::Where) = parser.peek() {
+            parser.advance();
+            Some(parse_comparison(parser)?)
+        } else {
+            None
+        };
+
+        if !matches!(parser.peek(), Some(Token::Arrow)) {
+            return Err(ParseError {
+                message: format!("Expected '->' in match arm, found {:?}", parser.peek()),
+                span: parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0)),
+            });
+        }
+        parser.advance();
+
+        let body = parse_comparison(parser)?;
+        arms.push(MatchArm { pattern, guard, body });
+
+        if matches!(parser.peek(), Some(Token::Pipe)) {
+            parser.advance();
+        } else {
+            break;
+        }
+    }
+
+    Ok(arms)
+}
+
+/// Parse a pattern: `_`, a literal, a binding or a constructor like `Ok(v)`
+fn parse_pattern(parser: &mut Parser) -> Result<Pattern, ParseError> {
+    match parser.peek().cloned() {
+        Some(Token::Underscore) => {
+            parser.advance();
+            Ok(Pattern::Wildcard)
+        }
+        Some(Token::Ident(name)) => {
+            parser.advance();
+            if parser.peek() != Some(&Token::LParen) {
+                return Ok(Pattern::Ident(name));
+            }
+
+            parser.advance(); // consume (
+            let mut fields = Vec::new();
+            while parser.peek() != Some(&Token::RParen) && !parser.is_at_end() {
+                fields.push(parse_pattern(parser)?);
+                if let Some(Token::Comma) = parser.peek() {
+                    parser.advance();
+                }
+            }
+            parser.consume(Token::RParen)?;
+            Ok(Pattern::Constructor { name, fields })
+        }
+        Some(Token::Int(_) | Token::Float(_) | Token::String(_) | Token::RawString(_) | Token::True | Token::False | Token::Nil | Token::Minus) => {
+            Ok(Pattern::Literal(parse_primary(parser)?))
+        }
+        other => Err(ParseError {
+            message: format!("Expected pattern, found {:?}", other),
+            span: parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0)),
+        }),
+    }
+}
+
 /// Parse @self_heal annotation with optional parameters
 /// @self_heal or @self_heal(max_attempts: 3, mode: "technical")
 fn parse_self_heal_config(parser: &mut Parser) -> Result<SelfHealConfig, ParseError> {
@@ -888,16 +1181,34 @@ fn parse_func_def_with_self_heal(parser: &mut Parser, self_heal: Option<SelfHeal
         parser.advance(); // consume (
 
         while parser.peek() != Some(&Token::RParen) && !parser.is_at_end() {
+            if params.last().is_some_and(|p: &Param| p.rest) {
+                return Err(ParseError {
+                    message: "Rest parameter must be the last parameter".to_string(),
+                    span: parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0)),
+                });
+            }
+
+            let rest = parser.peek() == Some(&Token::Ellipsis);
+            if rest {
+                parser.advance(); // consume ...
+            }
+
             if let Some(Token::Ident(param_name)) = parser.peek().cloned() {
                 parser.advance();
                 params.push(Param {
                     name: param_name,
                     ty: None,
+                    rest,
                 });
 
                 if let Some(Token::Comma) = parser.peek() {
                     parser.advance();
                 }
+            } else if rest {
+                return Err(ParseError {
+                    message: "Expected parameter name after '...'".to_string(),
+                    span: parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0)),
+                });
             } else {
                 break;
             }
@@ -907,6 +1218,8 @@ fn parse_func_def_with_self_heal(parser: &mut Parser, self_heal: Option<SelfHeal
     }
 
     parser.consume(Token::Eq)?;
+    // El cuerpo puede empezar en la linea siguiente (`f(x) =\n  x * 2`)
+    parser.skip_newlines();
 
     let body = parse_expr(parser)?;
 
@@ -1120,11 +1433,181 @@ fn parse_reason_expr(parser: &mut Parser) -> Result<Expr, ParseError> {
     }
 }
 
+/// Metodos HTTP aceptados en `route`
+const ROUTE_METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+/// Parse an explicit route: route "GET" "/users/:id" get_user
+/// `route` is a soft keyword (an Ident followed by a string)
+fn parse_route(parser: &mut Parser) -> Result<RouteDef, ParseError> {
+    let start = parser.current().map(|t| t.span.start).unwrap_or(0);
+    parser.advance(); // consume "route"
+
+    let method_span = parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0));
+    let method = match parser.peek().cloned() {
+        Some(Token::String(m)) => {
+            parser.advance();
+            m.to_uppercase()
+        }
+        _ => return Err(ParseError {
+            message: "Expected HTTP method string after 'route'".to_string(),
+            span: method_span,
+        }),
+    };
+    if !ROUTE_METHODS.contains(&method.as_str()) {
+        return Err(ParseError {
+            message: format!("Invalid HTTP method '{}', expected one of {}", method, ROUTE_METHODS.join(", ")),
+            span: method_span,
+        });
+    }
+
+    let path_span = parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0));
+    let path = match parser.peek().cloned() {
+        Some(Token::String(p)) if p.starts_with('/') => {
+            parser.advance();
+            p
+        }
+        _ => return Err(ParseError {
+            message: "Expected route path string starting with '/'".to_string(),
+            span: path_span,
+        }),
+    };
+
+    let handler = match parser.peek().cloned() {
+        Some(Token::Ident(name)) => {
+            parser.advance();
+            name
+        }
+        _ => return Err(ParseError {
+            message: "Expected handler function name after route path".to_string(),
+            span: parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0)),
+        }),
+    };
+
+    let end = parser.tokens.get(parser.pos.saturating_sub(1))
+        .map(|t| t.span.end)
+        .unwrap_or(0);
+
+    Ok(RouteDef {
+        method,
+        path,
+        handler,
+        span: Span::new(start, end),
+    })
+}
+
+/// Parse a selective import: import name1, name2 from "file.aura"
+fn parse_import(parser: &mut Parser) -> Result<ImportDef, ParseError> {
+    let start = parser.current().map(|t| t.span.start).unwrap_or(0);
+    parser.advance(); // consume "import"
+
+    // `import "path"`: el modulo entero, sin `from`
+    if let Some(Token::String(path)) = parser.peek().cloned() {
+        parser.advance();
+        let end = parser.tokens.get(parser.pos.saturating_sub(1))
+            .map(|t| t.span.end)
+            .unwrap_or(0);
+        return Ok(ImportDef { names: Vec::new(), path, span: Span::new(start, end) });
+    }
+
+    // `import` y `from` son palabras clave solo en esta posicion
+    let mut names = Vec::new();
+    while let Some(Token::Ident(name)) = parser.peek().cloned() {
+        if name == "from" {
+            break;
+        }
+        parser.advance();
+        names.push(name);
+        if let Some(Token::Comma) = parser.peek() {
+            parser.advance();
+        }
+    }
+
+    if !matches!(parser.peek(), Some(Token::Ident(kw)) if kw == "from") {
+        return Err(ParseError {
+            message: "Expected 'from' after imported names".to_string(),
+            span: parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0)),
+        });
+    }
+    parser.advance(); // consume "from"
+
+    let path = match parser.peek().cloned() {
+        Some(Token::String(path)) => {
+            parser.advance();
+            path
+        }
+        _ => return Err(ParseError {
+            message: "Expected file path string after 'from'".to_string(),
+            span: parser.current().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0)),
+        }),
+    };
+
+    let end = parser.tokens.get(parser.pos.saturating_sub(1))
+        .map(|t| t.span.end)
+        .unwrap_or(0);
+
+    Ok(ImportDef {
+        names,
+        path,
+        span: Span::new(start, end),
+    })
+}
+
 /// Parse an invariant declaration: invariant <expression>
 /// Invariants are constraints that the healing system cannot violate
-fn parse_invariant(parser: &mut Parser) -> Result<Expr, ParseError> {
+fn parse_invariant(parser: &mut Parser) -> Result<InvariantDef, ParseError> {
+    let start = parser.current().map(|t| t.span.start).unwrap_or(0);
     parser.consume(Token::Invariant)?;
-    parse_expr(parser)
+    let expr = parse_expr(parser)?;
+
+    let end = parser.tokens.get(parser.pos.saturating_sub(1))
+        .map(|t| t.span.end)
+        .unwrap_or(0);
+
+    Ok(InvariantDef { expr, span: Span::new(start, end) })
+}
+
+/// Un comentario `#test nombre: expr` (y no `#testing ...` o `#test` suelto)
+pub fn is_test_comment(comment: &str) -> bool {
+    comment.strip_prefix("#test").is_some_and(|rest| rest.starts_with(char::is_whitespace))
+}
+
+/// Parse `#test nombre: expr`. El lexer entrega el comentario entero como un
+/// token, asi que la expresion se tokeniza aparte y se corren sus spans para
+/// que apunten al archivo.
+fn parse_test_comment(comment: &str, span: &Span) -> Result<TestDef, ParseError> {
+    let rest = &comment["#test".len()..];
+    let invalid = || ParseError {
+        message: "Expected '#test nombre: expresion'".to_string(),
+        span: span.clone(),
+    };
+
+    let colon = rest.find(':').ok_or_else(invalid)?;
+    let name = rest[..colon].trim();
+    let expr_source = &rest[colon + 1..];
+    if name.is_empty() || expr_source.trim().is_empty() {
+        return Err(invalid());
+    }
+
+    let offset = span.start + "#test".len() + colon + 1;
+    let tokens = crate::lexer::tokenize(expr_source).map_err(|errors| {
+        let error = &errors[0];
+        ParseError {
+            message: error.message.clone(),
+            span: Span::new(error.span.start + offset, error.span.end + offset),
+        }
+    })?;
+    let tokens = tokens.into_iter()
+        .map(|t| Spanned::new(t.value, Span::new(t.span.start + offset, t.span.end + offset)))
+        .collect();
+    // Al final de la expresion el parser no tiene token: apuntar al fin del comentario
+    let expr = parse_expression_complete(tokens).map_err(|mut error| {
+        if error.span.end == 0 {
+            error.span = Span::new(span.end, span.end);
+        }
+        error
+    })?;
+
+    Ok(TestDef { name: name.to_string(), expr, span: span.clone() })
 }
 
 /// Parse a definition (type, function, goal, invariant, observe, or annotated function)
@@ -1160,9 +1643,24 @@ fn parse_definition(parser: &mut Parser) -> Result<Option<Definition>, ParseErro
         Some(Token::At) => {
             Ok(Some(Definition::TypeDef(parse_type_def(parser)?)))
         }
+        Some(Token::Ident(name))
+            if name == "route" && matches!(parser.peek_ahead(1), Some(Token::String(_))) =>
+        {
+            Ok(Some(Definition::Route(parse_route(parser)?)))
+        }
+        Some(Token::Ident(name))
+            if name == "import" && matches!(parser.peek_ahead(1), Some(Token::Ident(_) | Token::String(_))) =>
+        {
+            Ok(Some(Definition::Import(parse_import(parser)?)))
+        }
         Some(Token::Ident(_)) => {
             Ok(Some(Definition::FuncDef(parse_func_def(parser)?)))
         }
+        Some(Token::Comment(comment)) if is_test_comment(comment) => {
+            let comment = comment.clone();
+            let span = parser.advance().map(|t| t.span.clone()).unwrap_or(Span::new(0, 0));
+            Ok(Some(Definition::TestDef(parse_test_comment(&comment, &span)?)))
+        }
         None => Ok(None),
         _ => {
             parser.advance(); // Skip unknown token
@@ -1178,7 +1676,7 @@ pub fn parse(tokens: Vec<Spanned<Token>>) -> Result<Program, Vec<ParseError>> {
 
     parser.skip_newlines();
 
-    let capabilities = parse_capabilities(&mut parser);
+    let capabilities = parse_capabilities(&mut parser, &mut errors);
 
     let mut definitions = Vec::new();
 
@@ -1324,7 +1822,7 @@ pub fn looks_like_function_def(tokens: &[Spanned<Token>]) -> bool {
     }
 
     // Si es directamente =, es una definicion sin parametros (constante/funcion sin args)
-    if matches!(tokens[i].value, Token::Eq) {
+    if i < tokens.len() && matches!(tokens[i].value, Token::Eq) {
         return true;
     }
 
@@ -1345,6 +1843,34 @@ mod tests {
         assert_eq!(program.capabilities[1].name, "json");
     }
 
+    #[test]
+    fn test_parse_capability_args() {
+        let source = "+env(DB_URL, API_KEY) +http\nmain = 1\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        assert_eq!(program.capabilities[0].name, "env");
+        assert_eq!(program.capabilities[0].names(), vec!["DB_URL".to_string(), "API_KEY".to_string()]);
+        let span = &program.capabilities[0].span;
+        assert_eq!(&source[span.start..span.end], "+env(DB_URL, API_KEY)");
+        assert_eq!(program.capabilities[1].name, "http");
+        assert!(program.capabilities[1].args.is_empty());
+
+        let source = "+http(timeout: 5000, retries: -1, base: \"https://api\", verbose)\nmain = 1\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        let http = &program.capabilities[0];
+        assert_eq!(http.arg("timeout"), Some(&Expr::Int(5000)));
+        assert_eq!(http.arg("retries"), Some(&Expr::Int(-1)));
+        assert_eq!(http.arg("base"), Some(&Expr::String("https://api".to_string())));
+        assert_eq!(http.arg("verbose"), None);
+        assert_eq!(http.names(), vec!["verbose".to_string()]);
+
+        let err = parse(tokenize("+env()\nmain = 1\n").unwrap()).unwrap_err();
+        assert!(err[0].message.contains("+cap(...)"), "{}", err[0].message);
+        let err = parse(tokenize("+env(A B)\nmain = 1\n").unwrap()).unwrap_err();
+        assert!(err[0].message.contains("','"), "{}", err[0].message);
+        let err = parse(tokenize("+http(timeout: x)\nmain = 1\n").unwrap()).unwrap_err();
+        assert!(err[0].message.contains("literal"), "{}", err[0].message);
+    }
+
     #[test]
     fn test_parse_simple_function() {
         let tokens = tokenize("+http\nadd(a b) = a + b\n").unwrap();
@@ -1358,6 +1884,72 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_match_on_value() {
+        let expr = parse_expression(tokenize("r ? Ok(v) -> v | Err(_) -> 0 | _ -> nil").unwrap()).unwrap();
+        let Expr::Match { expr, arms } = expr else { panic!("Expected match") };
+        assert_eq!(*expr, Expr::Ident("r".to_string()));
+        assert_eq!(arms.len(), 3);
+        assert_eq!(
+            arms[0].pattern,
+            Pattern::Constructor { name: "Ok".to_string(), fields: vec![Pattern::Ident("v".to_string())] }
+        );
+        assert_eq!(arms[0].pattern.bindings(), vec!["v"]);
+        assert!(arms[1].pattern.bindings().is_empty());
+        assert_eq!(arms[2].pattern, Pattern::Wildcard);
+    }
+
+    #[test]
+    fn test_parse_match_guard() {
+        let expr = parse_expression(tokenize("r ? Ok(n) where n > 0 -> n | _ -> 0").unwrap()).unwrap();
+        let Expr::Match { arms, .. } = expr else { panic!("Expected match") };
+        assert!(matches!(arms[0].guard, Some(Expr::BinaryOp { op: BinaryOp::Gt, .. })));
+        assert_eq!(arms[1].guard, None);
+    }
+
+    #[test]
+    fn test_parse_selective_import() {
+        let program = parse(tokenize("import parse, render from \"lib.aura\"\nmain = 1\n").unwrap()).unwrap();
+        let Definition::Import(import) = &program.definitions[0] else { panic!("Expected import") };
+        assert_eq!(import.names, vec!["parse", "render"]);
+        assert_eq!(import.path, "lib.aura");
+
+        // Fuera de esa forma `import` sigue siendo un nombre comun
+        let program = parse(tokenize("import = 1\n").unwrap()).unwrap();
+        assert!(matches!(&program.definitions[0], Definition::FuncDef(f) if f.name == "import"));
+
+        let errors = parse(tokenize("import a b\n").unwrap()).unwrap_err();
+        assert!(errors[0].message.contains("'from'"));
+    }
+
+    #[test]
+    fn test_parse_whole_module_import() {
+        let program = parse(tokenize("import \"models/*.aura\"\nmain = 1\n").unwrap()).unwrap();
+        let Definition::Import(import) = &program.definitions[0] else { panic!("Expected import") };
+        assert!(import.names.is_empty());
+        assert_eq!(import.path, "models/*.aura");
+        assert!(matches!(&program.definitions[1], Definition::FuncDef(f) if f.name == "main"));
+    }
+
+    #[test]
+    fn test_parse_rest_param() {
+        let program = parse(tokenize("log(level, ...parts) = parts\n").unwrap()).unwrap();
+        let Definition::FuncDef(f) = &program.definitions[0] else { panic!("Expected function definition") };
+        assert!(!f.params[0].rest);
+        assert!(f.params[1].rest);
+        assert_eq!(f.rest_param().map(|p| p.name.as_str()), Some("parts"));
+        assert_eq!(f.min_arity(), 1);
+    }
+
+    #[test]
+    fn test_rest_param_must_be_last() {
+        let errors = parse(tokenize("f(...xs, y) = xs\n").unwrap()).unwrap_err();
+        assert!(errors[0].message.contains("must be the last"));
+
+        let errors = parse(tokenize("f(a, ...) = a\n").unwrap()).unwrap_err();
+        assert!(errors[0].message.contains("after '...'"));
+    }
+
     #[test]
     fn test_parse_function_with_effect() {
         let tokens = tokenize("+http\nfetch!(url) = http.get(url)\n").unwrap();
@@ -1463,6 +2055,41 @@ main = 42
         }
     }
 
+    #[test]
+    fn test_parse_route() {
+        let source = "route \"get\" \"/users/:id\" get_user\nget_user(id) = id";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+
+        assert_eq!(program.definitions.len(), 2);
+        match &program.definitions[0] {
+            Definition::Route(r) => {
+                assert_eq!(r.method, "GET");
+                assert_eq!(r.path, "/users/:id");
+                assert_eq!(r.handler, "get_user");
+            }
+            other => panic!("Expected Route, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_route_is_soft_keyword() {
+        // Una funcion llamada route sigue siendo valida
+        let program = parse(tokenize("route(x) = x\nroute2 = 1").unwrap()).unwrap();
+        assert!(matches!(&program.definitions[0], Definition::FuncDef(f) if f.name == "route"));
+    }
+
+    #[test]
+    fn test_parse_route_invalid_method() {
+        let errors = parse(tokenize("route \"FETCH\" \"/users\" list_users").unwrap()).unwrap_err();
+        assert!(errors[0].message.contains("Invalid HTTP method"));
+    }
+
+    #[test]
+    fn test_parse_route_requires_absolute_path() {
+        let errors = parse(tokenize("route \"GET\" \"users\" list_users").unwrap()).unwrap_err();
+        assert!(errors[0].message.contains("starting with '/'"));
+    }
+
     #[test]
     fn test_parse_observe() {
         let source = r#"+http
@@ -1579,6 +2206,21 @@ normal_func() = 42
         assert!(looks_like_function_def(&tokens));
     }
 
+    #[test]
+    fn test_func_def_body_on_next_line() {
+        let tokens = tokenize("double(x) =\n  x * 2").unwrap();
+        let func = parse_function_def(tokens).unwrap();
+        assert_eq!(func.name, "double");
+        assert!(matches!(func.body, Expr::BinaryOp { .. }));
+    }
+
+    #[test]
+    fn test_looks_like_function_def_call_expression() {
+        // Una llamada sin = no es definicion (y no debe salirse del slice)
+        let tokens = tokenize("double(21)").unwrap();
+        assert!(!looks_like_function_def(&tokens));
+    }
+
     #[test]
     fn test_parse_simple_invariant() {
         let source = r#"+http
@@ -1589,7 +2231,7 @@ main = 42
         let program = parse(tokens).unwrap();
 
         assert_eq!(program.definitions.len(), 2); // 1 invariant + 1 function
-        if let Definition::Invariant(expr) = &program.definitions[0] {
+        if let Definition::Invariant(InvariantDef { expr, .. }) = &program.definitions[0] {
             // Should be a BinaryOp with NotEq
             assert!(matches!(expr, Expr::BinaryOp { op: BinaryOp::NotEq, .. }));
         } else {
@@ -1629,7 +2271,7 @@ main = 42
         let tokens = tokenize(source).unwrap();
         let program = parse(tokens).unwrap();
 
-        if let Definition::Invariant(expr) = &program.definitions[0] {
+        if let Definition::Invariant(InvariantDef { expr, .. }) = &program.definitions[0] {
             // Should be a UnaryOp with Not
             assert!(matches!(expr, Expr::UnaryOp { op: UnaryOp::Not, .. }));
         } else {
@@ -1637,6 +2279,165 @@ main = 42
         }
     }
 
+    #[test]
+    fn test_parse_spread() {
+        let program = parse(tokenize("main = [...a, 1, ...b]\n").unwrap()).unwrap();
+        let Definition::FuncDef(f) = &program.definitions[0] else {
+            panic!("Expected function definition");
+        };
+        let Expr::List(items) = &f.body else {
+            panic!("Expected list, got {:?}", f.body);
+        };
+        assert!(matches!(&items[0], Expr::Spread(inner) if **inner == Expr::Ident("a".to_string())));
+        assert_eq!(items[1], Expr::Int(1));
+        assert!(matches!(&items[2], Expr::Spread(_)));
+
+        let program = parse(tokenize("main = {...base, x: 1}\n").unwrap()).unwrap();
+        let Definition::FuncDef(f) = &program.definitions[0] else {
+            panic!("Expected function definition");
+        };
+        let Expr::Record(fields) = &f.body else {
+            panic!("Expected record, got {:?}", f.body);
+        };
+        assert_eq!(fields[0].0, "");
+        assert!(matches!(&fields[0].1, Expr::Spread(_)));
+        assert_eq!(fields[1], ("x".to_string(), Expr::Int(1)));
+    }
+
+    #[test]
+    fn test_parse_in_operator() {
+        let program = parse(tokenize("main = 1 + 1 in [2] == true\n").unwrap()).unwrap();
+        let Definition::FuncDef(f) = &program.definitions[0] else {
+            panic!("Expected function definition");
+        };
+        // Precedencia de comparacion: ((1 + 1) in [2]) == true
+        let Expr::BinaryOp { op: BinaryOp::Eq, left, .. } = &f.body else {
+            panic!("Expected ==, got {:?}", f.body);
+        };
+        let Expr::BinaryOp { op: BinaryOp::In, left, .. } = left.as_ref() else {
+            panic!("Expected in, got {:?}", left);
+        };
+        assert!(matches!(left.as_ref(), Expr::BinaryOp { op: BinaryOp::Add, .. }));
+    }
+
+    #[test]
+    fn test_parse_null_coalesce_chain() {
+        let program = parse(tokenize("main = a ?? b | 1 ?? c\n").unwrap()).unwrap();
+        let Definition::FuncDef(f) = &program.definitions[0] else {
+            panic!("Expected function definition");
+        };
+        // Por la derecha y debajo de `|`: a ?? ((b | 1) ?? c)
+        let Expr::NullCoalesce(left, right) = &f.body else {
+            panic!("Expected ??, got {:?}", f.body);
+        };
+        assert_eq!(**left, Expr::Ident("a".to_string()));
+        let Expr::NullCoalesce(middle, last) = right.as_ref() else {
+            panic!("Expected ??, got {:?}", right);
+        };
+        assert!(matches!(middle.as_ref(), Expr::BinaryOp { op: BinaryOp::BitOr, .. }));
+        assert_eq!(**last, Expr::Ident("c".to_string()));
+    }
+
+    #[test]
+    fn test_parse_null_coalesce_assign() {
+        let program = parse(tokenize("main = : x ??= 1; x\n").unwrap()).unwrap();
+        let Definition::FuncDef(f) = &program.definitions[0] else {
+            panic!("Expected function definition");
+        };
+        let Expr::Block(items) = &f.body else {
+            panic!("Expected block, got {:?}", f.body);
+        };
+        assert_eq!(items[0], Expr::Let {
+            name: "x".to_string(),
+            value: Box::new(Expr::NullCoalesce(
+                Box::new(Expr::Ident("x".to_string())),
+                Box::new(Expr::Int(1)),
+            )),
+        });
+    }
+
+    #[test]
+    fn test_parse_test_comment() {
+        let source = "#test suma : 1 + 1 == 2\n# comentario normal\nmain = 1\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        assert_eq!(program.definitions.len(), 2);
+        let Definition::TestDef(t) = &program.definitions[0] else {
+            panic!("Expected test definition, got {:?}", program.definitions[0]);
+        };
+        assert_eq!(t.name, "suma");
+        let Expr::BinaryOp { op: BinaryOp::Eq, .. } = &t.expr else {
+            panic!("Expected ==, got {:?}", t.expr);
+        };
+
+        // Los errores apuntan al archivo, no al comentario
+        let err = parse(tokenize("main = 1\n#test roto: 1 +\n").unwrap()).unwrap_err();
+        assert!(err[0].span.start >= "main = 1\n#test roto:".len(), "{:?}", err[0]);
+
+        let err = parse(tokenize("#test sin expresion\nmain = 1\n").unwrap()).unwrap_err();
+        assert!(err[0].message.contains("#test nombre: expresion"), "{}", err[0].message);
+    }
+
+    #[test]
+    fn test_parse_cast_precedence() {
+        let program = parse(tokenize("main = -user.age as float * 2\n").unwrap()).unwrap();
+        let Definition::FuncDef(f) = &program.definitions[0] else {
+            panic!("Expected function definition");
+        };
+        // (-(user.age as float)) * 2: la llamada/acceso liga mas fuerte que `as`
+        let Expr::BinaryOp { op: BinaryOp::Mul, left, .. } = &f.body else {
+            panic!("Expected *, got {:?}", f.body);
+        };
+        let Expr::UnaryOp { op: UnaryOp::Neg, expr } = left.as_ref() else {
+            panic!("Expected -, got {:?}", left);
+        };
+        let Expr::Cast { expr, ty } = expr.as_ref() else {
+            panic!("Expected as, got {:?}", expr);
+        };
+        assert_eq!(*ty, Type::Float);
+        assert!(matches!(expr.as_ref(), Expr::FieldAccess(_, _)));
+
+        let err = parse(tokenize("main = 1 as bool\n").unwrap()).unwrap_err();
+        assert_eq!(err[0].message, "Expected int, float or string after 'as'");
+    }
+
+    #[test]
+    fn test_parse_bitwise_precedence() {
+        let program = parse(tokenize("main = a | b ^ c & d == 1 << 2 + 1\n").unwrap()).unwrap();
+        let Definition::FuncDef(f) = &program.definitions[0] else {
+            panic!("Expected function definition");
+        };
+        // a | (b ^ (c & (d == (1 << (2 + 1)))))
+        let Expr::BinaryOp { op: BinaryOp::BitOr, right, .. } = &f.body else {
+            panic!("Expected |, got {:?}", f.body);
+        };
+        let Expr::BinaryOp { op: BinaryOp::BitXor, right, .. } = right.as_ref() else {
+            panic!("Expected ^, got {:?}", right);
+        };
+        let Expr::BinaryOp { op: BinaryOp::BitAnd, right, .. } = right.as_ref() else {
+            panic!("Expected &, got {:?}", right);
+        };
+        let Expr::BinaryOp { op: BinaryOp::Eq, right, .. } = right.as_ref() else {
+            panic!("Expected ==, got {:?}", right);
+        };
+        let Expr::BinaryOp { op: BinaryOp::Shl, right, .. } = right.as_ref() else {
+            panic!("Expected <<, got {:?}", right);
+        };
+        assert!(matches!(right.as_ref(), Expr::BinaryOp { op: BinaryOp::Add, .. }));
+    }
+
+    #[test]
+    fn test_parse_bit_or_keeps_match_arms() {
+        // El `|` despues de un brazo sigue separando brazos
+        let program = parse(tokenize("main = x ? Ok(v) -> v | Err(e) -> 0\n").unwrap()).unwrap();
+        let Definition::FuncDef(f) = &program.definitions[0] else {
+            panic!("Expected function definition");
+        };
+        let Expr::Match { arms, .. } = &f.body else {
+            panic!("Expected match, got {:?}", f.body);
+        };
+        assert_eq!(arms.len(), 2);
+    }
+
     #[test]
     fn test_parse_invariant_with_comparison() {
         let source = r#"+http
@@ -1646,7 +2447,7 @@ main = 42
         let tokens = tokenize(source).unwrap();
         let program = parse(tokens).unwrap();
 
-        if let Definition::Invariant(expr) = &program.definitions[0] {
+        if let Definition::Invariant(InvariantDef { expr, .. }) = &program.definitions[0] {
             // Should be a BinaryOp with LtEq
             assert!(matches!(expr, Expr::BinaryOp { op: BinaryOp::LtEq, .. }));
         } else {
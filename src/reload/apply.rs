@@ -2,9 +2,19 @@
 //!
 //! Este modulo toma un `CodeDiff` y lo aplica a una VM existente,
 //! agregando nuevas funciones y tipos sin perder el estado actual.
+//!
+//! ## Estado tras recargar una funcion
+//!
+//! Las variables guardan valores ya evaluados, no expresiones: si antes de
+//! la recarga se hizo `x = double(5)`, `x` conserva el resultado calculado
+//! con el cuerpo viejo. Las llamadas posteriores a `double` usan el cuerpo
+//! nuevo, incluso a traves de una variable que guarde la funcion
+//! (`f = double`), porque `Value::Function` se resuelve por nombre en cada
+//! llamada.
 
 use crate::vm::VM;
 use crate::parser::{FuncDef, TypeDef, Program, Definition};
+use crate::types;
 use super::diff::{CodeDiff, ReloadError};
 
 /// Resultado de aplicar un diff
@@ -18,6 +28,10 @@ pub struct ApplyResult {
     pub types_added: usize,
     /// Numero de tipos actualizados
     pub types_updated: usize,
+    /// Numero de funciones eliminadas
+    pub functions_removed: usize,
+    /// Numero de tipos eliminados
+    pub types_removed: usize,
     /// Advertencias durante la aplicacion
     pub warnings: Vec<String>,
 }
@@ -30,7 +44,12 @@ impl ApplyResult {
 
     /// Total de cambios aplicados
     pub fn total_changes(&self) -> usize {
-        self.functions_added + self.functions_updated + self.types_added + self.types_updated
+        self.functions_added
+            + self.functions_updated
+            + self.functions_removed
+            + self.types_added
+            + self.types_updated
+            + self.types_removed
     }
 
     /// Verifica si hubo cambios
@@ -58,12 +77,18 @@ impl std::fmt::Display for ApplyResult {
         if self.functions_updated > 0 {
             parts.push(format!("~{} funciones", self.functions_updated));
         }
+        if self.functions_removed > 0 {
+            parts.push(format!("-{} funciones", self.functions_removed));
+        }
         if self.types_added > 0 {
             parts.push(format!("+{} tipos", self.types_added));
         }
         if self.types_updated > 0 {
             parts.push(format!("~{} tipos", self.types_updated));
         }
+        if self.types_removed > 0 {
+            parts.push(format!("-{} tipos", self.types_removed));
+        }
 
         write!(f, "{}", parts.join(", "))
     }
@@ -72,8 +97,8 @@ impl std::fmt::Display for ApplyResult {
 /// Aplica un diff a la VM
 ///
 /// Esta funcion toma las diferencias calculadas por `compute_diff` y las
-/// aplica al environment de la VM, agregando nuevas funciones/tipos y
-/// actualizando las existentes.
+/// aplica al environment de la VM, agregando nuevas funciones/tipos,
+/// actualizando las existentes y eliminando las que ya no estan.
 ///
 /// # Argumentos
 ///
@@ -115,9 +140,77 @@ pub fn apply_diff(vm: &mut VM, diff: CodeDiff) -> Result<ApplyResult, ReloadErro
         update_type(vm, ty, &mut result)?;
     }
 
+    // Eliminar funciones y tipos que ya no existen
+    for name in diff.removed_functions {
+        remove_function(vm, &name, &mut result);
+    }
+    for name in diff.removed_types {
+        remove_type(vm, &name, &mut result);
+    }
+
     Ok(result)
 }
 
+/// Aplica un diff a la VM solo si el programa resultante pasa el type checker
+///
+/// Construye el programa combinado (definiciones viejas + nuevas) y lo
+/// verifica con `types::check` antes de tocar la VM. Si hay errores de
+/// tipos, la VM queda intacta y se retorna `ReloadError::TypeError`.
+///
+/// # Ejemplo
+///
+/// ```rust,ignore
+/// let diff = compute_diff(&program, "double(x) = y * 2")?;
+/// match apply_diff_checked(&mut vm, &program, diff) {
+///     Err(ReloadError::TypeError(errors)) => { /* y no esta definido */ }
+///     _ => {}
+/// }
+/// ```
+pub fn apply_diff_checked(
+    vm: &mut VM,
+    program: &Program,
+    diff: CodeDiff,
+) -> Result<ApplyResult, ReloadError> {
+    let merged = merge_program(program, &diff);
+    types::check(&merged).map_err(ReloadError::TypeError)?;
+    apply_diff(vm, diff)
+}
+
+/// Combina el programa actual con las definiciones del diff
+///
+/// Las definiciones modificadas reemplazan a las originales en su lugar,
+/// las eliminadas se quitan y las nuevas se agregan al final.
+fn merge_program(program: &Program, diff: &CodeDiff) -> Program {
+    let mut merged = program.clone();
+
+    merged.definitions.retain(|def| match def {
+        Definition::FuncDef(func) => !diff.removed_functions.contains(&func.name),
+        Definition::TypeDef(ty) => !diff.removed_types.contains(&ty.name),
+        _ => true,
+    });
+
+    for def in merged.definitions.iter_mut() {
+        match def {
+            Definition::FuncDef(func) => {
+                if let Some(new_func) = diff.modified_functions.iter().find(|f| f.name == func.name) {
+                    *func = new_func.clone();
+                }
+            }
+            Definition::TypeDef(ty) => {
+                if let Some(new_ty) = diff.modified_types.iter().find(|t| t.name == ty.name) {
+                    *ty = new_ty.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    merged.definitions.extend(diff.added_types.iter().cloned().map(Definition::TypeDef));
+    merged.definitions.extend(diff.added_functions.iter().cloned().map(Definition::FuncDef));
+
+    merged
+}
+
 /// Aplica una funcion nueva a la VM
 fn apply_function(
     vm: &mut VM,
@@ -165,6 +258,30 @@ fn update_function(
     Ok(())
 }
 
+/// Elimina una funcion de la VM
+fn remove_function(vm: &mut VM, name: &str, result: &mut ApplyResult) {
+    if vm.remove_function(name).is_some() {
+        result.functions_removed += 1;
+    } else {
+        result.warnings.push(format!(
+            "Funcion '{}' no existia, no se elimino nada",
+            name
+        ));
+    }
+}
+
+/// Elimina un tipo de la VM
+fn remove_type(vm: &mut VM, name: &str, result: &mut ApplyResult) {
+    if vm.remove_type(name).is_some() {
+        result.types_removed += 1;
+    } else {
+        result.warnings.push(format!(
+            "Tipo '{}' no existia, no se elimino nada",
+            name
+        ));
+    }
+}
+
 /// Aplica un tipo nuevo a la VM
 ///
 /// Usa el mecanismo de carga de Program para agregar el tipo,
@@ -317,6 +434,51 @@ mod tests {
         assert!(result.warnings[0].contains("User"));
     }
 
+    #[test]
+    fn test_apply_full_replace_removes_function() {
+        let (mut vm, program) = setup_vm("+http\ndouble(x) = x * 2\ntriple(x) = x * 3\nmain = double(5)\n");
+
+        let diff = super::super::diff::compute_full_diff(
+            &program,
+            "+http\ndouble(x) = x * 2\nmain = double(5)\n",
+        )
+        .unwrap();
+        let result = apply_diff(&mut vm, diff).unwrap();
+
+        assert_eq!(result.functions_removed, 1);
+        assert_eq!(result.functions_added, 0);
+        let funcs = vm.list_functions();
+        assert!(funcs.contains(&"double".to_string()));
+        assert!(!funcs.contains(&"triple".to_string()));
+        assert!(result.to_string().contains("-1 funciones"));
+    }
+
+    #[test]
+    fn test_apply_partial_reload_keeps_functions() {
+        let (mut vm, program) = setup_vm("+http\ndouble(x) = x * 2\ntriple(x) = x * 3\nmain = double(5)\n");
+
+        let diff = super::super::diff::compute_diff(&program, "quad(x) = x * 4").unwrap();
+        let result = apply_diff(&mut vm, diff).unwrap();
+
+        assert_eq!(result.functions_added, 1);
+        assert_eq!(result.functions_removed, 0);
+        let funcs = vm.list_functions();
+        assert!(funcs.contains(&"triple".to_string()));
+        assert!(funcs.contains(&"quad".to_string()));
+    }
+
+    #[test]
+    fn test_apply_checked_rejects_removal_still_referenced() {
+        let (mut vm, program) = setup_vm("+http\ndouble(x) = x * 2\nmain = double(5)\n");
+
+        // main sigue llamando a double, que desaparece
+        let diff = super::super::diff::compute_full_diff(&program, "+http\nmain = double(5)\n").unwrap();
+        let result = apply_diff_checked(&mut vm, &program, diff);
+
+        assert!(matches!(result, Err(ReloadError::TypeError(_))));
+        assert!(vm.list_functions().contains(&"double".to_string()));
+    }
+
     #[test]
     fn test_apply_result_display() {
         let mut result = ApplyResult::new();
@@ -340,6 +502,37 @@ mod tests {
         assert!(vm.list_functions().contains(&"double".to_string()));
     }
 
+    #[test]
+    fn test_apply_checked_valid_reload() {
+        let (mut vm, program) = setup_vm("+http\ndouble(x) = x * 2\nmain = double(5)\n");
+
+        let diff = super::super::diff::compute_diff(&program, "triple(x) = double(x) + x").unwrap();
+        let result = apply_diff_checked(&mut vm, &program, diff).unwrap();
+
+        assert_eq!(result.functions_added, 1);
+        assert!(vm.list_functions().contains(&"triple".to_string()));
+    }
+
+    #[test]
+    fn test_apply_checked_rejects_undefined_name() {
+        let (mut vm, program) = setup_vm("+http\ndouble(x) = x * 2\nmain = double(5)\n");
+
+        let diff = super::super::diff::compute_diff(&program, "double(x) = y * 2\nbroken(x) = missing(x)").unwrap();
+        let result = apply_diff_checked(&mut vm, &program, diff);
+
+        match result {
+            Err(ReloadError::TypeError(errors)) => {
+                assert!(errors.iter().any(|e| e.message.contains("y")));
+                assert!(errors.iter().any(|e| e.message.contains("missing")));
+            }
+            other => panic!("Expected TypeError, got {:?}", other),
+        }
+
+        // La VM no debe haber cambiado
+        assert!(!vm.list_functions().contains(&"broken".to_string()));
+        assert_eq!(vm.call_by_name("double", vec![crate::vm::Value::Int(5)]).unwrap(), crate::vm::Value::Int(10));
+    }
+
     #[test]
     fn test_multiple_apply_operations() {
         let (mut vm, program) = setup_vm("+http\nmain = 42\n");
@@ -5,7 +5,8 @@
 
 use std::collections::HashMap;
 use crate::lexer::tokenize;
-use crate::parser::{parse, Program, Definition, FuncDef, TypeDef};
+use crate::parser::{parse, Annotation, Program, Definition, FuncDef, TypeDef};
+use crate::types::TypeError;
 
 /// Error durante el proceso de hot reload
 #[derive(Debug, Clone)]
@@ -18,6 +19,8 @@ pub enum ReloadError {
     IncompatibleChange(String),
     /// Error al aplicar cambios
     ApplyError(String),
+    /// El programa resultante no pasa el type checker
+    TypeError(Vec<TypeError>),
 }
 
 impl std::fmt::Display for ReloadError {
@@ -27,6 +30,10 @@ impl std::fmt::Display for ReloadError {
             ReloadError::ParseError(msg) => write!(f, "Error de parseo: {}", msg),
             ReloadError::IncompatibleChange(msg) => write!(f, "Cambio incompatible: {}", msg),
             ReloadError::ApplyError(msg) => write!(f, "Error al aplicar: {}", msg),
+            ReloadError::TypeError(errors) => {
+                let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+                write!(f, "Error de tipos: {}", messages.join("; "))
+            }
         }
     }
 }
@@ -44,6 +51,10 @@ pub struct CodeDiff {
     pub added_types: Vec<TypeDef>,
     /// Tipos que ya existian pero fueron modificados
     pub modified_types: Vec<TypeDef>,
+    /// Funciones que existian y ya no estan (solo en `compute_full_diff`)
+    pub removed_functions: Vec<String>,
+    /// Tipos que existian y ya no estan (solo en `compute_full_diff`)
+    pub removed_types: Vec<String>,
 }
 
 impl CodeDiff {
@@ -58,6 +69,8 @@ impl CodeDiff {
             && self.modified_functions.is_empty()
             && self.added_types.is_empty()
             && self.modified_types.is_empty()
+            && self.removed_functions.is_empty()
+            && self.removed_types.is_empty()
     }
 
     /// Numero total de cambios
@@ -66,11 +79,17 @@ impl CodeDiff {
             + self.modified_functions.len()
             + self.added_types.len()
             + self.modified_types.len()
+            + self.removed_functions.len()
+            + self.removed_types.len()
     }
 }
 
 /// Compara el programa actual con nuevo codigo y detecta diferencias
 ///
+/// El nuevo codigo se trata como un fragmento que se agrega al programa:
+/// las definiciones ausentes en `new_code` se conservan. Para reemplazar
+/// el archivo completo (y detectar eliminaciones) usar `compute_full_diff`.
+///
 /// # Argumentos
 ///
 /// * `old_program` - El programa actualmente cargado en la VM
@@ -87,6 +106,56 @@ impl CodeDiff {
 /// let diff = compute_diff(&program, "double(x) = x * 2")?;
 /// ```
 pub fn compute_diff(old_program: &Program, new_code: &str) -> Result<CodeDiff, ReloadError> {
+    let new_program = parse_code(new_code)?;
+    Ok(diff_programs(old_program, &new_program))
+}
+
+/// Compara el programa actual con la nueva version completa del archivo
+///
+/// A diferencia de `compute_diff`, `new_code` reemplaza al programa entero:
+/// toda funcion o tipo que existia antes y no aparece ahora se reporta en
+/// `removed_functions` / `removed_types`.
+///
+/// # Ejemplo
+///
+/// ```rust,ignore
+/// // Antes: double(x) = ...; triple(x) = ...; main = ...
+/// let diff = compute_full_diff(&program, "double(x) = x * 2\nmain = double(2)")?;
+/// assert_eq!(diff.removed_functions, vec!["triple"]);
+/// ```
+pub fn compute_full_diff(old_program: &Program, new_code: &str) -> Result<CodeDiff, ReloadError> {
+    let new_program = parse_code(new_code)?;
+    Ok(compute_program_diff(old_program, &new_program))
+}
+
+/// Compara dos programas completos ya parseados
+///
+/// Equivale a `compute_full_diff` sin pasar por el codigo fuente: se
+/// reportan definiciones agregadas, modificadas y eliminadas. Una funcion
+/// renombrada aparece como una eliminada y una agregada.
+pub fn compute_program_diff(old_program: &Program, new_program: &Program) -> CodeDiff {
+    let mut diff = diff_programs(old_program, new_program);
+
+    let new_functions = build_function_index(new_program);
+    let new_types = build_type_index(new_program);
+
+    for def in &old_program.definitions {
+        match def {
+            Definition::FuncDef(func) if !new_functions.contains_key(&func.name) => {
+                diff.removed_functions.push(func.name.clone());
+            }
+            Definition::TypeDef(ty) if !new_types.contains_key(&ty.name) => {
+                diff.removed_types.push(ty.name.clone());
+            }
+            _ => {}
+        }
+    }
+
+    diff
+}
+
+/// Tokeniza y parsea el nuevo codigo
+pub(crate) fn parse_code(new_code: &str) -> Result<Program, ReloadError> {
     // Tokenizar nuevo codigo
     let tokens = tokenize(new_code).map_err(|errors| {
         let messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
@@ -94,11 +163,14 @@ pub fn compute_diff(old_program: &Program, new_code: &str) -> Result<CodeDiff, R
     })?;
 
     // Parsear nuevo codigo
-    let new_program = parse(tokens).map_err(|errors| {
+    parse(tokens).map_err(|errors| {
         let messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
         ReloadError::ParseError(messages.join("; "))
-    })?;
+    })
+}
 
+/// Detecta definiciones agregadas y modificadas en `new_program`
+fn diff_programs(old_program: &Program, new_program: &Program) -> CodeDiff {
     // Construir indices del programa viejo
     let old_functions = build_function_index(old_program);
     let old_types = build_type_index(old_program);
@@ -135,7 +207,7 @@ pub fn compute_diff(old_program: &Program, new_code: &str) -> Result<CodeDiff, R
         }
     }
 
-    Ok(diff)
+    diff
 }
 
 /// Construye un indice de funciones por nombre
@@ -164,75 +236,77 @@ fn build_type_index(program: &Program) -> HashMap<String, &TypeDef> {
 ///
 /// Ignora el span ya que puede cambiar entre versiones.
 fn functions_equal(a: &FuncDef, b: &FuncDef) -> bool {
-    // Comparar nombre
-    if a.name != b.name {
-        return false;
-    }
+    a.name == b.name && function_changes(a, b).is_empty()
+}
 
-    // Comparar efecto
-    if a.has_effect != b.has_effect {
-        return false;
+/// Describe en que cambio una funcion entre dos versiones
+///
+/// Retorna razones cortas (`"params changed"`, `"body changed"`, ...) en un
+/// orden fijo, o un vector vacio si ambas versiones son iguales. No compara
+/// el nombre ni el span.
+pub fn function_changes(old: &FuncDef, new: &FuncDef) -> Vec<&'static str> {
+    let mut changes = Vec::new();
+
+    if old.has_effect != new.has_effect {
+        changes.push("effect changed");
     }
 
-    // Comparar parametros (nombre y tipo)
-    if a.params.len() != b.params.len() {
-        return false;
-    }
-    for (pa, pb) in a.params.iter().zip(b.params.iter()) {
-        if pa.name != pb.name || pa.ty != pb.ty {
-            return false;
-        }
+    // Parametros (nombre, tipo y rest)
+    let params_equal = old.params.len() == new.params.len()
+        && old.params.iter().zip(new.params.iter())
+            .all(|(pa, pb)| pa.name == pb.name && pa.ty == pb.ty && pa.rest == pb.rest);
+    if !params_equal {
+        changes.push("params changed");
     }
 
-    // Comparar tipo de retorno
-    if a.return_type != b.return_type {
-        return false;
+    if old.return_type != new.return_type {
+        changes.push("return type changed");
     }
 
-    // Comparar cuerpo
     // Nota: Esta comparacion es estructural, no semantica
-    a.body == b.body
+    if old.body != new.body {
+        changes.push("body changed");
+    }
+
+    changes
 }
 
 /// Compara dos tipos para ver si son iguales
 ///
 /// Ignora el span ya que puede cambiar entre versiones.
 fn types_equal(a: &TypeDef, b: &TypeDef) -> bool {
-    // Comparar nombre
-    if a.name != b.name {
-        return false;
-    }
+    a.name == b.name && type_changes(a, b).is_empty()
+}
 
-    // Comparar campos
-    if a.fields.len() != b.fields.len() {
-        return false;
-    }
-    for (fa, fb) in a.fields.iter().zip(b.fields.iter()) {
-        if fa.name != fb.name || fa.ty != fb.ty || fa.nullable != fb.nullable {
-            return false;
-        }
-        // Comparar anotaciones (ignorando span)
-        if fa.annotations.len() != fb.annotations.len() {
-            return false;
-        }
-        for (aa, ab) in fa.annotations.iter().zip(fb.annotations.iter()) {
-            if aa.name != ab.name || aa.args != ab.args {
-                return false;
-            }
-        }
+/// Describe en que cambio un tipo entre dos versiones
+///
+/// Retorna `"fields changed"` y/o `"annotations changed"` (anotaciones del
+/// tipo; las de cada campo cuentan como cambio de campos).
+pub fn type_changes(old: &TypeDef, new: &TypeDef) -> Vec<&'static str> {
+    let mut changes = Vec::new();
+
+    let fields_equal = old.fields.len() == new.fields.len()
+        && old.fields.iter().zip(new.fields.iter()).all(|(fa, fb)| {
+            fa.name == fb.name
+                && fa.ty == fb.ty
+                && fa.nullable == fb.nullable
+                && annotations_equal(&fa.annotations, &fb.annotations)
+        });
+    if !fields_equal {
+        changes.push("fields changed");
     }
 
-    // Comparar anotaciones del tipo
-    if a.annotations.len() != b.annotations.len() {
-        return false;
-    }
-    for (aa, ab) in a.annotations.iter().zip(b.annotations.iter()) {
-        if aa.name != ab.name || aa.args != ab.args {
-            return false;
-        }
+    if !annotations_equal(&old.annotations, &new.annotations) {
+        changes.push("annotations changed");
     }
 
-    true
+    changes
+}
+
+/// Compara anotaciones ignorando el span
+fn annotations_equal(a: &[Annotation], b: &[Annotation]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b.iter()).all(|(aa, ab)| aa.name == ab.name && aa.args == ab.args)
 }
 
 #[cfg(test)]
@@ -308,6 +382,28 @@ triple(x) = x * 3
         assert_eq!(diff.added_types.len(), 1); // User
     }
 
+    #[test]
+    fn test_partial_diff_never_removes() {
+        let old = parse_program("+http\nmain = 42\ndouble(x) = x * 2\n@User {\n  id:i @pk\n}\n");
+        let diff = compute_diff(&old, "triple(x) = x * 3").unwrap();
+
+        assert_eq!(diff.added_functions.len(), 1);
+        assert!(diff.removed_functions.is_empty());
+        assert!(diff.removed_types.is_empty());
+    }
+
+    #[test]
+    fn test_full_diff_detects_removals() {
+        let old = parse_program("+http\nmain = 42\ndouble(x) = x * 2\n@User {\n  id:i @pk\n}\n");
+        let diff = compute_full_diff(&old, "main = 42\ntriple(x) = x * 3").unwrap();
+
+        assert_eq!(diff.added_functions.len(), 1); // triple
+        assert!(diff.modified_functions.is_empty());
+        assert_eq!(diff.removed_functions, vec!["double".to_string()]);
+        assert_eq!(diff.removed_types, vec!["User".to_string()]);
+        assert_eq!(diff.total_changes(), 3);
+    }
+
     #[test]
     fn test_lex_error() {
         let old = parse_program("+http\nmain = 42\n");
@@ -341,4 +437,50 @@ triple(x) = x * 3
 
         assert_eq!(diff.modified_functions.len(), 1);
     }
+
+    #[test]
+    fn test_program_diff_renamed_function() {
+        let old = parse_program("double(x) = x * 2\nmain = double(2)\n");
+        let new = parse_program("twice(x) = x * 2\nmain = double(2)\n");
+        let diff = compute_program_diff(&old, &new);
+
+        assert_eq!(diff.removed_functions, vec!["double".to_string()]);
+        assert_eq!(diff.added_functions.len(), 1);
+        assert_eq!(diff.added_functions[0].name, "twice");
+        assert!(diff.modified_functions.is_empty());
+        assert_eq!(diff.total_changes(), 2);
+    }
+
+    #[test]
+    fn test_function_change_reasons() {
+        let old = parse_program("add(a, b) = a + b\n");
+        let body = parse_program("add(a, b) = b + a\n");
+        let params = parse_program("add!(x, y) = x + y\n");
+
+        let func = |p: &Program| match &p.definitions[0] {
+            Definition::FuncDef(f) => f.clone(),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(function_changes(&func(&old), &func(&body)), vec!["body changed"]);
+        assert_eq!(
+            function_changes(&func(&old), &func(&params)),
+            vec!["effect changed", "params changed", "body changed"]
+        );
+        assert!(function_changes(&func(&old), &func(&old)).is_empty());
+    }
+
+    #[test]
+    fn test_type_change_reasons() {
+        let old = parse_program("@User {\n  id:i @pk\n}\n");
+        let new = parse_program("@User {\n  id:i\n}\n");
+
+        let ty = |p: &Program| match &p.definitions[0] {
+            Definition::TypeDef(t) => t.clone(),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(type_changes(&ty(&old), &ty(&new)), vec!["fields changed"]);
+        assert!(type_changes(&ty(&old), &ty(&old)).is_empty());
+    }
 }
@@ -21,19 +21,126 @@
 //! let result = apply_diff(&mut vm, diff)?;
 //! println!("Funciones agregadas: {}", result.functions_added);
 //! ```
+//!
+//! ## Observar un archivo
+//!
+//! ```rust,ignore
+//! use aura::reload::hot_reload_file;
+//!
+//! hot_reload_file(&mut vm, Path::new("app.aura"), |outcome| {
+//!     match outcome {
+//!         Ok(result) => println!("Recargado: {}", result),
+//!         Err(e) => eprintln!("{}", e),
+//!     }
+//!     true // seguir observando
+//! })?;
+//! ```
 
 pub mod apply;
 pub mod diff;
+pub mod watch;
+
+pub use apply::{apply_diff, apply_diff_checked, hot_reload, ApplyResult};
+pub use diff::{
+    compute_diff, compute_full_diff, compute_program_diff, function_changes, type_changes, CodeDiff,
+    ReloadError,
+};
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::vm::VM;
+use diff::parse_code;
+
+/// Intervalo por defecto entre revisiones del archivo observado
+pub const WATCH_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Observa un archivo y aplica los cambios a la VM cada vez que se modifica
+///
+/// Equivale a `hot_reload_file_with_interval` con `WATCH_INTERVAL`.
+pub fn hot_reload_file<F>(vm: &mut VM, path: &Path, on_reload: F) -> Result<(), ReloadError>
+where
+    F: FnMut(&Result<ApplyResult, ReloadError>) -> bool,
+{
+    hot_reload_file_with_interval(vm, path, WATCH_INTERVAL, on_reload)
+}
+
+/// Observa un archivo (por polling) y aplica los cambios a la VM
+///
+/// El contenido actual del archivo se toma como el programa ya cargado en
+/// la VM. Cada vez que el contenido cambia se calcula un `compute_full_diff`
+/// (el archivo reemplaza al programa completo, asi que se detectan
+/// eliminaciones) y se aplica con `apply_diff_checked`: una version que no
+/// pasa el type checker se reporta como `ReloadError::TypeError` sin tocar
+/// la VM.
+///
+/// El callback recibe el resultado de cada recarga y retorna `true` para
+/// seguir observando o `false` para terminar. Los errores de lectura, lexer
+/// o parser se reportan al callback y la VM conserva el estado anterior.
+///
+/// # Errores
+///
+/// Retorna error solo si el contenido inicial no se puede leer o parsear.
+pub fn hot_reload_file_with_interval<F>(
+    vm: &mut VM,
+    path: &Path,
+    interval: Duration,
+    mut on_reload: F,
+) -> Result<(), ReloadError>
+where
+    F: FnMut(&Result<ApplyResult, ReloadError>) -> bool,
+{
+    let mut source = read_source(path)?;
+    let mut program = parse_code(&source)?;
+
+    loop {
+        std::thread::sleep(interval);
+
+        let new_source = match read_source(path) {
+            Ok(s) => s,
+            Err(e) => {
+                // El editor puede estar reescribiendo el archivo
+                if !on_reload(&Err(e)) {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+
+        if new_source == source {
+            continue;
+        }
 
-pub use apply::{apply_diff, hot_reload, ApplyResult};
-pub use diff::{compute_diff, CodeDiff, ReloadError};
+        // Un error de sintaxis a mitad de una edicion se reporta y se sigue
+        // observando; con cualquier error se conserva el programa anterior,
+        // pero no se reintenta hasta que el archivo vuelva a cambiar
+        source = new_source;
+        let outcome = parse_code(&source).and_then(|new_program| {
+            let diff = compute_program_diff(&program, &new_program);
+            let result = apply_diff_checked(vm, &program, diff)?;
+            program = new_program;
+            Ok(result)
+        });
+
+        if !on_reload(&outcome) {
+            return Ok(());
+        }
+    }
+}
+
+/// Lee el archivo observado
+fn read_source(path: &Path) -> Result<String, ReloadError> {
+    std::fs::read_to_string(path).map_err(|e| {
+        ReloadError::ApplyError(format!("No se pudo leer '{}': {}", path.display(), e))
+    })
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::lexer::tokenize;
-    use crate::parser::parse;
-    use crate::vm::VM;
+    use crate::parser::{parse, Expr};
+    use crate::vm::{Value, VM};
 
     #[test]
     fn test_hot_reload_add_function() {
@@ -153,6 +260,51 @@ triple(x) = x * 3
         assert!(funcs.contains(&"triple".to_string()));
     }
 
+    /// Carga un programa y define `x = double(5)` y `f = double` antes de recargar
+    fn setup_with_bindings() -> (VM, crate::parser::Program) {
+        let initial_code = "+http\ndouble(x) = x * 2\nmain = double(5)\n";
+        let program = parse(tokenize(initial_code).unwrap()).unwrap();
+
+        let mut vm = VM::new();
+        vm.load(&program);
+
+        let call = crate::parser::parse_expression(tokenize("double(5)").unwrap()).unwrap();
+        vm.eval(&Expr::Let { name: "x".to_string(), value: Box::new(call) }).unwrap();
+        vm.eval(&Expr::Let {
+            name: "f".to_string(),
+            value: Box::new(Expr::Ident("double".to_string())),
+        })
+        .unwrap();
+
+        (vm, program)
+    }
+
+    #[test]
+    fn test_hot_reload_keeps_variable_values() {
+        let (mut vm, program) = setup_with_bindings();
+
+        let diff = compute_diff(&program, "double(x) = x * 3").unwrap();
+        apply_diff(&mut vm, diff).unwrap();
+
+        // x se evaluo con el cuerpo viejo y conserva su valor
+        assert_eq!(vm.eval(&Expr::Ident("x".to_string())).unwrap(), Value::Int(10));
+        assert!(vm.list_variables().contains(&"x".to_string()));
+    }
+
+    #[test]
+    fn test_hot_reload_new_calls_use_new_body() {
+        let (mut vm, program) = setup_with_bindings();
+
+        let diff = compute_diff(&program, "double(x) = x * 3").unwrap();
+        apply_diff(&mut vm, diff).unwrap();
+
+        assert_eq!(vm.call_by_name("double", vec![Value::Int(5)]).unwrap(), Value::Int(15));
+
+        // Una variable que guarda la funcion la resuelve por nombre
+        let call_f = crate::parser::parse_expression(tokenize("f(5)").unwrap()).unwrap();
+        assert_eq!(vm.eval(&call_f).unwrap(), Value::Int(15));
+    }
+
     #[test]
     fn test_hot_reload_parse_error() {
         let initial_code = "+http\nmain = 42\n";
@@ -0,0 +1,134 @@
+//! Observacion de archivos para `run --watch` y `check --watch`
+//!
+//! Usa polling del contenido (igual que `hot_reload_file`) y agrupa los
+//! guardados seguidos en una sola notificacion.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::WATCH_INTERVAL;
+
+/// Tiempo sin cambios que se espera antes de notificar
+pub const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Agrupa cambios rapidos: solo se dispara cuando pasa `delay` desde el ultimo
+#[derive(Debug, Clone)]
+pub struct Debouncer {
+    delay: Duration,
+    pending: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(delay: Duration) -> Self {
+        Self { delay, pending: None }
+    }
+
+    /// Registra un cambio en `now`; reinicia la espera si ya habia uno pendiente
+    pub fn touch(&mut self, now: Instant) {
+        self.pending = Some(now);
+    }
+
+    /// Si hay un cambio pendiente y ya paso `delay` sin otros cambios.
+    /// Retorna `true` una sola vez por grupo de cambios.
+    pub fn ready(&mut self, now: Instant) -> bool {
+        match self.pending {
+            Some(last) if now.duration_since(last) >= self.delay => {
+                self.pending = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+/// Observa `path` y llama a `on_change` cada vez que su contenido cambia
+///
+/// Equivale a `watch_file_with` con `WATCH_INTERVAL` y `DEBOUNCE`.
+pub fn watch_file<F>(path: &Path, on_change: F)
+where
+    F: FnMut() -> bool,
+{
+    watch_file_with(path, WATCH_INTERVAL, DEBOUNCE, on_change)
+}
+
+/// Observa `path` (por polling cada `interval`) y llama a `on_change` cuando
+/// el contenido cambia y se mantiene estable durante `debounce`
+///
+/// Mientras el archivo no se pueda leer (p.ej. el editor lo esta
+/// reescribiendo) se sigue esperando. El callback retorna `true` para seguir
+/// observando o `false` para terminar.
+pub fn watch_file_with<F>(path: &Path, interval: Duration, debounce: Duration, mut on_change: F)
+where
+    F: FnMut() -> bool,
+{
+    let mut seen = std::fs::read(path).ok();
+    let mut debouncer = Debouncer::new(debounce);
+
+    loop {
+        std::thread::sleep(interval);
+
+        if let Ok(current) = std::fs::read(path)
+            && seen.as_deref() != Some(current.as_slice()) {
+            seen = Some(current);
+            debouncer.touch(Instant::now());
+        }
+
+        if debouncer.ready(Instant::now()) && !on_change() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debouncer_waits_for_quiet_period() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut debouncer = Debouncer::new(ms(100));
+
+        assert!(!debouncer.ready(start));
+
+        debouncer.touch(start);
+        assert!(!debouncer.ready(start + ms(50)));
+
+        // Un segundo guardado reinicia la espera
+        debouncer.touch(start + ms(80));
+        assert!(!debouncer.ready(start + ms(150)));
+        assert!(debouncer.ready(start + ms(180)));
+
+        // Se dispara una sola vez por grupo de cambios
+        assert!(!debouncer.is_pending());
+        assert!(!debouncer.ready(start + ms(500)));
+    }
+
+    #[test]
+    fn test_watch_file_notifies_after_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.aura");
+        std::fs::write(&path, "main = 1").unwrap();
+
+        let writer_path = path.clone();
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(30));
+            std::fs::write(&writer_path, "main = 2").unwrap();
+            std::fs::write(&writer_path, "main = 3").unwrap();
+        });
+
+        let mut calls = 0;
+        watch_file_with(&path, Duration::from_millis(5), Duration::from_millis(20), || {
+            calls += 1;
+            false
+        });
+        writer.join().unwrap();
+
+        assert_eq!(calls, 1);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "main = 3");
+    }
+}
@@ -0,0 +1,312 @@
+//! Utilidades para el REPL de AURA
+//!
+//! El loop interactivo vive en el binario (`main.rs`); aqui quedan las
+//! piezas que no dependen de la terminal y se pueden testear.
+
+use std::path::Path;
+use std::time::Duration;
+
+use rustyline::{Config, DefaultEditor};
+
+use crate::cli_output::JsonError;
+
+/// Tiempo maximo que el REPL espera una expresion antes de cancelarla
+pub const EVAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Historial del REPL, relativo al directorio actual (junto a snapshots y memoria)
+pub const HISTORY_PATH: &str = ".aura/repl_history";
+
+/// Entradas de historial que se conservan
+pub const HISTORY_SIZE: usize = 1000;
+
+/// Editor de linea (flechas, historial, Ctrl-C/Ctrl-D) con el historial de
+/// `path` ya cargado. Si el archivo no existe se empieza vacio.
+pub fn editor_with_history(path: &Path) -> rustyline::Result<DefaultEditor> {
+    let config = Config::builder()
+        .max_history_size(HISTORY_SIZE)?
+        .history_ignore_dups(true)?
+        .build();
+    let mut editor = DefaultEditor::with_config(config)?;
+    if path.exists() {
+        editor.load_history(path)?;
+    }
+    Ok(editor)
+}
+
+/// Guarda el historial en `path`, creando el directorio si hace falta
+pub fn save_history(editor: &mut DefaultEditor, path: &Path) -> rustyline::Result<()> {
+    if let Some(dir) = path.parent()
+        && !dir.as_os_str().is_empty() {
+        std::fs::create_dir_all(dir)?;
+    }
+    editor.save_history(path)
+}
+
+/// Formato en que el REPL reporta los errores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// Texto para humanos (`Error de parsing: ...`)
+    #[default]
+    Human,
+    /// Un `JsonError` por linea, para agentes que usan el REPL por un pipe
+    Json,
+}
+
+impl ErrorFormat {
+    /// Procesa el comando `:json [on|off]`
+    ///
+    /// Retorna `None` si `input` no es un comando `:json`, o el formato
+    /// resultante. Sin argumento el formato no cambia (el REPL lo muestra).
+    ///
+    /// # Ejemplo
+    ///
+    /// ```
+    /// use aura::repl::ErrorFormat;
+    ///
+    /// let format = ErrorFormat::Human;
+    /// assert_eq!(format.apply_command(":json on"), Some(Ok(ErrorFormat::Json)));
+    /// assert_eq!(format.apply_command("1 + 2"), None);
+    /// ```
+    pub fn apply_command(self, input: &str) -> Option<Result<ErrorFormat, String>> {
+        let arg = input.strip_prefix(":json")?;
+        if !arg.is_empty() && !arg.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        match arg.trim() {
+            "" => Some(Ok(self)),
+            "on" => Some(Ok(ErrorFormat::Json)),
+            "off" => Some(Ok(ErrorFormat::Human)),
+            other => Some(Err(format!("Opcion invalida '{}'. Uso: :json on|off", other))),
+        }
+    }
+
+    /// Renderiza un error; `context` es el prefijo del modo humano
+    pub fn render(self, context: &str, error: &JsonError) -> String {
+        match self {
+            ErrorFormat::Human => format!("{}: {}", context, error.message),
+            ErrorFormat::Json => serde_json::to_string(error).unwrap_or_default(),
+        }
+    }
+}
+
+/// Determina si el input acumulado necesita mas lineas para estar completo
+///
+/// Un input esta incompleto si:
+/// - Tiene `(`, `[` o `{` sin cerrar
+/// - Tiene un string literal sin cerrar
+/// - Termina en `=` (definicion sin cuerpo todavia)
+///
+/// Los delimitadores dentro de strings y comentarios (`# ...`) se ignoran.
+/// Si sobran delimitadores de cierre el input se considera completo, para
+/// que el parser reporte el error.
+///
+/// # Ejemplo
+///
+/// ```
+/// use aura::repl::needs_more_input;
+///
+/// assert!(needs_more_input("@User {"));
+/// assert!(needs_more_input("double(x) ="));
+/// assert!(!needs_more_input("print(\"{\")"));
+/// ```
+pub fn needs_more_input(input: &str) -> bool {
+    let mut depth: i32 = 0;
+    let mut in_string = false;
+    let mut in_comment = false;
+    let mut escaped = false;
+
+    for c in input.chars() {
+        if in_comment {
+            if c == '\n' {
+                in_comment = false;
+            }
+            continue;
+        }
+
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '#' => in_comment = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    if in_string || depth > 0 {
+        return true;
+    }
+
+    last_code_line(input).ends_with('=')
+}
+
+/// Retorna la ultima linea con codigo, sin comentario ni espacios finales
+fn last_code_line(input: &str) -> &str {
+    input
+        .lines()
+        .map(strip_comment)
+        .map(str::trim_end)
+        .rfind(|line| !line.trim().is_empty())
+        .unwrap_or("")
+}
+
+/// Quita un comentario `# ...` al final de la linea (fuera de strings)
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in line.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == '#' {
+            return &line[..i];
+        }
+    }
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_toggle_on_off() {
+        let format = ErrorFormat::default();
+        assert_eq!(format, ErrorFormat::Human);
+
+        let format = format.apply_command(":json on").unwrap().unwrap();
+        assert_eq!(format, ErrorFormat::Json);
+        // Repetir el comando no cambia nada
+        assert_eq!(format.apply_command(":json  on ").unwrap(), Ok(ErrorFormat::Json));
+
+        let format = format.apply_command(":json off").unwrap().unwrap();
+        assert_eq!(format, ErrorFormat::Human);
+    }
+
+    #[test]
+    fn test_json_toggle_without_argument_keeps_state() {
+        assert_eq!(ErrorFormat::Json.apply_command(":json"), Some(Ok(ErrorFormat::Json)));
+        assert_eq!(ErrorFormat::Human.apply_command(":json"), Some(Ok(ErrorFormat::Human)));
+    }
+
+    #[test]
+    fn test_json_toggle_invalid_argument() {
+        assert!(matches!(ErrorFormat::Human.apply_command(":json yes"), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_json_toggle_ignores_other_input() {
+        assert_eq!(ErrorFormat::Human.apply_command(":jsonx"), None);
+        assert_eq!(ErrorFormat::Human.apply_command(":load x.aura"), None);
+        assert_eq!(ErrorFormat::Human.apply_command("json"), None);
+    }
+
+    #[test]
+    fn test_render_error() {
+        let error = JsonError::new("E401", "Variable no definida: x");
+        assert_eq!(
+            ErrorFormat::Human.render("Error de ejecucion", &error),
+            "Error de ejecucion: Variable no definida: x"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&ErrorFormat::Json.render("Error de ejecucion", &error)).unwrap();
+        assert_eq!(json["code"], "E401");
+        assert_eq!(json["message"], "Variable no definida: x");
+    }
+
+    #[test]
+    fn test_complete_single_line() {
+        assert!(!needs_more_input("1 + 2"));
+        assert!(!needs_more_input("double(x) = x * 2"));
+        assert!(!needs_more_input("@User { id:i }"));
+    }
+
+    #[test]
+    fn test_unbalanced_braces() {
+        assert!(needs_more_input("@User {"));
+        assert!(needs_more_input("@User {\n  id:i @pk\n  name:s"));
+        assert!(!needs_more_input("@User {\n  id:i @pk\n  name:s\n}"));
+    }
+
+    #[test]
+    fn test_unbalanced_parens_and_brackets() {
+        assert!(needs_more_input("add(1,"));
+        assert!(needs_more_input("[1, 2,\n 3"));
+        assert!(!needs_more_input("add(1,\n 2)"));
+    }
+
+    #[test]
+    fn test_trailing_equals() {
+        assert!(needs_more_input("double(x) ="));
+        assert!(needs_more_input("main =   "));
+        assert!(needs_more_input("main = # cuerpo en la siguiente linea"));
+        assert!(!needs_more_input("double(x) =\n  x * 2"));
+    }
+
+    #[test]
+    fn test_braces_inside_strings_are_ignored() {
+        assert!(!needs_more_input("print(\"{\")"));
+        assert!(!needs_more_input("print(\"}}) (\")"));
+        assert!(!needs_more_input("print(\"dice \\\"{\\\"\")"));
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        assert!(needs_more_input("print(\"hola"));
+        assert!(needs_more_input("x = \"abc\\\""));
+    }
+
+    #[test]
+    fn test_braces_inside_comments_are_ignored() {
+        assert!(!needs_more_input("main = 42 # {"));
+        assert!(!needs_more_input("# (\nmain = 1"));
+    }
+
+    #[test]
+    fn test_extra_closing_is_complete() {
+        // El parser se encarga de reportar el error
+        assert!(!needs_more_input("1 + 2)"));
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        use rustyline::history::History;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".aura").join("repl_history");
+
+        // Sin archivo se empieza con el historial vacio
+        let mut editor = editor_with_history(&path).unwrap();
+        assert!(editor.history().is_empty());
+        editor.add_history_entry("x = 1").unwrap();
+        editor.add_history_entry("x = 1").unwrap();
+        editor.add_history_entry("print(x)").unwrap();
+        save_history(&mut editor, &path).unwrap();
+
+        let editor = editor_with_history(&path).unwrap();
+        let entries: Vec<&str> = editor.history().iter().map(String::as_str).collect();
+        // Los duplicados seguidos se guardan una vez
+        assert_eq!(entries, vec!["x = 1", "print(x)"]);
+    }
+}
@@ -0,0 +1,100 @@
+// Stream de eventos de `aura serve --events`
+// Un objeto JSON por request atendido, para que un agente observe el servidor
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cli_output::JsonError;
+
+/// Un request con su respuesta
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestEvent {
+    pub method: String,
+    pub path: String,
+    /// Parametros de la ruta (`:id`); vacio si no hubo ruta
+    pub params: BTreeMap<String, String>,
+    pub status: u16,
+    pub duration_ms: f64,
+    /// Error de ejecucion del handler, si fallo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonError>,
+}
+
+impl RequestEvent {
+    /// Una linea JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Callback que recibe cada evento
+pub type EventSink = Arc<dyn Fn(&RequestEvent) + Send + Sync>;
+
+/// Sink que escribe cada evento como una linea en stdout
+pub fn stdout_sink() -> EventSink {
+    Arc::new(|event: &RequestEvent| println!("{}", event.to_json()))
+}
+
+/// Sink que agrega cada evento como una linea al final de `path`
+///
+/// Un fallo al escribir se ignora: el stream es para observar, y el
+/// request ya se respondio.
+pub fn file_sink(path: &Path) -> std::io::Result<EventSink> {
+    let file: Mutex<File> = Mutex::new(OpenOptions::new().create(true).append(true).open(path)?);
+    Ok(Arc::new(move |event: &RequestEvent| {
+        if let Ok(mut file) = file.lock() {
+            let _ = writeln!(file, "{}", event.to_json());
+            let _ = file.flush();
+        }
+    }))
+}
+
+/// Lo que el handler sabe del request y el middleware no: viaja en las
+/// extensiones de la respuesta hasta el middleware que arma el evento
+#[derive(Debug, Clone, Default)]
+pub(super) struct HandlerOutcome {
+    pub params: BTreeMap<String, String>,
+    pub error: Option<JsonError>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(path: &str, status: u16) -> RequestEvent {
+        RequestEvent {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            params: BTreeMap::from([("id".to_string(), "7".to_string())]),
+            status,
+            duration_ms: 0.5,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_file_sink_appends_one_line_per_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        std::fs::write(&path, "").unwrap();
+
+        let sink = file_sink(&path).unwrap();
+        sink(&event("/user/7", 200));
+        sink(&event("/user/8", 404));
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let events: Vec<RequestEvent> = content.lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let seen: Vec<(&str, u16)> = events.iter().map(|e| (e.path.as_str(), e.status)).collect();
+        assert_eq!(seen, vec![("/user/7", 200), ("/user/8", 404)]);
+        assert_eq!(events[0].params["id"], "7");
+        // Sin error el campo no aparece
+        assert!(!content.contains("error"));
+    }
+}
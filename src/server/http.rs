@@ -2,62 +2,193 @@
 // Usa axum para manejar requests
 
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::time::Instant;
 use axum::{
     Router as AxumRouter,
-    routing::any,
-    extract::{Path, Query, State},
+    routing::{any, get},
+    extract::{Path, Query, Request, State, ws::WebSocketUpgrade},
     http::Method,
     body::Bytes,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
 };
+use serde::Serialize;
 use tokio::net::TcpListener;
 
-use crate::vm::{VM, Value};
+use crate::cli_output::JsonError;
+use crate::vm::{VM, Value, RuntimeError};
 use crate::parser::{Program, FuncDef};
+use super::events::{EventSink, HandlerOutcome, RequestEvent};
 use super::router::{Router, Route};
 use super::request::AuraRequest;
 use super::response::AuraResponse;
+use super::ws;
 
 /// Estado compartido del servidor
+///
+/// Cada handler corre en su propia VM armada con `program` (ver `new_vm`),
+/// asi dos requests concurrentes no se esperan ni comparten estado.
 pub struct ServerState {
     pub router: Router,
-    pub vm: Mutex<VM>,
     pub program: Program,
+    pub logger: Option<RequestLogger>,
+    pub events: Option<EventSink>,
 }
 
+/// Un request atendido, tal como lo recibe el logger
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RequestLog {
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    pub duration_ms: f64,
+}
+
+impl RequestLog {
+    /// Linea para humanos: `GET /users/1 200 0.8ms`
+    pub fn to_human(&self) -> String {
+        format!("{} {} {} {:.1}ms", self.method, self.path, self.status, self.duration_ms)
+    }
+
+    /// Una linea JSON por request
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Callback que recibe cada request atendido
+pub type RequestLogger = Arc<dyn Fn(&RequestLog) + Send + Sync>;
+
 /// Inicia el servidor HTTP
+///
+/// Con `logger`, cada request (incluido `/health`) se reporta una vez al
+/// terminar de atenderse. Con `events`, ademas, como `RequestEvent` con los
+/// parametros de la ruta y el error del handler.
 pub async fn start_server(
     port: u16,
     routes: Vec<Route>,
     program: Program,
+    logger: Option<RequestLogger>,
+    events: Option<EventSink>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    for route in &routes {
+        println!("  {} {}", route.method, route.path);
+    }
+    for path in ws::ws_paths(&program) {
+        println!("  WS {}", path);
+    }
+
+    let app = build_app(routes, program, logger, events);
+
+    let addr = format!("0.0.0.0:{}", port);
+    println!("AURA Server listening on http://{}", addr);
+
+    let listener = TcpListener::bind(&addr).await?;
+    let started = Instant::now();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    println!("AURA Server detenido tras {:.1}s", started.elapsed().as_secs_f64());
+
+    Ok(())
+}
+
+/// Arma la aplicacion axum: rutas del programa, WebSockets y, si hay logger
+/// o eventos, la capa que mide cada request
+pub(super) fn build_app(
+    routes: Vec<Route>,
+    program: Program,
+    logger: Option<RequestLogger>,
+    events: Option<EventSink>,
+) -> AxumRouter {
     let mut router = Router::new();
     for route in routes {
-        println!("  {} {}", route.method, route.path);
         router.add_route(route);
     }
 
-    let mut vm = VM::new();
-    vm.load(&program);
-
     let state = Arc::new(ServerState {
         router,
-        vm: Mutex::new(vm),
         program,
+        logger,
+        events,
     });
 
-    let app = AxumRouter::new()
+    let mut app = AxumRouter::new()
         .route("/*path", any(handle_request))
-        .route("/", any(handle_request))
-        .with_state(state);
+        .route("/", any(handle_request));
 
-    let addr = format!("0.0.0.0:{}", port);
-    println!("AURA Server listening on http://{}", addr);
+    for (path, handler) in ws::ws_endpoints(&state.program) {
+        app = app.route(&path, get(move |State(state): State<Arc<ServerState>>, upgrade: WebSocketUpgrade| {
+            ws::handle_upgrade(state, handler.clone(), upgrade)
+        }));
+    }
 
-    let listener = TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    if state.logger.is_some() || state.events.is_some() {
+        app = app.layer(middleware::from_fn_with_state(state.clone(), log_requests));
+    }
 
-    Ok(())
+    app.with_state(state)
+}
+
+impl ServerState {
+    /// VM nueva con el programa cargado, para atender un request o mensaje
+    pub fn new_vm(&self) -> VM {
+        let mut vm = VM::new();
+        vm.load(&self.program);
+        vm
+    }
+}
+
+/// Middleware que mide cada request y lo pasa al logger y al stream de eventos
+async fn log_requests(State(state): State<Arc<ServerState>>, request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let started = Instant::now();
+
+    let mut response = next.run(request).await;
+
+    let status = response.status().as_u16();
+    let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+    // Solo para el middleware: no hace falta que salga con la respuesta
+    let outcome = response.extensions_mut().remove::<HandlerOutcome>().unwrap_or_default();
+
+    if let Some(events) = &state.events {
+        events(&RequestEvent {
+            method: method.clone(),
+            path: path.clone(),
+            params: outcome.params,
+            status,
+            duration_ms,
+            error: outcome.error,
+        });
+    }
+
+    if let Some(logger) = &state.logger {
+        logger(&RequestLog { method, path, status, duration_ms });
+    }
+
+    response
+}
+
+/// Espera Ctrl-C para que axum termine los requests en curso y se detenga
+async fn shutdown_signal() {
+    if tokio::signal::ctrl_c().await.is_err() {
+        // Sin manejador de senales: correr hasta que maten el proceso
+        std::future::pending::<()>().await;
+    }
+    println!();
+    println!("Deteniendo servidor...");
+}
+
+/// Respuesta de `GET /health`, salvo que el programa defina esa ruta
+fn health_response(state: &ServerState) -> AuraResponse {
+    let mut map = HashMap::new();
+    map.insert("status".to_string(), Value::String("ok".to_string()));
+    map.insert("version".to_string(), Value::String(env!("CARGO_PKG_VERSION").to_string()));
+    map.insert("routes".to_string(), Value::Int(state.router.len() as i64));
+    AuraResponse::ok(Value::Record(map))
 }
 
 /// Handler principal que procesa todos los requests
@@ -67,13 +198,13 @@ async fn handle_request(
     Path(path): Path<String>,
     Query(query): Query<HashMap<String, String>>,
     body: Bytes,
-) -> AuraResponse {
+) -> Response {
     let path = format!("/{}", path);
     let method_str = method.as_str();
 
     // Handle CORS preflight
     if method == Method::OPTIONS {
-        return AuraResponse::ok(Value::Nil);
+        return AuraResponse::ok(Value::Nil).into_response();
     }
 
     // Buscar ruta que coincida
@@ -87,36 +218,63 @@ async fn handle_request(
                 .with_query(query);
 
             // Parsear body como JSON si hay contenido
-            if !body.is_empty() {
-                if let Ok(json_str) = std::str::from_utf8(&body) {
-                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(json_str) {
-                        request = request.with_body(json_to_value(json));
-                    }
-                }
+            if !body.is_empty()
+                && let Ok(json_str) = std::str::from_utf8(&body)
+                && let Ok(json) = serde_json::from_str::<serde_json::Value>(json_str)
+            {
+                request = request.with_body(Value::from_json(&json));
             }
 
-            // Ejecutar handler
-            execute_handler(&state, &route.handler_name, request, params)
+            // Los caps (http, db) bloquean: el handler corre fuera de los
+            // workers de tokio para no frenar al resto de los requests
+            let handler = route.handler_name.clone();
+            let worker_state = state.clone();
+            let mut outcome = HandlerOutcome { params: params.clone().into_iter().collect(), error: None };
+            let executed = tokio::task::spawn_blocking(move || execute_handler(&worker_state, &handler, request, params)).await;
+
+            let response = match executed {
+                Ok(Ok(response)) => response,
+                Ok(Err(e)) => {
+                    outcome.error = Some(JsonError::from_runtime_error(&e));
+                    AuraResponse::runtime_error(&e)
+                }
+                Err(e) => AuraResponse::error(&format!("Handler panicked: {}", e)),
+            };
+
+            // Para el middleware de eventos (los parametros y el error no
+            // se pueden leer de la respuesta ya armada)
+            let mut response = response.into_response();
+            response.extensions_mut().insert(outcome);
+            response
         }
+        None if method == Method::GET && path == "/health" => health_response(&state).into_response(),
         None => {
-            AuraResponse::not_found(&format!("Route not found: {} {}", method_str, path))
+            AuraResponse::not_found(&format!("Route not found: {} {}", method_str, path)).into_response()
         }
     }
 }
 
 /// Ejecuta un handler de AURA
+///
+/// Los parametros del handler se asignan por nombre:
+/// - `req`: el body JSON como record en POST/PUT/PATCH (`req.name`), el
+///   request completo en GET/DELETE
+/// - `request`: siempre el request completo (method, path, params, query,
+///   headers, body)
+/// - parametros de ruta (`:id`): el segmento del path, como Int si se puede
+/// - cualquier otro: el campo del mismo nombre en el body, o nil
+///
+/// `Err` si el handler fallo al ejecutarse.
 fn execute_handler(
     state: &ServerState,
     handler_name: &str,
     request: AuraRequest,
     params: HashMap<String, String>,
-) -> AuraResponse {
-    let mut vm = state.vm.lock().unwrap();
-
+) -> Result<AuraResponse, RuntimeError> {
     // Buscar la función handler para saber los parámetros
     let func = match find_handler(&state.program, handler_name) {
         Some(f) => f,
-        None => return AuraResponse::error(&format!("Handler not found: {}", handler_name)),
+        None => return Ok(AuraResponse::error(&format!("Handler not found: {}", handler_name))),
     };
 
     // Construir argumentos basados en la firma de la función
@@ -124,7 +282,10 @@ fn execute_handler(
 
     for param in &func.params {
         if param.name == "req" {
-            // El parámetro 'req' recibe el request completo
+            // 'req' recibe el body JSON en POST/PUT/PATCH, el request en el resto
+            args.push(request.req_binding());
+        } else if param.name == "request" {
+            // 'request' siempre recibe el request completo
             args.push(request.to_value());
         } else if let Some(value) = params.get(&param.name) {
             // Parámetro de ruta (ej: :id)
@@ -147,50 +308,188 @@ fn execute_handler(
     }
 
     // Llamar a la función con los argumentos
-    let result = vm.call_by_name(handler_name, args);
-
-    match result {
-        Ok(value) => AuraResponse::from_value(value),
-        Err(e) => AuraResponse::error(&e.message),
-    }
+    let value = state.new_vm().call_by_name(handler_name, args)?;
+    Ok(AuraResponse::from_value(value))
 }
 
 /// Busca un handler por nombre en el programa
 fn find_handler(program: &Program, name: &str) -> Option<FuncDef> {
     for def in &program.definitions {
-        if let crate::parser::Definition::FuncDef(func) = def {
-            if func.name == name {
-                return Some(func.clone());
-            }
+        if let crate::parser::Definition::FuncDef(func) = def
+            && func.name == name {
+            return Some(func.clone());
         }
     }
     None
 }
 
-/// Convierte serde_json::Value a Value de AURA
-fn json_to_value(json: serde_json::Value) -> Value {
-    match json {
-        serde_json::Value::Null => Value::Nil,
-        serde_json::Value::Bool(b) => Value::Bool(b),
-        serde_json::Value::Number(n) => {
-            if let Some(i) = n.as_i64() {
-                Value::Int(i)
-            } else if let Some(f) = n.as_f64() {
-                Value::Float(f)
-            } else {
-                Value::Nil
-            }
-        }
-        serde_json::Value::String(s) => Value::String(s),
-        serde_json::Value::Array(arr) => {
-            Value::List(arr.into_iter().map(json_to_value).collect())
-        }
-        serde_json::Value::Object(obj) => {
-            let mut map = HashMap::new();
-            for (k, v) in obj {
-                map.insert(k, json_to_value(v));
-            }
-            Value::Record(map)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::lexer::tokenize;
+    use crate::parser::parse;
+
+    /// Levanta la app en un puerto libre y retorna la URL base
+    async fn serve(source: &str, routes: Vec<Route>, logger: RequestLogger) -> String {
+        serve_app(source, routes, Some(logger), None).await
+    }
+
+    async fn serve_app(source: &str, routes: Vec<Route>, logger: Option<RequestLogger>, events: Option<EventSink>) -> String {
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        let app = build_app(routes, program, logger, events);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_logger_receives_each_request() {
+        let logs: Arc<Mutex<Vec<RequestLog>>> = Arc::default();
+        let sink = logs.clone();
+        let logger: RequestLogger = Arc::new(move |log| sink.lock().unwrap().push(log.clone()));
+
+        let base = serve(
+            "get_user(id) = {id: id}",
+            vec![Route::new("GET", "/user/:id", "get_user")],
+            logger,
+        ).await;
+
+        let client = reqwest::Client::new();
+        client.get(format!("{}/user/7", base)).send().await.unwrap();
+        client.post(format!("{}/missing", base)).send().await.unwrap();
+        client.get(format!("{}/health", base)).send().await.unwrap();
+
+        let logs = logs.lock().unwrap();
+        let seen: Vec<(&str, &str, u16)> = logs.iter()
+            .map(|l| (l.method.as_str(), l.path.as_str(), l.status))
+            .collect();
+        // /health se reporta una sola vez, como cualquier otro request
+        assert_eq!(seen, vec![
+            ("GET", "/user/7", 200),
+            ("POST", "/missing", 404),
+            ("GET", "/health", 200),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_events_include_params_and_runtime_errors() {
+        let events: Arc<Mutex<Vec<RequestEvent>>> = Arc::default();
+        let sink = events.clone();
+        let on_event: EventSink = Arc::new(move |event| sink.lock().unwrap().push(event.clone()));
+
+        let base = serve_app(
+            "get_user(id) = {id: id}\nget_ratio(n) = 10 / n",
+            vec![
+                Route::new("GET", "/user/:id", "get_user"),
+                Route::new("GET", "/ratio/:n", "get_ratio"),
+            ],
+            None,
+            Some(on_event),
+        ).await;
+
+        let client = reqwest::Client::new();
+        let ok = client.get(format!("{}/user/7", base)).send().await.unwrap();
+        let failed = client.get(format!("{}/ratio/0", base)).send().await.unwrap();
+
+        // Las respuestas no cambian por emitir eventos
+        assert_eq!(ok.json::<serde_json::Value>().await.unwrap(), serde_json::json!({"id": 7}));
+        assert_eq!(failed.status(), 500);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+
+        assert_eq!((events[0].method.as_str(), events[0].path.as_str(), events[0].status), ("GET", "/user/7", 200));
+        assert_eq!(events[0].params["id"], "7");
+        assert!(events[0].error.is_none());
+
+        assert_eq!((events[1].path.as_str(), events[1].status), ("/ratio/0", 500));
+        let error = events[1].error.as_ref().unwrap();
+        assert_eq!(error.code, "E401");
+        assert!(events[1].duration_ms >= 0.0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_blocking_handler_does_not_stall_the_runtime() {
+        // Upstream que no responde hasta que el test lo libera
+        let arrived = Arc::new(tokio::sync::Notify::new());
+        let release = Arc::new(tokio::sync::Notify::new());
+        let (upstream_arrived, upstream_release) = (arrived.clone(), release.clone());
+        let upstream = AxumRouter::new().route("/", get(move || async move {
+            upstream_arrived.notify_one();
+            upstream_release.notified().await;
+            "ok"
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, upstream).await.unwrap() });
+
+        let source = format!("+http\n\nslow = http.get(\"http://{}/\").status", upstream_addr);
+        let logger: RequestLogger = Arc::new(|_| {});
+        let base = serve(&source, vec![Route::new("GET", "/slow", "slow")], logger).await;
+
+        let client = reqwest::Client::new();
+        let slow = tokio::spawn(client.get(format!("{}/slow", base)).send());
+        arrived.notified().await;
+
+        // Con el unico worker de tokio libre, /health responde mientras `slow` espera
+        let health = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            client.get(format!("{}/health", base)).send(),
+        ).await.expect("/health quedo bloqueado por el handler");
+        assert_eq!(health.unwrap().status(), 200);
+
+        release.notify_one();
+        assert_eq!(slow.await.unwrap().unwrap().status(), 200);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_slow_handlers_run_in_parallel() {
+        // Upstream lento: cada GET tarda 300ms
+        let upstream = AxumRouter::new().route("/", get(|| async {
+            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+            "ok"
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, upstream).await.unwrap() });
+
+        let source = format!("+http\n\nslow = http.get(\"http://{}/\").status", upstream_addr);
+        let logger: RequestLogger = Arc::new(|_| {});
+        let base = serve(&source, vec![Route::new("GET", "/slow", "slow")], logger).await;
+        let client = reqwest::Client::new();
+
+        let started = Instant::now();
+        assert_eq!(client.get(format!("{}/slow", base)).send().await.unwrap().status(), 200);
+        let single = started.elapsed();
+
+        let started = Instant::now();
+        let requests = (0..4).map(|_| client.get(format!("{}/slow", base)).send());
+        for response in futures_util::future::join_all(requests).await {
+            assert_eq!(response.unwrap().status(), 200);
         }
+
+        // En serie serian cuatro veces `single`
+        let elapsed = started.elapsed();
+        assert!(elapsed < single * 2, "4 requests took {:?}, one took {:?}", elapsed, single);
+    }
+
+    #[test]
+    fn test_request_log_formats() {
+        let log = RequestLog {
+            method: "GET".to_string(),
+            path: "/users".to_string(),
+            status: 200,
+            duration_ms: 1.25,
+        };
+
+        assert_eq!(log.to_human(), "GET /users 200 1.2ms");
+        let json: serde_json::Value = serde_json::from_str(&log.to_json()).unwrap();
+        assert_eq!(json["method"], "GET");
+        assert_eq!(json["path"], "/users");
+        assert_eq!(json["status"], 200);
     }
 }
@@ -1,12 +1,16 @@
 // Módulo +server para AURA
 // Servidor HTTP nativo usando axum
 
+mod events;
 mod http;
 mod router;
 mod request;
 mod response;
+mod ws;
 
-pub use http::start_server;
+pub use events::{file_sink, stdout_sink, EventSink, RequestEvent};
+pub use http::{start_server, RequestLog, RequestLogger};
 pub use router::Route;
 pub use request::AuraRequest;
 pub use response::AuraResponse;
+pub use ws::ws_paths;
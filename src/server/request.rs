@@ -42,6 +42,19 @@ impl AuraRequest {
         self
     }
 
+    /// Valor que recibe el parametro `req` de un handler
+    ///
+    /// En POST, PUT y PATCH es el body JSON, para que el handler pueda hacer
+    /// `req.name` directamente (un record vacio si no hay body). En el resto
+    /// de metodos es el request completo (`req.params`, `req.query`, ...).
+    pub fn req_binding(&self) -> Value {
+        match self.method.to_uppercase().as_str() {
+            "POST" | "PUT" | "PATCH" => self.body.clone()
+                .unwrap_or_else(|| Value::Record(HashMap::new())),
+            _ => self.to_value(),
+        }
+    }
+
     /// Convierte el request a un Value de AURA (Record)
     pub fn to_value(&self) -> Value {
         let mut map = HashMap::new();
@@ -77,3 +90,41 @@ impl AuraRequest {
         Value::Record(map)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json_body() -> Value {
+        let mut body = HashMap::new();
+        body.insert("name".to_string(), Value::String("Ada".to_string()));
+        Value::Record(body)
+    }
+
+    #[test]
+    fn test_req_binding_is_body_for_write_methods() {
+        for method in ["POST", "PUT", "PATCH"] {
+            let request = AuraRequest::new(method, "/users").with_body(json_body());
+            assert_eq!(request.req_binding(), json_body());
+        }
+    }
+
+    #[test]
+    fn test_req_binding_without_body_is_empty_record() {
+        let request = AuraRequest::new("POST", "/users");
+        assert_eq!(request.req_binding(), Value::Record(HashMap::new()));
+    }
+
+    #[test]
+    fn test_req_binding_is_full_request_for_get() {
+        let mut params = HashMap::new();
+        params.insert("id".to_string(), "7".to_string());
+        let request = AuraRequest::new("GET", "/users/7").with_params(params);
+
+        let Value::Record(req) = request.req_binding() else {
+            panic!("req debe ser un record");
+        };
+        assert_eq!(req.get("method"), Some(&Value::String("GET".to_string())));
+        assert!(matches!(req.get("params"), Some(Value::Record(p)) if p.contains_key("id")));
+    }
+}
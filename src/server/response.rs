@@ -5,7 +5,9 @@ use std::collections::HashMap;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use crate::vm::Value;
+use axum::http::{HeaderName, HeaderValue};
+use crate::cli_output::JsonError;
+use crate::vm::{RuntimeError, Value};
 
 /// Response HTTP desde AURA
 #[derive(Debug, Clone)]
@@ -44,49 +46,56 @@ impl AuraResponse {
         Self::new(500, Value::Record(map))
     }
 
-    /// Convierte un Value de AURA a AuraResponse
-    /// Si es un record con {status: Int, body}, usa esa estructura
-    /// Si no, usa el valor completo como body con status 200
+    /// Error de ejecucion de un handler: 500 con `{error, code}`
+    pub fn runtime_error(err: &RuntimeError) -> Self {
+        let json = JsonError::from_runtime_error(err);
+        let mut map = HashMap::new();
+        map.insert("error".to_string(), Value::String(json.message));
+        map.insert("code".to_string(), Value::String(json.code));
+        Self::new(500, Value::Record(map))
+    }
+
+    /// Convierte el valor retornado por un handler a AuraResponse
+    ///
+    /// Convencion: un record con `status` (Int) y `body` es una respuesta
+    /// estructurada; `headers` (record de strings) es opcional:
+    ///
+    /// ```text
+    /// get_user(id) = {status: 404, body: {error: "no existe"}}
+    /// ```
+    ///
+    /// Un status fuera de 100..=599 produce un 500. Cualquier otro valor
+    /// se responde completo como body con status 200.
     pub fn from_value(value: Value) -> Self {
-        match &value {
-            Value::Record(map) => {
-                // Verificar si es un response estructurado (tiene status como Int y body)
-                let has_status_int = matches!(map.get("status"), Some(Value::Int(_)));
-                let has_body = map.contains_key("body");
-
-                if has_status_int && has_body {
-                    // Es un response estructurado
-                    let status = match map.get("status") {
-                        Some(Value::Int(n)) => *n as u16,
-                        _ => 200,
-                    };
-
-                    let body = map.get("body").cloned().unwrap_or(Value::Nil);
-
-                    let headers = match map.get("headers") {
-                        Some(Value::Record(h)) => {
-                            h.iter()
-                                .filter_map(|(k, v)| {
-                                    if let Value::String(s) = v {
-                                        Some((k.clone(), s.clone()))
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect()
+        let Value::Record(map) = &value else {
+            return Self::ok(value);
+        };
+
+        let (Some(Value::Int(status)), Some(body)) = (map.get("status"), map.get("body")) else {
+            // El record completo es el body
+            return Self::ok(value);
+        };
+
+        if !(100..=599).contains(status) {
+            return Self::error(&format!("Invalid HTTP status code: {}", status));
+        }
+
+        let headers = match map.get("headers") {
+            Some(Value::Record(h)) => {
+                h.iter()
+                    .filter_map(|(k, v)| {
+                        if let Value::String(s) = v {
+                            Some((k.clone(), s.clone()))
+                        } else {
+                            None
                         }
-                        _ => HashMap::new(),
-                    };
-
-                    Self { status, headers, body }
-                } else {
-                    // El record completo es el body
-                    Self::ok(value)
-                }
+                    })
+                    .collect()
             }
-            // Si no es un record, asumir 200 OK con el valor como body
-            _ => Self::ok(value),
-        }
+            _ => HashMap::new(),
+        };
+
+        Self { status: *status as u16, headers, body: body.clone() }
     }
 }
 
@@ -95,7 +104,7 @@ impl IntoResponse for AuraResponse {
         let status = StatusCode::from_u16(self.status)
             .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
 
-        let json_body = value_to_json(&self.body);
+        let json_body = self.body.to_json();
 
         // CORS headers
         let cors_headers = [
@@ -104,34 +113,65 @@ impl IntoResponse for AuraResponse {
             (axum::http::header::ACCESS_CONTROL_ALLOW_HEADERS, "Content-Type"),
         ];
 
-        (status, cors_headers, Json(json_body)).into_response()
+        let mut response = (status, cors_headers, Json(json_body)).into_response();
+
+        // Headers definidos por el handler; los nombres o valores invalidos se ignoran
+        for (name, value) in &self.headers {
+            if let (Ok(name), Ok(value)) = (HeaderName::try_from(name.as_str()), HeaderValue::try_from(value.as_str())) {
+                response.headers_mut().insert(name, value);
+            }
+        }
+
+        response
     }
 }
 
-/// Convierte Value de AURA a serde_json::Value
-fn value_to_json(value: &Value) -> serde_json::Value {
-    match value {
-        Value::Nil => serde_json::Value::Null,
-        Value::Bool(b) => serde_json::Value::Bool(*b),
-        Value::Int(n) => serde_json::Value::Number((*n).into()),
-        Value::Float(f) => {
-            serde_json::Number::from_f64(*f)
-                .map(serde_json::Value::Number)
-                .unwrap_or(serde_json::Value::Null)
-        }
-        Value::String(s) => serde_json::Value::String(s.clone()),
-        Value::List(l) => {
-            serde_json::Value::Array(l.iter().map(value_to_json).collect())
-        }
-        Value::Record(r) => {
-            let obj: serde_json::Map<String, serde_json::Value> = r.iter()
-                .map(|(k, v)| (k.clone(), value_to_json(v)))
-                .collect();
-            serde_json::Value::Object(obj)
-        }
-        Value::Function(name) => serde_json::Value::String(format!("<fn {}>", name)),
-        Value::Native { type_id, handle } => {
-            serde_json::Value::String(format!("<{} #{}>", type_id, handle))
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(fields: &[(&str, Value)]) -> Value {
+        Value::Record(fields.iter().map(|(k, v)| (k.to_string(), v.clone())).collect())
+    }
+
+    #[test]
+    fn test_plain_value_is_200() {
+        let response = AuraResponse::from_value(Value::List(vec![Value::Int(1)]));
+        assert_eq!(response.status, 200);
+        assert!(matches!(response.body, Value::List(_)));
+
+        // Un record sin body se responde completo, aunque tenga status
+        let value = record(&[("status", Value::Int(3))]);
+        let response = AuraResponse::from_value(value.clone());
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, value);
+    }
+
+    #[test]
+    fn test_structured_response() {
+        let body = record(&[("error", Value::String("no existe".into()))]);
+        let headers = record(&[("x-trace", Value::String("abc".into())), ("x-bad", Value::Int(1))]);
+        let value = record(&[("status", Value::Int(404)), ("body", body.clone()), ("headers", headers)]);
+
+        let response = AuraResponse::from_value(value);
+        assert_eq!(response.status, 404);
+        assert_eq!(response.body, body);
+        assert_eq!(response.headers.len(), 1);
+        assert_eq!(response.headers["x-trace"], "abc");
+    }
+
+    #[test]
+    fn test_invalid_status_is_500() {
+        let value = record(&[("status", Value::Int(70000)), ("body", Value::Nil)]);
+        assert_eq!(AuraResponse::from_value(value).status, 500);
+    }
+
+    #[test]
+    fn test_runtime_error_response() {
+        let response = AuraResponse::runtime_error(&RuntimeError::new("Division por cero"));
+        assert_eq!(response.status, 500);
+        let Value::Record(map) = response.body else { panic!("expected record") };
+        assert_eq!(map["error"], Value::String("Division por cero".into()));
+        assert_eq!(map["code"], Value::String("E401".into()));
     }
 }
@@ -40,9 +40,8 @@ impl Route {
         let mut params = HashMap::new();
 
         for (route_part, path_part) in route_parts.iter().zip(path_parts.iter()) {
-            if route_part.starts_with(':') {
+            if let Some(param_name) = route_part.strip_prefix(':') {
                 // Es un parámetro
-                let param_name = &route_part[1..];
                 params.insert(param_name.to_string(), path_part.to_string());
             } else if route_part != path_part {
                 // No coincide
@@ -78,6 +77,11 @@ impl Router {
         self.routes.push(route);
     }
 
+    /// Cantidad de rutas registradas
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
     /// Encuentra la ruta que coincide con el método y path
     pub fn find_route(&self, method: &str, path: &str) -> Option<(&Route, HashMap<String, String>)> {
         for route in &self.routes {
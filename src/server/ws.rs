@@ -0,0 +1,184 @@
+// WebSockets para AURA (capability `ws`)
+// Una funcion `ws_<name>` se expone en `/ws/<name>`
+
+use std::sync::Arc;
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::Response,
+};
+
+use crate::parser::{Definition, Program};
+use crate::vm::Value;
+use super::http::ServerState;
+use super::response::AuraResponse;
+
+/// Prefijo de las funciones que atienden WebSockets
+const WS_PREFIX: &str = "ws_";
+
+/// Nombre del endpoint para una funcion `ws_<name>`
+pub fn ws_endpoint_name(func_name: &str) -> Option<&str> {
+    func_name.strip_prefix(WS_PREFIX).filter(|name| !name.is_empty())
+}
+
+/// Paths `/ws/<name>` de todas las funciones WebSocket del programa
+pub fn ws_paths(program: &Program) -> Vec<String> {
+    ws_endpoints(program).into_iter().map(|(path, _)| path).collect()
+}
+
+/// Pares `(path, handler)` de las funciones WebSocket del programa
+pub(super) fn ws_endpoints(program: &Program) -> Vec<(String, String)> {
+    program.definitions.iter()
+        .filter_map(|def| match def {
+            Definition::FuncDef(func) => ws_endpoint_name(&func.name)
+                .map(|name| (format!("/ws/{}", name), func.name.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Upgrade de un path `/ws/<name>` hacia su funcion `ws_<name>`
+///
+/// Solo se registra para los handlers que existen, asi el resto de `/ws/...`
+/// queda para las rutas HTTP del programa
+pub(super) async fn handle_upgrade(
+    state: Arc<ServerState>,
+    handler: String,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade.on_upgrade(move |socket| session(state, handler, socket))
+}
+
+/// Atiende una conexion: cada mensaje de texto se pasa al handler y su
+/// resultado se envia de vuelta, hasta que el cliente cierra
+async fn session(state: Arc<ServerState>, handler: String, mut socket: WebSocket) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            // Ping/pong los responde axum; binario no esta soportado
+            _ => continue,
+        };
+
+        let worker_state = state.clone();
+        let worker_handler = handler.clone();
+        let outcome = tokio::task::spawn_blocking(move || {
+            worker_state.new_vm().call_by_name(&worker_handler, vec![Value::String(text)])
+        }).await;
+
+        let reply = match outcome {
+            Ok(Ok(value)) => reply_text(&value),
+            Ok(Err(e)) => {
+                let error = AuraResponse::runtime_error(&e);
+                error.body.to_json().to_string()
+            }
+            Err(e) => AuraResponse::error(&format!("Handler panicked: {}", e)).body.to_json().to_string(),
+        };
+
+        if socket.send(Message::Text(reply)).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Un string se envia tal cual; cualquier otro valor como JSON
+fn reply_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_json().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::{connect_async, tungstenite::Message as ClientMessage};
+    use crate::lexer::tokenize;
+    use crate::parser::parse;
+    use super::super::http::build_app;
+
+    const SOURCE: &str = r#"
+ws_echo(msg) = "eco: " ++ msg
+ws_size(msg) = {size: len(msg)}
+"#;
+
+    async fn serve() -> String {
+        let program = parse(tokenize(SOURCE).unwrap()).unwrap();
+        let app = build_app(Vec::new(), program, None, None);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("ws://{}", addr)
+    }
+
+    #[test]
+    fn test_ws_endpoint_name() {
+        assert_eq!(ws_endpoint_name("ws_chat"), Some("chat"));
+        assert_eq!(ws_endpoint_name("ws_"), None);
+        assert_eq!(ws_endpoint_name("get_users"), None);
+
+        let program = parse(tokenize(SOURCE).unwrap()).unwrap();
+        assert_eq!(ws_paths(&program), vec!["/ws/echo", "/ws/size"]);
+    }
+
+    #[tokio::test]
+    async fn test_echo_and_clean_close() {
+        let base = serve().await;
+        let (mut socket, _) = connect_async(format!("{}/ws/echo", base)).await.unwrap();
+
+        socket.send(ClientMessage::Text("hola".into())).await.unwrap();
+        let reply = socket.next().await.unwrap().unwrap();
+        assert_eq!(reply, ClientMessage::Text("eco: hola".into()));
+
+        socket.send(ClientMessage::Text("chau".into())).await.unwrap();
+        let reply = socket.next().await.unwrap().unwrap();
+        assert_eq!(reply, ClientMessage::Text("eco: chau".into()));
+
+        // El servidor responde el close y termina la conexion
+        socket.close(None).await.unwrap();
+        while let Some(message) = socket.next().await {
+            if let Ok(message) = message {
+                assert!(message.is_close());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_non_string_reply_is_json() {
+        let base = serve().await;
+        let (mut socket, _) = connect_async(format!("{}/ws/size", base)).await.unwrap();
+
+        socket.send(ClientMessage::Text("abc".into())).await.unwrap();
+        let reply = socket.next().await.unwrap().unwrap();
+        let json: serde_json::Value = serde_json::from_str(reply.to_text().unwrap()).unwrap();
+        assert_eq!(json["size"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_endpoint_is_rejected() {
+        let base = serve().await;
+        assert!(connect_async(format!("{}/ws/missing", base)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_http_routes_under_ws_still_resolve() {
+        let source = format!("{}get_status() = {{ok: true}}\n", SOURCE);
+        let program = parse(tokenize(&source).unwrap()).unwrap();
+        let routes = vec![super::super::router::Route::new("GET", "/ws/status", "get_status")];
+        let app = build_app(routes, program, None, None);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let response = reqwest::get(format!("http://{}/ws/status", addr)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let json: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(json["ok"], true);
+
+        // El handler WebSocket sigue en su path
+        assert!(connect_async(format!("ws://{}/ws/echo", addr)).await.is_ok());
+    }
+}
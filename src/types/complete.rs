@@ -0,0 +1,206 @@
+// Autocompletado de identificadores (`aura complete`)
+// Usa los mismos registros que el type checker para saber que hay en scope
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::TypeContext;
+use crate::parser::{Definition, Expr, FuncDef, Program};
+
+/// Que es cada candidato
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionKind {
+    /// Parametro o `let` de la funcion donde esta el cursor
+    Variable,
+    /// Funcion definida en el programa (o importada)
+    Function,
+    /// Tipo o enum definido en el programa
+    Type,
+    /// Modulo de una capacidad declarada (`http`, `json`, ...)
+    Capability,
+    /// Funcion del runtime
+    Builtin,
+}
+
+/// Candidato de autocompletado
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Completion {
+    pub name: String,
+    pub kind: CompletionKind,
+}
+
+/// Identificador parcial que termina en `offset` (vacio si no hay ninguno)
+pub fn prefix_at(source: &str, offset: usize) -> &str {
+    let offset = offset.min(source.len());
+    let before = source.get(..offset).unwrap_or("");
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(offset);
+    &before[start..]
+}
+
+/// Candidatos para el identificador en `offset` de `source`
+///
+/// `program` es el archivo ya cargado (con imports) y da las funciones,
+/// tipos y capacidades. Las variables salen de la funcion que contiene
+/// `offset`: sus parametros y los `let` de su cuerpo (las expresiones no
+/// tienen spans, asi que no se distingue si el `let` esta antes o despues).
+///
+/// Despues de un `.` no se sugiere nada (los metodos dependen del valor).
+/// El resultado viene filtrado por el prefijo y ordenado por tipo de
+/// candidato y nombre.
+pub fn complete(program: &Program, source: &str, offset: usize) -> Vec<Completion> {
+    let prefix = prefix_at(source, offset);
+    let before = &source[..offset.min(source.len()) - prefix.len()];
+    if before.ends_with('.') {
+        return Vec::new();
+    }
+
+    let mut ctx = TypeContext::new();
+    for cap in &program.capabilities {
+        ctx.register_capability(&cap.name);
+    }
+
+    let mut candidates: HashMap<String, CompletionKind> = HashMap::new();
+    let mut add = |name: &str, kind: CompletionKind| {
+        let entry = candidates.entry(name.to_string()).or_insert(kind);
+        *entry = (*entry).min(kind);
+    };
+
+    for def in &program.definitions {
+        match def {
+            Definition::TypeDef(t) => add(&t.name, CompletionKind::Type),
+            Definition::EnumDef(e) => add(&e.name, CompletionKind::Type),
+            Definition::FuncDef(f) => add(&f.name, CompletionKind::Function),
+            _ => {}
+        }
+    }
+
+    for name in &ctx.functions {
+        let kind = if ctx.capabilities.contains(name) {
+            CompletionKind::Capability
+        } else {
+            CompletionKind::Builtin
+        };
+        add(name, kind);
+    }
+
+    if let Some(func) = enclosing_function(source, offset) {
+        for param in &func.params {
+            add(&param.name, CompletionKind::Variable);
+        }
+        let mut lets = Vec::new();
+        collect_lets(&func.body, &mut lets);
+        for name in lets {
+            add(name, CompletionKind::Variable);
+        }
+    }
+
+    let mut completions: Vec<Completion> = candidates
+        .into_iter()
+        .filter(|(name, _)| name.starts_with(prefix))
+        .map(|(name, kind)| Completion { name, kind })
+        .collect();
+    completions.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+    completions
+}
+
+/// Funcion del propio archivo cuyo span contiene `offset`. Se parsea el
+/// fuente (y no el programa cargado) porque los spans de las definiciones
+/// importadas son de otros archivos.
+fn enclosing_function(source: &str, offset: usize) -> Option<FuncDef> {
+    let tokens = crate::lexer::tokenize(source).ok()?;
+    let program = crate::parser::parse(tokens).ok()?;
+    program.definitions.into_iter().find_map(|def| match def {
+        Definition::FuncDef(f) if f.span.start <= offset && offset <= f.span.end => Some(f),
+        _ => None,
+    })
+}
+
+/// Nombres ligados con `let` en una expresion
+fn collect_lets<'a>(expr: &'a Expr, names: &mut Vec<&'a str>) {
+    match expr {
+        Expr::Let { name, value } => {
+            names.push(name);
+            collect_lets(value, names);
+        }
+        Expr::Block(exprs) | Expr::Pipe(exprs) | Expr::List(exprs) => {
+            for expr in exprs {
+                collect_lets(expr, names);
+            }
+        }
+        Expr::If { condition, then_branch, else_branch } => {
+            collect_lets(condition, names);
+            collect_lets(then_branch, names);
+            if let Some(else_expr) = else_branch {
+                collect_lets(else_expr, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+    use crate::parser::parse;
+
+    fn complete_source(source: &str, offset: usize) -> Vec<Completion> {
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        complete(&program, source, offset)
+    }
+
+    fn names(completions: &[Completion]) -> Vec<&str> {
+        completions.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_prefix_at() {
+        assert_eq!(prefix_at("main = pri", 10), "pri");
+        assert_eq!(prefix_at("main = pri", 7), "");
+        assert_eq!(prefix_at("main = http.ge", 14), "ge");
+        assert_eq!(prefix_at("x", 100), "x");
+    }
+
+    #[test]
+    fn test_complete_includes_builtins_and_user_functions() {
+        let source = "+http\n@User { name s }\ndouble(x) = x * 2\nmain = d";
+        let completions = complete_source(source, source.len());
+        assert_eq!(names(&completions), vec!["double", "dedup"]);
+        assert_eq!(completions[0].kind, CompletionKind::Function);
+        assert_eq!(completions[1].kind, CompletionKind::Builtin);
+
+        let all = complete_source(source, source.len() - 1);
+        let find = |name: &str| all.iter().find(|c| c.name == name).map(|c| c.kind);
+        assert_eq!(find("print"), Some(CompletionKind::Builtin));
+        assert_eq!(find("http"), Some(CompletionKind::Capability));
+        assert_eq!(find("User"), Some(CompletionKind::Type));
+        assert_eq!(find("main"), Some(CompletionKind::Function));
+        // Sin +json su modulo no esta en scope
+        assert_eq!(find("json"), None);
+    }
+
+    #[test]
+    fn test_complete_variables_of_enclosing_function() {
+        let source = "area(width, height) = width * height\nmain = area(1, 2)";
+        let inside = complete_source(source, "area(width, height) = w".len());
+        assert_eq!(inside[0], Completion { name: "width".to_string(), kind: CompletionKind::Variable });
+
+        // En otra funcion los parametros de `area` no estan en scope
+        let outside = complete_source(source, source.len());
+        assert!(!names(&outside).contains(&"width"));
+    }
+
+    #[test]
+    fn test_complete_after_dot_is_empty() {
+        let source = "+http\nmain = http.g";
+        assert!(complete_source(source, source.len()).is_empty());
+    }
+}
@@ -0,0 +1,234 @@
+// Seguimiento de efectos (E301)
+// Una funcion tiene efectos si se declara con `!`, si llama a un modulo con
+// efectos (`http.get`, `db.query`, ...) o si llama a otra funcion con efectos
+
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::{Definition, Expr, FuncDef, Program, StringPart};
+use super::TypeWarning;
+
+/// Modulos cuyas llamadas salen del programa (red, base de datos, correo)
+const EFFECT_NAMESPACES: &[&str] = &["http", "db", "email"];
+
+/// Por que una funcion tiene efectos
+#[derive(Debug, Clone, PartialEq)]
+pub enum EffectSource {
+    /// Declarada con `!`
+    Declared,
+    /// Llama a un modulo con efectos (`http`, `db`, ...)
+    Namespace(String),
+    /// Llama a otra funcion del programa con efectos
+    Calls(String),
+}
+
+/// Funciones del programa con efectos y el motivo de cada una
+///
+/// Los efectos transitivos se propagan hasta un punto fijo, asi que una
+/// cadena `main -> load -> fetch` marca a las tres aunque solo `fetch`
+/// use `http`.
+pub fn effectful_functions(program: &Program) -> HashMap<String, EffectSource> {
+    let funcs: Vec<&FuncDef> = program.definitions.iter()
+        .filter_map(|def| match def {
+            Definition::FuncDef(f) => Some(f),
+            _ => None,
+        })
+        .collect();
+
+    let mut effects: HashMap<String, EffectSource> = HashMap::new();
+    let mut callees: Vec<Vec<String>> = Vec::with_capacity(funcs.len());
+
+    for func in &funcs {
+        let mut called = Vec::new();
+        let mut namespace = None;
+        for_each_call(&func.body, &mut |callee, _| match callee {
+            Expr::Ident(name) if !is_param(func, name) => called.push(name.clone()),
+            Expr::FieldAccess(obj, _) => {
+                if let Expr::Ident(ns) = obj.as_ref()
+                    && EFFECT_NAMESPACES.contains(&ns.as_str()) && namespace.is_none() {
+                    namespace = Some(ns.clone());
+                }
+            }
+            _ => {}
+        });
+
+        if func.has_effect {
+            effects.insert(func.name.clone(), EffectSource::Declared);
+        } else if let Some(ns) = namespace {
+            effects.insert(func.name.clone(), EffectSource::Namespace(ns));
+        }
+        callees.push(called);
+    }
+
+    loop {
+        let mut changed = false;
+        for (func, called) in funcs.iter().zip(&callees) {
+            if effects.contains_key(&func.name) {
+                continue;
+            }
+            if let Some(callee) = called.iter().find(|name| effects.contains_key(name.as_str())) {
+                effects.insert(func.name.clone(), EffectSource::Calls(callee.clone()));
+                changed = true;
+            }
+        }
+        if !changed {
+            return effects;
+        }
+    }
+}
+
+/// Una advertencia por cada funcion con efectos llamada sin `!`
+///
+/// Las expresiones no tienen spans: la advertencia apunta a la funcion
+/// donde esta la llamada, y se reporta una vez por par (llamador, llamada).
+pub fn check_effects(program: &Program) -> Vec<TypeWarning> {
+    let effects = effectful_functions(program);
+    let mut warnings = Vec::new();
+
+    for def in &program.definitions {
+        let Definition::FuncDef(func) = def else { continue };
+        let mut reported: HashSet<String> = HashSet::new();
+        for_each_call(&func.body, &mut |callee, has_effect| {
+            let Expr::Ident(name) = callee else { return };
+            if has_effect || is_param(func, name) || !reported.insert(name.clone()) {
+                return;
+            }
+            let Some(source) = effects.get(name) else { return };
+            let reason = match source {
+                EffectSource::Declared => String::new(),
+                EffectSource::Namespace(ns) => format!(" (usa {})", ns),
+                EffectSource::Calls(other) => format!(" (llama a '{}')", other),
+            };
+            warnings.push(
+                TypeWarning::new("E301", format!(
+                    "La funcion '{}' tiene efectos{} pero '{}' la llama sin '!'",
+                    name, reason, func.name
+                ))
+                .with_span(func.span.clone())
+                .with_suggestion(format!("Usa '{}!(...)'", name))
+            );
+        });
+    }
+
+    warnings
+}
+
+fn is_param(func: &FuncDef, name: &str) -> bool {
+    func.params.iter().any(|p| p.name == name)
+}
+
+/// Recorre las llamadas de una expresion: la funcion llamada y si se marco con `!`
+fn for_each_call(expr: &Expr, f: &mut dyn FnMut(&Expr, bool)) {
+    match expr {
+        Expr::Call { func, args, has_effect } => {
+            f(func, *has_effect);
+            for_each_call(func, f);
+            for arg in args {
+                for_each_call(arg, f);
+            }
+        }
+        Expr::List(items) | Expr::Pipe(items) | Expr::Block(items) => {
+            for item in items {
+                for_each_call(item, f);
+            }
+        }
+        Expr::Record(fields) => {
+            for (_, value) in fields {
+                for_each_call(value, f);
+            }
+        }
+        Expr::FieldAccess(obj, _) | Expr::SafeAccess(obj, _) => for_each_call(obj, f),
+        Expr::BinaryOp { left, right, .. } | Expr::NullCoalesce(left, right) => {
+            for_each_call(left, f);
+            for_each_call(right, f);
+        }
+        Expr::UnaryOp { expr, .. } | Expr::Spread(expr) | Expr::Cast { expr, .. } => for_each_call(expr, f),
+        Expr::Match { expr, arms } => {
+            for_each_call(expr, f);
+            for arm in arms {
+                if let Some(guard) = &arm.guard {
+                    for_each_call(guard, f);
+                }
+                for_each_call(&arm.body, f);
+            }
+        }
+        Expr::Lambda { body, .. } => for_each_call(body, f),
+        Expr::Let { value, .. } => for_each_call(value, f),
+        Expr::If { condition, then_branch, else_branch } => {
+            for_each_call(condition, f);
+            for_each_call(then_branch, f);
+            if let Some(else_expr) = else_branch {
+                for_each_call(else_expr, f);
+            }
+        }
+        Expr::For { iter, body, .. } => {
+            for_each_call(iter, f);
+            for_each_call(body, f);
+        }
+        Expr::InterpolatedString(parts) => {
+            for part in parts {
+                if let StringPart::Expr(expr) = part {
+                    for_each_call(expr, f);
+                }
+            }
+        }
+        Expr::Expect { condition, .. } => for_each_call(condition, f),
+        Expr::Observe { condition: Some(condition), .. } => for_each_call(condition, f),
+        Expr::Reason { observations, .. } => {
+            for observation in observations {
+                for_each_call(observation, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+    use crate::parser::parse;
+
+    fn program(source: &str) -> Program {
+        parse(tokenize(source).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_direct_effects() {
+        let source = "+http\nsave!(x) = x\nfetch(url) = http.get(url)\npure(x) = x * 2\nmain = 1\n";
+        let effects = effectful_functions(&program(source));
+        assert_eq!(effects.get("save"), Some(&EffectSource::Declared));
+        assert_eq!(effects.get("fetch"), Some(&EffectSource::Namespace("http".to_string())));
+        assert_eq!(effects.get("pure"), None);
+        assert_eq!(effects.get("main"), None);
+    }
+
+    #[test]
+    fn test_transitive_effects() {
+        let source = "+http\nfetch(url) = http.get(url)\nload(id) = fetch(\"/users/{id}\")\nreport(id) = [load(id), 2]\nmain = 1\n";
+        let effects = effectful_functions(&program(source));
+        assert_eq!(effects.get("load"), Some(&EffectSource::Calls("fetch".to_string())));
+        assert_eq!(effects.get("report"), Some(&EffectSource::Calls("load".to_string())));
+        assert_eq!(effects.get("main"), None);
+    }
+
+    #[test]
+    fn test_warns_calls_without_bang() {
+        let source = "+http\nfetch(url) = http.get(url)\nload(id) = fetch!(id)\nmain = [load(1), load(2), fetch(\"x\")]\n";
+        let warnings = check_effects(&program(source));
+        let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+        // `fetch!` en `load` no advierte y `load` se reporta una sola vez
+        assert_eq!(messages, vec![
+            "La funcion 'load' tiene efectos (llama a 'fetch') pero 'main' la llama sin '!'",
+            "La funcion 'fetch' tiene efectos (usa http) pero 'main' la llama sin '!'",
+        ]);
+        assert!(warnings.iter().all(|w| w.code == "E301"));
+        let span = warnings[0].span.clone().unwrap();
+        assert!(source[span.start..span.end].starts_with("main ="));
+    }
+
+    #[test]
+    fn test_parameter_shadows_effectful_function() {
+        let source = "send!(x) = x\napply(send, x) = send(x)\nmain = apply(str, 1)\n";
+        assert!(check_effects(&program(source)).is_empty());
+    }
+}
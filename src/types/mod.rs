@@ -1,8 +1,11 @@
 // Type checker básico de AURA
 // Verifica que funciones y tipos referenciados existan
 
-use std::collections::HashSet;
-use crate::parser::{Program, Definition, Expr, Type, TypeDef, FuncDef};
+pub mod complete;
+pub mod effects;
+
+use std::collections::{HashMap, HashSet};
+use crate::parser::{Program, Definition, Expr, Type, TypeDef, FuncDef, BinaryOp, UnaryOp, StringPart};
 use crate::lexer::Span;
 
 /// Error de tipo
@@ -11,6 +14,47 @@ pub struct TypeError {
     pub message: String,
     pub span: Option<Span>,
     pub suggestion: Option<String>,
+    /// Otra ubicacion relacionada (la primera definicion de un duplicado)
+    pub previous_span: Option<Span>,
+    /// Cambio al fuente que corrige el error (lo aplica `aura check --fix`)
+    pub replacement: Option<Replacement>,
+}
+
+/// Advertencia del type checker: se reporta pero no invalida el programa
+#[derive(Debug, Clone)]
+pub struct TypeWarning {
+    pub code: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+    pub suggestion: Option<String>,
+}
+
+impl TypeWarning {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            span: None,
+            suggestion: None,
+        }
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+}
+
+/// Reemplazo de un rango del fuente; un span vacio es una insercion
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replacement {
+    pub span: Span,
+    pub text: String,
 }
 
 impl TypeError {
@@ -19,6 +63,8 @@ impl TypeError {
             message: message.into(),
             span: None,
             suggestion: None,
+            previous_span: None,
+            replacement: None,
         }
     }
 
@@ -27,18 +73,33 @@ impl TypeError {
         self
     }
 
+    pub fn with_previous_span(mut self, span: Span) -> Self {
+        self.previous_span = Some(span);
+        self
+    }
+
     pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
         self.suggestion = Some(suggestion.into());
         self
     }
 
+    pub fn with_replacement(mut self, span: Span, text: impl Into<String>) -> Self {
+        self.replacement = Some(Replacement { span, text: text.into() });
+        self
+    }
+
     /// Serializa a JSON (para agentes)
     pub fn to_json(&self) -> String {
         serde_json::json!({
             "error": "type_error",
             "message": self.message,
             "span": self.span,
-            "suggestion": self.suggestion
+            "previous_span": self.previous_span,
+            "suggestion": self.suggestion,
+            "replacement": self.replacement.as_ref().map(|r| serde_json::json!({
+                "span": r.span,
+                "text": r.text,
+            }))
         }).to_string()
     }
 }
@@ -70,6 +131,19 @@ impl TypeContext {
         ctx.functions.insert("last".to_string());
         ctx.functions.insert("sort".to_string());
         ctx.functions.insert("join".to_string());
+        ctx.functions.insert("contains".to_string());
+        ctx.functions.insert("range".to_string());
+        ctx.functions.insert("sum".to_string());
+        ctx.functions.insert("min".to_string());
+        ctx.functions.insert("max".to_string());
+        ctx.functions.insert("avg".to_string());
+        ctx.functions.insert("reverse".to_string());
+        ctx.functions.insert("unique".to_string());
+        ctx.functions.insert("dedup".to_string());
+        ctx.functions.insert("bytes".to_string());
+        ctx.functions.insert("Ok".to_string());
+        ctx.functions.insert("Err".to_string());
+        ctx.functions.insert("math".to_string());
         ctx
     }
 
@@ -91,12 +165,27 @@ impl TypeContext {
         match name {
             "http" => {
                 // http.get, http.post, etc. se manejan como métodos
+                self.functions.insert("http".to_string());
             }
             "json" => {
                 self.functions.insert("json".to_string());
             }
+            "auth" => {
+                // auth.hash, auth.jwt_sign, etc.
+                self.functions.insert("auth".to_string());
+            }
+            "email" => {
+                self.functions.insert("email".to_string());
+            }
+            "crypto" => {
+                self.functions.insert("crypto".to_string());
+            }
+            "env" => {
+                self.functions.insert("env".to_string());
+            }
             "db" => {
-                // Métodos CRUD en tipos
+                // Métodos CRUD en tipos, y db.query/db.execute
+                self.functions.insert("db".to_string());
             }
             "fs" => {
                 self.functions.insert("read".to_string());
@@ -125,6 +214,17 @@ impl TypeContext {
 pub struct TypeChecker {
     ctx: TypeContext,
     errors: Vec<TypeError>,
+    warnings: Vec<TypeWarning>,
+    /// Capacidades faltantes ya reportadas (un error por capacidad)
+    missing_capabilities: HashSet<String>,
+    /// Funciones del programa (la ultima definicion, como en la VM)
+    definitions: HashMap<String, FuncDef>,
+    /// Tipos de retorno ya inferidos
+    return_types: HashMap<String, Type>,
+    /// Funciones cuyo cuerpo se esta infiriendo (corta la recursion)
+    inferring: HashSet<String>,
+    /// Funcion cuyo cuerpo se esta infiriendo: los errores apuntan a ella
+    current_function: Option<Span>,
 }
 
 impl TypeChecker {
@@ -132,6 +232,12 @@ impl TypeChecker {
         Self {
             ctx: TypeContext::new(),
             errors: Vec::new(),
+            warnings: Vec::new(),
+            missing_capabilities: HashSet::new(),
+            definitions: HashMap::new(),
+            return_types: HashMap::new(),
+            inferring: HashSet::new(),
+            current_function: None,
         }
     }
 
@@ -142,13 +248,39 @@ impl TypeChecker {
             self.ctx.register_capability(&cap.name);
         }
 
+        // La VM se queda con la ultima de dos definiciones con el mismo
+        // nombre; aca se reportan para que no pasen desapercibidas
+        let mut types_seen: HashMap<&str, &Span> = HashMap::new();
+        let mut functions_seen: HashMap<&str, &Span> = HashMap::new();
+
         for def in &program.definitions {
             match def {
                 Definition::TypeDef(t) => {
+                    if let Some(first) = types_seen.get(t.name.as_str()) {
+                        self.errors.push(
+                            TypeError::new(format!("Tipo '{}' definido mas de una vez", t.name))
+                                .with_span(t.span.clone())
+                                .with_previous_span((*first).clone())
+                                .with_suggestion(format!("Renombra o elimina una de las definiciones de @{}", t.name))
+                        );
+                    } else {
+                        types_seen.insert(&t.name, &t.span);
+                    }
                     self.ctx.register_type(&t.name);
                 }
                 Definition::FuncDef(f) => {
+                    if let Some(first) = functions_seen.get(f.name.as_str()) {
+                        self.errors.push(
+                            TypeError::new(format!("Funcion '{}' definida mas de una vez", f.name))
+                                .with_span(f.span.clone())
+                                .with_previous_span((*first).clone())
+                                .with_suggestion(format!("Renombra o elimina una de las definiciones de {}", f.name))
+                        );
+                    } else {
+                        functions_seen.insert(&f.name, &f.span);
+                    }
                     self.ctx.register_function(&f.name);
+                    self.definitions.insert(f.name.clone(), f.clone());
                 }
                 _ => {}
             }
@@ -163,10 +295,30 @@ impl TypeChecker {
                 Definition::FuncDef(f) => {
                     self.check_func_def(f);
                 }
+                Definition::TestDef(t) => {
+                    self.check_expr(&t.expr, &HashSet::new());
+                }
+                Definition::Route(r) if !self.ctx.function_exists(&r.handler) => {
+                    self.errors.push(
+                        TypeError::new(format!("Handler de ruta no definido: '{}'", r.handler))
+                            .with_span(r.span.clone())
+                            .with_suggestion(format!("Define la funcion: {} = ...", r.handler))
+                    );
+                }
                 _ => {}
             }
         }
 
+        // Tercera pasada: inferir los tipos de cada funcion
+        for def in &program.definitions {
+            if let Definition::FuncDef(f) = def {
+                self.infer_function(&f.name);
+            }
+        }
+
+        // Funciones con efectos llamadas sin `!`
+        self.warnings = effects::check_effects(program);
+
         // Verificar que existe main
         if !self.ctx.function_exists("main") {
             self.errors.push(
@@ -182,6 +334,11 @@ impl TypeChecker {
         }
     }
 
+    /// Advertencias de la ultima verificacion
+    pub fn warnings(&self) -> &[TypeWarning] {
+        &self.warnings
+    }
+
     /// Verifica una definición de tipo
     fn check_type_def(&mut self, ty: &TypeDef) {
         for field in &ty.fields {
@@ -192,13 +349,11 @@ impl TypeChecker {
     /// Verifica un tipo
     fn check_type(&mut self, ty: &Type) {
         match ty {
-            Type::Named(name) => {
-                if !self.ctx.type_exists(name) && !is_builtin_type(name) {
-                    self.errors.push(
-                        TypeError::new(format!("Tipo no definido: {}", name))
-                            .with_suggestion(format!("Definir: @{} {{ ... }}", name))
-                    );
-                }
+            Type::Named(name) if !self.ctx.type_exists(name) && !is_builtin_type(name) => {
+                self.errors.push(
+                    TypeError::new(format!("Tipo no definido: {}", name))
+                        .with_suggestion(format!("Definir: @{} {{ ... }}", name))
+                );
             }
             Type::List(inner) => {
                 self.check_type(inner);
@@ -229,12 +384,15 @@ impl TypeChecker {
     /// Verifica una expresión
     fn check_expr(&mut self, expr: &Expr, local_vars: &HashSet<String>) {
         match expr {
-            Expr::Ident(name) => {
-                // Verificar que la variable existe
+            // Verificar que la variable existe
+            Expr::Ident(name)
                 if !local_vars.contains(name)
                     && !self.ctx.function_exists(name)
-                    && !self.ctx.type_exists(name)
-                {
+                    && !self.ctx.type_exists(name) =>
+            {
+                if CAPABILITY_NAMESPACES.contains(&name.as_str()) {
+                    self.missing_capability(name);
+                } else {
                     self.errors.push(
                         TypeError::new(format!("Identificador no definido: {}", name))
                     );
@@ -290,6 +448,10 @@ impl TypeChecker {
                 }
             }
 
+            Expr::Spread(inner) => {
+                self.check_expr(inner, local_vars);
+            }
+
             Expr::Pipe(exprs) => {
                 for expr in exprs {
                     self.check_expr(expr, local_vars);
@@ -304,7 +466,19 @@ impl TypeChecker {
                 self.check_expr(body, &new_vars);
             }
 
-            Expr::Let { name, value } => {
+            Expr::Match { expr, arms } => {
+                self.check_expr(expr, local_vars);
+                for arm in arms {
+                    let mut arm_vars = local_vars.clone();
+                    arm_vars.extend(arm.pattern.bindings().into_iter().map(String::from));
+                    if let Some(guard) = &arm.guard {
+                        self.check_expr(guard, &arm_vars);
+                    }
+                    self.check_expr(&arm.body, &arm_vars);
+                }
+            }
+
+            Expr::Let { value, .. } => {
                 self.check_expr(value, local_vars);
                 // El nombre se agrega al scope después
             }
@@ -335,18 +509,370 @@ impl TypeChecker {
                 self.check_expr(right, local_vars);
             }
 
+            Expr::Cast { expr, .. } => {
+                self.check_expr(expr, local_vars);
+            }
+
             // Literales y otros no necesitan verificación
             _ => {}
         }
     }
 }
 
+/// Tipo que la inferencia no puede determinar (parametros, campos,
+/// resultados de capacidades); nunca produce un error
+fn any_type() -> Type {
+    Type::Named("any".to_string())
+}
+
+fn is_any(ty: &Type) -> bool {
+    matches!(ty, Type::Named(name) if name == "any")
+}
+
+fn is_number(ty: &Type) -> bool {
+    matches!(ty, Type::Int | Type::Float)
+}
+
+/// Tipo comun de dos ramas (if, arms de match): el mismo tipo, o Float si
+/// se mezclan Int y Float
+fn join_types(a: &Type, b: &Type) -> Option<Type> {
+    match (a, b) {
+        _ if a == b => Some(a.clone()),
+        (Type::Int | Type::Float, Type::Int | Type::Float) => Some(Type::Float),
+        _ => None,
+    }
+}
+
+/// Tipo de retorno de los builtins que siempre retornan lo mismo
+fn builtin_return_type(name: &str) -> Type {
+    match name {
+        "len" => Type::Int,
+        "str" | "join" | "type" => Type::String,
+        "contains" => Type::Bool,
+        _ => any_type(),
+    }
+}
+
+impl TypeChecker {
+    /// Infiere el tipo de una expresion
+    ///
+    /// Los errores (`"a" + 1`, un `if` con ramas de tipos distintos) se
+    /// agregan a los del checker. Lo que no se puede inferir es `any`, que
+    /// es compatible con todo.
+    pub fn infer(&mut self, expr: &Expr) -> Type {
+        self.infer_in(expr, &mut HashMap::new())
+    }
+
+    /// Tipo de retorno inferido de una funcion del programa verificado
+    pub fn return_type(&self, name: &str) -> Option<&Type> {
+        self.return_types.get(name)
+    }
+
+    /// Infiere (una sola vez) el tipo de retorno de una funcion del programa
+    fn infer_function(&mut self, name: &str) -> Type {
+        if let Some(ty) = self.return_types.get(name) {
+            return ty.clone();
+        }
+        // Una llamada recursiva no conoce todavia su propio tipo
+        if self.inferring.contains(name) {
+            return any_type();
+        }
+        let Some(func) = self.definitions.get(name).cloned() else {
+            return any_type();
+        };
+
+        self.inferring.insert(name.to_string());
+        let outer = self.current_function.replace(func.span.clone());
+        let mut env: HashMap<String, Type> = func.params.iter()
+            .map(|p| {
+                let ty = if p.rest { Type::List(Box::new(any_type())) } else { p.ty.clone().unwrap_or_else(any_type) };
+                (p.name.clone(), ty)
+            })
+            .collect();
+        let ty = self.infer_in(&func.body, &mut env);
+        self.current_function = outer;
+        self.inferring.remove(name);
+
+        self.return_types.insert(name.to_string(), ty.clone());
+        ty
+    }
+
+    fn infer_in(&mut self, expr: &Expr, env: &mut HashMap<String, Type>) -> Type {
+        match expr {
+            Expr::Int(_) => Type::Int,
+            Expr::Float(_) => Type::Float,
+            Expr::Bool(_) => Type::Bool,
+            Expr::String(_) | Expr::RawString(_) => Type::String,
+            Expr::InterpolatedString(parts) => {
+                for part in parts {
+                    if let StringPart::Expr(inner) = part {
+                        self.infer_in(inner, env);
+                    }
+                }
+                Type::String
+            }
+
+            Expr::Ident(name) => env.get(name).cloned().unwrap_or_else(any_type),
+
+            Expr::List(items) => {
+                let types: Vec<Type> = items.iter().map(|item| self.infer_in(item, env)).collect();
+                let spread = items.iter().any(|item| matches!(item, Expr::Spread(_)));
+                let element = match types.split_first() {
+                    Some((first, rest)) if !spread && rest.iter().all(|ty| ty == first) => first.clone(),
+                    _ => any_type(),
+                };
+                Type::List(Box::new(element))
+            }
+
+            Expr::Record(fields) => {
+                for (_, value) in fields {
+                    self.infer_in(value, env);
+                }
+                Type::Named("record".to_string())
+            }
+
+            Expr::Let { name, value } => {
+                let ty = self.infer_in(value, env);
+                env.insert(name.clone(), ty.clone());
+                ty
+            }
+
+            Expr::Block(items) => {
+                let mut scope = env.clone();
+                let mut ty = any_type();
+                for item in items {
+                    ty = self.infer_in(item, &mut scope);
+                }
+                ty
+            }
+
+            Expr::BinaryOp { left, op, right } => {
+                let left = self.infer_in(left, env);
+                let right = self.infer_in(right, env);
+                self.binary_type(op, &left, &right)
+            }
+
+            Expr::UnaryOp { op, expr } => {
+                let ty = self.infer_in(expr, env);
+                let expected_number = *op == UnaryOp::Neg;
+                let valid = is_any(&ty) || if expected_number { is_number(&ty) } else { ty == Type::Bool };
+                if !valid {
+                    let symbol = if expected_number { "-" } else { "!" };
+                    self.type_mismatch(format!("Operacion '{}' no soportada para {}", symbol, ty), None);
+                    return any_type();
+                }
+                if expected_number { ty } else { Type::Bool }
+            }
+
+            Expr::If { condition, then_branch, else_branch } => {
+                self.infer_in(condition, env);
+                let then_ty = self.infer_in(then_branch, env);
+                let Some(else_branch) = else_branch else {
+                    return any_type();
+                };
+                let else_ty = self.infer_in(else_branch, env);
+                if is_any(&then_ty) || is_any(&else_ty) {
+                    return any_type();
+                }
+                match join_types(&then_ty, &else_ty) {
+                    Some(ty) => ty,
+                    None => {
+                        self.type_mismatch(
+                            format!("Las ramas del if tienen tipos distintos: {} y {}", then_ty, else_ty),
+                            Some("Haz que las dos ramas retornen el mismo tipo".to_string()),
+                        );
+                        any_type()
+                    }
+                }
+            }
+
+            Expr::Match { expr, arms } => {
+                self.infer_in(expr, env);
+                let mut types = Vec::new();
+                for arm in arms {
+                    let mut scope = env.clone();
+                    for name in arm.pattern.bindings() {
+                        scope.insert(name.to_string(), any_type());
+                    }
+                    if let Some(guard) = &arm.guard {
+                        self.infer_in(guard, &mut scope);
+                    }
+                    types.push(self.infer_in(&arm.body, &mut scope));
+                }
+                let mut types = types.into_iter();
+                let first = types.next().unwrap_or_else(any_type);
+                types
+                    .try_fold(first, |acc, ty| join_types(&acc, &ty))
+                    .filter(|ty| !is_any(ty))
+                    .unwrap_or_else(any_type)
+            }
+
+            Expr::Call { func, args, .. } => {
+                for arg in args {
+                    self.infer_in(arg, env);
+                }
+                match func.as_ref() {
+                    Expr::Ident(name) if !env.contains_key(name) => {
+                        if self.definitions.contains_key(name) {
+                            self.infer_function(name)
+                        } else {
+                            builtin_return_type(name)
+                        }
+                    }
+                    other => {
+                        self.infer_in(other, env);
+                        any_type()
+                    }
+                }
+            }
+
+            Expr::Lambda { params, body } => {
+                let mut scope = env.clone();
+                for p in params {
+                    scope.insert(p.clone(), any_type());
+                }
+                self.infer_in(body, &mut scope);
+                any_type()
+            }
+
+            Expr::For { var, iter, body } => {
+                self.infer_in(iter, env);
+                let mut scope = env.clone();
+                scope.insert(var.clone(), any_type());
+                self.infer_in(body, &mut scope);
+                any_type()
+            }
+
+            Expr::FieldAccess(obj, _) | Expr::SafeAccess(obj, _) | Expr::Spread(obj) => {
+                self.infer_in(obj, env);
+                any_type()
+            }
+
+            Expr::Pipe(items) => {
+                for item in items {
+                    self.infer_in(item, env);
+                }
+                any_type()
+            }
+
+            Expr::NullCoalesce(left, right) => {
+                self.infer_in(left, env);
+                self.infer_in(right, env);
+                any_type()
+            }
+
+            Expr::Expect { condition, .. } => {
+                self.infer_in(condition, env);
+                any_type()
+            }
+
+            Expr::Cast { expr, ty } => {
+                self.infer_in(expr, env);
+                ty.clone()
+            }
+
+            _ => any_type(),
+        }
+    }
+
+    /// Tipo de una operacion binaria; reporta las combinaciones que la VM
+    /// rechaza cuando los dos lados son conocidos
+    fn binary_type(&mut self, op: &BinaryOp, left: &Type, right: &Type) -> Type {
+        use BinaryOp::*;
+
+        let unknown = is_any(left) || is_any(right);
+        let result = match op {
+            Eq | NotEq | In => return Type::Bool,
+            Lt | Gt | LtEq | GtEq => {
+                if unknown || (is_number(left) && is_number(right)) {
+                    return Type::Bool;
+                }
+                None
+            }
+            And | Or => {
+                if unknown || (*left == Type::Bool && *right == Type::Bool) {
+                    return Type::Bool;
+                }
+                None
+            }
+            _ if unknown => return any_type(),
+            Add => match (left, right) {
+                (Type::Int, Type::Int) => Some(Type::Int),
+                (Type::Int | Type::Float, Type::Int | Type::Float) => Some(Type::Float),
+                (Type::String, Type::String) => Some(Type::String),
+                _ => None,
+            },
+            Sub | Mul | Div => match (left, right) {
+                (Type::Int, Type::Int) => Some(Type::Int),
+                (Type::Float, Type::Float) => Some(Type::Float),
+                _ => None,
+            },
+            Mod | BitAnd | BitOr | BitXor | Shl | Shr => {
+                (*left == Type::Int && *right == Type::Int).then_some(Type::Int)
+            }
+            Concat => match (left, right) {
+                (Type::List(_), Type::List(_)) => Some(Type::List(Box::new(any_type()))),
+                (Type::String, Type::String | Type::Int | Type::Float | Type::Bool)
+                | (Type::Int | Type::Float | Type::Bool, Type::String) => Some(Type::String),
+                _ => None,
+            },
+        };
+
+        result.unwrap_or_else(|| {
+            let symbol = crate::fmt::binary_symbol(op);
+            let suggestion = match (op, left, right) {
+                (Add, Type::String, _) | (Add, _, Type::String) => {
+                    Some("Usa ++ para concatenar: convierte el otro lado a texto".to_string())
+                }
+                (Sub | Mul | Div, _, _) if is_number(left) && is_number(right) => {
+                    Some("Convierte los dos lados al mismo tipo numerico".to_string())
+                }
+                _ => None,
+            };
+            self.type_mismatch(
+                format!("Operacion '{}' no soportada entre {} y {}", symbol, left, right),
+                suggestion,
+            );
+            any_type()
+        })
+    }
+
+    fn type_mismatch(&mut self, message: String, suggestion: Option<String>) {
+        let mut error = TypeError::new(message);
+        if let Some(span) = &self.current_function {
+            error = error.with_span(span.clone());
+        }
+        if let Some(suggestion) = suggestion {
+            error = error.with_suggestion(suggestion);
+        }
+        self.errors.push(error);
+    }
+}
+
+impl TypeChecker {
+    /// Reporta un modulo (`http.get`, `json.parse`, ...) usado sin declarar su
+    /// capacidad; el fix inserta la declaracion al inicio del archivo
+    fn missing_capability(&mut self, name: &str) {
+        if !self.missing_capabilities.insert(name.to_string()) {
+            return;
+        }
+        self.errors.push(
+            TypeError::new(format!("La capacidad '+{}' es requerida pero no esta declarada", name))
+                .with_suggestion(format!("Agrega '+{}' al inicio del archivo", name))
+                .with_replacement(Span::new(0, 0), format!("+{}\n", name))
+        );
+    }
+}
+
 impl Default for TypeChecker {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Modulos de la VM que requieren declarar la capacidad del mismo nombre
+const CAPABILITY_NAMESPACES: &[&str] = &["http", "json", "db", "env", "auth", "email", "crypto"];
+
 /// Verifica si es un tipo builtin
 fn is_builtin_type(name: &str) -> bool {
     matches!(name, "int" | "float" | "string" | "bool" | "list" | "record" | "any")
@@ -358,6 +884,43 @@ pub fn check(program: &Program) -> Result<(), Vec<TypeError>> {
     checker.check(program)
 }
 
+/// Como `check`, y ademas retorna las advertencias (que no hacen fallar la
+/// verificacion)
+pub fn check_with_warnings(program: &Program) -> (Result<(), Vec<TypeError>>, Vec<TypeWarning>) {
+    let mut checker = TypeChecker::new();
+    let result = checker.check(program);
+    (result, checker.warnings)
+}
+
+/// Aplica al fuente los reemplazos de los errores y retorna el fuente nuevo
+/// con la cantidad aplicada. Se aplican de derecha a izquierda para que los
+/// spans pendientes sigan siendo validos; un reemplazo que se superpone con
+/// otro ya aplicado, o que repite uno igual, se descarta.
+pub fn apply_replacements(source: &str, errors: &[TypeError]) -> (String, usize) {
+    let mut replacements: Vec<&Replacement> = errors
+        .iter()
+        .filter_map(|e| e.replacement.as_ref())
+        .filter(|r| r.span.start <= r.span.end && r.span.end <= source.len())
+        .collect();
+    replacements.sort_by(|a, b| b.span.start.cmp(&a.span.start).then(b.span.end.cmp(&a.span.end)));
+    replacements.dedup();
+
+    let mut fixed = source.to_string();
+    let mut applied = 0;
+    // Inicio del ultimo reemplazo aplicado: todo lo que sigue ya cambio
+    let mut limit = usize::MAX;
+    for replacement in replacements {
+        let span = &replacement.span;
+        if span.end > limit || !source.is_char_boundary(span.start) || !source.is_char_boundary(span.end) {
+            continue;
+        }
+        fixed.replace_range(span.start..span.end, &replacement.text);
+        limit = span.start;
+        applied += 1;
+    }
+    (fixed, applied)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -384,6 +947,15 @@ mod tests {
         assert!(errors.iter().any(|e| e.message.contains("main")));
     }
 
+    #[test]
+    fn test_route_with_undefined_handler() {
+        let result = check_code("route \"GET\" \"/users\" list_users\nmain = 1\n");
+        let errors = result.unwrap_err();
+        assert!(errors.iter().any(|e| e.message.contains("list_users")));
+
+        assert!(check_code("route \"GET\" \"/users\" list_users\nlist_users = []\nmain = 1\n").is_ok());
+    }
+
     #[test]
     fn test_undefined_function() {
         let result = check_code("+http\nmain = undefined_func(42)\n");
@@ -398,6 +970,167 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_duplicate_function() {
+        let source = "main = 1\nhelper = 2\nmain = 3\n";
+        let errors = check_code(source).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Funcion 'main' definida mas de una vez");
+
+        // Las dos ubicaciones: la repetida y la primera
+        let span = errors[0].span.clone().unwrap();
+        let previous = errors[0].previous_span.clone().unwrap();
+        assert_eq!(&source[span.start..span.end], "main = 3");
+        assert_eq!(&source[previous.start..previous.end], "main = 1");
+    }
+
+    #[test]
+    fn test_duplicate_type() {
+        let source = "@User {name:s}\n@User {email:s}\n@User {id:i}\nmain = 1\n";
+        let errors = check_code(source).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|e| e.message == "Tipo 'User' definido mas de una vez"));
+        // Cada repeticion apunta a la primera definicion
+        assert_eq!(errors[0].previous_span, errors[1].previous_span);
+        assert_eq!(errors[0].previous_span.as_ref().unwrap().start, 0);
+    }
+
+    #[test]
+    fn test_function_and_type_may_share_name() {
+        assert!(check_code("@User {name:s}\nUser = 1\nmain = 1\n").is_ok());
+    }
+
+    #[test]
+    fn test_missing_capability_has_fix() {
+        let source = "main = [http.get(\"x\"), http.get(\"y\"), json.stringify(1)]\n";
+        let errors = check_code(source).unwrap_err();
+        // Un error por capacidad, aunque el modulo se use varias veces
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].message, "La capacidad '+http' es requerida pero no esta declarada");
+        let replacement = errors[0].replacement.as_ref().unwrap();
+        assert_eq!(replacement.span, Span::new(0, 0));
+        assert_eq!(replacement.text, "+http\n");
+
+        let (fixed, applied) = apply_replacements(source, &errors);
+        assert_eq!(applied, 2);
+        assert!(fixed.starts_with("+http\n+json\n") || fixed.starts_with("+json\n+http\n"));
+        assert!(check_code(&fixed).is_ok());
+    }
+
+    #[test]
+    fn test_declared_capability_namespace_is_defined() {
+        assert!(check_code("+http\nmain = http.get(\"http://localhost\")\n").is_ok());
+        assert!(check_code("main = math.sqrt(4)\n").is_ok());
+    }
+
+    #[test]
+    fn test_apply_replacements_right_to_left_skips_overlaps() {
+        let source = "abcdef";
+        let errors = vec![
+            // Se superpone con 2..4, que se aplica antes por estar mas a la derecha
+            TypeError::new("a").with_replacement(Span::new(1, 3), "X"),
+            TypeError::new("b").with_replacement(Span::new(4, 5), "Y"),
+            TypeError::new("c").with_replacement(Span::new(2, 4), "Z"),
+            TypeError::new("d"),
+        ];
+        let (fixed, applied) = apply_replacements(source, &errors);
+        assert_eq!(applied, 2);
+        assert_eq!(fixed, "abZYf");
+    }
+
+    fn infer_expr(source: &str) -> (Type, Vec<TypeError>) {
+        let program = parse(tokenize(&format!("main = {}", source)).unwrap()).unwrap();
+        let Some(Definition::FuncDef(main)) = program.definitions.first() else { panic!("sin main") };
+        let mut checker = TypeChecker::new();
+        let ty = checker.infer(&main.body);
+        (ty, checker.errors)
+    }
+
+    #[test]
+    fn test_infer_well_typed_expressions() {
+        let cases = [
+            ("1 + 2", Type::Int),
+            ("1 + 2.5", Type::Float),
+            ("7 % 2", Type::Int),
+            ("\"a\" + \"b\"", Type::String),
+            ("\"n: \" ++ 1", Type::String),
+            ("1 < 2.5", Type::Bool),
+            ("-(1.5)", Type::Float),
+            ("[1, 2, 3]", Type::List(Box::new(Type::Int))),
+            ("if 1 < 2 \"si\" else \"no\"", Type::String),
+            ("if 1 < 2 1 else 2.5", Type::Float),
+            ("if 1 < 2 1.5 else 2", Type::Float),
+            ("3 ? 1 -> 1 | _ -> 0.5", Type::Float),
+            ("3 ? 1 -> 1 | 2 -> 2 | _ -> 0", Type::Int),
+            ("len([1]) * 2", Type::Int),
+            (": a = 2; b = a * 3; b", Type::Int),
+        ];
+        for (source, expected) in cases {
+            let (ty, errors) = infer_expr(source);
+            assert_eq!(ty, expected, "{}", source);
+            assert!(errors.is_empty(), "{}: {:?}", source, errors);
+        }
+
+        // Sin informacion el tipo es `any` y no hay errores
+        let (ty, errors) = infer_expr("x.count + 1");
+        assert!(is_any(&ty));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_infer_mistyped_expressions() {
+        let cases = [
+            ("\"a\" + 1", "Operacion '+' no soportada entre :s y :i"),
+            ("1 - 2.5", "Operacion '-' no soportada entre :i y :f"),
+            ("[1] ++ 2", "Operacion '++' no soportada entre [:i] y :i"),
+            ("\"a\" < 1", "Operacion '<' no soportada entre :s y :i"),
+            ("-\"a\"", "Operacion '-' no soportada para :s"),
+            ("if 1 < 2 1 else \"no\"", "Las ramas del if tienen tipos distintos: :i y :s"),
+        ];
+        for (source, message) in cases {
+            let (ty, errors) = infer_expr(source);
+            assert!(is_any(&ty), "{}", source);
+            let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+            assert_eq!(messages, vec![message], "{}", source);
+        }
+
+        let (_, errors) = infer_expr("\"total: \" + 3");
+        assert!(errors[0].suggestion.as_ref().unwrap().contains("++"));
+    }
+
+    #[test]
+    fn test_infer_function_return_types() {
+        let source = "area(w, h) = w * h\nlabel = \"area\"\nsquare = 2 * 3\nmain = label() + square()\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        let mut checker = TypeChecker::new();
+        let errors = checker.check(&program).unwrap_err();
+
+        assert!(is_any(checker.return_type("area").unwrap()));
+        assert_eq!(checker.return_type("square"), Some(&Type::Int));
+        // El error usa los tipos de retorno y apunta a la funcion donde esta
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Operacion '+' no soportada entre :s y :i");
+        let span = errors[0].span.clone().unwrap();
+        assert!(source[span.start..span.end].starts_with("main ="));
+    }
+
+    #[test]
+    fn test_if_with_int_and_float_branches() {
+        let source = "f(x) = if x > 0 1 else 2.5\nmain = f(1) + 0.5\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        let mut checker = TypeChecker::new();
+        assert!(checker.check(&program).is_ok());
+        assert_eq!(checker.return_type("f"), Some(&Type::Float));
+
+        // Mezclar numeros con otro tipo sigue siendo un error
+        assert!(check_code("f(x) = if x > 0 1 else \"no\"\nmain = f(1)\n").is_err());
+    }
+
+    #[test]
+    fn test_infer_recursive_function() {
+        assert!(check_code("count(n) = if n == 0 0 else 1 + count(n - 1)\nmain = count(3)\n").is_ok());
+    }
+
     #[test]
     fn test_builtin_function() {
         let result = check_code("+http\nmain = len(\"hello\")\n");
@@ -119,6 +119,8 @@ pub struct AgentCognitiveRuntime<P: AgentProvider> {
     source_code: String,
     observation_buffer: Vec<ObservationEvent>,
     pub reasoning_trace: Vec<ReasoningEpisode>,
+    /// Traza de deliberacion; `None` si no se pidio con `with_trace`
+    trace: Option<Vec<TraceEntry>>,
     available_checkpoints: Vec<String>,
     max_deliberations: usize,
     deliberation_count: usize,
@@ -144,6 +146,7 @@ impl<P: AgentProvider> AgentCognitiveRuntime<P> {
             source_code,
             observation_buffer: Vec::new(),
             reasoning_trace: Vec::new(),
+            trace: None,
             available_checkpoints: Vec::new(),
             max_deliberations: 10,
             deliberation_count: 0,
@@ -165,6 +168,27 @@ impl<P: AgentProvider> AgentCognitiveRuntime<P> {
         self
     }
 
+    /// Enables collection of a deliberation trace (see `CognitiveRuntime::take_trace`)
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Vec::new());
+        self
+    }
+
+    /// Stores an episode, mirroring it into the trace when enabled
+    fn push_episode(&mut self, trigger: &DeliberationTrigger, episode: ReasoningEpisode) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(TraceEntry {
+                step: self.reasoning_trace.len() + 1,
+                trigger_type: episode.trigger_type.clone(),
+                trigger: trigger.to_string(),
+                observations: episode.observations.clone(),
+                decision: episode.decision.clone(),
+                decision_detail: episode.decision_detail.clone(),
+            });
+        }
+        self.reasoning_trace.push(episode);
+    }
+
     /// Builds a deliberation request for the agent
     fn build_deliberation_request(&self, trigger: &DeliberationTrigger) -> AgentRequest {
         let mut message_parts = vec![format!("DELIBERATION TRIGGER: {}", trigger)];
@@ -255,30 +279,15 @@ impl<P: AgentProvider> AgentCognitiveRuntime<P> {
 
     /// Records a reasoning episode
     fn record_episode(&mut self, trigger: &DeliberationTrigger, decision: &CognitiveDecision) {
-        let trigger_type = match trigger {
-            DeliberationTrigger::ExpectFailed { .. } => "expect_failed",
-            DeliberationTrigger::ExplicitReason { .. } => "reason",
-            DeliberationTrigger::TechnicalError { .. } => "technical_error",
-            DeliberationTrigger::GoalMisalignment { .. } => "goal_misalignment",
-        };
-
-        let (decision_str, detail) = match decision {
-            CognitiveDecision::Continue => ("continue", String::new()),
-            CognitiveDecision::Override(val) => ("override", format!("{}", val)),
-            CognitiveDecision::Fix { explanation, .. } => ("fix", explanation.clone()),
-            CognitiveDecision::Backtrack { checkpoint, .. } => ("backtrack", checkpoint.clone()),
-            CognitiveDecision::Halt(err) => ("halt", err.message.clone()),
-        };
-
         let observations: Vec<String> = self.observation_buffer.iter()
             .map(|o| format!("{:?}", o))
             .collect();
 
-        self.reasoning_trace.push(ReasoningEpisode {
-            trigger_type: trigger_type.to_string(),
+        self.push_episode(trigger, ReasoningEpisode {
+            trigger_type: trigger.kind().to_string(),
             observations,
-            decision: decision_str.to_string(),
-            decision_detail: detail,
+            decision: decision.kind().to_string(),
+            decision_detail: decision.detail(),
             outcome: None,
             timestamp: chrono::Utc::now(),
             context: EpisodeContext {
@@ -319,7 +328,8 @@ impl<P: AgentProvider> CognitiveRuntime for AgentCognitiveRuntime<P> {
                 decision = CognitiveDecision::Continue;
                 self.deliberations_without_progress += 1;
                 // Log rejection (visible in trace)
-                self.reasoning_trace.push(ReasoningEpisode {
+                let goals = self.goals.iter().map(|g| g.description.clone()).collect();
+                self.push_episode(&trigger, ReasoningEpisode {
                     trigger_type: "safety_rejected".to_string(),
                     observations: vec![reason],
                     decision: "continue".to_string(),
@@ -329,7 +339,7 @@ impl<P: AgentProvider> CognitiveRuntime for AgentCognitiveRuntime<P> {
                     context: EpisodeContext {
                         file: String::new(),
                         function: None,
-                        goals: self.goals.iter().map(|g| g.description.clone()).collect(),
+                        goals,
                     },
                 });
             } else {
@@ -341,10 +351,9 @@ impl<P: AgentProvider> CognitiveRuntime for AgentCognitiveRuntime<P> {
         // Safety: check backtrack depth
         if let CognitiveDecision::Backtrack { ref checkpoint, .. } = decision {
             self.consecutive_backtracks += 1;
-            if self.consecutive_backtracks > self.safety_config.max_backtrack_depth {
-                decision = CognitiveDecision::Continue;
-                self.deliberations_without_progress += 1;
-            } else if !self.available_checkpoints.contains(checkpoint) {
+            if self.consecutive_backtracks > self.safety_config.max_backtrack_depth
+                || !self.available_checkpoints.contains(checkpoint)
+            {
                 decision = CognitiveDecision::Continue;
                 self.deliberations_without_progress += 1;
             } else {
@@ -383,9 +392,14 @@ impl<P: AgentProvider> CognitiveRuntime for AgentCognitiveRuntime<P> {
     fn set_available_checkpoints(&mut self, checkpoints: Vec<String>) {
         self.available_checkpoints = checkpoints;
     }
+
+    fn take_trace(&mut self) -> Vec<TraceEntry> {
+        self.trace.as_mut().map(std::mem::take).unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
+#[allow(unused_imports)]
 mod tests {
     use super::*;
     use crate::agent::MockProvider;
@@ -445,6 +459,60 @@ mod tests {
         assert_eq!(runtime.reasoning_trace[0].trigger_type, "technical_error");
     }
 
+    #[test]
+    fn test_agent_cognitive_trace_disabled_by_default() {
+        let provider = MockProvider::new().with_latency(0);
+        let handle = make_handle();
+        let mut runtime = AgentCognitiveRuntime::new(
+            provider, handle, Vec::new(), Vec::new(), String::new(),
+        );
+
+        runtime.deliberate(DeliberationTrigger::TechnicalError {
+            error: RuntimeError::new("test"),
+        });
+
+        assert_eq!(runtime.reasoning_trace.len(), 1);
+        assert!(runtime.take_trace().is_empty());
+    }
+
+    #[test]
+    fn test_agent_cognitive_trace_records_episodes() {
+        let provider = MockProvider::new().with_latency(0);
+        let handle = make_handle();
+        let mut runtime = AgentCognitiveRuntime::new(
+            provider, handle, Vec::new(), Vec::new(), String::new(),
+        ).with_trace();
+
+        runtime.observe(ObservationEvent::Observed {
+            target: "x".to_string(),
+            value: Value::Int(1),
+        });
+        runtime.deliberate(DeliberationTrigger::TechnicalError {
+            error: RuntimeError::new("boom"),
+        });
+        runtime.deliberate(DeliberationTrigger::ExplicitReason {
+            observations: Vec::new(),
+            question: "que hago?".to_string(),
+        });
+
+        let trace = runtime.take_trace();
+        assert_eq!(trace.len(), runtime.reasoning_trace.len());
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace[0].step, 1);
+        assert_eq!(trace[0].trigger_type, "technical_error");
+        assert_eq!(trace[0].trigger, "technical error: boom");
+        assert_eq!(trace[0].observations.len(), 1);
+        assert_eq!(trace[1].step, 2);
+        assert_eq!(trace[1].trigger_type, "reason");
+        assert!(trace[1].observations.is_empty());
+        for (entry, episode) in trace.iter().zip(&runtime.reasoning_trace) {
+            assert_eq!(entry.decision, episode.decision);
+        }
+
+        // take_trace vacia la traza
+        assert!(runtime.take_trace().is_empty());
+    }
+
     #[test]
     fn test_agent_cognitive_observation_buffer() {
         let provider = MockProvider::new().with_latency(0);
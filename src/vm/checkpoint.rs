@@ -6,6 +6,7 @@
 use std::collections::HashMap;
 use std::time::Instant;
 use super::Value;
+use crate::parser::{FuncDef, TypeDef};
 
 /// Snapshot of the VM state at a point in execution
 #[derive(Debug, Clone)]
@@ -14,6 +15,10 @@ pub struct VMCheckpoint {
     pub name: String,
     /// Copy of all variables at checkpoint time
     pub variables: HashMap<String, Value>,
+    /// Functions defined at checkpoint time (empty for variable-only checkpoints)
+    pub functions: HashMap<String, FuncDef>,
+    /// Types defined at checkpoint time (empty for variable-only checkpoints)
+    pub types: HashMap<String, TypeDef>,
     /// Step count at checkpoint time
     pub step_count: u64,
     /// When the checkpoint was created
@@ -50,23 +55,31 @@ impl CheckpointManager {
 
     /// Saves a checkpoint with the given name and variables
     pub fn save(&mut self, name: String, variables: HashMap<String, Value>, step_count: u64) {
+        self.insert(VMCheckpoint {
+            name,
+            variables,
+            functions: HashMap::new(),
+            types: HashMap::new(),
+            step_count,
+            timestamp: Instant::now(),
+        });
+    }
+
+    /// Stores a checkpoint under its own name, replacing any previous one
+    pub fn insert(&mut self, checkpoint: VMCheckpoint) {
+        let name = checkpoint.name.clone();
+
         // If we're at max capacity and this is a new checkpoint, remove the oldest
-        if !self.checkpoints.contains_key(&name) && self.checkpoints.len() >= self.max_checkpoints {
-            if let Some(oldest) = self.order.first().cloned() {
-                self.checkpoints.remove(&oldest);
-                self.order.retain(|n| n != &oldest);
-            }
+        if !self.checkpoints.contains_key(&name) && self.checkpoints.len() >= self.max_checkpoints
+            && let Some(oldest) = self.order.first().cloned() {
+            self.checkpoints.remove(&oldest);
+            self.order.retain(|n| n != &oldest);
         }
 
         // Remove from order if already exists (we'll re-add at end)
         self.order.retain(|n| n != &name);
 
-        self.checkpoints.insert(name.clone(), VMCheckpoint {
-            name: name.clone(),
-            variables,
-            step_count,
-            timestamp: Instant::now(),
-        });
+        self.checkpoints.insert(name.clone(), checkpoint);
         self.order.push(name);
     }
 
@@ -4,6 +4,7 @@
 //! durante la ejecucion para observar, deliberar y ajustar.
 
 use super::{Value, RuntimeError, ExpectationFailure};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// Decision del cognitive runtime despues de deliberar
@@ -27,6 +28,30 @@ pub enum CognitiveDecision {
     Halt(RuntimeError),
 }
 
+impl CognitiveDecision {
+    /// Nombre corto de la decision, usado en episodios y trazas
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CognitiveDecision::Continue => "continue",
+            CognitiveDecision::Override(_) => "override",
+            CognitiveDecision::Fix { .. } => "fix",
+            CognitiveDecision::Backtrack { .. } => "backtrack",
+            CognitiveDecision::Halt(_) => "halt",
+        }
+    }
+
+    /// Detalle legible: el valor inyectado, la explicacion del fix, etc.
+    pub fn detail(&self) -> String {
+        match self {
+            CognitiveDecision::Continue => String::new(),
+            CognitiveDecision::Override(val) => format!("{}", val),
+            CognitiveDecision::Fix { explanation, .. } => explanation.clone(),
+            CognitiveDecision::Backtrack { checkpoint, .. } => checkpoint.clone(),
+            CognitiveDecision::Halt(err) => err.message.clone(),
+        }
+    }
+}
+
 /// Evento de observacion durante la ejecucion
 #[derive(Debug, Clone)]
 pub enum ObservationEvent {
@@ -51,6 +76,24 @@ pub enum ObservationEvent {
     CheckpointCreated {
         name: String,
     },
+    /// Un `observe` se evaluo (y su `where`, si tenia, fue verdadero)
+    Observed {
+        target: String,
+        value: Value,
+    },
+}
+
+impl ObservationEvent {
+    /// Nombre corto del evento
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ObservationEvent::ValueChanged { .. } => "value_changed",
+            ObservationEvent::ExpectEvaluated { .. } => "expect_evaluated",
+            ObservationEvent::FunctionReturned { .. } => "function_returned",
+            ObservationEvent::CheckpointCreated { .. } => "checkpoint_created",
+            ObservationEvent::Observed { .. } => "observed",
+        }
+    }
 }
 
 /// Trigger que provoca deliberacion
@@ -76,6 +119,18 @@ pub enum DeliberationTrigger {
     },
 }
 
+impl DeliberationTrigger {
+    /// Nombre corto del trigger, usado en episodios y trazas
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DeliberationTrigger::ExpectFailed { .. } => "expect_failed",
+            DeliberationTrigger::ExplicitReason { .. } => "reason",
+            DeliberationTrigger::TechnicalError { .. } => "technical_error",
+            DeliberationTrigger::GoalMisalignment { .. } => "goal_misalignment",
+        }
+    }
+}
+
 impl fmt::Display for DeliberationTrigger {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -95,6 +150,26 @@ impl fmt::Display for DeliberationTrigger {
     }
 }
 
+/// Un paso de deliberacion registrado en la traza (`aura run --cognitive --trace`)
+///
+/// Junta el trigger, las observaciones que lo acompanaron y la decision final,
+/// tal como quedo en el episodio de razonamiento.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceEntry {
+    /// Numero de paso, empezando en 1
+    pub step: usize,
+    /// Tipo de trigger: `expect_failed`, `reason`, `technical_error`,
+    /// `goal_misalignment` o `safety_rejected`
+    pub trigger_type: String,
+    /// Descripcion legible del trigger
+    pub trigger: String,
+    pub observations: Vec<String>,
+    /// Decision tomada: `continue`, `override`, `fix`, `backtrack` o `halt`
+    pub decision: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub decision_detail: String,
+}
+
 /// Trait para el runtime cognitivo
 ///
 /// Cuando `is_active()` retorna false, todas las operaciones son no-op
@@ -114,6 +189,11 @@ pub trait CognitiveRuntime: Send {
 
     /// Notifica los checkpoints disponibles para backtrack
     fn set_available_checkpoints(&mut self, _checkpoints: Vec<String>) {}
+
+    /// Retorna (y vacia) la traza de deliberacion, si el runtime la registra
+    fn take_trace(&mut self) -> Vec<TraceEntry> {
+        Vec::new()
+    }
 }
 
 /// Implementacion nula del CognitiveRuntime
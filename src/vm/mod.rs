@@ -5,70 +5,42 @@ pub mod cognitive;
 pub mod checkpoint;
 pub mod runner;
 pub mod agent_cognitive;
+pub mod recording;
 
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
-use crate::parser::{Program, Definition, Expr, BinaryOp, UnaryOp, FuncDef, TypeDef, SelfHealConfig, GoalDef};
-use crate::caps::http::{http_get, http_post, http_put, http_delete};
-use crate::caps::db::{db_connect, db_query, db_execute, db_close};
-use crate::caps::env::{env_get, env_get_or, env_set, env_remove, env_exists};
-pub use cognitive::{CognitiveRuntime, CognitiveDecision, ObservationEvent, DeliberationTrigger, NullCognitiveRuntime};
+use crate::parser::{Program, Definition, Expr, BinaryOp, UnaryOp, FuncDef, TypeDef, Type, SelfHealConfig, GoalDef, Pattern, TestDef};
+use crate::caps::http::{http_get, http_post, http_put, http_delete, HttpOptions};
+use crate::caps::auth::{hash_password, verify_password, jwt_sign, jwt_verify};
+use crate::caps::crypto::{random_bytes, sha256_hex};
+use crate::caps::email::email_send;
+use crate::caps::json::{json_parse, json_stringify};
+use crate::caps::db::{db_connect, db_query, db_execute, db_query_named, db_execute_named, db_close};
+use crate::caps::env::{env_get, env_get_or, env_set, env_remove, env_exists, EnvAllowlist};
+pub use cognitive::{CognitiveRuntime, CognitiveDecision, ObservationEvent, DeliberationTrigger, NullCognitiveRuntime, TraceEntry};
 pub use checkpoint::{VMCheckpoint, CheckpointManager};
 
-/// Convierte serde_json::Value a Value de AURA
-fn json_to_value(json: serde_json::Value) -> Value {
-    match json {
-        serde_json::Value::Null => Value::Nil,
-        serde_json::Value::Bool(b) => Value::Bool(b),
-        serde_json::Value::Number(n) => {
-            if let Some(i) = n.as_i64() {
-                Value::Int(i)
-            } else if let Some(f) = n.as_f64() {
-                Value::Float(f)
-            } else {
-                Value::Nil
-            }
-        }
-        serde_json::Value::String(s) => Value::String(s),
-        serde_json::Value::Array(arr) => {
-            Value::List(arr.into_iter().map(json_to_value).collect())
-        }
-        serde_json::Value::Object(obj) => {
-            let mut map = HashMap::new();
-            for (k, v) in obj {
-                map.insert(k, json_to_value(v));
-            }
-            Value::Record(map)
-        }
-    }
+/// Signo opcional, digitos, un punto y digitos: `3.14`, `-0.5`
+fn is_plain_decimal(text: &str) -> bool {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    let Some((int, frac)) = digits.split_once('.') else { return false };
+    let all_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    all_digits(int) && all_digits(frac)
 }
 
-/// Convierte Value de AURA a serde_json::Value
-fn value_to_json(value: &Value) -> serde_json::Value {
-    match value {
-        Value::Nil => serde_json::Value::Null,
-        Value::Bool(b) => serde_json::Value::Bool(*b),
-        Value::Int(n) => serde_json::Value::Number((*n).into()),
-        Value::Float(f) => {
-            serde_json::Number::from_f64(*f)
-                .map(serde_json::Value::Number)
-                .unwrap_or(serde_json::Value::Null)
-        }
-        Value::String(s) => serde_json::Value::String(s.clone()),
-        Value::List(l) => {
-            serde_json::Value::Array(l.iter().map(value_to_json).collect())
-        }
-        Value::Record(r) => {
-            let obj: serde_json::Map<String, serde_json::Value> = r.iter()
-                .map(|(k, v)| (k.clone(), value_to_json(v)))
-                .collect();
-            serde_json::Value::Object(obj)
-        }
-        Value::Function(name) => serde_json::Value::String(format!("<fn {}>", name)),
-        Value::Native { type_id, handle } => {
-            serde_json::Value::String(format!("<{} #{}>", type_id, handle))
-        }
-    }
+/// Bytes en JSON: base64 estandar, con padding
+pub fn bytes_to_base64(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// Representacion JSON de un handle nativo: `{"native": "db:sqlite", "handle": 3}`
+pub fn native_to_json(type_id: &str, handle: u64) -> serde_json::Value {
+    serde_json::json!({ "native": type_id, "handle": handle })
 }
 
 /// Valor en runtime
@@ -79,6 +51,8 @@ pub enum Value {
     Float(f64),
     String(String),
     Bool(bool),
+    /// Datos binarios (blobs de la base de datos, `crypto.random_bytes`)
+    Bytes(Vec<u8>),
     List(Vec<Value>),
     Record(HashMap<String, Value>),
     Function(String),
@@ -88,6 +62,122 @@ pub enum Value {
     Native { type_id: String, handle: u64 },
 }
 
+impl Value {
+    /// Convierte JSON a un valor de AURA
+    ///
+    /// Los numeros enteros que entran en un `i64` quedan como `Int` y el resto
+    /// (decimales o enteros fuera de rango) como `Float`; los objetos pasan a
+    /// records.
+    pub fn from_json(json: &serde_json::Value) -> Value {
+        match json {
+            serde_json::Value::Null => Value::Nil,
+            serde_json::Value::Bool(b) => Value::Bool(*b),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => Value::Int(i),
+                // El texto de un numero JSON siempre parsea como f64 (a lo sumo infinito)
+                None => Value::Float(n.as_f64().unwrap_or_else(|| n.to_string().parse().unwrap_or(f64::NAN))),
+            },
+            serde_json::Value::String(s) => Value::String(s.clone()),
+            serde_json::Value::Array(items) => Value::List(items.iter().map(Value::from_json).collect()),
+            serde_json::Value::Object(obj) => Value::Record(
+                obj.iter().map(|(k, v)| (k.clone(), Value::from_json(v))).collect()
+            ),
+        }
+    }
+
+    /// Convierte el valor a JSON
+    ///
+    /// Los valores sin equivalente en JSON no fallan: los bytes van en base64,
+    /// las funciones como `"<fn nombre>"`, los handles nativos como
+    /// `{"native": tipo, "handle": n}` y un float NaN o infinito como `null`.
+    /// Esos casos no vuelven al mismo valor con `from_json`.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Nil => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(*b),
+            Value::Int(n) => serde_json::Value::Number((*n).into()),
+            Value::Float(f) => serde_json::Number::from_f64(*f)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Value::String(s) => serde_json::Value::String(s.clone()),
+            Value::Bytes(b) => serde_json::Value::String(bytes_to_base64(b)),
+            Value::List(items) => serde_json::Value::Array(items.iter().map(Value::to_json).collect()),
+            Value::Record(fields) => serde_json::Value::Object(
+                fields.iter().map(|(k, v)| (k.clone(), v.to_json())).collect()
+            ),
+            Value::Function(name) => serde_json::Value::String(format!("<fn {}>", name)),
+            Value::Native { type_id, handle } => native_to_json(type_id, *handle),
+        }
+    }
+
+    /// Valor de un texto suelto, como `--set count=3`: entero, float o bool
+    /// si lo parece, y si no el texto tal cual
+    ///
+    /// Solo un decimal simple (`-2.5`) es float; `nan`, `inf` o `1e5` quedan
+    /// como texto aunque `f64` los acepte.
+    pub fn parse_scalar(text: &str) -> Value {
+        match text {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => match text.parse::<i64>() {
+                Ok(n) => Value::Int(n),
+                Err(_) if is_plain_decimal(text) => text.parse::<f64>()
+                    .map(Value::Float)
+                    .unwrap_or_else(|_| Value::String(text.to_string())),
+                Err(_) => Value::String(text.to_string()),
+            },
+        }
+    }
+
+    /// Nombre del tipo, el mismo que retorna `type(x)`
+    pub fn type_name(&self) -> &str {
+        match self {
+            Value::Nil => "nil",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::Bool(_) => "bool",
+            Value::Bytes(_) => "bytes",
+            Value::List(_) => "list",
+            Value::Record(_) => "record",
+            Value::Function(_) => "function",
+            Value::Native { type_id, .. } => type_id,
+        }
+    }
+
+    /// Igualdad del operador `==` de AURA
+    ///
+    /// - `Int` y `Float` se comparan por valor (`1 == 1.0`).
+    /// - `NaN` es distinto de todo, incluso de si mismo, como en IEEE 754:
+    ///   una lista con `NaN` tampoco es igual a si misma.
+    /// - Listas elemento a elemento; records por campos, sin importar el orden.
+    /// - Valores de tipos distintos nunca son iguales.
+    pub fn deep_equal(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Int(a), Value::Float(b)) | (Value::Float(b), Value::Int(a)) => (*a as f64) == *b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::List(a), Value::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.deep_equal(y))
+            }
+            (Value::Record(a), Value::Record(b)) => {
+                a.len() == b.len()
+                    && a.iter().all(|(key, x)| b.get(key).is_some_and(|y| x.deep_equal(y)))
+            }
+            (Value::Function(a), Value::Function(b)) => a == b,
+            (
+                Value::Native { type_id: ta, handle: ha },
+                Value::Native { type_id: tb, handle: hb },
+            ) => ta == tb && ha == hb,
+            _ => false,
+        }
+    }
+}
+
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -96,6 +186,13 @@ impl std::fmt::Display for Value {
             Value::Float(n) => write!(f, "{}", n),
             Value::String(s) => write!(f, "{}", s),
             Value::Bool(b) => write!(f, "{}", b),
+            Value::Bytes(bytes) => {
+                write!(f, "<bytes ")?;
+                for byte in bytes {
+                    write!(f, "{:02x}", byte)?;
+                }
+                write!(f, ">")
+            }
             Value::List(items) => {
                 write!(f, "[")?;
                 for (i, item) in items.iter().enumerate() {
@@ -117,7 +214,7 @@ impl std::fmt::Display for Value {
                 write!(f, "}}")
             }
             Value::Function(name) => write!(f, "<fn {}>", name),
-            Value::Native { type_id, handle } => write!(f, "<{} #{}>", type_id, handle),
+            Value::Native { type_id, handle } => write!(f, "<{}#{}>", type_id, handle),
         }
     }
 }
@@ -165,17 +262,74 @@ impl std::fmt::Display for ExpectationFailure {
     }
 }
 
-/// Entorno de ejecución
+/// Resultado del `check` de un goal, evaluado al terminar `main`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalOutcome {
+    pub description: String,
+    /// El check evaluo a `true`
+    pub passed: bool,
+    /// Valor del check (ausente si la evaluacion fallo)
+    pub value: Option<Value>,
+    /// Error al evaluar el check
+    pub error: Option<String>,
+}
+
+/// Resultado de un `#test nombre: expr`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestOutcome {
+    pub name: String,
+    pub passed: bool,
+    /// Valor obtenido; en un `a == b`, el de `a`
+    pub value: Option<Value>,
+    /// En un `a == b`, el valor de `b`
+    pub expected: Option<Value>,
+    /// Error al evaluar el test
+    pub error: Option<String>,
+}
+
+/// Llamadas y tiempo de una funcion (`aura run --profile`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionProfile {
+    pub name: String,
+    pub calls: u64,
+    /// Tiempo total dentro de la funcion, incluidas las que llama. En una
+    /// recursion solo cuenta la llamada de afuera, asi no se suma dos veces
+    pub total_us: u64,
+}
+
+/// Acumulado de una funcion mientras se perfila
 #[derive(Debug, Default)]
+struct ProfileCounter {
+    calls: u64,
+    total: Duration,
+    /// Llamadas en curso (mas de una si es recursiva)
+    active: u32,
+}
+
+/// Entorno de ejecución
+///
+/// Los scopes de variables forman una pila: entrar o salir de un bloque o de
+/// una llamada es un push o un pop, sin mover los scopes existentes. Una
+/// busqueda recorre la pila del scope actual al global, asi que una funcion
+/// ve las variables de quien la llama. Funciones y tipos son siempre globales.
+#[derive(Debug)]
 pub struct Environment {
-    /// Variables locales
-    variables: HashMap<String, Value>,
+    /// Pila de scopes; el primero es el global y el ultimo el actual
+    scopes: Vec<HashMap<String, Value>>,
     /// Funciones definidas
     functions: HashMap<String, FuncDef>,
     /// Tipos definidos
     types: HashMap<String, TypeDef>,
-    /// Entorno padre (para scopes anidados)
-    parent: Option<Box<Environment>>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+            functions: HashMap::new(),
+            types: HashMap::new(),
+        }
+    }
 }
 
 impl Environment {
@@ -183,20 +337,26 @@ impl Environment {
         Self::default()
     }
 
-    pub fn with_parent(parent: Environment) -> Self {
-        Self {
-            parent: Some(Box::new(parent)),
-            ..Default::default()
+    /// Abre un scope anidado que ve las variables del actual (llamadas, match)
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    /// Cierra el ultimo scope abierto; el global nunca se cierra
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
         }
     }
 
     pub fn define(&mut self, name: String, value: Value) {
-        self.variables.insert(name, value);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, value);
+        }
     }
 
     pub fn get(&self, name: &str) -> Option<Value> {
-        self.variables.get(name).cloned()
-            .or_else(|| self.parent.as_ref().and_then(|p| p.get(name)))
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).cloned()
     }
 
     pub fn define_function(&mut self, func: FuncDef) {
@@ -205,7 +365,6 @@ impl Environment {
 
     pub fn get_function(&self, name: &str) -> Option<&FuncDef> {
         self.functions.get(name)
-            .or_else(|| self.parent.as_ref().and_then(|p| p.get_function(name)))
     }
 
     pub fn define_type(&mut self, ty: TypeDef) {
@@ -214,26 +373,37 @@ impl Environment {
 
     pub fn get_type(&self, name: &str) -> Option<&TypeDef> {
         self.types.get(name)
-            .or_else(|| self.parent.as_ref().and_then(|p| p.get_type(name)))
+    }
+
+    /// Elimina una funcion del entorno
+    pub fn remove_function(&mut self, name: &str) -> Option<FuncDef> {
+        self.functions.remove(name)
+    }
+
+    /// Elimina un tipo del entorno
+    pub fn remove_type(&mut self, name: &str) -> Option<TypeDef> {
+        self.types.remove(name)
     }
 
     /// Lista los nombres de todas las funciones definidas
     pub fn list_functions(&self) -> Vec<String> {
         let mut names: Vec<String> = self.functions.keys().cloned().collect();
-        if let Some(ref parent) = self.parent {
-            names.extend(parent.list_functions());
-        }
         names.sort();
-        names.dedup();
         names
     }
 
-    /// Lista los nombres de todas las variables definidas
+    /// Lista los nombres de todos los tipos definidos
+    pub fn list_types(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.types.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Lista los nombres de las variables visibles desde el scope actual
     pub fn list_variables(&self) -> Vec<String> {
-        let mut names: Vec<String> = self.variables.keys().cloned().collect();
-        if let Some(ref parent) = self.parent {
-            names.extend(parent.list_variables());
-        }
+        let mut names: Vec<String> = self.scopes.iter()
+            .flat_map(|scope| scope.keys().cloned())
+            .collect();
         names.sort();
         names.dedup();
         names
@@ -241,10 +411,7 @@ impl Environment {
 
     /// Limpia el entorno (variables, funciones, tipos)
     pub fn clear(&mut self) {
-        self.variables.clear();
-        self.functions.clear();
-        self.types.clear();
-        self.parent = None;
+        *self = Self::default();
     }
 }
 
@@ -277,10 +444,67 @@ pub struct VM {
     checkpoint_manager: CheckpointManager,
     /// Pending fixes accumulated during cognitive execution
     pub pending_fixes: Vec<(String, String)>,
+    /// Backtrack pedido al fallar `main`: checkpoint y ajustes, para el runner
+    pub pending_backtrack: Option<(String, Vec<(String, Value)>)>,
     /// Goal evaluation interval (every N steps)
     goal_check_interval: u64,
+    /// Llamadas a funciones AURA en curso
+    call_depth: usize,
+    /// Profundidad maxima antes de cortar con un RuntimeError
+    max_call_depth: usize,
+    /// Expresiones evaluadas en la ejecucion actual
+    steps_executed: u64,
+    /// Limite de expresiones evaluadas (`run_with_budget`)
+    max_steps: Option<u64>,
+    /// Pedido de cancelacion, compartido con `CancelHandle`
+    cancel: Arc<AtomicBool>,
+    /// Capacidades declaradas con `+nombre` en el programa cargado
+    capabilities: HashSet<String>,
+    /// Eventos de `observe` pendientes de `take_observations`
+    observations: Vec<ObservationEvent>,
+    /// Lineas de `print`, si la salida se captura en vez de ir a stdout
+    captured_stdout: Option<Vec<String>>,
+    /// Llamadas y tiempo acumulado por funcion, si se pidio perfilar
+    profile: Option<HashMap<String, ProfileCounter>>,
+    /// Claves que `env.get` puede leer, segun `+env(...)`
+    env_allowlist: EnvAllowlist,
+    /// Avisos no fatales de la ejecucion (por ejemplo lecturas de env no declaradas)
+    warnings: Vec<String>,
+    /// Sin efectos externos: http, db, env y email fallan (`with_sandbox`)
+    sandboxed: bool,
+}
+
+/// Permite cancelar desde otro thread la evaluacion en curso de una VM
+///
+/// La cancelacion es cooperativa: `eval` revisa el flag antes de cada
+/// expresion y corta con `execution cancelled`. Un builtin bloqueante (por
+/// ejemplo `http.get` esperando la red) no se interrumpe; la evaluacion
+/// termina recien en la siguiente expresion.
+#[derive(Debug, Clone)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+impl CancelHandle {
+    /// Pide que la evaluacion en curso termine
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
 }
 
+/// Variable de entorno con la respuesta de `reason` cuando no hay runtime cognitivo
+pub const REASON_DEFAULT_ENV: &str = "AURA_REASON_DEFAULT";
+
+/// Profundidad de llamadas por defecto
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/// Stack libre minimo antes de evaluar el cuerpo de una funcion
+const STACK_RED_ZONE: usize = 256 * 1024;
+/// Tamano de cada segmento de stack nuevo
+const STACK_SEGMENT: usize = 4 * 1024 * 1024;
+
 impl VM {
     pub fn new() -> Self {
         Self {
@@ -293,7 +517,20 @@ impl VM {
             observed_vars: HashSet::new(),
             checkpoint_manager: CheckpointManager::new(),
             pending_fixes: Vec::new(),
+            pending_backtrack: None,
             goal_check_interval: 100,
+            call_depth: 0,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            steps_executed: 0,
+            max_steps: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            capabilities: HashSet::new(),
+            observations: Vec::new(),
+            captured_stdout: None,
+            profile: None,
+            env_allowlist: EnvAllowlist::any(),
+            warnings: Vec::new(),
+            sandboxed: false,
         }
     }
 
@@ -309,10 +546,90 @@ impl VM {
             observed_vars: HashSet::new(),
             checkpoint_manager: CheckpointManager::new(),
             pending_fixes: Vec::new(),
+            pending_backtrack: None,
             goal_check_interval: 100,
+            call_depth: 0,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            steps_executed: 0,
+            max_steps: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            capabilities: HashSet::new(),
+            observations: Vec::new(),
+            captured_stdout: None,
+            profile: None,
+            env_allowlist: EnvAllowlist::any(),
+            warnings: Vec::new(),
+            sandboxed: false,
         }
     }
 
+    /// Retorna (y vacia) los eventos de `observe` registrados hasta ahora
+    pub fn take_observations(&mut self) -> Vec<ObservationEvent> {
+        std::mem::take(&mut self.observations)
+    }
+
+    /// Retorna (y vacia) los avisos registrados hasta ahora
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Captura lo que imprime `print` en vez de escribirlo en stdout
+    pub fn with_captured_stdout(mut self) -> Self {
+        self.captured_stdout = Some(Vec::new());
+        self
+    }
+
+    /// Retorna (y vacia) las lineas capturadas de `print`
+    pub fn take_stdout(&mut self) -> Vec<String> {
+        self.captured_stdout.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// VM para evaluar codigo no confiable: las capacidades con efectos
+    /// (http, db, env, email) fallan aunque el programa las declare
+    pub fn with_sandbox(mut self) -> Self {
+        self.sandboxed = true;
+        self
+    }
+
+    /// Corta con `execution budget exceeded` tras `max_steps` expresiones,
+    /// contadas desde la creacion de la VM y no solo en `main`
+    pub fn with_step_budget(mut self, max_steps: u64) -> Self {
+        self.steps_executed = 0;
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Cuenta llamadas y tiempo por funcion; sin esto no se mide nada
+    pub fn with_profiling(mut self) -> Self {
+        self.profile = Some(HashMap::new());
+        self
+    }
+
+    /// Retorna (y vacia) el perfil, de mayor a menor tiempo total
+    pub fn take_profile(&mut self) -> Vec<FunctionProfile> {
+        let Some(profile) = self.profile.as_mut() else { return Vec::new() };
+        let mut entries: Vec<FunctionProfile> = profile.drain()
+            .map(|(name, counter)| FunctionProfile {
+                name,
+                calls: counter.calls,
+                total_us: counter.total.as_micros() as u64,
+            })
+            .collect();
+        entries.sort_by(|a, b| b.total_us.cmp(&a.total_us).then_with(|| a.name.cmp(&b.name)));
+        entries
+    }
+
+    /// Configura cuantas llamadas anidadas se permiten antes de fallar
+    pub fn with_max_call_depth(mut self, depth: usize) -> Self {
+        self.max_call_depth = depth;
+        self
+    }
+
+    /// Retorna (y vacia) la traza de deliberacion del runtime cognitivo
+    pub fn take_cognitive_trace(&mut self) -> Vec<TraceEntry> {
+        self.cognitive.take_trace()
+    }
+
     /// Returns whether the cognitive runtime is active
     pub fn is_cognitive_active(&self) -> bool {
         self.cognitive.is_active()
@@ -325,6 +642,18 @@ impl VM {
 
     /// Carga un programa en la VM
     pub fn load(&mut self, program: &Program) {
+        for cap in &program.capabilities {
+            self.capabilities.insert(cap.name.clone());
+            if cap.name == "env" {
+                // Con argumentos es una lista, aunque ninguno sea una clave (`+env(timeout: 1)`)
+                self.env_allowlist = if cap.args.is_empty() {
+                    EnvAllowlist::any()
+                } else {
+                    EnvAllowlist::only(&cap.names())
+                };
+            }
+        }
+
         // Cargar goals (metadata)
         for def in &program.definitions {
             if let Definition::Goal(goal_def) = def {
@@ -334,8 +663,8 @@ impl VM {
 
         // Cargar invariants
         for def in &program.definitions {
-            if let Definition::Invariant(expr) = def {
-                self.invariants.push(expr.clone());
+            if let Definition::Invariant(inv) = def {
+                self.invariants.push(inv.expr.clone());
             }
         }
 
@@ -371,6 +700,10 @@ impl VM {
                                     self.pending_fixes.push((new_code, explanation));
                                 }
                                 CognitiveDecision::Override(val) => return Ok(val),
+                                // `main` ya termino: el runner puede restaurar el estado completo
+                                CognitiveDecision::Backtrack { checkpoint, adjustments } => {
+                                    self.pending_backtrack = Some((checkpoint, adjustments));
+                                }
                                 _ => {}
                             }
                         }
@@ -382,11 +715,122 @@ impl VM {
         }
     }
 
+    /// Ejecuta una funcion del programa cargado con los argumentos dados
+    ///
+    /// Es la entrada para usar AURA embebido en otro programa Rust:
+    ///
+    /// ```
+    /// use aura::{tokenize, parse, vm::{VM, Value}};
+    ///
+    /// let program = parse(tokenize("add(a, b) = a + b\n").unwrap()).unwrap();
+    /// let mut vm = VM::new();
+    /// vm.load(&program);
+    /// assert_eq!(vm.run_function("add", vec![Value::Int(2), Value::Int(3)]).unwrap(), Value::Int(5));
+    /// ```
+    ///
+    /// A diferencia de `call_by_name`, falla si la cantidad de argumentos no
+    /// coincide con los parametros.
+    pub fn run_function(&mut self, name: &str, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let func = self.env.get_function(name).cloned()
+            .ok_or_else(|| RuntimeError::new(format!("Función no encontrada: {}", name)))?;
+
+        let arity = func.min_arity();
+        let accepted = if func.rest_param().is_some() { args.len() >= arity } else { args.len() == arity };
+        if !accepted {
+            let at_least = if func.rest_param().is_some() { "al menos " } else { "" };
+            return Err(RuntimeError::new(format!(
+                "{} espera {}{} argumentos, recibio {}",
+                name, at_least, arity, args.len()
+            )));
+        }
+
+        self.call_function(&func, &args)
+    }
+
+    /// Ejecuta `main` cortando si se evaluan mas de `max_steps` expresiones
+    ///
+    /// Cada expresion evaluada cuenta un paso, incluidas las de cuerpos de
+    /// `for`, pasos de pipes y llamadas, asi un loop accidental termina con
+    /// `execution budget exceeded` en vez de colgar al agente.
+    pub fn run_with_budget(&mut self, max_steps: u64) -> Result<Value, RuntimeError> {
+        self.steps_executed = 0;
+        self.max_steps = Some(max_steps);
+        let result = self.run();
+        self.max_steps = None;
+        result
+    }
+
+    /// Handle para cancelar la evaluacion desde otro thread
+    ///
+    /// Un pedido de cancelacion queda activo hasta `reset` o la proxima
+    /// `eval_with_timeout`.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle(Arc::clone(&self.cancel))
+    }
+
+    /// Evalua una expresion cortando si tarda mas de `timeout`
+    ///
+    /// La expresion se evalua en un thread de trabajo que toma prestada la
+    /// VM; este thread espera el resultado y, si vence el plazo, levanta el
+    /// flag de cancelacion. No se abandona el worker: se espera a que vea el
+    /// flag, asi la VM nunca queda a medio usar en otro thread y sigue
+    /// disponible despues del timeout. El costo es que un builtin bloqueado
+    /// demora el corte hasta que retorna (ver `CancelHandle`).
+    pub fn eval_with_timeout(&mut self, expr: &Expr, timeout: Duration) -> Result<Value, RuntimeError> {
+        self.cancel.store(false, Ordering::Relaxed);
+        let cancel = Arc::clone(&self.cancel);
+        let (done_tx, done_rx) = mpsc::channel();
+
+        let (result, timed_out) = std::thread::scope(|scope| {
+            let worker = std::thread::Builder::new()
+                .name("aura-eval".to_string())
+                .stack_size(STACK_SEGMENT)
+                .spawn_scoped(scope, || {
+                    let result = self.eval(expr);
+                    let _ = done_tx.send(());
+                    result
+                })
+                .map_err(|e| RuntimeError::new(format!("no se pudo crear el thread de evaluacion: {}", e)))?;
+
+            let timed_out = done_rx.recv_timeout(timeout).is_err();
+            if timed_out {
+                cancel.store(true, Ordering::Relaxed);
+            }
+            match worker.join() {
+                Ok(result) => Ok((result, timed_out)),
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        })?;
+
+        self.cancel.store(false, Ordering::Relaxed);
+        match result {
+            Err(e) if timed_out && e.message == "execution cancelled" => Err(RuntimeError::new(
+                format!("evaluation timed out after {}ms", timeout.as_millis()),
+            )),
+            other => other,
+        }
+    }
+
+    /// Expresiones evaluadas en la ultima ejecucion con presupuesto
+    pub fn steps_executed(&self) -> u64 {
+        self.steps_executed
+    }
+
     /// Define una funcion en el entorno actual
     pub fn define_function(&mut self, func: FuncDef) {
         self.env.define_function(func);
     }
 
+    /// Elimina una funcion del entorno. Retorna la definicion eliminada.
+    pub fn remove_function(&mut self, name: &str) -> Option<FuncDef> {
+        self.env.remove_function(name)
+    }
+
+    /// Elimina un tipo del entorno. Retorna la definicion eliminada.
+    pub fn remove_type(&mut self, name: &str) -> Option<TypeDef> {
+        self.env.remove_type(name)
+    }
+
     /// Lista las funciones definidas
     pub fn list_functions(&self) -> Vec<String> {
         self.env.list_functions()
@@ -397,6 +841,47 @@ impl VM {
         self.env.list_variables()
     }
 
+    /// Lista los tipos definidos
+    pub fn list_types(&self) -> Vec<String> {
+        self.env.list_types()
+    }
+
+    /// Obtiene la definicion de un tipo
+    pub fn get_type(&self, name: &str) -> Option<&TypeDef> {
+        self.env.get_type(name)
+    }
+
+    /// Obtiene la definicion de una funcion
+    pub fn get_function(&self, name: &str) -> Option<&FuncDef> {
+        self.env.get_function(name)
+    }
+
+    /// Firma de una funcion con la sintaxis de AURA, p.ej.
+    /// `fetch!(url, ...headers) - efecto: si`
+    pub fn get_function_signature(&self, name: &str) -> Option<String> {
+        let func = self.get_function(name)?;
+        let params: Vec<String> = func.params.iter()
+            .map(|p| {
+                let dots = if p.rest { "..." } else { "" };
+                match &p.ty {
+                    Some(ty) => format!("{}{}{}", dots, p.name, ty),
+                    None => format!("{}{}", dots, p.name),
+                }
+            })
+            .collect();
+        let ret = func.return_type.as_ref()
+            .map(|ty| format!(" -> {}", ty))
+            .unwrap_or_default();
+        Some(format!(
+            "{}{}({}){} - efecto: {}",
+            func.name,
+            if func.has_effect { "!" } else { "" },
+            params.join(", "),
+            ret,
+            if func.has_effect { "si" } else { "no" },
+        ))
+    }
+
     /// Obtiene los goals declarados en el programa
     pub fn get_goals(&self) -> &[GoalDef] {
         &self.goals
@@ -434,6 +919,12 @@ impl VM {
         self.failed_expectations.clear();
         self.invariants.clear();
         self.step_count = 0;
+        self.steps_executed = 0;
+        self.cancel.store(false, Ordering::Relaxed);
+        self.capabilities.clear();
+        self.env_allowlist = EnvAllowlist::any();
+        self.warnings.clear();
+        self.observations.clear();
         self.observed_vars.clear();
         self.checkpoint_manager = CheckpointManager::new();
         self.pending_fixes.clear();
@@ -519,9 +1010,10 @@ impl VM {
     }
 
     /// Creates a checkpoint of the current variable state
-    pub fn checkpoint(&mut self, name: &str) {
-        let variables = self.capture_variables();
-        self.checkpoint_manager.save(name.to_string(), variables, self.step_count);
+    pub fn save_checkpoint(&mut self, name: &str) {
+        let mut checkpoint = self.checkpoint();
+        checkpoint.name = name.to_string();
+        self.checkpoint_manager.insert(checkpoint);
         if self.cognitive.is_active() {
             self.cognitive.observe(ObservationEvent::CheckpointCreated {
                 name: name.to_string(),
@@ -530,6 +1022,47 @@ impl VM {
         }
     }
 
+    /// Captura el estado completo del entorno: variables, funciones y tipos
+    ///
+    /// Los scopes se aplanan: si un nombre esta en varios, gana el mas interno.
+    pub fn checkpoint(&self) -> VMCheckpoint {
+        let functions = self.env.list_functions().into_iter()
+            .filter_map(|name| self.env.get_function(&name).cloned().map(|f| (name, f)))
+            .collect();
+        let types = self.env.list_types().into_iter()
+            .filter_map(|name| self.env.get_type(&name).cloned().map(|t| (name, t)))
+            .collect();
+        VMCheckpoint {
+            name: String::new(),
+            variables: self.capture_variables(),
+            functions,
+            types,
+            step_count: self.step_count,
+            timestamp: std::time::Instant::now(),
+        }
+    }
+
+    /// Reemplaza el entorno por el capturado en `checkpoint`
+    ///
+    /// Lo definido despues del checkpoint desaparece. Reemplaza toda la
+    /// cadena de scopes, asi que solo debe usarse fuera de una evaluacion
+    /// (como hace el runner cognitivo); durante `eval` un backtrack usa
+    /// `restore_with_adjustments`, que solo redefine variables.
+    pub fn restore(&mut self, checkpoint: &VMCheckpoint) {
+        let mut env = Environment::new();
+        for (name, value) in &checkpoint.variables {
+            env.define(name.clone(), value.clone());
+        }
+        for func in checkpoint.functions.values() {
+            env.define_function(func.clone());
+        }
+        for ty in checkpoint.types.values() {
+            env.define_type(ty.clone());
+        }
+        self.env = env;
+        self.step_count = checkpoint.step_count;
+    }
+
     /// Restores VM state from a checkpoint
     pub fn restore_checkpoint(&mut self, name: &str) -> Result<(), RuntimeError> {
         let cp = self.checkpoint_manager.restore(name)
@@ -606,6 +1139,65 @@ impl VM {
         decisions
     }
 
+    /// Evalua cada test contra el programa cargado, sin ejecutar `main`
+    ///
+    /// Un test `a == b` compara los dos lados (y reporta ambos valores si
+    /// no coinciden); cualquier otra expresion pasa si evalua a `true`.
+    pub fn run_tests(&mut self, tests: &[TestDef]) -> Vec<TestOutcome> {
+        tests.iter()
+            .map(|test| {
+                let evaluated = match &test.expr {
+                    Expr::BinaryOp { left, op: BinaryOp::Eq, right } => self.eval(left)
+                        .and_then(|value| Ok((value, Some(self.eval(right)?)))),
+                    expr => self.eval(expr).map(|value| (value, None)),
+                };
+                match evaluated {
+                    Ok((value, expected)) => TestOutcome {
+                        name: test.name.clone(),
+                        passed: match &expected {
+                            Some(expected) => value.deep_equal(expected),
+                            None => value == Value::Bool(true),
+                        },
+                        value: Some(value),
+                        expected,
+                        error: None,
+                    },
+                    Err(e) => TestOutcome {
+                        name: test.name.clone(),
+                        passed: false,
+                        value: None,
+                        expected: None,
+                        error: Some(e.message),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Evalua el `check` de cada goal que lo tenga contra el entorno final
+    ///
+    /// A diferencia de `evaluate_goals` no delibera ni necesita runtime
+    /// cognitivo: solo informa. Un check que no da `true` (o que falla al
+    /// evaluarse) cuenta como no cumplido.
+    pub fn check_goals(&mut self) -> Vec<GoalOutcome> {
+        let goals = self.goals.clone();
+        goals.iter()
+            .filter_map(|goal| {
+                let check = goal.check.as_ref()?;
+                let (value, error) = match self.eval(check) {
+                    Ok(value) => (Some(value), None),
+                    Err(e) => (None, Some(e.message)),
+                };
+                Some(GoalOutcome {
+                    description: goal.description.clone(),
+                    passed: matches!(value, Some(Value::Bool(true))),
+                    value,
+                    error,
+                })
+            })
+            .collect()
+    }
+
     /// Returns the checkpoint manager
     pub fn checkpoint_manager(&self) -> &CheckpointManager {
         &self.checkpoint_manager
@@ -628,11 +1220,22 @@ impl VM {
 
     /// Evalúa una expresión
     pub fn eval(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(RuntimeError::new("execution cancelled"));
+        }
+        if let Some(max_steps) = self.max_steps {
+            self.steps_executed += 1;
+            if self.steps_executed > max_steps {
+                return Err(RuntimeError::new("execution budget exceeded"));
+            }
+        }
+
         match expr {
             // Literales
             Expr::Int(n) => Ok(Value::Int(*n)),
             Expr::Float(n) => Ok(Value::Float(*n)),
             Expr::String(s) => Ok(Value::String(self.interpolate_string(s)?)),
+            Expr::RawString(s) => Ok(Value::String(s.clone())),
             Expr::Bool(b) => Ok(Value::Bool(*b)),
             Expr::Nil => Ok(Value::Nil),
 
@@ -659,17 +1262,40 @@ impl VM {
 
             // Lista
             Expr::List(items) => {
-                let values: Result<Vec<_>, _> = items.iter()
-                    .map(|e| self.eval(e))
-                    .collect();
-                Ok(Value::List(values?))
+                let mut values = Vec::with_capacity(items.len());
+                for item in items {
+                    match item {
+                        Expr::Spread(inner) => match self.eval(inner)? {
+                            Value::List(spread) => values.extend(spread),
+                            other => return Err(RuntimeError::new(format!(
+                                "...{} en una lista requiere una lista, recibio un {}",
+                                crate::fmt::format_expr(inner),
+                                other.type_name()
+                            ))),
+                        },
+                        _ => values.push(self.eval(item)?),
+                    }
+                }
+                Ok(Value::List(values))
             }
 
-            // Record
+            // Record; con `...base` los campos posteriores pisan a los anteriores
             Expr::Record(fields) => {
                 let mut map = HashMap::new();
                 for (name, expr) in fields {
-                    map.insert(name.clone(), self.eval(expr)?);
+                    match expr {
+                        Expr::Spread(inner) => match self.eval(inner)? {
+                            Value::Record(spread) => map.extend(spread),
+                            other => return Err(RuntimeError::new(format!(
+                                "...{} en un record requiere un record, recibio un {}",
+                                crate::fmt::format_expr(inner),
+                                other.type_name()
+                            ))),
+                        },
+                        _ => {
+                            map.insert(name.clone(), self.eval(expr)?);
+                        }
+                    }
                 }
                 Ok(Value::Record(map))
             }
@@ -690,13 +1316,7 @@ impl VM {
             // Safe access
             Expr::SafeAccess(obj, field) => {
                 let obj_val = self.eval(obj)?;
-                match obj_val {
-                    Value::Nil => Ok(Value::Nil),
-                    Value::Record(map) => {
-                        Ok(map.get(field).cloned().unwrap_or(Value::Nil))
-                    }
-                    _ => Err(RuntimeError::new(format!("No se puede acceder a campo '{}' en {:?}", field, obj_val))),
-                }
+                safe_field(obj_val, field)
             }
 
             // Llamada a función
@@ -743,6 +1363,24 @@ impl VM {
                 }
             }
 
+            // Conversion explicita: usa los builtins int/float/str
+            Expr::Cast { expr, ty } => {
+                let value = self.eval(expr)?;
+                let builtin = match ty {
+                    Type::Int => "int",
+                    Type::Float => "float",
+                    _ => "str",
+                };
+                self.call_builtin(builtin, std::slice::from_ref(&value)).map_err(|_| {
+                    RuntimeError::new(format!(
+                        "No se puede convertir {} ({}) a {}",
+                        value,
+                        value.type_name(),
+                        ty.cast_name().unwrap_or("string")
+                    ))
+                })
+            }
+
             // Placeholder
             Expr::Placeholder => Ok(Value::Nil),
 
@@ -754,7 +1392,7 @@ impl VM {
                     result = self.eval(expr)?;
 
                     // Periodically check goals if cognitive runtime is active
-                    if self.cognitive.is_active() && self.step_count % self.goal_check_interval == 0 {
+                    if self.cognitive.is_active() && self.step_count.is_multiple_of(self.goal_check_interval) {
                         let decisions = self.evaluate_goals();
                         for decision in decisions {
                             match decision {
@@ -783,7 +1421,7 @@ impl VM {
                 // If this variable is observed and cognitive runtime is active
                 if self.observed_vars.contains(name) && self.cognitive.is_active() {
                     // Create implicit checkpoint
-                    self.checkpoint(&format!("let_{}", name));
+                    self.save_checkpoint(&format!("let_{}", name));
                     // Notify cognitive runtime
                     self.cognitive.observe(ObservationEvent::ValueChanged {
                         name: name.clone(),
@@ -830,8 +1468,9 @@ impl VM {
                     // Expectation passed - return true
                     Ok(Value::Bool(true))
                 } else {
-                    // Expectation failed - register but don't crash
-                    let condition_str = format!("{:?}", condition);
+                    // Expectation failed - se registra para el healing y, sin
+                    // runtime cognitivo que delibere, corta la ejecucion
+                    let condition_str = crate::fmt::format_expr(condition);
                     let failure = ExpectationFailure::new(
                         condition_str,
                         message.clone(),
@@ -842,7 +1481,7 @@ impl VM {
                     // If cognitive runtime is active, trigger deliberation
                     if self.cognitive.is_active() {
                         self.cognitive.observe(ObservationEvent::ExpectEvaluated {
-                            condition: format!("{:?}", condition),
+                            condition: failure.condition.clone(),
                             result: false,
                             failure: Some(failure.clone()),
                         });
@@ -866,26 +1505,63 @@ impl VM {
                             CognitiveDecision::Halt(err) => Err(err),
                         }
                     } else {
-                        // Return false to indicate failure, but continue execution
-                        Ok(Value::Bool(false))
+                        Err(RuntimeError::new(failure.to_string()))
                     }
                 }
             }
 
             // Observe expression - register variable for monitoring
-            Expr::Observe { target, condition: _ } => {
+            Expr::Observe { target, condition } => {
                 self.observed_vars.insert(target.clone());
                 if self.cognitive.is_active() {
                     // Create checkpoint at observe point
-                    self.checkpoint(&format!("observe_{}", target));
+                    self.save_checkpoint(&format!("observe_{}", target));
                 }
-                Ok(Value::Nil)
+
+                // `observe x` antes de definir x solo declara que se vigila
+                let root = target.split('.').next().unwrap_or_default();
+                if self.env.get(root).is_none() && self.env.get_function(root).is_none() {
+                    return Ok(Value::Nil);
+                }
+
+                // `response.status` -> response.status como acceso a campos
+                let mut segments = target.split('.');
+                let mut target_expr = Expr::Ident(segments.next().unwrap_or_default().to_string());
+                for field in segments {
+                    target_expr = Expr::FieldAccess(Box::new(target_expr), field.to_string());
+                }
+                let value = self.eval(&target_expr)?;
+
+                // El `where` ve el valor con el nombre del ultimo segmento (status)
+                let recorded = match condition {
+                    Some(cond) => {
+                        let name = target.rsplit('.').next().unwrap_or(target);
+                        self.env.push_scope();
+                        self.env.define(name.to_string(), value.clone());
+                        let result = self.eval(cond);
+                        self.env.pop_scope();
+                        self.is_truthy(&result?)
+                    }
+                    None => true,
+                };
+
+                if recorded {
+                    let event = ObservationEvent::Observed { target: target.clone(), value: value.clone() };
+                    if self.cognitive.is_active() {
+                        self.cognitive.observe(event.clone());
+                    }
+                    self.observations.push(event);
+                }
+                Ok(value)
             }
 
             // Reason expression - explicit deliberation
             Expr::Reason { observations, question } => {
+                // Sin agente la respuesta es deterministica: el default
+                // configurado o, si no hay, la pregunta misma
                 if !self.cognitive.is_active() {
-                    return Ok(Value::Nil);
+                    let answer = std::env::var(REASON_DEFAULT_ENV).unwrap_or_else(|_| question.clone());
+                    return Ok(Value::String(answer));
                 }
 
                 // Evaluate observation expressions
@@ -919,29 +1595,117 @@ impl VM {
                 }
             }
 
-            // Match, InterpolatedString, Spread - no implementados aún
+            // Match con valor: el primer brazo que calza (y cuya guarda da
+            // true) liga sus variables en un scope hijo y evalua el cuerpo ahi
+            Expr::Match { expr, arms } => {
+                let value = self.eval(expr)?;
+                for arm in arms {
+                    let mut bindings = Vec::new();
+                    if !self.match_pattern(&arm.pattern, &value, &mut bindings)? {
+                        continue;
+                    }
+
+                    self.env.push_scope();
+                    for (name, bound) in bindings {
+                        self.env.define(name, bound);
+                    }
+                    let result = match &arm.guard {
+                        Some(guard) => self.eval(guard).map(|v| self.is_truthy(&v)),
+                        None => Ok(true),
+                    }
+                    .and_then(|fires| if fires { self.eval(&arm.body).map(Some) } else { Ok(None) });
+                    self.env.pop_scope();
+                    match result {
+                        Ok(Some(result)) => return Ok(result),
+                        Ok(None) => continue,
+                        Err(e) => return Err(e),
+                    }
+                }
+                Err(RuntimeError::new(format!("Ningun patron coincide con {}", value)))
+            }
+
+            // InterpolatedString, y Spread fuera de una lista o record
             _ => Err(RuntimeError::new("Expresión no soportada aún")),
         }
     }
 
+    /// Intenta calzar `value` con `pattern`, acumulando las variables ligadas
+    ///
+    /// Un constructor `Name(p)` calza con el record `{tag: "Name", value: v}`
+    /// que producen `Ok(x)` y `Err(e)`; con varios campos, `value` es una
+    /// lista que se recorre en orden.
+    fn match_pattern(&mut self, pattern: &Pattern, value: &Value, bindings: &mut Vec<(String, Value)>) -> Result<bool, RuntimeError> {
+        match pattern {
+            Pattern::Wildcard => Ok(true),
+            Pattern::Ident(name) => {
+                bindings.push((name.clone(), value.clone()));
+                Ok(true)
+            }
+            Pattern::Literal(expr) => Ok(self.eval(expr)?.deep_equal(value)),
+            Pattern::Constructor { name, fields } => {
+                let Value::Record(record) = value else { return Ok(false) };
+                if record.get("tag") != Some(&Value::String(name.clone())) {
+                    return Ok(false);
+                }
+                match (fields.as_slice(), record.get("value")) {
+                    ([], _) => Ok(true),
+                    ([field], Some(inner)) => self.match_pattern(field, inner, bindings),
+                    (fields, Some(Value::List(items))) if fields.len() == items.len() => {
+                        for (field, item) in fields.iter().zip(items) {
+                            if !self.match_pattern(field, item, bindings)? {
+                                return Ok(false);
+                            }
+                        }
+                        Ok(true)
+                    }
+                    _ => Ok(false),
+                }
+            }
+        }
+    }
+
     /// Evalúa una llamada a función
     fn eval_call(&mut self, func: &Expr, args: &[Expr]) -> Result<Value, RuntimeError> {
         // Detectar llamadas a métodos de módulos (http.get, json.parse, etc.)
-        if let Expr::FieldAccess(obj, method) = func {
-            if let Expr::Ident(obj_name) = obj.as_ref() {
-                match obj_name.as_str() {
-                    "http" => return self.call_http_method(method, args),
-                    "json" => return self.call_json_method(method, args),
-                    "math" => return self.call_math_method(method, args),
-                    "db" => return self.call_db_method(method, args),
-                    "env" => return self.call_env_method(method, args),
-                    _ => {}
-                }
+        if let Expr::FieldAccess(obj, method) = func
+            && let Expr::Ident(obj_name) = obj.as_ref() {
+            if self.sandboxed && matches!(obj_name.as_str(), "http" | "db" | "env" | "email") {
+                return Err(RuntimeError::new(format!(
+                    "{}.{} no esta permitido en una VM sin efectos", obj_name, method
+                )));
+            }
+            match obj_name.as_str() {
+                "http" => return self.call_http_method(method, args),
+                "json" => return self.call_json_method(method, args),
+                "math" => return self.call_math_method(method, args),
+                "db" => return self.call_db_method(method, args),
+                "env" => return self.call_env_method(method, args),
+                "auth" => return self.call_auth_method(method, args),
+                "crypto" => return self.call_crypto_method(method, args),
+                "email" => return self.call_email_method(method, args),
+                _ => {}
             }
         }
 
-        // Evaluar la función
-        let func_val = self.eval(func)?;
+        // Evaluar la función; `.json()` sobre una respuesta HTTP o un string la parsea.
+        // Con `obj?.metodo()` y `obj` nil la llamada entera es nil: no se
+        // evaluan los argumentos ni se intenta llamar a nil
+        let safe = matches!(func, Expr::SafeAccess(_, _));
+        let func_val = match func {
+            Expr::FieldAccess(obj, method) | Expr::SafeAccess(obj, method) if method == "json" => {
+                let target = self.eval(obj)?;
+                match &target {
+                    Value::Nil if safe => return Ok(Value::Nil),
+                    Value::Record(map) if map.contains_key("json") => map["json"].clone(),
+                    _ => return self.parse_json_method(&target, args),
+                }
+            }
+            Expr::SafeAccess(obj, field) => match self.eval(obj)? {
+                Value::Nil => return Ok(Value::Nil),
+                receiver => safe_field(receiver, field)?,
+            },
+            _ => self.eval(func)?,
+        };
 
         // Evaluar argumentos
         let arg_values: Result<Vec<_>, _> = args.iter()
@@ -950,19 +1714,27 @@ impl VM {
         let arg_values = arg_values?;
 
         match func_val {
-            Value::Function(name) => {
-                // Buscar función definida
-                if let Some(func_def) = self.env.get_function(&name).cloned() {
-                    self.call_function(&func_def, &arg_values)
-                } else {
-                    // Funciones built-in
-                    self.call_builtin(&name, &arg_values)
-                }
-            }
+            Value::Function(name) => self.call_named(&name, &arg_values),
             _ => Err(RuntimeError::new(format!("No se puede llamar a {:?}", func_val))),
         }
     }
 
+    /// Llama a una función por nombre: primero las definidas, después las built-in
+    fn call_named(&mut self, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+        if let Some(func_def) = self.env.get_function(name).cloned() {
+            return self.call_function(&func_def, args);
+        }
+        // `sort(lista, f)` tiene que llamar a `f` para calcular cada clave
+        if let ("sort", [Value::List(items), Value::Function(key_fn)]) = (name, args) {
+            let keys = items
+                .iter()
+                .map(|item| self.call_named(key_fn, std::slice::from_ref(item)))
+                .collect::<Result<Vec<_>, _>>()?;
+            return sort_by_keys(items, keys);
+        }
+        self.call_builtin(name, args)
+    }
+
     /// Llama a un método HTTP (http.get, http.post, etc.)
     fn call_http_method(&mut self, method: &str, args: &[Expr]) -> Result<Value, RuntimeError> {
         // Evaluar argumentos
@@ -978,18 +1750,15 @@ impl VM {
             None => return Err(RuntimeError::new(format!("http.{} requiere al menos un argumento (URL)", method))),
         };
 
-        // Extraer body (segundo argumento, opcional)
-        let body = match arg_values.get(1) {
-            Some(Value::String(s)) => Some(s.as_str()),
-            Some(Value::Record(r)) => {
-                // Convertir record a JSON string
-                None // Por ahora no soportamos records como body directamente
-            }
-            _ => None,
+        // Extraer body (segundo argumento, opcional); records y listas van como JSON
+        let (body, is_json) = match arg_values.get(1) {
+            Some(Value::String(s)) => (Some(s.clone()), false),
+            Some(value @ (Value::Record(_) | Value::List(_))) => (Some(json_stringify(value)?), true),
+            _ => (None, false),
         };
 
         // Extraer headers (tercer argumento, opcional)
-        let headers = match arg_values.get(2) {
+        let mut headers = match arg_values.get(2) {
             Some(Value::Record(r)) => {
                 let mut h = std::collections::HashMap::new();
                 for (k, v) in r {
@@ -1002,15 +1771,124 @@ impl VM {
             _ => None,
         };
 
+        // Un Content-Type explicito del programa tiene prioridad
+        if is_json {
+            let h = headers.get_or_insert_with(std::collections::HashMap::new);
+            if !h.keys().any(|k| k.eq_ignore_ascii_case("content-type")) {
+                h.insert("Content-Type".to_string(), "application/json".to_string());
+            }
+        }
+        let body = body.as_deref();
+
+        // Opciones (cuarto argumento, opcional): {timeout_ms, retries}
+        let options = match arg_values.get(3) {
+            Some(Value::Record(r)) => HttpOptions::from_env()?.with_record(r)?,
+            Some(Value::Nil) | None => HttpOptions::from_env()?,
+            Some(other) => return Err(RuntimeError::new(format!("http.{} espera opciones como record, recibió: {:?}", method, other))),
+        };
+
         match method {
-            "get" => http_get(&url, headers.as_ref()),
-            "post" => http_post(&url, body, headers.as_ref()),
-            "put" => http_put(&url, body, headers.as_ref()),
-            "delete" => http_delete(&url, headers.as_ref()),
+            "get" => http_get(&url, headers.as_ref(), &options),
+            "post" => http_post(&url, body, headers.as_ref(), &options),
+            "put" => http_put(&url, body, headers.as_ref(), &options),
+            "delete" => http_delete(&url, headers.as_ref(), &options),
             _ => Err(RuntimeError::new(format!("Método HTTP no soportado: {}", method))),
         }
     }
 
+    /// `resp.json()` / `resp.json(Tipo)`: parsea el body de una respuesta HTTP
+    /// (o un string) y, con un tipo, valida el resultado contra sus campos
+    fn parse_json_method(&mut self, target: &Value, args: &[Expr]) -> Result<Value, RuntimeError> {
+        let text = match target {
+            Value::String(s) => s,
+            Value::Record(map) => match map.get("body") {
+                Some(Value::String(body)) => body,
+                _ => return Err(RuntimeError::new("json() requiere una respuesta HTTP con body string")),
+            },
+            other => return Err(RuntimeError::new(format!("json() requiere una respuesta HTTP o un string, recibio {}", other))),
+        };
+        let value = json_parse(text)?;
+
+        match args {
+            [] => Ok(value),
+            [Expr::Ident(type_name)] => {
+                let type_def = self.env.get_type(type_name).cloned()
+                    .ok_or_else(|| RuntimeError::new(format!("json(): tipo no definido: {}", type_name)))?;
+                match value {
+                    // Una lista de objetos se valida elemento a elemento
+                    Value::List(items) => items.into_iter()
+                        .map(|item| self.coerce_to_type(item, &type_def))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(Value::List),
+                    other => self.coerce_to_type(other, &type_def),
+                }
+            }
+            _ => Err(RuntimeError::new("json() acepta a lo sumo un nombre de tipo")),
+        }
+    }
+
+    /// Verifica que un record tenga los campos de `type_def` con tipos compatibles
+    ///
+    /// Un campo opcional (`:s?`) ausente queda en nil; un `:i` se acepta
+    /// donde se espera `:f`. Los campos extra se conservan.
+    fn coerce_to_type(&mut self, value: Value, type_def: &TypeDef) -> Result<Value, RuntimeError> {
+        let Value::Record(mut fields) = value else {
+            return Err(RuntimeError::new(format!("json(): se esperaba un objeto {}, recibio {}", type_def.name, value)));
+        };
+
+        for field in &type_def.fields {
+            let current = fields.remove(&field.name).unwrap_or(Value::Nil);
+            let coerced = match current {
+                Value::Nil => match &field.default {
+                    Some(default) => self.eval(default)?,
+                    None if field.nullable || matches!(field.ty, Type::Optional(_)) => Value::Nil,
+                    None => {
+                        return Err(RuntimeError::new(format!(
+                            "json(): falta el campo '{}' de {}", field.name, type_def.name
+                        )));
+                    }
+                },
+                other => self.coerce_field(other, &field.ty).map_err(|found| RuntimeError::new(format!(
+                    "json(): el campo '{}' de {} espera {}, recibio {}",
+                    field.name, type_def.name, field.ty, found
+                )))?,
+            };
+            fields.insert(field.name.clone(), coerced);
+        }
+
+        Ok(Value::Record(fields))
+    }
+
+    /// Convierte un valor al tipo de un campo; el error es el valor recibido
+    fn coerce_field(&mut self, value: Value, ty: &Type) -> Result<Value, Value> {
+        match (ty, value) {
+            (Type::Optional(_), Value::Nil) => Ok(Value::Nil),
+            (Type::Optional(inner), value) => self.coerce_field(value, inner),
+            (Type::Int, v @ Value::Int(_)) => Ok(v),
+            (Type::Float, Value::Int(n)) => Ok(Value::Float(n as f64)),
+            (Type::Float, v @ Value::Float(_)) => Ok(v),
+            (Type::String | Type::Timestamp | Type::Uuid, v @ Value::String(_)) => Ok(v),
+            (Type::Bool, v @ Value::Bool(_)) => Ok(v),
+            (Type::Map(_, _), v @ Value::Record(_)) => Ok(v),
+            (Type::List(inner), Value::List(items)) => {
+                let mut coerced = Vec::with_capacity(items.len());
+                for item in items {
+                    coerced.push(self.coerce_field(item, inner)?);
+                }
+                Ok(Value::List(coerced))
+            }
+            (Type::Named(name), value) => match self.env.get_type(name).cloned() {
+                Some(nested) => {
+                    let original = value.clone();
+                    self.coerce_to_type(value, &nested).map_err(|_| original)
+                }
+                // Tipo desconocido (por ejemplo un enum): se acepta tal cual
+                None => Ok(value),
+            },
+            (_, other) => Err(other),
+        }
+    }
+
     /// Llama a un método JSON (json.parse, json.stringify)
     fn call_json_method(&mut self, method: &str, args: &[Expr]) -> Result<Value, RuntimeError> {
         let arg_values: Result<Vec<_>, _> = args.iter()
@@ -1024,7 +1902,7 @@ impl VM {
                     Some(Value::String(s)) => {
                         // Parse JSON string to Value
                         match serde_json::from_str::<serde_json::Value>(s) {
-                            Ok(json) => Ok(json_to_value(json)),
+                            Ok(json) => Ok(Value::from_json(&json)),
                             Err(e) => Err(RuntimeError::new(format!("Error parsing JSON: {}", e))),
                         }
                     }
@@ -1034,7 +1912,7 @@ impl VM {
             "stringify" => {
                 match arg_values.first() {
                     Some(v) => {
-                        match serde_json::to_string(&value_to_json(v)) {
+                        match serde_json::to_string(&v.to_json()) {
                             Ok(s) => Ok(Value::String(s)),
                             Err(e) => Err(RuntimeError::new(format!("Error stringifying JSON: {}", e))),
                         }
@@ -1062,9 +1940,16 @@ impl VM {
                 }
             }
             "pow" => {
-                match (arg_values.get(0), arg_values.get(1)) {
+                match (arg_values.first(), arg_values.get(1)) {
                     (Some(Value::Int(base)), Some(Value::Int(exp))) => {
-                        Ok(Value::Int(base.pow(*exp as u32)))
+                        // Con exponente entero el resultado es entero: uno negativo no tiene sentido
+                        let exp = u32::try_from(*exp).map_err(|_| {
+                            RuntimeError::new(format!(
+                                "math.pow con base entera requiere un exponente entre 0 y {}, recibio {}; usa un float para exponentes negativos",
+                                u32::MAX, exp
+                            ))
+                        })?;
+                        checked_int(base.checked_pow(exp))
                     }
                     (Some(Value::Float(base)), Some(Value::Float(exp))) => {
                         Ok(Value::Float(base.powf(*exp)))
@@ -1115,10 +2000,13 @@ impl VM {
                 }
             }
             "query" => {
-                match (arg_values.get(0), arg_values.get(1), arg_values.get(2)) {
+                match (arg_values.first(), arg_values.get(1), arg_values.get(2)) {
                     (Some(conn), Some(Value::String(sql)), Some(Value::List(params))) => {
                         db_query(conn, sql, params)
                     }
+                    (Some(conn), Some(Value::String(sql)), Some(Value::Record(params))) => {
+                        db_query_named(conn, sql, params)
+                    }
                     (Some(conn), Some(Value::String(sql)), None) => {
                         db_query(conn, sql, &[])
                     }
@@ -1126,10 +2014,13 @@ impl VM {
                 }
             }
             "execute" => {
-                match (arg_values.get(0), arg_values.get(1), arg_values.get(2)) {
+                match (arg_values.first(), arg_values.get(1), arg_values.get(2)) {
                     (Some(conn), Some(Value::String(sql)), Some(Value::List(params))) => {
                         db_execute(conn, sql, params)
                     }
+                    (Some(conn), Some(Value::String(sql)), Some(Value::Record(params))) => {
+                        db_execute_named(conn, sql, params)
+                    }
                     (Some(conn), Some(Value::String(sql)), None) => {
                         db_execute(conn, sql, &[])
                     }
@@ -1149,16 +2040,33 @@ impl VM {
         }
     }
 
-    /// Llama a un método ENV (env.get, env.set, env.exists, env.remove)
+    /// Llama a un método ENV (env.get, env.get_or, env.set, env.exists, env.remove)
     fn call_env_method(&mut self, method: &str, args: &[Expr]) -> Result<Value, RuntimeError> {
+        self.require_capability("env", method)?;
+
         let arg_values: Result<Vec<_>, _> = args.iter()
             .map(|a| self.eval(a))
             .collect();
         let arg_values = arg_values?;
 
+        // Una clave fuera de `+env(...)` se lee como si no estuviera definida
+        if matches!(method, "get" | "get_or" | "exists")
+            && let Some(Value::String(name)) = arg_values.first()
+            && !self.env_allowlist.allows(name)
+        {
+            let warning = format!("env.{}(\"{}\"): la clave no esta declarada en +env(...)", method, name);
+            if !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
+            return Ok(match method {
+                "exists" => Value::Bool(false),
+                _ => arg_values.get(1).cloned().unwrap_or(Value::Nil),
+            });
+        }
+
         match method {
             "get" => {
-                match (arg_values.get(0), arg_values.get(1)) {
+                match (arg_values.first(), arg_values.get(1)) {
                     (Some(Value::String(name)), Some(default)) => {
                         // env.get(name, default)
                         Ok(env_get_or(name, default))
@@ -1170,8 +2078,14 @@ impl VM {
                     _ => Err(RuntimeError::new("env.get requiere (nombre) o (nombre, default)")),
                 }
             }
+            "get_or" => {
+                match (arg_values.first(), arg_values.get(1)) {
+                    (Some(Value::String(name)), Some(default)) => Ok(env_get_or(name, default)),
+                    _ => Err(RuntimeError::new("env.get_or requiere (nombre, default)")),
+                }
+            }
             "set" => {
-                match (arg_values.get(0), arg_values.get(1)) {
+                match (arg_values.first(), arg_values.get(1)) {
                     (Some(Value::String(name)), Some(Value::String(value))) => {
                         env_set(name, value)
                     }
@@ -1194,40 +2108,169 @@ impl VM {
         }
     }
 
+    /// Falla si el programa no declaro `+cap`
+    fn require_capability(&self, cap: &str, method: &str) -> Result<(), RuntimeError> {
+        if self.capabilities.contains(cap) {
+            Ok(())
+        } else {
+            Err(RuntimeError::new(format!("{}.{} requiere declarar +{}", cap, method, cap)))
+        }
+    }
+
+    /// Llama a un método EMAIL (email.send)
+    fn call_email_method(&mut self, method: &str, args: &[Expr]) -> Result<Value, RuntimeError> {
+        self.require_capability("email", method)?;
+
+        let arg_values: Result<Vec<_>, _> = args.iter()
+            .map(|a| self.eval(a))
+            .collect();
+        let arg_values = arg_values?;
+
+        match method {
+            "send" => {
+                match (arg_values.first(), arg_values.get(1), arg_values.get(2)) {
+                    (Some(Value::String(to)), Some(Value::String(subject)), Some(Value::String(body))) => {
+                        email_send(to, subject, body)
+                    }
+                    _ => Err(RuntimeError::new("email.send requiere (to, subject, body) como strings")),
+                }
+            }
+            _ => Err(RuntimeError::new(format!("Método email no soportado: {}", method))),
+        }
+    }
+
+    /// Llama a un método AUTH (auth.hash, auth.verify, auth.jwt_sign, auth.jwt_verify)
+    fn call_auth_method(&mut self, method: &str, args: &[Expr]) -> Result<Value, RuntimeError> {
+        self.require_capability("auth", method)?;
+
+        let arg_values: Result<Vec<_>, _> = args.iter()
+            .map(|a| self.eval(a))
+            .collect();
+        let arg_values = arg_values?;
+
+        match method {
+            "hash" => {
+                match arg_values.first() {
+                    Some(Value::String(password)) => hash_password(password).map(Value::String),
+                    _ => Err(RuntimeError::new("auth.hash requiere password como string")),
+                }
+            }
+            "verify" => {
+                match (arg_values.first(), arg_values.get(1)) {
+                    (Some(Value::String(password)), Some(Value::String(hash))) => {
+                        Ok(Value::Bool(verify_password(password, hash)))
+                    }
+                    _ => Err(RuntimeError::new("auth.verify requiere (password, hash) como strings")),
+                }
+            }
+            "jwt_sign" => {
+                match (arg_values.first(), arg_values.get(1)) {
+                    (Some(claims), Some(Value::String(secret))) => jwt_sign(claims, secret).map(Value::String),
+                    _ => Err(RuntimeError::new("auth.jwt_sign requiere (claims, secret)")),
+                }
+            }
+            "jwt_verify" => {
+                match (arg_values.first(), arg_values.get(1)) {
+                    (Some(Value::String(token)), Some(Value::String(secret))) => Ok(jwt_verify(token, secret)),
+                    _ => Err(RuntimeError::new("auth.jwt_verify requiere (token, secret) como strings")),
+                }
+            }
+            _ => Err(RuntimeError::new(format!("Método auth no soportado: {}", method))),
+        }
+    }
+
+    /// Llama a un método CRYPTO (crypto.sha256, crypto.random_bytes)
+    fn call_crypto_method(&mut self, method: &str, args: &[Expr]) -> Result<Value, RuntimeError> {
+        self.require_capability("crypto", method)?;
+
+        let arg_values: Result<Vec<_>, _> = args.iter()
+            .map(|a| self.eval(a))
+            .collect();
+        let arg_values = arg_values?;
+
+        match method {
+            "sha256" => {
+                match arg_values.first() {
+                    Some(Value::String(s)) => Ok(Value::String(sha256_hex(s))),
+                    Some(Value::Bytes(b)) => Ok(Value::String(sha256_hex(b))),
+                    _ => Err(RuntimeError::new("crypto.sha256 requiere un string o bytes")),
+                }
+            }
+            "random_bytes" => {
+                match arg_values.first() {
+                    Some(Value::Int(n)) if *n >= 0 => random_bytes(*n as usize).map(Value::Bytes),
+                    _ => Err(RuntimeError::new("crypto.random_bytes requiere una cantidad de bytes (int >= 0)")),
+                }
+            }
+            _ => Err(RuntimeError::new(format!("Método crypto no soportado: {}", method))),
+        }
+    }
+
     /// Llama a una función definida por el usuario
     fn call_function(&mut self, func: &FuncDef, args: &[Value]) -> Result<Value, RuntimeError> {
+        // Una recursion sin fin desbordaria el stack de Rust y abortaria el proceso
+        if self.call_depth >= self.max_call_depth {
+            return Err(RuntimeError::new("stack overflow: recursion limit exceeded"));
+        }
+
         // Create implicit checkpoint before function call (if cognitive active)
         if self.cognitive.is_active() {
-            self.checkpoint(&format!("call_{}", func.name));
+            self.save_checkpoint(&format!("call_{}", func.name));
         }
 
-        // Crear nuevo entorno con los parámetros
-        let mut new_env = Environment::new();
-
-        for (param, arg) in func.params.iter().zip(args.iter()) {
-            new_env.define(param.name.clone(), arg.clone());
+        if func.rest_param().is_some() && args.len() < func.min_arity() {
+            return Err(RuntimeError::new(format!(
+                "{} espera al menos {} argumentos, recibio {}",
+                func.name, func.min_arity(), args.len()
+            )));
         }
 
-        // Copiar funciones y tipos al nuevo entorno
-        new_env.parent = Some(Box::new(std::mem::take(&mut self.env)));
+        // Scope nuevo con los parámetros
+        self.env.push_scope();
+        match func.rest_param() {
+            Some(rest) => {
+                let fixed = func.min_arity();
+                for (param, arg) in func.params[..fixed].iter().zip(args.iter()) {
+                    self.env.define(param.name.clone(), arg.clone());
+                }
+                self.env.define(rest.name.clone(), Value::List(args[fixed..].to_vec()));
+            }
+            None => {
+                for (param, arg) in func.params.iter().zip(args.iter()) {
+                    self.env.define(param.name.clone(), arg.clone());
+                }
+            }
+        }
 
         // Evaluar el cuerpo
-        self.env = new_env;
-        let result = self.eval(&func.body);
-
-        // Restaurar entorno
-        if let Some(parent) = self.env.parent.take() {
-            self.env = *parent;
+        self.call_depth += 1;
+        let started = self.profile.as_mut().map(|profile| {
+            let counter = profile.entry(func.name.clone()).or_default();
+            counter.calls += 1;
+            counter.active += 1;
+            Instant::now()
+        });
+        // Cada llamada AURA usa varios frames de eval: el stack del thread
+        // (2MB en tests) no alcanza para llegar a max_call_depth
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || self.eval(&func.body));
+        if let (Some(started), Some(profile)) = (started, self.profile.as_mut())
+            && let Some(counter) = profile.get_mut(&func.name) {
+            counter.active -= 1;
+            if counter.active == 0 {
+                counter.total += started.elapsed();
+            }
         }
+        self.call_depth -= 1;
+
+        self.env.pop_scope();
 
         // Notify cognitive runtime of function return
-        if self.cognitive.is_active() {
-            if let Ok(ref val) = result {
-                self.cognitive.observe(ObservationEvent::FunctionReturned {
-                    name: func.name.clone(),
-                    result: val.clone(),
-                });
-            }
+        if self.cognitive.is_active()
+            && let Ok(ref val) = result {
+            self.cognitive.observe(ObservationEvent::FunctionReturned {
+                name: func.name.clone(),
+                result: val.clone(),
+            });
         }
 
         result
@@ -1242,18 +2285,24 @@ impl VM {
             "type" |
             "first" | "last" | "head" | "tail" |
             "keys" | "values" |
-            "push" | "pop" | "concat" |
-            "abs" | "min" | "max" |
-            "not"
+            "push" | "pop" | "concat" | "contains" |
+            "range" | "sort" | "join" | "reverse" | "unique" | "dedup" |
+            "bytes" |
+            "abs" | "min" | "max" | "sum" | "avg" |
+            "not" |
+            "Ok" | "Err"
         )
     }
 
     /// Llama a una función built-in
-    fn call_builtin(&self, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+    fn call_builtin(&mut self, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
         match name {
             "print" | "print!" => {
                 for arg in args {
-                    println!("{}", arg);
+                    match &mut self.captured_stdout {
+                        Some(lines) => lines.push(arg.to_string()),
+                        None => println!("{}", arg),
+                    }
                 }
                 Ok(Value::Nil)
             }
@@ -1262,6 +2311,7 @@ impl VM {
                     Some(Value::String(s)) => Ok(Value::Int(s.len() as i64)),
                     Some(Value::List(l)) => Ok(Value::Int(l.len() as i64)),
                     Some(Value::Record(r)) => Ok(Value::Int(r.len() as i64)),
+                    Some(Value::Bytes(b)) => Ok(Value::Int(b.len() as i64)),
                     _ => Err(RuntimeError::new("len requiere string, lista o record")),
                 }
             }
@@ -1302,18 +2352,8 @@ impl VM {
                 }
             }
             "type" => {
-                match args.first() {
-                    Some(Value::Nil) => Ok(Value::String("nil".to_string())),
-                    Some(Value::Int(_)) => Ok(Value::String("int".to_string())),
-                    Some(Value::Float(_)) => Ok(Value::String("float".to_string())),
-                    Some(Value::String(_)) => Ok(Value::String("string".to_string())),
-                    Some(Value::Bool(_)) => Ok(Value::String("bool".to_string())),
-                    Some(Value::List(_)) => Ok(Value::String("list".to_string())),
-                    Some(Value::Record(_)) => Ok(Value::String("record".to_string())),
-                    Some(Value::Function(_)) => Ok(Value::String("function".to_string())),
-                    Some(Value::Native { type_id, .. }) => Ok(Value::String(type_id.clone())),
-                    None => Ok(Value::String("nil".to_string())),
-                }
+                let name = args.first().map(Value::type_name).unwrap_or("nil");
+                Ok(Value::String(name.to_string()))
             }
             "first" | "head" => {
                 match args.first() {
@@ -1333,6 +2373,79 @@ impl VM {
                     _ => Err(RuntimeError::new("last requiere lista o string")),
                 }
             }
+            "sort" => {
+                match (args.first(), args.get(1)) {
+                    (Some(Value::List(items)), None) => sort_by_keys(items, items.clone()),
+                    // Con un string como clave se ordenan records por ese campo
+                    (Some(Value::List(items)), Some(Value::String(field))) => {
+                        let keys = items
+                            .iter()
+                            .map(|item| match item {
+                                Value::Record(fields) => fields.get(field).cloned().ok_or_else(|| {
+                                    RuntimeError::new(format!("sort: un elemento no tiene el campo '{}'", field))
+                                }),
+                                other => Err(RuntimeError::new(format!(
+                                    "sort por campo requiere una lista de records, recibio un {}",
+                                    other.type_name()
+                                ))),
+                            })
+                            .collect::<Result<Vec<_>, _>>()?;
+                        sort_by_keys(items, keys)
+                    }
+                    _ => Err(RuntimeError::new("sort requiere una lista, y opcionalmente una funcion o un campo como clave")),
+                }
+            }
+            "join" => {
+                let separator = match args.get(1) {
+                    Some(Value::String(s)) => s.as_str(),
+                    None => "",
+                    Some(other) => return Err(RuntimeError::new(format!("join requiere un separador string, recibio un {}", other.type_name()))),
+                };
+                match args.first() {
+                    Some(Value::List(items)) => Ok(Value::String(
+                        items.iter().map(|item| item.to_string()).collect::<Vec<_>>().join(separator),
+                    )),
+                    _ => Err(RuntimeError::new("join requiere una lista")),
+                }
+            }
+            // Los bytes de un string (UTF-8) o de una lista de enteros 0..=255
+            "bytes" => {
+                match args.first() {
+                    Some(Value::Bytes(b)) => Ok(Value::Bytes(b.clone())),
+                    Some(Value::String(s)) => Ok(Value::Bytes(s.as_bytes().to_vec())),
+                    Some(Value::List(items)) => items
+                        .iter()
+                        .map(|item| match item {
+                            Value::Int(n) => u8::try_from(*n)
+                                .map_err(|_| RuntimeError::new(format!("bytes: {} no es un byte (0..=255)", n))),
+                            other => Err(RuntimeError::new(format!("bytes: se esperaba un int, recibio un {}", other.type_name()))),
+                        })
+                        .collect::<Result<Vec<u8>, _>>()
+                        .map(Value::Bytes),
+                    _ => Err(RuntimeError::new("bytes requiere un string o una lista de enteros")),
+                }
+            }
+            "reverse" => {
+                match args.first() {
+                    Some(Value::List(items)) => Ok(Value::List(items.iter().rev().cloned().collect())),
+                    _ => Err(RuntimeError::new("reverse requiere una lista")),
+                }
+            }
+            // Se queda con la primera aparicion de cada valor, comparando como `==`
+            "unique" | "dedup" => {
+                match args.first() {
+                    Some(Value::List(items)) => {
+                        let mut seen: Vec<Value> = Vec::new();
+                        for item in items {
+                            if !seen.iter().any(|s| s.deep_equal(item)) {
+                                seen.push(item.clone());
+                            }
+                        }
+                        Ok(Value::List(seen))
+                    }
+                    _ => Err(RuntimeError::new(format!("{} requiere una lista", name))),
+                }
+            }
             "tail" => {
                 match args.first() {
                     Some(Value::List(l)) => {
@@ -1362,7 +2475,7 @@ impl VM {
                 }
             }
             "push" => {
-                match (args.get(0), args.get(1)) {
+                match (args.first(), args.get(1)) {
                     (Some(Value::List(l)), Some(v)) => {
                         let mut new_list = l.clone();
                         new_list.push(v.clone());
@@ -1372,7 +2485,7 @@ impl VM {
                 }
             }
             "concat" => {
-                match (args.get(0), args.get(1)) {
+                match (args.first(), args.get(1)) {
                     (Some(Value::List(a)), Some(Value::List(b))) => {
                         let mut new_list = a.clone();
                         new_list.extend(b.clone());
@@ -1384,33 +2497,89 @@ impl VM {
                     _ => Err(RuntimeError::new("concat requiere dos listas o dos strings")),
                 }
             }
+            "contains" => {
+                match (args.first(), args.get(1)) {
+                    (Some(Value::String(s)), Some(Value::String(sub))) => Ok(Value::Bool(s.contains(sub.as_str()))),
+                    (Some(Value::List(items)), Some(value)) => Ok(Value::Bool(items.iter().any(|item| item.deep_equal(value)))),
+                    (Some(Value::Record(map)), Some(Value::String(key))) => Ok(Value::Bool(map.contains_key(key))),
+                    _ => Err(RuntimeError::new("contains requiere (string, substring), (lista, valor) o (record, campo)")),
+                }
+            }
+            "range" => {
+                let bounds: Vec<i64> = args
+                    .iter()
+                    .map(|arg| match arg {
+                        Value::Int(n) => Ok(*n),
+                        other => Err(RuntimeError::new(format!("range requiere enteros, recibio {}", other.type_name()))),
+                    })
+                    .collect::<Result<_, _>>()?;
+                let (start, end, step) = match bounds.as_slice() {
+                    [end] => (0, *end, 1),
+                    [start, end] => (*start, *end, 1),
+                    [start, end, step] => (*start, *end, *step),
+                    _ => return Err(RuntimeError::new("range requiere (fin), (inicio, fin) o (inicio, fin, paso)")),
+                };
+                range_values(start, end, step)
+            }
             "abs" => {
                 match args.first() {
-                    Some(Value::Int(n)) => Ok(Value::Int(n.abs())),
+                    Some(Value::Int(n)) => checked_int(n.checked_abs()),
                     Some(Value::Float(f)) => Ok(Value::Float(f.abs())),
                     _ => Err(RuntimeError::new("abs requiere numero")),
                 }
             }
             "min" => {
-                match (args.get(0), args.get(1)) {
+                match (args.first(), args.get(1)) {
+                    (Some(Value::List(items)), None) => {
+                        Ok(Numbers::from_list("min", items)?.fold(i64::min, f64::min).unwrap_or(Value::Nil))
+                    }
                     (Some(Value::Int(a)), Some(Value::Int(b))) => Ok(Value::Int(*a.min(b))),
                     (Some(Value::Float(a)), Some(Value::Float(b))) => Ok(Value::Float(a.min(*b))),
-                    _ => Err(RuntimeError::new("min requiere dos numeros del mismo tipo")),
+                    _ => Err(RuntimeError::new("min requiere una lista o dos numeros del mismo tipo")),
                 }
             }
             "max" => {
-                match (args.get(0), args.get(1)) {
+                match (args.first(), args.get(1)) {
+                    (Some(Value::List(items)), None) => {
+                        Ok(Numbers::from_list("max", items)?.fold(i64::max, f64::max).unwrap_or(Value::Nil))
+                    }
                     (Some(Value::Int(a)), Some(Value::Int(b))) => Ok(Value::Int(*a.max(b))),
                     (Some(Value::Float(a)), Some(Value::Float(b))) => Ok(Value::Float(a.max(*b))),
-                    _ => Err(RuntimeError::new("max requiere dos numeros del mismo tipo")),
+                    _ => Err(RuntimeError::new("max requiere una lista o dos numeros del mismo tipo")),
                 }
             }
+            "sum" => match args {
+                [Value::List(items)] => {
+                    let numbers = Numbers::from_list("sum", items)?;
+                    Ok(numbers.sum()?.unwrap_or(Value::Int(0)))
+                }
+                _ => Err(RuntimeError::new("sum requiere una lista de numeros")),
+            },
+            "avg" => match args {
+                [Value::List(items)] => {
+                    let numbers = Numbers::from_list("avg", items)?;
+                    let count = items.len() as f64;
+                    Ok(match numbers.sum()? {
+                        Some(Value::Int(total)) => Value::Float(total as f64 / count),
+                        Some(Value::Float(total)) => Value::Float(total / count),
+                        _ => Value::Nil,
+                    })
+                }
+                _ => Err(RuntimeError::new("avg requiere una lista de numeros")),
+            },
             "not" => {
                 match args.first() {
                     Some(v) => Ok(Value::Bool(!self.is_truthy(v))),
                     None => Ok(Value::Bool(true)),
                 }
             }
+            // Valores etiquetados para matchear con `? Ok(v) -> .. | Err(e) -> ..`
+            "Ok" | "Err" => {
+                let mut record = HashMap::new();
+                record.insert("tag".to_string(), Value::String(name.to_string()));
+                record.insert("value".to_string(), args.first().cloned().unwrap_or(Value::Nil));
+                Ok(Value::Record(record))
+            }
             _ => Err(RuntimeError::new(format!("Función no definida: {}", name))),
         }
     }
@@ -1419,7 +2588,7 @@ impl VM {
     fn eval_pipe_step(&mut self, input: &Value, expr: &Expr) -> Result<Value, RuntimeError> {
         match expr {
             // Si es una llamada, agregar el input como primer argumento
-            Expr::Call { func, args, has_effect } => {
+            Expr::Call { func, args, has_effect: _ } => {
                 let mut new_args = vec![input.clone()];
                 for arg in args {
                     // Reemplazar placeholders con el input
@@ -1432,23 +2601,13 @@ impl VM {
 
                 let func_val = self.eval(func)?;
                 if let Value::Function(name) = func_val {
-                    if let Some(func_def) = self.env.get_function(&name).cloned() {
-                        self.call_function(&func_def, &new_args)
-                    } else {
-                        self.call_builtin(&name, &new_args)
-                    }
+                    self.call_named(&name, &new_args)
                 } else {
                     Err(RuntimeError::new("Pipe a algo que no es función"))
                 }
             }
             // Si es solo un identificador de función
-            Expr::Ident(name) => {
-                if let Some(func_def) = self.env.get_function(name).cloned() {
-                    self.call_function(&func_def, &[input.clone()])
-                } else {
-                    self.call_builtin(name, &[input.clone()])
-                }
-            }
+            Expr::Ident(name) => self.call_named(name, std::slice::from_ref(input)),
             _ => Err(RuntimeError::new("Expresión de pipe no soportada")),
         }
     }
@@ -1457,17 +2616,39 @@ impl VM {
     fn eval_binary_op(&self, left: &Value, op: &BinaryOp, right: &Value) -> Result<Value, RuntimeError> {
         match (left, op, right) {
             // Aritmética con enteros
-            (Value::Int(a), BinaryOp::Add, Value::Int(b)) => Ok(Value::Int(a + b)),
-            (Value::Int(a), BinaryOp::Sub, Value::Int(b)) => Ok(Value::Int(a - b)),
-            (Value::Int(a), BinaryOp::Mul, Value::Int(b)) => Ok(Value::Int(a * b)),
+            // Con `checked_*` un overflow es un error de runtime y no un
+            // panic (debug) o un valor que dio la vuelta (release)
+            (Value::Int(a), BinaryOp::Add, Value::Int(b)) => checked_int(a.checked_add(*b)),
+            (Value::Int(a), BinaryOp::Sub, Value::Int(b)) => checked_int(a.checked_sub(*b)),
+            (Value::Int(a), BinaryOp::Mul, Value::Int(b)) => checked_int(a.checked_mul(*b)),
             (Value::Int(a), BinaryOp::Div, Value::Int(b)) => {
                 if *b == 0 {
                     Err(RuntimeError::new("División por cero"))
                 } else {
-                    Ok(Value::Int(a / b))
+                    checked_int(a.checked_div(*b))
+                }
+            }
+            (Value::Int(a), BinaryOp::Mod, Value::Int(b)) => {
+                if *b == 0 {
+                    Err(RuntimeError::new("Módulo por cero"))
+                } else {
+                    checked_int(a.checked_rem(*b))
                 }
             }
-            (Value::Int(a), BinaryOp::Mod, Value::Int(b)) => Ok(Value::Int(a % b)),
+
+            // Bits, solo con enteros
+            (Value::Int(a), BinaryOp::BitAnd, Value::Int(b)) => Ok(Value::Int(a & b)),
+            (Value::Int(a), BinaryOp::BitOr, Value::Int(b)) => Ok(Value::Int(a | b)),
+            (Value::Int(a), BinaryOp::BitXor, Value::Int(b)) => Ok(Value::Int(a ^ b)),
+            (Value::Int(a), BinaryOp::Shl | BinaryOp::Shr, Value::Int(b)) => {
+                let shift = u32::try_from(*b)
+                    .ok()
+                    .filter(|s| *s < i64::BITS)
+                    .ok_or_else(|| RuntimeError::new(format!(
+                        "Desplazamiento invalido: {} (debe estar entre 0 y 63)", b
+                    )))?;
+                Ok(Value::Int(if *op == BinaryOp::Shl { a << shift } else { a >> shift }))
+            }
 
             // Aritmética con flotantes
             (Value::Float(a), BinaryOp::Add, Value::Float(b)) => Ok(Value::Float(a + b)),
@@ -1489,43 +2670,60 @@ impl VM {
             (Value::Bool(a), BinaryOp::Concat, Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
             (Value::String(a), BinaryOp::Add, Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
 
+            // Concatenación de listas: ambos lados tienen que ser listas
+            (Value::List(a), BinaryOp::Concat, Value::List(b)) => {
+                Ok(Value::List(a.iter().chain(b.iter()).cloned().collect()))
+            }
+            (Value::List(_), BinaryOp::Concat, other) | (other, BinaryOp::Concat, Value::List(_)) => {
+                Err(RuntimeError::new(format!(
+                    "++ con una lista requiere otra lista, recibio {}; para agregar un elemento usa lista ++ [x]",
+                    other.type_name()
+                )))
+            }
+
+            // Pertenencia: elemento en lista, clave en record, substring en string
+            (_, BinaryOp::In, Value::List(items)) => Ok(Value::Bool(items.iter().any(|item| left.deep_equal(item)))),
+            (Value::String(key), BinaryOp::In, Value::Record(fields)) => Ok(Value::Bool(fields.contains_key(key))),
+            (Value::String(needle), BinaryOp::In, Value::String(haystack)) => Ok(Value::Bool(haystack.contains(needle.as_str()))),
+            (_, BinaryOp::In, Value::Record(_) | Value::String(_)) => Err(RuntimeError::new(format!(
+                "in con un {} requiere un string a la izquierda, recibio {}",
+                right.type_name(),
+                left.type_name()
+            ))),
+            (_, BinaryOp::In, _) => Err(RuntimeError::new(format!(
+                "in requiere una lista, un record o un string a la derecha, recibio {}",
+                right.type_name()
+            ))),
+
             // Comparaciones enteras
-            (Value::Int(a), BinaryOp::Eq, Value::Int(b)) => Ok(Value::Bool(a == b)),
-            (Value::Int(a), BinaryOp::NotEq, Value::Int(b)) => Ok(Value::Bool(a != b)),
             (Value::Int(a), BinaryOp::Lt, Value::Int(b)) => Ok(Value::Bool(a < b)),
             (Value::Int(a), BinaryOp::Gt, Value::Int(b)) => Ok(Value::Bool(a > b)),
             (Value::Int(a), BinaryOp::LtEq, Value::Int(b)) => Ok(Value::Bool(a <= b)),
             (Value::Int(a), BinaryOp::GtEq, Value::Int(b)) => Ok(Value::Bool(a >= b)),
 
             // Comparaciones flotantes
-            (Value::Float(a), BinaryOp::Eq, Value::Float(b)) => Ok(Value::Bool(a == b)),
-            (Value::Float(a), BinaryOp::NotEq, Value::Float(b)) => Ok(Value::Bool(a != b)),
             (Value::Float(a), BinaryOp::Lt, Value::Float(b)) => Ok(Value::Bool(a < b)),
             (Value::Float(a), BinaryOp::Gt, Value::Float(b)) => Ok(Value::Bool(a > b)),
             (Value::Float(a), BinaryOp::LtEq, Value::Float(b)) => Ok(Value::Bool(a <= b)),
             (Value::Float(a), BinaryOp::GtEq, Value::Float(b)) => Ok(Value::Bool(a >= b)),
 
             // Comparaciones mixtas Int/Float
-            (Value::Int(a), BinaryOp::Eq, Value::Float(b)) => Ok(Value::Bool((*a as f64) == *b)),
-            (Value::Int(a), BinaryOp::NotEq, Value::Float(b)) => Ok(Value::Bool((*a as f64) != *b)),
             (Value::Int(a), BinaryOp::Lt, Value::Float(b)) => Ok(Value::Bool((*a as f64) < *b)),
             (Value::Int(a), BinaryOp::Gt, Value::Float(b)) => Ok(Value::Bool((*a as f64) > *b)),
             (Value::Int(a), BinaryOp::LtEq, Value::Float(b)) => Ok(Value::Bool((*a as f64) <= *b)),
             (Value::Int(a), BinaryOp::GtEq, Value::Float(b)) => Ok(Value::Bool((*a as f64) >= *b)),
-            (Value::Float(a), BinaryOp::Eq, Value::Int(b)) => Ok(Value::Bool(*a == (*b as f64))),
-            (Value::Float(a), BinaryOp::NotEq, Value::Int(b)) => Ok(Value::Bool(*a != (*b as f64))),
             (Value::Float(a), BinaryOp::Lt, Value::Int(b)) => Ok(Value::Bool(*a < (*b as f64))),
             (Value::Float(a), BinaryOp::Gt, Value::Int(b)) => Ok(Value::Bool(*a > (*b as f64))),
             (Value::Float(a), BinaryOp::LtEq, Value::Int(b)) => Ok(Value::Bool(*a <= (*b as f64))),
             (Value::Float(a), BinaryOp::GtEq, Value::Int(b)) => Ok(Value::Bool(*a >= (*b as f64))),
 
-            (Value::String(a), BinaryOp::Eq, Value::String(b)) => Ok(Value::Bool(a == b)),
-            (Value::String(a), BinaryOp::NotEq, Value::String(b)) => Ok(Value::Bool(a != b)),
-
-            (Value::Bool(a), BinaryOp::Eq, Value::Bool(b)) => Ok(Value::Bool(a == b)),
             (Value::Bool(a), BinaryOp::And, Value::Bool(b)) => Ok(Value::Bool(*a && *b)),
             (Value::Bool(a), BinaryOp::Or, Value::Bool(b)) => Ok(Value::Bool(*a || *b)),
 
+            // Igualdad estructural para cualquier par de valores (ver `Value::deep_equal`)
+            (_, BinaryOp::Eq, _) => Ok(Value::Bool(left.deep_equal(right))),
+            (_, BinaryOp::NotEq, _) => Ok(Value::Bool(!left.deep_equal(right))),
+
             _ => Err(RuntimeError::new(format!(
                 "Operación {:?} no soportada entre {:?} y {:?}",
                 op, left, right
@@ -1536,7 +2734,7 @@ impl VM {
     /// Evalúa una operación unaria
     fn eval_unary_op(&self, op: &UnaryOp, val: &Value) -> Result<Value, RuntimeError> {
         match (op, val) {
-            (UnaryOp::Neg, Value::Int(n)) => Ok(Value::Int(-n)),
+            (UnaryOp::Neg, Value::Int(n)) => checked_int(n.checked_neg()),
             (UnaryOp::Neg, Value::Float(n)) => Ok(Value::Float(-n)),
             (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
             _ => Err(RuntimeError::new(format!(
@@ -1633,6 +2831,125 @@ impl Default for VM {
     }
 }
 
+/// `obj?.field`: nil si `obj` es nil o no tiene el campo
+fn safe_field(obj: Value, field: &str) -> Result<Value, RuntimeError> {
+    match obj {
+        Value::Nil => Ok(Value::Nil),
+        Value::Record(map) => Ok(map.get(field).cloned().unwrap_or(Value::Nil)),
+        _ => Err(RuntimeError::new(format!("No se puede acceder a campo '{}' en {:?}", field, obj))),
+    }
+}
+
+/// Resultado de una operacion entera `checked_*`; `None` es un overflow
+fn checked_int(result: Option<i64>) -> Result<Value, RuntimeError> {
+    result
+        .map(Value::Int)
+        .ok_or_else(|| RuntimeError::new("integer overflow"))
+}
+
+/// Numeros de una lista para `sum`, `min`, `max` y `avg`: si hay algun float,
+/// todos se promueven a float
+enum Numbers {
+    Ints(Vec<i64>),
+    Floats(Vec<f64>),
+}
+
+impl Numbers {
+    fn from_list(name: &str, items: &[Value]) -> Result<Self, RuntimeError> {
+        let not_a_number = |other: &Value| {
+            RuntimeError::new(format!("{} requiere una lista de numeros, recibio un {}", name, other.type_name()))
+        };
+        if items.iter().all(|item| matches!(item, Value::Int(_))) {
+            return Ok(Numbers::Ints(items.iter().filter_map(|item| match item {
+                Value::Int(n) => Some(*n),
+                _ => None,
+            }).collect()));
+        }
+        items
+            .iter()
+            .map(|item| match item {
+                Value::Int(n) => Ok(*n as f64),
+                Value::Float(f) => Ok(*f),
+                other => Err(not_a_number(other)),
+            })
+            .collect::<Result<_, _>>()
+            .map(Numbers::Floats)
+    }
+
+    /// Reduce con `ints` o `floats` segun el caso; `None` si la lista esta vacia
+    fn fold(self, ints: impl Fn(i64, i64) -> i64, floats: impl Fn(f64, f64) -> f64) -> Option<Value> {
+        match self {
+            Numbers::Ints(values) => values.into_iter().reduce(ints).map(Value::Int),
+            Numbers::Floats(values) => values.into_iter().reduce(floats).map(Value::Float),
+        }
+    }
+
+    /// Suma de los numeros; `None` si la lista esta vacia
+    fn sum(self) -> Result<Option<Value>, RuntimeError> {
+        match self {
+            Numbers::Ints(values) if values.is_empty() => Ok(None),
+            Numbers::Ints(values) => checked_int(values.into_iter().try_fold(0i64, i64::checked_add)).map(Some),
+            floats => Ok(floats.fold(|a, b| a + b, |a, b| a + b)),
+        }
+    }
+}
+
+/// Ordena `items` segun la clave de cada uno (`keys[i]` es la de `items[i]`).
+/// Numeros de menor a mayor, antes que los strings, que van en orden
+/// lexicografico. El orden es estable.
+fn sort_by_keys(items: &[Value], keys: Vec<Value>) -> Result<Value, RuntimeError> {
+    if let Some(other) = keys.iter().find(|k| !matches!(k, Value::Int(_) | Value::Float(_) | Value::String(_))) {
+        return Err(RuntimeError::new(format!("sort requiere claves numericas o strings, recibio un {}", other.type_name())));
+    }
+    let mut pairs: Vec<(Value, &Value)> = keys.into_iter().zip(items).collect();
+    pairs.sort_by(|(a, _), (b, _)| sort_order(a, b));
+    Ok(Value::List(pairs.into_iter().map(|(_, item)| item.clone()).collect()))
+}
+
+/// Orden total para `sort`: los floats usan `total_cmp`, asi un NaN tiene
+/// un lugar fijo y el sort no recibe un orden inconsistente
+fn sort_order(a: &Value, b: &Value) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+    match (a, b) {
+        (Value::Int(x), Value::Int(y)) => x.cmp(y),
+        (Value::Float(x), Value::Float(y)) => x.total_cmp(y),
+        (Value::Int(x), Value::Float(y)) => int_float_order(*x, *y),
+        (Value::Float(x), Value::Int(y)) => int_float_order(*y, *x).reverse(),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::String(_), _) => Ordering::Greater,
+        (_, Value::String(_)) => Ordering::Less,
+        _ => Ordering::Equal,
+    }
+}
+
+/// Compara un entero con un float. `i as f64` redondea los enteros grandes;
+/// si coincide con el float se desempata con los valores exactos
+fn int_float_order(i: i64, f: f64) -> std::cmp::Ordering {
+    match (i as f64).total_cmp(&f) {
+        std::cmp::Ordering::Equal => (i as i128).cmp(&(f as i128)),
+        other => other,
+    }
+}
+
+/// Maximo de elementos que `range` puede generar de una vez
+const MAX_RANGE_LEN: i64 = 10_000_000;
+
+/// Enteros de `start` a `end` (exclusivo) avanzando de a `step`
+fn range_values(start: i64, end: i64, step: i64) -> Result<Value, RuntimeError> {
+    if step == 0 {
+        return Err(RuntimeError::new("range: el paso no puede ser 0"));
+    }
+    let span = if step > 0 { end.saturating_sub(start) } else { start.saturating_sub(end) };
+    let len = if span <= 0 { 0 } else { (span - 1) / step.saturating_abs() + 1 };
+    if len > MAX_RANGE_LEN {
+        return Err(RuntimeError::new(format!(
+            "range demasiado grande: {} elementos (maximo {})",
+            len, MAX_RANGE_LEN
+        )));
+    }
+    Ok(Value::List((0..len).map(|i| Value::Int(start + i * step)).collect()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1654,158 +2971,1379 @@ mod tests {
     }
 
     #[test]
-    fn test_string_literal() {
-        let result = run_code("+http\nmain = \"Hello\"\n");
-        assert_eq!(result.unwrap(), Value::String("Hello".to_string()));
+    fn test_in_list() {
+        assert_eq!(run_code("main = 2 in [1, 2, 3]\n").unwrap(), Value::Bool(true));
+        assert_eq!(run_code("main = 5 in [1, 2, 3]\n").unwrap(), Value::Bool(false));
+        // Usa la igualdad de `==`: 1 == 1.0, y listas por estructura
+        assert_eq!(run_code("main = 1.0 in [1]\n").unwrap(), Value::Bool(true));
+        assert_eq!(run_code("main = [1] in [[1], [2]]\n").unwrap(), Value::Bool(true));
+        assert_eq!(run_code("main = 1 in []\n").unwrap(), Value::Bool(false));
     }
 
     #[test]
-    fn test_arithmetic() {
-        let result = run_code("+http\nmain = 2 + 3 * 4\n");
-        assert_eq!(result.unwrap(), Value::Int(14));
+    fn test_in_record_keys() {
+        assert_eq!(run_code("main = \"name\" in {name: \"a\", age: 1}\n").unwrap(), Value::Bool(true));
+        assert_eq!(run_code("main = \"email\" in {name: \"a\"}\n").unwrap(), Value::Bool(false));
+        // Solo claves, no valores
+        assert_eq!(run_code("main = \"a\" in {name: \"a\"}\n").unwrap(), Value::Bool(false));
+
+        let err = run_code("main = 1 in {name: \"a\"}\n").unwrap_err();
+        assert_eq!(err.message, "in con un record requiere un string a la izquierda, recibio int");
     }
 
     #[test]
-    fn test_function_call() {
-        let result = run_code("+http\ndouble(x) = x * 2\nmain = double(21)\n");
-        assert_eq!(result.unwrap(), Value::Int(42));
+    fn test_in_string() {
+        assert_eq!(run_code("main = \"ell\" in \"hello\"\n").unwrap(), Value::Bool(true));
+        assert_eq!(run_code("main = \"xyz\" in \"hello\"\n").unwrap(), Value::Bool(false));
+        assert_eq!(run_code("main = \"\" in \"hello\"\n").unwrap(), Value::Bool(true));
+        assert!(run_code("main = 1 in \"123\"\n").is_err());
     }
 
     #[test]
-    fn test_string_interpolation() {
-        let result = run_code("+http\ngreeting(name) = \"Hello {name}!\"\nmain = greeting(\"AURA\")\n");
-        // La interpolación no funciona aún porque name no está en scope al evaluar el string
-        // Por ahora verificamos que no crashee
-        assert!(result.is_ok());
+    fn test_in_requires_container() {
+        let err = run_code("main = 1 in 2\n").unwrap_err();
+        assert_eq!(err.message, "in requiere una lista, un record o un string a la derecha, recibio int");
     }
 
     #[test]
-    fn test_goals_stored() {
-        let source = r#"+http
-goal "fetch user data"
-goal "return formatted profile"
-main = 42
-"#;
-        let tokens = tokenize(source).expect("Tokenize failed");
-        let program = parse(tokens).expect("Parse failed");
-        let mut vm = VM::new();
-        vm.load(&program);
+    fn test_list_concat() {
+        let result = run_code("main = [1, 2] ++ [3, 4]\n").unwrap();
+        assert_eq!(result, Value::List(vec![Value::Int(1), Value::Int(2), Value::Int(3), Value::Int(4)]));
+
+        // Los operandos no cambian
+        let source = "main = : a = [1]; b = a ++ [2]; [len(a), len(b)]\n";
+        assert_eq!(run_code(source).unwrap(), Value::List(vec![Value::Int(1), Value::Int(2)]));
+        assert_eq!(run_code("main = [] ++ []\n").unwrap(), Value::List(vec![]));
+    }
 
-        let goals = vm.get_goal_descriptions();
-        assert_eq!(goals.len(), 2);
-        assert_eq!(goals[0], "fetch user data");
-        assert_eq!(goals[1], "return formatted profile");
+    #[test]
+    fn test_list_concat_requires_two_lists() {
+        let err = run_code("main = [1, 2] ++ 3\n").unwrap_err();
+        assert!(err.message.contains("++ con una lista requiere otra lista, recibio int"), "{}", err.message);
+        assert!(run_code("main = \"a\" ++ [1]\n").is_err());
     }
 
     #[test]
-    fn test_goals_dont_affect_execution() {
-        let source = r#"+http
-goal "this is just metadata"
-double(x) = x * 2
-main = double(21)
-"#;
-        let result = run_code(source);
-        assert_eq!(result.unwrap(), Value::Int(42));
+    fn test_infinite_recursion_is_a_runtime_error() {
+        let result = run_code("loop(n) = loop(n + 1)\nmain = loop(0)\n");
+        assert_eq!(result.unwrap_err().message, "stack overflow: recursion limit exceeded");
     }
 
     #[test]
-    fn test_goals_reset() {
-        let source = r#"+http
-goal "test goal"
-main = 1
-"#;
-        let tokens = tokenize(source).expect("Tokenize failed");
-        let program = parse(tokens).expect("Parse failed");
-        let mut vm = VM::new();
+    fn test_mutual_recursion_hits_limit() {
+        let source = "ping(n) = pong(n)\npong(n) = ping(n)\nmain = ping(1)\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        let mut vm = VM::new().with_max_call_depth(50);
         vm.load(&program);
+        assert!(vm.run().unwrap_err().message.contains("recursion limit"));
 
-        assert_eq!(vm.get_goal_descriptions().len(), 1);
-        vm.reset();
-        assert_eq!(vm.get_goal_descriptions().len(), 0);
+        // El contador vuelve a cero: la VM sigue usable
+        assert_eq!(vm.call_depth, 0);
     }
 
     #[test]
-    fn test_expect_passes() {
-        let source = r#"+http
-main = : x = 5; expect x > 0; x
-"#;
-        let result = run_code(source);
-        assert_eq!(result.unwrap(), Value::Int(5));
+    fn test_recursion_within_limit() {
+        // Mas profundo de lo que entra en el stack de un thread de test
+        let source = "count(n) = if n == 0 0 else 1 + count(n - 1)\nmain = count(990)\n";
+        assert_eq!(run_code(source).unwrap(), Value::Int(990));
     }
 
     #[test]
-    fn test_expect_fails_but_continues() {
-        let source = r#"+http
-main = : x = -3; expect x > 0; x
-"#;
-        let tokens = tokenize(source).expect("Tokenize failed");
-        let program = parse(tokens).expect("Parse failed");
+    fn test_run_function() {
+        let source = "add(a, b) = a + b\ngreet(name) = \"Hola {name}\"\nlog(level, ...parts) = [level, len(parts)]\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
         let mut vm = VM::new();
         vm.load(&program);
 
-        let result = vm.run();
-        // Program should continue executing and return the value
-        assert_eq!(result.unwrap(), Value::Int(-3));
-        // But there should be a failed expectation
-        assert!(vm.has_failed_expectations());
-        assert_eq!(vm.get_failed_expectations().len(), 1);
+        assert_eq!(vm.run_function("add", vec![Value::Int(2), Value::Int(40)]).unwrap(), Value::Int(42));
+        assert_eq!(
+            vm.run_function("greet", vec![Value::String("Ana".to_string())]).unwrap(),
+            Value::String("Hola Ana".to_string())
+        );
+        assert_eq!(
+            vm.run_function("log", vec![Value::String("info".to_string()), Value::Int(1), Value::Int(2)]).unwrap(),
+            Value::List(vec![Value::String("info".to_string()), Value::Int(2)])
+        );
+
+        let err = vm.run_function("add", vec![Value::Int(1)]).unwrap_err();
+        assert_eq!(err.message, "add espera 2 argumentos, recibio 1");
+        let err = vm.run_function("add", vec![Value::Int(1), Value::Int(2), Value::Int(3)]).unwrap_err();
+        assert_eq!(err.message, "add espera 2 argumentos, recibio 3");
+        let err = vm.run_function("log", vec![]).unwrap_err();
+        assert_eq!(err.message, "log espera al menos 1 argumentos, recibio 0");
+        let err = vm.run_function("missing", vec![]).unwrap_err();
+        assert_eq!(err.message, "Función no encontrada: missing");
     }
 
     #[test]
-    fn test_expect_with_message() {
-        let source = r#"+http
-main = : x = -1; expect x > 0 "x should be positive"; x
-"#;
-        let tokens = tokenize(source).expect("Tokenize failed");
-        let program = parse(tokens).expect("Parse failed");
+    fn test_env_allowlist() {
+        unsafe {
+            std::env::set_var("AURA_TEST_ALLOWED_KEY", "si");
+            std::env::set_var("AURA_TEST_SECRET_KEY", "secreto");
+        }
+        let source = "+env(AURA_TEST_ALLOWED_KEY)\nmain = [env.get(\"AURA_TEST_ALLOWED_KEY\"), env.get(\"AURA_TEST_SECRET_KEY\"), env.get(\"AURA_TEST_SECRET_KEY\", \"def\"), env.exists(\"AURA_TEST_SECRET_KEY\")]\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
         let mut vm = VM::new();
         vm.load(&program);
+        assert_eq!(
+            vm.run().unwrap(),
+            Value::List(vec![
+                Value::String("si".to_string()),
+                Value::Nil,
+                Value::String("def".to_string()),
+                Value::Bool(false),
+            ])
+        );
+        let warnings = vm.take_warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("AURA_TEST_SECRET_KEY"), "{:?}", warnings);
+
+        // `+env` sin lista sigue leyendo cualquier clave
+        let source = "+env\nmain = env.get(\"AURA_TEST_SECRET_KEY\")\n";
+        assert_eq!(run_code(source).unwrap(), Value::String("secreto".to_string()));
+
+        // Solo argumentos con valor: ninguna clave declarada, nada se lee
+        let source = "+env(timeout: 1)\nmain = env.get(\"AURA_TEST_SECRET_KEY\")\n";
+        assert_eq!(run_code(source).unwrap(), Value::Nil);
+    }
 
-        let result = vm.run();
-        assert_eq!(result.unwrap(), Value::Int(-1));
+    #[test]
+    fn test_deep_call_chain() {
+        // Cada nivel abre un scope y busca una funcion y una variable globales
+        let source = "step(x) = x + offset\ncount(n) = if n == 0 0 else step(count(n - 1))\nmain = count(2000)\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        let mut vm = VM::new().with_max_call_depth(5_000);
+        vm.load(&program);
+        vm.define_var("offset".to_string(), Value::Int(2));
 
-        let failures = vm.get_failed_expectations();
-        assert_eq!(failures.len(), 1);
-        assert_eq!(failures[0].message, Some("x should be positive".to_string()));
+        assert_eq!(vm.run().unwrap(), Value::Int(4_000));
+
+        // Todos los scopes se cerraron
+        assert_eq!(vm.env.scopes.len(), 1);
     }
 
     #[test]
-    fn test_multiple_expects() {
-        let source = r#"+http
-main = : a = 5; b = -2; expect a > 0; expect b > 0; a + b
-"#;
-        let tokens = tokenize(source).expect("Tokenize failed");
-        let program = parse(tokens).expect("Parse failed");
+    fn test_callee_sees_caller_locals() {
+        // La busqueda es dinamica: `peek` ve el `secret` de `outer`
+        let source = "peek() = secret\nouter() = : secret = 1; peek()\nmain = outer()\n";
+        assert_eq!(run_code(source).unwrap(), Value::Int(1));
+    }
+
+    fn run_with_budget(source: &str, max_steps: u64) -> (Result<Value, RuntimeError>, u64) {
+        let program = parse(tokenize(source).unwrap()).unwrap();
         let mut vm = VM::new();
         vm.load(&program);
+        let result = vm.run_with_budget(max_steps);
+        (result, vm.steps_executed())
+    }
 
-        let result = vm.run();
-        // Program continues and returns the sum
-        assert_eq!(result.unwrap(), Value::Int(3));
-        // One expect passed, one failed
-        assert_eq!(vm.get_failed_expectations().len(), 1);
+    /// `main = for i in [0, 1, ..] i * 2`, armado a mano (el parser no tiene `for`)
+    fn loop_main(iterations: i64) -> FuncDef {
+        let double = Expr::BinaryOp {
+            op: crate::parser::BinaryOp::Mul,
+            left: Box::new(Expr::Ident("i".to_string())),
+            right: Box::new(Expr::Int(2)),
+        };
+        FuncDef {
+            name: "main".to_string(),
+            has_effect: false,
+            params: Vec::new(),
+            return_type: None,
+            body: Expr::For {
+                var: "i".to_string(),
+                iter: Box::new(Expr::List((0..iterations).map(Expr::Int).collect())),
+                body: Box::new(double),
+            },
+            span: crate::lexer::Span::new(0, 0),
+            self_heal: None,
+        }
     }
 
     #[test]
-    fn test_expect_clears_on_reset() {
-        let source = r#"+http
-main = : expect false; 42
-"#;
-        let tokens = tokenize(source).expect("Tokenize failed");
-        let program = parse(tokens).expect("Parse failed");
+    fn test_long_loop_exceeds_budget() {
         let mut vm = VM::new();
-        vm.load(&program);
-        vm.run().unwrap();
+        vm.define_function(loop_main(100_000));
 
-        assert!(vm.has_failed_expectations());
-        vm.reset();
-        assert!(!vm.has_failed_expectations());
+        let error = vm.run_with_budget(150_000).unwrap_err();
+        assert_eq!(error.message, "execution budget exceeded");
+        // Armar la lista cuesta 100_001 pasos; el resto se consume en el cuerpo
+        assert_eq!(vm.steps_executed(), 150_001);
     }
 
     #[test]
-    fn test_invariants_stored() {
-        let source = r#"+http
-invariant api_url != "https://prod.example.com"
+    fn test_budget_counts_pipe_steps_and_calls() {
+        let source = "inc(x) = x + 1\nmain = 1 |> inc |> inc |> inc\n";
+        let (result, steps) = run_with_budget(source, 1000);
+        assert_eq!(result.unwrap(), Value::Int(4));
+
+        // Con un presupuesto justo por debajo de lo necesario falla
+        let (result, _) = run_with_budget(source, steps - 1);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_run_without_budget_is_unlimited() {
+        let mut vm = VM::new();
+        vm.define_function(loop_main(5000));
+        assert_eq!(vm.run().unwrap(), Value::Int(9998));
+        assert_eq!(vm.steps_executed(), 0);
+    }
+
+    /// `for a in [..n] for b in [..n] for c in [..n] a`, n^3 iteraciones
+    fn nested_loops(n: i64) -> Expr {
+        let list = || Box::new(Expr::List((0..n).map(Expr::Int).collect()));
+        let innermost = Expr::For { var: "c".to_string(), iter: list(), body: Box::new(Expr::Ident("a".to_string())) };
+        let middle = Expr::For { var: "b".to_string(), iter: list(), body: Box::new(innermost) };
+        Expr::For { var: "a".to_string(), iter: list(), body: Box::new(middle) }
+    }
+
+    #[test]
+    fn test_eval_with_timeout_cancels_long_computation() {
+        let mut vm = VM::new();
+        let started = std::time::Instant::now();
+
+        let error = vm.eval_with_timeout(&nested_loops(1000), Duration::from_millis(100)).unwrap_err();
+        assert_eq!(error.message, "evaluation timed out after 100ms");
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // El flag se limpia: la VM sigue usable
+        assert!(!vm.cancel_handle().is_cancelled());
+        assert_eq!(vm.eval(&Expr::Int(7)).unwrap(), Value::Int(7));
+    }
+
+    #[test]
+    fn test_eval_with_timeout_returns_fast_results() {
+        let mut vm = VM::new();
+        let result = vm.eval_with_timeout(&nested_loops(3), Duration::from_secs(30)).unwrap();
+        assert_eq!(result, Value::Int(2));
+    }
+
+    #[test]
+    fn test_cancel_handle_from_another_thread() {
+        let mut vm = VM::new();
+        let handle = vm.cancel_handle();
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            handle.cancel();
+        });
+
+        let error = vm.eval(&nested_loops(1000)).unwrap_err();
+        assert_eq!(error.message, "execution cancelled");
+        canceller.join().unwrap();
+
+        // Sigue cancelada hasta el reset
+        assert!(vm.eval(&Expr::Int(1)).is_err());
+        vm.reset();
+        assert_eq!(vm.eval(&Expr::Int(1)).unwrap(), Value::Int(1));
+    }
+
+    #[test]
+    fn test_raw_string_skips_interpolation() {
+        let source = "name = \"Ana\"\nmain = r\"C:\\path\\{name}\"\n";
+        assert_eq!(run_code(source).unwrap(), Value::String(r"C:\path\{name}".to_string()));
+    }
+
+    #[test]
+    fn test_triple_quoted_string_interpolates() {
+        let source = "greet(name) = \"\"\"\nHola {name},\n  dijo \"bienvenida\"\"\"\"\nmain = greet(\"Ana\")\n";
+        assert_eq!(run_code(source).unwrap(), Value::String("Hola Ana,\n  dijo \"bienvenida\"".to_string()));
+    }
+
+    #[test]
+    fn test_numeric_literal_forms() {
+        assert_eq!(run_code("main = 1_000 == 1000\n").unwrap(), Value::Bool(true));
+        assert_eq!(run_code("main = 0xFF + 0b1 + 0o10\n").unwrap(), Value::Int(264));
+    }
+
+    #[test]
+    fn test_auth_jwt_round_trip() {
+        let source = r#"+auth
+
+token = auth.jwt_sign({sub: "alice"}, "clave")
+main = auth.jwt_verify(token(), "clave").sub
+"#;
+        assert_eq!(run_code(source).unwrap(), Value::String("alice".to_string()));
+
+        let source = "+auth\n\nmain = auth.jwt_verify(auth.jwt_sign({sub: 1}, \"a\"), \"b\")\n";
+        assert_eq!(run_code(source).unwrap(), Value::Nil);
+    }
+
+    #[test]
+    fn test_bytes_values() {
+        assert_eq!(run_code("main = bytes(\"hi\")\n").unwrap(), Value::Bytes(b"hi".to_vec()));
+        assert_eq!(run_code("main = bytes([0, 255])\n").unwrap(), Value::Bytes(vec![0, 255]));
+        assert_eq!(run_code("main = len(bytes(\"hola\"))\n").unwrap(), Value::Int(4));
+        assert_eq!(run_code("main = type(bytes(\"x\"))\n").unwrap(), Value::String("bytes".to_string()));
+        assert_eq!(run_code("main = bytes(\"a\") == bytes([97])\n").unwrap(), Value::Bool(true));
+        assert_eq!(run_code("main = str(bytes([1, 171]))\n").unwrap(), Value::String("<bytes 01ab>".to_string()));
+        assert!(run_code("main = bytes([256])\n").unwrap_err().message.contains("no es un byte"));
+    }
+
+    #[test]
+    fn test_parse_scalar() {
+        assert_eq!(Value::parse_scalar("3"), Value::Int(3));
+        assert_eq!(Value::parse_scalar("-12"), Value::Int(-12));
+        assert_eq!(Value::parse_scalar("2.5"), Value::Float(2.5));
+        assert_eq!(Value::parse_scalar("true"), Value::Bool(true));
+        assert_eq!(Value::parse_scalar("false"), Value::Bool(false));
+        assert_eq!(Value::parse_scalar("alice"), Value::String("alice".to_string()));
+        assert_eq!(Value::parse_scalar("True"), Value::String("True".to_string()));
+        assert_eq!(Value::parse_scalar(""), Value::String(String::new()));
+    }
+
+    #[test]
+    fn test_parse_scalar_only_plain_decimals_are_float() {
+        assert_eq!(Value::parse_scalar("-0.5"), Value::Float(-0.5));
+        assert_eq!(Value::parse_scalar("+1.25"), Value::Float(1.25));
+        for text in ["nan", "NaN", "inf", "-inf", "infinity", "Infinity", "1e5", "2.5E3", "1.", ".5", "1.2.3"] {
+            assert_eq!(Value::parse_scalar(text), Value::String(text.to_string()), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_value_json_roundtrip() {
+        let json = serde_json::json!({
+            "name": "Ana",
+            "age": 31,
+            "score": 9.5,
+            "active": true,
+            "manager": null,
+            "tags": ["admin", "ops"],
+            "projects": [
+                {"id": 1, "members": [{"name": "Luis", "roles": []}]},
+                {"id": 2, "members": []}
+            ]
+        });
+
+        let value = Value::from_json(&json);
+        let Value::Record(fields) = &value else { panic!("Expected record, got {:?}", value) };
+        assert_eq!(fields["age"], Value::Int(31));
+        assert_eq!(fields["score"], Value::Float(9.5));
+        assert_eq!(fields["manager"], Value::Nil);
+        let Value::List(projects) = &fields["projects"] else { panic!("Expected list") };
+        assert_eq!(projects.len(), 2);
+
+        assert_eq!(value.to_json(), json);
+        assert_eq!(Value::from_json(&value.to_json()), value);
+    }
+
+    #[test]
+    fn test_from_json_out_of_range_int_is_float() {
+        let big = serde_json::json!(u64::MAX);
+        assert_eq!(Value::from_json(&big), Value::Float(u64::MAX as f64));
+        assert_eq!(Value::from_json(&serde_json::json!(i64::MIN)), Value::Int(i64::MIN));
+        assert_eq!(json_parse("18446744073709551616").unwrap(), Value::Float(18446744073709551616.0));
+    }
+
+    #[test]
+    fn test_value_to_json_placeholders() {
+        let native = Value::Native { type_id: "db:sqlite".to_string(), handle: 3 };
+        assert_eq!(native.to_json(), serde_json::json!({"native": "db:sqlite", "handle": 3}));
+        assert_eq!(Value::Function("double".to_string()).to_json(), serde_json::json!("<fn double>"));
+        assert_eq!(Value::Float(f64::NAN).to_json(), serde_json::Value::Null);
+
+        // Anidados tambien se convierten, sin fallar
+        let list = Value::List(vec![Value::Bytes(vec![1, 2]), native]);
+        assert_eq!(list.to_json(), serde_json::json!(["AQI=", {"native": "db:sqlite", "handle": 3}]));
+    }
+
+    #[test]
+    fn test_bytes_to_json_is_base64() {
+        let value = Value::Bytes(b"Hello".to_vec());
+        assert_eq!(json_stringify(&value).unwrap(), "\"SGVsbG8=\"");
+        assert_eq!(value.to_json(), serde_json::json!("SGVsbG8="));
+
+        // El base64 del JSON vuelve a dar los mismos bytes
+        let Value::String(encoded) = json_parse(&json_stringify(&value).unwrap()).unwrap() else {
+            panic!("Expected string");
+        };
+        use base64::Engine;
+        let decoded = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+        assert_eq!(Value::Bytes(decoded), value);
+    }
+
+    #[test]
+    fn test_crypto_methods() {
+        let source = "+crypto\nmain = len(crypto.random_bytes(16))\n";
+        assert_eq!(run_code(source).unwrap(), Value::Int(16));
+        let source = "+crypto\nmain = type(crypto.random_bytes(4))\n";
+        assert_eq!(run_code(source).unwrap(), Value::String("bytes".to_string()));
+
+        let source = "+crypto\nmain = crypto.sha256(\"abc\") == crypto.sha256(bytes(\"abc\"))\n";
+        assert_eq!(run_code(source).unwrap(), Value::Bool(true));
+
+        let err = run_code("main = crypto.random_bytes(4)\n").unwrap_err();
+        assert_eq!(err.message, "crypto.random_bytes requiere declarar +crypto");
+    }
+
+    #[test]
+    fn test_auth_requires_capability() {
+        let err = run_code("main = auth.jwt_sign({sub: 1}, \"clave\")\n").unwrap_err();
+        assert_eq!(err.message, "auth.jwt_sign requiere declarar +auth");
+    }
+
+    #[test]
+    fn test_http_options_record_sets_timeout() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        // Acepta y no responde
+        std::thread::spawn(move || {
+            let _open: Vec<_> = listener.incoming().collect();
+        });
+
+        let source = format!("+http\n\nmain = http.get(\"http://{}/\", nil, nil, {{timeout_ms: 150}})\n", addr);
+        let err = run_code(&source).unwrap_err();
+        assert!(err.message.contains("timeout"), "{}", err.message);
+        assert!(err.message.contains("150ms"));
+    }
+
+    /// Headers (en minuscula) y body de un request capturado
+    type CapturedRequest = (HashMap<String, String>, String);
+
+    /// Atiende un solo request HTTP y lo retorna al hacer join
+    fn capture_one_request() -> (std::net::SocketAddr, std::thread::JoinHandle<CapturedRequest>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut headers = HashMap::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+                }
+            }
+            let length: usize = headers.get("content-length").map_or(0, |l| l.parse().unwrap());
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok").unwrap();
+            (headers, String::from_utf8(body).unwrap())
+        });
+        (addr, handle)
+    }
+
+    #[test]
+    fn test_http_post_record_body_is_json() {
+        let (addr, server) = capture_one_request();
+        let source = format!("+http\n\nmain = http.post(\"http://{}/\", {{name: \"Ana\", tags: [1, 2]}}).status\n", addr);
+        assert_eq!(run_code(&source).unwrap(), Value::Int(200));
+
+        let (headers, body) = server.join().unwrap();
+        assert_eq!(headers.get("content-type").map(String::as_str), Some("application/json"));
+        let json: serde_json::Value = serde_json::from_str(&body).expect("body should be valid JSON");
+        assert_eq!(json, serde_json::json!({"name": "Ana", "tags": [1, 2]}));
+    }
+
+    #[test]
+    fn test_http_put_record_body_keeps_explicit_content_type() {
+        let (addr, server) = capture_one_request();
+        // Un record literal no admite claves con guion: los headers salen de json.parse
+        let source = format!(
+            "+http +json\n\nheaders = json.parse(\"{{\\\"content-type\\\": \\\"application/vnd.api+json\\\"}}\")\nmain = http.put(\"http://{}/\", {{ok: true}}, headers()).status\n",
+            addr
+        );
+        assert_eq!(run_code(&source).unwrap(), Value::Int(200));
+
+        let (headers, body) = server.join().unwrap();
+        assert_eq!(headers.get("content-type").map(String::as_str), Some("application/vnd.api+json"));
+        assert_eq!(body, r#"{"ok":true}"#);
+    }
+
+    /// Responde un solo request con `body` como JSON
+    fn respond_once(body: &'static str) -> std::net::SocketAddr {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        addr
+    }
+
+    #[test]
+    fn test_http_response_json_method() {
+        let addr = respond_once(r#"{"name": "Ana", "tags": ["a", "b"]}"#);
+        let source = format!("+http\n\nmain = : r = http.get(\"http://{}/\"); [r.status, r.json().name, r.json().tags]\n", addr);
+        assert_eq!(run_code(&source).unwrap(), Value::List(vec![
+            Value::Int(200),
+            Value::String("Ana".to_string()),
+            Value::List(vec![Value::String("a".to_string()), Value::String("b".to_string())]),
+        ]));
+    }
+
+    #[test]
+    fn test_http_status_is_an_int_in_aura() {
+        let addr = respond_once("{}");
+        let source = format!("+http\n\nmain = : r = http.get(\"http://{}/\"); [type(r.status), r.status >= 400, type(r.body)]\n", addr);
+        assert_eq!(run_code(&source).unwrap(), Value::List(vec![
+            Value::String("int".to_string()),
+            Value::Bool(false),
+            Value::String("string".to_string()),
+        ]));
+    }
+
+    #[test]
+    fn test_json_method_with_type_coerces_fields() {
+        let addr = respond_once(r#"[{"id": 1, "score": 3, "extra": true}, {"id": 2, "score": 4.5, "email": "b@x.com"}]"#);
+        let source = format!(r#"+http
+
+@User {{
+    id :i
+    score :f
+    email :s?
+}}
+
+main = http.get("http://{}/").json(User)
+"#, addr);
+        let Value::List(users) = run_code(&source).unwrap() else { panic!("Expected List") };
+        let Value::Record(first) = &users[0] else { panic!("Expected Record") };
+        assert_eq!(first.get("score"), Some(&Value::Float(3.0)));
+        assert_eq!(first.get("email"), Some(&Value::Nil));
+        assert_eq!(first.get("extra"), Some(&Value::Bool(true)));
+        let Value::Record(second) = &users[1] else { panic!("Expected Record") };
+        assert_eq!(second.get("email"), Some(&Value::String("b@x.com".to_string())));
+    }
+
+    #[test]
+    fn test_json_method_on_string_reports_type_mismatches() {
+        let types = "@User {\n    id :i\n    name :s\n}\n\n";
+
+        let source = format!("{}main = \"{{\\\"id\\\": 1}}\".json(User)\n", types);
+        assert_eq!(run_code(&source).unwrap_err().message, "json(): falta el campo 'name' de User");
+
+        let source = format!("{}main = \"{{\\\"id\\\": \\\"1\\\", \\\"name\\\": \\\"x\\\"}}\".json(User)\n", types);
+        let err = run_code(&source).unwrap_err();
+        assert!(err.message.starts_with("json(): el campo 'id' de User espera :i"), "{}", err.message);
+
+        let source = format!("{}main = \"[1, 2]\".json()\n", types);
+        assert_eq!(run_code(&source).unwrap(), Value::List(vec![Value::Int(1), Value::Int(2)]));
+    }
+
+    #[test]
+    fn test_env_set_and_get_from_source() {
+        let source = r#"+env
+
+main = : env.set("AURA_VM_TEST_ENV", "desde aura"); [env.get("AURA_VM_TEST_ENV"), env.exists("AURA_VM_TEST_ENV")]
+"#;
+        assert_eq!(run_code(source).unwrap(), Value::List(vec![
+            Value::String("desde aura".to_string()),
+            Value::Bool(true),
+        ]));
+    }
+
+    #[test]
+    fn test_env_missing_key_and_default() {
+        let source = r#"+env
+
+main = [env.get("AURA_VM_TEST_MISSING"), env.get_or("AURA_VM_TEST_MISSING", 8080), env.exists("AURA_VM_TEST_MISSING")]
+"#;
+        assert_eq!(run_code(source).unwrap(), Value::List(vec![
+            Value::Nil,
+            Value::Int(8080),
+            Value::Bool(false),
+        ]));
+    }
+
+    #[test]
+    fn test_env_requires_capability() {
+        let err = run_code("main = env.get(\"HOME\")\n").unwrap_err();
+        assert_eq!(err.message, "env.get requiere declarar +env");
+    }
+
+    #[test]
+    fn test_email_send_with_mock_transport() {
+        // SAFETY: ningun otro test cambia esta variable
+        unsafe { std::env::set_var(crate::caps::email::TRANSPORT_VAR, "mock"); }
+
+        let source = "+email\n\nmain = email.send(\"ana@example.com\", \"Reporte vm\", \"listo\")\n";
+        let Value::Record(result) = run_code(source).unwrap() else { panic!("Expected Record") };
+        assert_eq!(result.get("transport"), Some(&Value::String("mock".to_string())));
+
+        let sent = crate::caps::sent_messages();
+        let message = sent.iter()
+            .find(|m| Some(&Value::String(m.message_id.clone())) == result.get("message_id"))
+            .expect("message captured");
+        assert_eq!(message.to, "ana@example.com");
+        assert_eq!(message.subject, "Reporte vm");
+        assert_eq!(message.body, "listo");
+    }
+
+    #[test]
+    fn test_email_requires_capability() {
+        let err = run_code("main = email.send(\"a@b.c\", \"s\", \"b\")\n").unwrap_err();
+        assert_eq!(err.message, "email.send requiere declarar +email");
+    }
+
+    #[test]
+    fn test_observe_records_value_and_target() {
+        let source = "main = : response = {status: 503}; code = observe response.status; observe code where code < 400; code\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        let mut vm = VM::new();
+        vm.load(&program);
+
+        assert_eq!(vm.run().unwrap(), Value::Int(503));
+        let observations = vm.take_observations();
+        assert_eq!(observations.len(), 1, "the where condition filters the second observe");
+        let ObservationEvent::Observed { target, value } = &observations[0] else { panic!("Expected Observed") };
+        assert_eq!(target, "response.status");
+        assert_eq!(value, &Value::Int(503));
+
+        assert!(vm.take_observations().is_empty());
+    }
+
+    #[test]
+    fn test_observe_where_binds_last_segment() {
+        let source = "main = : response = {status: 503}; observe response.status where status >= 400\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        let mut vm = VM::new();
+        vm.load(&program);
+
+        assert_eq!(vm.run().unwrap(), Value::Int(503));
+        assert_eq!(vm.take_observations().len(), 1);
+    }
+
+    #[test]
+    fn test_match_constructor_binds_value() {
+        let source = "unwrap(r) = r ? Ok(v) -> v | Err(e) -> \"fallo: \" ++ e\nmain = [unwrap(Ok(42)), unwrap(Err(\"timeout\"))]\n";
+        assert_eq!(
+            run_code(source).unwrap(),
+            Value::List(vec![Value::Int(42), Value::String("fallo: timeout".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_match_literals_and_wildcard() {
+        let source = "name(n) = n ? 0 -> \"cero\" | 1 -> \"uno\" | -1 -> \"menos uno\" | _ -> \"otro\"\nmain = [name(0), name(1), name(-1), name(7)]\n";
+        let names = ["cero", "uno", "menos uno", "otro"].map(|s| Value::String(s.to_string()));
+        assert_eq!(run_code(source).unwrap(), Value::List(names.to_vec()));
+
+        // Un identificador liga el valor completo
+        assert_eq!(run_code("main = 5 ? x -> x * 2\n").unwrap(), Value::Int(10));
+    }
+
+    #[test]
+    fn test_match_bindings_do_not_leak() {
+        let source = "main = [Ok(1) ? Ok(v) -> v, v]\n";
+        let error = run_code(source).unwrap_err();
+        assert!(error.message.contains("Variable no definida: v"), "{}", error.message);
+    }
+
+    #[test]
+    fn test_match_guard_falls_through() {
+        let source = "sign(r) = r ? Ok(n) where n > 0 -> \"positivo\" | Ok(n) where n < 0 -> \"negativo\" | Ok(_) -> \"cero\" | _ -> \"error\"\nmain = [sign(Ok(3)), sign(Ok(-2)), sign(Ok(0)), sign(Err(\"x\"))]\n";
+        let expected = ["positivo", "negativo", "cero", "error"].map(|s| Value::String(s.to_string()));
+        assert_eq!(run_code(source).unwrap(), Value::List(expected.to_vec()));
+    }
+
+    #[test]
+    fn test_match_guard_sees_outer_scope() {
+        let source = "over(x, limit) = x ? n where n > limit -> n - limit | _ -> 0\nmain = [over(10, 4), over(2, 4)]\n";
+        assert_eq!(run_code(source).unwrap(), Value::List(vec![Value::Int(6), Value::Int(0)]));
+    }
+
+    #[test]
+    fn test_match_without_matching_arm_fails() {
+        let error = run_code("main = Err(1) ? Ok(v) -> v\n").unwrap_err();
+        assert!(error.message.contains("Ningun patron"), "{}", error.message);
+    }
+
+    const SUM_VARIADIC: &str = "total(xs) = if len(xs) == 0 0 else first(xs) + total(tail(xs))\nsum(...nums) = total(nums)\n";
+
+    #[test]
+    fn test_rest_param_collects_arguments() {
+        let cases = [("sum()", 0), ("sum(5)", 5), ("sum(1, 2, 3)", 6), ("sum(1, 2, 3, 4, 5, 6, 7)", 28)];
+        for (call, expected) in cases {
+            let source = format!("{}main = {}\n", SUM_VARIADIC, call);
+            assert_eq!(run_code(&source).unwrap(), Value::Int(expected), "{}", call);
+        }
+    }
+
+    #[test]
+    fn test_rest_param_after_fixed_params() {
+        let source = "tag(label, ...items) = {label: label, items: items}\nmain = tag(\"x\", 1, 2)\n";
+        let Value::Record(fields) = run_code(source).unwrap() else { panic!("Expected record") };
+        assert_eq!(fields["label"], Value::String("x".to_string()));
+        assert_eq!(fields["items"], Value::List(vec![Value::Int(1), Value::Int(2)]));
+
+        let source = "tag(label, ...items) = items\nmain = tag(\"x\")\n";
+        assert_eq!(run_code(source).unwrap(), Value::List(vec![]));
+
+        // Los parametros fijos siguen siendo obligatorios
+        let source = "tag(label, ...items) = items\nmain = tag()\n";
+        let error = run_code(source).unwrap_err();
+        assert!(error.message.contains("al menos 1"), "{}", error.message);
+    }
+
+    #[test]
+    fn test_nested_record_equality() {
+        let source = "main = {user: {name: \"Ana\", tags: [\"a\", \"b\"]}, id: 1} == {id: 1.0, user: {tags: [\"a\", \"b\"], name: \"Ana\"}}\n";
+        assert_eq!(run_code(source).unwrap(), Value::Bool(true));
+
+        let source = "main = {user: {name: \"Ana\", tags: [\"a\"]}} != {user: {name: \"Ana\", tags: [\"b\"]}}\n";
+        assert_eq!(run_code(source).unwrap(), Value::Bool(true));
+
+        // Un campo de mas tampoco es igual
+        assert_eq!(run_code("main = {a: 1} == {a: 1, b: 2}\n").unwrap(), Value::Bool(false));
+    }
+
+    #[test]
+    fn test_list_and_mixed_type_equality() {
+        assert_eq!(run_code("main = [1, [2, 3]] == [1, [2, 3]]\n").unwrap(), Value::Bool(true));
+        assert_eq!(run_code("main = [1, 2] == [2, 1]\n").unwrap(), Value::Bool(false));
+        assert_eq!(run_code("main = 1 == \"1\"\n").unwrap(), Value::Bool(false));
+        assert_eq!(run_code("main = nil == nil\n").unwrap(), Value::Bool(true));
+        assert_eq!(run_code("main = contains([[1], [2.0]], [2])\n").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_nan_is_never_equal() {
+        let nan = Value::Float(f64::NAN);
+        assert!(!nan.deep_equal(&nan));
+        assert!(!Value::List(vec![nan.clone()]).deep_equal(&Value::List(vec![nan.clone()])));
+        assert!(Value::Float(0.0).deep_equal(&Value::Float(-0.0)));
+
+        let vm = VM::new();
+        let ne = vm.eval_binary_op(&nan, &BinaryOp::NotEq, &nan).unwrap();
+        assert_eq!(ne, Value::Bool(true));
+    }
+
+    #[test]
+    fn test_native_display() {
+        let conn = Value::Native { type_id: "db:sqlite".to_string(), handle: 3 };
+        assert_eq!(conn.to_string(), "<db:sqlite#3>");
+        assert_eq!(Value::List(vec![conn]).to_string(), "[<db:sqlite#3>]");
+    }
+
+    #[test]
+    fn test_list_types_sorted() {
+        let tokens = tokenize("@User {\n  id:i\n}\n@Post {\n  title:s\n}\nmain = 1\n").unwrap();
+        let program = parse(tokens).unwrap();
+        let mut vm = VM::new();
+        vm.load(&program);
+
+        assert_eq!(vm.list_types(), vec!["Post".to_string(), "User".to_string()]);
+        assert_eq!(vm.get_type("User").unwrap().fields[0].name, "id");
+        assert!(vm.get_type("Comment").is_none());
+    }
+
+    #[test]
+    fn test_function_signature() {
+        let tokens = tokenize("area(width, height) = width * height\nsend!(to, ...parts) = to\nmain = 1\n").unwrap();
+        let program = parse(tokens).unwrap();
+        let mut vm = VM::new();
+        vm.load(&program);
+
+        assert_eq!(vm.get_function_signature("area").unwrap(), "area(width, height) - efecto: no");
+        assert_eq!(vm.get_function_signature("send").unwrap(), "send!(to, ...parts) - efecto: si");
+        assert_eq!(vm.get_function_signature("main").unwrap(), "main() - efecto: no");
+        assert!(vm.get_function_signature("nope").is_none());
+    }
+
+    #[test]
+    fn test_list_types_empty_and_parent_scope() {
+        let vm = VM::new();
+        assert!(vm.list_types().is_empty());
+
+        let mut env = Environment::new();
+        env.define_type(TypeDef {
+            name: "User".to_string(),
+            fields: vec![],
+            annotations: vec![],
+            span: crate::lexer::Span::new(0, 0),
+        });
+        env.push_scope();
+        env.push_scope();
+        assert_eq!(env.list_types(), vec!["User".to_string()]);
+    }
+
+    #[test]
+    fn test_string_literal() {
+        let result = run_code("+http\nmain = \"Hello\"\n");
+        assert_eq!(result.unwrap(), Value::String("Hello".to_string()));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let result = run_code("+http\nmain = 2 + 3 * 4\n");
+        assert_eq!(result.unwrap(), Value::Int(14));
+    }
+
+    #[test]
+    fn test_integer_overflow_is_runtime_error() {
+        let overflow = |expr: &str| {
+            let err = run_code(&format!("main = : big = 9223372036854775807; {}\n", expr)).unwrap_err();
+            assert_eq!(err.message, "integer overflow", "{}", expr);
+        };
+        overflow("big * 2");
+        overflow("big + 1");
+        overflow("(0 - big) - 2");
+        overflow("(0 - big - 1) / (0 - 1)");
+        overflow("-(0 - big - 1)");
+
+        // Cerca del limite pero sin pasarse sigue funcionando
+        let result = run_code("main = : big = 9223372036854775807; big - 1 + 1\n");
+        assert_eq!(result.unwrap(), Value::Int(i64::MAX));
+    }
+
+    #[test]
+    fn test_builtin_integer_overflow() {
+        let err = run_code("main = abs(-9223372036854775807 - 1)\n").unwrap_err();
+        assert_eq!(err.message, "integer overflow");
+        assert_eq!(run_code("main = abs(-9223372036854775807)\n").unwrap(), Value::Int(i64::MAX));
+
+        let err = run_code("+math\nmain = math.pow(2, 100)\n").unwrap_err();
+        assert_eq!(err.message, "integer overflow");
+        assert_eq!(run_code("+math\nmain = math.pow(2, 62)\n").unwrap(), Value::Int(1 << 62));
+
+        let err = run_code("+math\nmain = math.pow(2, -1)\n").unwrap_err();
+        assert!(err.message.contains("exponente entre 0"), "{}", err.message);
+        let err = run_code("+math\nmain = math.pow(1, 4294967296)\n").unwrap_err();
+        assert!(err.message.contains("recibio 4294967296"), "{}", err.message);
+        assert_eq!(run_code("+math\nmain = math.pow(2, -1.0)\n").unwrap(), Value::Float(0.5));
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        assert_eq!(run_code("main = 12 & 10\n").unwrap(), Value::Int(8));
+        assert_eq!(run_code("main = 12 | 10\n").unwrap(), Value::Int(14));
+        assert_eq!(run_code("main = 12 ^ 10\n").unwrap(), Value::Int(6));
+        assert_eq!(run_code("main = 1 << 4\n").unwrap(), Value::Int(16));
+        assert_eq!(run_code("main = 256 >> 4\n").unwrap(), Value::Int(16));
+        // `>>` es aritmetico: conserva el signo
+        assert_eq!(run_code("main = (0 - 16) >> 2\n").unwrap(), Value::Int(-4));
+        // Mascara de flags
+        assert_eq!(run_code("main = : flags = 5; (flags & 4) == 4\n").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_bitwise_errors() {
+        let err = run_code("main = 1 << (0 - 1)\n").unwrap_err();
+        assert!(err.message.contains("Desplazamiento invalido: -1"), "{}", err.message);
+        let err = run_code("main = 1 >> 64\n").unwrap_err();
+        assert!(err.message.contains("Desplazamiento invalido: 64"), "{}", err.message);
+        assert!(run_code("main = 1.5 & 1\n").is_err());
+        assert!(run_code("main = true | false\n").is_err());
+    }
+
+    #[test]
+    fn test_null_coalesce_chain() {
+        assert_eq!(run_code("main = nil ?? nil ?? 3\n").unwrap(), Value::Int(3));
+        assert_eq!(run_code("main = : u = {name: nil}; u.name ?? u?.nick ?? \"anon\"\n").unwrap(), Value::String("anon".to_string()));
+        // El lado derecho solo se evalua si el izquierdo es nil
+        assert_eq!(run_code("main = nil ?? 2 ?? 1 / 0\n").unwrap(), Value::Int(2));
+        assert_eq!(run_code("main = 1 ?? 1 / 0\n").unwrap(), Value::Int(1));
+        // false no es nil
+        assert_eq!(run_code("main = false ?? true\n").unwrap(), Value::Bool(false));
+    }
+
+    #[test]
+    fn test_null_coalesce_assign() {
+        assert_eq!(run_code("main = : x = nil; x ??= 5; x\n").unwrap(), Value::Int(5));
+        assert_eq!(run_code("main = : x = 1; x ??= 5; x\n").unwrap(), Value::Int(1));
+    }
+
+    #[test]
+    fn test_run_tests() {
+        let source = "#test ok: double(2) == 4\n#test mal: double(2) == 5\n#test cond: double(1) > 1\n#test error: 1 / 0\ndouble(x) = x * 2\nmain = 1\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        let tests: Vec<TestDef> = program.definitions.iter().filter_map(|d| match d {
+            Definition::TestDef(t) => Some(t.clone()),
+            _ => None,
+        }).collect();
+
+        let mut vm = VM::new();
+        vm.load(&program);
+        let outcomes = vm.run_tests(&tests);
+
+        let passed: Vec<bool> = outcomes.iter().map(|o| o.passed).collect();
+        assert_eq!(passed, vec![true, false, true, false]);
+        assert_eq!(outcomes[1].value, Some(Value::Int(4)));
+        assert_eq!(outcomes[1].expected, Some(Value::Int(5)));
+        assert!(outcomes[3].error.is_some());
+    }
+
+    #[test]
+    fn test_safe_navigation_call() {
+        // Receptor nil: la llamada no se hace y los argumentos no se evaluan
+        assert_eq!(run_code("main = : u = nil; u?.foo()\n").unwrap(), Value::Nil);
+        assert_eq!(run_code("main = : u = nil; u?.foo(1 / 0)\n").unwrap(), Value::Nil);
+        assert_eq!(run_code("main = : u = nil; u?.profile?.name()\n").unwrap(), Value::Nil);
+        assert_eq!(run_code("main = : u = nil; u?.json()\n").unwrap(), Value::Nil);
+
+        // Receptor record: se llama normalmente
+        let source = "double(x) = x * 2\nmain = : rec = {foo: double}; rec?.foo(21)\n";
+        assert_eq!(run_code(source).unwrap(), Value::Int(42));
+
+        // Sin `?.` llamar sobre nil sigue siendo un error
+        assert!(run_code("main = : u = nil; u.foo()\n").is_err());
+    }
+
+    #[test]
+    fn test_cast() {
+        assert_eq!(run_code("main = \"42\" as int\n").unwrap(), Value::Int(42));
+        assert_eq!(run_code("main = 3.9 as int\n").unwrap(), Value::Int(3));
+        assert_eq!(run_code("main = \"2.5\" as float\n").unwrap(), Value::Float(2.5));
+        assert_eq!(run_code("main = 2 as float\n").unwrap(), Value::Float(2.0));
+        assert_eq!(run_code("main = 7 as string ++ \"!\"\n").unwrap(), Value::String("7!".to_string()));
+        assert_eq!(run_code("main = [1, 2] as string\n").unwrap(), Value::String("[1 2]".to_string()));
+    }
+
+    #[test]
+    fn test_cast_failure() {
+        let err = run_code("main = \"abc\" as int\n").unwrap_err();
+        assert_eq!(err.message, "No se puede convertir abc (string) a int");
+        let err = run_code("main = true as float\n").unwrap_err();
+        assert_eq!(err.message, "No se puede convertir true (bool) a float");
+    }
+
+    #[test]
+    fn test_modulo_by_zero() {
+        let err = run_code("main = 7 % 0\n").unwrap_err();
+        assert_eq!(err.message, "Módulo por cero");
+        assert_eq!(run_code("main = 7 % 3\n").unwrap(), Value::Int(1));
+    }
+
+    #[test]
+    fn test_function_call() {
+        let result = run_code("+http\ndouble(x) = x * 2\nmain = double(21)\n");
+        assert_eq!(result.unwrap(), Value::Int(42));
+    }
+
+    #[test]
+    fn test_range_builtin() {
+        let ints = |values: &[i64]| Value::List(values.iter().copied().map(Value::Int).collect());
+
+        // El fin es exclusivo
+        assert_eq!(run_code("main = range(3)\n").unwrap(), ints(&[0, 1, 2]));
+        assert_eq!(run_code("main = range(2, 5)\n").unwrap(), ints(&[2, 3, 4]));
+        assert_eq!(run_code("main = range(0, 10, 3)\n").unwrap(), ints(&[0, 3, 6, 9]));
+        assert_eq!(run_code("main = range(0)\n").unwrap(), ints(&[]));
+        assert_eq!(run_code("main = range(5, 2)\n").unwrap(), ints(&[]));
+    }
+
+    #[test]
+    fn test_range_negative_step() {
+        let ints = |values: &[i64]| Value::List(values.iter().copied().map(Value::Int).collect());
+
+        assert_eq!(run_code("main = range(5, 0, -1)\n").unwrap(), ints(&[5, 4, 3, 2, 1]));
+        assert_eq!(run_code("main = range(3, -4, -3)\n").unwrap(), ints(&[3, 0, -3]));
+        assert_eq!(run_code("main = range(0, 5, -1)\n").unwrap(), ints(&[]));
+    }
+
+    #[test]
+    fn test_range_errors() {
+        assert_eq!(run_code("main = range(0, 5, 0)\n").unwrap_err().message, "range: el paso no puede ser 0");
+        assert!(run_code("main = range(1.5)\n").unwrap_err().message.contains("range requiere enteros"));
+        assert!(run_code("main = range()\n").is_err());
+        assert!(run_code("main = range(0, 100000000000)\n").unwrap_err().message.contains("demasiado grande"));
+    }
+
+    #[test]
+    fn test_range_literal() {
+        let ints = |values: &[i64]| Value::List(values.iter().copied().map(Value::Int).collect());
+        assert_eq!(run_code("main = 1..4\n").unwrap(), ints(&[1, 2, 3]));
+        assert_eq!(run_code("main = : n = 2; 0..n + 1\n").unwrap(), ints(&[0, 1, 2]));
+        assert_eq!(run_code("main = 2 in 0..3\n").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_for_over_range() {
+        let mut vm = VM::new();
+        let mut main = loop_main(0);
+        let Expr::For { iter, .. } = &mut main.body else {
+            unreachable!()
+        };
+        **iter = Expr::Call {
+            func: Box::new(Expr::Ident("range".to_string())),
+            args: vec![Expr::Int(3)],
+            has_effect: false,
+        };
+        vm.define_function(main);
+        // El for retorna el valor de la ultima iteracion: i = 2
+        assert_eq!(vm.run().unwrap(), Value::Int(4));
+    }
+
+    #[test]
+    fn test_aggregate_builtins() {
+        assert_eq!(run_code("main = sum([1, 2, 3])\n").unwrap(), Value::Int(6));
+        assert_eq!(run_code("main = min([4, 2, 8])\n").unwrap(), Value::Int(2));
+        assert_eq!(run_code("main = max([4, 2, 8])\n").unwrap(), Value::Int(8));
+        // avg siempre retorna float
+        assert_eq!(run_code("main = avg([1, 2])\n").unwrap(), Value::Float(1.5));
+        assert_eq!(run_code("main = avg([2, 4])\n").unwrap(), Value::Float(3.0));
+        // La forma de dos argumentos sigue igual
+        assert_eq!(run_code("main = max(1, 5)\n").unwrap(), Value::Int(5));
+    }
+
+    #[test]
+    fn test_aggregate_mixed_numbers_promote_to_float() {
+        assert_eq!(run_code("main = sum([1, 2.5])\n").unwrap(), Value::Float(3.5));
+        assert_eq!(run_code("main = min([3, 1.5, 2])\n").unwrap(), Value::Float(1.5));
+        assert_eq!(run_code("main = max([3, 1.5])\n").unwrap(), Value::Float(3.0));
+        assert_eq!(run_code("main = avg([1, 2.0, 3])\n").unwrap(), Value::Float(2.0));
+    }
+
+    #[test]
+    fn test_aggregate_empty_list() {
+        // La suma vacia es 0; min, max y avg de nada son nil
+        assert_eq!(run_code("main = sum([])\n").unwrap(), Value::Int(0));
+        assert_eq!(run_code("main = min([])\n").unwrap(), Value::Nil);
+        assert_eq!(run_code("main = max([])\n").unwrap(), Value::Nil);
+        assert_eq!(run_code("main = avg([])\n").unwrap(), Value::Nil);
+    }
+
+    #[test]
+    fn test_aggregate_rejects_non_numbers() {
+        let err = run_code("main = sum([1, \"2\"])\n").unwrap_err();
+        assert_eq!(err.message, "sum requiere una lista de numeros, recibio un string");
+        assert!(run_code("main = avg(3)\n").is_err());
+    }
+
+    #[test]
+    fn test_aggregate_integer_overflow() {
+        let err = run_code("main = sum([9223372036854775807, 1])\n").unwrap_err();
+        assert_eq!(err.message, "integer overflow");
+        let err = run_code("main = avg([9223372036854775807, 9223372036854775807])\n").unwrap_err();
+        assert_eq!(err.message, "integer overflow");
+        // Cerca del limite pero sin pasarse sigue funcionando
+        assert_eq!(
+            run_code("main = sum([9223372036854775807, 0 - 1])\n").unwrap(),
+            Value::Int(i64::MAX - 1)
+        );
+    }
+
+    #[test]
+    fn test_first_and_last() {
+        assert_eq!(run_code("main = first([1, 2, 3])\n").unwrap(), Value::Int(1));
+        assert_eq!(run_code("main = last([1, 2, 3])\n").unwrap(), Value::Int(3));
+        assert_eq!(run_code("main = first([])\n").unwrap(), Value::Nil);
+        assert_eq!(run_code("main = last([])\n").unwrap(), Value::Nil);
+    }
+
+    #[test]
+    fn test_sort_numbers_and_strings() {
+        let ints = |values: &[i64]| Value::List(values.iter().copied().map(Value::Int).collect());
+        assert_eq!(run_code("main = sort([3, 1, 2])\n").unwrap(), ints(&[1, 2, 3]));
+
+        let strings = run_code("main = sort([\"pera\", \"banana\", \"Zapallo\", \"anana\"])\n").unwrap();
+        assert_eq!(strings.to_string(), "[Zapallo anana banana pera]");
+
+        // Numeros primero (int y float juntos), despues strings
+        let mixed = run_code("main = sort([\"b\", 2.5, \"a\", 1, 3])\n").unwrap();
+        assert_eq!(mixed.to_string(), "[1 2.5 3 a b]");
+
+        assert!(run_code("main = sort([1, [2]])\n").unwrap_err().message.contains("recibio un list"));
+    }
+
+    #[test]
+    fn test_sort_order_is_total() {
+        use std::cmp::Ordering;
+        let nan = Value::Float(f64::NAN);
+        let values = [
+            Value::Int(9007199254740993),
+            Value::Float(9007199254740992.0),
+            Value::Int(9007199254740992),
+            Value::Float(-0.5),
+            nan.clone(),
+            Value::Int(0),
+            Value::Float(f64::INFINITY),
+        ];
+        // Antisimetrico y transitivo para todos los pares y ternas
+        for a in &values {
+            assert_eq!(sort_order(a, a), Ordering::Equal);
+            for b in &values {
+                assert_eq!(sort_order(a, b), sort_order(b, a).reverse(), "{} {}", a, b);
+                for c in &values {
+                    if sort_order(a, b) != Ordering::Greater && sort_order(b, c) != Ordering::Greater {
+                        assert_ne!(sort_order(a, c), Ordering::Greater, "{} {} {}", a, b, c);
+                    }
+                }
+            }
+        }
+        // El int grande no se confunde con el float redondeado
+        assert_eq!(sort_order(&values[0], &values[1]), Ordering::Greater);
+
+        // NaN en la lista no rompe el sort; el resto queda ordenado
+        let sorted = run_code("+math\nmain = sort([3.5, math.sqrt(0.0 - 1.0), 1, 2.0])\n").unwrap();
+        let Value::List(items) = sorted else { panic!("se esperaba una lista") };
+        let numbers: Vec<f64> = items.iter()
+            .filter_map(|v| match v {
+                Value::Int(n) => Some(*n as f64),
+                Value::Float(f) if !f.is_nan() => Some(*f),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(numbers, vec![1.0, 2.0, 3.5]);
+        assert_eq!(items.len(), 4);
+    }
+
+    #[test]
+    fn test_sort_with_key_is_stable() {
+        let users = "[{n: \"a\", age: 30}, {n: \"b\", age: 20}, {n: \"c\", age: 30}, {n: \"d\", age: 20}]";
+        let expected = "[{n: \"b\", age: 20}, {n: \"d\", age: 20}, {n: \"a\", age: 30}, {n: \"c\", age: 30}]";
+
+        // Con una funcion como clave, y con el nombre del campo
+        let source = format!("age(u) = u.age\nmain = sort({}, age) == {}\n", users, expected);
+        assert_eq!(run_code(&source).unwrap(), Value::Bool(true));
+        let source = format!("main = sort({}, \"age\") == {}\n", users, expected);
+        assert_eq!(run_code(&source).unwrap(), Value::Bool(true));
+
+        // Por pipe, la lista entra como primer argumento
+        let source = format!("age(u) = u.age\nmain = ({} |> sort(age)) == {}\n", users, expected);
+        assert_eq!(run_code(&source).unwrap(), Value::Bool(true));
+
+        assert!(run_code("main = sort([{a: 1}], \"b\")\n").unwrap_err().message.contains("campo 'b'"));
+    }
+
+    #[test]
+    fn test_list_spread() {
+        let ints = |values: &[i64]| Value::List(values.iter().copied().map(Value::Int).collect());
+        assert_eq!(run_code("main = : a = [1, 2]; [...a, 3]\n").unwrap(), ints(&[1, 2, 3]));
+        assert_eq!(run_code("main = : a = [2]; b = [3, 4]; [1, ...a, ...b, ...[]]\n").unwrap(), ints(&[1, 2, 3, 4]));
+        // Solo aplana un nivel
+        assert_eq!(run_code("main = [...[[1], 2]]\n").unwrap().to_string(), "[[1] 2]");
+
+        let err = run_code("main = : n = 1; [...n]\n").unwrap_err();
+        assert_eq!(err.message, "...n en una lista requiere una lista, recibio un int");
+    }
+
+    #[test]
+    fn test_record_spread_later_keys_win() {
+        let source = "main = : base = {a: 1, b: 2}; {...base, b: 3, c: 4}\n";
+        assert_eq!(run_code(source).unwrap(), run_code("main = {a: 1, b: 3, c: 4}\n").unwrap());
+
+        // Un spread posterior pisa los campos escritos antes
+        let source = "main = : over = {b: 9}; {a: 1, b: 2, ...over}\n";
+        assert_eq!(run_code(source).unwrap(), run_code("main = {a: 1, b: 9}\n").unwrap());
+
+        // La base no cambia
+        let source = "main = : base = {a: 1}; copy = {...base, a: 2}; base.a\n";
+        assert_eq!(run_code(source).unwrap(), Value::Int(1));
+
+        assert!(run_code("main = {...[1]}\n").unwrap_err().message.contains("requiere un record, recibio un list"));
+    }
+
+    #[test]
+    fn test_reverse() {
+        let ints = |values: &[i64]| Value::List(values.iter().copied().map(Value::Int).collect());
+        assert_eq!(run_code("main = reverse([1, 2, 3])\n").unwrap(), ints(&[3, 2, 1]));
+        assert_eq!(run_code("main = reverse([])\n").unwrap(), ints(&[]));
+        assert!(run_code("main = reverse(\"abc\")\n").is_err());
+    }
+
+    #[test]
+    fn test_unique_keeps_first_occurrence() {
+        let ints = |values: &[i64]| Value::List(values.iter().copied().map(Value::Int).collect());
+        assert_eq!(run_code("main = unique([3, 1, 3, 2, 1])\n").unwrap(), ints(&[3, 1, 2]));
+        // Igualdad de `==`: 1 y 1.0 son el mismo valor
+        assert_eq!(run_code("main = unique([1, 1.0, 2])\n").unwrap(), ints(&[1, 2]));
+        assert_eq!(run_code("main = dedup([])\n").unwrap(), ints(&[]));
+    }
+
+    #[test]
+    fn test_unique_nested_elements() {
+        let source = "main = unique([{a: 1, b: [1, 2]}, {b: [1, 2], a: 1}, {a: 2}, [1, [2]], [1, [2]]]) \
+            == [{a: 1, b: [1, 2]}, {a: 2}, [1, [2]]]\n";
+        assert_eq!(run_code(source).unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_join() {
+        assert_eq!(run_code("main = join([\"a\", \"b\", \"c\"], \", \")\n").unwrap(), Value::String("a, b, c".to_string()));
+        assert_eq!(run_code("main = join([1, 2, 3], \"-\")\n").unwrap(), Value::String("1-2-3".to_string()));
+        assert_eq!(run_code("main = join([\"x\", \"y\"])\n").unwrap(), Value::String("xy".to_string()));
+        assert_eq!(run_code("main = join([], \",\")\n").unwrap(), Value::String(String::new()));
+        assert!(run_code("main = join(\"abc\", \",\")\n").is_err());
+    }
+
+    #[test]
+    fn test_contains_builtin() {
+        assert_eq!(run_code("main = contains(\"mock_data\", \"mock\")\n").unwrap(), Value::Bool(true));
+        assert_eq!(run_code("main = contains([1, 2, 3], 4)\n").unwrap(), Value::Bool(false));
+        assert_eq!(run_code("main = contains({a: 1}, \"a\")\n").unwrap(), Value::Bool(true));
+        assert!(run_code("main = contains(1, 2)\n").is_err());
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        let result = run_code("+http\ngreeting(name) = \"Hello {name}!\"\nmain = greeting(\"AURA\")\n");
+        // La interpolación no funciona aún porque name no está en scope al evaluar el string
+        // Por ahora verificamos que no crashee
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_goals_stored() {
+        let source = r#"+http
+goal "fetch user data"
+goal "return formatted profile"
+main = 42
+"#;
+        let tokens = tokenize(source).expect("Tokenize failed");
+        let program = parse(tokens).expect("Parse failed");
+        let mut vm = VM::new();
+        vm.load(&program);
+
+        let goals = vm.get_goal_descriptions();
+        assert_eq!(goals.len(), 2);
+        assert_eq!(goals[0], "fetch user data");
+        assert_eq!(goals[1], "return formatted profile");
+    }
+
+    #[test]
+    fn test_goals_dont_affect_execution() {
+        let source = r#"+http
+goal "this is just metadata"
+double(x) = x * 2
+main = double(21)
+"#;
+        let result = run_code(source);
+        assert_eq!(result.unwrap(), Value::Int(42));
+    }
+
+    #[test]
+    fn test_goals_reset() {
+        let source = r#"+http
+goal "test goal"
+main = 1
+"#;
+        let tokens = tokenize(source).expect("Tokenize failed");
+        let program = parse(tokens).expect("Parse failed");
+        let mut vm = VM::new();
+        vm.load(&program);
+
+        assert_eq!(vm.get_goal_descriptions().len(), 1);
+        vm.reset();
+        assert_eq!(vm.get_goal_descriptions().len(), 0);
+    }
+
+    #[test]
+    fn test_check_goals_after_main() {
+        let source = r#"goal "hay datos" check datos != nil
+goal "muchos datos" check len(datos) > 5
+goal "sin check"
+goal "check roto" check no_existe > 0
+main = : datos = [1, 2, 3]; len(datos)
+"#;
+        let tokens = tokenize(source).expect("Tokenize failed");
+        let program = parse(tokens).expect("Parse failed");
+        let mut vm = VM::new();
+        vm.load(&program);
+        assert_eq!(vm.run().unwrap(), Value::Int(3));
+
+        let goals = vm.check_goals();
+        // Los goals sin check no se evaluan
+        assert_eq!(goals.len(), 3);
+        assert!(goals[0].passed);
+        assert_eq!(goals[0].value, Some(Value::Bool(true)));
+        assert!(!goals[1].passed);
+        assert_eq!(goals[1].value, Some(Value::Bool(false)));
+        assert!(!goals[2].passed);
+        assert!(goals[2].error.as_deref().unwrap().contains("no_existe"));
+    }
+
+    #[test]
+    fn test_expect_passes() {
+        let source = r#"+http
+main = : x = 5; expect x > 0; x
+"#;
+        let result = run_code(source);
+        assert_eq!(result.unwrap(), Value::Int(5));
+    }
+
+    #[test]
+    fn test_expect_failure_stops_execution() {
+        let source = r#"+http
+main = : x = -3; expect x > 0; print("despues"); x
+"#;
+        let tokens = tokenize(source).expect("Tokenize failed");
+        let program = parse(tokens).expect("Parse failed");
+        let mut vm = VM::new().with_captured_stdout();
+        vm.load(&program);
+
+        let err = vm.run().unwrap_err();
+        assert_eq!(err.message, "Expectation failed: x > 0");
+        // Lo que sigue al expect no se ejecuta
+        assert!(vm.take_stdout().is_empty());
+        // La expectativa queda registrada para el healing
+        assert!(vm.has_failed_expectations());
+        assert_eq!(vm.get_failed_expectations().len(), 1);
+    }
+
+    #[test]
+    fn test_expect_with_message() {
+        let source = r#"+http
+main = : x = -1; expect x > 0 "x should be positive"; x
+"#;
+        let tokens = tokenize(source).expect("Tokenize failed");
+        let program = parse(tokens).expect("Parse failed");
+        let mut vm = VM::new();
+        vm.load(&program);
+
+        let err = vm.run().unwrap_err();
+        assert_eq!(err.message, "Expectation failed: x should be positive (x > 0)");
+
+        let failures = vm.get_failed_expectations();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].message, Some("x should be positive".to_string()));
+    }
+
+    #[test]
+    fn test_multiple_expects() {
+        let source = r#"+http
+main = : a = 5; b = -2; expect a > 0; expect b > 0; expect a > 10; a + b
+"#;
+        let tokens = tokenize(source).expect("Tokenize failed");
+        let program = parse(tokens).expect("Parse failed");
+        let mut vm = VM::new();
+        vm.load(&program);
+
+        // El primero pasa; el segundo falla y los siguientes no se evaluan
+        let err = vm.run().unwrap_err();
+        assert_eq!(err.message, "Expectation failed: b > 0");
+        assert_eq!(vm.get_failed_expectations().len(), 1);
+    }
+
+    #[test]
+    fn test_expect_clears_on_reset() {
+        let source = r#"+http
+main = : expect false; 42
+"#;
+        let tokens = tokenize(source).expect("Tokenize failed");
+        let program = parse(tokens).expect("Parse failed");
+        let mut vm = VM::new();
+        vm.load(&program);
+        assert!(vm.run().is_err());
+
+        assert!(vm.has_failed_expectations());
+        vm.reset();
+        assert!(!vm.has_failed_expectations());
+    }
+
+    #[test]
+    fn test_invariants_stored() {
+        let source = r#"+http
+invariant api_url != "https://prod.example.com"
 invariant max_retries <= 5
 main = 42
 "#;
@@ -1917,22 +4455,31 @@ main = : observe x; x = 5; x
         let result = vm.run();
         // x is defined after observe, so this should work
         assert!(result.is_ok());
+        // Nothing to record yet when observe runs
+        assert!(vm.take_observations().is_empty());
     }
 
     #[test]
-    fn test_reason_without_agent_returns_nil() {
+    fn test_reason_without_agent_returns_question() {
         let source = r#"+http
 main = : result = reason "should I retry?"; result
 "#;
         let result = run_code(source);
-        assert_eq!(result.unwrap(), Value::Nil);
+        assert_eq!(result.unwrap(), Value::String("should I retry?".to_string()));
+    }
+
+    #[test]
+    fn test_reason_structured_without_agent_is_usable() {
+        let source = r#"main = : n = 3; answer = reason { observed: [n], question: "sigo?" }; "respuesta: " ++ answer"#;
+        let result = run_code(source);
+        assert_eq!(result.unwrap(), Value::String("respuesta: sigo?".to_string()));
     }
 
     #[test]
     fn test_checkpoint_basic() {
         let mut vm = VM::new();
         vm.define_var("x".to_string(), Value::Int(42));
-        vm.checkpoint("test_cp");
+        vm.save_checkpoint("test_cp");
         vm.define_var("x".to_string(), Value::Int(99));
 
         // Restore
@@ -1941,6 +4488,100 @@ main = : result = reason "should I retry?"; result
         assert_eq!(vm.env.get("x"), Some(Value::Int(42)));
     }
 
+    #[test]
+    fn test_profile_counts_calls() {
+        let source = "helper(x) = x + 1\ntwice(x) = helper(helper(x))\nmain = sum([helper(1), helper(2), helper(3), helper(4), helper(5)]) + twice(0)\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+
+        let mut vm = VM::new().with_profiling();
+        vm.load(&program);
+        vm.run().unwrap();
+        let profile = vm.take_profile();
+        let helper = profile.iter().find(|p| p.name == "helper").expect("helper profiled");
+        assert_eq!(helper.calls, 7);
+        let twice = profile.iter().find(|p| p.name == "twice").expect("twice profiled");
+        assert_eq!(twice.calls, 1);
+        assert!(profile.windows(2).all(|w| w[0].total_us >= w[1].total_us));
+
+        // Sin with_profiling no se registra nada
+        let mut vm = VM::new();
+        vm.load(&program);
+        vm.run().unwrap();
+        assert!(vm.take_profile().is_empty());
+    }
+
+    #[test]
+    fn test_captured_stdout() {
+        let tokens = tokenize("main = : print(\"a\", 1); [1, 2] |> print; nil").unwrap();
+        let program = parse(tokens).unwrap();
+        let mut vm = VM::new().with_captured_stdout();
+        vm.load(&program);
+        vm.run().unwrap();
+        assert_eq!(vm.take_stdout(), vec!["a", "1", "[1 2]"]);
+        assert!(vm.take_stdout().is_empty());
+
+        // Sin captura no se guarda nada
+        assert!(VM::new().take_stdout().is_empty());
+    }
+
+    #[test]
+    fn test_sandbox_blocks_effects_and_budget_applies_to_calls() {
+        let tokens = tokenize("+env +json\nhome = env.get(\"HOME\")\nparsed = json.parse(\"[1]\")\nspin(n) = if n > 0 spin(n - 1) else 0").unwrap();
+        let program = parse(tokens).unwrap();
+        let mut vm = VM::new().with_sandbox().with_step_budget(1_000);
+        vm.load(&program);
+
+        let error = vm.call_by_name("home", Vec::new()).unwrap_err();
+        assert!(error.message.contains("env.get"), "{}", error.message);
+        // Los modulos sin efectos siguen disponibles
+        assert!(vm.call_by_name("parsed", Vec::new()).is_ok());
+        let error = vm.call_by_name("spin", vec![Value::Int(10_000)]).unwrap_err();
+        assert_eq!(error.message, "execution budget exceeded");
+    }
+
+    #[test]
+    fn test_checkpoint_and_restore_environment() {
+        let tokens = tokenize("@User { name s }\ndoble(x) = x * 2\nmain = 1").unwrap();
+        let program = parse(tokens).unwrap();
+        let mut vm = VM::new();
+        vm.load(&program);
+        vm.define_var("x".to_string(), Value::Int(1));
+
+        let cp = vm.checkpoint();
+        assert_eq!(cp.variables.get("x"), Some(&Value::Int(1)));
+        assert!(cp.functions.contains_key("doble"));
+        assert!(cp.types.contains_key("User"));
+
+        vm.define_var("x".to_string(), Value::Int(2));
+        vm.define_var("nueva".to_string(), Value::Bool(true));
+        vm.env.remove_function("doble");
+
+        vm.restore(&cp);
+        assert_eq!(vm.env.get("x"), Some(Value::Int(1)));
+        assert_eq!(vm.env.get("nueva"), None);
+        assert_eq!(vm.call_by_name("doble", vec![Value::Int(4)]).unwrap(), Value::Int(8));
+        assert!(vm.env.get_type("User").is_some());
+        assert_eq!(vm.run().unwrap(), Value::Int(1));
+    }
+
+    #[test]
+    fn test_checkpoint_flattens_scopes() {
+        let mut vm = VM::new();
+        vm.define_var("x".to_string(), Value::Int(1));
+        vm.env.push_scope();
+        vm.define_var("x".to_string(), Value::Int(2));
+        vm.define_var("y".to_string(), Value::Int(3));
+
+        let cp = vm.checkpoint();
+        assert_eq!(cp.variables.get("x"), Some(&Value::Int(2)));
+        assert_eq!(cp.variables.get("y"), Some(&Value::Int(3)));
+
+        let mut other = VM::new();
+        other.restore(&cp);
+        assert_eq!(other.env.get("x"), Some(Value::Int(2)));
+        assert_eq!(other.env.scopes.len(), 1);
+    }
+
     #[test]
     fn test_invariants_as_strings() {
         let source = r#"+http
@@ -0,0 +1,252 @@
+//! RecordingCognitiveRuntime - Registro de la ejecucion cognitiva en JSONL
+//!
+//! Envuelve a otro `CognitiveRuntime` y escribe cada observacion,
+//! deliberacion y chequeo de goals como una linea JSON, para poder
+//! reproducir y analizar despues lo que hizo el agente. Las decisiones
+//! las sigue tomando el runtime interno.
+//!
+//! ## Ejemplo de uso
+//!
+//! ```ignore
+//! use aura::{RecordingCognitiveRuntime, NullCognitiveRuntime};
+//!
+//! let recorder = RecordingCognitiveRuntime::create("run.jsonl", Box::new(NullCognitiveRuntime))?;
+//! let mut vm = aura::vm::VM::with_cognitive(Box::new(recorder));
+//! vm.load(&program);
+//! vm.run()?;
+//!
+//! for event in aura::vm::recording::read("run.jsonl")? {
+//!     println!("{:?}", event.kind);
+//! }
+//! ```
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::cognitive::*;
+
+/// Una linea del registro
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: RecordedKind,
+}
+
+/// Que paso en cada linea del registro
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RecordedKind {
+    /// El VM notifico una observacion
+    Observation {
+        /// `value_changed`, `expect_evaluated`, `function_returned`,
+        /// `checkpoint_created` u `observed`
+        kind: String,
+        detail: String,
+    },
+    /// Un episodio de razonamiento: trigger y decision del runtime interno
+    Deliberation {
+        trigger_type: String,
+        trigger: String,
+        decision: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        decision_detail: String,
+    },
+    /// Decisiones devueltas por `check_goals` (solo si hubo alguna)
+    GoalCheck {
+        decisions: Vec<String>,
+    },
+}
+
+/// Runtime cognitivo que registra todo lo que pasa por el runtime interno
+///
+/// Siempre se reporta activo, para que el VM le notifique eventos aun
+/// cuando el interno es un `NullCognitiveRuntime`. La escritura es best
+/// effort: un error de IO no corta la ejecucion, queda en `write_error()`.
+pub struct RecordingCognitiveRuntime {
+    inner: Box<dyn CognitiveRuntime>,
+    file: File,
+    write_error: Option<io::Error>,
+}
+
+impl RecordingCognitiveRuntime {
+    /// Abre (o crea) el registro en modo append
+    pub fn create<P: AsRef<Path>>(path: P, inner: Box<dyn CognitiveRuntime>) -> io::Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { inner, file, write_error: None })
+    }
+
+    /// Primer error de escritura, si hubo alguno
+    pub fn write_error(&self) -> Option<&io::Error> {
+        self.write_error.as_ref()
+    }
+
+    fn record(&mut self, kind: RecordedKind) {
+        let event = RecordedEvent { timestamp: Utc::now(), kind };
+        let mut line = serde_json::to_string(&event).unwrap_or_default();
+        line.push('\n');
+        // Una escritura por linea: si el proceso muere, lo registrado queda completo
+        if let Err(e) = self.file.write_all(line.as_bytes()) {
+            self.write_error.get_or_insert(e);
+        }
+    }
+}
+
+impl CognitiveRuntime for RecordingCognitiveRuntime {
+    fn observe(&mut self, event: ObservationEvent) {
+        self.record(RecordedKind::Observation {
+            kind: event.kind().to_string(),
+            detail: format!("{:?}", event),
+        });
+        self.inner.observe(event);
+    }
+
+    fn deliberate(&mut self, trigger: DeliberationTrigger) -> CognitiveDecision {
+        let trigger_type = trigger.kind().to_string();
+        let trigger_text = trigger.to_string();
+        let decision = self.inner.deliberate(trigger);
+        self.record(RecordedKind::Deliberation {
+            trigger_type,
+            trigger: trigger_text,
+            decision: decision.kind().to_string(),
+            decision_detail: decision.detail(),
+        });
+        decision
+    }
+
+    fn check_goals(&mut self) -> Vec<CognitiveDecision> {
+        let decisions = self.inner.check_goals();
+        if !decisions.is_empty() {
+            self.record(RecordedKind::GoalCheck {
+                decisions: decisions.iter().map(|d| d.kind().to_string()).collect(),
+            });
+        }
+        decisions
+    }
+
+    fn is_active(&self) -> bool {
+        true
+    }
+
+    fn set_available_checkpoints(&mut self, checkpoints: Vec<String>) {
+        self.inner.set_available_checkpoints(checkpoints);
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceEntry> {
+        self.inner.take_trace()
+    }
+}
+
+/// Lee un registro completo, en orden de escritura
+///
+/// Las lineas que no se pueden parsear (una escritura cortada) se ignoran.
+pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Vec<RecordedEvent>> {
+    let content = fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{RuntimeError, Value, VM};
+
+    fn run_recorded(source: &str, path: &Path) -> Result<Value, RuntimeError> {
+        let tokens = crate::lexer::tokenize(source).unwrap();
+        let program = crate::parser::parse(tokens).unwrap();
+        let recorder = RecordingCognitiveRuntime::create(path, Box::new(NullCognitiveRuntime)).unwrap();
+        let mut vm = VM::with_cognitive(Box::new(recorder));
+        vm.load(&program);
+        vm.run()
+    }
+
+    #[test]
+    fn test_records_observations_and_deliberations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/run.jsonl");
+
+        let source = "main = : x = 5; observe x; y = reason \"sigo?\"; x\n";
+        assert_eq!(run_recorded(source, &path).unwrap(), Value::Int(5));
+
+        let events = read(&path).unwrap();
+        let observed: Vec<_> = events.iter().filter_map(|e| match &e.kind {
+            RecordedKind::Observation { kind, .. } => Some(kind.as_str()),
+            _ => None,
+        }).collect();
+        assert!(observed.contains(&"observed"), "observaciones: {:?}", observed);
+
+        let deliberations: Vec<_> = events.iter().filter_map(|e| match &e.kind {
+            RecordedKind::Deliberation { trigger_type, trigger, decision, .. } => {
+                Some((trigger_type.as_str(), trigger.as_str(), decision.as_str()))
+            }
+            _ => None,
+        }).collect();
+        assert_eq!(deliberations, vec![("reason", "reason: sigo?", "continue")]);
+
+        // El reason se registra despues del observe
+        let observe_at = events.iter().position(|e| matches!(&e.kind, RecordedKind::Observation { kind, .. } if kind == "observed")).unwrap();
+        let reason_at = events.iter().position(|e| matches!(e.kind, RecordedKind::Deliberation { .. })).unwrap();
+        assert!(observe_at < reason_at);
+    }
+
+    #[test]
+    fn test_records_failed_expect() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.jsonl");
+
+        let _ = run_recorded("main = : x = 0; expect x > 0 \"positivo\"; x\n", &path);
+
+        let events = read(&path).unwrap();
+        assert!(events.iter().any(|e| matches!(&e.kind, RecordedKind::Observation { kind, .. } if kind == "expect_evaluated")));
+        assert!(events.iter().any(|e| matches!(&e.kind, RecordedKind::Deliberation { trigger_type, .. } if trigger_type == "expect_failed")));
+    }
+
+    #[test]
+    fn test_log_is_appended_across_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.jsonl");
+
+        run_recorded("main = : y = reason \"uno\"; 1\n", &path).unwrap();
+        run_recorded("main = : y = reason \"dos\"; 2\n", &path).unwrap();
+
+        let triggers: Vec<_> = read(&path).unwrap().into_iter().filter_map(|e| match e.kind {
+            RecordedKind::Deliberation { trigger, .. } => Some(trigger),
+            _ => None,
+        }).collect();
+        assert_eq!(triggers, vec!["reason: uno", "reason: dos"]);
+    }
+
+    #[test]
+    fn test_event_json_shape() {
+        let event = RecordedEvent {
+            timestamp: Utc::now(),
+            kind: RecordedKind::Deliberation {
+                trigger_type: "reason".to_string(),
+                trigger: "reason: x".to_string(),
+                decision: "continue".to_string(),
+                decision_detail: String::new(),
+            },
+        };
+        let json: serde_json::Value = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "deliberation");
+        assert_eq!(json["trigger_type"], "reason");
+        assert!(json.get("decision_detail").is_none());
+        assert!(json["timestamp"].is_string());
+
+        let parsed: RecordedEvent = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, event);
+    }
+}
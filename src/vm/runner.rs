@@ -1,11 +1,11 @@
 //! Cognitive execution runner
 //!
 //! Provides `run_cognitive()` which wraps the VM execution with
-//! retry logic and fix application for cognitive mode.
+//! retry logic, fix application and backtracking for cognitive mode.
 
 use crate::parser::Program;
 use super::{VM, Value, RuntimeError};
-use super::cognitive::CognitiveRuntime;
+use super::cognitive::{CognitiveRuntime, TraceEntry};
 
 /// Result of a cognitive run
 #[derive(Debug)]
@@ -16,15 +16,21 @@ pub struct CognitiveRunResult {
     pub applied_fixes: Vec<(String, String)>,
     /// Number of retries that were needed
     pub retries: usize,
+    /// Times the VM was restored to a checkpoint at the runtime's request
+    pub backtracks: usize,
+    /// Deliberation steps, if the cognitive runtime was built with a trace
+    pub trace: Vec<TraceEntry>,
 }
 
 /// Runs a program with cognitive runtime support
 ///
 /// For each attempt:
 /// 1. Loads and runs the program
-/// 2. If there are pending_fixes, applies them and re-parses
-/// 3. If the result is Ok and no fixes pending, returns
-/// 4. If max_retries exhausted, returns the error
+/// 2. If the runtime asked to backtrack, restores that checkpoint and reruns
+///    `main` on the same VM (at most max_retries times per attempt)
+/// 3. If there are pending_fixes, applies them and re-parses
+/// 4. If the result is Ok and no fixes pending, returns
+/// 5. If max_retries exhausted, returns the error
 pub fn run_cognitive(
     source: &str,
     cognitive: Box<dyn CognitiveRuntime>,
@@ -32,6 +38,8 @@ pub fn run_cognitive(
 ) -> Result<CognitiveRunResult, RuntimeError> {
     let mut current_source = source.to_string();
     let mut all_fixes = Vec::new();
+    let mut trace = Vec::new();
+    let mut backtracks = 0;
     let mut cognitive_opt: Option<Box<dyn CognitiveRuntime>> = Some(cognitive);
 
     for attempt in 0..=max_retries {
@@ -52,7 +60,20 @@ pub fn run_cognitive(
         };
 
         vm.load(&program);
-        let result = vm.run();
+        let mut result = vm.run();
+        let mut attempt_backtracks = 0;
+        while result.is_err() && attempt_backtracks < max_retries {
+            let Some((name, adjustments)) = vm.pending_backtrack.take() else { break };
+            let Some(checkpoint) = vm.checkpoint_manager().restore(&name).cloned() else { break };
+            vm.restore(&checkpoint);
+            for (var, value) in adjustments {
+                vm.define_var(var, value);
+            }
+            attempt_backtracks += 1;
+            result = vm.run();
+        }
+        backtracks += attempt_backtracks;
+        trace.extend(vm.take_cognitive_trace());
 
         // Check for pending fixes
         if !vm.pending_fixes.is_empty() {
@@ -79,6 +100,8 @@ pub fn run_cognitive(
                     value,
                     applied_fixes: all_fixes,
                     retries: attempt,
+                    backtracks,
+                    trace,
                 });
             }
             Err(err) => {
@@ -105,6 +128,8 @@ pub fn run_program_cognitive(
         value: result,
         applied_fixes: vm.pending_fixes.clone(),
         retries: 0,
+        backtracks: 0,
+        trace: vm.take_cognitive_trace(),
     })
 }
 
@@ -121,6 +146,70 @@ mod tests {
         assert_eq!(result.unwrap().value, Value::Int(42));
     }
 
+    #[test]
+    fn test_run_cognitive_collects_trace() {
+        use crate::agent::MockProvider;
+        use crate::vm::agent_cognitive::AgentCognitiveRuntime;
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let source = "+http\nmain = : x = reason \"como sigo?\"; 1\n";
+        let cognitive = AgentCognitiveRuntime::new(
+            MockProvider::new().with_latency(0),
+            rt.handle().clone(),
+            Vec::new(),
+            Vec::new(),
+            source.to_string(),
+        ).with_trace();
+
+        let result = run_cognitive(source, Box::new(cognitive), 3).unwrap();
+        assert_eq!(result.trace.len(), 1);
+        assert_eq!(result.trace[0].trigger_type, "reason");
+        assert_eq!(result.trace[0].trigger, "reason: como sigo?");
+    }
+
+    /// Pide backtrack a `call_f` (con `limite` ajustado) la primera vez que falla
+    struct BacktrackOnce {
+        asked: bool,
+    }
+
+    impl CognitiveRuntime for BacktrackOnce {
+        fn observe(&mut self, _event: crate::vm::ObservationEvent) {}
+
+        fn deliberate(&mut self, _trigger: crate::vm::DeliberationTrigger) -> crate::vm::CognitiveDecision {
+            if self.asked {
+                return crate::vm::CognitiveDecision::Continue;
+            }
+            self.asked = true;
+            crate::vm::CognitiveDecision::Backtrack {
+                checkpoint: "call_f".to_string(),
+                adjustments: vec![("limite".to_string(), Value::Int(10))],
+            }
+        }
+
+        fn check_goals(&mut self) -> Vec<crate::vm::CognitiveDecision> {
+            Vec::new()
+        }
+
+        fn is_active(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_run_cognitive_backtracks_to_checkpoint() {
+        let source = "f() = 1\nmain = : a = f(); a + limite\n";
+        let result = run_cognitive(source, Box::new(BacktrackOnce { asked: false }), 3).unwrap();
+        assert_eq!(result.value, Value::Int(11));
+        assert_eq!(result.backtracks, 1);
+        assert_eq!(result.retries, 0);
+    }
+
+    #[test]
+    fn test_run_cognitive_without_trace() {
+        let result = run_cognitive("+http\nmain = 42\n", Box::new(NullCognitiveRuntime), 3).unwrap();
+        assert!(result.trace.is_empty());
+    }
+
     #[test]
     fn test_run_program_cognitive_simple() {
         let tokens = crate::lexer::tokenize("+http\nmain = 42\n").unwrap();
@@ -0,0 +1,105 @@
+//! Integration tests for `aura ast-diff`.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn aura_binary() -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("target");
+    path.push("debug");
+    path.push("aura");
+    path
+}
+
+fn temp_file(name: &str, content: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("target");
+    path.push(format!("test_ast_diff_{}.aura", name));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+/// Ejecuta `aura ast-diff --json` y retorna (exito, json)
+fn run_diff(old: &str, new: &str, name: &str) -> (bool, serde_json::Value) {
+    let old_path = temp_file(&format!("{}_old", name), old);
+    let new_path = temp_file(&format!("{}_new", name), new);
+
+    let output = Command::new(aura_binary())
+        .args(["ast-diff", "--json"])
+        .arg(&old_path)
+        .arg(&new_path)
+        .output()
+        .expect("Failed to run aura ast-diff");
+
+    fs::remove_file(&old_path).ok();
+    fs::remove_file(&new_path).ok();
+
+    let json = serde_json::from_slice(&output.stdout).expect("ast-diff should print JSON");
+    (output.status.success(), json)
+}
+
+#[test]
+fn test_renamed_function_is_removed_plus_added() {
+    let (ok, json) = run_diff(
+        "double(x) = x * 2\nmain = 1\n",
+        "twice(x) = x * 2\nmain = 1\n",
+        "rename",
+    );
+
+    assert!(ok);
+    assert_eq!(json["functions"]["added"], serde_json::json!(["twice"]));
+    assert_eq!(json["functions"]["removed"], serde_json::json!(["double"]));
+    assert_eq!(json["functions"]["modified"], serde_json::json!([]));
+    assert_eq!(json["total_changes"], 2);
+}
+
+#[test]
+fn test_modified_function_reasons() {
+    let (_, json) = run_diff(
+        "add(a, b) = a + b\nmain = add(1, 2)\n",
+        "add(x, y) = x + y\nmain = add(2, 2)\n",
+        "reasons",
+    );
+
+    let modified = json["functions"]["modified"].as_array().unwrap();
+    assert_eq!(modified.len(), 2);
+    assert_eq!(modified[0]["name"], "add");
+    assert_eq!(modified[0]["reasons"], serde_json::json!(["params changed", "body changed"]));
+    assert_eq!(modified[1]["name"], "main");
+    assert_eq!(modified[1]["reasons"], serde_json::json!(["body changed"]));
+}
+
+#[test]
+fn test_type_changes() {
+    let (_, json) = run_diff(
+        "@User {\n  id:i @pk\n}\n@Post {\n  id:i\n}\n",
+        "@User {\n  id:i @pk\n  name:s\n}\n@Tag {\n  id:i\n}\n",
+        "types",
+    );
+
+    assert_eq!(json["types"]["added"], serde_json::json!(["Tag"]));
+    assert_eq!(json["types"]["removed"], serde_json::json!(["Post"]));
+    assert_eq!(json["types"]["modified"][0]["reasons"], serde_json::json!(["fields changed"]));
+}
+
+#[test]
+fn test_formatting_only_changes_are_ignored() {
+    let (ok, json) = run_diff(
+        "add(a b)=a+b\n",
+        "# suma\nadd(a, b) = a + b\n",
+        "formatting",
+    );
+
+    assert!(ok);
+    assert_eq!(json["total_changes"], 0);
+}
+
+#[test]
+fn test_parse_error_is_reported() {
+    let (ok, json) = run_diff("main = 1\n", "double(x = x * 2\n", "error");
+
+    assert!(!ok);
+    assert_eq!(json["success"], false);
+    assert_eq!(json["errors"][0]["code"], "E101");
+}
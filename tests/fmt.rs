@@ -0,0 +1,261 @@
+//! Golden-file tests for `aura fmt`.
+//!
+//! Each `tests/fmt_golden/<case>.aura` is formatted and compared with
+//! `<case>.expected`. The formatted output must also parse back to the
+//! same AST and be stable under a second pass.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use aura::{parse, tokenize, Program};
+
+fn aura_binary() -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("target");
+    path.push("debug");
+    path.push("aura");
+    path
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fmt_golden")
+}
+
+fn parse_source(source: &str) -> Program {
+    parse(tokenize(source).expect("lex")).expect("parse")
+}
+
+/// AST serializado sin posiciones, para comparar programas
+fn ast_without_spans(program: &Program) -> serde_json::Value {
+    fn strip(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                map.remove("span");
+                map.values_mut().for_each(strip);
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(strip),
+            _ => {}
+        }
+    }
+    let mut value = serde_json::to_value(program).unwrap();
+    strip(&mut value);
+    value
+}
+
+fn golden_cases() -> Vec<PathBuf> {
+    let mut cases: Vec<PathBuf> = fs::read_dir(golden_dir())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "aura"))
+        .collect();
+    cases.sort();
+    assert!(!cases.is_empty(), "no golden cases found");
+    cases
+}
+
+#[test]
+fn test_golden_files() {
+    for input in golden_cases() {
+        let source = fs::read_to_string(&input).unwrap();
+        let expected = fs::read_to_string(input.with_extension("expected")).unwrap();
+
+        let formatted = aura::fmt::format(&parse_source(&source));
+        assert_eq!(formatted, expected, "golden mismatch for {}", input.display());
+    }
+}
+
+#[test]
+fn test_golden_files_roundtrip() {
+    for input in golden_cases() {
+        let source = fs::read_to_string(&input).unwrap();
+        let program = parse_source(&source);
+        let formatted = aura::fmt::format(&program);
+        let reparsed = parse_source(&formatted);
+
+        assert_eq!(
+            ast_without_spans(&program),
+            ast_without_spans(&reparsed),
+            "AST changed after formatting {}",
+            input.display()
+        );
+        assert_eq!(aura::fmt::format(&reparsed), formatted, "not idempotent: {}", input.display());
+    }
+}
+
+#[test]
+fn test_examples_roundtrip() {
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let mut checked = 0;
+
+    for entry in fs::read_dir(examples).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "aura") {
+            continue;
+        }
+        let source = fs::read_to_string(&path).unwrap();
+        // Algunos ejemplos usan sintaxis que el parser aun no soporta
+        let Ok(program) = tokenize(&source).map_err(|_| ()).and_then(|t| parse(t).map_err(|_| ())) else {
+            continue;
+        };
+
+        let formatted = aura::fmt::format(&program);
+        assert_eq!(
+            ast_without_spans(&program),
+            ast_without_spans(&parse_source(&formatted)),
+            "AST changed after formatting {}",
+            path.display()
+        );
+        checked += 1;
+    }
+
+    assert!(checked > 0);
+}
+
+mod cli {
+    use super::*;
+
+    fn temp_file(dir: &Path, content: &str) -> PathBuf {
+        let path = dir.join("input.aura");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_fmt_prints_formatted_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_file(dir.path(), "add(a b)=a+b\n");
+
+        let output = Command::new(aura_binary()).arg("fmt").arg(&path).output().unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "add(a, b) = a + b\n");
+        // Sin --write el archivo no cambia
+        assert_eq!(fs::read_to_string(&path).unwrap(), "add(a b)=a+b\n");
+    }
+
+    #[test]
+    fn test_fmt_write_overwrites_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_file(dir.path(), "main=1+2\n");
+
+        let output = Command::new(aura_binary())
+            .args(["fmt", "--write", "--json"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+        assert_eq!(json["success"], true);
+        assert_eq!(json["changed"], true);
+        assert_eq!(json["written"], true);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "main = 1 + 2\n");
+    }
+
+    #[test]
+    fn test_fmt_json_reports_parse_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_file(dir.path(), "double(x = x * 2\n");
+
+        let output = Command::new(aura_binary())
+            .args(["fmt", "--json"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+        assert!(!output.status.success());
+        assert_eq!(json["success"], false);
+        assert_eq!(json["errors"][0]["code"], "E101");
+    }
+
+    #[test]
+    fn test_fmt_keeps_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_file(dir.path(), "# doble\ndouble(x)=x*2\n");
+
+        let output = Command::new(aura_binary())
+            .args(["fmt", "--json"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+        assert_eq!(json["formatted"], "# doble\ndouble(x) = x * 2\n");
+        assert!(json["warnings"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fmt_write_roundtrips_file_with_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = "# utilidades\n\n#doc Un usuario\n@User {\n  #doc Correo\n  email:s\n}\n\n#doc Duplica\ndouble(x)=x*2\n\nmain=double(2)\n";
+        let path = temp_file(dir.path(), source);
+
+        let output = Command::new(aura_binary())
+            .args(["fmt", "--write"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        let written = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            written,
+            "# utilidades\n#doc Un usuario\n@User {\n  #doc Correo\n  email :s\n}\n\n#doc Duplica\ndouble(x) = x * 2\n\nmain = double(2)\n"
+        );
+        assert_eq!(ast_without_spans(&parse_source(source)), ast_without_spans(&parse_source(&written)));
+        let docs = |source: &str| {
+            let tokens = tokenize(source).unwrap();
+            let program = parse(tokens.clone()).unwrap();
+            aura::docs::extract_docs(&tokens, &program)
+        };
+        assert_eq!(docs(source).len(), 3);
+        assert_eq!(docs(source).into_iter().map(|d| d.doc).collect::<Vec<_>>(),
+            docs(&written).into_iter().map(|d| d.doc).collect::<Vec<_>>());
+
+        // Un segundo pase no cambia nada
+        let output = Command::new(aura_binary())
+            .args(["fmt", "--write"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(fs::read_to_string(&path).unwrap(), written);
+    }
+
+    #[test]
+    fn test_fmt_write_refuses_to_drop_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_file(dir.path(), "main=1 # al final\n");
+
+        let output = Command::new(aura_binary())
+            .args(["fmt", "--write", "--json"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(json["success"], false);
+        assert_eq!(json["errors"][0]["code"], "E003");
+        assert_eq!(json["errors"][0]["location"]["line"], 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "main=1 # al final\n");
+    }
+
+    #[test]
+    fn test_fmt_warns_about_comments_it_cannot_keep() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_file(dir.path(), "main=1 # al final\n");
+
+        let output = Command::new(aura_binary())
+            .args(["fmt", "--json"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(json["formatted"], "main = 1\n");
+        assert_eq!(json["warnings"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,159 @@
+//! Integration tests for file-watching hot reload.
+//!
+//! Rewrites a temporary .aura file while `hot_reload_file_with_interval`
+//! is watching it and checks that the VM picks up the changes.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use aura::reload::{hot_reload_file_with_interval, ReloadError};
+use aura::vm::VM;
+use aura::{parse, tokenize, Value};
+
+fn temp_file(dir: &Path, content: &str) -> PathBuf {
+    let path = dir.join("watched.aura");
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn load_vm(code: &str) -> VM {
+    let program = parse(tokenize(code).unwrap()).unwrap();
+    let mut vm = VM::new();
+    vm.load(&program);
+    vm
+}
+
+/// Reescribe el archivo con cada version, con una pausa entre ellas
+///
+/// Escribe a un archivo temporal y lo renombra, para que el watcher nunca
+/// lea un archivo a medio escribir.
+fn rewrite_later(path: PathBuf, versions: Vec<&'static str>) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let tmp = path.with_extension("tmp");
+        for version in versions {
+            thread::sleep(Duration::from_millis(100));
+            fs::write(&tmp, version).unwrap();
+            fs::rename(&tmp, &path).unwrap();
+        }
+    })
+}
+
+#[test]
+fn test_watch_applies_rewritten_file() {
+    let initial = "double(x) = x * 2\ntriple(x) = x * 3\nmain = double(5)\n";
+    let dir = tempfile::tempdir().unwrap();
+    let path = temp_file(dir.path(), initial);
+    let mut vm = load_vm(initial);
+
+    let writer = rewrite_later(path.clone(), vec!["double(x) = x + x + x\nmain = double(5)\n"]);
+
+    let mut results = Vec::new();
+    hot_reload_file_with_interval(&mut vm, &path, Duration::from_millis(20), |outcome| {
+        results.push(outcome.clone());
+        false
+    })
+    .unwrap();
+    writer.join().unwrap();
+
+    assert_eq!(results.len(), 1);
+    let result = results[0].as_ref().unwrap();
+    assert_eq!(result.functions_updated, 1);
+    assert_eq!(result.functions_removed, 1);
+
+    assert!(!vm.list_functions().contains(&"triple".to_string()));
+    assert_eq!(vm.call_by_name("double", vec![Value::Int(5)]).unwrap(), Value::Int(15));
+}
+
+#[test]
+fn test_watch_survives_parse_error() {
+    let initial = "double(x) = x * 2\nmain = double(5)\n";
+    let dir = tempfile::tempdir().unwrap();
+    let path = temp_file(dir.path(), initial);
+    let mut vm = load_vm(initial);
+
+    let writer = rewrite_later(
+        path.clone(),
+        vec![
+            "double(x = x * 3\nmain = double(5)\n",
+            "double(x) = x * 4\nmain = double(5)\n",
+        ],
+    );
+
+    let mut saw_parse_error = false;
+    let mut updated = 0;
+    hot_reload_file_with_interval(&mut vm, &path, Duration::from_millis(20), |outcome| {
+        match outcome {
+            Err(ReloadError::ParseError(_)) => {
+                saw_parse_error = true;
+                true
+            }
+            Ok(result) => {
+                updated += result.functions_updated;
+                false
+            }
+            Err(_) => true,
+        }
+    })
+    .unwrap();
+    writer.join().unwrap();
+
+    assert!(saw_parse_error);
+    assert_eq!(updated, 1);
+    assert_eq!(vm.call_by_name("double", vec![Value::Int(5)]).unwrap(), Value::Int(20));
+}
+
+#[test]
+fn test_watch_survives_lex_error() {
+    let initial = "double(x) = x * 2\nmain = double(5)\n";
+    let dir = tempfile::tempdir().unwrap();
+    let path = temp_file(dir.path(), initial);
+    let mut vm = load_vm(initial);
+
+    let writer = rewrite_later(
+        path.clone(),
+        vec![
+            "double(x) = x * 3 \"sin cerrar\nmain = double(5)\n",
+            "double(x) = x * 4\nmain = double(5)\n",
+        ],
+    );
+
+    let mut errors = Vec::new();
+    hot_reload_file_with_interval(&mut vm, &path, Duration::from_millis(20), |outcome| {
+        match outcome {
+            Err(e) => {
+                errors.push(e.clone());
+                true
+            }
+            Ok(_) => false,
+        }
+    })
+    .unwrap();
+    writer.join().unwrap();
+
+    assert!(matches!(errors.as_slice(), [ReloadError::LexError(_)]), "{:?}", errors);
+    assert_eq!(vm.call_by_name("double", vec![Value::Int(5)]).unwrap(), Value::Int(20));
+}
+
+#[test]
+fn test_watch_rejects_type_errors() {
+    let initial = "double(x) = x * 2\nmain = double(5)\n";
+    let dir = tempfile::tempdir().unwrap();
+    let path = temp_file(dir.path(), initial);
+    let mut vm = load_vm(initial);
+
+    let writer = rewrite_later(path.clone(), vec!["double(x) = y * 3\nmain = double(5)\n"]);
+
+    let mut results = Vec::new();
+    hot_reload_file_with_interval(&mut vm, &path, Duration::from_millis(20), |outcome| {
+        results.push(outcome.clone());
+        false
+    })
+    .unwrap();
+    writer.join().unwrap();
+
+    assert!(matches!(results[0], Err(ReloadError::TypeError(_))), "{:?}", results[0]);
+    // La version con error no se instalo
+    assert_eq!(vm.call_by_name("double", vec![Value::Int(5)]).unwrap(), Value::Int(10));
+}
@@ -43,6 +43,123 @@ mod check_command {
         assert!(json["stats"]["definitions"].as_u64().is_some());
     }
 
+    #[test]
+    fn test_check_duplicate_definition_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dup.aura");
+        std::fs::write(&path, "main = 1\n\nmain = 2\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["check", "--json"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura check");
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], false);
+        let error = &json["errors"][0];
+        assert!(error["message"].as_str().unwrap().contains("'main'"));
+        assert_eq!(error["location"]["line"], 3);
+        assert_eq!(error["previous_location"]["line"], 1);
+    }
+
+    #[test]
+    fn test_check_effect_warnings_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("effects.aura");
+        std::fs::write(&path, "+http\nfetch(url) = http.get(url)\nload(id) = fetch!(id)\n\nmain = load(1)\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["check", "--json"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura check");
+
+        // Las advertencias no hacen fallar la verificacion
+        assert!(output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], true);
+        let warnings = json["warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0]["code"], "E301");
+        assert!(warnings[0]["message"].as_str().unwrap().contains("'load'"));
+        assert_eq!(warnings[0]["location"]["line"], 5);
+        assert_eq!(warnings[0]["suggestion"], "Usa 'load!(...)'");
+    }
+
+    #[test]
+    fn test_check_fix_inserts_missing_capability() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fix.aura");
+        std::fs::write(&path, "main = http.get(\"http://localhost\")\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["check", "--fix", "--json"])
+            .arg(&path)
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to execute aura check");
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(output.status.success());
+        assert_eq!(json["success"], true);
+        assert_eq!(json["fixes"]["applied"], 1);
+
+        let fixed = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(fixed, "+http\nmain = http.get(\"http://localhost\")\n");
+
+        // El snapshot guarda el archivo original
+        let snapshot_id = json["fixes"]["snapshot_id"].as_str().unwrap();
+        let snapshot = dir.path().join(".aura/snapshots").join(format!("{}.json", snapshot_id));
+        let snapshot: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(snapshot).unwrap()).unwrap();
+        assert_eq!(snapshot["files"][0]["content"], "main = http.get(\"http://localhost\")\n");
+    }
+
+    #[test]
+    fn test_check_without_fix_leaves_file_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nofix.aura");
+        std::fs::write(&path, "main = json.stringify(1)\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["check", "--json"])
+            .arg(&path)
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to execute aura check");
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], false);
+        assert!(json["errors"][0]["message"].as_str().unwrap().contains("+json"));
+        assert!(json.get("fixes").is_none());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "main = json.stringify(1)\n");
+    }
+
+    #[test]
+    fn test_check_type_error_includes_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ctx.aura");
+        std::fs::write(&path, "a = 1\nb = 2\na = 3\nc = 4\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["check", "--json"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura check");
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let context = json["errors"][0]["context"].as_array().expect("context lines");
+        let lines: Vec<(u64, &str, bool)> = context
+            .iter()
+            .map(|c| (
+                c["line"].as_u64().unwrap(),
+                c["code"].as_str().unwrap(),
+                c["highlight"].as_bool().unwrap_or(false),
+            ))
+            .collect();
+        assert_eq!(lines, vec![(2, "b = 2", false), (3, "a = 3", true), (4, "c = 4", false)]);
+    }
+
     #[test]
     fn test_check_error_json() {
         let output = Command::new(aura_binary())
@@ -81,6 +198,56 @@ mod check_command {
         assert_eq!(json["success"], false);
         assert!(!json["errors"].as_array().unwrap().is_empty());
     }
+
+    fn dir_with_good_and_bad_file() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("lib")).unwrap();
+        std::fs::write(dir.path().join("good.aura"), "main = 1\n").unwrap();
+        std::fs::write(dir.path().join("lib/bad.aura"), "main = 1\n\nmain = 2\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_check_directory_json() {
+        let dir = dir_with_good_and_bad_file();
+
+        let output = Command::new(aura_binary())
+            .args(["check", "--json"])
+            .arg(dir.path())
+            .output()
+            .expect("Failed to execute aura check");
+
+        // Sigue despues del archivo con errores y sale con el codigo de tipos
+        assert_eq!(output.status.code(), Some(3));
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["kind"], "check_dir");
+        assert_eq!(json["success"], false);
+        let results = json["files"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0]["file"].as_str().unwrap().ends_with("good.aura"));
+        assert_eq!(results[0]["success"], true);
+        assert!(results[1]["file"].as_str().unwrap().ends_with("bad.aura"));
+        assert_eq!(results[1]["success"], false);
+        assert!(!results[1]["errors"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_directory_human() {
+        let dir = dir_with_good_and_bad_file();
+        std::fs::remove_file(dir.path().join("lib/bad.aura")).unwrap();
+
+        let output = Command::new(aura_binary())
+            .arg("check")
+            .arg(dir.path())
+            .output()
+            .expect("Failed to execute aura check");
+
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("good.aura:"));
+        assert!(stdout.contains("1 file(s) checked, 0 with errors"));
+    }
 }
 
 mod run_command {
@@ -123,81 +290,1105 @@ mod run_command {
     }
 
     #[test]
-    fn test_run_nonexistent_file_json() {
+    fn test_bench_json_stats() {
         let output = Command::new(aura_binary())
-            .args(["run", "--json", "nonexistent.aura"])
+            .args(["bench", "--json", "-n", "5"])
+            .arg(examples_dir().join("simple.aura"))
+            .output()
+            .expect("Failed to execute aura bench");
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+            .expect("Output should be valid JSON");
+
+        assert_eq!(json["success"], true);
+        assert_eq!(json["iterations"], 5);
+        for field in ["min_us", "max_us", "mean_us", "median_us", "p95_us"] {
+            assert!(json[field].as_u64().is_some(), "missing {field}");
+        }
+        assert_eq!(json["runs_us"].as_array().unwrap().len(), 5);
+        assert!(json["min_us"].as_u64() <= json["p95_us"].as_u64());
+    }
+
+    #[test]
+    fn test_run_set_defines_globals() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("greet.aura");
+        std::fs::write(&path, "label() = \"{user}!\"\nmain = [label(), count + 1, verbose]\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--json", "--set", "user=alice", "--set", "count=3", "--set", "verbose=true"])
+            .arg(&path)
             .output()
             .expect("Failed to execute aura run");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+            .expect("Output should be valid JSON");
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let json: serde_json::Value = serde_json::from_str(&stdout)
+        assert_eq!(json["success"], true, "{}", json);
+        assert_eq!(json["result"], serde_json::json!(["alice!", 4, true]));
+
+        // Sin --set la variable no existe
+        let output = Command::new(aura_binary())
+            .args(["run", "--json"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura run");
+        assert_eq!(output.status.code(), Some(4));
+    }
+
+    #[test]
+    fn test_run_env_allowlist_warnings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("env.aura");
+        std::fs::write(&path, "+env(AURA_JSON_ALLOWED)\n\nmain = [env.get(\"AURA_JSON_ALLOWED\"), env.get(\"AURA_JSON_SECRET\")]\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--json"])
+            .arg(&path)
+            .env("AURA_JSON_ALLOWED", "ok")
+            .env("AURA_JSON_SECRET", "secreto")
+            .output()
+            .expect("Failed to execute aura run");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+            .expect("Output should be valid JSON");
+
+        assert_eq!(json["success"], true, "{}", json);
+        assert_eq!(json["result"], serde_json::json!(["ok", null]));
+        let warnings = json["warnings"].as_array().expect("warnings array");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].as_str().unwrap().contains("AURA_JSON_SECRET"));
+    }
+
+    #[test]
+    fn test_run_profile_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("profile.aura");
+        std::fs::write(&path, "helper(x) = x * 2\nmain = sum([helper(1), helper(2), helper(3)])\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--json", "--profile"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura run");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)
             .expect("Output should be valid JSON");
 
+        assert_eq!(json["success"], true);
+        assert_eq!(json["result"], 12);
+        let profile = json["profile"].as_array().expect("profile array");
+        assert_eq!(profile.len(), 1);
+        assert_eq!(profile[0]["name"], "helper");
+        assert_eq!(profile[0]["calls"], 3);
+        assert!(profile[0]["total_us"].as_u64().is_some());
+
+        // Sin --profile no hay campo
+        let output = Command::new(aura_binary())
+            .args(["run", "--json"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura run");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(json.get("profile").is_none());
+    }
+
+    #[test]
+    fn test_run_json_includes_observations() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("observe.aura");
+        std::fs::write(&file, "main = : response = {status: 404}; observe response.status where status >= 400; 1\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--json"])
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura run");
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], true);
+        assert_eq!(json["observations"][0]["target"], "response.status");
+        assert_eq!(json["observations"][0]["value"], 404);
+        assert_eq!(json["observations"][0]["type"], "Int");
+    }
+
+    #[test]
+    fn test_run_cognitive_trace_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("reason.aura");
+        std::fs::write(&file, "main = : x = reason \"como sigo?\"; 1\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--cognitive", "--trace", "--json"])
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura run");
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], true);
+        let trace = json["trace"].as_array().expect("trace should be an array");
+        assert_eq!(trace.len(), 1);
+        assert_eq!(trace[0]["step"], 1);
+        assert_eq!(trace[0]["trigger_type"], "reason");
+        assert!(trace[0]["decision"].is_string());
+
+        // Sin --trace no aparece el campo
+        let output = Command::new(aura_binary())
+            .args(["run", "--cognitive", "--json"])
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura run");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(json.get("trace").is_none());
+    }
+
+    #[test]
+    fn test_run_reason_without_cognitive() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("reason.aura");
+        std::fs::write(&file, "main = : x = reason \"como sigo?\"; x\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--json"])
+            .arg(&file)
+            .env_remove("AURA_REASON_DEFAULT")
+            .output()
+            .expect("Failed to execute aura run");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], true);
+        assert_eq!(json["result"], "como sigo?");
+        assert_eq!(json["type"], "String");
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--json"])
+            .arg(&file)
+            .env("AURA_REASON_DEFAULT", "reintentar")
+            .output()
+            .expect("Failed to execute aura run");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["result"], "reintentar");
+    }
+
+    #[test]
+    fn test_run_json_captures_print() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("print.aura");
+        std::fs::write(&file, "main = : print(\"hola\"); print(\"x = {1 + 1}\"); 42\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--json"])
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura run");
+
+        // Todo stdout es un unico documento JSON, sin texto suelto
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+            .expect("stdout should be only the JSON result");
+        assert_eq!(json["success"], true);
+        assert_eq!(json["result"], 42);
+        assert_eq!(json["stdout"], serde_json::json!(["hola", "x = 2"]));
+
+        // Sin --json se imprime directo
+        let output = Command::new(aura_binary())
+            .arg("run")
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura run");
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hola\nx = 2\n42\n");
+    }
+
+    #[test]
+    fn test_run_json_captures_print_before_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("print_error.aura");
+        std::fs::write(&file, "main = : print(\"antes\"); no_existe\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--json"])
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura run");
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
         assert_eq!(json["success"], false);
-        assert!(json["error"].is_object());
+        assert_eq!(json["stdout"], serde_json::json!(["antes"]));
     }
-}
 
-mod lex_command {
-    use super::*;
+    #[test]
+    fn test_run_failed_expect_exits_non_zero() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("expect.aura");
+        std::fs::write(&file, "main = : x = 0; expect x > 0 \"x debe ser positivo\"; print(\"despues\"); x\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--json"])
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura run");
+
+        assert!(!output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error"]["code"], "E402");
+        assert!(json["error"]["message"].as_str().unwrap().contains("x debe ser positivo"));
+        // El expect corta la ejecucion: el print siguiente no corre
+        assert_eq!(json["stdout"].as_array().map(|lines| lines.len()).unwrap_or(0), 0);
+    }
 
     #[test]
-    fn test_lex_json() {
+    fn test_run_max_steps_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("deep.aura");
+        std::fs::write(&file, "count(n) = if n == 0 0 else 1 + count(n - 1)\nmain = count(200)\n").unwrap();
+
         let output = Command::new(aura_binary())
-            .args(["lex", "--json"])
-            .arg(examples_dir().join("simple.aura"))
+            .args(["run", "--json", "--max-steps", "100"])
+            .arg(&file)
             .output()
-            .expect("Failed to execute aura lex");
+            .expect("Failed to execute aura run");
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let json: serde_json::Value = serde_json::from_str(&stdout)
+        let json: serde_json::Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout))
             .expect("Output should be valid JSON");
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error"]["message"], "execution budget exceeded");
 
-        // Should be an array of tokens
-        assert!(json.is_array());
-        let tokens = json.as_array().unwrap();
-        assert!(!tokens.is_empty());
+        let output = Command::new(aura_binary())
+            .args(["run", "--json", "--max-steps", "100000"])
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura run");
+
+        let json: serde_json::Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout))
+            .expect("Output should be valid JSON");
+        assert_eq!(json["success"], true);
+        assert_eq!(json["result"], 200);
     }
-}
 
-mod parse_command {
-    use super::*;
+    #[test]
+    fn test_run_returning_connection_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("conn.aura");
+        std::fs::write(&file, "+db\nmain = db.connect(\"sqlite::memory:\")\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--json"])
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura run");
+
+        let json: serde_json::Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout))
+            .expect("Output should be valid JSON");
+        assert_eq!(json["success"], true);
+        assert_eq!(json["type"], "Native");
+        assert_eq!(json["result"]["native"], "db:sqlite");
+        assert!(json["result"]["handle"].is_u64());
+
+        let output = Command::new(aura_binary())
+            .arg("run")
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura run");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.trim().starts_with("<db:sqlite#"), "got {}", stdout);
+    }
 
     #[test]
-    fn test_parse_json() {
+    fn test_run_nonexistent_file_json() {
         let output = Command::new(aura_binary())
-            .args(["parse", "--json"])
-            .arg(examples_dir().join("simple.aura"))
+            .args(["run", "--json", "nonexistent.aura"])
             .output()
-            .expect("Failed to execute aura parse");
+            .expect("Failed to execute aura run");
 
         let stdout = String::from_utf8_lossy(&output.stdout);
         let json: serde_json::Value = serde_json::from_str(&stdout)
             .expect("Output should be valid JSON");
 
-        // Should have capabilities and definitions
-        assert!(json["capabilities"].is_array());
-        assert!(json["definitions"].is_array());
+        assert_eq!(json["success"], false);
+        assert!(json["error"].is_object());
     }
 }
 
-mod info_command {
+mod goal_checks {
     use super::*;
 
+    fn write_program(dir: &tempfile::TempDir, min: usize) -> PathBuf {
+        let path = dir.path().join("goals.aura");
+        let source = format!(
+            "goal \"hay datos\" check len(datos) > {}\nmain = : datos = [1, 2, 3]; len(datos)\n",
+            min
+        );
+        std::fs::write(&path, source).unwrap();
+        path
+    }
+
     #[test]
-    fn test_info_json() {
+    fn test_run_json_reports_goals() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_program(&dir, 0);
+
         let output = Command::new(aura_binary())
-            .args(["info", "--json"])
+            .args(["run", "--json"])
+            .arg(&path)
             .output()
-            .expect("Failed to execute aura info");
+            .expect("Failed to execute aura run");
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let json: serde_json::Value = serde_json::from_str(&stdout)
-            .expect("Output should be valid JSON");
+        assert!(output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], true);
+        assert_eq!(json["result"], 3);
+        assert_eq!(json["goals"][0]["description"], "hay datos");
+        assert_eq!(json["goals"][0]["passed"], true);
+    }
 
-        assert_eq!(json["name"], "AURA");
-        assert!(json["version"].as_str().is_some());
-        assert!(json["capabilities"].is_array());
-        assert!(json["features"].is_object());
+    #[test]
+    fn test_failed_goal_does_not_fail_run_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_program(&dir, 10);
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--json"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura run");
+
+        assert!(output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], true);
+        assert_eq!(json["goals"][0]["passed"], false);
+        assert_eq!(json["goals"][0]["value"], false);
+    }
+
+    #[test]
+    fn test_enforce_goals_fails_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_program(&dir, 10);
+
+        let output = Command::new(aura_binary())
+            .args(["run", "--json", "--enforce-goals"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura run");
+
+        assert_eq!(output.status.code(), Some(4));
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error"]["code"], "E403");
+        assert!(json["error"]["message"].as_str().unwrap().contains("hay datos"));
+    }
+}
+
+mod test_command {
+    use super::*;
+
+    fn run_tests(source: &str) -> (std::process::ExitStatus, serde_json::Value) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tests.aura");
+        std::fs::write(&path, source).unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["test", "--json"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura test");
+        let json = serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+        (output.status, json)
+    }
+
+    #[test]
+    fn test_passing_tests_json() {
+        let (status, json) = run_tests("#test suma: 1 + 1 == 2\n#test doble: double(3) == 6\ndouble(x) = x * 2\nmain = 1\n");
+        assert!(status.success());
+        assert_eq!(json["kind"], "test");
+        assert_eq!(json["success"], true);
+        assert_eq!(json["passed"], 2);
+        assert_eq!(json["failed"], 0);
+        assert_eq!(json["tests"][1]["name"], "doble");
+    }
+
+    #[test]
+    fn test_failing_tests_json() {
+        let (status, json) = run_tests("#test suma: 1 + 1 == 3\n#test resta: 2 - 1 == 1\nmain = 1\n");
+        assert_eq!(status.code(), Some(1));
+        assert_eq!(json["success"], false);
+        assert_eq!(json["passed"], 1);
+        assert_eq!(json["failed"], 1);
+        assert_eq!(json["tests"][0]["passed"], false);
+        assert_eq!(json["tests"][0]["value"], 2);
+        assert_eq!(json["tests"][0]["expected"], 3);
+    }
+}
+
+mod lex_command {
+    use super::*;
+
+    #[test]
+    fn test_lex_json() {
+        let output = Command::new(aura_binary())
+            .args(["lex", "--json"])
+            .arg(examples_dir().join("simple.aura"))
+            .output()
+            .expect("Failed to execute aura lex");
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let json: serde_json::Value = serde_json::from_str(&stdout)
+            .expect("Output should be valid JSON");
+
+        // Should be an array of tokens
+        assert!(json.is_array());
+        let tokens = json.as_array().unwrap();
+        assert!(!tokens.is_empty());
+    }
+
+    #[test]
+    fn test_lex_errors_report_line_and_col() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.aura");
+        std::fs::write(&path, "x = 1\ny = 2 $\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .arg("lex")
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura lex");
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("error at line 2 col 7"), "{}", stderr);
+
+        // El JSON sigue usando offsets en bytes
+        let output = Command::new(aura_binary())
+            .args(["lex", "--json"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura lex");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json[0]["span"]["start"], 12);
+    }
+}
+
+mod parse_command {
+    use super::*;
+
+    #[test]
+    fn test_parse_json() {
+        let output = Command::new(aura_binary())
+            .args(["parse", "--json"])
+            .arg(examples_dir().join("simple.aura"))
+            .output()
+            .expect("Failed to execute aura parse");
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let json: serde_json::Value = serde_json::from_str(&stdout)
+            .expect("Output should be valid JSON");
+
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["kind"], "parse");
+        assert_eq!(json["success"], true);
+        // Should have capabilities and definitions
+        assert!(json["program"]["capabilities"].is_array());
+        assert!(json["program"]["definitions"].is_array());
+    }
+
+    #[test]
+    fn test_parse_json_reports_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("bad.aura");
+        std::fs::write(&file, "double(x = x * 2\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["parse", "--json"])
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura parse");
+
+        assert_eq!(output.status.code(), Some(2));
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["kind"], "parse");
+        assert_eq!(json["success"], false);
+        assert!(json["program"].is_null());
+        assert!(json["errors"][0]["message"].as_str().unwrap().contains("parsing"));
+    }
+}
+
+mod info_command {
+    use super::*;
+
+    #[test]
+    fn test_info_json() {
+        let output = Command::new(aura_binary())
+            .args(["info", "--json"])
+            .output()
+            .expect("Failed to execute aura info");
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let json: serde_json::Value = serde_json::from_str(&stdout)
+            .expect("Output should be valid JSON");
+
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["kind"], "info");
+        assert_eq!(json["name"], "AURA");
+        assert!(json["version"].as_str().is_some());
+        assert!(json["capabilities"].is_array());
+        assert!(json["features"].is_object());
+    }
+}
+
+mod explain_command {
+    use super::*;
+
+    #[test]
+    fn test_explain_json() {
+        let output = Command::new(aura_binary())
+            .args(["explain", "E501", "--json"])
+            .output()
+            .expect("Failed to execute aura explain");
+
+        assert!(output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+            .expect("Output should be valid JSON");
+
+        assert_eq!(json["success"], true);
+        assert_eq!(json["matched"], "E501");
+        assert!(json["description"].as_str().is_some());
+        assert!(!json["causes"].as_array().unwrap().is_empty());
+        assert!(json["fix"].as_str().is_some());
+    }
+
+    #[test]
+    fn test_explain_falls_back_to_category() {
+        let output = Command::new(aura_binary())
+            .args(["explain", "E305", "--json"])
+            .output()
+            .expect("Failed to execute aura explain");
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+            .expect("Output should be valid JSON");
+        assert_eq!(json["code"], "E305");
+        assert_eq!(json["matched"], "E3xx");
+    }
+
+    #[test]
+    fn test_explain_unknown_code() {
+        let output = Command::new(aura_binary())
+            .args(["explain", "E777", "--json"])
+            .output()
+            .expect("Failed to execute aura explain");
+
+        assert!(!output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+            .expect("Output should be valid JSON");
+        assert_eq!(json["success"], false);
+        assert!(json["error"].as_str().unwrap().contains("E777"));
+    }
+}
+
+mod complete_command {
+    use super::*;
+
+    #[test]
+    fn test_complete_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.aura");
+        let source = "+http\ndouble(x) = x * 2\nmain = d";
+        std::fs::write(&path, source).unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["complete", "--json"])
+            .arg(&path)
+            .arg(source.len().to_string())
+            .output()
+            .expect("Failed to execute aura complete");
+
+        assert!(output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], true);
+        assert_eq!(json["prefix"], "d");
+        let candidates = json["candidates"].as_array().unwrap();
+        assert_eq!(candidates[0]["name"], "double");
+        assert_eq!(candidates[0]["kind"], "function");
+        assert!(candidates.iter().any(|c| c["name"] == "dedup" && c["kind"] == "builtin"));
+        assert!(candidates.iter().all(|c| c["name"].as_str().unwrap().starts_with('d')));
+    }
+
+    #[test]
+    fn test_complete_offset_out_of_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.aura");
+        std::fs::write(&path, "main = 1").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["complete", "--json"])
+            .arg(&path)
+            .arg("100")
+            .output()
+            .expect("Failed to execute aura complete");
+
+        assert_eq!(output.status.code(), Some(1));
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], false);
+        assert_eq!(json["errors"][0]["code"], "E002");
+    }
+}
+
+mod symbols_command {
+    use super::*;
+
+    #[test]
+    fn test_symbols_json() {
+        let output = Command::new(aura_binary())
+            .args(["symbols", "--json"])
+            .arg(examples_dir().join("cognitive_config.aura"))
+            .output()
+            .expect("Failed to execute aura symbols");
+
+        assert!(output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], true);
+        let symbols = json["symbols"].as_array().unwrap();
+        let count = |kind: &str| symbols.iter().filter(|s| s["kind"] == kind).count();
+        assert_eq!(count("goal"), 2);
+        assert_eq!(count("invariant"), 1);
+        assert_eq!(count("function"), 3);
+
+        let main = symbols.iter().find(|s| s["name"] == "main").unwrap();
+        assert_eq!(main["start"]["col"], 1);
+        assert!(main["end"]["line"].as_u64() >= main["start"]["line"].as_u64());
+    }
+
+    #[test]
+    fn test_symbols_parse_error_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.aura");
+        std::fs::write(&path, "main = (1 +\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["symbols", "--json"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura symbols");
+
+        assert_eq!(output.status.code(), Some(2));
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], false);
+        assert!(json["symbols"].as_array().unwrap().is_empty());
+    }
+}
+
+mod docs_command {
+    use super::*;
+
+    #[test]
+    fn test_docs_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.aura");
+        std::fs::write(&path, "#doc Un usuario\n@User {\n    #doc Correo de contacto\n    email :s\n}\n\n#doc Duplica un numero\ndouble(x) = x * 2\nmain = double(2)\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["docs", "--json"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura docs");
+        assert!(output.status.success());
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+            .expect("Output should be valid JSON");
+        assert_eq!(json["kind"], "docs");
+        let docs = json["docs"].as_array().unwrap();
+        assert_eq!(docs.len(), 3);
+        assert_eq!(docs[0]["fields"][0]["doc"], "Correo de contacto");
+        assert_eq!(docs[1]["name"], "double");
+        assert_eq!(docs[1]["kind"], "function");
+        assert_eq!(docs[1]["doc"], "Duplica un numero");
+        assert_eq!(docs[1]["signature"], "double(x)");
+        assert!(docs[2]["doc"].is_null());
+    }
+}
+
+mod heal_command {
+    use super::*;
+
+    #[test]
+    fn test_heal_rejects_fix_violating_invariant_json() {
+        // El mock reemplaza api_url por una URL de jsonplaceholder
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("app.aura");
+        std::fs::write(&file, "invariant !contains(source, \"jsonplaceholder\")\nmain = api_url\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["heal", "--json", "--apply"])
+            .arg(&file)
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to execute aura heal");
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let json: serde_json::Value = serde_json::from_str(stdout.trim())
+            .expect("Output should be valid JSON");
+
+        assert_eq!(json["kind"], "heal");
+        assert_eq!(json["success"], false);
+        assert_eq!(json["cannot_fix"], true);
+        assert!(json["reason"].as_str().unwrap().contains("jsonplaceholder"));
+        // El archivo no se modifica
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "invariant !contains(source, \"jsonplaceholder\")\nmain = api_url\n"
+        );
+    }
+    #[test]
+    fn test_heal_memory_does_not_overwrite_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.aura");
+        let b = dir.path().join("b.aura");
+        std::fs::write(&a, "main = api_url\n").unwrap();
+        std::fs::write(&b, "total(x)=x*2\nmain = total(foo)\n").unwrap();
+
+        let heal = |file: &std::path::Path| -> serde_json::Value {
+            let output = Command::new(aura_binary())
+                .args(["heal", "--json", "--apply"])
+                .arg(file)
+                .current_dir(dir.path())
+                .output()
+                .expect("Failed to execute aura heal");
+            serde_json::from_str(String::from_utf8_lossy(&output.stdout).trim())
+                .expect("Output should be valid JSON")
+        };
+
+        // El fix de a.aura queda en memoria como archivo completo
+        let json = heal(&a);
+        assert_eq!(json["saved_to_memory"], true);
+        let fixed_a = std::fs::read_to_string(&a).unwrap();
+
+        // b.aura tiene el mismo tipo de error pero otro codigo: va al agente
+        let json = heal(&b);
+        assert!(json["from_memory"].is_null(), "{}", json);
+        let healed_b = std::fs::read_to_string(&b).unwrap();
+        assert_ne!(healed_b, fixed_a);
+        assert!(healed_b.contains("total"), "{}", healed_b);
+
+        // El mismo archivo de nuevo si reusa el fix guardado
+        std::fs::write(&a, "main = api_url\n").unwrap();
+        let json = heal(&a);
+        assert_eq!(json["from_memory"], true);
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), fixed_a);
+    }
+
+    #[test]
+    fn test_heal_attempts_are_logged() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("app.aura");
+        std::fs::write(&file, "invariant !contains(source, \"jsonplaceholder\")\nmain = api_url\n").unwrap();
+
+        // Dos intentos: el log crece en vez de reescribirse
+        for _ in 0..2 {
+            Command::new(aura_binary())
+                .args(["heal", "--json"])
+                .arg(&file)
+                .current_dir(dir.path())
+                .output()
+                .expect("Failed to execute aura heal");
+        }
+
+        let log = std::fs::read_to_string(dir.path().join(".aura/healing_log.jsonl")).unwrap();
+        assert_eq!(log.lines().count(), 2);
+
+        let output = Command::new(aura_binary())
+            .args(["heal-log", "--json", "-n", "1", "--outcome", "cannot_fix"])
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to execute aura heal-log");
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let json: serde_json::Value = serde_json::from_str(stdout.trim())
+            .expect("Output should be valid JSON");
+
+        assert_eq!(json["kind"], "heal_log");
+        assert_eq!(json["success"], true);
+        assert_eq!(json["total"], 2);
+        let entries = json["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["outcome"], "cannot_fix");
+        assert_eq!(entries[0]["provider"], "mock");
+        assert!(entries[0]["error"].as_str().unwrap().contains("api_url"));
+        assert!(entries[0]["confidence"].is_number());
+    }
+
+    #[test]
+    fn test_heal_log_rejects_unknown_outcome() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = Command::new(aura_binary())
+            .args(["heal-log", "--json", "--outcome", "bogus"])
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to execute aura heal-log");
+
+        assert!(!output.status.success());
+        let json: serde_json::Value = serde_json::from_str(String::from_utf8_lossy(&output.stdout).trim()).unwrap();
+        assert_eq!(json["kind"], "heal_log");
+        assert_eq!(json["success"], false);
+    }
+
+    #[test]
+    fn test_heal_parse_error_reports_span_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("app.aura");
+        std::fs::write(&file, "main = 1\nbroken = (1 +\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["heal", "--json"])
+            .arg(&file)
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to execute aura heal");
+
+        assert_eq!(output.status.code(), Some(2));
+        let json: serde_json::Value = serde_json::from_str(String::from_utf8_lossy(&output.stdout).trim())
+            .expect("Output should be valid JSON");
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["kind"], "heal");
+        assert_eq!(json["success"], false);
+        assert_eq!(json["stage"], "parse");
+        assert!(json["span"]["start"].as_u64().unwrap() >= 9);
+        assert!(json["span"]["end"].as_u64().unwrap() >= json["span"]["start"].as_u64().unwrap());
+        assert_eq!(json["location"]["line"], 2);
+    }
+
+    #[test]
+    fn test_heal_demo_without_colors() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("app.aura");
+        std::fs::write(&file, "main = 1 + 1\n").unwrap();
+
+        for args in [vec!["heal"], vec!["--no-color", "heal"]] {
+            let output = Command::new(aura_binary())
+                .args(&args)
+                .arg(&file)
+                .env("NO_COLOR", if args.len() == 1 { "1" } else { "" })
+                .current_dir(dir.path())
+                .output()
+                .expect("Failed to execute aura heal");
+
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(stdout.contains("no healing needed"));
+            assert!(!stdout.contains('\x1b'), "ANSI escape with {:?}: {}", args, stdout);
+        }
+    }
+}
+
+mod memory_command {
+    use super::*;
+
+    fn aura_json(dir: &std::path::Path, args: &[&str]) -> serde_json::Value {
+        let output = Command::new(aura_binary())
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .expect("Failed to execute aura memory");
+        serde_json::from_str(String::from_utf8_lossy(&output.stdout).trim())
+            .expect("Output should be valid JSON")
+    }
+
+    #[test]
+    fn test_export_then_import_merges_counts() {
+        let team = tempfile::tempdir().unwrap();
+        let pattern = r#"{"error":"Variable no definida: x","context":"","fix":"x = 1","count":2,"last_used":"2024-01-01T00:00:00Z"}"#;
+        std::fs::write(
+            team.path().join(".aura-memory.json"),
+            format!(r#"{{"version":"2.0","patterns":[{}],"project_defaults":{{"timeout":"30"}}}}"#, pattern),
+        ).unwrap();
+
+        let json = aura_json(team.path(), &["memory", "export", "shared.json", "--json"]);
+        assert_eq!(json["kind"], "memory_export");
+        assert_eq!(json["success"], true);
+        assert_eq!(json["patterns"], 1);
+
+        // Otro proyecto ya conoce el mismo error y tiene otro timeout
+        let local = tempfile::tempdir().unwrap();
+        std::fs::write(
+            local.path().join(".aura-memory.json"),
+            format!(r#"{{"version":"2.0","patterns":[{}],"project_defaults":{{"timeout":"60"}}}}"#, pattern.replace("\"count\":2", "\"count\":3")),
+        ).unwrap();
+        let shared = team.path().join("shared.json");
+
+        let json = aura_json(local.path(), &["memory", "import", shared.to_str().unwrap(), "--merge", "--json"]);
+        assert_eq!(json["kind"], "memory_import");
+        assert_eq!(json["success"], true);
+        assert_eq!(json["updated"], 1);
+        assert_eq!(json["conflicts"], serde_json::json!(["timeout"]));
+
+        let json = aura_json(local.path(), &["memory", "list", "--json"]);
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["kind"], "memory_list");
+        assert_eq!(json["patterns"][0]["count"], 5);
+        assert_eq!(json["defaults"]["timeout"], "60");
+
+        aura_json(local.path(), &["memory", "import", shared.to_str().unwrap(), "--force", "--json"]);
+        let json = aura_json(local.path(), &["memory", "list", "--json"]);
+        assert_eq!(json["patterns"][0]["count"], 2);
+        assert_eq!(json["defaults"]["timeout"], "30");
+    }
+
+    #[test]
+    fn test_import_missing_file_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = aura_json(dir.path(), &["memory", "import", "nope.json", "--json"]);
+        assert_eq!(json["kind"], "memory_import");
+        assert_eq!(json["success"], false);
+        assert!(!dir.path().join(".aura-memory.json").exists());
+    }
+
+    #[test]
+    fn test_defaults_and_clear_json() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let json = aura_json(dir.path(), &["memory", "defaults", "--set", "timeout=30", "--json"]);
+        assert_eq!(json["kind"], "memory_defaults");
+        assert_eq!(json["action"], "set");
+        assert_eq!(json["value"], "30");
+
+        let json = aura_json(dir.path(), &["memory", "defaults", "--json"]);
+        assert_eq!(json["kind"], "memory_defaults");
+        assert_eq!(json["defaults"]["timeout"], "30");
+
+        let json = aura_json(dir.path(), &["memory", "defaults", "--remove", "missing", "--json"]);
+        assert_eq!(json["kind"], "memory_defaults");
+        assert_eq!(json["success"], false);
+        assert!(json["error"].as_str().unwrap().contains("missing"));
+
+        let json = aura_json(dir.path(), &["memory", "clear", "--all", "--json"]);
+        assert_eq!(json["kind"], "memory_clear");
+        assert_eq!(json["defaults_cleared"], 1);
+    }
+}
+
+mod serve_command {
+    use super::*;
+
+    #[test]
+    fn test_serve_without_routes_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.aura");
+        std::fs::write(&path, "main = 1\n").unwrap();
+
+        let output = Command::new(aura_binary())
+            .args(["serve", "--json"])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute aura serve");
+
+        assert_eq!(output.status.code(), Some(1));
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+            .expect("Output should be valid JSON");
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["kind"], "serve");
+        assert_eq!(json["success"], false);
+        assert!(json["error"].as_str().unwrap().contains("No routes"));
+    }
+}
+
+mod seed {
+    use super::*;
+
+    fn random_result(file: &std::path::Path, seed: Option<&str>) -> serde_json::Value {
+        let mut cmd = Command::new(aura_binary());
+        if let Some(seed) = seed {
+            cmd.args(["--seed", seed]);
+        }
+        let output = cmd.args(["run", "--json"]).arg(file).output().expect("Failed to execute aura run");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["success"], true, "{}", json);
+        json["result"].clone()
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_random_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("random.aura");
+        std::fs::write(&file, "+crypto\nmain = crypto.sha256(crypto.random_bytes(16)) ++ crypto.sha256(crypto.random_bytes(16))\n").unwrap();
+
+        let first = random_result(&file, Some("7"));
+        assert_eq!(first, random_result(&file, Some("7")));
+        assert_ne!(first, random_result(&file, Some("8")));
+        // Sin semilla cada corrida es distinta
+        assert_ne!(random_result(&file, None), random_result(&file, None));
+    }
+}
+
+mod exit_codes {
+    use super::*;
+
+    fn status_of(args: &[&str], source: Option<&str>) -> (i32, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("prog.aura");
+        if let Some(source) = source {
+            std::fs::write(&file, source).unwrap();
+        }
+        let output = Command::new(aura_binary())
+            .args(args)
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura");
+        (output.status.code().unwrap(), String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    #[test]
+    fn test_success_is_zero() {
+        assert_eq!(status_of(&["run"], Some("main = 1\n")).0, 0);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(status_of(&["run"], Some("main = (1 +\n")).0, 2);
+        assert_eq!(status_of(&["check", "--json"], Some("main = (1 +\n")).0, 2);
+        assert_eq!(status_of(&["fmt"], Some("main = (1 +\n")).0, 2);
+    }
+
+    #[test]
+    fn test_type_error() {
+        assert_eq!(status_of(&["check"], Some("main = no_existe\n")).0, 3);
+    }
+
+    #[test]
+    fn test_runtime_error() {
+        assert_eq!(status_of(&["run"], Some("main = no_existe\n")).0, 4);
+        assert_eq!(status_of(&["run", "--json"], Some("main = : expect false \"nunca\"; 1\n")).0, 4);
+    }
+
+    #[test]
+    fn test_missing_file_is_io() {
+        let (code, stdout) = status_of(&["run", "--json"], None);
+        assert_eq!(code, 5);
+        let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(json["error"]["code"], "E501");
+        assert_eq!(status_of(&["check"], None).0, 5);
+    }
+
+    #[test]
+    fn test_bad_arguments_are_generic_failure() {
+        let output = Command::new(aura_binary())
+            .args(["run", "--no-such-flag", "x.aura"])
+            .output()
+            .expect("Failed to execute aura");
+        assert_eq!(output.status.code(), Some(1));
+    }
+
+    #[test]
+    fn test_quiet_suppresses_chatter_keeps_code() {
+        let (code, stdout) = status_of(&["check"], Some("main = 1\n"));
+        assert_eq!(code, 0);
+        assert!(stdout.contains("Valid program"));
+
+        let (code, stdout) = status_of(&["check", "--quiet"], Some("main = 1\n"));
+        assert_eq!(code, 0);
+        assert_eq!(stdout, "");
+
+        // Es global: tambien va antes del subcomando, y no oculta los resultados
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("prog.aura");
+        std::fs::write(&file, "main = 7\n").unwrap();
+        let output = Command::new(aura_binary())
+            .args(["-q", "run"])
+            .arg(&file)
+            .output()
+            .expect("Failed to execute aura");
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "7\n");
+
+        assert_eq!(status_of(&["check", "-q"], Some("main = no_existe\n")).0, 3);
     }
 }
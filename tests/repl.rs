@@ -0,0 +1,239 @@
+//! Integration tests for the interactive REPL.
+//!
+//! Drives `aura repl` through stdin and checks what it prints.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+fn aura_binary() -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("target");
+    path.push("debug");
+    path.push("aura");
+    path
+}
+
+/// Ejecuta el REPL con la entrada dada y retorna (stdout, stderr)
+///
+/// Corre en un directorio temporal para que el historial (`.aura/repl_history`)
+/// no quede en el repo.
+fn run_repl(input: &str) -> (String, String) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut child = Command::new(aura_binary())
+        .arg("repl")
+        .current_dir(dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start aura repl");
+
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().expect("Failed to wait for aura repl");
+
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
+
+fn temp_file(dir: &std::path::Path, content: &str) -> PathBuf {
+    let path = dir.join("loaded.aura");
+    fs::write(&path, content).unwrap();
+    path
+}
+
+mod load_command {
+    use super::*;
+
+    #[test]
+    fn test_load_defines_functions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_file(dir.path(), "double(x) = x * 2\nmain = double(4)\n");
+
+        let (stdout, _) = run_repl(&format!(":load {}\ndouble(21)\nexit\n", path.display()));
+
+        assert!(stdout.contains("Cargado"));
+        assert!(stdout.contains("2 funciones"));
+        assert!(stdout.contains("42"));
+    }
+
+    #[test]
+    fn test_load_missing_file_keeps_repl_alive() {
+        let (stdout, stderr) = run_repl(":load does_not_exist.aura\n1 + 1\nexit\n");
+
+        assert!(stderr.contains("Error cargando"));
+        assert!(stdout.contains('2'));
+    }
+
+    #[test]
+    fn test_reload_picks_up_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_file(dir.path(), "double(x) = x * 2\n");
+
+        let mut child = Command::new(aura_binary())
+            .arg("repl")
+            .current_dir(dir.path())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Failed to start aura repl");
+
+        let mut stdin = child.stdin.take().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        writeln!(stdin, ":load {}", path.display()).unwrap();
+        stdin.flush().unwrap();
+
+        // El archivo se reescribe recien cuando el REPL confirma el :load
+        let mut line = String::new();
+        while !line.contains("Cargado") {
+            line.clear();
+            assert!(stdout.read_line(&mut line).unwrap() > 0, "el REPL termino antes de cargar");
+        }
+        fs::write(&path, "double(x) = x * 3\n").unwrap();
+        writeln!(stdin, ":reload\ndouble(5)\nexit").unwrap();
+        drop(stdin);
+
+        let mut rest = String::new();
+        std::io::Read::read_to_string(&mut stdout, &mut rest).unwrap();
+        child.wait().unwrap();
+
+        assert!(rest.contains("Recargado"));
+        assert!(rest.contains("15"));
+    }
+
+    #[test]
+    fn test_reload_without_load() {
+        let (_, stderr) = run_repl(":reload\nexit\n");
+        assert!(stderr.contains("No hay archivo cargado"));
+    }
+
+    #[test]
+    fn test_failed_load_is_not_reloaded() {
+        let (_, stderr) = run_repl(":load does_not_exist.aura\n:reload\nexit\n");
+        assert!(stderr.contains("Error cargando"));
+        assert!(stderr.contains("No hay archivo cargado"));
+    }
+}
+
+mod multiline_input {
+    use super::*;
+
+    #[test]
+    fn test_type_def_across_lines() {
+        let (stdout, stderr) = run_repl("@User {\n  id:i @pk\n  name:s\n}\nexit\n");
+
+        assert!(stdout.contains("... "));
+        assert!(stdout.contains("<type User>"));
+        assert!(stderr.is_empty());
+    }
+
+    #[test]
+    fn test_function_body_on_next_line() {
+        let (stdout, _) = run_repl("double(x) =\n  x * 2\ndouble(4)\nexit\n");
+
+        assert!(stdout.contains("<fn double>"));
+        assert!(stdout.contains('8'));
+    }
+
+    #[test]
+    fn test_blank_line_forces_evaluation() {
+        let (stdout, stderr) = run_repl("add(1,\n\n1 + 1\nexit\n");
+
+        assert!(stderr.contains("Error de parsing"));
+        assert!(stdout.contains('2'));
+    }
+}
+
+mod introspection {
+    use super::*;
+
+    #[test]
+    fn test_types_lists_defined_types() {
+        let (stdout, _) = run_repl("?types\n@User {\n  id:i @pk\n  name:s?\n}\n?types\n?type User\n?type Nope\nexit\n");
+
+        assert!(stdout.contains("Tipos definidos: (ninguno)"));
+        assert!(stdout.contains("Tipos definidos: User"));
+        assert!(stdout.contains("@User {"));
+        assert!(stdout.contains("id :i @pk"));
+        assert!(stdout.contains("name :s?"));
+        assert!(stdout.contains("Tipo no definido: Nope"));
+    }
+
+    #[test]
+    fn test_signature_and_funcs_arity() {
+        let (stdout, _) = run_repl("area(w, h) = w * h\nsend!(...parts) = parts\n?funcs\n?signature area\n?signature send\n?signature nope\nexit\n");
+
+        assert!(stdout.contains("Funciones definidas: area/2, send/0+"));
+        assert!(stdout.contains("area(w, h) - efecto: no"));
+        assert!(stdout.contains("send!(...parts) - efecto: si"));
+        assert!(stdout.contains("Funcion no definida: nope"));
+    }
+}
+
+mod json_errors {
+    use super::*;
+
+    #[test]
+    fn test_errors_are_human_by_default() {
+        let (_, stderr) = run_repl("nope\nexit\n");
+        assert!(stderr.contains("Error de ejecucion"));
+    }
+
+    #[test]
+    fn test_json_on_prints_one_error_object_per_line() {
+        let (stdout, stderr) = run_repl(":json on\nnope\n:load does_not_exist.aura\nexit\n");
+
+        assert!(stdout.contains("JSON errors: ON"));
+        let errors: Vec<serde_json::Value> = stderr.lines()
+            .map(|l| serde_json::from_str(l).expect("each error line should be JSON"))
+            .collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0]["code"], "E401");
+        assert_eq!(errors[1]["code"], "E001");
+    }
+
+    #[test]
+    fn test_json_off_restores_human_errors() {
+        let (stdout, stderr) = run_repl(":json on\n:json off\nnope\nexit\n");
+
+        assert!(stdout.contains("JSON errors: OFF"));
+        assert!(stderr.contains("Error de ejecucion"));
+    }
+}
+
+mod history {
+    use super::*;
+
+    fn run_repl_in(dir: &std::path::Path, input: &str) {
+        let mut child = Command::new(aura_binary())
+            .arg("repl")
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start aura repl");
+        child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+        assert!(child.wait().unwrap().success());
+    }
+
+    #[test]
+    fn test_history_persists_between_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = dir.path().join(".aura").join("repl_history");
+
+        run_repl_in(dir.path(), "1 + 1\nexit\n");
+        let first = fs::read_to_string(&history).unwrap();
+        assert!(first.contains("1 + 1"));
+
+        // La segunda sesion carga el historial y agrega al final
+        run_repl_in(dir.path(), "2 + 2\n");
+        let second = fs::read_to_string(&history).unwrap();
+        let entries: Vec<&str> = second.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(entries, vec!["1 + 1", "exit", "2 + 2"]);
+    }
+}
@@ -0,0 +1,237 @@
+//! Integration tests for `aura serve`.
+//!
+//! Starts the server on a free port and talks to it over HTTP.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+fn aura_binary() -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("target");
+    path.push("debug");
+    path.push("aura");
+    path
+}
+
+/// Servidor levantado para un test; se detiene al salir de scope
+struct Server {
+    child: Child,
+    port: u16,
+    _file: tempfile::NamedTempFile,
+}
+
+impl Server {
+    fn start(source: &str) -> Self {
+        let mut file = tempfile::Builder::new().suffix(".aura").tempfile().unwrap();
+        file.write_all(source.as_bytes()).unwrap();
+
+        // Puerto libre asignado por el sistema
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        let child = Command::new(aura_binary())
+            .args(["serve", "--port", &port.to_string()])
+            .arg(file.path())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start aura serve");
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            assert!(Instant::now() < deadline, "server did not start");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+
+        Server { child, port, _file: file }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("http://127.0.0.1:{}{}", self.port, path)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+mod request_binding {
+    use super::*;
+
+    const SOURCE: &str = r#"
+post_user(req) = {created: true, name: req.name}
+put_user(id req) = {id: id, name: req.name}
+get_user(id) = {id: id}
+get_search(request) = {q: request.query.q}
+"#;
+
+    #[test]
+    fn test_post_body_is_bound_to_req() {
+        let server = Server::start(SOURCE);
+
+        let response = reqwest::blocking::Client::new()
+            .post(server.url("/user"))
+            .json(&serde_json::json!({"name": "Ada"}))
+            .send()
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().unwrap();
+        assert_eq!(body["created"], true);
+        assert_eq!(body["name"], "Ada");
+    }
+
+    #[test]
+    fn test_put_binds_path_param_and_body() {
+        let server = Server::start(SOURCE);
+
+        let body: serde_json::Value = reqwest::blocking::Client::new()
+            .put(server.url("/user/42"))
+            .json(&serde_json::json!({"name": "Grace"}))
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+
+        assert_eq!(body["id"], 42);
+        assert_eq!(body["name"], "Grace");
+    }
+
+    #[test]
+    fn test_get_binds_path_param_and_full_request() {
+        let server = Server::start(SOURCE);
+
+        let body: serde_json::Value = reqwest::blocking::get(server.url("/user/7")).unwrap().json().unwrap();
+        assert_eq!(body["id"], 7);
+
+        let body: serde_json::Value = reqwest::blocking::get(server.url("/search?q=aura")).unwrap().json().unwrap();
+        assert_eq!(body["q"], "aura");
+    }
+}
+
+mod explicit_routes {
+    use super::*;
+
+    const SOURCE: &str = r#"
+route "GET" "/api/v2/people/:id" get_user
+get_user(id) = {id: id}
+get_health = {ok: true}
+"#;
+
+    #[test]
+    fn test_explicit_route_path_is_honored() {
+        let server = Server::start(SOURCE);
+
+        let response = reqwest::blocking::get(server.url("/api/v2/people/5")).unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().unwrap();
+        assert_eq!(body["id"], 5);
+
+        // El handler explicito ya no se expone por convencion
+        let response = reqwest::blocking::get(server.url("/user/5")).unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    #[test]
+    fn test_convention_still_applies_to_other_functions() {
+        let server = Server::start(SOURCE);
+
+        let body: serde_json::Value = reqwest::blocking::get(server.url("/health")).unwrap().json().unwrap();
+        assert_eq!(body["ok"], true);
+    }
+}
+
+mod status_codes {
+    use super::*;
+
+    const SOURCE: &str = r#"
+get_item(id) = if id == 1 {id: 1, name: "uno"} else {status: 404, body: {error: "Item no encontrado"}}
+get_ratio(id) = 10 / (id - id)
+"#;
+
+    #[test]
+    fn test_plain_value_returns_200() {
+        let server = Server::start(SOURCE);
+
+        let response = reqwest::blocking::get(server.url("/item/1")).unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().unwrap();
+        assert_eq!(body["name"], "uno");
+    }
+
+    #[test]
+    fn test_status_record_sets_status_and_body() {
+        let server = Server::start(SOURCE);
+
+        let response = reqwest::blocking::get(server.url("/item/2")).unwrap();
+        assert_eq!(response.status(), 404);
+        let body: serde_json::Value = response.json().unwrap();
+        assert_eq!(body, serde_json::json!({"error": "Item no encontrado"}));
+    }
+
+    #[test]
+    fn test_runtime_error_returns_500_with_json_error() {
+        let server = Server::start(SOURCE);
+
+        let response = reqwest::blocking::get(server.url("/ratio/3")).unwrap();
+        assert_eq!(response.status(), 500);
+        let body: serde_json::Value = response.json().unwrap();
+        assert_eq!(body["code"], "E401");
+        assert!(body["error"].is_string());
+    }
+}
+
+mod health {
+    use super::*;
+
+    #[test]
+    fn test_builtin_health_endpoint() {
+        let server = Server::start("get_users = []\npost_user(req) = req\n");
+
+        let response = reqwest::blocking::get(server.url("/health")).unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().unwrap();
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(body["routes"], 2);
+    }
+
+    #[test]
+    fn test_user_health_route_takes_precedence() {
+        let server = Server::start("get_health = {status: \"custom\"}\n");
+
+        let body: serde_json::Value = reqwest::blocking::get(server.url("/health")).unwrap().json().unwrap();
+        assert_eq!(body, serde_json::json!({"status": "custom"}));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ctrl_c_shuts_down_cleanly() {
+        let mut server = Server::start("get_users = []\n");
+
+        let status = Command::new("kill")
+            .args(["-INT", &server.child.id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let exit = loop {
+            if let Some(exit) = server.child.try_wait().unwrap() {
+                break exit;
+            }
+            assert!(Instant::now() < deadline, "server did not stop");
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        assert!(exit.success());
+
+        let mut stdout = String::new();
+        server.child.stdout.take().unwrap().read_to_string(&mut stdout).unwrap();
+        assert!(stdout.contains("AURA Server detenido tras"), "stdout: {}", stdout);
+    }
+}
@@ -107,6 +107,59 @@ mod undo_command {
         cleanup_dir(&dir);
     }
 
+    #[test]
+    fn test_undo_dry_run_does_not_write_json() {
+        let dir = setup_test_dir();
+        let test_file = dir.join("test.aura");
+        fs::write(&test_file, "x = 42\nmain = x\n").expect("Failed to write test file");
+
+        let output = Command::new(aura_binary())
+            .current_dir(&dir)
+            .args(["snapshots", "create", "--json"])
+            .arg(&test_file)
+            .output()
+            .expect("Failed to execute aura snapshots create");
+        let json: serde_json::Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout)).unwrap();
+        let snapshot_id = json["id"].as_str().unwrap().to_string();
+
+        // Un heal aplicado despues del snapshot
+        fs::write(&test_file, "x = 100\nmain = x\n").unwrap();
+        let state = serde_json::json!({
+            "actions": [{
+                "snapshot_id": snapshot_id,
+                "timestamp": 0,
+                "file_path": test_file.display().to_string(),
+                "old_code": "x = 42\nmain = x\n",
+                "new_code": "x = 100\nmain = x\n",
+                "confidence": 0.9
+            }],
+            "current_position": 1
+        });
+        let state_path = dir.join(".aura").join("undo_state.json");
+        fs::write(&state_path, state.to_string()).unwrap();
+
+        let output = Command::new(aura_binary())
+            .current_dir(&dir)
+            .args(["undo", "--dry-run", "--json"])
+            .output()
+            .expect("Failed to execute aura undo --dry-run");
+        let json: serde_json::Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout))
+            .expect("Output should be valid JSON");
+
+        assert_eq!(json["success"], true);
+        assert_eq!(json["dry_run"], true);
+        assert_eq!(json["files_to_overwrite"].as_array().unwrap().len(), 1);
+        let diff = json["files"][0]["diff"].as_str().unwrap();
+        assert!(diff.contains("-x = 100\n+x = 42\n"));
+
+        // Ni el archivo ni el historial cambiaron
+        assert_eq!(fs::read_to_string(&test_file).unwrap(), "x = 100\nmain = x\n");
+        let state: serde_json::Value = serde_json::from_str(&fs::read_to_string(&state_path).unwrap()).unwrap();
+        assert_eq!(state["current_position"], 1);
+
+        cleanup_dir(&dir);
+    }
+
     #[test]
     fn test_undo_to_nonexistent_json() {
         let dir = setup_test_dir();
@@ -309,6 +362,114 @@ mod snapshots_command {
         cleanup_dir(&dir);
     }
 
+    fn create_snapshot(dir: &std::path::Path, file: &std::path::Path) -> String {
+        let output = Command::new(aura_binary())
+            .current_dir(dir)
+            .args(["snapshots", "create", "--json"])
+            .arg(file)
+            .output()
+            .expect("Failed to execute aura snapshots create");
+        let json: serde_json::Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout))
+            .expect("Create output should be valid JSON");
+        json["id"].as_str().unwrap().to_string()
+    }
+
+    fn run_json(dir: &std::path::Path, args: &[&str]) -> serde_json::Value {
+        let output = Command::new(aura_binary())
+            .current_dir(dir)
+            .args(args)
+            .output()
+            .expect("Failed to execute aura snapshots");
+        serde_json::from_str(&String::from_utf8_lossy(&output.stdout))
+            .expect("Output should be valid JSON")
+    }
+
+    #[test]
+    fn test_snapshots_verify_reports_drift_json() {
+        let dir = setup_test_dir();
+        let test_file = dir.join("test.aura");
+        fs::write(&test_file, "x = 42").expect("Failed to write test file");
+        let snapshot_id = create_snapshot(&dir, &test_file);
+
+        let json = run_json(&dir, &["snapshots", "verify", &snapshot_id, "--json"]);
+        assert_eq!(json["success"], true);
+        assert_eq!(json["drift"], false);
+        assert_eq!(json["files"][0]["status"], "unchanged");
+
+        fs::write(&test_file, "x = 100").expect("Failed to modify test file");
+        let json = run_json(&dir, &["snapshots", "verify", &snapshot_id, "--json"]);
+        assert_eq!(json["drift"], true);
+        assert_eq!(json["files"][0]["status"], "drifted");
+
+        cleanup_dir(&dir);
+    }
+
+    #[test]
+    fn test_snapshots_verify_detects_tampered_snapshot_json() {
+        let dir = setup_test_dir();
+        let test_file = dir.join("test.aura");
+        fs::write(&test_file, "x = 42").expect("Failed to write test file");
+        let snapshot_id = create_snapshot(&dir, &test_file);
+
+        let snap_path = dir.join(".aura").join("snapshots").join(format!("{}.json", snapshot_id));
+        let tampered = fs::read_to_string(&snap_path).unwrap().replace("x = 42", "x = 7");
+        fs::write(&snap_path, tampered).unwrap();
+
+        let json = run_json(&dir, &["snapshots", "verify", &snapshot_id, "--json"]);
+        assert_eq!(json["success"], false);
+        assert_eq!(json["files"][0]["status"], "corrupt");
+
+        // Un snapshot corrupto no se restaura
+        let json = run_json(&dir, &["snapshots", "restore", &snapshot_id, "--json"]);
+        assert_eq!(json["success"], false);
+        assert_eq!(fs::read_to_string(&test_file).unwrap(), "x = 42");
+
+        cleanup_dir(&dir);
+    }
+
+    #[test]
+    fn test_snapshots_restore_dry_run_does_not_write() {
+        let dir = setup_test_dir();
+        let test_file = dir.join("test.aura");
+        fs::write(&test_file, "x = 42").expect("Failed to write test file");
+        let snapshot_id = create_snapshot(&dir, &test_file);
+        fs::write(&test_file, "x = 100").unwrap();
+
+        let json = run_json(&dir, &["snapshots", "restore", &snapshot_id, "--dry-run", "--json"]);
+        assert_eq!(json["dry_run"], true);
+        assert_eq!(json["files"][0]["status"], "drifted");
+        assert_eq!(json["files_to_overwrite"][0], test_file.display().to_string());
+        assert_eq!(fs::read_to_string(&test_file).unwrap(), "x = 100");
+
+        // Modo humano: lista y diff, tampoco escribe
+        let output = Command::new(aura_binary())
+            .current_dir(&dir)
+            .args(["snapshots", "restore", &snapshot_id, "--dry-run"])
+            .output()
+            .expect("Failed to execute aura snapshots restore --dry-run");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("would overwrite 1 file"));
+        assert!(stdout.contains("+x = 42"));
+        assert_eq!(fs::read_to_string(&test_file).unwrap(), "x = 100");
+
+        cleanup_dir(&dir);
+    }
+
+    #[test]
+    fn test_snapshots_restore_unchanged_is_noop_json() {
+        let dir = setup_test_dir();
+        let test_file = dir.join("test.aura");
+        fs::write(&test_file, "x = 42").expect("Failed to write test file");
+        let snapshot_id = create_snapshot(&dir, &test_file);
+
+        let json = run_json(&dir, &["snapshots", "restore", &snapshot_id, "--json"]);
+        assert_eq!(json["success"], true);
+        assert!(json.get("files_restored").is_none());
+        assert_eq!(json["files_unchanged"].as_array().unwrap().len(), 1);
+
+        cleanup_dir(&dir);
+    }
+
     #[test]
     fn test_snapshots_prune_json() {
         let dir = setup_test_dir();